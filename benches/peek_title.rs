@@ -0,0 +1,64 @@
+//! 比较只读取标题和节点数与完整读取图谱文件的耗时，用于确认启动时可以为最近的文件列表逐个读取标题。
+//!
+//! 运行：`cargo bench --bench peek_title`
+
+use std::{
+    fs,
+    hint::black_box,
+    time::{Duration, Instant},
+};
+
+use py_better_kt_sqep::{
+    file::FiledKnowledgeGraph,
+    graph::{DistinctEntityType, KnowledgeGraph, Relation, peek_title},
+};
+
+const NODES: usize = 2000; // 测试图谱的节点数，远多于通常的章节文件
+const ITERATIONS: u32 = 20;
+
+/// 多次运行并返回平均耗时
+fn measure<F>(mut f: F) -> Duration
+where
+    F: FnMut(),
+{
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        f();
+    }
+    start.elapsed() / ITERATIONS
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let mut graph = KnowledgeGraph::default();
+    for i in 0..NODES {
+        let id = graph.add_entity(
+            format!("节点 {i} 的内容，用于模拟真实的文字长度"),
+            DistinctEntityType::KnowledgePoint,
+            &[],
+            ((i % 40) as f64 * 200.0, (i / 40) as f64 * 200.0),
+        )?;
+        if id > 1 {
+            graph.add_edge(id - 1, id, Relation::Order)?;
+        }
+    }
+    let path =
+        std::env::temp_dir().join(format!("better_kt_sqep_bench_{}.xml", std::process::id()));
+    fs::write(&path, graph.current_snapshot().to_xml()?)?;
+
+    let peek = measure(|| {
+        black_box(peek_title(&path).unwrap());
+    });
+    let full = measure(|| {
+        black_box(FiledKnowledgeGraph::read_snapshot(&path).unwrap());
+    });
+    println!("{NODES} 个节点，平均每次：");
+    println!("  peek_title     {peek:?}");
+    println!("  read_snapshot  {full:?}");
+    println!(
+        "  加速比         {:.1}x",
+        full.as_secs_f64() / peek.as_secs_f64()
+    );
+
+    fs::remove_file(&path)?;
+    Ok(())
+}
@@ -0,0 +1,15 @@
+//! 以任意字节作为 XML 读取图谱，只允许返回错误，不允许崩溃。
+//! 运行：cargo +nightly fuzz run from_xml
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use py_better_kt_sqep::graph::Snapshot;
+
+fuzz_target!(|data: &[u8]| {
+    let Ok(xml) = std::str::from_utf8(data) else {
+        return;
+    };
+    let _ = Snapshot::from_xml(xml);
+    let _ = Snapshot::from_xml_lenient(xml);
+});
@@ -0,0 +1,76 @@
+#ifndef BETTER_KT_SQEP_H
+#define BETTER_KT_SQEP_H
+
+/* 由 cbindgen 生成，请勿手动修改 */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+// 已加载的图谱，以 kg_load 创建，以 kg_free 释放
+typedef struct KgHandle KgHandle;
+
+// 从 XML 文件加载图谱，失败时返回空指针
+//
+// # Safety
+// path 必须是以 NUL 结尾的 UTF-8 字符串；error 为空或指向可写的指针。
+struct KgHandle *kg_load(const char *path, char **error);
+
+// 节点数，句柄为空时返回 0
+//
+// # Safety
+// handle 为空或由 kg_load 返回且未释放。
+uintptr_t kg_node_count(const struct KgHandle *handle);
+
+// 边数，句柄为空时返回 0
+//
+// # Safety
+// handle 为空或由 kg_load 返回且未释放。
+uintptr_t kg_edge_count(const struct KgHandle *handle);
+
+// 校验图谱，以 JSON 数组写入发现的问题，每项包含 level、message 和 entities。
+// 返回值和缓冲区的约定同 kg_to_xml
+//
+// # Safety
+// handle 为空或由 kg_load 返回且未释放；buffer 为空或指向至少 len 字节的可写内存；
+// error 为空或指向可写的指针。
+intptr_t kg_validate(const struct KgHandle *handle,
+                     char *buffer,
+                     uintptr_t len,
+                     char **error);
+
+// 将图谱以 XML 写入缓冲区并以 NUL 结尾，返回 XML 的字节数（不含 NUL），出错时返回 -1。
+// buffer 为空或 len 不大于返回值时不写入，调用方可按返回值加一分配缓冲区后再次调用
+//
+// # Safety
+// handle 为空或由 kg_load 返回且未释放；buffer 为空或指向至少 len 字节的可写内存；
+// error 为空或指向可写的指针。
+intptr_t kg_to_xml(const struct KgHandle *handle,
+                   char *buffer,
+                   uintptr_t len,
+                   char **error);
+
+// 将图谱以 JSON 写入缓冲区，包含 nodes 和 edges 两个数组。返回值和缓冲区的约定同 kg_to_xml
+//
+// # Safety
+// handle 为空或由 kg_load 返回且未释放；buffer 为空或指向至少 len 字节的可写内存；
+// error 为空或指向可写的指针。
+intptr_t kg_to_json(const struct KgHandle *handle,
+                    char *buffer,
+                    uintptr_t len,
+                    char **error);
+
+// 释放 kg_load 返回的句柄，句柄为空时不做任何事
+//
+// # Safety
+// handle 为空或由 kg_load 返回且未释放，释放后不可再使用。
+void kg_free(struct KgHandle *handle);
+
+// 释放通过 error 参数返回的错误信息，为空时不做任何事
+//
+// # Safety
+// error 为空或由本库返回且未释放，释放后不可再使用。
+void kg_free_error(char *error);
+
+#endif  /* BETTER_KT_SQEP_H */
@@ -0,0 +1,54 @@
+//! 双击检测模块，根据两次单击的时间间隔和位置判断是否为双击。
+//! 只比较传入的时间点，不对 Instant 做减法，避免在 Instant 起点接近零的平台上溢出。
+
+use std::time::{Duration, Instant};
+
+use eframe::egui::Pos2;
+
+/// 双击检测
+#[derive(Debug, Default)]
+pub struct DoubleClick {
+    last: Option<(Instant, Pos2)>, // 上一次单击的时间和位置，尚未单击时为 None
+}
+
+impl DoubleClick {
+    /// 每次单击时调用。与上一次单击的间隔小于 interval 且距离小于 tolerance 时返回 true。
+    /// 时间倒退时视为不是双击。
+    pub fn click(&mut self, pos: Pos2, now: Instant, interval: Duration, tolerance: f32) -> bool {
+        let is_double = self.last.is_some_and(|(time, last_pos)| {
+            now.checked_duration_since(time)
+                .is_some_and(|elapsed| elapsed < interval)
+                && last_pos.distance(pos) < tolerance
+        });
+        self.last = Some((now, pos));
+        is_double
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_double_click() {
+        let now = Instant::now();
+        let interval = Duration::from_millis(300);
+        let pos = Pos2::new(10.0, 10.0);
+
+        // 第一次单击不是双击
+        let mut double_click = DoubleClick::default();
+        assert!(!double_click.click(pos, now, interval, 5.0));
+        assert!(double_click.click(pos, now + interval / 2, interval, 5.0));
+
+        // 间隔过长
+        let later = now + interval * 4;
+        assert!(!double_click.click(pos, later, interval, 5.0));
+
+        // 距离过远
+        let later = later + interval / 2;
+        assert!(!double_click.click(Pos2::new(20.0, 10.0), later, interval, 5.0));
+
+        // 时间倒退
+        assert!(!double_click.click(Pos2::new(20.0, 10.0), now, interval, 5.0));
+    }
+}
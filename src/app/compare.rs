@@ -0,0 +1,153 @@
+//! 对比模块，将当前图谱与另一版本的差异叠加显示在画布上，或将两个版本分屏、叠加显示以便并排浏览。
+//! 对比期间画布只读。
+
+use eframe::egui::{Color32, Rect, Vec2};
+
+use crate::graph::{Change, Snapshot, SnapshotDiff};
+
+/// 对比的显示方式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum CompareMode {
+    /// 在当前图谱上标出差异
+    #[default]
+    Diff,
+    /// 左右分屏，左侧为当前图谱，右侧为对比版本
+    SideBySide,
+    /// 以不同色调叠加显示两个版本
+    Overlay,
+}
+
+impl CompareMode {
+    pub const ALL: [CompareMode; 3] = [
+        CompareMode::Diff,
+        CompareMode::SideBySide,
+        CompareMode::Overlay,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            CompareMode::Diff => "差异",
+            CompareMode::SideBySide => "分屏",
+            CompareMode::Overlay => "叠加",
+        }
+    }
+}
+
+/// 叠加显示时对比版本的色调
+pub const OVERLAY_BASE_COLOR: Color32 = Color32::from_rgb(54, 131, 248);
+
+/// 一次对比
+#[derive(Debug)]
+pub struct Comparison {
+    pub label: String,      // 对比对象的名称，如文件名
+    pub base: Snapshot,     // 作为基准的另一版本，节点已按当前图谱重新编号
+    pub diff: SnapshotDiff, // 当前图谱相对基准的差异
+    pub mode: CompareMode,  // 显示方式
+    pub linked: bool,       // 分屏时两侧是否联动平移
+    pub pane_offset: Vec2,  // 分屏时右侧相对左侧额外的滚动偏移（屏幕长度）
+    source: Snapshot,       // 读取的原始版本
+    match_content: bool,    // ID 对应不上时是否按内容匹配节点
+    revision: u64,          // 差异对应的图谱修订号
+}
+
+impl Comparison {
+    pub fn new(label: String, source: Snapshot, current: &Snapshot, revision: u64) -> Self {
+        let mut comparison = Self {
+            label,
+            base: Snapshot::default(),
+            diff: SnapshotDiff::default(),
+            mode: CompareMode::default(),
+            linked: true,
+            pane_offset: Vec2::ZERO,
+            source,
+            match_content: true,
+            revision,
+        };
+        comparison.recompute(current);
+        comparison
+    }
+
+    /// 图谱变化后重新计算差异
+    pub fn refresh(&mut self, current: &Snapshot, revision: u64) {
+        if self.revision != revision {
+            self.recompute(current);
+            self.revision = revision;
+        }
+    }
+
+    #[inline]
+    pub fn match_content(&self) -> bool {
+        self.match_content
+    }
+
+    /// 切换是否按内容匹配节点，并重新计算差异
+    pub fn set_match_content(&mut self, match_content: bool, current: &Snapshot) {
+        if self.match_content != match_content {
+            self.match_content = match_content;
+            self.recompute(current);
+        }
+    }
+
+    /// 分屏时分配画布本帧的平移量 delta，返回左侧（即画布本身）应保留的平移量。
+    /// 联动时两侧一起移动；否则只移动指针所在的一侧，另一侧保持不动
+    pub fn split_pan(&mut self, delta: Vec2, in_right_pane: bool) -> Vec2 {
+        if self.linked || self.mode != CompareMode::SideBySide {
+            delta
+        } else if in_right_pane {
+            self.pane_offset += delta;
+            Vec2::ZERO
+        } else {
+            self.pane_offset -= delta;
+            delta
+        }
+    }
+
+    fn recompute(&mut self, current: &Snapshot) {
+        self.base = self.source.aligned_to(current, self.match_content);
+        self.diff = self.base.diff(current);
+    }
+}
+
+/// 将画布等分为左右两半，分屏时左侧显示当前图谱，右侧显示对比版本
+pub fn split_rects(rect: Rect) -> (Rect, Rect) {
+    let center = rect.center().x;
+    (rect.with_max_x(center), rect.with_min_x(center))
+}
+
+/// 变化对应的颜色：新增为绿色，删除为红色，修改为橙色
+pub fn change_color(change: Change) -> Color32 {
+    match change {
+        Change::Added => Color32::from_rgb(46, 160, 67),
+        Change::Removed => Color32::from_rgb(218, 54, 51),
+        Change::Modified => Color32::from_rgb(240, 140, 20),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_pan() {
+        let current = Snapshot::default();
+        let mut comparison = Comparison::new("对比".to_string(), Snapshot::default(), &current, 0);
+        let delta = Vec2::new(10.0, 5.0);
+
+        // 默认联动，不需要额外的偏移
+        comparison.mode = CompareMode::SideBySide;
+        assert_eq!(comparison.split_pan(delta, true), delta);
+        assert_eq!(comparison.pane_offset, Vec2::ZERO);
+
+        // 分别平移时只移动指针所在的一侧
+        comparison.linked = false;
+        assert_eq!(comparison.split_pan(delta, true), Vec2::ZERO);
+        assert_eq!(comparison.pane_offset, delta);
+        assert_eq!(comparison.split_pan(delta, false), delta);
+        assert_eq!(comparison.pane_offset, Vec2::ZERO);
+
+        // 不分屏时不区分两侧
+        comparison.mode = CompareMode::Overlay;
+        assert_eq!(comparison.split_pan(delta, true), delta);
+        assert_eq!(comparison.pane_offset, Vec2::ZERO);
+    }
+}
@@ -0,0 +1,227 @@
+//! 绘制边的流程。从节点中心按下开始拖动，松开在另一个节点上时弹出窗口选择关系，
+//! 松开在空白处时新建下级节点。流程中端点可能被撤销等操作删除，每帧需要重新检查。
+
+use eframe::egui::Key;
+
+use crate::graph::{DistinctEntityType, Relation};
+
+/// 按顺序对应 Relation::ALL 的数字键，按下后直接以对应的关系确认
+pub const RELATION_KEYS: [Key; 3] = [Key::Num1, Key::Num2, Key::Num3];
+
+/// 由起止节点的类型推断的默认关系，连边时预选在选择关系的窗口中。调整规则时修改此表
+pub const RELATION_RULES: [(DistinctEntityType, DistinctEntityType, Relation); 4] = [
+    (
+        DistinctEntityType::KnowledgeArena,
+        DistinctEntityType::KnowledgeUnit,
+        Relation::Contain,
+    ),
+    (
+        DistinctEntityType::KnowledgeUnit,
+        DistinctEntityType::KnowledgePoint,
+        Relation::Contain,
+    ),
+    (
+        DistinctEntityType::KnowledgePoint,
+        DistinctEntityType::KnowledgeDetail,
+        Relation::Contain,
+    ),
+    (
+        DistinctEntityType::KnowledgePoint,
+        DistinctEntityType::KnowledgePoint,
+        Relation::Order,
+    ),
+];
+
+/// 按 RELATION_RULES 推断起止节点类型之间的默认关系，没有对应的规则时返回 None
+pub fn suggested_relation(from: DistinctEntityType, to: DistinctEntityType) -> Option<Relation> {
+    RELATION_RULES
+        .iter()
+        .find(|(rule_from, rule_to, _)| *rule_from == from && *rule_to == to)
+        .map(|(_, _, relation)| *relation)
+}
+
+/// 绘制边的状态
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum EdgeDraft {
+    /// 没有在绘制边
+    #[default]
+    Idle,
+    /// 正在拖动，或松开在空白处后等待新建下级节点
+    Dragging { from: u64 },
+    /// 松开在目标节点上，等待在窗口中选择关系
+    Choosing { from: u64, to: u64 },
+}
+
+impl EdgeDraft {
+    /// 从节点开始拖动，已经在绘制边时忽略
+    pub fn start(&mut self, from: u64) {
+        if *self == EdgeDraft::Idle {
+            *self = EdgeDraft::Dragging { from };
+        }
+    }
+
+    /// 松开在目标节点上。目标为起点自身时结束流程
+    pub fn release_on(&mut self, to: u64) {
+        if let EdgeDraft::Dragging { from } = *self {
+            *self = if from == to {
+                EdgeDraft::Idle
+            } else {
+                EdgeDraft::Choosing { from, to }
+            };
+        }
+    }
+
+    /// 结束流程
+    #[inline]
+    pub fn cancel(&mut self) {
+        *self = EdgeDraft::Idle;
+    }
+
+    /// 是否正在绘制边，包括选择关系的窗口打开时
+    #[inline]
+    pub fn is_active(&self) -> bool {
+        *self != EdgeDraft::Idle
+    }
+
+    /// 检查端点是否仍然存在，任一端点不存在时结束流程并返回 false
+    pub fn retain_endpoints(&mut self, exists: impl Fn(u64) -> bool) -> bool {
+        let valid = match *self {
+            EdgeDraft::Idle => true,
+            EdgeDraft::Dragging { from } => exists(from),
+            EdgeDraft::Choosing { from, to } => exists(from) && exists(to),
+        };
+        if !valid {
+            self.cancel();
+        }
+        valid
+    }
+}
+
+/// 选择关系窗口中本帧的结果
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RelationChoice {
+    /// 尚未选择
+    Pending,
+    /// 以该关系确认
+    Confirmed(Relation),
+    /// 取消绘制
+    Cancelled,
+}
+
+/// 由本帧按下的键得到选择关系窗口的结果：Escape 取消，数字键直接以对应的关系确认，
+/// Enter 以当前选中的关系 current 确认。pressed 判断某个键本帧是否按下
+pub fn relation_choice(current: Relation, pressed: impl Fn(Key) -> bool) -> RelationChoice {
+    if pressed(Key::Escape) {
+        return RelationChoice::Cancelled;
+    }
+    if let Some((_, relation)) = RELATION_KEYS
+        .iter()
+        .zip(Relation::ALL)
+        .find(|(key, _)| pressed(**key))
+    {
+        return RelationChoice::Confirmed(relation);
+    }
+    if pressed(Key::Enter) {
+        RelationChoice::Confirmed(current)
+    } else {
+        RelationChoice::Pending
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_edge_draft() {
+        let mut draft = EdgeDraft::default();
+        assert!(!draft.is_active());
+
+        draft.start(1);
+        draft.start(2); // 已经在绘制时忽略
+        assert_eq!(draft, EdgeDraft::Dragging { from: 1 });
+        draft.release_on(3);
+        assert_eq!(draft, EdgeDraft::Choosing { from: 1, to: 3 });
+        assert!(draft.is_active());
+
+        // 松开在起点自身时结束
+        draft.cancel();
+        draft.start(1);
+        draft.release_on(1);
+        assert_eq!(draft, EdgeDraft::Idle);
+    }
+
+    #[test]
+    fn test_retain_endpoints() {
+        let mut draft = EdgeDraft::Choosing { from: 1, to: 2 };
+        assert!(draft.retain_endpoints(|_| true));
+        assert!(draft.is_active());
+
+        // 选择关系时终点被删除
+        assert!(!draft.retain_endpoints(|id| id != 2));
+        assert_eq!(draft, EdgeDraft::Idle);
+
+        // 拖动时起点被删除
+        draft.start(1);
+        assert!(!draft.retain_endpoints(|id| id != 1));
+        assert!(draft.retain_endpoints(|_| false)); // 没有在绘制时总是有效
+    }
+
+    #[test]
+    fn test_suggested_relation() {
+        use DistinctEntityType::*;
+        assert_eq!(
+            suggested_relation(KnowledgeArena, KnowledgeUnit),
+            Some(Relation::Contain)
+        );
+        assert_eq!(
+            suggested_relation(KnowledgeUnit, KnowledgePoint),
+            Some(Relation::Contain)
+        );
+        assert_eq!(
+            suggested_relation(KnowledgePoint, KnowledgePoint),
+            Some(Relation::Order)
+        );
+        // 反向或跨级没有规则，沿用上次的关系
+        assert_eq!(suggested_relation(KnowledgeUnit, KnowledgeArena), None);
+        assert_eq!(suggested_relation(KnowledgeArena, KnowledgePoint), None);
+    }
+
+    #[test]
+    fn test_relation_choice() {
+        let only = |pressed: Key| move |key: Key| key == pressed;
+        assert_eq!(
+            relation_choice(Relation::Order, |_| false),
+            RelationChoice::Pending
+        );
+        assert_eq!(
+            relation_choice(Relation::Order, only(Key::Enter)),
+            RelationChoice::Confirmed(Relation::Order)
+        );
+        assert_eq!(
+            relation_choice(Relation::Order, only(Key::Escape)),
+            RelationChoice::Cancelled
+        );
+        for (key, relation) in RELATION_KEYS.into_iter().zip(Relation::ALL) {
+            assert_eq!(
+                relation_choice(Relation::Contain, only(key)),
+                RelationChoice::Confirmed(relation)
+            );
+        }
+        assert_eq!(
+            relation_choice(Relation::Contain, only(Key::Num4)),
+            RelationChoice::Pending
+        );
+
+        // 同时按下时数字键优先于 Enter，Escape 优先于其他键
+        let keys = [Key::Enter, Key::Num2];
+        assert_eq!(
+            relation_choice(Relation::Contain, |key| keys.contains(&key)),
+            RelationChoice::Confirmed(Relation::Order)
+        );
+        assert_eq!(
+            relation_choice(Relation::Contain, |key| key != Key::Num1),
+            RelationChoice::Cancelled
+        );
+    }
+}
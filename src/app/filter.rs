@@ -0,0 +1,202 @@
+//! 筛选模块，按节点类型、附加类型和评审标记筛选节点。被筛除的节点在画布上淡化显示。
+
+use std::collections::HashSet;
+
+use crate::graph::{AddonEntityType, DistinctEntityType, EntityNode, Flag};
+
+/// 所有独立实体类型，按层级排列
+pub const DISTINCT_TYPES: [DistinctEntityType; 4] = [
+    DistinctEntityType::KnowledgeArena,
+    DistinctEntityType::KnowledgeUnit,
+    DistinctEntityType::KnowledgePoint,
+    DistinctEntityType::KnowledgeDetail,
+];
+
+/// 所有附加实体类型
+pub const ADDON_TYPES: [AddonEntityType; 6] = [
+    AddonEntityType::Knowledge,
+    AddonEntityType::Thinking,
+    AddonEntityType::Example,
+    AddonEntityType::Question,
+    AddonEntityType::Practice,
+    AddonEntityType::Political,
+];
+
+/// 节点筛选条件，默认不筛除任何节点
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct NodeFilter {
+    hidden_distinct: HashSet<DistinctEntityType>, // 被筛除的独立实体类型
+    hidden_addons: HashSet<AddonEntityType>,      // 被筛除的附加类型
+    flag: Option<Flag>,                           // 只显示带有该评审标记的节点
+}
+
+impl NodeFilter {
+    /// 是否筛除了任何类型
+    #[inline]
+    pub fn is_active(&self) -> bool {
+        !self.hidden_distinct.is_empty() || !self.hidden_addons.is_empty() || self.flag.is_some()
+    }
+
+    /// 节点是否通过筛选：独立实体类型未被筛除，且附加类型未被筛除时带有任一未被筛除的附加类型。
+    /// 附加类型全部未被筛除时不按附加类型筛选，没有附加类型的节点也能通过。
+    /// 按评审标记筛选时，还需带有该标记
+    pub fn matches(&self, node: &EntityNode) -> bool {
+        if self.hidden_distinct.contains(&node.distinct_type)
+            || self.flag.is_some_and(|flag| node.flag != Some(flag))
+        {
+            return false;
+        }
+        self.hidden_addons.is_empty()
+            || node
+                .addon_types
+                .iter()
+                .any(|addon| !self.hidden_addons.contains(addon))
+    }
+
+    #[inline]
+    pub fn shows_distinct(&self, distinct_type: DistinctEntityType) -> bool {
+        !self.hidden_distinct.contains(&distinct_type)
+    }
+
+    #[inline]
+    pub fn shows_addon(&self, addon_type: AddonEntityType) -> bool {
+        !self.hidden_addons.contains(&addon_type)
+    }
+
+    /// 切换是否筛除某个独立实体类型
+    pub fn toggle_distinct(&mut self, distinct_type: DistinctEntityType) {
+        if !self.hidden_distinct.remove(&distinct_type) {
+            self.hidden_distinct.insert(distinct_type);
+        }
+    }
+
+    /// 只保留某个独立实体类型，筛除其余类型
+    pub fn isolate_distinct(&mut self, distinct_type: DistinctEntityType) {
+        self.hidden_distinct = DISTINCT_TYPES
+            .into_iter()
+            .filter(|t| *t != distinct_type)
+            .collect();
+    }
+
+    /// 切换是否筛除某个附加类型
+    pub fn toggle_addon(&mut self, addon_type: AddonEntityType) {
+        if !self.hidden_addons.remove(&addon_type) {
+            self.hidden_addons.insert(addon_type);
+        }
+    }
+
+    /// 只保留某个附加类型，筛除其余附加类型
+    pub fn isolate_addon(&mut self, addon_type: AddonEntityType) {
+        self.hidden_addons = ADDON_TYPES
+            .into_iter()
+            .filter(|t| *t != addon_type)
+            .collect();
+    }
+
+    /// 是否只显示带有该评审标记的节点
+    #[inline]
+    pub fn shows_only_flag(&self, flag: Flag) -> bool {
+        self.flag == Some(flag)
+    }
+
+    /// 切换是否只显示带有该评审标记的节点，同时只能按一种标记筛选
+    pub fn toggle_flag(&mut self, flag: Flag) {
+        self.flag = (self.flag != Some(flag)).then_some(flag);
+    }
+
+    /// 清除所有筛选
+    pub fn clear(&mut self) {
+        self.hidden_distinct.clear();
+        self.hidden_addons.clear();
+        self.flag = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn node(distinct_type: DistinctEntityType, addon_types: &[AddonEntityType]) -> EntityNode {
+        EntityNode::new(1, String::new(), distinct_type, addon_types, (0.0, 0.0))
+    }
+
+    #[test]
+    fn test_distinct_filter() {
+        let mut filter = NodeFilter::default();
+        let point = node(DistinctEntityType::KnowledgePoint, &[]);
+        let unit = node(DistinctEntityType::KnowledgeUnit, &[]);
+        assert!(!filter.is_active());
+        assert!(filter.matches(&point));
+
+        filter.toggle_distinct(DistinctEntityType::KnowledgePoint);
+        assert!(filter.is_active());
+        assert!(!filter.matches(&point));
+        assert!(filter.matches(&unit));
+
+        filter.isolate_distinct(DistinctEntityType::KnowledgePoint);
+        assert!(filter.matches(&point));
+        assert!(!filter.matches(&unit));
+
+        filter.clear();
+        assert!(!filter.is_active());
+        assert!(filter.matches(&unit));
+    }
+
+    #[test]
+    fn test_addon_filter() {
+        let mut filter = NodeFilter::default();
+        let plain = node(DistinctEntityType::KnowledgePoint, &[]);
+        let example = node(
+            DistinctEntityType::KnowledgePoint,
+            &[AddonEntityType::Example],
+        );
+        let both = node(
+            DistinctEntityType::KnowledgePoint,
+            &[AddonEntityType::Example, AddonEntityType::Question],
+        );
+
+        // 带有其他未筛除附加类型的节点仍能通过
+        filter.toggle_addon(AddonEntityType::Example);
+        assert!(!filter.matches(&example));
+        assert!(filter.matches(&both));
+        assert!(!filter.matches(&plain));
+
+        filter.isolate_addon(AddonEntityType::Question);
+        assert!(filter.shows_addon(AddonEntityType::Question));
+        assert!(!filter.shows_addon(AddonEntityType::Example));
+        assert!(!filter.matches(&example));
+        assert!(filter.matches(&both));
+
+        filter.toggle_addon(AddonEntityType::Question);
+        filter.clear();
+        assert!(filter.matches(&plain));
+    }
+
+    #[test]
+    fn test_flag_filter() {
+        let mut filter = NodeFilter::default();
+        let plain = node(DistinctEntityType::KnowledgePoint, &[]);
+        let mut todo = plain.clone();
+        todo.flag = Some(Flag::Todo);
+
+        filter.toggle_flag(Flag::Todo);
+        assert!(filter.is_active());
+        assert!(filter.shows_only_flag(Flag::Todo));
+        assert!(filter.matches(&todo));
+        assert!(!filter.matches(&plain));
+
+        // 同时只按一种标记筛选，再次切换同一标记时取消
+        filter.toggle_flag(Flag::Approved);
+        assert!(!filter.matches(&todo));
+        filter.toggle_flag(Flag::Approved);
+        assert!(!filter.is_active());
+        assert!(filter.matches(&plain));
+
+        // 与类型筛选同时生效
+        filter.toggle_flag(Flag::Todo);
+        filter.toggle_distinct(DistinctEntityType::KnowledgePoint);
+        assert!(!filter.matches(&todo));
+        filter.clear();
+        assert!(filter.matches(&plain));
+    }
+}
@@ -0,0 +1,328 @@
+//! 折叠模块。折叠节点后隐藏其沿包含关系的下级节点，便于按受众控制大图谱的展示粒度。
+//! 折叠状态只是视图状态，不写入图谱。下级节点还能经由其他未折叠的父节点到达时仍然显示。
+
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use crate::graph::{Relation, Snapshot};
+
+/// 折叠预览中最多列出的下级节点数
+pub const PREVIEW_LIMIT: usize = 30;
+
+/// 每个节点沿包含关系的子节点
+fn contain_children(snapshot: &Snapshot) -> HashMap<u64, Vec<u64>> {
+    let mut children = HashMap::<u64, Vec<u64>>::new();
+    for ((from, to), relation) in snapshot.edges.iter() {
+        if *relation == Relation::Contain {
+            children.entry(*from).or_default().push(*to);
+        }
+    }
+    children
+}
+
+/// 沿包含关系从 starts 广度优先可到达的节点及其深度，starts 的深度为 0。
+/// expand 判断是否继续展开某个节点的子节点
+fn breadth_first(
+    children: &HashMap<u64, Vec<u64>>,
+    starts: impl IntoIterator<Item = u64>,
+    expand: impl Fn(u64) -> bool,
+) -> HashMap<u64, usize> {
+    let mut depths = HashMap::new();
+    let mut queue = VecDeque::new();
+    for id in starts {
+        if depths.insert(id, 0).is_none() {
+            queue.push_back(id);
+        }
+    }
+    while let Some(id) = queue.pop_front() {
+        if !expand(id) {
+            continue;
+        }
+        let depth = depths[&id] + 1;
+        for child in children.get(&id).into_iter().flatten() {
+            if !depths.contains_key(child) {
+                depths.insert(*child, depth);
+                queue.push_back(*child);
+            }
+        }
+    }
+    depths
+}
+
+/// 各节点的层级深度：从没有父节点的根节点（通常为知识领域）沿包含关系广度优先计算，根节点为 0。
+/// 只能经由包含关系的环到达的节点也视为根节点
+pub fn contain_depths(snapshot: &Snapshot) -> HashMap<u64, usize> {
+    let children = contain_children(snapshot);
+    let has_parent = children.values().flatten().copied().collect::<HashSet<_>>();
+    let roots = snapshot
+        .nodes
+        .keys()
+        .copied()
+        .filter(|id| !has_parent.contains(id));
+    let mut depths = breadth_first(&children, roots, |_| true);
+
+    // 环上的节点按 ID 依次作为根节点，保证结果与哈希表的顺序无关
+    let mut rest = snapshot
+        .nodes
+        .keys()
+        .copied()
+        .filter(|id| !depths.contains_key(id))
+        .collect::<Vec<_>>();
+    rest.sort_unstable();
+    for id in rest {
+        if !depths.contains_key(&id) {
+            let reached = breadth_first(&children, [id], |id| !depths.contains_key(&id));
+            for (id, depth) in reached {
+                depths.entry(id).or_insert(depth);
+            }
+        }
+    }
+    depths
+}
+
+/// 折叠的节点 id 所隐藏的下级节点的预览，按先序每行一个节点，以缩进表示相对深度，多行内容只取第一行。
+/// 超过 limit 个时只列出前 limit 个，最后一行为“…”
+pub fn subtree_preview(snapshot: &Snapshot, id: u64, limit: usize) -> Vec<String> {
+    let descendants = snapshot.descendants(id);
+    let mut lines = descendants
+        .iter()
+        .take(limit)
+        .map(|(id, depth)| {
+            let content = snapshot
+                .nodes
+                .get(id)
+                .and_then(|node| node.content.lines().next())
+                .filter(|line| !line.trim().is_empty())
+                .unwrap_or("（空）");
+            format!("{}{content}", "    ".repeat(depth - 1))
+        })
+        .collect::<Vec<_>>();
+    if descendants.len() > limit {
+        lines.push("…".to_string());
+    }
+    lines
+}
+
+/// 节点的折叠状态，默认全部展开
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Folding {
+    collapsed: HashSet<u64>, // 折叠了下级节点的节点
+}
+
+impl Folding {
+    /// 是否有折叠的节点
+    #[inline]
+    pub fn is_active(&self) -> bool {
+        !self.collapsed.is_empty()
+    }
+
+    #[inline]
+    pub fn is_collapsed(&self, id: u64) -> bool {
+        self.collapsed.contains(&id)
+    }
+
+    /// 切换单个节点的折叠状态
+    pub fn toggle(&mut self, id: u64) {
+        if !self.collapsed.remove(&id) {
+            self.collapsed.insert(id);
+        }
+    }
+
+    /// 全部展开
+    #[inline]
+    pub fn expand_all(&mut self) {
+        self.collapsed.clear();
+    }
+
+    /// 全部折叠，只显示根节点
+    pub fn collapse_all(&mut self, snapshot: &Snapshot) {
+        self.collapsed = contain_children(snapshot).into_keys().collect();
+    }
+
+    /// 展开到第 level 层，根节点为第 1 层。level 为 0 时视为 1
+    pub fn expand_to_level(&mut self, snapshot: &Snapshot, level: usize) {
+        let children = contain_children(snapshot);
+        let depths = contain_depths(snapshot);
+        self.collapsed = children
+            .into_keys()
+            .filter(|id| depths.get(id).is_some_and(|depth| depth + 1 >= level))
+            .collect();
+    }
+
+    /// 展开 ids 及其所有下级节点
+    pub fn expand_subtrees(&mut self, snapshot: &Snapshot, ids: &[u64]) {
+        let children = contain_children(snapshot);
+        for id in breadth_first(&children, ids.iter().copied(), |_| true).into_keys() {
+            self.collapsed.remove(&id);
+        }
+    }
+
+    /// 折叠 ids 及其所有有子节点的下级节点，之后逐层展开时下级节点仍是折叠的
+    pub fn collapse_subtrees(&mut self, snapshot: &Snapshot, ids: &[u64]) {
+        let children = contain_children(snapshot);
+        let reached = breadth_first(&children, ids.iter().copied(), |_| true);
+        self.collapsed.extend(
+            reached
+                .into_keys()
+                .filter(|id| children.contains_key(id) || ids.contains(id)),
+        );
+    }
+
+    /// 因折叠而隐藏的节点。从根节点出发沿包含关系可到达、但不经过折叠的节点就无法到达的节点被隐藏
+    pub fn hidden(&self, snapshot: &Snapshot) -> HashSet<u64> {
+        if self.collapsed.is_empty() {
+            return HashSet::new();
+        }
+        let children = contain_children(snapshot);
+        let depths = contain_depths(snapshot);
+        let roots = depths
+            .iter()
+            .filter(|(_, depth)| **depth == 0)
+            .map(|(id, _)| *id);
+        let visible = breadth_first(&children, roots, |id| !self.collapsed.contains(&id));
+        snapshot
+            .nodes
+            .keys()
+            .copied()
+            .filter(|id| !visible.contains_key(id))
+            .collect()
+    }
+
+    /// 节点 ID 重新编号后按映射更新，映射中没有的节点去掉
+    pub fn remap(&mut self, map: &HashMap<u64, u64>) {
+        self.collapsed = self
+            .collapsed
+            .iter()
+            .filter_map(|id| map.get(id).copied())
+            .collect();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::{DistinctEntityType, KnowledgeGraph};
+
+    /// 领域 1 包含单元 2、3，单元 2 包含知识点 4，知识点 4 包含细节 5；单元 3 也包含知识点 4
+    fn graph() -> KnowledgeGraph {
+        let mut graph = KnowledgeGraph::default();
+        for content in ["领域", "单元一", "单元二", "知识点", "细节"] {
+            graph
+                .add_entity(
+                    content.to_string(),
+                    DistinctEntityType::KnowledgePoint,
+                    &[],
+                    (0.0, 0.0),
+                )
+                .unwrap();
+        }
+        for (from, to) in [(1, 2), (1, 3), (2, 4), (3, 4), (4, 5)] {
+            graph.add_edge(from, to, Relation::Contain).unwrap();
+        }
+        graph.add_edge(2, 3, Relation::Order).unwrap(); // 次序关系不影响层级
+        graph
+    }
+
+    #[test]
+    fn test_contain_depths() {
+        let graph = graph();
+        let depths = contain_depths(&graph.current);
+        assert_eq!(
+            depths,
+            HashMap::from([(1, 0), (2, 1), (3, 1), (4, 2), (5, 3)])
+        );
+
+        // 成环的节点也有深度
+        let mut graph = graph;
+        graph.add_edge(5, 4, Relation::Contain).unwrap();
+        graph.remove_edge(2, 4).unwrap();
+        graph.remove_edge(3, 4).unwrap();
+        let depths = contain_depths(&graph.current);
+        assert_eq!(depths[&4], 0);
+        assert_eq!(depths[&5], 1);
+    }
+
+    #[test]
+    fn test_folding() {
+        let graph = graph();
+        let snapshot = &graph.current;
+        let mut folding = Folding::default();
+        assert!(folding.hidden(snapshot).is_empty());
+
+        // 知识点 4 还能经由未折叠的单元 3 到达
+        folding.toggle(2);
+        assert_eq!(folding.hidden(snapshot), HashSet::new());
+        folding.toggle(3);
+        assert_eq!(folding.hidden(snapshot), HashSet::from([4, 5]));
+        folding.toggle(3);
+        assert!(!folding.is_collapsed(3));
+
+        folding.collapse_all(snapshot);
+        assert_eq!(folding.hidden(snapshot), HashSet::from([2, 3, 4, 5]));
+        folding.expand_all();
+        assert!(!folding.is_active());
+
+        // 展开到第 2 层只显示领域和单元
+        folding.expand_to_level(snapshot, 2);
+        assert_eq!(folding.hidden(snapshot), HashSet::from([4, 5]));
+        folding.expand_to_level(snapshot, 0);
+        assert_eq!(folding.hidden(snapshot), HashSet::from([2, 3, 4, 5]));
+        folding.expand_to_level(snapshot, 4);
+        assert!(folding.hidden(snapshot).is_empty());
+
+        // 展开子树后下级节点全部展开，折叠子树后逐层展开时下级仍是折叠的
+        folding.collapse_all(snapshot);
+        folding.expand_subtrees(snapshot, &[1]);
+        assert!(!folding.is_active());
+        folding.collapse_subtrees(snapshot, &[2, 3]);
+        assert_eq!(folding.hidden(snapshot), HashSet::from([4, 5]));
+        folding.toggle(2);
+        assert_eq!(folding.hidden(snapshot), HashSet::from([5]));
+
+        folding.remap(&HashMap::from([(2, 20), (4, 40)]));
+        assert!(folding.is_collapsed(40));
+        assert!(!folding.is_collapsed(3));
+    }
+
+    #[test]
+    fn test_subtree_preview() {
+        let graph = graph();
+        let snapshot = &graph.current;
+        assert_eq!(
+            subtree_preview(snapshot, 2, PREVIEW_LIMIT),
+            vec!["知识点", "    细节"]
+        );
+        assert!(subtree_preview(snapshot, 5, PREVIEW_LIMIT).is_empty());
+
+        // 超过上限时截断并以省略号结尾，经由多个父节点到达的节点只列一次
+        assert_eq!(
+            subtree_preview(snapshot, 1, 3),
+            vec!["单元一", "    知识点", "        细节", "…"]
+        );
+        assert_eq!(subtree_preview(snapshot, 1, 4).len(), 4);
+
+        let mut graph = KnowledgeGraph::default();
+        let root = graph
+            .add_entity(
+                String::new(),
+                DistinctEntityType::KnowledgeUnit,
+                &[],
+                (0.0, 0.0),
+            )
+            .unwrap();
+        for i in 0..PREVIEW_LIMIT + 5 {
+            let id = graph
+                .add_entity(
+                    format!("第 {i} 个\n第二行"),
+                    DistinctEntityType::KnowledgePoint,
+                    &[],
+                    (0.0, 0.0),
+                )
+                .unwrap();
+            graph.add_edge(root, id, Relation::Contain).unwrap();
+        }
+        let lines = subtree_preview(&graph.current, root, PREVIEW_LIMIT);
+        assert_eq!(lines.len(), PREVIEW_LIMIT + 1);
+        assert_eq!(lines[0], "第 0 个");
+        assert_eq!(lines.last().unwrap(), "…");
+    }
+}
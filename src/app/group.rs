@@ -0,0 +1,92 @@
+//! 分组框的命中检测和范围计算。分组框只能通过边框、标签栏和右下角的调整柄选中，
+//! 内部的空白仍属于画布，可以照常新建和框选节点。
+
+use eframe::egui::{Pos2, Rect, Vec2};
+
+use crate::graph::CoordinateBounds;
+
+pub const GROUP_HEADER_HEIGHT: f32 = 24.0; // 标签栏的高度，缩放为 1 时
+pub const GROUP_MIN_SIZE: f64 = 100.0; // 调整大小时的最小宽高
+pub const GROUP_DEFAULT_SIZE: (f64, f64) = (400.0, 300.0); // 空白处新建的分组框大小
+const BORDER_TOLERANCE: f32 = 5.0; // 边框两侧可选中的距离
+const HANDLE_SIZE: f32 = 14.0; // 右下角调整柄的边长
+
+/// 指针所在的分组框部位
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GroupHit {
+    Frame,  // 边框或标签栏，拖动时移动分组框
+    Resize, // 右下角的调整柄，拖动时调整大小
+}
+
+/// 标签栏在屏幕上的范围，分组框过矮时不超出分组框
+pub fn header_rect(rect: Rect, zoom: f32) -> Rect {
+    let bottom = (rect.min.y + GROUP_HEADER_HEIGHT * zoom).min(rect.max.y);
+    Rect::from_min_max(rect.min, Pos2::new(rect.max.x, bottom))
+}
+
+/// 检测屏幕位置 pos 位于屏幕范围为 rect 的分组框的哪个部位
+pub fn hit_test(rect: Rect, zoom: f32, pos: Pos2) -> Option<GroupHit> {
+    if Rect::from_center_size(rect.max, Vec2::splat(HANDLE_SIZE)).contains(pos) {
+        return Some(GroupHit::Resize);
+    }
+    let on_border =
+        rect.expand(BORDER_TOLERANCE).contains(pos) && !rect.shrink(BORDER_TOLERANCE).contains(pos);
+    (on_border || header_rect(rect, zoom).contains(pos)).then_some(GroupHit::Frame)
+}
+
+/// 右下角移动 offset 后的范围，宽高不小于 GROUP_MIN_SIZE
+pub fn resized(rect: CoordinateBounds, offset: (f64, f64)) -> CoordinateBounds {
+    CoordinateBounds {
+        min: rect.min,
+        max: (
+            (rect.max.0 + offset.0).max(rect.min.0 + GROUP_MIN_SIZE),
+            (rect.max.1 + offset.1).max(rect.min.1 + GROUP_MIN_SIZE),
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hit_test() {
+        let rect = Rect::from_min_max(Pos2::new(0.0, 0.0), Pos2::new(400.0, 300.0));
+        // 边框和标签栏
+        assert_eq!(
+            hit_test(rect, 1.0, Pos2::new(-3.0, 150.0)),
+            Some(GroupHit::Frame)
+        );
+        assert_eq!(
+            hit_test(rect, 1.0, Pos2::new(200.0, 302.0)),
+            Some(GroupHit::Frame)
+        );
+        assert_eq!(
+            hit_test(rect, 1.0, Pos2::new(200.0, 20.0)),
+            Some(GroupHit::Frame)
+        );
+        // 标签栏随缩放变高
+        assert_eq!(
+            hit_test(rect, 2.0, Pos2::new(200.0, 40.0)),
+            Some(GroupHit::Frame)
+        );
+        // 右下角优先作为调整柄
+        assert_eq!(
+            hit_test(rect, 1.0, Pos2::new(398.0, 298.0)),
+            Some(GroupHit::Resize)
+        );
+        // 内部和外部属于画布
+        assert_eq!(hit_test(rect, 1.0, Pos2::new(200.0, 150.0)), None);
+        assert_eq!(hit_test(rect, 1.0, Pos2::new(200.0, 320.0)), None);
+    }
+
+    #[test]
+    fn test_resized() {
+        let rect = CoordinateBounds {
+            min: (0.0, 0.0),
+            max: (400.0, 300.0),
+        };
+        assert_eq!(resized(rect, (100.0, -50.0)).max, (500.0, 250.0));
+        assert_eq!(resized(rect, (-1000.0, -1000.0)).max, (100.0, 100.0));
+    }
+}
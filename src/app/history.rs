@@ -0,0 +1,178 @@
+//! 视图历史模块，为图谱的每条撤回记录保存修改时的选区、视口和该步涉及的主要节点，撤回和重做时恢复。
+//! 只保存界面状态，与 KnowledgeGraph 的撤回栈按修订号对应，不影响图谱数据。
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use eframe::egui::Vec2;
+
+use crate::graph::Snapshot;
+
+/// 修改图谱时的界面状态
+#[derive(Debug, Clone, PartialEq)]
+pub struct ViewContext {
+    pub selection: BTreeSet<u64>,
+    pub scroll_offset: Vec2,
+    pub zoom: f32,
+    pub primary: BTreeSet<u64>, // 该步修改增删或改动的节点，撤回后重新选中
+}
+
+/// 与图谱撤回栈一一对应的界面状态
+#[derive(Debug, Default)]
+pub struct ViewHistory {
+    undo: BTreeMap<u64, ViewContext>, // 以记录入栈时的修订号为键，最大的为最近的记录
+    redo: Vec<ViewContext>,
+    revision: u64,              // 上次观察到的图谱修订号
+    last: Option<ViewContext>,  // 上次观察到的界面状态，即修改前的状态
+    snapshot: Option<Snapshot>, // 上次观察到的快照，用于找出修改涉及的节点
+}
+
+impl ViewHistory {
+    /// 每帧调用。图谱发生了撤回和重做以外的修改时，为新增的每条撤回记录保存修改前的界面状态，
+    /// 以及相对上次观察到的快照增删或改动的节点。undo_len 为图谱撤回栈的长度，超出的最早记录被丢弃。
+    pub fn observe(
+        &mut self,
+        revision: u64,
+        undo_len: usize,
+        context: ViewContext,
+        snapshot: &Snapshot,
+    ) {
+        if revision != self.revision {
+            // 每次修改修订号加一，并压入一条撤回记录。一帧内的多次修改共用涉及的节点
+            if let Some(last) = self.last.as_ref() {
+                let primary = self
+                    .snapshot
+                    .as_ref()
+                    .map(|old| old.diff(snapshot).nodes.into_keys().collect())
+                    .unwrap_or_default();
+                for r in self.revision + 1..=revision {
+                    self.undo.insert(
+                        r,
+                        ViewContext {
+                            primary: BTreeSet::clone(&primary),
+                            ..last.clone()
+                        },
+                    );
+                }
+            }
+            self.redo.clear();
+            self.revision = revision;
+        }
+        self.trim(undo_len);
+        self.last = Some(context);
+        self.snapshot = Some(snapshot.clone());
+    }
+
+    /// 图谱撤回成功后调用，返回被撤回的修改发生时的界面状态。current 为撤回前的界面状态。
+    pub fn undo(&mut self, revision: u64, current: ViewContext) -> Option<ViewContext> {
+        let (_, context) = self.undo.pop_last()?;
+        self.redo.push(current);
+        self.revision = revision;
+        Some(context)
+    }
+
+    /// 图谱重做成功后调用，返回撤回前的界面状态。current 为重做前的界面状态。
+    pub fn redo(&mut self, revision: u64, current: ViewContext) -> Option<ViewContext> {
+        let context = self.redo.pop()?;
+        self.undo.insert(revision, current);
+        self.revision = revision;
+        Some(context)
+    }
+
+    /// 丢弃图谱撤回栈中已不存在的最早记录
+    fn trim(&mut self, undo_len: usize) {
+        while self.undo.len() > undo_len {
+            self.undo.pop_first();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::{DistinctEntityType, KnowledgeGraph, Relation};
+
+    fn context(selected: u64) -> ViewContext {
+        ViewContext {
+            selection: BTreeSet::from([selected]),
+            scroll_offset: Vec2::new(selected as f32, 0.0),
+            zoom: 1.0,
+            primary: BTreeSet::new(),
+        }
+    }
+
+    #[test]
+    fn test_undo_redo_context() {
+        let mut history = ViewHistory::default();
+        let snapshot = Snapshot::default();
+        history.observe(0, 0, context(1), &snapshot);
+        // 修改时选中 2，修改前选中 1
+        history.observe(1, 1, context(2), &snapshot);
+        history.observe(2, 2, context(3), &snapshot);
+
+        assert_eq!(history.undo(3, context(3)), Some(context(2)));
+        assert_eq!(history.undo(4, context(2)), Some(context(1)));
+        assert_eq!(history.undo(5, context(1)), None);
+
+        // 重做恢复撤回前的状态
+        assert_eq!(history.redo(5, context(1)), Some(context(2)));
+        history.observe(5, 1, context(2), &snapshot);
+        assert_eq!(history.undo(6, context(2)), Some(context(1)));
+    }
+
+    #[test]
+    fn test_trim_and_new_mutation() {
+        let mut history = ViewHistory::default();
+        let snapshot = Snapshot::default();
+        history.observe(0, 0, context(0), &snapshot);
+        // 一帧内发生多次修改，撤回栈只保留最近的两条
+        history.observe(3, 2, context(3), &snapshot);
+        assert_eq!(history.undo.len(), 2);
+
+        assert!(history.undo(4, context(3)).is_some());
+        // 新的修改清空重做记录
+        history.observe(5, 2, context(5), &snapshot);
+        assert_eq!(history.redo(6, context(5)), None);
+    }
+
+    #[test]
+    fn test_primary_nodes() {
+        let mut graph = KnowledgeGraph::default();
+        let mut history = ViewHistory::default();
+        let kept = graph
+            .add_entity(
+                "Kept".to_string(),
+                DistinctEntityType::KnowledgePoint,
+                &[],
+                (0.0, 0.0),
+            )
+            .unwrap();
+        let removed = graph
+            .add_entity(
+                "Removed".to_string(),
+                DistinctEntityType::KnowledgePoint,
+                &[],
+                (200.0, 0.0),
+            )
+            .unwrap();
+        graph.add_edge(kept, removed, Relation::Contain).unwrap();
+        history.observe(
+            graph.revision(),
+            graph.undo_len(),
+            context(removed),
+            &graph.current,
+        );
+
+        // 删除节点的记录只涉及被删除的节点，不包括边另一端的节点
+        graph.remove_entity(removed).unwrap();
+        history.observe(
+            graph.revision(),
+            graph.undo_len(),
+            context(kept),
+            &graph.current,
+        );
+        graph.undo().unwrap();
+        let view = history.undo(graph.revision(), context(kept)).unwrap();
+        assert_eq!(view.primary, BTreeSet::from([removed]));
+        assert_eq!(view.selection, BTreeSet::from([removed]));
+    }
+}
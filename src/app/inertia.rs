@@ -0,0 +1,144 @@
+//! 惯性滚动模块。双指或滚轮平移画布结束后，按最后的速度继续滑动一小段并逐渐减速停止。
+
+use std::{
+    collections::VecDeque,
+    time::{Duration, Instant},
+};
+
+use eframe::egui::Vec2;
+
+const SAMPLE_WINDOW: Duration = Duration::from_millis(100); // 只用最近这段时间内的位移求速度
+const TIME_CONSTANT: f32 = 0.3; // 速度衰减到 1/e 所需的秒数
+const MIN_SPEED: f32 = 30.0; // 速度低于此值（点/秒）时停止
+
+/// 惯性滚动的状态
+#[derive(Debug, Default)]
+pub struct Inertia {
+    samples: VecDeque<(Instant, Vec2)>, // 最近几帧用户平移的时间和滚动偏移的变化
+    velocity: Vec2,                     // 惯性滚动的速度（点/秒），为零时不在滚动
+}
+
+impl Inertia {
+    /// 用户平移时每帧调用，记录滚动偏移的变化，并停止正在进行的惯性滚动
+    pub fn record(&mut self, now: Instant, delta: Vec2) {
+        self.velocity = Vec2::ZERO;
+        self.samples.push_back((now, delta));
+        while let Some((time, _)) = self.samples.front()
+            && now.saturating_duration_since(*time) > SAMPLE_WINDOW
+        {
+            self.samples.pop_front();
+        }
+    }
+
+    /// 用户停止平移时调用，以最近几帧的平均速度开始惯性滚动，速度太小时不滚动
+    pub fn release(&mut self, now: Instant) {
+        let samples = std::mem::take(&mut self.samples);
+        let Some((start, _)) = samples
+            .iter()
+            .find(|(time, _)| now.saturating_duration_since(*time) <= SAMPLE_WINDOW)
+        else {
+            return;
+        };
+        let elapsed = now.saturating_duration_since(*start).as_secs_f32();
+        if elapsed <= 0.0 {
+            return;
+        }
+        // 第一个采样的位移发生在它之前，不计入
+        let distance = samples
+            .iter()
+            .filter(|(time, _)| time > start)
+            .fold(Vec2::ZERO, |sum, (_, delta)| sum + *delta);
+        let velocity = distance / elapsed;
+        if velocity.length() >= MIN_SPEED {
+            self.velocity = velocity;
+        }
+    }
+
+    /// 立即停止惯性滚动，并丢弃记录的位移
+    pub fn stop(&mut self) {
+        self.velocity = Vec2::ZERO;
+        self.samples.clear();
+    }
+
+    /// 是否正在惯性滚动
+    #[inline]
+    pub fn is_active(&self) -> bool {
+        self.velocity != Vec2::ZERO
+    }
+
+    /// 是否记录了用户平移，即平移尚未结束
+    #[inline]
+    pub fn is_tracking(&self) -> bool {
+        !self.samples.is_empty()
+    }
+
+    /// 惯性滚动时每帧调用，返回经过 dt 秒后滚动偏移的变化，速度按指数衰减，低于阈值时停止
+    pub fn step(&mut self, dt: f32) -> Vec2 {
+        if !self.is_active() {
+            return Vec2::ZERO;
+        }
+        // 对衰减的速度在 dt 内积分，使位移与帧率无关
+        let decay = (-dt / TIME_CONSTANT).exp();
+        let delta = self.velocity * TIME_CONSTANT * (1.0 - decay);
+        self.velocity *= decay;
+        if self.velocity.length() < MIN_SPEED {
+            self.velocity = Vec2::ZERO;
+        }
+        delta
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_inertia() {
+        let start = Instant::now();
+        let frame = Duration::from_millis(10);
+        let mut inertia = Inertia::default();
+
+        // 以 1000 点/秒平移，松开后开始惯性滚动
+        for i in 1..=10 {
+            inertia.record(start + frame * i, Vec2::new(10.0, 0.0));
+        }
+        inertia.release(start + frame * 10);
+        assert!(inertia.is_active());
+        assert!(!inertia.is_tracking());
+
+        // 总位移约为速度乘以时间常数，与帧率无关，最终停止
+        let distance = |dt: f32, inertia: &mut Inertia| {
+            let mut total = Vec2::ZERO;
+            for _ in 0..1000 {
+                total += inertia.step(dt);
+            }
+            total
+        };
+        let mut other = Inertia {
+            velocity: inertia.velocity,
+            ..Default::default()
+        };
+        let slow = distance(1.0 / 30.0, &mut inertia);
+        let fast = distance(1.0 / 120.0, &mut other);
+        assert!(!inertia.is_active() && !other.is_active());
+        assert!(slow.y == 0.0 && (slow.x - fast.x).abs() < 2.0);
+        assert!(slow.x > 200.0 && slow.x < 1000.0 * TIME_CONSTANT);
+
+        // 停顿后才松开，或速度太小时不滚动
+        inertia.record(start, Vec2::new(10.0, 0.0));
+        inertia.release(start + Duration::from_millis(500));
+        assert!(!inertia.is_active());
+        inertia.record(start, Vec2::new(0.1, 0.0));
+        inertia.record(start + frame, Vec2::new(0.1, 0.0));
+        inertia.release(start + frame);
+        assert!(!inertia.is_active());
+
+        // 再次平移时停止惯性滚动
+        inertia.record(start, Vec2::new(10.0, 0.0));
+        inertia.record(start + frame, Vec2::new(10.0, 0.0));
+        inertia.release(start + frame);
+        assert!(inertia.is_active());
+        inertia.record(start + frame * 2, Vec2::new(1.0, 0.0));
+        assert!(!inertia.is_active());
+    }
+}
@@ -0,0 +1,122 @@
+//! 链接模块，识别节点内容中的 http(s) 网址，供画布以链接样式绘制并在单击时用浏览器打开，
+//! 并检查节点关联的链接（网址或本地文件路径）的格式。
+
+use std::ops::Range;
+
+const SCHEMES: [&str; 2] = ["http://", "https://"];
+
+/// 网址中允许出现的字符：除空白、引号和尖括号外的 ASCII 可见字符。
+/// 中文等非 ASCII 字符（包括全角标点）视为网址的结束
+#[inline]
+fn is_url_char(c: char) -> bool {
+    c.is_ascii_graphic() && !matches!(c, '"' | '<' | '>')
+}
+
+/// 网址末尾不计入网址的标点，如句末的句号和包裹网址的右括号
+#[inline]
+fn is_trailing_punctuation(c: char) -> bool {
+    matches!(
+        c,
+        '.' | ',' | ';' | ':' | '!' | '?' | '\'' | ')' | ']' | '}'
+    )
+}
+
+/// 查找文本中的 http(s) 网址，返回各网址的字节范围。协议名后没有内容的不视为网址
+pub fn find_urls(text: &str) -> Vec<Range<usize>> {
+    let mut urls = Vec::new();
+    let mut start = 0;
+    while let Some(offset) = text[start..].find("http") {
+        let begin = start + offset;
+        let rest = &text[begin..];
+        let len = rest.find(|c| !is_url_char(c)).unwrap_or(rest.len());
+        start = begin + len.max(1);
+
+        // 前面紧跟字母或数字时是其他单词的一部分
+        if text[..begin]
+            .chars()
+            .next_back()
+            .is_some_and(|c| c.is_ascii_alphanumeric())
+        {
+            continue;
+        }
+        let Some(scheme) = SCHEMES.iter().find(|scheme| rest.starts_with(*scheme)) else {
+            continue;
+        };
+        let url = rest[..len].trim_end_matches(is_trailing_punctuation);
+        if url.len() > scheme.len() {
+            urls.push(begin..begin + url.len());
+        }
+    }
+    urls
+}
+
+/// 是否为可以打开的节点链接：协议名后有内容的 http(s) 或 file 网址，或本地文件的绝对路径
+/// （包括 Windows 的 `C:\` 和 `\\服务器` 形式）。只检查格式，不检查是否存在
+pub fn is_valid_link(link: &str) -> bool {
+    let link = link.trim();
+    if let Some(rest) = SCHEMES
+        .iter()
+        .chain(&["file://"])
+        .find_map(|scheme| link.strip_prefix(scheme))
+    {
+        return !rest.is_empty() && rest.chars().all(|c| !c.is_whitespace());
+    }
+    let mut chars = link.chars();
+    let windows_drive = matches!(
+        (chars.next(), chars.next(), chars.next()),
+        (Some(drive), Some(':'), Some('\\' | '/')) if drive.is_ascii_alphabetic()
+    );
+    windows_drive || link.starts_with("\\\\") || link.starts_with('/')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn urls(text: &str) -> Vec<&str> {
+        find_urls(text)
+            .into_iter()
+            .map(|range| &text[range])
+            .collect()
+    }
+
+    #[test]
+    fn test_find_urls() {
+        assert!(urls("没有链接").is_empty());
+        assert_eq!(urls("https://example.com"), ["https://example.com"]);
+        assert_eq!(
+            urls("参考 http://a.org/x?y=1&z=2 和https://b.cn/路径"),
+            ["http://a.org/x?y=1&z=2", "https://b.cn/"]
+        );
+        // 中文标点和末尾的英文标点不计入网址
+        assert_eq!(urls("见https://a.org/b。"), ["https://a.org/b"]);
+        assert_eq!(urls("(see https://a.org/b)."), ["https://a.org/b"]);
+        // 只有协议名、其他协议或单词中间的 http 不是网址
+        assert!(urls("http:// ftp://a.org xhttp://a.org httpd").is_empty());
+    }
+
+    #[test]
+    fn test_is_valid_link() {
+        for link in [
+            "https://www.icourse163.org/course/PKU-1001",
+            " http://a.org ",
+            "file:///home/a.pptx",
+            r"C:\课件\第一章.pptx",
+            "D:/视频/1.mp4",
+            r"\\server\share\a.ppt",
+            "/home/teacher/课件.pdf",
+        ] {
+            assert!(is_valid_link(link), "{link}");
+        }
+        for link in [
+            "",
+            "https://",
+            "https://a b",
+            "www.a.org",
+            "课件.pptx",
+            "C:",
+        ] {
+            assert!(!is_valid_link(link), "{link}");
+        }
+    }
+}
@@ -0,0 +1,233 @@
+//! 日志模块。图形界面没有可见的标准错误输出，错误、警告和 panic 写入用户数据目录下的日志文件，
+//! 便于事后排查。日志文件超过大小上限时滚动，只保留最近的几个。
+
+use std::{
+    fs::{self, File, OpenOptions},
+    io::Write,
+    panic,
+    path::{Path, PathBuf},
+    sync::{Mutex, OnceLock},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use log::{LevelFilter, Log, Metadata, Record};
+
+/// 日志文件名
+const LOG_FILE: &str = "better_kt_sqep.log";
+
+/// 单个日志文件的大小上限，超过后滚动
+const MAX_LOG_SIZE: u64 = 1024 * 1024;
+
+/// 滚动后保留的旧日志数，依次为 .log.1、.log.2……
+const LOG_BACKUPS: usize = 3;
+
+/// 当前日志文件的路径，初始化成功后设置
+static LOG_PATH: OnceLock<PathBuf> = OnceLock::new();
+
+/// 按大小滚动的日志文件
+#[derive(Debug)]
+struct RollingFile {
+    path: PathBuf,
+    max_size: u64,
+    backups: usize,
+    file: Option<File>, // 打开失败或滚动失败后为 None，下次写入时重试
+    size: u64,
+}
+
+impl RollingFile {
+    fn new(path: PathBuf, max_size: u64, backups: usize) -> Self {
+        Self {
+            path,
+            max_size,
+            backups,
+            file: None,
+            size: 0,
+        }
+    }
+
+    /// 第 index 个旧日志的路径
+    fn backup_path(&self, index: usize) -> PathBuf {
+        let mut path = self.path.clone().into_os_string();
+        path.push(format!(".{index}"));
+        PathBuf::from(path)
+    }
+
+    /// 写入一行。写入后超过大小上限时，将旧日志依次后移，最旧的被覆盖
+    fn write_line(&mut self, line: &str) -> std::io::Result<()> {
+        if self.file.is_none() {
+            let file = OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(&self.path)?;
+            self.size = file.metadata()?.len();
+            self.file = Some(file);
+        }
+        if let Some(file) = self.file.as_mut() {
+            writeln!(file, "{line}")?;
+            self.size += line.len() as u64 + 1;
+        }
+
+        if self.size > self.max_size {
+            self.file = None;
+            for index in (1..self.backups).rev() {
+                let from = self.backup_path(index);
+                if from.exists() {
+                    fs::rename(from, self.backup_path(index + 1))?;
+                }
+            }
+            if self.backups > 0 {
+                fs::rename(&self.path, self.backup_path(1))?;
+            } else {
+                fs::remove_file(&self.path)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// 写入文件的日志记录器
+struct FileLogger {
+    file: Mutex<RollingFile>,
+}
+
+impl Log for FileLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= log::max_level()
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+        let line = format!(
+            "{} [{}] {}",
+            format_time(SystemTime::now()),
+            record.level(),
+            record.args()
+        );
+        // 日志写入失败无处报告，忽略
+        if let Ok(mut file) = self.file.lock() {
+            let _ = file.write_line(&line);
+        }
+    }
+
+    fn flush(&self) {
+        if let Ok(mut file) = self.file.lock()
+            && let Some(file) = file.file.as_mut()
+        {
+            let _ = file.flush();
+        }
+    }
+}
+
+/// 将时间格式化为 UTC 的 `年-月-日 时:分:秒Z`
+fn format_time(time: SystemTime) -> String {
+    let secs = time
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |duration| duration.as_secs());
+    let (days, rest) = (secs / 86400, secs % 86400);
+
+    // 由 1970-01-01 起的天数计算公历日期
+    let z = days as i64 + 719_468;
+    let era = z.div_euclid(146_097);
+    let day_of_era = z.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+
+    format!(
+        "{year:04}-{month:02}-{day:02} {:02}:{:02}:{:02}Z",
+        rest / 3600,
+        rest % 3600 / 60,
+        rest % 60
+    )
+}
+
+/// 在 app_id 对应的用户数据目录下的 logs 文件夹中记录日志，并记录 panic 的信息。
+/// 无法确定或创建目录时不记录日志
+pub fn init(app_id: &str) {
+    let Some(dir) = eframe::storage_dir(app_id).map(|dir| dir.join("logs")) else {
+        return;
+    };
+    if fs::create_dir_all(&dir).is_err() {
+        return;
+    }
+    let path = dir.join(LOG_FILE);
+    let logger = FileLogger {
+        file: Mutex::new(RollingFile::new(path.clone(), MAX_LOG_SIZE, LOG_BACKUPS)),
+    };
+    if log::set_boxed_logger(Box::new(logger)).is_err() {
+        return;
+    }
+    log::set_max_level(LevelFilter::Info);
+    let _ = LOG_PATH.set(path);
+
+    // 先写入日志，再交给原有的处理
+    let default_hook = panic::take_hook();
+    panic::set_hook(Box::new(move |info| {
+        log::error!("panic: {info}");
+        log::logger().flush();
+        default_hook(info);
+    }));
+}
+
+/// 日志文件的路径，未记录日志时返回 None
+#[inline]
+pub fn log_path() -> Option<&'static Path> {
+    LOG_PATH.get().map(PathBuf::as_path)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    #[test]
+    fn test_format_time() {
+        assert_eq!(format_time(UNIX_EPOCH), "1970-01-01 00:00:00Z");
+        let time = UNIX_EPOCH + Duration::from_secs(951_827_696); // 闰年的 2 月 29 日
+        assert_eq!(format_time(time), "2000-02-29 12:34:56Z");
+        let time = UNIX_EPOCH + Duration::from_secs(1_792_108_799);
+        assert_eq!(format_time(time), "2026-10-15 23:59:59Z");
+    }
+
+    #[test]
+    fn test_rolling_file() -> std::io::Result<()> {
+        let dir = std::env::temp_dir().join(format!("better_kt_sqep_log_{}", std::process::id()));
+        fs::create_dir_all(&dir)?;
+        let path = dir.join(LOG_FILE);
+        let mut file = RollingFile::new(path.clone(), 20, 2);
+
+        // 每两行超过上限滚动一次，只保留两个旧日志
+        for i in 0..8 {
+            file.write_line(&format!("line {i:05}"))?;
+        }
+        assert!(!path.exists());
+        assert_eq!(
+            fs::read_to_string(file.backup_path(1))?,
+            "line 00006\nline 00007\n"
+        );
+        assert_eq!(
+            fs::read_to_string(file.backup_path(2))?,
+            "line 00004\nline 00005\n"
+        );
+        assert!(!file.backup_path(3).exists());
+
+        // 重新打开时接着已有的内容计算大小
+        file.write_line("line 00008")?;
+        let mut reopened = RollingFile::new(path.clone(), 20, 2);
+        reopened.write_line("line 00009")?;
+        assert!(!path.exists());
+        assert_eq!(
+            fs::read_to_string(file.backup_path(1))?,
+            "line 00008\nline 00009\n"
+        );
+
+        fs::remove_dir_all(&dir)
+    }
+}
@@ -0,0 +1,8099 @@
+use std::{
+    borrow::Cow,
+    collections::{BTreeSet, HashMap, HashSet},
+    fs,
+    ops::Range,
+    path::PathBuf,
+    sync::Arc,
+    time,
+};
+
+use click::DoubleClick;
+use compare::{CompareMode, Comparison, OVERLAY_BASE_COLOR, change_color, split_rects};
+use edge_draft::{EdgeDraft, RELATION_KEYS, RelationChoice, relation_choice, suggested_relation};
+use filter::{ADDON_TYPES, DISTINCT_TYPES, NodeFilter};
+use fold::{Folding, PREVIEW_LIMIT, subtree_preview};
+use group::{GROUP_DEFAULT_SIZE, GroupHit};
+use history::{ViewContext, ViewHistory};
+use inertia::Inertia;
+use notification::{Notifications, Severity};
+use settings::{Detail, NodeTemplate, SETTINGS_KEY, Settings};
+use stagger::{Anchor, stagger};
+use theme::{Palette, Theme};
+use touch::LongPress;
+use transition::Transition;
+use underlay::{Underlay, ViewSidecar};
+use viewport::{interactable_rect, is_interactable, overlay_scrollbar_width};
+
+use eframe::{
+    App,
+    egui::{
+        self, Align2, Color32, Context, FontFamily, FontId, Painter, Pos2, Rect, RichText, Sense,
+        Stroke, TextFormat, Vec2,
+        text::{CCursor, CCursorRange, LayoutJob},
+    },
+    emath::Rot2,
+};
+
+use crate::{
+    error::{Error, GraphError},
+    file::{
+        FiledKnowledgeGraph, UnitExport, export_compatible, export_split_by_arena, export_units,
+        session::{self, Session, SessionView},
+        task::BackgroundTask,
+    },
+    graph::{
+        self, AddonEntityType, Bookmark, Change, Command, CoordinateBounds, DEFAULT_CONTENT_LIMIT,
+        DEFAULT_COORDINATE_BOUNDS, DEFAULT_GROUP_COLOR, DEFAULT_TITLE, DistinctEntityType,
+        EntityNode, Flag, Glossary, GraphMetadata, GroupFrame, IssueLevel, LayoutDirection, Macro,
+        MarkerStyle, NormalizeOptions, ReciprocalEdge, Relation, RelationRegistry, RemovePolicy,
+        RepairReport, Snapshot, Statistics, TermChecker, ValidationIssue, apply_term_matches,
+        relation_registry, set_relation_registry,
+    },
+};
+
+mod click;
+mod compare;
+mod edge_draft;
+mod filter;
+mod fold;
+mod group;
+mod history;
+mod inertia;
+mod link;
+pub mod logging;
+mod notification;
+mod settings;
+mod stagger;
+mod theme;
+mod touch;
+mod transition;
+mod underlay;
+mod viewport;
+
+const NODE_SIZE: Vec2 = Vec2::new(graph::NODE_SIZE.0 as f32, graph::NODE_SIZE.1 as f32);
+const TOP_PANEL_HEIGHT: f32 = 50.0;
+const FLOW_DOT_SPACING: f32 = 30.0; // 次序边流向动画中小点的间距
+const FLOW_DOT_SPEED: f32 = 20.0; // 次序边流向动画中小点每秒移动的距离
+const LOGICAL_GRID: f64 = 1.0; // 逻辑坐标的最小单位，保存的坐标均为其整数倍
+const DEFAULT_MAX_NODES: usize = 500; // 启用节点数限制时的默认值
+const DEFAULT_MAX_EDGES: usize = 1000; // 启用边数限制时的默认值
+const PASTE_CONTENT_LIMIT: usize = 200; // 粘贴时单个节点内容的最大字符数
+const PASTE_NODE_SPACING: f64 = 150.0; // 粘贴的节点纵向间距
+const HOTKEY_KEYS: [egui::Key; 9] = [
+    egui::Key::Num1,
+    egui::Key::Num2,
+    egui::Key::Num3,
+    egui::Key::Num4,
+    egui::Key::Num5,
+    egui::Key::Num6,
+    egui::Key::Num7,
+    egui::Key::Num8,
+    egui::Key::Num9,
+]; // 按 Ctrl 和数字键跳转到快捷位 1~9 绑定的节点
+const ADDON_KEYS: [egui::Key; 6] = [
+    egui::Key::Num1,
+    egui::Key::Num2,
+    egui::Key::Num3,
+    egui::Key::Num4,
+    egui::Key::Num5,
+    egui::Key::Num6,
+]; // 按 Alt 和数字键按图例的顺序切换附加类型
+const DRAW_CONTENT_LIMIT: usize = 100; // 画布上节点最多显示的字符数，超出部分以省略号代替
+const NODE_CORNER_RADIUS: f32 = 10.0;
+const ADDON_COLLAPSE_COUNT: usize = 3; // 附加类型超过此数量时折叠，只显示前两个和剩余数量
+const ADDON_BADGE_ZOOM: f32 = 0.8; // 缩放低于此值时附加类型只显示数量
+const BREADCRUMB_CONTENT_LIMIT: usize = 12; // 面包屑中每个节点最多显示的字符数
+const CONTENT_VERSIONS: usize = 5; // 编辑窗口中最多列出的内容历史版本数
+const VERSION_CONTENT_LIMIT: usize = 30; // 历史版本菜单中每个版本最多显示的字符数
+const DOUBLE_CLICK_ZOOM: f32 = 1.5; // 按住 Alt 双击空白处时放大的倍数
+const SCALE_HINT_RATIO: f64 = 3.0; // 打开文件时估算的缩放系数超出此倍数范围则提示缩放坐标
+const WAYPOINT_HANDLE_RADIUS: f32 = 5.0; // 选中的边上折点拖动柄的半径
+const MARKER_SIZE: f32 = 8.0; // 关系符号的大小，即箭头的长度和半圆的半径
+const MARKER_GAP: f32 = 16.0; // 错开关系符号时相邻符号的最小距离
+const EDGE_LABEL_GAP: f32 = 16.0; // 错开边标签时相邻标签的最小距离，随缩放比例放大
+const LOD_LABEL_SIZE: Vec2 = Vec2::new(12.0, 10.0); // 简化渲染不绘制文字时标签框内部的大小
+
+/// 双向边及选择保留的边
+type EdgeChoice = (ReciprocalEdge, Option<(u64, u64)>);
+
+pub struct GraphApp {
+    pub graph: Option<FiledKnowledgeGraph>,
+
+    // 用户设置，以及由设置生成的主题
+    settings: Settings,
+    theme: Theme,
+    showing_settings: bool,
+
+    // 设置中的标准术语表，以及是否显示术语检查的结果
+    glossary: Option<Glossary>,
+    showing_term_issues: bool,
+
+    // 左键双击检测
+    double_click: DoubleClick,
+    // 设置中试用双击阈值的区域的双击检测，以及识别出的双击次数
+    trial_click: DoubleClick,
+    trial_double_clicks: usize,
+
+    // 就地编辑内容的节点及编辑中的内容，在节点原位显示编辑框
+    inline_editing: Option<(u64, String)>,
+
+    // 编辑的节点
+    editing_node: Option<u64>,
+    editing_content: String,
+    editing_distinct_type: DistinctEntityType,
+    editing_addon_types: HashMap<AddonEntityType, bool>,
+    editing_notes: String,
+    editing_reference: bool,    // 是否仅作参考、不导出
+    editing_links: Vec<String>, // 关联的网址或本地文件路径，每行一个
+    editing_new_node: bool,
+    editing_template_name: String,           // 保存为模板时输入的名称
+    content_selection: Option<Range<usize>>, // 下一帧在内容编辑框中选中的字符范围
+    pending_child: Option<PendingChild>, // 拖出边在空白处松开时待新建的节点，与 editing_node 互斥
+
+    // 上一次使用的节点类型
+    last_distinct_type: DistinctEntityType,
+
+    // 粘贴的多行文本，每行为 (内容, 备注)，以及放置的起始逻辑坐标
+    pasting_lines: Option<Vec<(String, String)>>,
+    pasting_origin: (f64, f64),
+
+    // 编辑的边
+    editing_edge: Option<(u64, u64)>,
+    editing_relation: Relation,
+    editing_edge_label: String,
+
+    // 编辑的图谱元数据
+    editing_metadata: Option<GraphMetadata>,
+
+    // 正在命名的画布书签
+    naming_bookmark: Option<String>,
+
+    // 上次按 Alt + 数字键切换附加类型的节点及切换后的修订号，连续切换同一节点时合并为一次操作
+    addon_toggle: Option<(BTreeSet<u64>, u64)>,
+
+    // 是否显示快捷键说明
+    showing_shortcuts: bool,
+
+    // 选中的节点或边，按住 Ctrl 单击可选中多个节点
+    selected_nodes: BTreeSet<u64>,
+    breadcrumb: (u64, usize), // 面包屑对应的节点，以及其有多条层级路径时显示的序号
+    selected_edges: BTreeSet<(u64, u64)>,
+
+    // 多选节点的统计信息，只在选区或图谱变化时重新计算
+    selection_stats: Option<SelectionStats>,
+
+    // 拖拽的节点
+    dragging_node: Option<u64>,
+    dragging_offset: Vec2,
+
+    // 选中的分组框，拖动边框或调整大小中的分组框（与拖拽的节点共用位移），
+    // 以及编辑中的分组框，ID 为 0 表示新建
+    selected_group: Option<u64>,
+    dragging_group: Option<(u64, GroupHit)>,
+    editing_group: Option<GroupFrame>,
+
+    // 拖动中的折点所在的边及其序号（与拖拽的节点共用位移）
+    dragging_waypoint: Option<((u64, u64), usize)>,
+
+    // 鼠标所在的节点或边
+    hovered_node: Option<(u64, bool)>,
+    hovered_edge: Option<(u64, u64)>,
+
+    // 绘制边的流程
+    edge_draft: EdgeDraft,
+    current_relation: Relation,
+
+    // 正在调整子节点顺序的节点，及调整中的子节点顺序
+    ordering_children: Option<(u64, Vec<u64>)>,
+    // 正在规范化的双向边，及每对选择保留的边，None 表示两条都保留
+    normalizing_edges: Option<Vec<EdgeChoice>>,
+    // 正在缩放坐标时输入的系数
+    scaling_coordinates: Option<f64>,
+
+    // 错误信息 (title, message)，以模态窗口显示
+    error: Option<(String, String)>,
+    // 最后写入日志的错误信息，同一个错误只记录一次
+    logged_error: Option<(String, String)>,
+    // 新建文件时选中的已有内容的文件，等待确认
+    confirming_new_file: Option<PathBuf>,
+    // 打开失败且可尝试宽容模式的文件
+    lenient_path: Option<PathBuf>,
+    // 后台执行的打开或导出任务，完成前显示进度窗口，可中途取消
+    file_task: Option<FileTask>,
+    // 批量导出单元的结果 (导出的文件夹, 结果)，以窗口列出写入的文件和跳过的单元
+    unit_export: Option<(PathBuf, UnitExport)>,
+    // 打开或导入文件时的警告 (说明, 警告列表)，如宽容模式忽略的内容
+    open_warnings: Option<(&'static str, Vec<String>)>,
+    // 检查并导出时未通过的问题，点击可跳转到相关节点
+    export_issues: Option<Vec<ValidationIssue>>,
+    // 非阻塞的提示和警告
+    notifications: Notifications,
+
+    // 用于记录图谱整体平移的偏移量
+    scroll_offset: Vec2,
+    canvas_rect: Rect, // 可交互的画布区域，不包括顶部控制栏和滚动条
+
+    // 用于记录缩放比例和缩放中心
+    zoom_factor: f32,
+
+    // 节点位置批量变化时的过渡动画
+    transition: Option<Transition>,
+    // 次序边流向动画的相位，即小点已移动的距离
+    flow_phase: f32,
+
+    // 最近的输入是否来自触摸屏或触控笔
+    touch_input: bool,
+    long_press: LongPress,
+    // 双指手势修改的滚动偏移，下一帧应用到滚动区域
+    pending_scroll: Option<Vec2>,
+    // 平移画布结束后的惯性滚动
+    inertia: Inertia,
+
+    // 右键菜单的位置，以及所在的节点或分组框
+    context_menu: Option<(Pos2, MenuTarget)>,
+
+    // 正在进行的版本对比，对比期间画布只读
+    comparing: Option<Comparison>,
+
+    // 最近一次自动修复的报告，关闭前一直显示
+    repair_report: Option<RepairReport>,
+
+    // 既有父节点又有子节点、等待选择如何处理子节点的待删除节点
+    removing_node: Option<u64>,
+
+    // 正在设置的规范化内容选项，打开规范化内容窗口时为 Some
+    normalizing: Option<NormalizeOptions>,
+
+    // 正在合并的两个节点 (保留的节点, 删除的节点, 是否拼接内容)
+    merging: Option<(u64, u64, bool)>,
+
+    // 打印时图谱横向所占的页数，打开打印窗口时为 Some
+    printing: Option<u32>,
+
+    // 正在预览的 XML，关闭预览窗口时为 None
+    previewing_xml: Option<String>,
+
+    // 欢迎页中最近文件的标题和节点数，读取失败时为 None
+    recent_titles: HashMap<PathBuf, Option<(String, usize)>>,
+
+    // 当前文件的视图设置，保存在图谱文件旁的视图文件中
+    view_sidecar: ViewSidecar,
+    // 已上传的底图纹理及其图片路径，图片路径变化时重新读取
+    underlay_texture: Option<(PathBuf, egui::TextureHandle)>,
+
+    // 聚焦的节点，只正常显示它及其一跳邻居，按 Esc 退出
+    focused_node: Option<u64>,
+    // 节点的折叠状态，折叠的节点的下级节点不显示；以及批量展开到的层数
+    folding: Folding,
+    fold_level: usize,
+
+    // 图例面板及其筛选条件，被筛除的节点淡化显示
+    showing_legend: bool,
+    filter: NodeFilter,
+    // 按评审标记分组列出节点的面板
+    showing_flag_list: bool,
+    // 整个图谱的统计信息及其对应的修订号，只在图谱变化时重新计算
+    graph_stats: Option<(u64, Statistics)>,
+
+    // 每条撤回记录对应的选区和视口，撤回和恢复时还原
+    view_history: ViewHistory,
+}
+
+/// 拖出边在空白处松开时待新建的节点，在编辑窗口中保存时才与边一起创建
+#[derive(Debug, Clone, Copy)]
+struct PendingChild {
+    from: u64,          // 边的起点
+    coor: (f64, f64),   // 新节点的逻辑坐标
+    relation: Relation, // 起点到新节点的关系
+}
+
+/// 在后台执行的文件任务及其目标文件
+enum FileTask {
+    Open(PathBuf, BackgroundTask<FiledKnowledgeGraph>),
+    Export(PathBuf, BackgroundTask<()>),
+    ExportUnits(PathBuf, BackgroundTask<UnitExport>),
+}
+
+/// 右键菜单作用的对象
+#[derive(Debug, Clone, Copy)]
+enum MenuTarget {
+    Canvas,
+    Node(u64),
+    Group(u64),
+    Edge((u64, u64)),
+}
+
+/// 多选节点的统计信息及其对应的修订号和选区
+struct SelectionStats {
+    revision: u64,
+    selection: BTreeSet<u64>,
+    stats: Statistics,     // 由选中节点构成的子图的统计
+    boundary_edges: usize, // 连接选区内外的边数
+}
+
+impl Default for GraphApp {
+    fn default() -> Self {
+        Self {
+            graph: None,
+            settings: Settings::default(),
+            theme: Theme::default(),
+            showing_settings: false,
+            glossary: None,
+            showing_term_issues: false,
+            double_click: DoubleClick::default(),
+            trial_click: DoubleClick::default(),
+            trial_double_clicks: 0,
+            inline_editing: None,
+            editing_node: None,
+            editing_content: String::new(),
+            editing_distinct_type: DistinctEntityType::KnowledgeArena,
+            editing_addon_types: HashMap::with_capacity(6),
+            editing_notes: String::new(),
+            editing_reference: false,
+            editing_links: Vec::new(),
+            editing_new_node: false,
+            editing_template_name: String::new(),
+            content_selection: None,
+            pending_child: None,
+            last_distinct_type: DistinctEntityType::KnowledgePoint,
+            pasting_lines: None,
+            pasting_origin: (0.0, 0.0),
+            editing_edge: None,
+            editing_relation: Relation::Contain,
+            editing_edge_label: String::new(),
+            editing_metadata: None,
+            naming_bookmark: None,
+            addon_toggle: None,
+            showing_shortcuts: false,
+            selected_nodes: BTreeSet::new(),
+            breadcrumb: (0, 0),
+            selected_edges: BTreeSet::new(),
+            selection_stats: None,
+            dragging_node: None,
+            dragging_offset: Vec2::ZERO,
+            selected_group: None,
+            dragging_group: None,
+            dragging_waypoint: None,
+            editing_group: None,
+            hovered_node: None,
+            hovered_edge: None,
+            edge_draft: EdgeDraft::default(),
+            current_relation: Relation::Contain,
+            ordering_children: None,
+            normalizing_edges: None,
+            scaling_coordinates: None,
+            error: None,
+            logged_error: None,
+            confirming_new_file: None,
+            lenient_path: None,
+            file_task: None,
+            unit_export: None,
+            open_warnings: None,
+            export_issues: None,
+            notifications: Notifications::default(),
+            scroll_offset: Vec2::ZERO,
+            canvas_rect: Rect::NOTHING,
+            zoom_factor: 1.0,
+            transition: None,
+            flow_phase: 0.0,
+            touch_input: false,
+            long_press: LongPress::default(),
+            pending_scroll: None,
+            inertia: Inertia::default(),
+            context_menu: None,
+            comparing: None,
+            focused_node: None,
+            folding: Folding::default(),
+            fold_level: 2,
+            showing_legend: false,
+            filter: NodeFilter::default(),
+            showing_flag_list: false,
+            graph_stats: None,
+            repair_report: None,
+            removing_node: None,
+            normalizing: None,
+            merging: None,
+            printing: None,
+            previewing_xml: None,
+            recent_titles: HashMap::new(),
+            view_sidecar: ViewSidecar::default(),
+            underlay_texture: None,
+            view_history: ViewHistory::default(),
+        }
+    }
+}
+
+macro_rules! dialog_error {
+    ($this:ident, $result:expr, $ignored_errors:expr, $msg:expr) => {
+        if let Err(e) = $result {
+            if $ignored_errors.iter().all(|err| e != *err) {
+                $this.error = Some(($msg.to_string(), e.zh_message()));
+            }
+        }
+    };
+}
+
+impl GraphApp {
+    /// 创建应用，并恢复上次保存的设置
+    pub fn new(cc: &eframe::CreationContext<'_>) -> Self {
+        let settings = cc
+            .storage
+            .and_then(|storage| eframe::get_value::<Settings>(storage, SETTINGS_KEY))
+            .unwrap_or_default();
+        let mut app = Self {
+            theme: settings.theme(),
+            settings,
+            ..Default::default()
+        };
+        app.load_glossary();
+        app.load_relation_types();
+        app
+    }
+
+    /// 读取设置中的关系类型表，未设置或读取失败时使用内置的关系。读取失败时提示，设置保持不变。
+    /// 关系类型表是进程级的，所有标签页中的图谱共用
+    fn load_relation_types(&mut self) {
+        let Some(path) = self.settings.relation_types_path.as_ref() else {
+            set_relation_registry(RelationRegistry::default());
+            return;
+        };
+        let result = fs::read(path).map_err(Error::from).and_then(|bytes| {
+            let text = graph::decode_xml(&bytes)?;
+            Ok(RelationRegistry::from_json(&text)?)
+        });
+        match result {
+            Ok(registry) => {
+                set_relation_registry(registry);
+            }
+            Err(e) => {
+                set_relation_registry(RelationRegistry::default());
+                self.notifications
+                    .warning(format!("读取关系类型表失败：{}", e.zh_message()));
+            }
+        }
+    }
+
+    /// 读取设置中的术语表。读取失败时提示，设置保持不变，以便文件恢复后重新读取
+    fn load_glossary(&mut self) {
+        self.glossary = None;
+        let Some(path) = self.settings.glossary_path.as_ref() else {
+            return;
+        };
+        let result = fs::read(path).map_err(Error::from).and_then(|bytes| {
+            let text = graph::decode_xml(&bytes)?;
+            Ok(Glossary::from_text(&text)?)
+        });
+        match result {
+            Ok(glossary) => self.glossary = Some(glossary),
+            Err(e) => self
+                .notifications
+                .warning(format!("读取术语表失败：{}", e.zh_message())),
+        }
+    }
+}
+
+impl App for GraphApp {
+    fn save(&mut self, storage: &mut dyn eframe::Storage) {
+        eframe::set_value(storage, SETTINGS_KEY, &self.settings);
+        // 退出时也会调用，写入等待自动保存的修改，以免丢失
+        if let Some(graph) = self.graph.as_ref()
+            && let Err(e) = graph.flush()
+        {
+            self.error = Some(("自动保存失败".to_string(), e.zh_message()));
+        }
+    }
+
+    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        egui::TopBottomPanel::top("控制栏")
+            .min_height(TOP_PANEL_HEIGHT)
+            .max_height(TOP_PANEL_HEIGHT)
+            .show(ctx, |ui| {
+                self.show_topbar(ui);
+            });
+        egui::TopBottomPanel::bottom("状态栏").show(ctx, |ui| {
+            self.show_statusbar(ui);
+        });
+        self.show_legend_panel(ctx);
+        self.show_flag_list_panel(ctx);
+        self.show_selection_panel(ctx);
+        egui::CentralPanel::default().show(ctx, |ui| {
+            // 拖动节点或连接边时暂缓自动保存
+            let interacting = self.is_dragging() || self.is_linking_edge();
+            if let Some(graph) = self.graph.as_mut() {
+                graph.set_interacting(interacting);
+            }
+
+            // 处理自动保存的结果
+            self.process_autosave_results();
+
+            // 更新过渡动画
+            self.update_transition(ctx);
+
+            // 推进次序边的流向动画
+            self.update_flow_phase(ctx);
+
+            // 底图图片变化时重新读取
+            self.update_underlay_texture(ctx);
+
+            // 绘制错误信息
+            self.show_error_popup(ctx);
+
+            // 新建文件时选中了已有文件，确认如何处理
+            self.show_confirm_new_file_window(ctx);
+
+            // 正在后台打开或导出时，显示进度并处理结果
+            self.show_file_task_window(ctx);
+
+            // 批量导出单元完成后，显示导出的结果
+            self.show_unit_export_window(ctx);
+
+            // 绘制非阻塞通知
+            self.show_notifications(ctx);
+
+            // 如果打开了设置，则弹出设置窗口
+            self.show_settings_window(ctx);
+
+            // 如果正在对比，则显示对比图例
+            self.show_comparison_window(ctx);
+
+            // 如果选中了多条边，则显示批量操作窗口
+            self.show_edge_batch_window(ctx);
+
+            // 如果检查并导出未通过，则显示问题列表
+            self.show_export_issues_window(ctx);
+
+            // 如果进行了自动修复，则显示修复报告
+            self.show_repair_report_window(ctx);
+
+            // 如果删除的节点既有父节点又有子节点，则选择如何处理子节点
+            self.show_remove_node_window(ctx);
+
+            // 如果正在规范化内容，则显示选项窗口
+            self.show_normalize_window(ctx);
+
+            // 如果正在合并节点，则选择保留的节点
+            self.show_merge_window(ctx);
+
+            // 如果正在检查术语，则列出使用了非标准写法的节点
+            self.show_term_issues_window(ctx);
+
+            // 如果正在预览 XML，则显示预览窗口
+            self.show_xml_preview_window(ctx);
+
+            // 如果正在打印，则选择分页方式
+            self.show_print_window(ctx);
+
+            // 未打开文件时，显示提示信息
+            if self.graph.is_none() {
+                self.show_welcome_page(ui);
+                return;
+            }
+
+            let mut scroll_area = egui::ScrollArea::both()
+                .auto_shrink([false, false])
+                .drag_to_scroll(false); // 禁用拖动滚动，避免与拖动节点冲突
+            if let Some(offset) = self.pending_scroll.take() {
+                scroll_area = scroll_area.scroll_offset(offset);
+            }
+            let offset_before = self.scroll_offset;
+
+            let scroll_response = scroll_area.show(ui, |ui| {
+                // 计算内容边界以正确显示滚动条
+                let mut content_rect = Rect::NOTHING;
+                if let Some(graph) = self.graph.as_ref() {
+                    let snapshot = graph.current_snapshot();
+                    // 对比时还需包含已删除的节点
+                    let removed_nodes = self
+                        .comparing
+                        .iter()
+                        .flat_map(|c| c.base.nodes.values())
+                        .filter(|node| !snapshot.nodes.contains_key(&node.id));
+                    for node in snapshot.nodes.values().chain(removed_nodes) {
+                        let pos = self.node_screen_pos(node);
+                        let node_rect = Rect::from_center_size(pos, NODE_SIZE * self.zoom_factor);
+                        content_rect = content_rect.union(node_rect);
+                    }
+                    for group in snapshot.groups.iter() {
+                        content_rect = content_rect.union(self.group_screen_rect(group));
+                    }
+                }
+                content_rect = content_rect.expand(200.0); // 扩大一些边界，避免节点贴边
+                ui.expand_to_include_rect(content_rect); // 告诉UI内容区域大小
+
+                // 原有绘制和处理逻辑
+                let painter = ui.painter();
+
+                self.draw_edges_and_nodes(painter);
+
+                // 如果选中了节点，则突出显示
+                self.show_selected_node(painter);
+
+                // 如果选中了边，则突出显示
+                self.show_selected_edges(painter);
+
+                // 如果正在拖动节点，则进行绘制
+                self.show_dragging_node(painter);
+
+                // 如果正在拖动分组框或调整其大小，则进行绘制
+                self.show_dragging_group(painter);
+
+                // 如果正在拖动边的折点，则进行绘制
+                self.show_dragging_waypoint(painter);
+
+                // 如果鼠标悬停在节点或边上，则进行绘制
+                self.show_hovered_node(painter);
+
+                // 如果鼠标悬停在边上，则进行绘制
+                self.show_hovered_edge(painter);
+
+                // 如果鼠标悬停在折叠标记上，则预览隐藏的下级节点
+                self.show_fold_preview(ui);
+
+                // 如果正在绘制边，则进行绘制
+                self.show_drawing_edge(ui, painter, ctx);
+            });
+
+            self.scroll_offset = scroll_response.state.offset;
+            self.canvas_rect = interactable_rect(
+                scroll_response.inner_rect,
+                scroll_response.content_size,
+                overlay_scrollbar_width(&ui.style().spacing.scroll),
+            );
+
+            // 分屏对比且分别平移时，指针在哪一侧就只平移哪一侧
+            if let Some(comparison) = self.comparing.as_mut() {
+                let delta = self.scroll_offset - offset_before;
+                let (_, right) = split_rects(scroll_response.inner_rect);
+                let kept = comparison.split_pan(delta, ui.rect_contains_pointer(right));
+                if kept != delta {
+                    self.scroll_offset = offset_before + kept;
+                    self.pending_scroll = Some(self.scroll_offset);
+                }
+            }
+
+            // 处理触摸手势，包括长按和双指平移缩放
+            self.process_touch(ui);
+
+            // 平移结束后继续惯性滚动
+            self.process_inertia(ui, offset_before);
+
+            // 处理鼠标悬停事件
+            self.process_hover(ui);
+
+            // 检测点击事件
+            self.process_primary_click(ui);
+
+            // 检测右键点击，打开右键菜单
+            self.process_secondary_click(ui);
+
+            // 检测拖动事件，包括鼠标点击与抬起
+            self.process_primary_down(ui);
+
+            // 若鼠标左键抬起，则停止拖动节点
+            self.process_primary_up(ui);
+
+            // 检测删除
+            self.process_keyboard_delete(ui);
+
+            // 检测撤销和恢复
+            self.process_undo_redo(ui);
+
+            // 处理缩放
+            self.process_zoom(ctx);
+
+            // 检测保存按键
+            self.process_keyboard_save(ui);
+
+            // 按 Esc 退出聚焦
+            self.process_keyboard_unfocus(ui);
+
+            // 按 Ctrl + N 在视图中心新建节点
+            self.process_keyboard_new_node(ui);
+
+            // 按 Tab 键新建选中节点的下级节点
+            self.process_keyboard_new_child(ui);
+
+            // 按数字键修改选中节点的类型
+            self.process_keyboard_distinct_type(ui);
+
+            // 按 Alt + 数字键切换选中节点的附加类型
+            self.process_keyboard_addon_type(ui);
+
+            // 按 F 键循环切换选中节点的评审标记
+            self.process_keyboard_flag(ui);
+
+            // 按 Ctrl + 数字键跳转到快捷位绑定的节点
+            self.process_keyboard_hotkey(ui);
+
+            // 按 F2 或回车就地编辑选中节点的内容
+            self.process_keyboard_inline_edit(ui);
+
+            // 检测粘贴多行文本
+            self.process_paste(ui);
+
+            // 如果正在就地编辑节点内容，则在节点原位显示编辑框
+            self.show_inline_edit(ctx);
+
+            // 如果处于节点编辑状态，则弹出编辑窗口
+            self.show_node_edit_window(ctx);
+
+            // 如果处于边编辑状态，则弹出编辑窗口
+            self.show_edge_edit_window(ctx);
+
+            // 如果正在粘贴多行文本，则弹出确认窗口
+            self.show_paste_window(ctx);
+
+            // 如果正在调整子节点顺序，则弹出调整窗口
+            self.show_children_order_window(ctx);
+
+            // 如果正在规范化双向边，则弹出选择窗口
+            self.show_normalize_edges_window(ctx);
+
+            // 如果正在缩放坐标，则弹出输入系数的窗口
+            self.show_scale_coordinates_window(ctx);
+
+            // 如果正在编辑图谱信息，则弹出编辑窗口
+            self.show_metadata_window(ctx);
+
+            // 如果正在编辑分组框，则弹出编辑窗口
+            self.show_group_edit_window(ctx);
+
+            // 如果正在添加书签，则弹出命名窗口
+            self.show_bookmark_window(ctx);
+
+            // 如果打开了快捷键说明，则进行绘制
+            self.show_shortcuts_window(ctx);
+
+            // 如果打开了右键菜单，则进行绘制
+            self.show_context_menu(ctx);
+
+            // 为本帧新增的撤回记录保存修改前的选区和视口
+            self.observe_view_history();
+        });
+    }
+}
+
+impl GraphApp {
+    #[inline]
+    fn is_editing(&self) -> bool {
+        self.inline_editing.is_some()
+            || self.editing_node.is_some()
+            || self.pending_child.is_some()
+            || self.editing_edge.is_some()
+            || self.pasting_lines.is_some()
+            || self.editing_metadata.is_some()
+            || self.editing_group.is_some()
+            || self.naming_bookmark.is_some()
+            || self.removing_node.is_some()
+            || self.normalizing.is_some()
+            || self.previewing_xml.is_some()
+            || self.merging.is_some()
+            || self.printing.is_some()
+            || self.showing_settings
+            || self.context_menu.is_some()
+            || self.comparing.is_some()
+            || self.confirming_new_file.is_some()
+            || self.ordering_children.is_some()
+            || self.normalizing_edges.is_some()
+            || self.scaling_coordinates.is_some()
+    }
+
+    #[inline]
+    fn is_linking_edge(&self) -> bool {
+        self.edge_draft.is_active()
+    }
+
+    #[inline]
+    fn is_dragging(&self) -> bool {
+        self.dragging_node.is_some()
+            || self.dragging_group.is_some()
+            || self.dragging_waypoint.is_some()
+    }
+
+    #[inline]
+    fn node_screen_pos(&self, node: &EntityNode) -> Pos2 {
+        self.logical_to_screen(node.coor)
+    }
+
+    /// 节点绘制的位置，过渡动画进行中时为动画的当前位置。
+    /// 点击检测等交互应使用 node_screen_pos，以最终位置为准。
+    #[inline]
+    fn node_draw_pos(&self, node: &EntityNode) -> Pos2 {
+        let coor = self
+            .transition
+            .as_ref()
+            .map_or(node.coor, |t| t.position(node.id, node.coor));
+        self.logical_to_screen(coor)
+    }
+
+    #[inline]
+    fn logical_to_screen(&self, coor: (f64, f64)) -> Pos2 {
+        let logical_pos = Pos2::new(coor.0 as f32, coor.1 as f32);
+        (logical_pos * self.zoom_factor) - self.scroll_offset + Vec2::new(0.0, TOP_PANEL_HEIGHT)
+    }
+
+    /// 将屏幕位置换算为逻辑坐标，并对齐到逻辑网格
+    #[inline]
+    fn screen_to_logical(&self, screen_pos: Pos2) -> (f64, f64) {
+        let logical_pos =
+            (screen_pos - Vec2::new(0.0, TOP_PANEL_HEIGHT) + self.scroll_offset) / self.zoom_factor;
+        snap_to_grid((logical_pos.x as f64, logical_pos.y as f64))
+    }
+
+    fn draw_edges_and_nodes(&self, painter: &Painter) {
+        if let Some(graph) = self.graph.as_ref() {
+            // 从图谱中获取当前快照
+            let snapshot = graph.current_snapshot();
+
+            // 分屏对比时，底图和分组框只绘制在显示当前图谱的左侧
+            let side_by_side = self
+                .comparing
+                .as_ref()
+                .is_some_and(|c| c.mode == CompareMode::SideBySide);
+            let left = painter.with_clip_rect(split_rects(painter.clip_rect()).0);
+            let background = if side_by_side { &left } else { painter };
+
+            // 底图绘制在最底层
+            self.draw_underlay(background);
+
+            // 分组框绘制在边和节点的后面
+            self.draw_groups(background, snapshot);
+
+            // 对比时按显示方式绘制两个版本
+            if let Some(comparison) = self.comparing.as_ref() {
+                match comparison.mode {
+                    CompareMode::Diff => self.draw_comparison(painter, snapshot, comparison),
+                    CompareMode::SideBySide => {
+                        self.draw_side_by_side(painter, snapshot, comparison)
+                    }
+                    CompareMode::Overlay => self.draw_overlay(painter, snapshot, comparison),
+                }
+                return;
+            }
+
+            // 被筛除的节点及其关联的边淡化显示，关闭图例时不筛选
+            let filtering = self.showing_legend && self.filter.is_active();
+            // 聚焦时只正常显示聚焦的节点、其一跳邻居以及它们之间的边
+            let focused = self
+                .focused_node
+                .filter(|id| snapshot.nodes.contains_key(id))
+                .map(|id| {
+                    let mut ids = snapshot.neighbors(id);
+                    ids.insert(id);
+                    (id, ids)
+                });
+            let node_visible = |node: &EntityNode| {
+                (!filtering || self.filter.matches(node))
+                    && focused
+                        .as_ref()
+                        .is_none_or(|(_, ids)| ids.contains(&node.id))
+            };
+            let mut dimmed = painter.clone();
+            dimmed.multiply_opacity(0.15);
+            let painter_for = |visible: bool| if visible { painter } else { &dimmed };
+            // 折叠的节点的下级节点及其关联的边不绘制
+            let folded = self.folding.hidden(snapshot);
+
+            // 节点有多个父节点时，主包含边加粗，次要包含边以细虚线绘制
+            let secondary = snapshot.secondary_contains();
+            let multi_parent = secondary.iter().map(|(_, to)| *to).collect::<HashSet<_>>();
+            // 简化渲染时不绘制边的标签
+            let edge_labels = self.settings.detail(self.zoom_factor) == Detail::Full;
+
+            // 先收集要绘制的边及其在屏幕上的折线，按 ID 排列使错开的结果不随帧变化
+            let mut edges = snapshot
+                .edges
+                .iter()
+                .filter(|((from, to), _)| !folded.contains(from) && !folded.contains(to))
+                .filter_map(|((from, to), relation)| {
+                    let (from_node, to_node) = (snapshot.nodes.get(from)?, snapshot.nodes.get(to)?);
+                    let visible = node_visible(from_node)
+                        && node_visible(to_node)
+                        && focused
+                            .as_ref()
+                            .is_none_or(|(id, _)| from == id || to == id);
+                    let path = self.edge_path(from_node, to_node, snapshot.waypoints(*from, *to));
+                    Some(((*from, *to), *relation, visible, path))
+                })
+                .collect::<Vec<_>>();
+            edges.sort_unstable_by_key(|(edge, ..)| *edge);
+
+            // 多条边交汇处的关系符号和边标签沿各自的边错开
+            let markers = edges
+                .iter()
+                .map(|(_, relation, _, path)| marker_anchor(path, relation.marker()))
+                .collect::<Vec<_>>();
+            let labels = edges
+                .iter()
+                .filter(|(edge, ..)| edge_labels && snapshot.edge_labels.contains_key(edge))
+                .map(|(.., path)| marker_anchor(path, MarkerStyle::Plain))
+                .collect::<Vec<_>>();
+            let (marker_shifts, label_shifts) = if self.settings.stagger_markers {
+                (
+                    stagger(&markers, MARKER_GAP),
+                    stagger(&labels, EDGE_LABEL_GAP * self.zoom_factor),
+                )
+            } else {
+                (vec![0.0; markers.len()], vec![0.0; labels.len()])
+            };
+            let mut labels = labels.into_iter().zip(label_shifts);
+
+            for ((edge, relation, visible, path), shift) in edges.iter().zip(marker_shifts) {
+                let painter = painter_for(*visible);
+                if secondary.contains(edge) {
+                    self.draw_secondary_edge(painter, path, relation.edge_color(), shift);
+                } else {
+                    let stroke_size = if multi_parent.contains(&edge.1) {
+                        3.5
+                    } else {
+                        2.0
+                    };
+                    self.draw_edge_path(
+                        painter,
+                        path,
+                        *relation,
+                        Stroke::new(stroke_size, relation.edge_color()),
+                        shift,
+                    );
+                }
+                if *relation == Relation::Order && self.settings.flow_animation {
+                    self.draw_flow_dots(painter, path);
+                }
+                if let Some(label) = snapshot.edge_labels.get(edge)
+                    && edge_labels
+                    && let Some((anchor, shift)) = labels.next()
+                {
+                    self.draw_edge_label(painter, anchor.pos + anchor.dir * shift, label);
+                }
+            }
+
+            // 绘制节点，折叠的节点下方标出子节点数，绑定了快捷位的节点右下角标出快捷位
+            let hotkeys = self
+                .view_sidecar
+                .hotkeys
+                .iter()
+                .map(|(slot, id)| (*id, *slot))
+                .collect::<HashMap<_, _>>();
+            for (_, node) in snapshot.nodes.iter() {
+                if folded.contains(&node.id) {
+                    continue;
+                }
+                let painter = painter_for(node_visible(node));
+                self.draw_node(painter, node, 2.0);
+                if let Some(slot) = hotkeys.get(&node.id) {
+                    self.draw_hotkey_badge(painter, node, *slot);
+                }
+                if self.folding.is_collapsed(node.id) {
+                    let children = snapshot.children(node.id).len();
+                    if children > 0 {
+                        self.draw_fold_badge(painter, node, children);
+                    }
+                }
+            }
+        }
+    }
+
+    /// 绘制当前图谱与对比版本的叠加视图。
+    /// 删除的节点和边取自对比版本并半透明绘制，新增、删除和修改的节点和边分别以绿色、红色和橙色标出。
+    fn draw_comparison(&self, painter: &Painter, snapshot: &Snapshot, comparison: &Comparison) {
+        let base = &comparison.base;
+        let diff = &comparison.diff;
+        let find_node = |id: &u64| snapshot.nodes.get(id).or_else(|| base.nodes.get(id));
+        let removed_nodes = diff
+            .nodes
+            .iter()
+            .filter(|(_, change)| **change == Change::Removed)
+            .filter_map(|(id, _)| base.nodes.get(id));
+        let removed_edges = diff
+            .edges
+            .iter()
+            .filter(|(_, change)| **change == Change::Removed)
+            .filter_map(|(key, _)| base.edges.get_key_value(key));
+        let mut ghost = painter.clone();
+        ghost.multiply_opacity(0.4);
+
+        // 先绘制边
+        let edges = snapshot
+            .edges
+            .iter()
+            .map(|edge| (edge, painter))
+            .chain(removed_edges.map(|edge| (edge, &ghost)));
+        for (((from, to), relation), painter) in edges {
+            if let (Some(from_node), Some(to_node)) = (find_node(from), find_node(to)) {
+                let color = diff
+                    .edges
+                    .get(&(*from, *to))
+                    .map_or(Color32::BLACK, |change| change_color(*change));
+                let waypoints = snapshot
+                    .edge_waypoints
+                    .get(&(*from, *to))
+                    .or_else(|| base.edge_waypoints.get(&(*from, *to)))
+                    .map_or(&[][..], Vec::as_slice);
+                self.draw_edge(
+                    painter,
+                    from_node,
+                    to_node,
+                    waypoints,
+                    *relation,
+                    Stroke::new(2.0, color),
+                );
+            }
+        }
+
+        // 绘制节点，发生变化的节点加上对应颜色的边框
+        let nodes = snapshot
+            .nodes
+            .values()
+            .map(|node| (node, painter))
+            .chain(removed_nodes.map(|node| (node, &ghost)));
+        for (node, node_painter) in nodes {
+            self.draw_node(node_painter, node, 2.0);
+            if let Some(change) = diff.nodes.get(&node.id) {
+                let size =
+                    Vec2::new(NODE_SIZE.x, NODE_SIZE.y) * self.zoom_factor + Vec2::splat(3.0);
+                let rect = Rect::from_center_size(self.node_draw_pos(node), size);
+                painter.rect_stroke(
+                    rect,
+                    10.0,
+                    Stroke::new(6.0, change_color(*change)),
+                    egui::StrokeKind::Outside,
+                );
+            }
+        }
+    }
+
+    /// 分屏绘制两个版本，左侧为当前图谱，右侧为对比版本。
+    /// 右侧的视图在左侧的基础上右移半个画布，再加上单独平移的偏移
+    fn draw_side_by_side(&self, painter: &Painter, snapshot: &Snapshot, comparison: &Comparison) {
+        let (left, right) = split_rects(painter.clip_rect());
+        self.draw_plain(
+            &painter.with_clip_rect(left),
+            snapshot,
+            Vec2::ZERO,
+            Color32::BLACK,
+        );
+        let shift = Vec2::new(left.width(), 0.0) - comparison.pane_offset;
+        self.draw_plain(
+            &painter.with_clip_rect(right),
+            &comparison.base,
+            shift,
+            Color32::BLACK,
+        );
+
+        painter.vline(
+            right.min.x,
+            right.y_range(),
+            Stroke::new(1.0, Color32::GRAY),
+        );
+        let font = FontId::new(14.0, FontFamily::Proportional);
+        for (rect, name) in [(left, "当前"), (right, comparison.label.as_str())] {
+            painter.text(
+                rect.left_top() + Vec2::new(8.0, 8.0),
+                Align2::LEFT_TOP,
+                name,
+                font.clone(),
+                Color32::GRAY,
+            );
+        }
+    }
+
+    /// 以不同色调叠加绘制两个版本：对比版本半透明并以蓝色标出，当前图谱略微透明地绘制在其上
+    fn draw_overlay(&self, painter: &Painter, snapshot: &Snapshot, comparison: &Comparison) {
+        let mut ghost = painter.clone();
+        ghost.multiply_opacity(0.4);
+        self.draw_plain(&ghost, &comparison.base, Vec2::ZERO, OVERLAY_BASE_COLOR);
+        let mut current = painter.clone();
+        current.multiply_opacity(0.8);
+        self.draw_plain(&current, snapshot, Vec2::ZERO, Color32::BLACK);
+
+        // 对比版本的节点边框绘制在最上层，避免被位置相同的节点遮住
+        let size = Vec2::new(NODE_SIZE.x, NODE_SIZE.y) * self.zoom_factor + Vec2::splat(3.0);
+        for node in comparison.base.nodes.values() {
+            let rect = Rect::from_center_size(self.node_draw_pos(node), size);
+            painter.rect_stroke(
+                rect,
+                10.0,
+                Stroke::new(2.0, OVERLAY_BASE_COLOR),
+                egui::StrokeKind::Outside,
+            );
+        }
+    }
+
+    /// 将快照中的边和节点在屏幕上平移 shift 后绘制，不显示筛选、聚焦等状态
+    fn draw_plain(&self, painter: &Painter, snapshot: &Snapshot, shift: Vec2, edge_color: Color32) {
+        let shift = shift / self.zoom_factor;
+        let nodes = snapshot
+            .nodes
+            .values()
+            .map(|node| {
+                let mut node = node.clone();
+                node.coor = (node.coor.0 + shift.x as f64, node.coor.1 + shift.y as f64);
+                (node.id, node)
+            })
+            .collect::<HashMap<_, _>>();
+
+        for ((from, to), relation) in snapshot.edges.iter() {
+            if let (Some(from_node), Some(to_node)) = (nodes.get(from), nodes.get(to)) {
+                let waypoints = snapshot
+                    .waypoints(*from, *to)
+                    .iter()
+                    .map(|(x, y)| (x + shift.x as f64, y + shift.y as f64))
+                    .collect::<Vec<_>>();
+                let path = self.edge_path(from_node, to_node, &waypoints);
+                self.draw_edge_path(painter, &path, *relation, Stroke::new(2.0, edge_color), 0.0);
+                if let Some(label) = snapshot.edge_labels.get(&(*from, *to)) {
+                    let center = marker_anchor(&path, MarkerStyle::Plain).pos;
+                    self.draw_edge_label(painter, center, label);
+                }
+            }
+        }
+        for node in nodes.values() {
+            self.draw_node(painter, node, 2.0);
+        }
+    }
+
+    /// 按折线绘制边，waypoints 为边的折点的逻辑坐标，stroke 的宽度随缩放比例放大
+    fn draw_edge(
+        &self,
+        painter: &Painter,
+        from: &EntityNode,
+        to: &EntityNode,
+        waypoints: &[(f64, f64)],
+        relation: Relation,
+        stroke: Stroke,
+    ) {
+        let path = self.edge_path(from, to, waypoints);
+        self.draw_edge_path(painter, &path, relation, stroke, 0.0);
+    }
+
+    /// 按屏幕上的折线绘制边，关系符号沿边错开 shift，见 shifted_marker_segment
+    fn draw_edge_path(
+        &self,
+        painter: &Painter,
+        path: &[Pos2],
+        relation: Relation,
+        stroke: Stroke,
+        shift: f32,
+    ) {
+        let stroke = Stroke::new(stroke.width * self.zoom_factor, stroke.color);
+        painter.add(egui::Shape::line(path.to_vec(), stroke));
+        let marker = relation.marker();
+        let (start, end) = shifted_marker_segment(path, marker, shift);
+        draw_relation_marker(painter, start, end, marker, stroke);
+    }
+
+    /// 以细虚线绘制次要包含边，即起点不是终点的主父节点的包含边，半圆沿边错开 shift
+    fn draw_secondary_edge(&self, painter: &Painter, path: &[Pos2], color: Color32, shift: f32) {
+        let stroke = Stroke::new(1.0 * self.zoom_factor, color);
+        painter.extend(egui::Shape::dashed_line(
+            path,
+            stroke,
+            6.0 * self.zoom_factor,
+            4.0 * self.zoom_factor,
+        ));
+        let marker = Relation::Contain.marker();
+        let (start, end) = shifted_marker_segment(path, marker, shift);
+        draw_relation_marker(painter, start, end, marker, stroke);
+    }
+
+    /// 以 center 为中心绘制边的自定义标签，带有底色以免与连线重叠难以辨认
+    fn draw_edge_label(&self, painter: &Painter, center: Pos2, label: &str) {
+        let galley = painter.layout_no_wrap(
+            label.to_string(),
+            FontId::new(10.0 * self.zoom_factor, FontFamily::Proportional),
+            Color32::BLACK,
+        );
+        let padding = Vec2::new(2.0, 2.0);
+        let bg_rect = Rect::from_center_size(center, galley.size() + 2.0 * padding);
+        painter.rect_filled(bg_rect, 3.0, Color32::from_white_alpha(220));
+        painter.galley(bg_rect.min + padding, galley, Color32::PLACEHOLDER);
+    }
+
+    /// 边在屏幕上的起点和终点，为两节点中心连线与各自边框的交点，
+    /// 使边从节点边缘出发、到达节点边缘。节点互相重叠时退回为两节点的中心。
+    fn edge_endpoints(&self, from: &EntityNode, to: &EntityNode) -> (Pos2, Pos2) {
+        let from_pos = self.node_draw_pos(from);
+        let to_pos = self.node_draw_pos(to);
+        // 边框向外描边，端点再留出少许间隙
+        let half_size = NODE_SIZE * self.zoom_factor / 2.0 + Vec2::splat(3.0);
+        let start = rect_boundary_point(from_pos, to_pos, half_size);
+        let end = rect_boundary_point(to_pos, from_pos, half_size);
+        if (end - start).dot(to_pos - from_pos) <= 0.0 {
+            (from_pos, to_pos)
+        } else {
+            (start, end)
+        }
+    }
+
+    /// 边在屏幕上经过的点：起点、各折点和终点。没有折点时同 edge_endpoints；
+    /// 有折点时两端为节点中心指向相邻折点的连线与边框的交点
+    fn edge_path(&self, from: &EntityNode, to: &EntityNode, waypoints: &[(f64, f64)]) -> Vec<Pos2> {
+        let (Some(first), Some(last)) = (waypoints.first(), waypoints.last()) else {
+            let (start, end) = self.edge_endpoints(from, to);
+            return vec![start, end];
+        };
+        let half_size = NODE_SIZE * self.zoom_factor / 2.0 + Vec2::splat(3.0);
+        let from_pos = self.node_draw_pos(from);
+        let to_pos = self.node_draw_pos(to);
+        let mut path = vec![rect_boundary_point(
+            from_pos,
+            self.logical_to_screen(*first),
+            half_size,
+        )];
+        path.extend(waypoints.iter().map(|coor| self.logical_to_screen(*coor)));
+        path.push(rect_boundary_point(
+            to_pos,
+            self.logical_to_screen(*last),
+            half_size,
+        ));
+        path
+    }
+
+    /// 点到边在屏幕上的距离，以节点中心和折点连成的折线计算，供点击和悬停检测
+    fn distance_to_edge(
+        &self,
+        pos: Pos2,
+        from: &EntityNode,
+        to: &EntityNode,
+        waypoints: &[(f64, f64)],
+    ) -> f32 {
+        let points = std::iter::once(self.node_screen_pos(from))
+            .chain(waypoints.iter().map(|coor| self.logical_to_screen(*coor)))
+            .chain(std::iter::once(self.node_screen_pos(to)))
+            .collect::<Vec<_>>();
+        points
+            .windows(2)
+            .map(|segment| distance_point_to_segment(pos, segment[0], segment[1]))
+            .fold(f32::INFINITY, f32::min)
+    }
+
+    /// pos 处的边，折叠隐藏的边除外
+    fn edge_at(&self, pos: Pos2) -> Option<(u64, u64)> {
+        let snapshot = self.graph.as_ref()?.current_snapshot();
+        let folded = self.folding.hidden(snapshot);
+        snapshot.edges.keys().copied().find(|(from, to)| {
+            if folded.contains(from) || folded.contains(to) {
+                return false;
+            }
+            let (Some(from_node), Some(to_node)) =
+                (snapshot.nodes.get(from), snapshot.nodes.get(to))
+            else {
+                return false;
+            };
+            self.distance_to_edge(pos, from_node, to_node, snapshot.waypoints(*from, *to)) < 5.0
+        })
+    }
+
+    /// 沿次序边在屏幕上的折线从前驱到后继绘制移动的小点，只在两节点边缘之间绘制
+    fn draw_flow_dots(&self, painter: &Painter, path: &[Pos2]) {
+        let spacing = FLOW_DOT_SPACING * self.zoom_factor;
+        let mut distance = (self.flow_phase * self.zoom_factor) % spacing;
+        for segment in path.windows(2) {
+            let (start, end) = (segment[0], segment[1]);
+            let length = start.distance(end);
+            if length < f32::EPSILON {
+                continue;
+            }
+
+            let dir = (end - start) / length;
+            while distance < length {
+                painter.circle_filled(
+                    start + dir * distance,
+                    2.5 * self.zoom_factor,
+                    Color32::from_rgb(54, 131, 248),
+                );
+                distance += spacing;
+            }
+            distance -= length;
+        }
+    }
+
+    fn draw_node(&self, painter: &Painter, node: &EntityNode, stroke_size: f32) {
+        let pos = self.node_draw_pos(node);
+        let size = Vec2::new(NODE_SIZE.x, NODE_SIZE.y) * self.zoom_factor;
+        let rect = Rect::from_center_size(pos, size);
+        let corner_radius = NODE_CORNER_RADIUS;
+
+        // 绘制填充矩形
+        painter.rect_filled(
+            rect,
+            corner_radius,
+            self.theme.rect_color(node.distinct_type),
+        );
+        self.theme
+            .draw_type_mark(painter, rect, node.distinct_type, self.zoom_factor);
+
+        // 绘制边框，仅作参考的节点为灰色虚线框
+        if node.reference {
+            let rect = rect.expand(stroke_size / 2.0);
+            let corners = [
+                rect.left_top(),
+                rect.right_top(),
+                rect.right_bottom(),
+                rect.left_bottom(),
+                rect.left_top(),
+            ];
+            painter.extend(egui::Shape::dashed_line(
+                &corners,
+                Stroke::new(stroke_size, Color32::GRAY),
+                6.0 * self.zoom_factor,
+                4.0 * self.zoom_factor,
+            ));
+        } else {
+            painter.rect_stroke(
+                rect,
+                corner_radius,
+                Stroke::new(stroke_size, Color32::from_rgb(54, 131, 248)),
+                egui::StrokeKind::Outside,
+            );
+        }
+
+        // 缩放较小时简化渲染，不绘制文字，更小时连标签框也不绘制
+        let detail = self.settings.detail(self.zoom_factor);
+        if detail == Detail::Blocks {
+            return;
+        }
+        let text = detail == Detail::Full;
+        let label_size = LOD_LABEL_SIZE * self.zoom_factor;
+
+        // 绘制节点类型
+        let type_galley = text.then(|| {
+            painter.layout(
+                node.distinct_type.class_name_abbr().to_string(),
+                FontId::new(10.0 * self.zoom_factor, FontFamily::Proportional),
+                Color32::from_rgb(189, 53, 61),
+                rect.width() / 2.0,
+            )
+        });
+        let padding = Vec2::new(2.0, 2.0);
+        let text_size = type_galley
+            .as_ref()
+            .map_or(label_size, |galley| galley.size());
+        let bg_size = text_size + 2.0 * padding;
+        let gap = Vec2::new(4.0, 4.0);
+        let bg_min = rect.min + gap;
+        let bg_rect = Rect::from_min_size(bg_min, bg_size);
+        painter.rect_filled(bg_rect, 3.0, Color32::from_rgb(255, 192, 122));
+        if let Some(type_galley) = type_galley {
+            painter.galley(bg_rect.min + padding, type_galley, Color32::PLACEHOLDER);
+        }
+
+        // 有关联链接的节点在左下角绘制链接标记，单击打开第一个链接
+        if text && !node.links.is_empty() {
+            painter.text(
+                self.link_mark_rect(node).center(),
+                Align2::CENTER_CENTER,
+                "🔗",
+                FontId::new(12.0 * self.zoom_factor, FontFamily::Proportional),
+                Color32::from_rgb(54, 131, 248),
+            );
+        }
+
+        // 有评审标记的节点在右上角绘制标记徽章
+        if let Some(flag) = node.flag {
+            let center = rect.right_top();
+            painter.circle(
+                center,
+                8.0 * self.zoom_factor,
+                flag.color(),
+                Stroke::new(1.0, Color32::WHITE),
+            );
+            if text {
+                painter.text(
+                    center,
+                    Align2::CENTER_CENTER,
+                    flag.symbol(),
+                    FontId::new(11.0 * self.zoom_factor, FontFamily::Proportional),
+                    Color32::WHITE,
+                );
+            }
+        }
+
+        // 绘制节点附加类型
+        let mut addon_types = node
+            .addon_types
+            .iter()
+            .map(|t| t.name())
+            .collect::<Vec<_>>();
+        if !addon_types.is_empty() {
+            addon_types.sort();
+            // 鼠标悬停时展开全部附加类型
+            let expanded = self.hovered_node.is_some_and(|(id, _)| id == node.id);
+            let addon_galley = text.then(|| {
+                painter.layout(
+                    addon_label(&addon_types, expanded, self.zoom_factor),
+                    FontId::new(8.0 * self.zoom_factor, FontFamily::Proportional),
+                    Color32::from_rgb(189, 53, 61),
+                    rect.width() / 2.0,
+                )
+            });
+            let padding = Vec2::new(2.0, 2.0);
+            let text_size = addon_galley
+                .as_ref()
+                .map_or(label_size, |galley| galley.size());
+            let bg_size = text_size + 2.0 * padding;
+            let gap = Vec2::new(4.0, 4.0);
+            let bg_min = rect.max - bg_size - gap;
+            let bg_rect = Rect::from_min_size(bg_min, bg_size);
+            painter.rect_filled(bg_rect, 3.0, Color32::from_rgb(255, 192, 122));
+            if let Some(addon_galley) = addon_galley {
+                painter.galley(bg_rect.min + padding, addon_galley, Color32::PLACEHOLDER);
+            }
+        }
+
+        // 绘制节点内容，其中的网址以带下划线的链接色绘制
+        if !text {
+            return;
+        }
+        let (job, _) = self.content_layout(node);
+        let galley = painter.layout_job(job);
+        let text_pos = Pos2::new(pos.x - galley.size().x / 2.0, pos.y - galley.size().y / 2.0);
+        painter.galley(text_pos, galley, Color32::PLACEHOLDER);
+    }
+
+    /// 节点内容的排版，使用默认字体。内容过长时只排版开头部分，避免每帧排版大段文字。
+    /// 同时返回其中各网址的字符范围和完整网址，被截断的网址也打开完整的地址
+    fn content_layout(&self, node: &EntityNode) -> (LayoutJob, Vec<(Range<usize>, String)>) {
+        let content = match node.content.char_indices().nth(DRAW_CONTENT_LIMIT) {
+            Some((index, _)) => format!("{}…", &node.content[..index]),
+            None => node.content.clone(),
+        };
+        // 按填充色的亮度选择黑色或白色的文字，保证深色填充上也清晰可读
+        let fill = self.theme.rect_color(node.distinct_type);
+        let text_format = TextFormat::simple(
+            FontId::new(12.0 * self.zoom_factor, FontFamily::Proportional),
+            theme::text_color(fill),
+        );
+        let link_color = theme::link_color(fill);
+        let link_format = TextFormat {
+            color: link_color,
+            underline: Stroke::new(1.0, link_color),
+            ..text_format.clone()
+        };
+
+        let mut job = LayoutJob::default();
+        job.wrap.max_width = NODE_SIZE.x * self.zoom_factor - 2.0 * NODE_CORNER_RADIUS;
+        let mut links = Vec::new();
+        let mut end = 0;
+        for url in link::find_urls(&node.content) {
+            if url.start >= content.len() {
+                break;
+            }
+            let shown = url.start..url.end.min(content.len());
+            job.append(&content[end..shown.start], 0.0, text_format.clone());
+            job.append(&content[shown.clone()], 0.0, link_format.clone());
+            let start = content[..shown.start].chars().count();
+            let chars = start..start + content[shown.clone()].chars().count();
+            links.push((chars, node.content[url].to_string()));
+            end = shown.end;
+        }
+        job.append(&content[end..], 0.0, text_format);
+        (job, links)
+    }
+
+    /// 在折叠的节点下边缘绘制折叠的子节点数
+    fn draw_fold_badge(&self, painter: &Painter, node: &EntityNode, children: usize) {
+        let (badge, galley) = self.fold_badge(painter, node, children);
+        let padding = (badge.size() - galley.size()) / 2.0;
+        painter.rect_filled(badge, badge.height() / 2.0, Color32::from_rgb(54, 131, 248));
+        painter.galley(badge.min + padding, galley, Color32::PLACEHOLDER);
+    }
+
+    /// 折叠标记的屏幕区域及其中的文字
+    fn fold_badge(
+        &self,
+        painter: &Painter,
+        node: &EntityNode,
+        children: usize,
+    ) -> (Rect, Arc<egui::Galley>) {
+        let size = Vec2::new(NODE_SIZE.x, NODE_SIZE.y) * self.zoom_factor;
+        let rect = Rect::from_center_size(self.node_draw_pos(node), size);
+        let galley = painter.layout_no_wrap(
+            format!("+{children}"),
+            FontId::new(10.0 * self.zoom_factor, FontFamily::Proportional),
+            Color32::WHITE,
+        );
+        let padding = Vec2::new(4.0, 1.0) * self.zoom_factor;
+        let badge = Rect::from_center_size(rect.center_bottom(), galley.size() + 2.0 * padding);
+        (badge, galley)
+    }
+
+    /// 鼠标悬停在折叠标记上时，在旁边的浮窗中以缩进列表预览隐藏的下级节点。
+    /// 浮窗不响应鼠标，移出标记即关闭，也不影响画布上的点击
+    fn show_fold_preview(&self, ui: &egui::Ui) {
+        let Some(graph) = self.graph.as_ref() else {
+            return;
+        };
+        let Some(pointer) = ui.ctx().pointer_hover_pos() else {
+            return;
+        };
+        if self.is_dragging() || self.is_editing() || self.is_linking_edge() {
+            return;
+        }
+        let snapshot = graph.current_snapshot();
+        let folded = self.folding.hidden(snapshot);
+        let hovered = snapshot.nodes.values().find_map(|node| {
+            if folded.contains(&node.id) || !self.folding.is_collapsed(node.id) {
+                return None;
+            }
+            let children = snapshot.children(node.id).len();
+            let (badge, _) = self.fold_badge(ui.painter(), node, children);
+            (children > 0 && badge.contains(pointer)).then_some((node.id, badge))
+        });
+        if let Some((id, badge)) = hovered {
+            let lines = subtree_preview(snapshot, id, PREVIEW_LIMIT);
+            egui::containers::show_tooltip_at(
+                ui.ctx(),
+                ui.layer_id(),
+                egui::Id::new("折叠预览"),
+                badge.right_bottom(),
+                |ui| {
+                    for line in lines {
+                        ui.label(line);
+                    }
+                },
+            );
+        }
+    }
+
+    /// 在节点右下角绘制绑定的快捷位
+    fn draw_hotkey_badge(&self, painter: &Painter, node: &EntityNode, slot: u8) {
+        let size = Vec2::new(NODE_SIZE.x, NODE_SIZE.y) * self.zoom_factor;
+        let rect = Rect::from_center_size(self.node_draw_pos(node), size);
+        let radius = 8.0 * self.zoom_factor;
+        let center = rect.right_bottom() - Vec2::splat(radius + 4.0 * self.zoom_factor);
+        painter.circle_filled(center, radius, Color32::from_rgb(54, 131, 248));
+        painter.text(
+            center,
+            Align2::CENTER_CENTER,
+            slot.to_string(),
+            FontId::new(11.0 * self.zoom_factor, FontFamily::Proportional),
+            Color32::WHITE,
+        );
+    }
+
+    /// 节点左下角链接标记的屏幕区域
+    fn link_mark_rect(&self, node: &EntityNode) -> Rect {
+        let size = Vec2::new(NODE_SIZE.x, NODE_SIZE.y) * self.zoom_factor;
+        let rect = Rect::from_center_size(self.node_draw_pos(node), size);
+        let mark = Vec2::splat(16.0 * self.zoom_factor);
+        let gap = 4.0 * self.zoom_factor;
+        Rect::from_min_size(rect.left_bottom() + Vec2::new(gap, -gap - mark.y), mark)
+    }
+
+    /// 查找屏幕位置上的链接标记所属节点的第一个链接
+    fn link_mark_at(&self, pos: Pos2) -> Option<String> {
+        let snapshot = self.graph.as_ref()?.current_snapshot();
+        let node = snapshot.nodes.get(&self.node_at(pos)?)?;
+        let link = node.links.first()?;
+        self.link_mark_rect(node)
+            .contains(pos)
+            .then(|| link.clone())
+    }
+
+    /// 用系统默认的程序打开节点的链接（网址或本地文件），失败时显示警告
+    fn open_link(&mut self, link: &str) {
+        if let Err(e) = open::that_detached(link.trim()) {
+            self.notifications
+                .warning(format!("无法打开链接 {link}：{e}"));
+        }
+    }
+
+    /// 查找屏幕位置上的节点内容中的网址
+    fn link_at(&self, ui: &egui::Ui, pos: Pos2) -> Option<String> {
+        let snapshot = self.graph.as_ref()?.current_snapshot();
+        let node = snapshot.nodes.get(&self.node_at(pos)?)?;
+        let (job, links) = self.content_layout(node);
+        if links.is_empty() {
+            return None;
+        }
+        let galley = ui.fonts(|fonts| fonts.layout_job(job));
+        // 与 draw_node 一致，内容在节点中居中
+        let offset = pos - (self.node_screen_pos(node) - galley.size() / 2.0);
+        links.into_iter().find_map(|(chars, url)| {
+            chars
+                .into_iter()
+                .any(|index| {
+                    let left = galley.pos_from_ccursor(CCursor::new(index));
+                    let right = galley.pos_from_ccursor(CCursor::new(index + 1));
+                    // 换行处的字符没有可靠的右边界，忽略
+                    left.min.y == right.min.y
+                        && Rect::from_min_max(left.min, Pos2::new(right.min.x, left.max.y))
+                            .contains(offset.to_pos2())
+                })
+                .then_some(url)
+        })
+    }
+
+    /// 保存编辑窗口：待新建下级节点时创建节点和边，否则更新正在编辑的节点
+    fn commit_node_editor(&mut self) -> Result<(), GraphError> {
+        match (self.pending_child, self.editing_node) {
+            (Some(pending), _) => self.commit_pending_child(pending),
+            (None, Some(edit_id)) => self.commit_edit(edit_id),
+            (None, None) => Ok(()),
+        }
+    }
+
+    /// 拖出边在空白处松开或按 Tab 键时，打开编辑窗口准备在 coor 处新建低一级的节点，
+    /// 以 relation 连到新节点。节点和边在保存时作为一次可撤回的操作创建
+    fn begin_pending_child(&mut self, from: u64, coor: (f64, f64), relation: Relation) {
+        let Some(node) = self
+            .graph
+            .as_ref()
+            .and_then(|graph| graph.current_snapshot().nodes.get(&from))
+        else {
+            return;
+        };
+        self.editing_distinct_type = node.distinct_type.child_type();
+        self.editing_content = String::new();
+        self.editing_notes = String::new();
+        self.editing_links.clear();
+        self.editing_addon_types.clear();
+        self.pending_child = Some(PendingChild {
+            from,
+            coor,
+            relation,
+        });
+    }
+
+    /// 将待新建的节点和连向它的边作为一次可撤回的操作创建，并选中新节点
+    fn commit_pending_child(&mut self, pending: PendingChild) -> Result<(), GraphError> {
+        let Some(graph) = self.graph.as_mut() else {
+            return Ok(());
+        };
+        let addon_types = self
+            .editing_addon_types
+            .iter()
+            .filter_map(|(t, selected)| selected.then_some(*t))
+            .collect::<Vec<_>>();
+        let id = graph.current_snapshot().next_id();
+        let mut commands = vec![Command::AddEntity {
+            content: self.editing_content.clone(),
+            distinct_type: self.editing_distinct_type,
+            addon_types: addon_types.clone(),
+            coor: pending.coor,
+        }];
+        if !self.editing_notes.is_empty() {
+            commands.push(Command::UpdateEntityNotes {
+                id,
+                notes: self.editing_notes.clone(),
+            });
+        }
+        let links = edited_links(&self.editing_links);
+        if !links.is_empty() {
+            let mut node = EntityNode::builder(id, self.editing_distinct_type)
+                .content(self.editing_content.clone())
+                .addon_types(&addon_types)
+                .coor(pending.coor)
+                .notes(self.editing_notes.clone())
+                .build();
+            node.links = links;
+            commands.push(Command::ReplaceEntity(node));
+        }
+        commands.push(Command::AddEdge {
+            from: pending.from,
+            to: id,
+            relation: pending.relation,
+        });
+        graph.apply_batch(commands)?;
+
+        // 记住选择的关系，下次拖出边新建节点时沿用
+        self.current_relation = pending.relation;
+        self.last_distinct_type = self.editing_distinct_type;
+        self.selected_nodes = BTreeSet::from([id]);
+        self.cancel_pending_child();
+        Ok(())
+    }
+
+    /// 关闭待新建节点的编辑窗口，同时结束绘制边
+    fn cancel_pending_child(&mut self) {
+        if self.pending_child.take().is_some() {
+            self.edge_draft.cancel();
+        }
+    }
+
+    fn commit_edit(&mut self, edit_id: u64) -> Result<(), GraphError> {
+        if let Some(graph) = self.graph.as_mut() {
+            let addon_types = self
+                .editing_addon_types
+                .iter()
+                .filter_map(|(t, selected)| if *selected { Some(*t) } else { None })
+                .collect::<Vec<_>>();
+            // 这里假设 snapshot 内部数据已不会导致借用冲突（可重新获取相关数据）
+            graph.update_entity_with(edit_id, |node| {
+                node.update(
+                    self.editing_content.clone(),
+                    self.editing_distinct_type,
+                    &addon_types,
+                    node.coor,
+                );
+                node.notes = self.editing_notes.clone();
+                node.reference = self.editing_reference;
+                node.links = edited_links(&self.editing_links);
+            })?;
+            self.last_distinct_type = self.editing_distinct_type;
+            self.editing_node = None;
+        }
+        Ok(())
+    }
+
+    fn show_welcome_page(&mut self, ui: &mut egui::Ui) {
+        if self.graph.is_some() {
+            return;
+        }
+        if self.settings.recent_files.is_empty() {
+            ui.with_layout(
+                egui::Layout::centered_and_justified(egui::Direction::TopDown),
+                |ui| {
+                    ui.label(
+                        egui::RichText::new("请点击上方按钮新建或添加文件")
+                            .color(egui::Color32::GRAY)
+                            .size(20.0),
+                    );
+                },
+            );
+            return;
+        }
+
+        // 只读取标题和节点数，读取失败时只显示文件名
+        for path in self.settings.recent_files.iter() {
+            self.recent_titles
+                .entry(path.clone())
+                .or_insert_with(|| graph::peek_title(path).ok());
+        }
+
+        let mut opened = None;
+        ui.vertical_centered(|ui| {
+            ui.add_space(40.0);
+            ui.label(
+                egui::RichText::new("请点击上方按钮新建或添加文件，或打开最近的文件")
+                    .color(egui::Color32::GRAY)
+                    .size(20.0),
+            );
+            ui.add_space(20.0);
+            for path in self.settings.recent_files.iter() {
+                let name = path.file_name().map_or_else(
+                    || path.display().to_string(),
+                    |name| name.to_string_lossy().into_owned(),
+                );
+                let text = match self.recent_titles.get(path) {
+                    Some(Some((title, count))) if !title.is_empty() && title != DEFAULT_TITLE => {
+                        format!("{name}    {title} · {count} 个节点")
+                    }
+                    Some(Some((_, count))) => format!("{name}    {count} 个节点"),
+                    _ => name,
+                };
+                if ui
+                    .button(text)
+                    .on_hover_text(path.display().to_string())
+                    .clicked()
+                {
+                    opened = Some(path.clone());
+                }
+            }
+        });
+        if let Some(path) = opened {
+            self.open_file(path);
+        }
+    }
+
+    fn show_relation_window(&mut self, ctx: &Context, edge_start_node: u64, edge_end_node: u64) {
+        if self.graph.is_none() {
+            return;
+        }
+
+        let mut choice = RelationChoice::Pending;
+        egui::Window::new("设置关系")
+            .collapsible(false)
+            .resizable(false)
+            .anchor(Align2::CENTER_CENTER, [0.0, 0.0])
+            .show(ctx, |ui| {
+                ui.label("选择关系类型:");
+                ui.vertical(|ui| {
+                    let relations = relation_registry().relations();
+                    for (index, relation) in relations.into_iter().enumerate() {
+                        // 只有前几种关系有对应的数字键
+                        let label = match RELATION_KEYS.get(index) {
+                            Some(key) => format!("{} ({})", relation.label(), key.name()),
+                            None => relation.label().into_owned(),
+                        };
+                        let response = ui.radio_value(&mut self.current_relation, relation, label);
+                        // 窗口打开时焦点在上次确认的关系上
+                        if relation == self.current_relation
+                            && ui.memory(|memory| memory.focused().is_none())
+                        {
+                            response.request_focus();
+                        }
+                    }
+                });
+
+                ui.horizontal(|ui| {
+                    if ui.button("确定").on_hover_text("Enter").clicked() {
+                        choice = RelationChoice::Confirmed(self.current_relation);
+                    }
+                    if ui.button("取消").on_hover_text("Esc").clicked() {
+                        choice = RelationChoice::Cancelled;
+                    }
+                });
+                if choice == RelationChoice::Pending {
+                    choice = ui.input(|i| {
+                        relation_choice(self.current_relation, |key| i.key_pressed(key))
+                    });
+                }
+            });
+
+        match choice {
+            RelationChoice::Pending => {}
+            RelationChoice::Cancelled => self.edge_draft.cancel(),
+            RelationChoice::Confirmed(relation) => {
+                // 确认的关系作为之后绘制边和新建下级节点的默认关系
+                self.current_relation = relation;
+                dialog_error!(
+                    self,
+                    self.graph
+                        .as_mut()
+                        .unwrap()
+                        .add_edge(edge_start_node, edge_end_node, relation),
+                    &[],
+                    "添加边失败"
+                );
+                self.edge_draft.cancel();
+            }
+        }
+    }
+
+    fn show_edge_edit_window(&mut self, ctx: &Context) {
+        if self.graph.is_some()
+            && let Some((from_id, to_id)) = self.editing_edge
+        {
+            egui::Window::new("编辑边关系")
+                .collapsible(false)
+                .resizable(false)
+                .anchor(Align2::CENTER_CENTER, [0.0, 0.0])
+                .show(ctx, |ui| {
+                    ui.label("修改边关系类型:");
+                    ui.vertical(|ui| relation_radios(ui, &mut self.editing_relation));
+
+                    ui.label("标签:");
+                    ui.add(
+                        egui::TextEdit::singleline(&mut self.editing_edge_label)
+                            .hint_text("留空则显示关系名"),
+                    );
+
+                    ui.horizontal(|ui| {
+                        if ui.button("保存").clicked() {
+                            // 关系和标签的修改作为一次操作
+                            let commands = [
+                                Command::UpdateEdge {
+                                    from: from_id,
+                                    to: to_id,
+                                    relation: self.editing_relation,
+                                },
+                                Command::SetEdgeLabel {
+                                    from: from_id,
+                                    to: to_id,
+                                    label: self.editing_edge_label.trim().to_string(),
+                                },
+                            ];
+                            dialog_error!(
+                                self,
+                                self.graph.as_mut().unwrap().apply_batch(commands),
+                                &[],
+                                "更新边失败"
+                            );
+                            self.editing_edge = None;
+                        }
+                        if ui.button("取消").clicked() {
+                            self.editing_edge = None;
+                        }
+                    });
+                });
+        }
+    }
+
+    /// 在节点原位显示贴合节点的编辑框，只修改内容。回车提交，Shift + 回车换行，Esc 取消，
+    /// 点击其他位置使编辑框失去焦点时也提交
+    fn show_inline_edit(&mut self, ctx: &Context) {
+        let Some(id) = self.inline_editing.as_ref().map(|(id, _)| *id) else {
+            return;
+        };
+        let Some(node) = self
+            .graph
+            .as_ref()
+            .and_then(|graph| graph.current_snapshot().nodes.get(&id))
+        else {
+            // 节点已被删除（如撤销了新建）
+            self.inline_editing = None;
+            return;
+        };
+        let size = Vec2::new(NODE_SIZE.x, NODE_SIZE.y) * self.zoom_factor;
+        let rect = Rect::from_center_size(self.node_screen_pos(node), size);
+        let Some((_, content)) = self.inline_editing.as_mut() else {
+            return;
+        };
+
+        let response = egui::Area::new(egui::Id::new("inline_edit"))
+            .fixed_pos(rect.min)
+            .order(egui::Order::Foreground)
+            .show(ctx, |ui| {
+                ui.set_max_size(rect.size());
+                let response = ui.add_sized(
+                    rect.size(),
+                    egui::TextEdit::multiline(content).return_key(Some(
+                        egui::KeyboardShortcut::new(egui::Modifiers::SHIFT, egui::Key::Enter),
+                    )),
+                );
+                // 打开时焦点在编辑框上
+                if ui.memory(|memory| memory.focused().is_none()) {
+                    response.request_focus();
+                }
+                response
+            })
+            .inner;
+
+        let (escape, enter) = ctx.input(|i| {
+            (
+                i.key_pressed(egui::Key::Escape),
+                i.key_pressed(egui::Key::Enter) && i.modifiers.is_none(),
+            )
+        });
+        if escape {
+            self.inline_editing = None;
+        } else if response.lost_focus() || (response.has_focus() && enter) {
+            let Some((id, content)) = self.inline_editing.take() else {
+                return;
+            };
+            let unchanged = self
+                .graph
+                .as_ref()
+                .and_then(|graph| graph.current_snapshot().nodes.get(&id))
+                .is_none_or(|node| node.content == content);
+            if !unchanged && let Some(graph) = self.graph.as_mut() {
+                let result = graph.update_entity_with(id, |node| node.content = content);
+                dialog_error!(self, result, &[], "修改节点内容失败");
+            }
+        }
+    }
+
+    fn show_node_edit_window(&mut self, ctx: &Context) {
+        if self.graph.is_some() && (self.editing_node.is_some() || self.pending_child.is_some()) {
+            let edit_id = self.editing_node.unwrap_or_default();
+            let title = if self.pending_child.is_some() {
+                "新建下级节点"
+            } else {
+                "编辑节点内容"
+            };
+            egui::Window::new(title)
+                .collapsible(false)
+                .resizable(false)
+                .anchor(Align2::CENTER_CENTER, [0.0, 0.0])
+                .show(ctx, |ui| {
+                    // 检查 Esc 键：退出编辑状态
+                    if ui.input(|i| i.key_pressed(egui::Key::Escape)) {
+                        self.editing_node = None;
+                        self.cancel_pending_child();
+                        return; // 退出窗口显示逻辑
+                    }
+                    // 检查 Ctrl + Enter 键：提交保存操作
+                    if ui.input(|i| i.key_pressed(egui::Key::Enter) && i.modifiers.command) {
+                        dialog_error!(self, self.commit_node_editor(), &[], "保存节点失败");
+                        return;
+                    }
+
+                    // 新建下级节点时选择与起点的关系
+                    if let Some(pending) = self.pending_child.as_mut() {
+                        ui.label(format!("与节点 {} 的关系:", pending.from));
+                        ui.horizontal(|ui| relation_radios(ui, &mut pending.relation));
+                        ui.separator();
+                    }
+
+                    // 从模板填充类型和附加类型
+                    if !self.settings.templates.is_empty() {
+                        let mut selected = None;
+                        egui::ComboBox::from_label("模板")
+                            .selected_text("选择模板填充类型")
+                            .show_ui(ui, |ui| {
+                                for template in self.settings.templates.iter() {
+                                    if ui.selectable_label(false, &template.name).clicked() {
+                                        selected = Some(template.clone());
+                                    }
+                                }
+                            });
+                        if let Some(template) = selected {
+                            self.apply_template(&template);
+                        }
+                        ui.separator();
+                    }
+
+                    ui.label("修改节点类型:");
+                    ui.horizontal(|ui| {
+                        distinct_type_radios(ui, &mut self.editing_distinct_type);
+                    });
+
+                    ui.separator();
+                    ui.label("修改节点附加类型:");
+                    ui.horizontal(|ui| {
+                        ui.checkbox(
+                            self.editing_addon_types
+                                .entry(AddonEntityType::Example)
+                                .or_default(),
+                            "示例",
+                        );
+                        ui.checkbox(
+                            self.editing_addon_types
+                                .entry(AddonEntityType::Question)
+                                .or_default(),
+                            "问题",
+                        );
+                        ui.checkbox(
+                            self.editing_addon_types
+                                .entry(AddonEntityType::Practice)
+                                .or_default(),
+                            "练习",
+                        );
+                        ui.checkbox(
+                            self.editing_addon_types
+                                .entry(AddonEntityType::Thinking)
+                                .or_default(),
+                            "思考",
+                        );
+                        ui.checkbox(
+                            self.editing_addon_types
+                                .entry(AddonEntityType::Knowledge)
+                                .or_default(),
+                            "知识",
+                        );
+                        ui.checkbox(
+                            self.editing_addon_types
+                                .entry(AddonEntityType::Political)
+                                .or_default(),
+                            "思政",
+                        );
+                    });
+                    // 对接调试时对照 XML 中的 attach 编码
+                    let addon_types = self
+                        .editing_addon_types
+                        .iter()
+                        .filter(|(_, checked)| **checked)
+                        .map(|(addon_type, _)| *addon_type)
+                        .collect();
+                    let order = graph::ATTACH_ORDER
+                        .iter()
+                        .map(AddonEntityType::name)
+                        .collect::<Vec<_>>()
+                        .join("、");
+                    ui.label(
+                        RichText::new(format!("attach 编码：{}", graph::addon_flags(&addon_types)))
+                            .monospace(),
+                    )
+                    .on_hover_text(format!("各位依次对应：{order}"));
+
+                    ui.separator();
+                    // 从撤回栈中找出该节点以前的内容，选择后填入编辑框，确认后作为一次普通的修改
+                    let versions = self.graph.as_ref().map_or_else(Vec::new, |graph| {
+                        graph.content_versions(edit_id, CONTENT_VERSIONS)
+                    });
+                    ui.horizontal(|ui| {
+                        ui.label("修改节点内容:");
+                        if !versions.is_empty() {
+                            ui.menu_button("历史版本", |ui| {
+                                for version in versions {
+                                    let label =
+                                        match version.char_indices().nth(VERSION_CONTENT_LIMIT) {
+                                            Some((index, _)) => format!("{}…", &version[..index]),
+                                            None => version.clone(),
+                                        };
+                                    if ui.button(label).on_hover_text(&version).clicked() {
+                                        ui.close_menu();
+                                        self.editing_content = version;
+                                    }
+                                }
+                            })
+                            .response
+                            .on_hover_text("查看本次会话中该节点以前的内容，选择后填入编辑框");
+                        }
+                    });
+                    // 内容过长时在限定高度内滚动，避免窗口超出屏幕
+                    egui::ScrollArea::vertical()
+                        .id_salt("editing_content")
+                        .max_height(200.0)
+                        .show(ui, |ui| {
+                            let output =
+                                egui::TextEdit::multiline(&mut self.editing_content).show(ui);
+                            if let Some(range) = self.content_selection.take() {
+                                let mut state = output.state;
+                                state.cursor.set_char_range(Some(CCursorRange::two(
+                                    CCursor::new(range.start),
+                                    CCursor::new(range.end),
+                                )));
+                                state.store(ui.ctx(), output.response.id);
+                                output.response.request_focus();
+                            }
+                        });
+                    let limit = self.graph.as_ref().map_or(DEFAULT_CONTENT_LIMIT, |g| {
+                        g.current_snapshot().metadata.content_limit()
+                    });
+                    let count = self.editing_content.chars().count();
+                    if count > limit {
+                        ui.horizontal(|ui| {
+                            ui.colored_label(
+                                issue_color(IssueLevel::Warning),
+                                format!("内容有 {count} 字，超过建议的 {limit} 字"),
+                            );
+                            if ui.small_button("将超出部分移到备注").clicked() {
+                                let mut node = EntityNode::new(
+                                    edit_id,
+                                    std::mem::take(&mut self.editing_content),
+                                    self.editing_distinct_type,
+                                    &[],
+                                    (0.0, 0.0),
+                                );
+                                node.notes = std::mem::take(&mut self.editing_notes);
+                                node.move_excess_content(limit);
+                                self.editing_content = node.content;
+                                self.editing_notes = node.notes;
+                            }
+                        });
+                    }
+                    if let Some(glossary) = self.glossary.as_ref() {
+                        let matches = glossary.check(&self.editing_content);
+                        if !matches.is_empty() {
+                            let terms = matches
+                                .iter()
+                                .map(|m| format!("“{}”应写作“{}”", m.found, m.standard))
+                                .collect::<Vec<_>>();
+                            ui.horizontal_wrapped(|ui| {
+                                ui.colored_label(
+                                    issue_color(IssueLevel::Warning),
+                                    format!("术语不规范：{}", terms.join("，")),
+                                );
+                                if ui.small_button("替换为标准写法").clicked() {
+                                    self.editing_content =
+                                        apply_term_matches(&self.editing_content, &matches);
+                                }
+                            });
+                        }
+                    }
+
+                    ui.label("修改节点备注:");
+                    egui::ScrollArea::vertical()
+                        .id_salt("editing_notes")
+                        .max_height(200.0)
+                        .show(ui, |ui| ui.text_edit_multiline(&mut self.editing_notes));
+                    ui.horizontal(|ui| {
+                        ui.label("链接:");
+                        if ui
+                            .small_button("添加")
+                            .on_hover_text("网址或本地文件的完整路径，如慕课视频、课件")
+                            .clicked()
+                        {
+                            self.editing_links.push(String::new());
+                        }
+                    });
+                    let mut removed = None;
+                    for (i, link) in self.editing_links.iter_mut().enumerate() {
+                        ui.horizontal(|ui| {
+                            ui.add(
+                                egui::TextEdit::singleline(link)
+                                    .hint_text("https://… 或 C:\\课件\\…")
+                                    .desired_width(240.0),
+                            );
+                            if ui.small_button("删除").clicked() {
+                                removed = Some(i);
+                            }
+                        });
+                        if !link.trim().is_empty() && !link::is_valid_link(link) {
+                            ui.colored_label(
+                                issue_color(IssueLevel::Warning),
+                                "不是有效的网址或绝对路径，可能无法打开",
+                            );
+                        }
+                    }
+                    if let Some(i) = removed {
+                        self.editing_links.remove(i);
+                    }
+
+                    if self.pending_child.is_none() {
+                        ui.checkbox(&mut self.editing_reference, "仅作参考，不导出")
+                            .on_hover_text("作为样板保留在文件和画布中，导出时跳过该节点及其边");
+                    }
+
+                    ui.horizontal(|ui| {
+                        ui.add(
+                            egui::TextEdit::singleline(&mut self.editing_template_name)
+                                .hint_text("模板名称")
+                                .desired_width(120.0),
+                        );
+                        let name = self.editing_template_name.trim();
+                        if ui
+                            .add_enabled(!name.is_empty(), egui::Button::new("保存为模板"))
+                            .on_hover_text("保存当前的类型和附加类型，内容和备注不保存")
+                            .clicked()
+                        {
+                            let template = self.current_template(name.to_string());
+                            self.settings.save_template(template);
+                            self.notifications.info(format!("已保存模板 {name}"));
+                            self.editing_template_name.clear();
+                        }
+                    });
+
+                    ui.horizontal(|ui| {
+                        if ui.button("保存").clicked() {
+                            dialog_error!(self, self.commit_node_editor(), &[], "保存节点失败");
+                        }
+                        if ui.button("取消").clicked() {
+                            // 待新建的节点尚未创建，直接关闭
+                            if self.pending_child.is_some() {
+                                self.cancel_pending_child();
+                                return;
+                            }
+                            // 如果是新建的节点，则删除
+                            if self.editing_new_node {
+                                dialog_error!(
+                                    self,
+                                    self.graph.as_mut().unwrap().remove_entity(edit_id),
+                                    &[],
+                                    "删除节点失败"
+                                );
+                            }
+                            self.editing_node = None;
+                        }
+                    });
+                });
+        }
+    }
+
+    /// 由编辑窗口中当前的类型和附加类型生成模板
+    fn current_template(&self, name: String) -> NodeTemplate {
+        let mut addon_types = self
+            .editing_addon_types
+            .iter()
+            .filter_map(|(addon, checked)| checked.then_some(*addon))
+            .collect::<Vec<_>>();
+        addon_types.sort_by(|a, b| a.name().cmp(b.name()));
+        NodeTemplate {
+            name,
+            distinct_type: self.editing_distinct_type,
+            addon_types,
+            content: String::new(),
+        }
+    }
+
+    /// 用模板填充编辑窗口中的类型和附加类型。内容为空且模板带有内容时一并填入，
+    /// 并选中其中的占位符，没有占位符时光标置于末尾
+    fn apply_template(&mut self, template: &NodeTemplate) {
+        self.editing_distinct_type = template.distinct_type;
+        for (addon, checked) in self.editing_addon_types.iter_mut() {
+            *checked = template.addon_types.contains(addon);
+        }
+        for addon in template.addon_types.iter() {
+            self.editing_addon_types.insert(*addon, true);
+        }
+        if self.editing_content.is_empty() && !template.content.is_empty() {
+            self.editing_content = template.content.clone();
+            let end = self.editing_content.chars().count();
+            self.content_selection = Some(template.placeholder().unwrap_or(end..end));
+        }
+    }
+
+    /// 查找屏幕位置所在的节点
+    /// 分组框在屏幕上的范围
+    #[inline]
+    fn group_screen_rect(&self, group: &GroupFrame) -> Rect {
+        let bounds = self.graph.as_ref().map_or(group.rect, |graph| {
+            graph.current_snapshot().group_bounds(group)
+        });
+        Rect::from_min_max(
+            self.logical_to_screen(bounds.min),
+            self.logical_to_screen(bounds.max),
+        )
+    }
+
+    fn draw_groups(&self, painter: &Painter, snapshot: &Snapshot) {
+        for group in snapshot.groups.iter() {
+            let selected = self.selected_group == Some(group.id);
+            self.draw_group(painter, group, self.group_screen_rect(group), selected);
+        }
+    }
+
+    /// 以屏幕范围 rect 绘制分组框：半透明的圆角填充、边框、左上角的标签，
+    /// 以及没有成员时右下角的调整柄
+    fn draw_group(&self, painter: &Painter, group: &GroupFrame, rect: Rect, selected: bool) {
+        let [r, g, b] = group.color;
+        let color = Color32::from_rgb(r, g, b);
+        let stroke_width = if selected { 3.0 } else { 1.5 };
+        painter.rect_filled(rect, 4.0, color.gamma_multiply(0.12));
+        painter.rect_stroke(
+            rect,
+            4.0,
+            Stroke::new(stroke_width, color),
+            egui::StrokeKind::Inside,
+        );
+
+        if !group.label.is_empty() {
+            let galley = painter.layout_no_wrap(
+                group.label.clone(),
+                FontId::new(14.0 * self.zoom_factor, FontFamily::Proportional),
+                theme::text_color(color),
+            );
+            let header = group::header_rect(rect, self.zoom_factor);
+            let padding = 6.0 * self.zoom_factor;
+            let tag = Rect::from_min_size(
+                header.min,
+                Vec2::new(galley.size().x + 2.0 * padding, header.height()),
+            )
+            .intersect(rect);
+            painter.rect_filled(tag, 4.0, color);
+            let text_pos = Pos2::new(tag.min.x + padding, tag.center().y - galley.size().y / 2.0);
+            painter
+                .with_clip_rect(tag)
+                .galley(text_pos, galley, Color32::PLACEHOLDER);
+        }
+
+        if group.members.is_empty() {
+            let handle = Rect::from_min_max(rect.max - Vec2::splat(8.0), rect.max);
+            painter.rect_filled(handle, 0.0, color);
+        }
+    }
+
+    /// 查找屏幕位置所在的分组框及其部位，后绘制的分组框优先。
+    /// 有成员的分组框随成员调整大小，调整柄也视为边框
+    fn group_at(&self, pos: Pos2) -> Option<(u64, GroupHit)> {
+        let snapshot = self.graph.as_ref()?.current_snapshot();
+        snapshot.groups.iter().rev().find_map(|frame| {
+            group::hit_test(self.group_screen_rect(frame), self.zoom_factor, pos).map(|hit| {
+                if frame.members.is_empty() {
+                    (frame.id, hit)
+                } else {
+                    (frame.id, GroupHit::Frame)
+                }
+            })
+        })
+    }
+
+    /// 打开分组框编辑窗口。有选中的节点时将其归为一组，分组框随这些节点调整范围；
+    /// 没有选中节点时在 pos 处新建默认大小的分组框
+    fn begin_new_group(&mut self, pos: Pos2) {
+        let Some(graph) = self.graph.as_ref() else {
+            return;
+        };
+        let snapshot = graph.current_snapshot();
+        let mut members = self
+            .selected_nodes
+            .iter()
+            .copied()
+            .filter(|id| snapshot.nodes.contains_key(id))
+            .collect::<Vec<_>>();
+        members.sort_unstable();
+        let min = self.screen_to_logical(pos);
+        let mut frame = GroupFrame {
+            id: 0,
+            label: String::new(),
+            rect: CoordinateBounds {
+                min,
+                max: (min.0 + GROUP_DEFAULT_SIZE.0, min.1 + GROUP_DEFAULT_SIZE.1),
+            },
+            color: DEFAULT_GROUP_COLOR,
+            members,
+        };
+        // 成员都被删除后仍保留最后的范围
+        frame.rect = snapshot.group_bounds(&frame);
+        self.editing_group = Some(frame);
+    }
+
+    fn begin_edit_group(&mut self, id: u64) {
+        self.editing_group = self
+            .graph
+            .as_ref()
+            .and_then(|graph| graph.current_snapshot().group(id))
+            .cloned();
+    }
+
+    fn node_at(&self, pos: Pos2) -> Option<u64> {
+        let snapshot = self.graph.as_ref()?.current_snapshot();
+        let folded = self.folding.hidden(snapshot);
+        snapshot.nodes.values().find_map(|node| {
+            if folded.contains(&node.id) {
+                return None;
+            }
+            let size = Vec2::new(NODE_SIZE.x, NODE_SIZE.y) * self.zoom_factor;
+            let rect = Rect::from_center_size(self.node_screen_pos(node), size);
+            rect.contains(pos).then_some(node.id)
+        })
+    }
+
+    /// 进入编辑节点状态
+    fn begin_edit_node(&mut self, id: u64) {
+        let Some(node) = self
+            .graph
+            .as_ref()
+            .and_then(|graph| graph.current_snapshot().nodes.get(&id))
+        else {
+            return;
+        };
+        self.editing_distinct_type = node.distinct_type;
+        self.editing_content = node.content.clone();
+        self.editing_notes = node.notes.clone();
+        self.editing_reference = node.reference;
+        self.editing_links = node.links.clone();
+        for t in node.addon_types.iter() {
+            self.editing_addon_types.insert(*t, true);
+        }
+        self.editing_node = Some(id);
+        self.editing_new_node = false;
+    }
+
+    /// 在屏幕位置新建一个节点并进入编辑状态，需要排除位置在顶部控制栏或滚动条上的情况
+    fn create_node_at(&mut self, pos: Pos2) {
+        if !is_interactable(pos, self.canvas_rect) {
+            return;
+        }
+        let node_pos = self.screen_to_logical(pos);
+        let Some(graph) = self.graph.as_mut() else {
+            return;
+        };
+
+        match graph.add_entity(
+            String::new(),
+            DistinctEntityType::KnowledgePoint,
+            &[],
+            node_pos,
+        ) {
+            Ok(new_id) => {
+                self.editing_distinct_type = DistinctEntityType::KnowledgePoint;
+                self.editing_content = String::new();
+                self.editing_notes = String::new();
+                self.editing_reference = false;
+                self.editing_links.clear();
+                self.editing_addon_types.clear();
+                self.editing_node = Some(new_id);
+                self.editing_new_node = true;
+            }
+            Err(e) => self.error = Some(("新建节点失败".to_string(), e.zh_message())),
+        }
+    }
+
+    /// 在屏幕位置新建节点并用模板填充编辑窗口
+    fn create_node_from_template(&mut self, pos: Pos2, template: &NodeTemplate) {
+        self.create_node_at(pos);
+        if self.editing_new_node && self.editing_node.is_some() {
+            self.apply_template(template);
+        }
+    }
+
+    fn process_primary_click(&mut self, ui: &egui::Ui) {
+        if self.graph.is_none() {
+            return;
+        }
+        // 长按已打开右键菜单时，忽略手指抬起产生的单击
+        if self.touch_input && self.long_press.fired() {
+            return;
+        }
+        if ui.input(|i| i.pointer.primary_clicked())
+            && let Some(click_pos) = ui.input(|i| i.pointer.interact_pos())
+        {
+            // 触摸时手指的位置不如鼠标精确，双击的位置容差更大
+            let tolerance = if self.touch_input {
+                self.settings.double_tap_tolerance
+            } else {
+                self.settings.double_click_distance
+            };
+            let is_double = self.double_click.click(
+                click_pos,
+                time::Instant::now(),
+                time::Duration::from_millis(self.settings.double_click_ms),
+                tolerance,
+            );
+
+            if is_double {
+                // 认为是双击事件，查找点击位置是否在节点区域，若是则进入编辑节点状态
+                if self.editing_node.is_none()
+                    && let Some(id) = self.node_at(click_pos)
+                {
+                    self.begin_edit_node(id);
+                }
+
+                // 查找是否在分组框的边框或标签栏上，若是则编辑分组框
+                if self.editing_node.is_none()
+                    && let Some((id, _)) = self.group_at(click_pos)
+                {
+                    self.begin_edit_group(id);
+                }
+
+                // 查找是否在边区域，若是则编辑边
+                if !self.is_editing()
+                    && let Some(edge) = self.edge_at(click_pos)
+                {
+                    self.begin_edit_edge(edge);
+                }
+
+                // 如果未选中节点，则认为是新创建一个节点；按住 Alt 时改为以该点为中心放大一档
+                if !self.is_editing() {
+                    if ui.input(|i| i.modifiers.alt) {
+                        self.zoom_in_at(click_pos);
+                    } else {
+                        self.create_node_at(click_pos);
+                    }
+                }
+            } else if !self.is_editing()
+                && !ui.input(|i| i.modifiers.command)
+                && let Some(url) = self.link_at(ui, click_pos)
+            {
+                // 单击节点内容中的网址时用浏览器打开，不改变选中状态
+                ui.ctx().open_url(egui::OpenUrl::new_tab(url));
+            } else if !self.is_editing()
+                && let Some(link) = self.link_mark_at(click_pos)
+            {
+                // 单击（包括按住 Ctrl 单击）链接标记时打开节点的第一个链接，不改变选中状态
+                self.open_link(&link);
+            } else if !self.is_editing() {
+                // 认为是单击事件，查找点击位置是否在节点区域或者边区域，若是则选中节点或边
+                // 按住 Ctrl 时切换节点的选中状态，否则重置选中状态
+                let multi_select = ui.input(|i| i.modifiers.command);
+                if !multi_select {
+                    self.selected_nodes.clear();
+                    self.selected_edges.clear();
+                }
+                self.selected_group = None;
+
+                // 优先选中节点
+                let snapshot = self.graph.as_ref().unwrap().current_snapshot();
+
+                let mut clicked_node = None;
+                for (id, node) in snapshot.nodes.iter() {
+                    let node_pos = self.node_screen_pos(node);
+                    let size = Vec2::new(NODE_SIZE.x, NODE_SIZE.y) * self.zoom_factor;
+                    let rect = Rect::from_center_size(node_pos, size);
+                    if rect.contains(click_pos) {
+                        clicked_node = Some(*id);
+                        break;
+                    }
+                }
+                if let Some(id) = clicked_node
+                    && !self.selected_nodes.remove(&id)
+                {
+                    self.selected_nodes.insert(id);
+                }
+
+                // 若未选中节点，则尝试选中分组框
+                let clicked_group = clicked_node
+                    .is_none()
+                    .then(|| self.group_at(click_pos))
+                    .flatten();
+                if let Some((id, _)) = clicked_group {
+                    self.selected_group = Some(id);
+                }
+
+                // 若未选中节点和分组框，则尝试选中边，按住 Ctrl 时同样切换边的选中状态
+                if clicked_node.is_none() && clicked_group.is_none() {
+                    let clicked_edge = self.edge_at(click_pos);
+                    if let Some(edge) = clicked_edge
+                        && !self.selected_edges.remove(&edge)
+                    {
+                        self.selected_edges.insert(edge);
+                    }
+                }
+            }
+        }
+    }
+
+    fn process_hover(&mut self, ui: &egui::Ui) {
+        if let Some(graph) = self.graph.as_ref()
+            && let Some(pos) = ui.input(|i| i.pointer.latest_pos())
+        {
+            let snapshot = graph.current_snapshot();
+
+            // 重置悬停状态
+            self.hovered_node = None;
+            self.hovered_edge = None;
+
+            // 优先悬停节点
+            for (id, node) in snapshot.nodes.iter() {
+                let node_pos = self.node_screen_pos(node);
+                let size = Vec2::new(NODE_SIZE.x, NODE_SIZE.y) * self.zoom_factor;
+                let rect = Rect::from_center_size(node_pos, size);
+                if rect.contains(pos) {
+                    if node_pos.distance(pos) < 4.0 {
+                        self.hovered_node = Some((*id, true));
+                    } else {
+                        self.hovered_node = Some((*id, false));
+                    }
+                    break;
+                }
+            }
+
+            // 若未悬停节点，则尝试悬停边
+            if self.hovered_node.is_none() {
+                self.hovered_edge = self.edge_at(pos);
+            }
+
+            if self.hovered_node.is_some() && self.link_at(ui, pos).is_some() {
+                ui.ctx().set_cursor_icon(egui::CursorIcon::PointingHand);
+            }
+            if self.hovered_node.is_none()
+                && let Some((_, hit)) = self.group_at(pos)
+            {
+                ui.ctx().set_cursor_icon(match hit {
+                    GroupHit::Frame => egui::CursorIcon::Move,
+                    GroupHit::Resize => egui::CursorIcon::ResizeNwSe,
+                });
+            }
+        }
+    }
+
+    fn process_primary_down(&mut self, ui: &egui::Ui) {
+        if let Some(graph) = self.graph.as_ref()
+            && ui.input(|i| i.pointer.primary_down())
+            && !self.is_editing()
+        {
+            if !self.is_dragging()
+                && !self.is_linking_edge()
+                && let Some(click_pos) = ui.input(|i| i.pointer.interact_pos())
+            {
+                // 在控制栏或滚动条上按下时不处理
+                if !is_interactable(click_pos, self.canvas_rect) {
+                    return;
+                }
+                // 判断点击的节点
+                let mut clicked_node = None;
+                let snapshot = graph.current_snapshot();
+                for (_, node) in snapshot.nodes.iter() {
+                    let node_pos = self.node_screen_pos(node);
+                    let size = Vec2::new(NODE_SIZE.x, NODE_SIZE.y) * self.zoom_factor;
+                    let rect = Rect::from_center_size(node_pos, size);
+                    if rect.contains(click_pos) {
+                        clicked_node = Some(node);
+                        break;
+                    }
+                }
+
+                if let Some(node) = clicked_node {
+                    let node_pos = self.node_screen_pos(node);
+
+                    if node_pos.distance(click_pos) < 4.0 {
+                        // 如果节点中心和 click_pos 接近，则开始绘制边
+                        self.edge_draft.start(node.id);
+                        self.dragging_offset = Vec2::ZERO;
+                    } else {
+                        // 否则拖动节点
+                        self.dragging_node = Some(node.id);
+                    }
+                } else if let Some(handle) = self.waypoint_at(click_pos) {
+                    // 其次拖动选中的边的折点
+                    self.dragging_waypoint = Some(handle);
+                } else if let Some(group) = self.group_at(click_pos) {
+                    // 其次拖动分组框的边框或调整柄
+                    self.dragging_group = Some(group);
+                }
+            }
+            // 获取鼠标拖动的位移
+            if self.is_dragging() {
+                let drag_delta = ui.input(|i| i.pointer.delta());
+                self.dragging_offset += drag_delta;
+            }
+        }
+    }
+
+    fn process_primary_up(&mut self, ui: &egui::Ui) {
+        if self.graph.is_none() {
+            return;
+        }
+        if ui.input(|i| i.pointer.primary_released()) {
+            // 如果设置拖拽节点
+            if let Some(dragging_node) = self.dragging_node {
+                if let Some(node) = self
+                    .graph
+                    .as_ref()
+                    .unwrap()
+                    .current_snapshot()
+                    .nodes
+                    .get(&dragging_node)
+                {
+                    // 拖动的位移是屏幕上的距离，需要除以缩放比例换算为逻辑距离
+                    let offset = self.dragging_offset / self.zoom_factor;
+                    let new_pos = snap_to_grid((
+                        node.coor.0 + offset.x as f64,
+                        node.coor.1 + offset.y as f64,
+                    ));
+                    dialog_error!(
+                        self,
+                        self.graph
+                            .as_mut()
+                            .unwrap()
+                            .update_entity_position(dragging_node, new_pos),
+                        &[],
+                        "更新节点位置失败"
+                    );
+                }
+                // 设置选中节点，多选时保留其他选中的节点
+                if !ui.input(|i| i.modifiers.command) {
+                    self.selected_nodes = BTreeSet::from([dragging_node]);
+                }
+
+                // 重置变量
+                self.dragging_node = None;
+                self.dragging_offset = Vec2::ZERO;
+            }
+
+            // 如果拖动了折点，则移动折点
+            if let Some(((from, to), index)) = self.dragging_waypoint.take() {
+                let offset = self.dragging_offset / self.zoom_factor;
+                self.dragging_offset = Vec2::ZERO;
+                if let Some(graph) = self.graph.as_mut() {
+                    let mut waypoints = graph.current_snapshot().waypoints(from, to).to_vec();
+                    if let Some(point) = waypoints.get_mut(index)
+                        && offset != Vec2::ZERO
+                    {
+                        *point =
+                            snap_to_grid((point.0 + offset.x as f64, point.1 + offset.y as f64));
+                        dialog_error!(
+                            self,
+                            graph.set_edge_waypoints(from, to, waypoints),
+                            &[],
+                            "移动折点失败"
+                        );
+                    }
+                }
+            }
+
+            // 如果拖动了分组框，则移动分组框及其中的节点，或调整分组框的大小
+            if let Some((id, hit)) = self.dragging_group.take() {
+                let offset = self.dragging_offset / self.zoom_factor;
+                let offset = snap_to_grid((offset.x as f64, offset.y as f64));
+                self.dragging_offset = Vec2::ZERO;
+                self.selected_group = Some(id);
+                if let Some(graph) = self.graph.as_mut()
+                    && let Some(group) = graph.current_snapshot().group(id).cloned()
+                    && offset != (0.0, 0.0)
+                {
+                    let result = match hit {
+                        GroupHit::Frame => graph.move_group(id, offset),
+                        GroupHit::Resize => graph.update_group(GroupFrame {
+                            rect: group::resized(group.rect, offset),
+                            ..group
+                        }),
+                    };
+                    dialog_error!(self, result, &[], "修改分组框失败");
+                }
+            }
+
+            // 如果设置绘制边
+            if let EdgeDraft::Dragging {
+                from: edge_start_node,
+            } = self.edge_draft
+            {
+                let snapshot = self.graph.as_ref().unwrap().current_snapshot();
+                if self.pending_child.is_none()
+                    && snapshot.nodes.get(&edge_start_node).is_some()
+                    && let Some(pos) = ui.input(|i| i.pointer.interact_pos())
+                {
+                    let target = snapshot.nodes.values().find(|node| {
+                        let size = Vec2::new(NODE_SIZE.x, NODE_SIZE.y) * self.zoom_factor;
+                        Rect::from_center_size(self.node_screen_pos(node), size).contains(pos)
+                    });
+                    match target {
+                        Some(node) => {
+                            self.edge_draft.release_on(node.id);
+                            // 按起止节点的类型预选关系，没有对应规则时沿用上次确认的关系
+                            if let EdgeDraft::Choosing { .. } = self.edge_draft
+                                && let Some(from) = snapshot.nodes.get(&edge_start_node)
+                                && let Some(relation) =
+                                    suggested_relation(from.distinct_type, node.distinct_type)
+                            {
+                                self.current_relation = relation;
+                            }
+                        }
+                        // 如果未选中节点，则在空白处新建下一级的节点，以当前关系连边，
+                        // 顶部控制栏或滚动条上松开时取消绘制边
+                        None if is_interactable(pos, self.canvas_rect) => {
+                            let coor = self.screen_to_logical(pos);
+                            let relation = self.current_relation;
+                            self.begin_pending_child(edge_start_node, coor, relation)
+                        }
+                        None => self.edge_draft.cancel(),
+                    }
+                }
+            }
+        }
+    }
+
+    fn process_secondary_click(&mut self, ui: &egui::Ui) {
+        if self.graph.is_none() || self.is_editing() {
+            return;
+        }
+        if ui.input(|i| i.pointer.secondary_clicked())
+            && let Some(pos) = ui.input(|i| i.pointer.interact_pos())
+        {
+            self.open_context_menu(pos);
+        }
+    }
+
+    fn open_context_menu(&mut self, pos: Pos2) {
+        if !is_interactable(pos, self.canvas_rect) {
+            return;
+        }
+        let target = match (self.node_at(pos), self.group_at(pos)) {
+            (Some(id), _) => MenuTarget::Node(id),
+            (None, Some((id, _))) => MenuTarget::Group(id),
+            (None, None) => self
+                .edge_at(pos)
+                .map_or(MenuTarget::Canvas, MenuTarget::Edge),
+        };
+        self.context_menu = Some((pos, target));
+    }
+
+    /// 记录本帧双指或滚轮平移的滚动偏移变化，平移结束后按最后的速度继续滑动，
+    /// 按下指针或缩放时立即停止
+    fn process_inertia(&mut self, ui: &egui::Ui, offset_before: Vec2) {
+        if !self.settings.inertial_scroll || self.graph.is_none() {
+            self.inertia.stop();
+            return;
+        }
+
+        let now = time::Instant::now();
+        let (panning, interrupted, dt) = ui.input(|i| {
+            (
+                i.smooth_scroll_delta != Vec2::ZERO || i.multi_touch().is_some(),
+                i.pointer.any_down() || i.zoom_delta() != 1.0,
+                i.stable_dt.min(0.1), // 避免窗口失去焦点后一帧移动过远
+            )
+        });
+        if panning {
+            self.inertia.record(now, self.scroll_offset - offset_before);
+        } else if interrupted {
+            self.inertia.stop();
+        } else if self.inertia.is_tracking() {
+            self.inertia.release(now);
+        }
+
+        if self.inertia.is_active() {
+            let offset = (self.scroll_offset + self.inertia.step(dt)).max(Vec2::ZERO);
+            self.scroll_offset = offset;
+            self.pending_scroll = Some(offset);
+            ui.ctx().request_repaint();
+        }
+    }
+
+    fn process_touch(&mut self, ui: &egui::Ui) {
+        if self.graph.is_none() {
+            return;
+        }
+
+        // 根据本帧的事件判断输入来源，触摸时 egui 同时会生成模拟的鼠标事件
+        let (touched, pointer_moved) = ui.input(|i| {
+            (
+                i.events
+                    .iter()
+                    .any(|e| matches!(e, egui::Event::Touch { .. })),
+                i.events.iter().any(|e| {
+                    matches!(
+                        e,
+                        egui::Event::PointerMoved(_) | egui::Event::PointerButton { .. }
+                    )
+                }),
+            )
+        });
+        if touched {
+            self.touch_input = true;
+        } else if pointer_moved {
+            self.touch_input = false;
+        }
+        if !self.touch_input {
+            return;
+        }
+
+        // 双指手势：平移画布并以手势中心缩放，同时取消单指的操作
+        if let Some(multi_touch) = ui.ctx().multi_touch() {
+            self.long_press.release();
+            self.dragging_node = None;
+            self.dragging_group = None;
+            self.dragging_waypoint = None;
+            self.dragging_offset = Vec2::ZERO;
+            self.edge_draft.cancel();
+
+            let old_zoom = self.zoom_factor;
+            self.zoom_factor = (old_zoom * multi_touch.zoom_delta).clamp(0.5, 3.0);
+            let anchor = multi_touch.center_pos - Pos2::new(0.0, TOP_PANEL_HEIGHT);
+            let offset = touch::zoom_about(self.scroll_offset, anchor, old_zoom, self.zoom_factor)
+                - multi_touch.translation_delta;
+            self.scroll_offset = offset;
+            self.pending_scroll = Some(offset);
+            return;
+        }
+
+        // 长按相当于右键点击
+        let (pressed, released, pos) = ui.input(|i| {
+            (
+                i.pointer.primary_pressed(),
+                i.pointer.primary_released(),
+                i.pointer.interact_pos(),
+            )
+        });
+        let now = time::Instant::now();
+        if pressed && let Some(pos) = pos {
+            self.long_press.press(pos, now);
+        } else if released {
+            self.long_press.release();
+        } else if let Some(pos) = pos
+            && !self.is_editing()
+            && let Some(pos) = self.long_press.update(
+                pos,
+                now,
+                time::Duration::from_millis(self.settings.long_press_ms),
+                self.settings.double_tap_tolerance,
+            )
+        {
+            // 取消按下时开始的拖动和绘制边
+            self.dragging_node = None;
+            self.dragging_group = None;
+            self.dragging_waypoint = None;
+            self.dragging_offset = Vec2::ZERO;
+            self.edge_draft.cancel();
+            self.open_context_menu(pos);
+        }
+        if self.long_press.is_pressed() {
+            // 按住期间持续刷新，以便及时触发长按
+            ui.ctx().request_repaint();
+        }
+    }
+
+    fn show_context_menu(&mut self, ctx: &Context) {
+        let Some((pos, target)) = self.context_menu else {
+            return;
+        };
+
+        let mut close = false;
+        let response = egui::Area::new(egui::Id::new("右键菜单"))
+            .fixed_pos(pos)
+            .order(egui::Order::Foreground)
+            .show(ctx, |ui| {
+                egui::Frame::menu(ui.style()).show(ui, |ui| match target {
+                    MenuTarget::Node(id) => {
+                        if ui.button("编辑节点").clicked() {
+                            close = true;
+                            self.context_menu = None;
+                            self.begin_edit_node(id);
+                        }
+                        if ui.button("聚焦节点").clicked() {
+                            close = true;
+                            self.focused_node = Some(id);
+                        }
+                        let links = self
+                            .graph
+                            .as_ref()
+                            .and_then(|graph| graph.current_snapshot().nodes.get(&id))
+                            .map(|node| node.links.clone())
+                            .unwrap_or_default();
+                        match links.as_slice() {
+                            [] => {}
+                            [link] => {
+                                if ui.button("打开链接").on_hover_text(link).clicked() {
+                                    close = true;
+                                    self.open_link(link);
+                                }
+                            }
+                            links => {
+                                ui.menu_button("打开链接", |ui| {
+                                    for link in links {
+                                        if ui.button(link).clicked() {
+                                            close = true;
+                                            self.open_link(link);
+                                        }
+                                    }
+                                });
+                            }
+                        }
+                        // 右键的节点在选区中时标记整个选区
+                        let flag = self
+                            .graph
+                            .as_ref()
+                            .and_then(|graph| graph.current_snapshot().nodes.get(&id))
+                            .and_then(|node| node.flag);
+                        ui.menu_button("评审标记", |ui| {
+                            for option in Flag::ALL.map(Some).into_iter().chain([None]) {
+                                let label = option.map_or("清除标记", |flag| flag.label());
+                                if ui.selectable_label(flag == option, label).clicked() {
+                                    close = true;
+                                    let ids = if self.selected_nodes.contains(&id) {
+                                        self.selected_nodes.iter().copied().collect()
+                                    } else {
+                                        vec![id]
+                                    };
+                                    self.set_flags(&ids, option);
+                                }
+                            }
+                        });
+                        // 快捷位已绑定其他节点时改为绑定该节点
+                        let hotkey = self
+                            .view_sidecar
+                            .hotkeys
+                            .iter()
+                            .find(|(_, bound)| **bound == id)
+                            .map(|(slot, _)| *slot);
+                        ui.menu_button("快捷位", |ui| {
+                            for slot in 1..=9 {
+                                let label = match self.view_sidecar.hotkeys.get(&slot) {
+                                    Some(bound) if *bound != id => {
+                                        format!("Ctrl + {slot}（替换节点 {bound}）")
+                                    }
+                                    _ => format!("Ctrl + {slot}"),
+                                };
+                                if ui.selectable_label(hotkey == Some(slot), label).clicked() {
+                                    close = true;
+                                    self.set_hotkey(id, Some(slot));
+                                }
+                            }
+                            if hotkey.is_some() && ui.button("解除绑定").clicked() {
+                                close = true;
+                                self.set_hotkey(id, None);
+                            }
+                        });
+                        // 有多个父节点时选择主父节点，面包屑和教学要求矩阵以主父节点为准
+                        let parents = self
+                            .graph
+                            .as_ref()
+                            .map(|graph| {
+                                let snapshot = graph.current_snapshot();
+                                let primary = snapshot.primary_parent(id);
+                                snapshot
+                                    .parents(id)
+                                    .into_iter()
+                                    .filter_map(|parent| {
+                                        let content = snapshot.nodes.get(&parent)?.content.clone();
+                                        Some((parent, content, primary == Some(parent)))
+                                    })
+                                    .collect::<Vec<_>>()
+                            })
+                            .unwrap_or_default();
+                        if parents.len() > 1 {
+                            ui.menu_button("主父节点", |ui| {
+                                for (parent, content, primary) in parents {
+                                    if ui.selectable_label(primary, content).clicked() {
+                                        close = true;
+                                        self.set_primary_parent(id, parent);
+                                    }
+                                }
+                            });
+                        }
+                        let children = self
+                            .graph
+                            .as_ref()
+                            .map(|graph| graph.current_snapshot().children(id))
+                            .unwrap_or_default();
+                        // 只切换该节点自身，批量折叠或展开子树在顶部的折叠菜单中
+                        if !children.is_empty() {
+                            let label = if self.folding.is_collapsed(id) {
+                                "展开下级节点"
+                            } else {
+                                "折叠下级节点"
+                            };
+                            if ui.button(label).clicked() {
+                                close = true;
+                                self.folding.toggle(id);
+                            }
+                        }
+                        if children.len() > 1 && ui.button("调整子节点顺序").clicked() {
+                            close = true;
+                            self.context_menu = None;
+                            self.ordering_children = Some((id, children));
+                        }
+                        if ui.button("选中所有边").clicked() {
+                            close = true;
+                            self.select_edges_of(id, false);
+                        }
+                        if ui.button("选中所有出边").clicked() {
+                            close = true;
+                            self.select_edges_of(id, true);
+                        }
+                        if ui.button("删除所有出边").clicked() {
+                            close = true;
+                            if let Some(graph) = self.graph.as_mut() {
+                                let commands = graph
+                                    .current_snapshot()
+                                    .edges
+                                    .keys()
+                                    .filter(|(from, _)| *from == id)
+                                    .map(|&(from, to)| Command::RemoveEdge { from, to })
+                                    .collect::<Vec<_>>();
+                                dialog_error!(self, graph.apply_batch(commands), &[], "删除边失败");
+                            }
+                        }
+                        if ui.button("删除节点").clicked() {
+                            close = true;
+                            self.remove_node(id);
+                        }
+                    }
+                    MenuTarget::Group(id) => {
+                        if ui.button("编辑分组框").clicked() {
+                            close = true;
+                            self.context_menu = None;
+                            self.begin_edit_group(id);
+                        }
+                        if ui.button("选中其中的节点").clicked() {
+                            close = true;
+                            if let Some(graph) = self.graph.as_ref() {
+                                let nodes = graph.current_snapshot().nodes_in_group(id);
+                                self.selected_nodes = nodes.into_iter().collect();
+                                self.selected_edges.clear();
+                            }
+                        }
+                        if ui.button("删除分组框").clicked() {
+                            close = true;
+                            if let Some(graph) = self.graph.as_mut() {
+                                dialog_error!(self, graph.remove_group(id), &[], "删除分组框失败");
+                            }
+                            self.selected_group = None;
+                        }
+                    }
+                    MenuTarget::Edge(edge) => {
+                        if ui.button("编辑边").clicked() {
+                            close = true;
+                            self.context_menu = None;
+                            self.begin_edit_edge(edge);
+                        }
+                        if ui
+                            .button("在此处添加折点")
+                            .on_hover_text("选中边后可拖动折点，使边绕开其他节点")
+                            .clicked()
+                        {
+                            close = true;
+                            self.add_waypoint(edge, pos);
+                        }
+                        if let Some(index) = self.waypoint_index_at(edge, pos)
+                            && ui.button("删除此折点").clicked()
+                        {
+                            close = true;
+                            self.remove_waypoint(edge, Some(index));
+                        }
+                        let has_waypoints = self.graph.as_ref().is_some_and(|graph| {
+                            !graph
+                                .current_snapshot()
+                                .waypoints(edge.0, edge.1)
+                                .is_empty()
+                        });
+                        if has_waypoints && ui.button("清除所有折点").clicked() {
+                            close = true;
+                            self.remove_waypoint(edge, None);
+                        }
+                    }
+                    MenuTarget::Canvas => {
+                        if ui.button("新建节点").clicked() {
+                            close = true;
+                            self.context_menu = None;
+                            self.create_node_at(pos);
+                        }
+                        if !self.settings.templates.is_empty() {
+                            let mut selected = None;
+                            ui.menu_button("从模板新建", |ui| {
+                                for template in self.settings.templates.iter() {
+                                    if ui.button(&template.name).clicked() {
+                                        selected = Some(template.clone());
+                                    }
+                                }
+                            });
+                            if let Some(template) = selected {
+                                close = true;
+                                self.context_menu = None;
+                                self.create_node_from_template(pos, &template);
+                            }
+                        }
+                        let label = if self.selected_nodes.is_empty() {
+                            "新建分组框"
+                        } else {
+                            "将选中的节点归为一组"
+                        };
+                        if ui.button(label).clicked() {
+                            close = true;
+                            self.context_menu = None;
+                            self.begin_new_group(pos);
+                        }
+                    }
+                })
+            })
+            .response;
+
+        // 点击菜单以外的位置时关闭菜单
+        if close || (ctx.input(|i| i.pointer.any_pressed()) && !response.contains_pointer()) {
+            self.context_menu = None;
+        }
+    }
+
+    /// 以屏幕上的 pos 为中心放大一档，pos 下的内容保持不动
+    fn zoom_in_at(&mut self, pos: Pos2) {
+        let old_zoom = self.zoom_factor;
+        self.zoom_factor = (old_zoom * DOUBLE_CLICK_ZOOM).clamp(0.5, 3.0);
+        let anchor = pos - Pos2::new(0.0, TOP_PANEL_HEIGHT);
+        let offset = touch::zoom_about(self.scroll_offset, anchor, old_zoom, self.zoom_factor);
+        self.scroll_offset = offset;
+        self.pending_scroll = Some(offset);
+    }
+
+    fn process_zoom(&mut self, ctx: &Context) {
+        if self.touch_input && ctx.multi_touch().is_some() {
+            // 双指缩放已在 process_touch 中处理
+            return;
+        }
+        let zoom_delta = ctx.input(|i| i.zoom_delta());
+        if (zoom_delta - 1.0).abs() > f32::EPSILON
+            && let Some(hover_pos) = ctx.input(|i| i.pointer.hover_pos())
+        {
+            // 设置了围绕选中节点缩放且只选中一个节点时，该节点在屏幕上保持不动，否则鼠标下的内容保持不动
+            let selected_pos = self
+                .graph
+                .as_ref()
+                .filter(|_| self.settings.zoom_around_selection && self.selected_nodes.len() == 1)
+                .and_then(|graph| {
+                    let id = self.selected_nodes.first()?;
+                    graph.current_snapshot().nodes.get(id)
+                })
+                .map(|node| self.node_draw_pos(node));
+            let pos = selected_pos.unwrap_or(hover_pos);
+
+            let old_zoom = self.zoom_factor;
+            self.zoom_factor = (old_zoom * zoom_delta).clamp(0.5, 3.0);
+            let anchor = pos - Pos2::new(0.0, TOP_PANEL_HEIGHT);
+            let offset = touch::zoom_about(self.scroll_offset, anchor, old_zoom, self.zoom_factor);
+            self.scroll_offset = offset;
+            self.pending_scroll = Some(offset);
+        }
+    }
+
+    fn process_keyboard_delete(&mut self, ui: &egui::Ui) {
+        // 在编辑框中按 Delete 是删除文字
+        if ui.ctx().wants_keyboard_input() {
+            return;
+        }
+        if let Some(graph) = self.graph.as_mut()
+            && ui.input(|i| i.key_pressed(egui::Key::Delete))
+        {
+            if let Some(&selected_node) = self.selected_nodes.first() {
+                // 只删除单独选中的节点，避免误删多个节点
+                if self.selected_nodes.len() == 1 {
+                    self.remove_node(selected_node);
+                }
+            } else if let Some(id) = self.selected_group.take() {
+                dialog_error!(self, graph.remove_group(id), &[], "删除分组框失败");
+            } else if !self.selected_edges.is_empty() {
+                let commands = self
+                    .selected_edges
+                    .iter()
+                    .map(|&(from, to)| Command::RemoveEdge { from, to });
+                dialog_error!(self, graph.apply_batch(commands), &[], "删除边失败");
+                self.selected_edges.clear();
+            }
+        }
+    }
+
+    /// 按数字键 1~4 将选中的节点分别设为知识领域、知识单元、知识点、关键知识细节，
+    /// 只修改节点类型，多个节点作为一次操作修改
+    fn process_keyboard_distinct_type(&mut self, ui: &egui::Ui) {
+        if self.selected_nodes.is_empty() || self.is_editing() || self.is_linking_edge() {
+            return;
+        }
+        let keys = [
+            egui::Key::Num1,
+            egui::Key::Num2,
+            egui::Key::Num3,
+            egui::Key::Num4,
+        ];
+        let Some(distinct_type) = ui.input(|i| {
+            keys.iter()
+                .zip(DISTINCT_TYPES)
+                .find(|(key, _)| i.key_pressed(**key) && i.modifiers.is_none())
+                .map(|(_, distinct_type)| distinct_type)
+        }) else {
+            return;
+        };
+        let Some(graph) = self.graph.as_mut() else {
+            return;
+        };
+
+        let snapshot = graph.current_snapshot();
+        let commands = self
+            .selected_nodes
+            .iter()
+            .filter_map(|id| snapshot.nodes.get(id))
+            .filter(|node| node.distinct_type != distinct_type)
+            .map(|node| Command::UpdateEntityContent {
+                id: node.id,
+                content: node.content.clone(),
+                distinct_type,
+                addon_types: node.addon_types.iter().copied().collect(),
+            })
+            .collect::<Vec<_>>();
+        if commands.is_empty() {
+            return;
+        }
+        let result = graph.apply_batch(commands);
+        dialog_error!(self, result, &[], "修改节点类型失败");
+    }
+
+    /// 按 Ctrl + 数字键 1~9 选中快捷位绑定的节点并滚动到视图中心
+    fn process_keyboard_hotkey(&mut self, ui: &egui::Ui) {
+        if self.is_editing() || self.is_linking_edge() {
+            return;
+        }
+        let Some(slot) = ui.input(|i| {
+            (1..=9)
+                .zip(HOTKEY_KEYS)
+                .find(|(_, key)| i.key_pressed(*key) && i.modifiers.command_only())
+                .map(|(slot, _)| slot)
+        }) else {
+            return;
+        };
+        let Some(&id) = self.view_sidecar.hotkeys.get(&slot) else {
+            self.notifications.info(format!("快捷位 {slot} 未绑定节点"));
+            return;
+        };
+        let Some(coor) = self
+            .graph
+            .as_ref()
+            .and_then(|graph| graph.current_snapshot().nodes.get(&id))
+            .map(|node| node.coor)
+        else {
+            self.notifications
+                .warning(format!("快捷位 {slot} 绑定的节点 {id} 已不存在"));
+            return;
+        };
+        self.selected_nodes = BTreeSet::from([id]);
+        self.scroll_to(ui.ctx(), coor);
+    }
+
+    /// 将节点绑定到快捷位，slot 为 None 时解除绑定。每个节点只占一个快捷位，绑定后写入视图文件
+    fn set_hotkey(&mut self, id: u64, slot: Option<u8>) {
+        let Some(graph) = self.graph.as_ref() else {
+            return;
+        };
+        self.view_sidecar.hotkeys.retain(|_, bound| *bound != id);
+        if let Some(slot) = slot {
+            self.view_sidecar.hotkeys.insert(slot, id);
+        }
+        if let Err(e) = self.view_sidecar.save(&graph.file_path) {
+            self.notifications
+                .warning(format!("保存视图设置失败：{}", e.zh_message()));
+        }
+    }
+
+    /// 按 Alt + 数字键 1~6 按图例的顺序切换选中节点的附加类型。选中的节点都带有该附加类型时移除，否则添加。
+    /// 不带修饰键的数字键用于设置节点类型，Ctrl + 数字键用于跳转到快捷位，因此使用 Alt
+    /// 每次按键是一次操作，但之后没有其他修改时，连续切换同一组节点会合并为一次操作
+    fn process_keyboard_addon_type(&mut self, ui: &egui::Ui) {
+        if self.selected_nodes.is_empty() || self.is_editing() || self.is_linking_edge() {
+            return;
+        }
+        let Some(addon_type) = ui.input(|i| {
+            ADDON_KEYS
+                .iter()
+                .zip(ADDON_TYPES)
+                .find(|(key, _)| {
+                    i.key_pressed(**key) && i.modifiers.matches_exact(egui::Modifiers::ALT)
+                })
+                .map(|(_, addon_type)| addon_type)
+        }) else {
+            return;
+        };
+        let current = self.view_context();
+        let Some(graph) = self.graph.as_mut() else {
+            return;
+        };
+
+        // 以当前状态计算切换后的附加类型，合并时撤销上一次切换后再一并修改
+        let snapshot = graph.current_snapshot();
+        let nodes = self
+            .selected_nodes
+            .iter()
+            .filter_map(|id| snapshot.nodes.get(id))
+            .collect::<Vec<_>>();
+        let remove = nodes
+            .iter()
+            .all(|node| node.addon_types.contains(&addon_type));
+        let targets = nodes
+            .into_iter()
+            .map(|node| {
+                let mut addon_types = node.addon_types.clone();
+                if remove {
+                    addon_types.remove(&addon_type);
+                } else {
+                    addon_types.insert(addon_type);
+                }
+                (node.id, addon_types)
+            })
+            .collect::<Vec<_>>();
+        if targets.is_empty() {
+            return;
+        }
+
+        let coalesce = self.addon_toggle.as_ref().is_some_and(|(ids, revision)| {
+            *ids == self.selected_nodes && *revision == graph.revision()
+        });
+        if coalesce && graph.undo().is_ok() {
+            self.view_history.undo(graph.revision(), current);
+        }
+
+        let snapshot = graph.current_snapshot();
+        let commands = targets
+            .into_iter()
+            .filter_map(|(id, addon_types)| {
+                let node = snapshot.nodes.get(&id)?;
+                (node.addon_types != addon_types).then(|| Command::UpdateEntityContent {
+                    id,
+                    content: node.content.clone(),
+                    distinct_type: node.distinct_type,
+                    addon_types: addon_types.into_iter().collect(),
+                })
+            })
+            .collect::<Vec<_>>();
+        // 连续切换回到了原来的状态时不需要修改
+        if !commands.is_empty()
+            && let Err(e) = graph.apply_batch(commands)
+        {
+            self.error = Some(("修改附加类型失败".to_string(), e.zh_message()));
+            self.addon_toggle = None;
+            return;
+        }
+        self.addon_toggle = Some((self.selected_nodes.clone(), graph.revision()));
+
+        let target = match self.selected_nodes.iter().collect::<Vec<_>>().as_slice() {
+            [id] => format!("节点 {id}"),
+            ids => format!("{} 个节点", ids.len()),
+        };
+        let sign = if remove { '-' } else { '+' };
+        self.notifications
+            .info(format!("{target}: {sign}{}", addon_type.name()));
+    }
+
+    fn process_keyboard_unfocus(&mut self, ui: &egui::Ui) {
+        if self.focused_node.is_some()
+            && !self.is_editing()
+            && ui.input(|i| i.key_pressed(egui::Key::Escape))
+        {
+            self.focused_node = None;
+        }
+    }
+
+    fn process_keyboard_new_node(&mut self, ui: &egui::Ui) {
+        if self.graph.is_some()
+            && !self.is_editing()
+            && !self.is_linking_edge()
+            && !self.is_dragging()
+            && ui.input(|i| i.key_pressed(egui::Key::N) && i.modifiers.command)
+        {
+            self.create_node_at(ui.ctx().screen_rect().center());
+        }
+    }
+
+    /// 单独选中一个节点时按 Tab 键，在按节点类型建议的位置新建低一级的节点
+    /// 只选中一个节点时按 F2 或回车，在节点原位编辑其内容
+    fn process_keyboard_inline_edit(&mut self, ui: &egui::Ui) {
+        if self.selected_nodes.len() != 1
+            || self.is_editing()
+            || self.is_linking_edge()
+            || self.is_dragging()
+            || !ui.input(|i| {
+                (i.key_pressed(egui::Key::F2) || i.key_pressed(egui::Key::Enter))
+                    && i.modifiers.is_none()
+            })
+        {
+            return;
+        }
+        let Some(&id) = self.selected_nodes.first() else {
+            return;
+        };
+        if let Some(node) = self
+            .graph
+            .as_ref()
+            .and_then(|graph| graph.current_snapshot().nodes.get(&id))
+        {
+            self.inline_editing = Some((id, node.content.clone()));
+        }
+    }
+
+    fn process_keyboard_new_child(&mut self, ui: &egui::Ui) {
+        if self.selected_nodes.len() != 1
+            || self.is_editing()
+            || self.is_linking_edge()
+            || self.is_dragging()
+            || !ui.input(|i| i.key_pressed(egui::Key::Tab) && i.modifiers.is_none())
+        {
+            return;
+        }
+        let Some(&from) = self.selected_nodes.first() else {
+            return;
+        };
+        let Some(snapshot) = self.graph.as_ref().map(|graph| graph.current_snapshot()) else {
+            return;
+        };
+        let Some(node) = snapshot.nodes.get(&from) else {
+            return;
+        };
+        let coor = graph::suggest_child_position(snapshot, from, node.distinct_type.child_type());
+        // 与拖出边一致，编辑窗口打开期间绘制连到新节点位置的边
+        self.edge_draft.start(from);
+        self.begin_pending_child(from, coor, Relation::Contain);
+        self.scroll_to(ui.ctx(), coor);
+    }
+
+    fn process_keyboard_save(&mut self, ui: &egui::Ui) {
+        if ui.input(|i| i.key_pressed(egui::Key::S) && i.modifiers.command) {
+            self.save();
+        }
+    }
+
+    /// 显式保存当前图谱，成功时显示提示，失败时弹出错误窗口
+    fn save(&mut self) {
+        if let Some(graph) = self.graph.as_mut() {
+            match graph.save() {
+                Ok(()) => self.notifications.info("保存成功"),
+                Err(e) => {
+                    self.error = Some((
+                        format!(
+                            "保存 {} 失败",
+                            graph.file_path.as_os_str().to_string_lossy()
+                        ),
+                        e.zh_message(),
+                    ))
+                }
+            }
+        }
+    }
+
+    /// 编辑、绘制边（包括选择关系的窗口打开时）或拖动期间不能撤销和恢复，
+    /// 以免删除这些操作正在引用的节点
+    fn can_undo_redo(&self) -> bool {
+        !self.is_editing() && !self.is_linking_edge() && !self.is_dragging()
+    }
+
+    /// 撤销，没有可撤销的操作时只显示警告。撤销后选中受影响的节点，并还原修改时的视口
+    fn undo(&mut self, ctx: &Context) {
+        if !self.can_undo_redo() {
+            return;
+        }
+        if let Some(graph) = self.graph.as_mut() {
+            let before = node_positions(graph);
+            let old = graph.current_snapshot().clone();
+            match graph.undo() {
+                Ok(()) => {
+                    let revision = graph.revision();
+                    self.notifications.info("撤销成功");
+                    let view = self.view_history.undo(revision, self.view_context());
+                    self.restore_view(ctx, &old, &before, view);
+                    self.start_transition(before);
+                }
+                Err(GraphError::NothingToUndo) => self.notifications.warning("没有可撤销的操作"),
+                Err(e) => self.error = Some(("撤销失败".to_string(), e.zh_message())),
+            }
+        }
+    }
+
+    /// 恢复，没有可恢复的操作时只显示警告。恢复后选中受影响的节点，并还原撤销前的视口
+    fn redo(&mut self, ctx: &Context) {
+        if !self.can_undo_redo() {
+            return;
+        }
+        if let Some(graph) = self.graph.as_mut() {
+            let before = node_positions(graph);
+            let old = graph.current_snapshot().clone();
+            match graph.redo() {
+                Ok(()) => {
+                    let revision = graph.revision();
+                    self.notifications.info("恢复成功");
+                    let view = self.view_history.redo(revision, self.view_context());
+                    self.restore_view(ctx, &old, &before, view);
+                    self.start_transition(before);
+                }
+                Err(GraphError::NothingToRedo) => self.notifications.warning("没有可恢复的操作"),
+                Err(e) => self.error = Some(("恢复失败".to_string(), e.zh_message())),
+            }
+        }
+    }
+
+    fn observe_view_history(&mut self) {
+        if let Some(graph) = self.graph.as_ref() {
+            let (revision, undo_len) = (graph.revision(), graph.undo_len());
+            self.view_history.observe(
+                revision,
+                undo_len,
+                self.view_context(),
+                graph.current_snapshot(),
+            );
+        }
+    }
+
+    /// 当前的选区和视口
+    fn view_context(&self) -> ViewContext {
+        ViewContext {
+            selection: self.selected_nodes.clone(),
+            scroll_offset: self.scroll_offset,
+            zoom: self.zoom_factor,
+            primary: BTreeSet::new(),
+        }
+    }
+
+    /// 撤销或恢复后，还原记录的视口，并选中相对 old 发生变化（含移动）的节点及变化的边的端点。
+    /// 撤销的记录带有仍存在的主要节点（如撤销删除后恢复的节点）时只选中主要节点；
+    /// 没有受影响的节点时还原记录的选区；第一个选中的节点不在窗口内时滚动到它
+    fn restore_view(
+        &mut self,
+        ctx: &Context,
+        old: &Snapshot,
+        positions: &HashMap<u64, (f64, f64)>,
+        view: Option<ViewContext>,
+    ) {
+        let Some(graph) = self.graph.as_ref() else {
+            return;
+        };
+        let snapshot = graph.current_snapshot();
+        let diff = old.diff(snapshot);
+        let mut affected = diff
+            .nodes
+            .keys()
+            .copied()
+            .chain(diff.edges.keys().flat_map(|(from, to)| [*from, *to]))
+            .chain(
+                snapshot
+                    .nodes
+                    .values()
+                    .filter(|node| {
+                        positions
+                            .get(&node.id)
+                            .is_some_and(|coor| *coor != node.coor)
+                    })
+                    .map(|node| node.id),
+            )
+            .filter(|id| snapshot.nodes.contains_key(id))
+            .collect::<BTreeSet<_>>();
+        if let Some(view) = view.as_ref() {
+            let primary = view
+                .primary
+                .iter()
+                .copied()
+                .filter(|id| snapshot.nodes.contains_key(id))
+                .collect::<BTreeSet<_>>();
+            if !primary.is_empty() {
+                affected = primary;
+            }
+        }
+        let first = affected.first().map(|id| snapshot.nodes[id].coor);
+
+        if let Some(view) = view {
+            self.zoom_factor = view.zoom;
+            self.scroll_offset = view.scroll_offset;
+            self.pending_scroll = Some(view.scroll_offset);
+            if affected.is_empty() {
+                self.selected_nodes = view
+                    .selection
+                    .into_iter()
+                    .filter(|id| snapshot.nodes.contains_key(id))
+                    .collect();
+            }
+        }
+        if !affected.is_empty() {
+            self.selected_nodes = affected;
+        }
+        self.selected_edges
+            .retain(|edge| snapshot.edges.contains_key(edge));
+
+        if let Some(coor) = first
+            && !ctx.screen_rect().contains(self.logical_to_screen(coor))
+        {
+            self.scroll_to(ctx, coor);
+        }
+    }
+
+    /// 开始或停止录制宏，停止时选择保存位置
+    fn toggle_recording(&mut self) {
+        let Some(graph) = self.graph.as_mut() else {
+            return;
+        };
+        if !graph.is_recording() {
+            graph.start_recording();
+            self.notifications.info("开始录制宏");
+            return;
+        }
+
+        let recorded = graph.stop_recording().unwrap_or_default();
+        if recorded.is_empty() {
+            self.notifications.warning("没有录制到任何操作");
+            return;
+        }
+        let Some(file) = rfd::FileDialog::new()
+            .add_filter("宏文件", &["json"])
+            .set_file_name("macro.json")
+            .save_file()
+        else {
+            return;
+        };
+        let result = recorded
+            .to_json()
+            .map_err(Error::from)
+            .and_then(|json| fs::write(&file, json).map_err(Error::from));
+        match result {
+            Ok(()) => self.notifications.info("宏已保存"),
+            Err(e) => {
+                self.error = Some((
+                    format!("保存 {} 失败", file.as_os_str().to_string_lossy()),
+                    e.zh_message(),
+                ))
+            }
+        }
+    }
+
+    /// 将节点模板列表导出为 JSON 文件，便于与他人共享
+    fn export_templates(&mut self) {
+        let Some(file) = rfd::FileDialog::new()
+            .add_filter("模板文件", &["json"])
+            .set_file_name("templates.json")
+            .save_file()
+        else {
+            return;
+        };
+        let result = NodeTemplate::list_to_json(&self.settings.templates)
+            .map_err(Error::from)
+            .and_then(|json| fs::write(&file, json).map_err(Error::from));
+        match result {
+            Ok(()) => self.notifications.info("模板已导出"),
+            Err(e) => {
+                self.error = Some((
+                    format!("导出 {} 失败", file.as_os_str().to_string_lossy()),
+                    e.zh_message(),
+                ))
+            }
+        }
+    }
+
+    /// 将本次打开文件以来的操作历史导出为操作日志格式的文件
+    fn export_history(&mut self) {
+        let Some(graph) = self.graph.as_ref() else {
+            return;
+        };
+        let Some(file) = rfd::FileDialog::new()
+            .add_filter("操作日志", &["journal"])
+            .set_file_name("history.journal")
+            .save_file()
+        else {
+            return;
+        };
+        match graph.export_history(&file) {
+            Ok(()) => self
+                .notifications
+                .info(format!("已导出 {} 条操作", graph.history_len())),
+            Err(e) => {
+                self.error = Some((
+                    format!("导出 {} 失败", file.as_os_str().to_string_lossy()),
+                    e.zh_message(),
+                ))
+            }
+        }
+    }
+
+    /// 从 JSON 文件导入节点模板，与已有模板同名时替换
+    fn import_templates(&mut self) {
+        let Some(file) = rfd::FileDialog::new()
+            .add_filter("模板文件", &["json"])
+            .pick_file()
+        else {
+            return;
+        };
+        let result = fs::read_to_string(&file)
+            .map_err(Error::from)
+            .and_then(|json| NodeTemplate::list_from_json(&json).map_err(Error::from));
+        match result {
+            Ok(templates) => {
+                let count = templates.len();
+                for template in templates {
+                    self.settings.save_template(template);
+                }
+                self.notifications.info(format!("已导入 {count} 个模板"));
+            }
+            Err(e) => self.error = Some(("导入模板失败".to_string(), e.zh_message())),
+        }
+    }
+
+    /// 将当前的图谱、视图文件、底图和节点模板导出为工作包
+    fn export_bundle(&mut self) {
+        let Some(graph) = self.graph.as_ref() else {
+            return;
+        };
+        let Some(file) = rfd::FileDialog::new()
+            .add_filter("工作包", &["zip"])
+            .set_file_name("工作包.zip")
+            .save_file()
+        else {
+            return;
+        };
+        let templates = if self.settings.templates.is_empty() {
+            None
+        } else {
+            match NodeTemplate::list_to_json(&self.settings.templates) {
+                Ok(json) => Some(json),
+                Err(e) => {
+                    self.error = Some(("导出工作包失败".to_string(), e.zh_message()));
+                    return;
+                }
+            }
+        };
+        let result = session::export_bundle(
+            &file,
+            &Session {
+                graph_path: &graph.file_path,
+                snapshot: graph.current_snapshot(),
+                view: Some(SessionView {
+                    zoom: self.zoom_factor,
+                    scroll_offset: self.scroll_offset.into(),
+                }),
+                templates: templates.as_deref(),
+            },
+        );
+        match result {
+            Ok(()) => self.notifications.info("工作包已导出"),
+            Err(e) => self.error = Some(("导出工作包失败".to_string(), e.zh_message())),
+        }
+    }
+
+    /// 选择工作包和解压的文件夹，打开其中的图谱，恢复视图并导入节点模板
+    fn import_bundle(&mut self) {
+        let Some(file) = rfd::FileDialog::new()
+            .add_filter("工作包", &["zip"])
+            .pick_file()
+        else {
+            return;
+        };
+        let Some(dir) = rfd::FileDialog::new()
+            .set_title("选择解压到的文件夹")
+            .pick_folder()
+        else {
+            return;
+        };
+        let imported = match session::import_bundle(&file, &dir) {
+            Ok(imported) => imported,
+            Err(e) => {
+                self.error = Some(("导入工作包失败".to_string(), e.zh_message()));
+                return;
+            }
+        };
+
+        self.open_file_now(imported.graph_path);
+        if self.graph.is_none() {
+            return;
+        }
+        if let Some(view) = imported.view {
+            self.zoom_factor = view.zoom;
+            self.scroll_offset = view.scroll_offset.into();
+            self.pending_scroll = Some(self.scroll_offset);
+        }
+        if let Some(json) = imported.templates {
+            match NodeTemplate::list_from_json(&json) {
+                Ok(templates) => {
+                    for template in templates {
+                        self.settings.save_template(template);
+                    }
+                }
+                Err(e) => self
+                    .notifications
+                    .warning(format!("工作包中的节点模板无法读取：{}", e.zh_message())),
+            }
+        }
+        if imported.missing.is_empty() {
+            self.notifications.info("工作包已导入");
+        } else {
+            self.notifications.warning(format!(
+                "工作包已导入，缺少 {}",
+                imported.missing.join("、")
+            ));
+        }
+    }
+
+    /// 选择宏文件并在当前图谱上回放
+    fn play_macro(&mut self) {
+        let Some(graph) = self.graph.as_mut() else {
+            return;
+        };
+        let Some(file) = rfd::FileDialog::new()
+            .add_filter("宏文件", &["json"])
+            .pick_file()
+        else {
+            return;
+        };
+
+        let result = fs::read_to_string(&file)
+            .map_err(Error::from)
+            .and_then(|json| Macro::from_json(&json).map_err(Error::from))
+            .and_then(|recorded| graph.play_macro(&recorded).map_err(Error::from));
+        match result {
+            Ok(_) => self.notifications.info("宏回放完成"),
+            Err(e) => self.error = Some(("回放宏失败".to_string(), e.zh_message())),
+        }
+    }
+
+    /// 将互相重叠的节点中较晚添加的移到附近的空闲位置，整体作为一次可撤回的操作
+    fn resolve_overlaps(&mut self) {
+        let Some(graph) = self.graph.as_mut() else {
+            return;
+        };
+        let moves = graph.current_snapshot().resolve_overlaps(graph::NODE_SIZE);
+        if moves.is_empty() {
+            self.notifications.info("没有重叠的节点");
+            return;
+        }
+
+        let before = node_positions(graph);
+        let count = moves.len();
+        let commands = moves
+            .into_iter()
+            .map(|(id, coor)| Command::UpdateEntityPosition { id, coor });
+        match graph.apply_batch(commands) {
+            Ok(_) => {
+                self.start_transition(before);
+                self.notifications.info(format!("已移动 {count} 个节点"));
+            }
+            Err(e) => self.error = Some(("解决重叠失败".to_string(), e.zh_message())),
+        }
+    }
+
+    /// 按设置的方向和间距分层排列所有节点，整体作为一次可撤回的操作
+    fn auto_layout(&mut self) {
+        let Some(graph) = self.graph.as_mut() else {
+            return;
+        };
+        let before = node_positions(graph);
+        match graph.auto_layout(&self.settings.layout) {
+            Ok(0) => self.notifications.info("节点已按当前设置排列"),
+            Ok(count) => {
+                self.start_transition(before);
+                self.notifications.info(format!("已移动 {count} 个节点"));
+            }
+            Err(e) => self.error = Some(("自动排版失败".to_string(), e.zh_message())),
+        }
+    }
+
+    /// 将离群节点移到当前视图的左上角排成一列并选中，整体作为一次可撤回的操作
+    fn recall_outliers(&mut self) {
+        let margin =
+            Vec2::new(NODE_SIZE.x, NODE_SIZE.y) * self.zoom_factor / 2.0 + Vec2::splat(20.0);
+        let origin = self.screen_to_logical(self.canvas_rect.left_top() + margin);
+        let Some(graph) = self.graph.as_mut() else {
+            return;
+        };
+        let before = node_positions(graph);
+        match graph.recall_outliers(graph::DEFAULT_OUTLIER_DISTANCE, origin) {
+            Ok(ids) if ids.is_empty() => self.notifications.info("没有离群节点"),
+            Ok(ids) => {
+                self.start_transition(before);
+                self.notifications
+                    .info(format!("已收回 {} 个离群节点", ids.len()));
+                self.selected_nodes = ids.into_iter().collect();
+            }
+            Err(e) => self.error = Some(("收回离群节点失败".to_string(), e.zh_message())),
+        }
+    }
+
+    /// 将所有节点等比压缩到图谱的坐标范围内，整体作为一次可撤回的操作
+    fn fit_to_bounds(&mut self) {
+        let Some(graph) = self.graph.as_mut() else {
+            return;
+        };
+        let before = node_positions(graph);
+        let bounds = graph.current_snapshot().metadata.coordinate_bounds();
+        match graph.fit_to_bounds(bounds) {
+            Ok(0) => self.notifications.info("所有节点都在画布范围内"),
+            Ok(count) => {
+                self.start_transition(before);
+                self.notifications.info(format!("已移动 {count} 个节点"));
+            }
+            Err(e) => self.error = Some(("压缩到画布范围失败".to_string(), e.zh_message())),
+        }
+    }
+
+    /// 紧凑化节点 ID，并同步更新界面中按 ID 记录的状态
+    fn compact_ids(&mut self) {
+        let Some(graph) = self.graph.as_mut() else {
+            return;
+        };
+        let revision = graph.revision();
+        let map = graph.compact_ids();
+        if graph.revision() == revision {
+            self.notifications.info("节点 ID 已经是连续的");
+            return;
+        }
+
+        // 选区随节点一起重新编号，动画中的位置以旧 ID 记录，直接结束
+        self.selected_nodes = self.selected_nodes.iter().map(|id| map[id]).collect();
+        self.selected_edges = self
+            .selected_edges
+            .iter()
+            .map(|(from, to)| (map[from], map[to]))
+            .collect();
+        self.focused_node = self.focused_node.map(|id| map[&id]);
+        self.folding.remap(&map);
+        self.transition = None;
+        let hotkeys = &mut self.view_sidecar.hotkeys;
+        hotkeys.retain(|_, id| map.contains_key(id));
+        hotkeys.values_mut().for_each(|id| *id = map[id]);
+        if let Some(graph) = self.graph.as_ref()
+            && let Err(e) = self.view_sidecar.save(&graph.file_path)
+        {
+            self.notifications
+                .warning(format!("保存视图设置失败：{}", e.zh_message()));
+        }
+        self.notifications.info("已重新编号节点 ID");
+    }
+
+    /// 自动修复图谱的一致性问题，并显示修复报告
+    fn auto_repair(&mut self) {
+        let Some(graph) = self.graph.as_mut() else {
+            return;
+        };
+        let report = graph.auto_repair();
+        if report.is_empty() {
+            self.notifications.info("没有发现需要修复的问题");
+            return;
+        }
+
+        // 被删除的边不再保持选中
+        let snapshot = graph.current_snapshot();
+        self.selected_edges
+            .retain(|edge| snapshot.edges.contains_key(edge));
+        self.repair_report = Some(report);
+    }
+
+    fn show_repair_report_window(&mut self, ctx: &Context) {
+        let Some(report) = self.repair_report else {
+            return;
+        };
+
+        egui::Window::new("修复报告")
+            .collapsible(false)
+            .resizable(false)
+            .anchor(Align2::CENTER_CENTER, [0.0, 0.0])
+            .show(ctx, |ui| {
+                egui::Grid::new("修复报告").show(ui, |ui| {
+                    for (item, count) in report.items() {
+                        if count > 0 {
+                            ui.label(item);
+                            ui.label(format!("{count} 处"));
+                            ui.end_row();
+                        }
+                    }
+                });
+                ui.label("修复可以撤销");
+                if ui.button("关闭").clicked() {
+                    self.repair_report = None;
+                }
+            });
+    }
+
+    /// 删除节点。节点既有父节点又有子节点时，先弹出窗口选择如何处理子节点
+    fn remove_node(&mut self, id: u64) {
+        let Some(graph) = self.graph.as_mut() else {
+            return;
+        };
+        let snapshot = graph.current_snapshot();
+        if !snapshot.parents(id).is_empty() && !snapshot.children(id).is_empty() {
+            self.removing_node = Some(id);
+            return;
+        }
+        dialog_error!(self, graph.remove_entity(id), &[], "删除节点失败");
+        self.selected_nodes.remove(&id);
+    }
+
+    /// 选择删除节点时如何处理子节点
+    fn show_remove_node_window(&mut self, ctx: &Context) {
+        let (Some(id), Some(graph)) = (self.removing_node, self.graph.as_mut()) else {
+            return;
+        };
+        if !graph.current_snapshot().nodes.contains_key(&id) {
+            self.removing_node = None;
+            return;
+        }
+
+        let mut policy = None;
+        let mut cancel = false;
+        egui::Window::new("删除节点")
+            .collapsible(false)
+            .resizable(false)
+            .anchor(Align2::CENTER_CENTER, [0.0, 0.0])
+            .show(ctx, |ui| {
+                ui.label("该节点包含子节点，删除后如何处理这些子节点？");
+                if ui
+                    .button("与图谱断开")
+                    .on_hover_text("只删除该节点及其边")
+                    .clicked()
+                {
+                    policy = Some(RemovePolicy::Detach);
+                }
+                if ui
+                    .button("改由上级节点包含")
+                    .on_hover_text("子节点改由该节点的各个父节点包含，已有的边保持不变")
+                    .clicked()
+                {
+                    policy = Some(RemovePolicy::ReattachToParents);
+                }
+                if ui
+                    .button("一并删除")
+                    .on_hover_text("同时删除该节点直接或间接包含的所有节点")
+                    .clicked()
+                {
+                    policy = Some(RemovePolicy::Cascade);
+                }
+                cancel =
+                    ui.button("取消").clicked() || ui.input(|i| i.key_pressed(egui::Key::Escape));
+            });
+
+        if let Some(policy) = policy {
+            self.removing_node = None;
+            dialog_error!(
+                self,
+                graph.remove_entity_with_policy(id, policy),
+                &[],
+                "删除节点失败"
+            );
+            let nodes = &graph.current_snapshot().nodes;
+            self.selected_nodes.retain(|id| nodes.contains_key(id));
+        } else if cancel {
+            self.removing_node = None;
+        }
+    }
+
+    /// 规范化内容的选项窗口，作用于选中的节点，未选中节点时作用于全部节点
+    fn show_normalize_window(&mut self, ctx: &Context) {
+        let (Some(mut options), Some(graph)) = (self.normalizing, self.graph.as_mut()) else {
+            return;
+        };
+
+        let mut confirm = false;
+        let mut cancel = false;
+        egui::Window::new("规范化内容")
+            .collapsible(false)
+            .resizable(false)
+            .anchor(Align2::CENTER_CENTER, [0.0, 0.0])
+            .show(ctx, |ui| {
+                ui.label(if self.selected_nodes.is_empty() {
+                    "作用于全部节点".to_string()
+                } else {
+                    format!("作用于选中的 {} 个节点", self.selected_nodes.len())
+                });
+                ui.label("去掉首尾的空白，并将连续的空白合并为一个");
+                ui.checkbox(&mut options.halfwidth, "全角字母、数字和标点转为半角");
+                ui.horizontal(|ui| {
+                    confirm = ui.button("确定").clicked();
+                    cancel = ui.button("取消").clicked()
+                        || ui.input(|i| i.key_pressed(egui::Key::Escape));
+                });
+            });
+        self.normalizing = Some(options);
+
+        if confirm {
+            self.normalizing = None;
+            let ids = if self.selected_nodes.is_empty() {
+                graph.current_snapshot().nodes.keys().copied().collect()
+            } else {
+                self.selected_nodes.clone()
+            };
+            match graph.normalize_contents(&ids, options) {
+                Ok(0) => self.notifications.info("内容都已规范"),
+                Ok(count) => self.notifications.info(format!("已规范化 {count} 个节点")),
+                Err(e) => self.error = Some(("规范化内容失败".to_string(), e.zh_message())),
+            }
+        } else if cancel {
+            self.normalizing = None;
+        }
+    }
+
+    /// 重新读取磁盘上的文件，选中发生变化的节点
+    fn reload(&mut self, ctx: &Context) {
+        let Some(graph) = self.graph.as_mut() else {
+            return;
+        };
+        let before = node_positions(graph);
+        let old = graph.current_snapshot().clone();
+        let revision = graph.revision();
+        if let Err(e) = graph.reload() {
+            self.error = Some((
+                format!(
+                    "重新载入 {} 失败",
+                    graph.file_path.as_os_str().to_string_lossy()
+                ),
+                e.zh_message(),
+            ));
+            return;
+        }
+        if graph.revision() == revision {
+            self.notifications.info("文件没有变化");
+            return;
+        }
+
+        // 不再存在的节点、边和分组框不再选中
+        let snapshot = graph.current_snapshot();
+        self.selected_nodes
+            .retain(|id| snapshot.nodes.contains_key(id));
+        self.selected_edges
+            .retain(|edge| snapshot.edges.contains_key(edge));
+        self.selected_group = self
+            .selected_group
+            .filter(|id| snapshot.group(*id).is_some());
+        self.notifications.info("已重新载入，可撤销找回之前的内容");
+        self.restore_view(ctx, &old, &before, None);
+        self.start_transition(before);
+    }
+
+    /// 图谱变化后，为位置发生变化的节点启动过渡动画。
+    /// before 为变化前各节点的位置。若上一个动画尚未结束，则从其当前位置开始。
+    fn start_transition(&mut self, before: HashMap<u64, (f64, f64)>) {
+        let Some(graph) = self.graph.as_ref() else {
+            return;
+        };
+        if !self.settings.animations {
+            self.transition = None;
+            return;
+        }
+
+        let from = graph
+            .current_snapshot()
+            .nodes
+            .values()
+            .filter_map(|node| {
+                let old = *before.get(&node.id)?;
+                let from = self
+                    .transition
+                    .as_ref()
+                    .map_or(old, |t| t.position(node.id, old));
+                (from != node.coor).then_some((node.id, from))
+            })
+            .collect::<HashMap<_, _>>();
+
+        self.transition = (!from.is_empty())
+            .then(|| Transition::new(from, graph.revision(), time::Instant::now()));
+    }
+
+    /// 推进次序边的流向动画，只在开启且存在次序边时持续刷新
+    fn update_flow_phase(&mut self, ctx: &Context) {
+        if !self.settings.flow_animation {
+            return;
+        }
+        let has_order_edge = self.graph.as_ref().is_some_and(|graph| {
+            graph
+                .current_snapshot()
+                .edges
+                .values()
+                .any(|relation| *relation == Relation::Order)
+        });
+        if !has_order_edge {
+            return;
+        }
+
+        let time = ctx.input(|i| i.time);
+        self.flow_phase = (time as f32 * FLOW_DOT_SPEED) % FLOW_DOT_SPACING;
+        ctx.request_repaint();
+    }
+
+    /// 推进过渡动画，动画结束或图谱再次变化时停止
+    fn update_transition(&mut self, ctx: &Context) {
+        let Some(transition) = self.transition.as_mut() else {
+            return;
+        };
+
+        let outdated = self
+            .graph
+            .as_ref()
+            .is_none_or(|graph| graph.revision() != transition.revision());
+        if outdated || !transition.tick(time::Instant::now()) {
+            self.transition = None;
+        } else {
+            ctx.request_repaint();
+        }
+    }
+
+    fn process_autosave_results(&mut self) {
+        let Some(graph) = self.graph.as_mut() else {
+            return;
+        };
+
+        if let Some(e) = graph.take_journal_error() {
+            self.error = Some(("写入日志失败，已停止记录".to_string(), e.zh_message()));
+        }
+
+        for result in graph.take_autosave_results() {
+            match result {
+                Ok(()) => self.notifications.info("已自动保存"),
+                Err(e) => {
+                    self.error = Some((
+                        format!(
+                            "自动保存 {} 失败",
+                            graph.file_path.as_os_str().to_string_lossy()
+                        ),
+                        e.zh_message(),
+                    ))
+                }
+            }
+        }
+    }
+
+    fn process_paste(&mut self, ui: &egui::Ui) {
+        if self.graph.is_none() || self.is_editing() || self.is_linking_edge() || self.is_dragging()
+        {
+            return;
+        }
+
+        // 剪贴板中的文本通过 egui 的粘贴事件传入
+        let pasted = ui.input(|i| {
+            i.events.iter().find_map(|event| match event {
+                egui::Event::Paste(text) => Some(text.clone()),
+                _ => None,
+            })
+        });
+
+        if let Some(text) = pasted {
+            let lines = split_pasted_text(&text);
+            // 只处理多行文本
+            if lines.len() > 1 {
+                let pos = ui
+                    .input(|i| i.pointer.latest_pos())
+                    .unwrap_or(ui.ctx().screen_rect().center());
+                self.pasting_lines = Some(lines);
+                self.pasting_origin = self.screen_to_logical(pos);
+            }
+        }
+    }
+
+    fn process_undo_redo(&mut self, ui: &egui::Ui) {
+        if self.graph.is_none() {
+            return;
+        }
+
+        // 检测撤销
+        if ui.input(|i| i.key_pressed(egui::Key::Z) && i.modifiers.command) {
+            self.undo(ui.ctx());
+        }
+
+        // 检测重做
+        if ui.input(|i| i.key_pressed(egui::Key::Y) && i.modifiers.command) {
+            self.redo(ui.ctx());
+        }
+    }
+
+    fn show_selected_node(&self, painter: &Painter) {
+        if self.graph.is_none() {
+            return;
+        }
+
+        // 只在未拖动节点且未进入编辑时绘制
+        if !self.is_dragging() && !self.is_editing() {
+            let snapshot = self.graph.as_ref().unwrap().current_snapshot();
+            for selected_node in self.selected_nodes.iter() {
+                if let Some(node) = snapshot.nodes.get(selected_node) {
+                    let pos = self.node_draw_pos(node);
+                    let size =
+                        Vec2::new(NODE_SIZE.x, NODE_SIZE.y) * self.zoom_factor + Vec2::splat(3.0);
+                    let rect = Rect::from_center_size(pos, size);
+                    let corner_radius = 10.0;
+
+                    // 绘制边框
+                    painter.rect_stroke(
+                        rect,
+                        corner_radius,
+                        Stroke::new(6.0, Color32::RED),
+                        egui::StrokeKind::Outside,
+                    );
+                }
+            }
+        }
+    }
+
+    fn show_selected_edges(&self, painter: &Painter) {
+        let Some(graph) = self.graph.as_ref() else {
+            return;
+        };
+
+        // 只在未拖动节点且未进入编辑时绘制
+        if self.is_dragging() || self.is_editing() || self.is_linking_edge() {
+            return;
+        }
+        let snapshot = graph.current_snapshot();
+        for (from, to) in self.selected_edges.iter() {
+            if let (Some(from_node), Some(to_node)) =
+                (snapshot.nodes.get(from), snapshot.nodes.get(to))
+                && let Some(relation) = snapshot.edges.get(&(*from, *to))
+            {
+                // 绘制边及其折点的拖动柄
+                let waypoints = snapshot.waypoints(*from, *to);
+                self.draw_edge(
+                    painter,
+                    from_node,
+                    to_node,
+                    waypoints,
+                    *relation,
+                    Stroke::new(6.0, Color32::RED),
+                );
+                for coor in waypoints {
+                    draw_waypoint_handle(painter, self.logical_to_screen(*coor));
+                }
+
+                // 绘制边连接的节点
+                for node in [from_node, to_node] {
+                    self.draw_node(painter, node, 2.0);
+                }
+            }
+        }
+    }
+
+    /// 绘制拖动中的折点及其所在的边
+    fn show_dragging_waypoint(&self, painter: &Painter) {
+        let (Some(graph), Some(((from, to), index))) =
+            (self.graph.as_ref(), self.dragging_waypoint)
+        else {
+            return;
+        };
+        let snapshot = graph.current_snapshot();
+        let (Some(from_node), Some(to_node), Some(relation)) = (
+            snapshot.nodes.get(&from),
+            snapshot.nodes.get(&to),
+            snapshot.edges.get(&(from, to)),
+        ) else {
+            return;
+        };
+
+        let offset = self.dragging_offset / self.zoom_factor;
+        let mut waypoints = snapshot.waypoints(from, to).to_vec();
+        if let Some(point) = waypoints.get_mut(index) {
+            *point = (point.0 + offset.x as f64, point.1 + offset.y as f64);
+        }
+        let mut ghost = painter.clone();
+        ghost.multiply_opacity(0.6);
+        self.draw_edge(
+            &ghost,
+            from_node,
+            to_node,
+            &waypoints,
+            *relation,
+            Stroke::new(6.0, Color32::RED),
+        );
+        for coor in waypoints.iter() {
+            draw_waypoint_handle(painter, self.logical_to_screen(*coor));
+        }
+    }
+
+    /// 边上位于屏幕位置 pos 处的折点的序号
+    fn waypoint_index_at(&self, (from, to): (u64, u64), pos: Pos2) -> Option<usize> {
+        let snapshot = self.graph.as_ref()?.current_snapshot();
+        snapshot.waypoints(from, to).iter().position(|coor| {
+            self.logical_to_screen(*coor).distance(pos) <= WAYPOINT_HANDLE_RADIUS + 2.0
+        })
+    }
+
+    /// 选中的边中位于 pos 处的折点，返回所在的边及其序号
+    fn waypoint_at(&self, pos: Pos2) -> Option<((u64, u64), usize)> {
+        self.selected_edges
+            .iter()
+            .find_map(|edge| Some((*edge, self.waypoint_index_at(*edge, pos)?)))
+    }
+
+    /// 打开边的编辑窗口
+    fn begin_edit_edge(&mut self, (from, to): (u64, u64)) {
+        let Some(graph) = self.graph.as_ref() else {
+            return;
+        };
+        self.editing_edge_label = graph
+            .current_snapshot()
+            .edge_labels
+            .get(&(from, to))
+            .cloned()
+            .unwrap_or_default();
+        self.editing_edge = Some((from, to));
+    }
+
+    /// 在边上屏幕位置 pos 处添加折点并选中该边，作为一次可撤回的操作
+    fn add_waypoint(&mut self, (from, to): (u64, u64), pos: Pos2) {
+        let coor = snap_to_grid(self.screen_to_logical(pos));
+        let Some(graph) = self.graph.as_mut() else {
+            return;
+        };
+        if let Some(waypoints) = graph
+            .current_snapshot()
+            .waypoints_with_inserted(from, to, coor)
+        {
+            dialog_error!(
+                self,
+                graph.set_edge_waypoints(from, to, waypoints),
+                &[],
+                "添加折点失败"
+            );
+        }
+        self.selected_nodes.clear();
+        self.selected_edges = BTreeSet::from([(from, to)]);
+    }
+
+    /// 删除边的第 index 个折点，index 为 None 时删除全部折点，作为一次可撤回的操作
+    fn remove_waypoint(&mut self, (from, to): (u64, u64), index: Option<usize>) {
+        let Some(graph) = self.graph.as_mut() else {
+            return;
+        };
+        let mut waypoints = graph.current_snapshot().waypoints(from, to).to_vec();
+        match index {
+            Some(index) if index < waypoints.len() => {
+                waypoints.remove(index);
+            }
+            Some(_) => return,
+            None => waypoints.clear(),
+        }
+        dialog_error!(
+            self,
+            graph.set_edge_waypoints(from, to, waypoints),
+            &[],
+            "删除折点失败"
+        );
+    }
+
+    /// 选中多条边时显示批量操作窗口，删除或修改关系作为一次操作
+    fn show_edge_batch_window(&mut self, ctx: &Context) {
+        if self.selected_edges.len() < 2 || self.is_editing() {
+            return;
+        }
+
+        let mut command = None::<Box<dyn Fn(u64, u64) -> Command>>;
+        egui::Window::new("批量修改边")
+            .collapsible(false)
+            .resizable(false)
+            .anchor(Align2::RIGHT_BOTTOM, [-10.0, -40.0])
+            .show(ctx, |ui| {
+                ui.label(format!("已选 {} 条边", self.selected_edges.len()));
+                ui.horizontal(|ui| {
+                    for relation in relation_registry().relations() {
+                        if ui.button(format!("改为{}关系", relation.label())).clicked() {
+                            command = Some(Box::new(move |from, to| Command::UpdateEdge {
+                                from,
+                                to,
+                                relation,
+                            }));
+                        }
+                    }
+                    if ui.button("删除").clicked() {
+                        command = Some(Box::new(|from, to| Command::RemoveEdge { from, to }));
+                    }
+                    if ui.button("取消选择").clicked() {
+                        self.selected_edges.clear();
+                    }
+                });
+            });
+
+        if let Some(command) = command
+            && let Some(graph) = self.graph.as_mut()
+        {
+            let commands = self
+                .selected_edges
+                .iter()
+                .map(|&(from, to)| command(from, to));
+            dialog_error!(self, graph.apply_batch(commands), &[], "批量修改边失败");
+            let snapshot = graph.current_snapshot();
+            self.selected_edges
+                .retain(|edge| snapshot.edges.contains_key(edge));
+        }
+    }
+
+    /// 按 F 键按无标记、待办、疑问、已审核的顺序循环切换选中节点的评审标记，
+    /// 以 ID 最小的选中节点的标记为准，多个节点作为一次操作修改
+    fn process_keyboard_flag(&mut self, ui: &egui::Ui) {
+        if self.selected_nodes.is_empty()
+            || self.is_editing()
+            || self.is_linking_edge()
+            || ui.ctx().wants_keyboard_input()
+            || !ui.input(|i| i.key_pressed(egui::Key::F) && i.modifiers.is_none())
+        {
+            return;
+        }
+        let Some(graph) = self.graph.as_ref() else {
+            return;
+        };
+        let current = self
+            .selected_nodes
+            .first()
+            .and_then(|id| graph.current_snapshot().nodes.get(id))
+            .and_then(|node| node.flag);
+        let ids = self.selected_nodes.iter().copied().collect::<Vec<_>>();
+        self.set_flags(&ids, Flag::cycle(current));
+    }
+
+    /// 设置节点的评审标记，多个节点作为一次操作修改
+    fn set_flags(&mut self, ids: &[u64], flag: Option<Flag>) {
+        let Some(graph) = self.graph.as_mut() else {
+            return;
+        };
+        let result = graph.set_flags(ids, flag);
+        dialog_error!(self, result, &[], "设置评审标记失败");
+    }
+
+    fn set_primary_parent(&mut self, child: u64, parent: u64) {
+        let Some(graph) = self.graph.as_mut() else {
+            return;
+        };
+        let result = graph.set_primary_parent(child, parent);
+        dialog_error!(self, result, &[], "设置主父节点失败");
+    }
+
+    /// 选中节点的边，outgoing 为 true 时只选中出边
+    fn select_edges_of(&mut self, id: u64, outgoing: bool) {
+        let Some(graph) = self.graph.as_ref() else {
+            return;
+        };
+        self.selected_nodes.clear();
+        self.selected_edges = graph
+            .current_snapshot()
+            .edges
+            .keys()
+            .filter(|(from, to)| *from == id || (!outgoing && *to == id))
+            .copied()
+            .collect();
+    }
+
+    fn show_dragging_node(&self, painter: &Painter) {
+        if self.graph.is_none() {
+            return;
+        }
+
+        if let Some(dragging_node) = self.dragging_node
+            && !self.is_editing()
+            && !self.is_linking_edge()
+            && let Some(node) = self
+                .graph
+                .as_ref()
+                .unwrap()
+                .current_snapshot()
+                .nodes
+                .get(&dragging_node)
+        {
+            let pos = self.node_draw_pos(node) + self.dragging_offset;
+            let size = Vec2::new(NODE_SIZE.x, NODE_SIZE.y) * self.zoom_factor;
+            let rect = Rect::from_center_size(pos, size);
+            let corner_radius = 10.0;
+
+            // 绘制填充矩形
+            let mut color = self.theme.rect_color(node.distinct_type);
+            color[3] = 200; // 设置透明度
+            painter.rect_filled(rect, corner_radius, color);
+            self.theme
+                .draw_type_mark(painter, rect, node.distinct_type, self.zoom_factor);
+
+            // 绘制边框
+            painter.rect_stroke(
+                rect,
+                corner_radius,
+                Stroke::new(2.0, Color32::from_rgb(54, 131, 248)),
+                egui::StrokeKind::Outside,
+            );
+        }
+    }
+
+    /// 拖动分组框时绘制移动或调整大小后的分组框，移动时一并绘制其中节点的轮廓
+    fn show_dragging_group(&self, painter: &Painter) {
+        let (Some(graph), Some((id, hit))) = (self.graph.as_ref(), self.dragging_group) else {
+            return;
+        };
+        let snapshot = graph.current_snapshot();
+        let Some(group) = snapshot.group(id) else {
+            return;
+        };
+
+        let mut ghost = painter.clone();
+        ghost.multiply_opacity(0.6);
+        match hit {
+            GroupHit::Frame => {
+                let rect = self
+                    .group_screen_rect(group)
+                    .translate(self.dragging_offset);
+                self.draw_group(&ghost, group, rect, true);
+                let size = Vec2::new(NODE_SIZE.x, NODE_SIZE.y) * self.zoom_factor;
+                for node in snapshot
+                    .nodes_in_group(id)
+                    .iter()
+                    .map(|id| &snapshot.nodes[id])
+                {
+                    let pos = self.node_draw_pos(node) + self.dragging_offset;
+                    ghost.rect_stroke(
+                        Rect::from_center_size(pos, size),
+                        NODE_CORNER_RADIUS,
+                        Stroke::new(2.0, Color32::from_rgb(54, 131, 248)),
+                        egui::StrokeKind::Outside,
+                    );
+                }
+            }
+            GroupHit::Resize => {
+                let offset = self.dragging_offset / self.zoom_factor;
+                let resized = group::resized(group.rect, (offset.x as f64, offset.y as f64));
+                let rect = Rect::from_min_max(
+                    self.logical_to_screen(resized.min),
+                    self.logical_to_screen(resized.max),
+                );
+                self.draw_group(&ghost, group, rect, true);
+            }
+        }
+    }
+
+    fn show_hovered_node(&self, painter: &Painter) {
+        if self.graph.is_none() {
+            return;
+        }
+
+        if let Some((hovered_node, is_center_hovered)) = self.hovered_node
+            && !self.is_dragging()
+            && !self.is_editing()
+        {
+            let snapshot = self.graph.as_ref().unwrap().current_snapshot();
+            if let Some(node) = snapshot.nodes.get(&hovered_node) {
+                let pos = self.node_draw_pos(node);
+                let size =
+                    Vec2::new(NODE_SIZE.x, NODE_SIZE.y) * self.zoom_factor + Vec2::splat(3.0);
+                let rect = Rect::from_center_size(pos, size);
+                let corner_radius = 10.0;
+
+                // 绘制边框
+                painter.rect_stroke(
+                    rect,
+                    corner_radius,
+                    Stroke::new(4.0, Color32::from_gray(200)),
+                    egui::StrokeKind::Outside,
+                );
+
+                // 绘制中心点
+                if !self.is_linking_edge() {
+                    if is_center_hovered {
+                        painter.circle(pos, 4.0, Color32::WHITE, Stroke::new(4.0, Color32::GRAY));
+                    } else {
+                        painter.circle(pos, 4.0, Color32::WHITE, Stroke::new(2.0, Color32::GRAY));
+                    }
+                }
+            }
+        }
+    }
+
+    fn show_hovered_edge(&self, painter: &Painter) {
+        if self.graph.is_none() {
+            return;
+        }
+
+        if let Some((from, to)) = self.hovered_edge
+            && !self.is_dragging()
+            && !self.is_editing()
+            && !self.is_linking_edge()
+        {
+            let snapshot = self.graph.as_ref().unwrap().current_snapshot();
+            if let (Some(from_node), Some(to_node)) =
+                (snapshot.nodes.get(&from), snapshot.nodes.get(&to))
+                && let Some(relation) = snapshot.edges.get(&(from, to))
+            {
+                // 绘制边
+                self.draw_edge(
+                    painter,
+                    from_node,
+                    to_node,
+                    snapshot.waypoints(from, to),
+                    *relation,
+                    Stroke::new(4.0, Color32::from_gray(200)),
+                );
+
+                // 绘制边连接的节点
+                for node in [from_node, to_node] {
+                    self.draw_node(painter, node, 2.0);
+                }
+            }
+        }
+    }
+
+    fn show_drawing_edge(&mut self, ui: &egui::Ui, painter: &Painter, ctx: &Context) {
+        if self.graph.is_none() {
+            return;
+        }
+
+        // 端点可能已被删除（如在流程中撤销），此时结束流程
+        let snapshot = self.graph.as_ref().unwrap().current_snapshot();
+        if !self
+            .edge_draft
+            .retain_endpoints(|id| snapshot.nodes.contains_key(&id))
+        {
+            self.cancel_pending_child();
+            self.notifications.info("边的端点已被删除，已取消添加边");
+            return;
+        }
+
+        match self.edge_draft {
+            EdgeDraft::Idle => {}
+            EdgeDraft::Choosing { from, to } => self.show_relation_window(ctx, from, to),
+            EdgeDraft::Dragging { from } => {
+                // 绘制正在绘制的边，待新建节点时连到新节点的位置，直到编辑窗口关闭
+                if let Some(from_node) = snapshot.nodes.get(&from) {
+                    let start = self.node_draw_pos(from_node);
+                    let end = match self.pending_child {
+                        Some(pending) => Some(self.logical_to_screen(pending.coor)),
+                        None => ui.input(|i| i.pointer.interact_pos()),
+                    };
+                    if let Some(end) = end {
+                        painter.line_segment([start, end], Stroke::new(2.0, Color32::BLACK));
+                    }
+                }
+            }
+        }
+    }
+
+    fn show_paste_window(&mut self, ctx: &Context) {
+        let Some(count) = self.pasting_lines.as_ref().map(Vec::len) else {
+            return;
+        };
+
+        let mut confirmed = false;
+        let mut cancelled = false;
+        egui::Window::new("粘贴为节点")
+            .collapsible(false)
+            .resizable(false)
+            .anchor(Align2::CENTER_CENTER, [0.0, 0.0])
+            .show(ctx, |ui| {
+                ui.label(format!("将创建 {count} 个节点，选择节点类型:"));
+                ui.horizontal(|ui| {
+                    distinct_type_radios(ui, &mut self.last_distinct_type);
+                });
+
+                ui.horizontal(|ui| {
+                    confirmed = ui.button("确定").clicked();
+                    cancelled = ui.button("取消").clicked();
+                });
+            });
+
+        if confirmed && let Some(lines) = self.pasting_lines.take() {
+            let origin = self.pasting_origin;
+            // 从起始位置开始纵向排列，作为一次操作添加
+            let nodes = lines
+                .into_iter()
+                .enumerate()
+                .map(|(i, (content, notes))| {
+                    let mut node = EntityNode::new(
+                        0,
+                        content,
+                        self.last_distinct_type,
+                        &[],
+                        (origin.0, origin.1 + i as f64 * PASTE_NODE_SPACING),
+                    );
+                    node.notes = notes;
+                    node
+                })
+                .collect::<Vec<_>>();
+            if let Some(graph) = self.graph.as_mut() {
+                let result = graph.add_entities(nodes);
+                dialog_error!(self, result, &[], "粘贴失败");
+            }
+        } else if cancelled {
+            self.pasting_lines = None;
+        }
+    }
+
+    fn show_children_order_window(&mut self, ctx: &Context) {
+        let (Some((parent, children)), Some(graph)) =
+            (self.ordering_children.as_mut(), self.graph.as_ref())
+        else {
+            return;
+        };
+
+        let snapshot = graph.current_snapshot();
+        let mut confirmed = false;
+        let mut cancelled = false;
+        egui::Window::new("调整子节点顺序")
+            .collapsible(false)
+            .resizable(false)
+            .anchor(Align2::CENTER_CENTER, [0.0, 0.0])
+            .show(ctx, |ui| {
+                ui.label("对接平台按此顺序显示子节点:");
+                let count = children.len();
+                let mut swap = None;
+                egui::Grid::new("子节点顺序").striped(true).show(ui, |ui| {
+                    for (i, id) in children.iter().enumerate() {
+                        ui.label(format!("{}.", i + 1));
+                        let content = snapshot.nodes.get(id).map_or("", |n| n.content.as_str());
+                        ui.label(content);
+                        if ui.add_enabled(i > 0, egui::Button::new("⬆")).clicked() {
+                            swap = Some(i - 1);
+                        }
+                        if ui
+                            .add_enabled(i + 1 < count, egui::Button::new("⬇"))
+                            .clicked()
+                        {
+                            swap = Some(i);
+                        }
+                        ui.end_row();
+                    }
+                });
+                if let Some(i) = swap {
+                    children.swap(i, i + 1);
+                }
+
+                ui.horizontal(|ui| {
+                    confirmed = ui.button("确定").clicked();
+                    cancelled = ui.button("取消").clicked();
+                });
+            });
+        let parent = *parent;
+
+        if confirmed && let Some((_, children)) = self.ordering_children.take() {
+            if let Some(graph) = self.graph.as_mut() {
+                let result = graph.set_children_order(parent, &children);
+                dialog_error!(self, result, &[], "调整子节点顺序失败");
+            }
+        } else if cancelled {
+            self.ordering_children = None;
+        }
+    }
+
+    /// 列出双向边并打开选择窗口，默认按层级选择保留的边，没有双向边时只提示
+    fn begin_normalize_edges(&mut self) {
+        let Some(graph) = self.graph.as_ref() else {
+            return;
+        };
+        let edges = graph.current_snapshot().reciprocal_edges();
+        if edges.is_empty() {
+            self.notifications.info("没有双向边");
+            return;
+        }
+        self.normalizing_edges = Some(
+            edges
+                .into_iter()
+                .map(|edge| (edge, edge.suggested))
+                .collect(),
+        );
+    }
+
+    /// 打开缩放坐标的窗口，系数预填为按排版设置的间距估算的值，无法估算时为 1
+    fn begin_scale_coordinates(&mut self) {
+        let Some(graph) = self.graph.as_ref() else {
+            return;
+        };
+        let factor = graph
+            .current_snapshot()
+            .estimate_coordinate_scale(self.settings.layout.node_spacing)
+            .unwrap_or(1.0);
+        self.scaling_coordinates = Some(factor);
+    }
+
+    /// 缩放坐标的窗口，确定后将所有坐标乘以系数，整体作为一次可撤回的操作
+    fn show_scale_coordinates_window(&mut self, ctx: &Context) {
+        let (Some(factor), Some(graph)) = (self.scaling_coordinates.as_mut(), self.graph.as_ref())
+        else {
+            return;
+        };
+
+        let mut confirmed = false;
+        let mut cancelled = false;
+        egui::Window::new("缩放坐标")
+            .collapsible(false)
+            .resizable(false)
+            .anchor(Align2::CENTER_CENTER, [0.0, 0.0])
+            .show(ctx, |ui| {
+                ui.label("将所有节点的坐标和分组框乘以系数，用于换算其他系统的坐标单位");
+                ui.horizontal(|ui| {
+                    ui.label("系数:");
+                    ui.add(
+                        egui::DragValue::new(factor)
+                            .range(0.001..=1000.0)
+                            .speed(0.01)
+                            .max_decimals(3),
+                    );
+                    let spacing = self.settings.layout.node_spacing;
+                    if ui
+                        .button("按间距估算")
+                        .on_hover_text(format!(
+                            "使节点与最近的节点的距离大多接近排版设置的节点间距 {spacing}"
+                        ))
+                        .clicked()
+                        && let Some(estimated) =
+                            graph.current_snapshot().estimate_coordinate_scale(spacing)
+                    {
+                        *factor = estimated;
+                    }
+                });
+                ui.horizontal(|ui| {
+                    confirmed = ui.button("确定").clicked();
+                    cancelled = ui.button("取消").clicked();
+                });
+            });
+
+        if confirmed && let Some(factor) = self.scaling_coordinates.take() {
+            let Some(graph) = self.graph.as_mut() else {
+                return;
+            };
+            let before = node_positions(graph);
+            match graph.scale_coordinates(factor) {
+                Ok(0) => {}
+                Ok(count) => {
+                    self.start_transition(before);
+                    self.notifications.info(format!("已移动 {count} 个节点"));
+                }
+                Err(e) => self.error = Some(("缩放坐标失败".to_string(), e.zh_message())),
+            }
+        } else if cancelled {
+            self.scaling_coordinates = None;
+        }
+    }
+
+    /// 双向边的选择窗口，每对节点选择保留的方向，确定后整体作为一次可撤回的操作删除另一条
+    fn show_normalize_edges_window(&mut self, ctx: &Context) {
+        let (Some(edges), Some(graph)) = (self.normalizing_edges.as_mut(), self.graph.as_ref())
+        else {
+            return;
+        };
+
+        let snapshot = graph.current_snapshot();
+        let content = |id: u64| {
+            let content = snapshot.nodes.get(&id).map_or("", |n| n.content.as_str());
+            match content.char_indices().nth(BREADCRUMB_CONTENT_LIMIT) {
+                Some((index, _)) => format!("{}…", &content[..index]),
+                None => content.to_string(),
+            }
+        };
+        let mut confirmed = false;
+        let mut cancelled = false;
+        egui::Window::new("规范化双向边")
+            .collapsible(false)
+            .resizable(false)
+            .anchor(Align2::CENTER_CENTER, [0.0, 0.0])
+            .show(ctx, |ui| {
+                ui.label("以下节点之间两个方向都有边，选择保留的一条:");
+                egui::ScrollArea::vertical()
+                    .max_height(400.0)
+                    .show(ui, |ui| {
+                        egui::Grid::new("双向边").striped(true).show(ui, |ui| {
+                            for (edge, keep) in edges.iter_mut() {
+                                let (a, b) = edge.forward;
+                                let kind = if edge.is_conflict() {
+                                    "层级矛盾"
+                                } else {
+                                    "关系重复"
+                                };
+                                ui.colored_label(issue_color(IssueLevel::Warning), kind);
+                                ui.radio_value(
+                                    keep,
+                                    Some(edge.forward),
+                                    format!(
+                                        "{} → {}（{}）",
+                                        content(a),
+                                        content(b),
+                                        edge.forward_relation.label()
+                                    ),
+                                );
+                                ui.radio_value(
+                                    keep,
+                                    Some(edge.backward()),
+                                    format!(
+                                        "{} → {}（{}）",
+                                        content(b),
+                                        content(a),
+                                        edge.backward_relation.label()
+                                    ),
+                                );
+                                ui.radio_value(keep, None, "都保留");
+                                ui.end_row();
+                            }
+                        });
+                    });
+
+                ui.horizontal(|ui| {
+                    if ui
+                        .button("按层级选择")
+                        .on_hover_text("保留从上级指向下级的边，层级相同的保持不变")
+                        .clicked()
+                    {
+                        for (edge, keep) in edges.iter_mut() {
+                            *keep = edge.suggested;
+                        }
+                    }
+                    confirmed = ui.button("确定").clicked();
+                    cancelled = ui.button("取消").clicked();
+                });
+            });
+
+        if confirmed && let Some(edges) = self.normalizing_edges.take() {
+            let keep = edges
+                .into_iter()
+                .filter_map(|(_, keep)| keep)
+                .collect::<Vec<_>>();
+            match self
+                .graph
+                .as_mut()
+                .map(|graph| graph.normalize_edges(&keep))
+            {
+                Some(Ok(0)) | None => {}
+                Some(Ok(count)) => self.notifications.info(format!("已删除 {count} 条边")),
+                Some(Err(e)) => {
+                    self.error = Some(("规范化双向边失败".to_string(), e.zh_message()));
+                }
+            }
+        } else if cancelled {
+            self.normalizing_edges = None;
+        }
+    }
+
+    fn show_notifications(&mut self, ctx: &Context) {
+        self.notifications.remove_expired(time::Instant::now());
+        if self.notifications.is_empty() {
+            return;
+        }
+
+        // 在右下角堆叠显示，不拦截鼠标操作
+        egui::Area::new(egui::Id::new("通知"))
+            .anchor(Align2::RIGHT_BOTTOM, [-10.0, -10.0])
+            .interactable(false)
+            .show(ctx, |ui| {
+                for notification in self.notifications.iter() {
+                    let (fill, color) = match notification.severity {
+                        Severity::Info => (
+                            Color32::from_rgb(232, 240, 254),
+                            Color32::from_rgb(54, 131, 248),
+                        ),
+                        Severity::Warning => (
+                            Color32::from_rgb(255, 243, 205),
+                            Color32::from_rgb(189, 53, 61),
+                        ),
+                    };
+                    egui::Frame::popup(ui.style()).fill(fill).show(ui, |ui| {
+                        ui.label(egui::RichText::new(&notification.message).color(color));
+                    });
+                }
+            });
+
+        // 通知过期后需要重绘以将其移除
+        ctx.request_repaint_after(time::Duration::from_millis(200));
+    }
+
+    fn show_error_popup(&mut self, ctx: &Context) {
+        if self.error.is_some() && self.error != self.logged_error {
+            if let Some((title, message)) = self.error.as_ref() {
+                log::error!("{title}: {message}");
+            }
+            self.logged_error = self.error.clone();
+        }
+        if let Some((ref title, ref message)) = self.error.clone() {
+            egui::Window::new(title)
+                .collapsible(false)
+                .resizable(false)
+                .anchor(Align2::CENTER_CENTER, [0.0, 0.0])
+                .show(ctx, |ui| {
+                    ui.label(message);
+                    if let Some(path) = logging::log_path() {
+                        ui.label(
+                            RichText::new(format!("详细日志见 {}", path.display()))
+                                .small()
+                                .weak(),
+                        );
+                    }
+                    ui.horizontal(|ui| {
+                        if ui.button("确定").clicked() {
+                            self.error = None;
+                            self.lenient_path = None;
+                        }
+                        if self.lenient_path.is_some()
+                            && ui
+                                .button("尝试宽容模式")
+                                .on_hover_text("忽略无法识别的节点和边后打开")
+                                .clicked()
+                        {
+                            self.open_lenient();
+                        }
+                    });
+                });
+        }
+
+        if let Some((description, warnings)) = self.open_warnings.as_ref() {
+            let mut closed = false;
+            egui::Window::new("警告")
+                .collapsible(false)
+                .resizable(false)
+                .anchor(Align2::CENTER_CENTER, [0.0, 0.0])
+                .show(ctx, |ui| {
+                    ui.label(*description);
+                    egui::ScrollArea::vertical()
+                        .max_height(200.0)
+                        .show(ui, |ui| {
+                            for warning in warnings {
+                                ui.label(warning);
+                            }
+                        });
+                    closed = ui.button("确定").clicked();
+                });
+            if closed {
+                self.open_warnings = None;
+            }
+        }
+    }
+
+    /// 保存当前图谱并切换到另一个文件，清除与原图谱相关的状态
+    fn close_current_file(&mut self) {
+        if let Some(graph) = self.graph.as_mut()
+            && let Err(e) = graph.save()
+        {
+            self.error = Some((
+                format!(
+                    "保存 {} 失败",
+                    graph.file_path.as_os_str().to_string_lossy()
+                ),
+                e.zh_message(),
+            ));
+        }
+        self.comparing = None;
+        self.graph_stats = None;
+        self.focused_node = None;
+        self.folding.expand_all();
+        self.ordering_children = None;
+        self.normalizing_edges = None;
+        self.scaling_coordinates = None;
+        self.export_issues = None;
+        self.naming_bookmark = None;
+        self.selected_group = None;
+        self.dragging_group = None;
+        self.dragging_waypoint = None;
+        self.editing_group = None;
+        self.cancel_pending_child();
+        self.view_history = ViewHistory::default();
+        self.view_sidecar = ViewSidecar::default();
+        self.underlay_texture = None;
+        // 文件可能已被修改，回到欢迎页时重新读取标题
+        self.recent_titles.clear();
+    }
+
+    /// 在后台打开文件，完成前当前的图谱保持不变，取消时不做任何修改
+    fn open_file(&mut self, file: PathBuf) {
+        let path = file.clone();
+        let task = BackgroundTask::spawn(
+            format!("正在打开 {}", file.to_string_lossy()),
+            move |token| FiledKnowledgeGraph::open_cancellable(&path, token),
+        );
+        self.file_task = Some(FileTask::Open(file, task));
+    }
+
+    /// 立即打开文件，用于已在其他步骤中读取过的文件
+    fn open_file_now(&mut self, file: PathBuf) {
+        let result = FiledKnowledgeGraph::new(&file, false);
+        self.finish_open_file(file, result);
+    }
+
+    /// 处理打开文件的结果，成功时关闭当前的图谱并切换到打开的图谱
+    fn finish_open_file(&mut self, file: PathBuf, result: Result<FiledKnowledgeGraph, Error>) {
+        if result.is_ok() {
+            self.close_current_file();
+        }
+        match result {
+            Ok(mut graph) => {
+                log::info!("打开 {}", file.display());
+                let warnings = graph.take_load_warnings();
+                for warning in warnings.iter() {
+                    log::warn!("{}: {warning}", file.display());
+                }
+                if !warnings.is_empty() {
+                    self.open_warnings = Some((
+                        "打开文件时发现以下情况（类型与级别矛盾的节点已按节点类型读取）：",
+                        warnings,
+                    ));
+                }
+                self.set_graph(graph);
+                if self.settings.move_long_content {
+                    self.move_long_content();
+                }
+                self.hint_coordinate_scale();
+            }
+            Err(e) => {
+                // 格式问题可尝试以宽容模式打开
+                if matches!(e, Error::Serde(_)) {
+                    self.lenient_path = Some(file.clone());
+                }
+                self.error = Some((
+                    format!("打开 {} 失败", file.as_os_str().to_string_lossy()),
+                    e.zh_message(),
+                ))
+            }
+        }
+    }
+
+    /// 节点间距与排版设置的间距相差过大时，提示坐标单位可能与本软件不同，可以缩放坐标
+    fn hint_coordinate_scale(&mut self) {
+        let Some(graph) = self.graph.as_ref() else {
+            return;
+        };
+        let spacing = self.settings.layout.node_spacing;
+        if let Some(factor) = graph.current_snapshot().estimate_coordinate_scale(spacing)
+            && !(1.0 / SCALE_HINT_RATIO..=SCALE_HINT_RATIO).contains(&factor)
+        {
+            self.notifications.warning(format!(
+                "节点间距约为常用间距的 {:.2} 倍，坐标单位可能不同，可用“缩放坐标”调整",
+                1.0 / factor
+            ));
+        }
+    }
+
+    /// 将内容超过建议上限的节点的超出部分移到备注，作为一次可撤回的操作
+    fn move_long_content(&mut self) {
+        let Some(graph) = self.graph.as_mut() else {
+            return;
+        };
+        let snapshot = graph.current_snapshot();
+        let limit = snapshot.metadata.content_limit();
+        let commands = snapshot
+            .nodes
+            .values()
+            .filter_map(|node| {
+                let mut node = node.clone();
+                node.move_excess_content(limit)
+                    .then_some(Command::ReplaceEntity(node))
+            })
+            .collect::<Vec<_>>();
+        if commands.is_empty() {
+            return;
+        }
+
+        let count = commands.len();
+        match graph.apply_batch(commands) {
+            Ok(_) => self.notifications.warning(format!(
+                "已将 {count} 个节点超过 {limit} 字的内容移到备注，可撤销"
+            )),
+            Err(e) => self.error = Some(("移动超长内容失败".to_string(), e.zh_message())),
+        }
+    }
+
+    /// 设置打开的图谱，并按设置开启日志和缩略图
+    fn set_graph(&mut self, graph: FiledKnowledgeGraph) {
+        self.view_sidecar = ViewSidecar::load(&graph.file_path).unwrap_or_else(|e| {
+            self.notifications
+                .warning(format!("读取视图设置失败：{}", e.zh_message()));
+            ViewSidecar::default()
+        });
+        self.underlay_texture = None;
+        self.settings.add_recent_file(graph.file_path.clone());
+        self.graph = Some(graph);
+        self.apply_file_settings();
+    }
+
+    /// 按设置开启或关闭当前图谱的日志和缩略图
+    fn apply_file_settings(&mut self) {
+        let Some(graph) = self.graph.as_mut() else {
+            return;
+        };
+        if graph.autosave_timing() != self.settings.autosave_timing() {
+            graph.set_autosave_timing(self.settings.autosave_timing());
+        }
+        if graph.is_thumbnail_enabled() != self.settings.thumbnail {
+            graph.set_thumbnail(self.settings.thumbnail);
+        }
+        if graph.is_journaling() != self.settings.journal
+            && let Err(e) = graph.set_journal(self.settings.journal)
+        {
+            self.error = Some(("开启操作日志失败".to_string(), e.zh_message()));
+        }
+        if graph.is_delta_logging() != self.settings.delta_log
+            && let Err(e) = graph.set_delta_log(self.settings.delta_log)
+        {
+            self.error = Some(("开启增量日志失败".to_string(), e.zh_message()));
+        }
+    }
+
+    /// 新建空图谱，overwrite 为 true 时覆盖已有内容的文件
+    fn create_file(&mut self, file: PathBuf, overwrite: bool) {
+        self.close_current_file();
+        let result = if overwrite {
+            FiledKnowledgeGraph::overwrite(&file)
+        } else {
+            FiledKnowledgeGraph::new(&file, true)
+        };
+        match result {
+            Ok(graph) => self.set_graph(graph),
+            Err(e) => {
+                self.error = Some((
+                    format!("新建 {} 失败", file.as_os_str().to_string_lossy()),
+                    e.zh_message(),
+                ))
+            }
+        }
+    }
+
+    /// 新建文件时选中了已有内容的文件，确认打开、覆盖还是取消
+    fn show_confirm_new_file_window(&mut self, ctx: &Context) {
+        let Some(file) = self.confirming_new_file.as_ref() else {
+            return;
+        };
+
+        let mut choice = None; // Some(true) 为覆盖，Some(false) 为打开
+        let mut cancelled = false;
+        egui::Window::new("文件已存在")
+            .collapsible(false)
+            .resizable(false)
+            .anchor(Align2::CENTER_CENTER, [0.0, 0.0])
+            .show(ctx, |ui| {
+                ui.label(format!(
+                    "{} 已有内容，要打开它还是覆盖为新图谱？",
+                    file.as_os_str().to_string_lossy()
+                ));
+                ui.horizontal(|ui| {
+                    if ui.button("打开该文件").clicked() {
+                        choice = Some(false);
+                    }
+                    if ui
+                        .button(RichText::new("覆盖为新图谱").color(Color32::RED))
+                        .clicked()
+                    {
+                        choice = Some(true);
+                    }
+                    cancelled = ui.button("取消").clicked();
+                });
+            });
+
+        if cancelled {
+            self.confirming_new_file = None;
+        } else if let Some(overwrite) = choice
+            && let Some(file) = self.confirming_new_file.take()
+        {
+            if overwrite {
+                self.create_file(file, true);
+            } else {
+                self.open_file(file);
+            }
+        }
+    }
+
+    /// 以宽容模式重新打开上次打开失败的文件
+    fn open_lenient(&mut self) {
+        let Some(file) = self.lenient_path.take() else {
+            return;
+        };
+        match FiledKnowledgeGraph::open_lenient(&file) {
+            Ok((graph, warnings)) => {
+                self.graph_stats = None;
+                self.error = None;
+                self.set_graph(graph);
+                if warnings.is_empty() {
+                    self.notifications.info("已以宽容模式打开");
+                } else {
+                    self.open_warnings = Some((
+                        "已以宽容模式打开，以下内容已被忽略，保存时将以标准格式覆盖原文件：",
+                        warnings,
+                    ));
+                }
+            }
+            Err(e) => {
+                self.error = Some((
+                    format!("打开 {} 失败", file.as_os_str().to_string_lossy()),
+                    e.zh_message(),
+                ))
+            }
+        }
+    }
+
+    fn show_topbar(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal_centered(|ui| {
+            let icon_size = Vec2::new(TOP_PANEL_HEIGHT * 0.7, TOP_PANEL_HEIGHT * 0.7);
+            if ui
+                .add_sized(
+                    icon_size,
+                    egui::ImageButton::new(egui::include_image!(
+                        "../../assets/note_add_35dp_5985E1_FILL0_wght400_GRAD0_opsz40.svg"
+                    )),
+                )
+                .on_hover_text("新建文件")
+                .clicked()
+                && let Some(file) = rfd::FileDialog::new()
+                    .set_title("选择保存位置并输入文件名")
+                    .set_file_name("knowledge_graph.xml")
+                    .save_file()
+            {
+                // 选中已有内容的文件时先确认，避免误将打开当作新建而清空文件
+                if FiledKnowledgeGraph::has_content(&file) {
+                    self.confirming_new_file = Some(file);
+                } else {
+                    self.create_file(file, false);
+                }
+            }
+            if ui
+                .add_sized(
+                    icon_size,
+                    egui::ImageButton::new(egui::include_image!(
+                        "../../assets/file_open_35dp_5985E1_FILL0_wght400_GRAD0_opsz40.svg"
+                    )),
+                )
+                .on_hover_text("打开文件")
+                .clicked()
+                && let Some(file) = rfd::FileDialog::new()
+                    .add_filter("XML 文件", &["xml"])
+                    .pick_file()
+            {
+                self.open_file(file);
+            }
+            if ui
+                .add_sized(
+                    icon_size,
+                    egui::ImageButton::new(egui::include_image!(
+                        "../../assets/save_35dp_5985E1_FILL0_wght400_GRAD0_opsz40.svg"
+                    )),
+                )
+                .on_hover_text("保存文件")
+                .clicked()
+            {
+                self.save();
+            }
+            if ui
+                .add_sized(
+                    icon_size,
+                    egui::ImageButton::new(egui::include_image!(
+                        "../../assets/arrow_back_35dp_5985E1_FILL0_wght400_GRAD0_opsz40.svg"
+                    )),
+                )
+                .on_hover_text("撤销")
+                .clicked()
+            {
+                self.undo(ui.ctx());
+            }
+            if ui
+                .add_sized(
+                    icon_size,
+                    egui::ImageButton::new(egui::include_image!(
+                        "../../assets/arrow_forward_35dp_5985E1_FILL0_wght400_GRAD0_opsz40.svg"
+                    )),
+                )
+                .on_hover_text("恢复")
+                .clicked()
+            {
+                self.redo(ui.ctx());
+            }
+            ui.menu_button("工作包", |ui| {
+                if ui
+                    .add_enabled(self.graph.is_some(), egui::Button::new("导出工作包"))
+                    .on_hover_text("将图谱、视图设置、底图和节点模板打包为一个 zip 文件")
+                    .clicked()
+                {
+                    ui.close_menu();
+                    self.export_bundle();
+                }
+                if ui
+                    .button("导入工作包")
+                    .on_hover_text("解压到选定的文件夹，打开其中的图谱并恢复视图")
+                    .clicked()
+                {
+                    ui.close_menu();
+                    self.import_bundle();
+                }
+            });
+            if let Some(graph) = self.graph.as_ref()
+                && ui.button("图谱信息").clicked()
+            {
+                self.editing_metadata = Some(graph.current_snapshot().metadata.clone());
+            }
+            if self.graph.is_some() {
+                ui.menu_button("书签", |ui| self.show_bookmark_menu(ui));
+                ui.menu_button("底图", |ui| self.show_underlay_menu(ui));
+                ui.menu_button("折叠", |ui| self.show_fold_menu(ui));
+            }
+            if self.graph.is_some()
+                && ui
+                    .button("规范化双向边")
+                    .on_hover_text("列出两个节点之间两个方向都有的边，选择保留的一条，导出前使关系自洽")
+                    .clicked()
+            {
+                self.begin_normalize_edges();
+            }
+            if self.graph.is_some()
+                && ui
+                    .button("检查并导出")
+                    .on_hover_text("检查对接平台的要求，全部通过后导出平台可直接打开的 XML")
+                    .clicked()
+            {
+                self.strict_export(false);
+            }
+            if self.graph.is_some()
+                && self.glossary.is_some()
+                && ui
+                    .button("术语检查")
+                    .on_hover_text("按设置中的术语表列出使用了非标准写法的节点")
+                    .clicked()
+            {
+                self.showing_term_issues = true;
+            }
+            if self.graph.is_some()
+                && ui
+                    .button("预览 XML")
+                    .on_hover_text("查看保存时将写入文件的 XML")
+                    .clicked()
+            {
+                self.preview_xml();
+            }
+            if self.graph.is_some()
+                && ui
+                    .button("导出教学要求矩阵")
+                    .on_hover_text("按知识单元统计各附加类型的节点数，导出为 CSV 或 HTML 表格")
+                    .clicked()
+            {
+                self.export_addon_matrix();
+            }
+            if let Some(graph) = self.graph.as_ref() {
+                ui.menu_button("复制为缩进文本", |ui| {
+                    // 以知识领域为根按包含关系缩进，可直接粘贴到聊天或邮件中
+                    for (label, numbered) in [("不加序号", false), ("加上序号", true)] {
+                        if ui.button(label).clicked() {
+                            ui.close_menu();
+                            let tree = graph.current_snapshot().to_text_tree_with(numbered);
+                            ui.ctx().copy_text(tree);
+                            self.notifications.info("已复制缩进文本");
+                        }
+                    }
+                });
+            }
+            if self.graph.is_some()
+                && ui
+                    .button("导出 Anki 卡片")
+                    .on_hover_text("带有问题或练习的节点为正面，其包含的子节点为背面，可导入 Anki 复习")
+                    .clicked()
+            {
+                self.export_anki();
+            }
+            if self.graph.is_some() {
+                ui.menu_button("坐标表", |ui| {
+                    if ui
+                        .button("导出坐标表")
+                        .on_hover_text("导出每个节点的 ID、内容和坐标，可在 Excel 中编辑坐标后导回")
+                        .clicked()
+                    {
+                        ui.close_menu();
+                        self.export_coordinates();
+                    }
+                    if ui
+                        .button("从坐标表导入坐标")
+                        .on_hover_text("按 ID 更新节点的坐标，内容和类型不变，可撤销")
+                        .clicked()
+                    {
+                        ui.close_menu();
+                        self.import_coordinates();
+                    }
+                });
+            }
+            if self.graph.is_some()
+                && ui
+                    .button("按领域拆分导出")
+                    .on_hover_text(
+                        "以每个知识领域为根沿包含关系拆分，分别导出到选定的文件夹，其余节点归入“其他”",
+                    )
+                    .clicked()
+            {
+                self.export_split_by_arena();
+            }
+            if self.graph.is_some() {
+                ui.menu_button("批量导出单元", |ui| {
+                    ui.checkbox(&mut self.settings.unit_export_arena, "包含所属的知识领域");
+                    if ui
+                        .button("选择文件夹并导出")
+                        .on_hover_text("每个知识单元及其下级节点导出为单独的 XML 文件，内容为空的单元被跳过")
+                        .clicked()
+                    {
+                        ui.close_menu();
+                        self.export_units();
+                    }
+                });
+            }
+            if self.graph.is_some()
+                && ui
+                    .button("打印")
+                    .on_hover_text("导出为分页的 PDF 并用系统默认程序打开，在其中打印")
+                    .clicked()
+            {
+                self.printing = Some(1);
+            }
+            if self.graph.is_some() && ui.button("解决重叠").clicked() {
+                self.resolve_overlaps();
+            }
+            if self.graph.is_some()
+                && ui
+                    .button("自动排版")
+                    .on_hover_text("沿边的方向分层排列所有节点，方向和间距可在设置中调整")
+                    .clicked()
+            {
+                self.auto_layout();
+            }
+            if self.graph.is_some()
+                && ui
+                    .button("压缩到画布范围")
+                    .on_hover_text("等比缩放并平移所有节点，使坐标位于对接平台接受的范围内")
+                    .clicked()
+            {
+                self.fit_to_bounds();
+            }
+            if self.graph.is_some()
+                && ui
+                    .button("缩放坐标")
+                    .on_hover_text("将所有坐标乘以系数，换算其他系统的坐标单位，可按节点间距自动估算")
+                    .clicked()
+            {
+                self.begin_scale_coordinates();
+            }
+            if self.graph.is_some()
+                && ui
+                    .button("收回离群节点")
+                    .on_hover_text("将坐标远离其余节点的节点移到当前视图的左上角，排成一列")
+                    .clicked()
+            {
+                self.recall_outliers();
+            }
+            // ID 变化无法录制，录制时不提供紧凑化
+            if self.graph.as_ref().is_some_and(|g| !g.is_recording())
+                && ui
+                    .button("紧凑化 ID")
+                    .on_hover_text("将节点 ID 重新编号为从 1 开始的连续整数，供对接系统使用")
+                    .clicked()
+            {
+                self.compact_ids();
+            }
+            // 修复无法录制，录制时不提供自动修复
+            if self.graph.as_ref().is_some_and(|g| !g.is_recording())
+                && ui
+                    .button("自动修复")
+                    .on_hover_text("删除悬空的边、自环和无效的分组成员等，修复可以撤销")
+                    .clicked()
+            {
+                self.auto_repair();
+            }
+            if self.graph.is_some()
+                && ui
+                    .button("规范化内容")
+                    .on_hover_text("去掉选中节点（未选中时为全部节点）内容首尾的空白并合并连续空白")
+                    .clicked()
+            {
+                self.normalizing = Some(NormalizeOptions::default());
+            }
+            // 整体替换无法录制，录制时不提供重新载入
+            if self.graph.as_ref().is_some_and(|g| !g.is_recording())
+                && ui
+                    .button("重新载入")
+                    .on_hover_text("重新读取磁盘上被其他程序修改的文件，载入前的内容可以撤销找回")
+                    .clicked()
+            {
+                self.reload(ui.ctx());
+            }
+            if self.graph.is_some() {
+                let recording = self.graph.as_ref().is_some_and(|g| g.is_recording());
+                if ui
+                    .button(if recording {
+                        "停止录制"
+                    } else {
+                        "录制宏"
+                    })
+                    .clicked()
+                {
+                    self.toggle_recording();
+                }
+                if !recording && ui.button("回放宏").clicked() {
+                    self.play_macro();
+                }
+            }
+            if self.graph.is_some() {
+                if self.comparing.is_some() {
+                    if ui.button("结束对比").clicked() {
+                        self.comparing = None;
+                    }
+                } else {
+                    ui.menu_button("对比", |ui| {
+                        if ui.button("与磁盘版本对比").clicked() {
+                            ui.close_menu();
+                            self.compare_with_disk();
+                        }
+                        if ui.button("与其他文件对比").clicked() {
+                            ui.close_menu();
+                            self.compare_with_file();
+                        }
+                    });
+                }
+            }
+            if self.graph.is_some() {
+                ui.toggle_value(&mut self.showing_legend, "图例");
+                ui.toggle_value(&mut self.showing_flag_list, "标记清单");
+            }
+            if ui.button("设置").clicked() {
+                self.showing_settings = true;
+            }
+            ui.toggle_value(&mut self.showing_shortcuts, "快捷键");
+        });
+    }
+
+    fn show_statusbar(&mut self, ui: &mut egui::Ui) {
+        let Some(graph) = self.graph.as_ref() else {
+            return;
+        };
+        let snapshot = graph.current_snapshot();
+
+        // 显示节点数和边数，设置了上限时一并显示
+        let count_text = |count: usize, max: Option<usize>| match max {
+            Some(max) => format!("{count}/{max}"),
+            None => count.to_string(),
+        };
+        ui.horizontal(|ui| {
+            ui.label(format!(
+                "节点 {} · 边 {}",
+                count_text(snapshot.nodes.len(), snapshot.metadata.max_nodes),
+                count_text(snapshot.edges.len(), snapshot.metadata.max_edges),
+            ));
+
+            // 显示最严重的校验问题
+            if let Some(issue) = snapshot.validate().into_iter().max_by_key(|i| i.level) {
+                ui.separator();
+                ui.colored_label(issue_color(issue.level), issue.message);
+            }
+        });
+
+        // 只选中一个节点时，显示其沿包含关系向上的层级路径，点击祖先节点跳转
+        let mut selected = self.selected_nodes.iter();
+        let (Some(&id), None) = (selected.next(), selected.next()) else {
+            return;
+        };
+        let paths = snapshot.ancestor_paths(id);
+        if paths.is_empty() {
+            return;
+        }
+        if self.breadcrumb.0 != id {
+            self.breadcrumb = (id, 0);
+        }
+        let index = self.breadcrumb.1 % paths.len();
+        let mut jump = None;
+        ui.horizontal(|ui| {
+            if paths.len() > 1
+                && ui
+                    .small_button(format!("路径 {}/{}", index + 1, paths.len()))
+                    .on_hover_text("节点有多个父节点，点击切换层级路径")
+                    .clicked()
+            {
+                self.breadcrumb.1 = (index + 1) % paths.len();
+            }
+            for (i, ancestor) in paths[index].iter().enumerate() {
+                let Some(node) = snapshot.nodes.get(ancestor) else {
+                    continue;
+                };
+                if i > 0 {
+                    ui.label(">");
+                }
+                let label = match node.content.char_indices().nth(BREADCRUMB_CONTENT_LIMIT) {
+                    Some((index, _)) => format!("{}…", &node.content[..index]),
+                    None => node.content.clone(),
+                };
+                if *ancestor == id {
+                    ui.strong(label);
+                } else if ui.link(label).on_hover_text(&node.content).clicked() {
+                    jump = Some((*ancestor, node.coor));
+                }
+            }
+        });
+        if let Some((ancestor, coor)) = jump {
+            self.selected_nodes = BTreeSet::from([ancestor]);
+            self.selected_edges.clear();
+            let ctx = ui.ctx().clone();
+            if !ctx.screen_rect().contains(self.logical_to_screen(coor)) {
+                self.scroll_to(&ctx, coor);
+            }
+        }
+    }
+
+    /// 图例面板：显示各类型的节点数，点击类型切换筛选，按住 Shift 点击只保留该类型，点击标题清除筛选
+    fn show_legend_panel(&mut self, ctx: &Context) {
+        let Some(graph) = self.graph.as_ref() else {
+            return;
+        };
+        if !self.showing_legend {
+            return;
+        }
+
+        // 只在图谱变化时重新统计
+        let revision = graph.revision();
+        if self
+            .graph_stats
+            .as_ref()
+            .is_none_or(|(cached, _)| *cached != revision)
+        {
+            self.graph_stats = Some((revision, graph.current_snapshot().statistics()));
+        }
+        let Some((_, stats)) = self.graph_stats.as_ref() else {
+            return;
+        };
+
+        let isolate = ctx.input(|i| i.modifiers.shift);
+        let filter = &mut self.filter;
+        let theme = &self.theme;
+        egui::SidePanel::right("图例")
+            .resizable(false)
+            .show(ctx, |ui| {
+                let header = ui
+                    .add(egui::Label::new(RichText::new("图例").heading()).sense(Sense::click()))
+                    .on_hover_text("点击清除筛选");
+                if header.clicked() {
+                    filter.clear();
+                }
+
+                ui.separator();
+                egui::Grid::new("图例节点类型").show(ui, |ui| {
+                    for distinct_type in DISTINCT_TYPES {
+                        ui.colored_label(theme.rect_color(distinct_type), "■");
+                        let count = stats.distinct_counts.get(&distinct_type).unwrap_or(&0);
+                        let label = format!("{} {count}", distinct_type.class_name_abbr());
+                        if ui
+                            .selectable_label(filter.shows_distinct(distinct_type), label)
+                            .clicked()
+                        {
+                            if isolate {
+                                filter.isolate_distinct(distinct_type);
+                            } else {
+                                filter.toggle_distinct(distinct_type);
+                            }
+                        }
+                        ui.end_row();
+                    }
+                });
+
+                ui.separator();
+                ui.horizontal_wrapped(|ui| {
+                    for addon_type in ADDON_TYPES {
+                        let count = stats.addon_counts.get(&addon_type).unwrap_or(&0);
+                        let label = format!("{} {count}", addon_type.name());
+                        if ui
+                            .selectable_label(filter.shows_addon(addon_type), label)
+                            .clicked()
+                        {
+                            if isolate {
+                                filter.isolate_addon(addon_type);
+                            } else {
+                                filter.toggle_addon(addon_type);
+                            }
+                        }
+                    }
+                });
+
+                ui.separator();
+                ui.horizontal_wrapped(|ui| {
+                    for flag in Flag::ALL {
+                        let count = stats.flag_counts.get(&flag).unwrap_or(&0);
+                        let label =
+                            RichText::new(format!("{} {count}", flag.label())).color(flag.color());
+                        if ui
+                            .selectable_label(filter.shows_only_flag(flag), label)
+                            .on_hover_text("只显示带有该标记的节点")
+                            .clicked()
+                        {
+                            filter.toggle_flag(flag);
+                        }
+                    }
+                });
+
+                ui.separator();
+                egui::Grid::new("图例关系类型").show(ui, |ui| {
+                    for relation in relation_registry().relations() {
+                        let (rect, _) =
+                            ui.allocate_exact_size(Vec2::new(40.0, 16.0), Sense::hover());
+                        let color = relation
+                            .color()
+                            .map_or(ui.visuals().text_color(), |[r, g, b]| {
+                                Color32::from_rgb(r, g, b)
+                            });
+                        let stroke = Stroke::new(2.0, color);
+                        let (start, end) = (rect.left_center(), rect.right_center());
+                        ui.painter().line_segment([start, end], stroke);
+                        draw_relation_marker(ui.painter(), start, end, relation.marker(), stroke);
+                        ui.label(format!("{}关系", relation.label()));
+                        ui.end_row();
+                    }
+
+                    // 多父节点的主包含边和次要包含边
+                    for (secondary, label) in [(false, "主包含（多父）"), (true, "次要包含")]
+                    {
+                        let (rect, _) =
+                            ui.allocate_exact_size(Vec2::new(40.0, 16.0), Sense::hover());
+                        let color = ui.visuals().text_color();
+                        let (start, end) = (rect.left_center(), rect.right_center());
+                        let stroke = if secondary {
+                            let stroke = Stroke::new(1.0, color);
+                            ui.painter().extend(egui::Shape::dashed_line(
+                                &[start, end],
+                                stroke,
+                                6.0,
+                                4.0,
+                            ));
+                            stroke
+                        } else {
+                            let stroke = Stroke::new(3.5, color);
+                            ui.painter().line_segment([start, end], stroke);
+                            stroke
+                        };
+                        let marker = Relation::Contain.marker();
+                        draw_relation_marker(ui.painter(), start, end, marker, stroke);
+                        ui.label(label);
+                        ui.end_row();
+                    }
+                });
+            });
+    }
+
+    fn show_selection_panel(&mut self, ctx: &Context) {
+        let Some(graph) = self.graph.as_ref() else {
+            return;
+        };
+        // 少于两个节点时不显示
+        if self.selected_nodes.len() < 2 {
+            return;
+        }
+
+        // 只在选区或图谱变化时重新统计
+        let revision = graph.revision();
+        if self
+            .selection_stats
+            .as_ref()
+            .is_none_or(|s| s.revision != revision || s.selection != self.selected_nodes)
+        {
+            let snapshot = graph.current_snapshot();
+            self.selection_stats = Some(SelectionStats {
+                revision,
+                selection: self.selected_nodes.clone(),
+                stats: snapshot.subgraph(&self.selected_nodes).statistics(),
+                boundary_edges: snapshot.boundary_edges(&self.selected_nodes).len(),
+            });
+        }
+        let Some(selection) = self.selection_stats.as_ref() else {
+            return;
+        };
+        let stats = &selection.stats;
+
+        egui::SidePanel::right("选区统计")
+            .resizable(false)
+            .show(ctx, |ui| {
+                ui.heading(format!("已选 {} 个节点", stats.node_count));
+
+                ui.separator();
+                egui::Grid::new("选区节点类型").show(ui, |ui| {
+                    for distinct_type in [
+                        DistinctEntityType::KnowledgeArena,
+                        DistinctEntityType::KnowledgeUnit,
+                        DistinctEntityType::KnowledgePoint,
+                        DistinctEntityType::KnowledgeDetail,
+                    ] {
+                        let count = stats.distinct_counts.get(&distinct_type).unwrap_or(&0);
+                        ui.label(distinct_type.class_name_abbr());
+                        ui.label(count.to_string());
+                        ui.end_row();
+                    }
+                });
+
+                ui.separator();
+                egui::Grid::new("选区附加类型").show(ui, |ui| {
+                    for addon_type in [
+                        AddonEntityType::Knowledge,
+                        AddonEntityType::Thinking,
+                        AddonEntityType::Example,
+                        AddonEntityType::Question,
+                        AddonEntityType::Practice,
+                        AddonEntityType::Political,
+                    ] {
+                        let count = stats.addon_counts.get(&addon_type).unwrap_or(&0);
+                        ui.label(addon_type.name());
+                        ui.label(format!("{count}/{}", stats.node_count));
+                        ui.end_row();
+                    }
+                });
+
+                ui.separator();
+                egui::Grid::new("选区边").show(ui, |ui| {
+                    ui.label("内部边");
+                    ui.label(stats.edge_count.to_string());
+                    ui.end_row();
+                    ui.label("边界边");
+                    // 边界边不为 0 时说明选区不是自包含的
+                    if selection.boundary_edges > 0 {
+                        ui.colored_label(
+                            Color32::from_rgb(230, 140, 20),
+                            selection.boundary_edges.to_string(),
+                        );
+                    } else {
+                        ui.label("0");
+                    }
+                    ui.end_row();
+                    if let Some((width, height)) = stats.bounding_size() {
+                        ui.label("范围");
+                        ui.label(format!("{width:.0} × {height:.0}"));
+                        ui.end_row();
+                    }
+                });
+
+                // 恰好选中两个节点时可以合并
+                if let [first, second] = selection.selection.iter().copied().collect::<Vec<_>>()[..]
+                {
+                    ui.separator();
+                    if ui
+                        .button("合并节点")
+                        .on_hover_text("将一个节点的边和备注并入另一个节点，再删除前者")
+                        .clicked()
+                    {
+                        self.merging = Some((first, second, false));
+                    }
+                }
+            });
+    }
+
+    /// 选择合并时保留的节点和是否拼接内容
+    fn show_merge_window(&mut self, ctx: &Context) {
+        let (Some((mut keep, mut remove, mut concat)), Some(graph)) =
+            (self.merging, self.graph.as_mut())
+        else {
+            return;
+        };
+        let snapshot = graph.current_snapshot();
+        let (Some(kept), Some(removed)) = (snapshot.nodes.get(&keep), snapshot.nodes.get(&remove))
+        else {
+            self.merging = None;
+            return;
+        };
+
+        let mut confirm = false;
+        let mut cancel = false;
+        egui::Window::new("合并节点")
+            .collapsible(false)
+            .resizable(false)
+            .anchor(Align2::CENTER_CENTER, [0.0, 0.0])
+            .show(ctx, |ui| {
+                ui.label("保留的节点:");
+                // 点击另一个节点时交换保留和删除的节点
+                let _ = ui.radio(true, kept.content.as_str());
+                if ui.radio(false, removed.content.as_str()).clicked() {
+                    std::mem::swap(&mut keep, &mut remove);
+                }
+                ui.checkbox(&mut concat, "拼接两个节点的内容");
+                ui.label(
+                    RichText::new("另一个节点的边和备注会并入保留的节点，重复的边和自环会被跳过")
+                        .color(Color32::GRAY),
+                );
+                ui.horizontal(|ui| {
+                    confirm = ui.button("确定").clicked();
+                    cancel = ui.button("取消").clicked()
+                        || ui.input(|i| i.key_pressed(egui::Key::Escape));
+                });
+            });
+        self.merging = Some((keep, remove, concat));
+
+        if confirm {
+            self.merging = None;
+            match graph.merge_nodes_with(keep, remove, concat) {
+                Ok(()) => self.selected_nodes = BTreeSet::from([keep]),
+                Err(e) => self.error = Some(("合并节点失败".to_string(), e.zh_message())),
+            }
+        } else if cancel {
+            self.merging = None;
+        }
+    }
+
+    fn show_settings_window(&mut self, ctx: &Context) {
+        if !self.showing_settings {
+            return;
+        }
+
+        let mut open = true;
+        egui::Window::new("设置")
+            .collapsible(false)
+            .resizable(false)
+            .open(&mut open)
+            .anchor(Align2::CENTER_CENTER, [0.0, 0.0])
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label("配色方案:");
+                    for palette in [Palette::Standard, Palette::ColorblindSafe] {
+                        ui.radio_value(&mut self.settings.palette, palette, palette.name());
+                    }
+                });
+                ui.checkbox(&mut self.settings.animations, "撤销和恢复时显示过渡动画");
+                ui.checkbox(&mut self.settings.flow_animation, "显示次序关系的流向动画");
+                ui.checkbox(&mut self.settings.inertial_scroll, "平移画布后惯性滑动");
+                ui.checkbox(
+                    &mut self.settings.zoom_around_selection,
+                    "按住 Ctrl 滚动缩放时围绕选中的节点",
+                )
+                .on_hover_text(
+                    "只选中一个节点时，缩放后该节点在屏幕上的位置不变；否则围绕鼠标位置缩放",
+                );
+                ui.checkbox(
+                    &mut self.settings.stagger_markers,
+                    "错开多条边交汇处重叠的关系符号和标签",
+                )
+                .on_hover_text("只改变绘制位置，不影响图谱内容");
+
+                ui.separator();
+                ui.label("简化渲染")
+                    .on_hover_text("缩小画布时节点逐级省略文字和标签框，使大图谱的总览更流畅");
+                ui.horizontal(|ui| {
+                    ui.label("缩放低于此值时不绘制文字:");
+                    ui.add(
+                        egui::DragValue::new(&mut self.settings.lod_text_zoom)
+                            .range(0.5..=3.0)
+                            .speed(0.01),
+                    );
+                });
+                ui.horizontal(|ui| {
+                    ui.label("缩放低于此值时不绘制标签框:");
+                    ui.add(
+                        egui::DragValue::new(&mut self.settings.lod_label_zoom)
+                            .range(0.5..=self.settings.lod_text_zoom)
+                            .speed(0.01),
+                    );
+                    if ui.button("恢复默认").clicked() {
+                        let default = Settings::default();
+                        self.settings.lod_text_zoom = default.lod_text_zoom;
+                        self.settings.lod_label_zoom = default.lod_label_zoom;
+                    }
+                });
+
+                ui.separator();
+                ui.label("节点模板");
+                if self.settings.templates.is_empty() {
+                    ui.label("在节点编辑窗口中可将类型组合保存为模板");
+                }
+                let mut removed = None;
+                for (i, template) in self.settings.templates.iter_mut().enumerate() {
+                    ui.horizontal(|ui| {
+                        ui.label(&template.name);
+                        ui.add(
+                            egui::TextEdit::singleline(&mut template.content)
+                                .hint_text("预填内容，____ 处为光标"),
+                        );
+                        if ui.small_button("删除").clicked() {
+                            removed = Some(i);
+                        }
+                    });
+                }
+                if let Some(i) = removed {
+                    self.settings.templates.remove(i);
+                }
+                ui.horizontal(|ui| {
+                    if ui
+                        .add_enabled(
+                            !self.settings.templates.is_empty(),
+                            egui::Button::new("导出模板"),
+                        )
+                        .clicked()
+                    {
+                        self.export_templates();
+                    }
+                    if ui.button("导入模板").clicked() {
+                        self.import_templates();
+                    }
+                });
+
+                ui.separator();
+                ui.label("自动排版");
+                ui.horizontal(|ui| {
+                    ui.label("方向:");
+                    for direction in LayoutDirection::ALL {
+                        ui.radio_value(
+                            &mut self.settings.layout.direction,
+                            direction,
+                            direction.name(),
+                        );
+                    }
+                });
+                ui.horizontal(|ui| {
+                    ui.label("层间距:");
+                    ui.add(
+                        egui::DragValue::new(&mut self.settings.layout.layer_spacing)
+                            .range(NODE_SIZE.y as f64..=1000.0),
+                    );
+                    ui.label("同层间距:");
+                    ui.add(
+                        egui::DragValue::new(&mut self.settings.layout.node_spacing)
+                            .range(NODE_SIZE.x as f64..=1000.0),
+                    );
+                });
+
+                ui.separator();
+                ui.label("术语检查");
+                ui.horizontal(|ui| {
+                    match (self.settings.glossary_path.as_ref(), self.glossary.as_ref()) {
+                        (Some(path), Some(glossary)) => ui.label(format!(
+                            "{}（{} 条）",
+                            path.file_name().unwrap_or_default().to_string_lossy(),
+                            glossary.len()
+                        )),
+                        (Some(path), None) => ui.colored_label(
+                            issue_color(IssueLevel::Warning),
+                            format!("{} 读取失败", path.display()),
+                        ),
+                        (None, _) => ui.label("未设置术语表"),
+                    }
+                    .on_hover_text("每行一项：标准写法 = 变体1, 变体2；# 开头的行为注释");
+                    if ui.button("选择术语表").clicked()
+                        && let Some(file) = rfd::FileDialog::new()
+                            .add_filter("文本文件", &["txt"])
+                            .pick_file()
+                    {
+                        self.settings.glossary_path = Some(file);
+                        self.load_glossary();
+                    }
+                    if self.settings.glossary_path.is_some() && ui.button("移除").clicked() {
+                        self.settings.glossary_path = None;
+                        self.load_glossary();
+                    }
+                });
+
+                ui.separator();
+                ui.label("关系类型");
+                ui.horizontal(|ui| {
+                    match self.settings.relation_types_path.as_ref() {
+                        Some(path) => ui.label(format!(
+                            "{}（{} 种自定义关系）",
+                            path.file_name().unwrap_or_default().to_string_lossy(),
+                            relation_registry().custom_len()
+                        )),
+                        None => ui.label("仅使用内置的关系"),
+                    }
+                    .on_hover_text(
+                        "JSON 数组，每项含 id、name、class_name，可选 color（如 [200, 60, 60]）\
+                         和 marker（arrow、semicircle、circle、plain）。id 从 3 开始，0、1、2 为内置的包含、次序、扩展关系。\
+                         关系类型表对所有打开的图谱生效，更换后请重新打开图谱",
+                    );
+                    if ui.button("选择关系类型表").clicked()
+                        && let Some(file) = rfd::FileDialog::new()
+                            .add_filter("JSON 文件", &["json"])
+                            .pick_file()
+                    {
+                        self.settings.relation_types_path = Some(file);
+                        self.load_relation_types();
+                    }
+                    if self.settings.relation_types_path.is_some() && ui.button("移除").clicked()
+                    {
+                        self.settings.relation_types_path = None;
+                        self.load_relation_types();
+                    }
+                });
+
+                ui.separator();
+                ui.label("双击")
+                    .on_hover_text("双击空白处新建节点，按住 Alt 双击空白处以该点为中心放大");
+                ui.horizontal(|ui| {
+                    ui.label("双击间隔（毫秒）:");
+                    ui.add(
+                        egui::DragValue::new(&mut self.settings.double_click_ms).range(100..=1000),
+                    );
+                });
+                ui.horizontal(|ui| {
+                    ui.label("鼠标双击位置容差:");
+                    ui.add(
+                        egui::DragValue::new(&mut self.settings.double_click_distance)
+                            .range(1.0..=20.0),
+                    );
+                });
+                ui.horizontal(|ui| {
+                    // 按当前的阈值识别试用区域中的双击，便于调校
+                    let (rect, response) =
+                        ui.allocate_exact_size(Vec2::new(160.0, 32.0), Sense::click());
+                    if response.clicked()
+                        && let Some(pos) = response.interact_pointer_pos()
+                        && self.trial_click.click(
+                            pos,
+                            time::Instant::now(),
+                            time::Duration::from_millis(self.settings.double_click_ms),
+                            self.settings.double_click_distance,
+                        )
+                    {
+                        self.trial_double_clicks += 1;
+                    }
+                    let visuals = ui.style().interact(&response);
+                    ui.painter()
+                        .rect_filled(rect, visuals.corner_radius, visuals.bg_fill);
+                    ui.painter().text(
+                        rect.center(),
+                        Align2::CENTER_CENTER,
+                        format!("在此双击试用（{} 次）", self.trial_double_clicks),
+                        FontId::default(),
+                        visuals.text_color(),
+                    );
+                    if ui.button("恢复默认").clicked() {
+                        let default = Settings::default();
+                        self.settings.double_click_ms = default.double_click_ms;
+                        self.settings.double_click_distance = default.double_click_distance;
+                    }
+                });
+
+                ui.separator();
+                ui.label("触摸屏");
+                ui.horizontal(|ui| {
+                    ui.label("长按时长（毫秒）:");
+                    ui.add(
+                        egui::DragValue::new(&mut self.settings.long_press_ms).range(200..=2000),
+                    );
+                });
+                ui.horizontal(|ui| {
+                    ui.label("双击位置容差:");
+                    ui.add(
+                        egui::DragValue::new(&mut self.settings.double_tap_tolerance)
+                            .range(5.0..=50.0),
+                    );
+                });
+
+                ui.separator();
+                ui.label("自动保存");
+                ui.horizontal(|ui| {
+                    ui.label("停止修改后保存（毫秒）:");
+                    ui.add(
+                        egui::DragValue::new(&mut self.settings.autosave_quiet_ms)
+                            .range(50..=60_000),
+                    );
+                });
+                ui.horizontal(|ui| {
+                    ui.label("持续修改时最迟保存（毫秒）:");
+                    ui.add(
+                        egui::DragValue::new(&mut self.settings.autosave_max_delay_ms)
+                            .range(1000..=600_000),
+                    );
+                });
+
+                ui.separator();
+                ui.checkbox(&mut self.settings.thumbnail, "保存时生成缩略图")
+                    .on_hover_text("在图谱文件旁生成 .<文件名>.thumb.png，供文件管理器预览");
+                ui.checkbox(
+                    &mut self.settings.move_long_content,
+                    "打开文件时将超长内容移到备注",
+                )
+                .on_hover_text("超过图谱信息中建议字数的部分会移到备注，可撤销");
+                ui.checkbox(&mut self.settings.delta_log, "记录增量日志")
+                    .on_hover_text(
+                        "未保存的修改追加到图谱文件旁的 .<文件名>.log，保存后清空；\
+                         程序崩溃后再次打开文件时自动恢复",
+                    );
+                ui.checkbox(&mut self.settings.journal, "记录操作日志");
+                ui.label(
+                    RichText::new(
+                        "开启后每次修改的时间和内容（包括节点文字）都会记录到图谱文件旁的 \
+                         .journal 文件中，仅保存在本机，不会上传。分享图谱时请勿附带该文件。",
+                    )
+                    .small()
+                    .weak(),
+                );
+                if self.graph.is_some()
+                    && ui
+                        .button("导出操作历史")
+                        .on_hover_text(
+                            "将本次打开文件以来的全部操作导出为日志文件，报告问题时附上以便复现。\
+                             文件包含节点文字等全部内容",
+                        )
+                        .clicked()
+                {
+                    self.export_history();
+                }
+            });
+        self.showing_settings = open;
+        self.apply_file_settings();
+
+        // 设置立即生效
+        self.theme = self.settings.theme();
+    }
+
+    /// 与磁盘上保存的版本对比
+    fn compare_with_disk(&mut self) {
+        if let Some(graph) = self.graph.as_ref() {
+            let path = graph.file_path.clone();
+            self.start_comparison(&path, "磁盘版本".to_string());
+        }
+    }
+
+    /// 选择另一个文件进行对比
+    fn compare_with_file(&mut self) {
+        if let Some(file) = rfd::FileDialog::new()
+            .add_filter("XML 文件", &["xml"])
+            .pick_file()
+        {
+            let label = file
+                .file_name()
+                .map_or_else(String::new, |name| name.to_string_lossy().to_string());
+            self.start_comparison(&file, label);
+        }
+    }
+
+    fn start_comparison(&mut self, path: &std::path::Path, label: String) {
+        let Some(graph) = self.graph.as_ref() else {
+            return;
+        };
+        match FiledKnowledgeGraph::read_snapshot(path) {
+            Ok(base) => {
+                self.comparing = Some(Comparison::new(
+                    label,
+                    base,
+                    graph.current_snapshot(),
+                    graph.revision(),
+                ));
+                // 对比期间只读，清除选中和悬停状态
+                self.selected_nodes.clear();
+                self.selected_edges.clear();
+                self.hovered_node = None;
+                self.hovered_edge = None;
+            }
+            Err(e) => {
+                self.error = Some((
+                    format!("读取 {} 失败", path.as_os_str().to_string_lossy()),
+                    e.zh_message(),
+                ))
+            }
+        }
+    }
+
+    fn show_comparison_window(&mut self, ctx: &Context) {
+        let (Some(graph), Some(comparison)) = (self.graph.as_ref(), self.comparing.as_mut()) else {
+            return;
+        };
+        let current = graph.current_snapshot();
+        comparison.refresh(current, graph.revision());
+
+        let mut open = true;
+        let mut ended = false;
+        let mut jump = None;
+        egui::Window::new(format!("与{}对比", comparison.label))
+            .collapsible(false)
+            .resizable(false)
+            .open(&mut open)
+            .anchor(Align2::RIGHT_TOP, [-10.0, TOP_PANEL_HEIGHT + 10.0])
+            .show(ctx, |ui| {
+                // 只读提示条
+                ui.horizontal(|ui| {
+                    ui.colored_label(Color32::from_rgb(54, 131, 248), "对比期间图谱只读");
+                    ended = ui.button("结束对比").clicked();
+                });
+                let mut match_content = comparison.match_content();
+                if ui
+                    .checkbox(&mut match_content, "按内容匹配节点")
+                    .on_hover_text("ID 对应不上时，将内容和类型相同且唯一的节点视为同一节点")
+                    .changed()
+                {
+                    comparison.set_match_content(match_content, current);
+                }
+                ui.horizontal(|ui| {
+                    ui.label("显示方式:");
+                    for mode in CompareMode::ALL {
+                        ui.selectable_value(&mut comparison.mode, mode, mode.name());
+                    }
+                });
+                if comparison.mode == CompareMode::SideBySide {
+                    ui.horizontal(|ui| {
+                        ui.checkbox(&mut comparison.linked, "联动平移")
+                            .on_hover_text("取消后在哪一侧滚动就只平移哪一侧");
+                        if ui.button("对齐两侧").clicked() {
+                            comparison.pane_offset = Vec2::ZERO;
+                        }
+                    });
+                }
+                ui.separator();
+
+                if comparison.diff.is_empty() {
+                    ui.label("没有差异");
+                    return;
+                }
+                egui::Grid::new("对比图例").show(ui, |ui| {
+                    for (change, name) in [
+                        (Change::Added, "新增"),
+                        (Change::Removed, "删除"),
+                        (Change::Modified, "修改"),
+                    ] {
+                        ui.colored_label(change_color(change), "■");
+                        ui.label(name);
+                        ui.label(comparison.diff.count(change).to_string());
+                        ui.end_row();
+                    }
+                });
+                ui.separator();
+
+                // 变化列表，点击跳转到对应的节点或边
+                let base = &comparison.base;
+                let find_node = |id: &u64| current.nodes.get(id).or_else(|| base.nodes.get(id));
+                let change_name = |change: Change| match change {
+                    Change::Added => "新增",
+                    Change::Removed => "删除",
+                    Change::Modified => "修改",
+                };
+                egui::ScrollArea::vertical()
+                    .max_height(300.0)
+                    .show(ui, |ui| {
+                        for (id, change) in comparison.diff.nodes.iter() {
+                            let Some(node) = find_node(id) else {
+                                continue;
+                            };
+                            let text = format!("{}节点：{}", change_name(*change), node.content);
+                            if ui
+                                .add(
+                                    egui::Label::new(
+                                        RichText::new(text).color(change_color(*change)),
+                                    )
+                                    .sense(Sense::click())
+                                    .truncate(),
+                                )
+                                .clicked()
+                            {
+                                jump = Some(node.coor);
+                            }
+                        }
+                        for ((from, to), change) in comparison.diff.edges.iter() {
+                            let (Some(from), Some(to)) = (find_node(from), find_node(to)) else {
+                                continue;
+                            };
+                            let text = format!(
+                                "{}边：{} → {}",
+                                change_name(*change),
+                                from.content,
+                                to.content
+                            );
+                            if ui
+                                .add(
+                                    egui::Label::new(
+                                        RichText::new(text).color(change_color(*change)),
+                                    )
+                                    .sense(Sense::click())
+                                    .truncate(),
+                                )
+                                .clicked()
+                            {
+                                jump = Some((
+                                    (from.coor.0 + to.coor.0) / 2.0,
+                                    (from.coor.1 + to.coor.1) / 2.0,
+                                ));
+                            }
+                        }
+                    });
+            });
+        if !open || ended {
+            self.comparing = None;
+        }
+        if let Some(coor) = jump {
+            self.scroll_to(ctx, coor);
+        }
+    }
+
+    /// 严格检查当前图谱，全部通过时选择位置导出对接平台兼容的 XML，否则显示问题列表。
+    /// allow_out_of_bounds 为 true 时忽略坐标超出范围的问题，用于用户确认后仍然导出
+    fn strict_export(&mut self, allow_out_of_bounds: bool) {
+        let Some(graph) = self.graph.as_ref() else {
+            return;
+        };
+        let snapshot = graph.current_snapshot();
+        let mut issues = snapshot.strict_issues();
+        if allow_out_of_bounds && let Some(coordinate_issue) = snapshot.coordinate_issue() {
+            issues.retain(|issue| *issue != coordinate_issue);
+        }
+        if !issues.is_empty() {
+            self.export_issues = Some(issues);
+            return;
+        }
+        self.export_issues = None;
+
+        let file_name = graph
+            .file_path
+            .file_name()
+            .map_or("knowledge_graph.xml".into(), |name| name.to_string_lossy());
+        let Some(file) = rfd::FileDialog::new()
+            .add_filter("XML 文件", &["xml"])
+            .set_file_name(file_name)
+            .save_file()
+        else {
+            return;
+        };
+        let path = file.clone();
+        let snapshot = snapshot.clone();
+        let task = BackgroundTask::spawn(
+            format!("正在导出 {}", file.to_string_lossy()),
+            move |token| export_compatible(&snapshot, &path, token),
+        );
+        self.file_task = Some(FileTask::Export(file, task));
+    }
+
+    /// 后台任务的进度窗口，可取消任务。任务完成时处理其结果
+    fn show_file_task_window(&mut self, ctx: &Context) {
+        let Some(task) = self.file_task.take() else {
+            return;
+        };
+
+        match task {
+            FileTask::Open(file, task) => match task.try_take() {
+                None => self.file_task = Some(FileTask::Open(file, task)),
+                Some(Err(Error::Cancelled)) => {}
+                Some(result) => self.finish_open_file(file, result),
+            },
+            FileTask::Export(file, task) => match task.try_take() {
+                None => self.file_task = Some(FileTask::Export(file, task)),
+                Some(Ok(())) => self.notifications.info("检查通过，已导出"),
+                Some(Err(Error::Cancelled)) => {}
+                Some(Err(e)) => {
+                    self.error = Some((
+                        format!("导出 {} 失败", file.as_os_str().to_string_lossy()),
+                        e.zh_message(),
+                    ))
+                }
+            },
+            FileTask::ExportUnits(dir, task) => match task.try_take() {
+                None => self.file_task = Some(FileTask::ExportUnits(dir, task)),
+                Some(Ok(export)) => self.unit_export = Some((dir, export)),
+                Some(Err(Error::Cancelled)) => {}
+                Some(Err(e)) => self.error = Some(("批量导出单元失败".to_string(), e.zh_message())),
+            },
+        }
+
+        let Some(task) = self.file_task.as_ref() else {
+            return;
+        };
+        let (label, cancel) = match task {
+            FileTask::Open(_, task) => (&task.label, "取消打开"),
+            FileTask::Export(_, task) => (&task.label, "取消导出"),
+            FileTask::ExportUnits(_, task) => (&task.label, "取消导出"),
+        };
+        let mut cancelled = false;
+        egui::Window::new("请稍候")
+            .collapsible(false)
+            .resizable(false)
+            .anchor(Align2::CENTER_CENTER, [0.0, 0.0])
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    ui.spinner();
+                    ui.label(label);
+                });
+                cancelled = ui.button(cancel).clicked();
+            });
+        // 后台任务完成时界面未必有输入，需要持续刷新以取出结果
+        ctx.request_repaint_after(time::Duration::from_millis(50));
+
+        if cancelled && let Some(task) = self.file_task.take() {
+            match task {
+                FileTask::Open(_, task) => task.cancel(),
+                FileTask::Export(_, task) => task.cancel(),
+                FileTask::ExportUnits(_, task) => task.cancel(),
+            }
+            self.notifications.info(format!("已{cancel}"));
+        }
+    }
+
+    /// 生成当前图谱的 XML 并打开预览窗口
+    fn preview_xml(&mut self) {
+        let Some(graph) = self.graph.as_ref() else {
+            return;
+        };
+        match graph.current_snapshot().to_xml() {
+            Ok(xml) => self.previewing_xml = Some(xml),
+            Err(e) => self.error = Some(("生成 XML 失败".to_string(), e.zh_message())),
+        }
+    }
+
+    /// 以只读文本框显示预览的 XML，可复制全部内容
+    fn show_xml_preview_window(&mut self, ctx: &Context) {
+        let Some(xml) = self.previewing_xml.as_ref() else {
+            return;
+        };
+
+        let mut open = true;
+        egui::Window::new("预览 XML")
+            .open(&mut open)
+            .collapsible(false)
+            .default_size([600.0, 500.0])
+            .anchor(Align2::CENTER_CENTER, [0.0, 0.0])
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    if ui.button("复制").clicked() {
+                        ctx.copy_text(xml.clone());
+                        self.notifications.info("XML 已复制到剪贴板");
+                    }
+                    ui.label(
+                        RichText::new(format!("{} 行", xml.lines().count())).color(Color32::GRAY),
+                    );
+                });
+                egui::ScrollArea::both()
+                    .auto_shrink([false, false])
+                    .show(ui, |ui| {
+                        ui.add(
+                            egui::TextEdit::multiline(&mut xml.as_str())
+                                .code_editor()
+                                .desired_width(f32::INFINITY),
+                        );
+                    });
+            });
+        if !open {
+            self.previewing_xml = None;
+        }
+    }
+
+    /// 选择文件夹，按知识领域拆分图谱，每个领域导出为以领域名命名的 XML 文件
+    fn export_split_by_arena(&mut self) {
+        let Some(graph) = self.graph.as_ref() else {
+            return;
+        };
+        let Some(dir) = rfd::FileDialog::new().pick_folder() else {
+            return;
+        };
+        match export_split_by_arena(graph.current_snapshot(), &dir) {
+            Ok(paths) => self.notifications.info(format!(
+                "已导出 {} 个文件到 {}",
+                paths.len(),
+                dir.display()
+            )),
+            Err(e) => self.error = Some(("按领域拆分导出失败".to_string(), e.zh_message())),
+        }
+    }
+
+    /// 选择文件夹，在后台将每个知识单元导出为单独的 XML 文件，完成后显示结果
+    fn export_units(&mut self) {
+        let Some(graph) = self.graph.as_ref() else {
+            return;
+        };
+        let Some(dir) = rfd::FileDialog::new().pick_folder() else {
+            return;
+        };
+        let snapshot = graph.current_snapshot().clone();
+        let with_arena = self.settings.unit_export_arena;
+        let path = dir.clone();
+        let task = BackgroundTask::spawn(
+            format!("正在导出知识单元到 {}", dir.to_string_lossy()),
+            move |token| export_units(&snapshot, &path, with_arena, token),
+        );
+        self.file_task = Some(FileTask::ExportUnits(dir, task));
+    }
+
+    /// 批量导出单元的结果，列出写入的文件和因内容为空而跳过的单元，点击跳过的单元可定位
+    fn show_unit_export_window(&mut self, ctx: &Context) {
+        let Some((dir, export)) = self.unit_export.as_ref() else {
+            return;
+        };
+
+        let mut closed = false;
+        let mut focus = None;
+        egui::Window::new("批量导出单元")
+            .collapsible(false)
+            .resizable(true)
+            .anchor(Align2::CENTER_CENTER, [0.0, 0.0])
+            .show(ctx, |ui| {
+                ui.label(format!(
+                    "已导出 {} 个知识单元到 {}",
+                    export.written.len(),
+                    dir.display()
+                ));
+                egui::ScrollArea::vertical()
+                    .max_height(240.0)
+                    .show(ui, |ui| {
+                        for path in export.written.iter() {
+                            let name = path.file_name().unwrap_or_default().to_string_lossy();
+                            ui.label(name);
+                        }
+                    });
+                if !export.skipped.is_empty() {
+                    ui.separator();
+                    ui.label(format!(
+                        "{} 个知识单元内容为空，已跳过：",
+                        export.skipped.len()
+                    ));
+                    ui.horizontal_wrapped(|ui| {
+                        for id in export.skipped.iter() {
+                            if ui.link(format!("#{id}")).clicked() {
+                                focus = Some(*id);
+                            }
+                        }
+                    });
+                }
+                closed = ui.button("关闭").clicked();
+            });
+
+        if let Some(id) = focus
+            && let Some(coor) = self
+                .graph
+                .as_ref()
+                .and_then(|graph| graph.current_snapshot().nodes.get(&id))
+                .map(|node| node.coor)
+        {
+            self.selected_nodes = BTreeSet::from([id]);
+            self.scroll_to(ctx, coor);
+        }
+        if closed {
+            self.unit_export = None;
+        }
+    }
+
+    /// 选择位置导出 Anki 可导入的记忆卡片
+    fn export_anki(&mut self) {
+        let Some(graph) = self.graph.as_ref() else {
+            return;
+        };
+        let Some(file) = rfd::FileDialog::new()
+            .add_filter("TSV 文件", &["tsv", "txt"])
+            .set_file_name("卡片.tsv")
+            .save_file()
+        else {
+            return;
+        };
+        match fs::write(&file, graph.current_snapshot().to_anki_tsv()) {
+            Ok(()) => self.notifications.info("Anki 卡片已导出"),
+            Err(e) => {
+                self.error = Some((
+                    format!("导出 {} 失败", file.as_os_str().to_string_lossy()),
+                    Error::from(e).zh_message(),
+                ))
+            }
+        }
+    }
+
+    /// 选择位置导出坐标表
+    fn export_coordinates(&mut self) {
+        let Some(graph) = self.graph.as_ref() else {
+            return;
+        };
+        let Some(file) = rfd::FileDialog::new()
+            .add_filter("TSV 文件", &["tsv", "txt"])
+            .set_file_name("坐标.tsv")
+            .save_file()
+        else {
+            return;
+        };
+        match fs::write(&file, graph.current_snapshot().to_coordinates_tsv()) {
+            Ok(()) => self.notifications.info("坐标表已导出"),
+            Err(e) => {
+                self.error = Some((
+                    format!("导出 {} 失败", file.as_os_str().to_string_lossy()),
+                    Error::from(e).zh_message(),
+                ))
+            }
+        }
+    }
+
+    /// 选择编辑后的坐标表，按 ID 更新节点的坐标，作为一次可撤回的操作。匹配不上的行列在警告窗口中
+    fn import_coordinates(&mut self) {
+        if self.graph.is_none() {
+            return;
+        }
+        let Some(file) = rfd::FileDialog::new()
+            .add_filter("TSV 文件", &["tsv", "txt"])
+            .pick_file()
+        else {
+            return;
+        };
+        // 只读取 ID 和坐标，其他编码保存的内容列无法识别也不影响
+        let tsv = match fs::read(&file) {
+            Ok(bytes) => graph::decode_xml(&bytes)
+                .unwrap_or_else(|_| String::from_utf8_lossy(&bytes).into_owned()),
+            Err(e) => {
+                self.error = Some((
+                    format!("读取 {} 失败", file.as_os_str().to_string_lossy()),
+                    Error::from(e).zh_message(),
+                ));
+                return;
+            }
+        };
+        let Some(graph) = self.graph.as_mut() else {
+            return;
+        };
+        match graph.import_coordinates(&tsv) {
+            Ok((count, warnings)) => {
+                self.notifications
+                    .info(format!("已更新 {count} 个节点的坐标"));
+                if !warnings.is_empty() {
+                    self.open_warnings =
+                        Some(("导入坐标时以下行未能使用，其余节点已更新：", warnings));
+                }
+            }
+            Err(e) => self.error = Some(("导入坐标失败".to_string(), e.zh_message())),
+        }
+    }
+
+    /// 选择位置导出教学要求矩阵，按扩展名导出为 HTML 表格或 CSV
+    fn export_addon_matrix(&mut self) {
+        let Some(graph) = self.graph.as_ref() else {
+            return;
+        };
+        let Some(file) = rfd::FileDialog::new()
+            .add_filter("CSV 文件", &["csv"])
+            .add_filter("HTML 文件", &["html"])
+            .set_file_name("教学要求矩阵.csv")
+            .save_file()
+        else {
+            return;
+        };
+        let snapshot = graph.current_snapshot();
+        let content = if file.extension().is_some_and(|ext| ext == "html") {
+            snapshot.to_matrix_html()
+        } else {
+            snapshot.to_matrix_csv()
+        };
+        match fs::write(&file, content) {
+            Ok(()) => self.notifications.info("教学要求矩阵已导出"),
+            Err(e) => {
+                self.error = Some((
+                    format!("导出 {} 失败", file.as_os_str().to_string_lossy()),
+                    Error::from(e).zh_message(),
+                ))
+            }
+        }
+    }
+
+    /// 打印窗口：选择横向所占的页数，导出 PDF 后用系统默认程序打开
+    fn show_print_window(&mut self, ctx: &Context) {
+        let (Some(mut pages_across), Some(graph)) = (self.printing, self.graph.as_ref()) else {
+            return;
+        };
+
+        let mut confirm = false;
+        let mut cancel = false;
+        egui::Window::new("打印")
+            .collapsible(false)
+            .resizable(false)
+            .anchor(Align2::CENTER_CENTER, [0.0, 0.0])
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label("横向页数:");
+                    ui.add(egui::DragValue::new(&mut pages_across).range(1..=10));
+                });
+                ui.label(
+                    RichText::new("按 A4 横向排版，纵向按需分页；多页时各页标有行列号，便于拼接")
+                        .small()
+                        .weak(),
+                );
+                ui.horizontal(|ui| {
+                    confirm = ui.button("导出并打开").clicked();
+                    cancel = ui.button("取消").clicked()
+                        || ui.input(|i| i.key_pressed(egui::Key::Escape));
+                });
+            });
+        self.printing = Some(pages_across);
+
+        if cancel {
+            self.printing = None;
+            return;
+        }
+        if !confirm {
+            return;
+        }
+        self.printing = None;
+        let Some(file) = rfd::FileDialog::new()
+            .add_filter("PDF 文件", &["pdf"])
+            .set_file_name("图谱.pdf")
+            .save_file()
+        else {
+            return;
+        };
+        let pdf = graph.current_snapshot().to_pdf(pages_across);
+        match fs::write(&file, pdf) {
+            Ok(()) => {
+                // 系统打印对话框需由 PDF 阅读器提供，本地路径会以默认程序打开
+                ctx.open_url(egui::OpenUrl::new_tab(file.display().to_string()));
+                self.notifications.info("PDF 已导出，请在打开的程序中打印");
+            }
+            Err(e) => {
+                self.error = Some((
+                    format!("导出 {} 失败", file.as_os_str().to_string_lossy()),
+                    Error::from(e).zh_message(),
+                ))
+            }
+        }
+    }
+
+    /// 检查未通过的问题列表，点击有相关节点的问题时选中这些节点并跳转到第一个
+    fn show_export_issues_window(&mut self, ctx: &Context) {
+        let (Some(issues), Some(graph)) = (self.export_issues.as_ref(), self.graph.as_ref()) else {
+            return;
+        };
+
+        let snapshot = graph.current_snapshot();
+        let coordinate_issue = snapshot.coordinate_issue();
+        let has_coordinate_issue = coordinate_issue
+            .as_ref()
+            .is_some_and(|coordinate_issue| issues.contains(coordinate_issue));
+        let coordinate_only = has_coordinate_issue && issues.len() == 1;
+        let has_charset_issue = snapshot
+            .charset_issue()
+            .is_some_and(|charset_issue| issues.contains(&charset_issue));
+        let duplicates = snapshot.cross_level_duplicates();
+        let mut open = true;
+        let mut jump = None;
+        let mut fit = false;
+        let mut strip = false;
+        let mut merge = None;
+        let mut export_anyway = false;
+        egui::Window::new("导出检查未通过")
+            .collapsible(false)
+            .open(&mut open)
+            .anchor(Align2::RIGHT_TOP, [-10.0, TOP_PANEL_HEIGHT + 10.0])
+            .show(ctx, |ui| {
+                ui.label("修改以下问题后再次检查并导出:");
+                egui::ScrollArea::vertical()
+                    .max_height(300.0)
+                    .show(ui, |ui| {
+                        for issue in issues.iter() {
+                            let text =
+                                RichText::new(&issue.message).color(issue_color(issue.level));
+                            let entities = issue
+                                .entities
+                                .iter()
+                                .filter(|id| snapshot.nodes.contains_key(id))
+                                .copied()
+                                .collect::<BTreeSet<_>>();
+                            if entities.is_empty() {
+                                ui.label(text);
+                            } else if ui
+                                .add(egui::Label::new(text).sense(Sense::click()))
+                                .on_hover_text("点击跳转")
+                                .clicked()
+                            {
+                                jump = Some(entities);
+                            }
+
+                            // 同一内容出现在不同层级时，选择保留的节点，其余节点合并到它
+                            if duplicates.contains(&issue.entities) {
+                                ui.menu_button("合并节点", |ui| {
+                                    for id in issue.entities.iter() {
+                                        let node = &snapshot.nodes[id];
+                                        let label = format!(
+                                            "保留{}（ID {id}）",
+                                            node.distinct_type.class_name_abbr()
+                                        );
+                                        if ui
+                                            .button(label)
+                                            .on_hover_text("其余节点的边和备注并入保留的节点后删除")
+                                            .clicked()
+                                        {
+                                            let others = issue
+                                                .entities
+                                                .iter()
+                                                .copied()
+                                                .filter(|other| other != id)
+                                                .collect::<Vec<_>>();
+                                            merge = Some((*id, others));
+                                            ui.close_menu();
+                                        }
+                                    }
+                                });
+                            }
+                        }
+                    });
+
+                // 坐标超出范围不影响文件的格式，可以压缩后重新检查，或确认后仍然导出
+                if has_coordinate_issue {
+                    ui.separator();
+                    ui.horizontal(|ui| {
+                        fit = ui.button("压缩到画布范围").clicked();
+                        if coordinate_only {
+                            export_anyway = ui
+                                .button("仍然导出")
+                                .on_hover_text("超出范围的节点在对接平台上会被裁掉")
+                                .clicked();
+                        }
+                    });
+                }
+                if has_charset_issue {
+                    ui.separator();
+                    strip = ui
+                        .button("移除不支持的字符")
+                        .on_hover_text("从所有节点的内容中移除，可撤销")
+                        .clicked();
+                }
+            });
+
+        if let Some(entities) = jump
+            && let Some(node) = entities.first().map(|id| &snapshot.nodes[id])
+        {
+            let coor = node.coor;
+            self.selected_nodes = entities;
+            self.scroll_to(ctx, coor);
+        }
+        if !open {
+            self.export_issues = None;
+        }
+        if fit {
+            self.fit_to_bounds();
+            self.strict_export(false);
+        } else if strip {
+            self.strip_unsupported_chars();
+            self.strict_export(false);
+        } else if let Some((keep, remove)) = merge {
+            if let Some(graph) = self.graph.as_mut() {
+                match graph.merge_entities(keep, &remove) {
+                    Ok(()) => self.selected_nodes = BTreeSet::from([keep]),
+                    Err(e) => self.error = Some(("合并节点失败".to_string(), e.zh_message())),
+                }
+            }
+            self.strict_export(false);
+        } else if export_anyway {
+            self.strict_export(true);
+        }
+    }
+
+    /// 术语检查的结果，随图谱的修改更新。点击问题跳转到节点，可一键替换为标准写法
+    fn show_term_issues_window(&mut self, ctx: &Context) {
+        if !self.showing_term_issues {
+            return;
+        }
+        let (Some(graph), Some(glossary)) = (self.graph.as_ref(), self.glossary.as_ref()) else {
+            self.showing_term_issues = false;
+            return;
+        };
+        let snapshot = graph.current_snapshot();
+        let issues = snapshot.term_issues(glossary);
+
+        let mut open = true;
+        let mut jump = None;
+        let mut replace = false;
+        egui::Window::new("术语检查")
+            .collapsible(false)
+            .resizable(true)
+            .open(&mut open)
+            .show(ctx, |ui| {
+                if issues.is_empty() {
+                    ui.label("所有节点的术语都符合术语表");
+                    return;
+                }
+                egui::ScrollArea::vertical()
+                    .max_height(300.0)
+                    .show(ui, |ui| {
+                        for issue in issues.iter() {
+                            let text =
+                                RichText::new(&issue.message).color(issue_color(issue.level));
+                            if ui
+                                .add(egui::Label::new(text).sense(Sense::click()))
+                                .on_hover_text("点击跳转")
+                                .clicked()
+                            {
+                                jump = issue.entities.first().copied();
+                            }
+                        }
+                    });
+                ui.separator();
+                replace = ui
+                    .button("全部替换为标准写法")
+                    .on_hover_text("替换所有节点中的非标准写法，可撤销")
+                    .clicked();
+            });
+
+        if let Some(id) = jump
+            && let Some(node) = snapshot.nodes.get(&id)
+        {
+            let coor = node.coor;
+            self.selected_nodes = BTreeSet::from([id]);
+            self.scroll_to(ctx, coor);
+        }
+        if replace
+            && let (Some(graph), Some(glossary)) = (self.graph.as_mut(), self.glossary.as_ref())
+        {
+            match graph.apply_standard_terms(glossary) {
+                Ok(count) => self
+                    .notifications
+                    .info(format!("已替换 {count} 个节点中的非标准术语")),
+                Err(e) => self.error = Some(("替换术语失败".to_string(), e.zh_message())),
+            }
+        }
+        if !open {
+            self.showing_term_issues = false;
+        }
+    }
+
+    /// 按评审标记分组列出节点，点击节点跳转
+    fn show_flag_list_panel(&mut self, ctx: &Context) {
+        if !self.showing_flag_list {
+            return;
+        }
+        let Some(graph) = self.graph.as_ref() else {
+            return;
+        };
+        let snapshot = graph.current_snapshot();
+
+        let mut jump = None;
+        egui::SidePanel::right("标记清单")
+            .resizable(true)
+            .show(ctx, |ui| {
+                ui.heading("标记清单");
+                egui::ScrollArea::vertical().show(ui, |ui| {
+                    for flag in Flag::ALL {
+                        let ids = snapshot.flagged(flag);
+                        ui.separator();
+                        ui.colored_label(
+                            flag.color(),
+                            format!("{} {} ({})", flag.symbol(), flag.label(), ids.len()),
+                        );
+                        for id in ids {
+                            let content = &snapshot.nodes[&id].content;
+                            let selected = self.selected_nodes.contains(&id);
+                            if ui
+                                .selectable_label(selected, content)
+                                .on_hover_text("点击跳转")
+                                .clicked()
+                            {
+                                jump = Some(id);
+                            }
+                        }
+                    }
+                });
+            });
+
+        if let Some(id) = jump
+            && let Some(node) = snapshot.nodes.get(&id)
+        {
+            let coor = node.coor;
+            self.selected_nodes = BTreeSet::from([id]);
+            self.scroll_to(ctx, coor);
+        }
+    }
+
+    /// 移除所有节点内容中对接平台不支持的字符，作为一次可撤回的操作
+    fn strip_unsupported_chars(&mut self) {
+        let Some(graph) = self.graph.as_mut() else {
+            return;
+        };
+        match graph.strip_unsupported_chars() {
+            Ok(count) => self
+                .notifications
+                .info(format!("已移除 {count} 个节点中不支持的字符")),
+            Err(e) => self.error = Some(("移除字符失败".to_string(), e.zh_message())),
+        }
+    }
+
+    /// 滚动画布，使逻辑坐标 coor 位于窗口中央
+    fn scroll_to(&mut self, ctx: &Context, coor: (f64, f64)) {
+        let center = ctx.screen_rect().center();
+        let logical = Pos2::new(coor.0 as f32, coor.1 as f32);
+        let offset = (logical * self.zoom_factor + Vec2::new(0.0, TOP_PANEL_HEIGHT) - center)
+            .max(Vec2::ZERO);
+        self.scroll_offset = offset;
+        self.pending_scroll = Some(offset);
+    }
+
+    /// 快捷键说明窗口，不阻止画布操作，便于对照着使用
+    fn show_shortcuts_window(&mut self, ctx: &Context) {
+        let addon_keys = ADDON_TYPES
+            .iter()
+            .enumerate()
+            .map(|(i, addon_type)| format!("{} {}", i + 1, addon_type.name()))
+            .collect::<Vec<_>>()
+            .join("、");
+        let distinct_keys = DISTINCT_TYPES
+            .iter()
+            .enumerate()
+            .map(|(i, distinct_type)| format!("{} {}", i + 1, distinct_type.class_name_abbr()))
+            .collect::<Vec<_>>()
+            .join("、");
+        let shortcuts = [
+            ("Ctrl + S", "保存".to_string()),
+            ("Ctrl + Z / Ctrl + Y", "撤销 / 恢复".to_string()),
+            ("Ctrl + 单击", "多选节点或边".to_string()),
+            ("Ctrl + N", "在视图中心新建节点".to_string()),
+            ("从节点中心拖到空白处", "新建下级节点并连线".to_string()),
+            ("Delete", "删除选中的节点、边或分组框".to_string()),
+            (
+                "拖动分组框的边框或标签",
+                "移动分组框及其中的节点".to_string(),
+            ),
+            (
+                "拖动分组框右下角",
+                "调整分组框大小，归组的分组框随节点自动调整".to_string(),
+            ),
+            ("数字键", format!("设置选中节点的类型：{distinct_keys}")),
+            (
+                "Alt + 数字键",
+                format!("切换选中节点的附加类型：{addon_keys}，连续切换合并为一次操作"),
+            ),
+            (
+                "F",
+                "循环切换选中节点的评审标记：待办、疑问、已审核、无标记".to_string(),
+            ),
+            (
+                "Ctrl + 数字键",
+                "跳转到快捷位绑定的节点，在节点的右键菜单中绑定".to_string(),
+            ),
+            (
+                "F2 / Enter",
+                "就地编辑选中节点的内容，回车提交，Shift + 回车换行".to_string(),
+            ),
+            ("Ctrl + V", "粘贴多行文本，每行新建一个节点".to_string()),
+            ("Ctrl + Enter", "编辑节点时保存".to_string()),
+            ("Esc", "编辑节点时取消，或退出聚焦".to_string()),
+        ];
+
+        egui::Window::new("快捷键")
+            .collapsible(false)
+            .resizable(false)
+            .open(&mut self.showing_shortcuts)
+            .show(ctx, |ui| {
+                egui::Grid::new("shortcuts").striped(true).show(ui, |ui| {
+                    for (keys, description) in shortcuts {
+                        ui.label(RichText::new(keys).strong());
+                        ui.label(description);
+                        ui.end_row();
+                    }
+                });
+            });
+    }
+
+    /// 折叠菜单：全部展开、全部折叠、展开到指定的层，以及折叠或展开选中节点的子树。
+    /// 层数从各知识领域沿包含关系计算，折叠只影响显示
+    fn show_fold_menu(&mut self, ui: &mut egui::Ui) {
+        let Some(graph) = self.graph.as_ref() else {
+            return;
+        };
+        let snapshot = graph.current_snapshot();
+        if ui
+            .add_enabled(self.folding.is_active(), egui::Button::new("全部展开"))
+            .clicked()
+        {
+            ui.close_menu();
+            self.folding.expand_all();
+        }
+        if ui.button("全部折叠").clicked() {
+            ui.close_menu();
+            self.folding.collapse_all(snapshot);
+        }
+        ui.horizontal(|ui| {
+            ui.label("展开到第");
+            ui.add(egui::DragValue::new(&mut self.fold_level).range(1..=20));
+            ui.label("层");
+            if ui.button("应用").clicked() {
+                ui.close_menu();
+                self.folding.expand_to_level(snapshot, self.fold_level);
+            }
+        });
+        ui.separator();
+        let ids = self.selected_nodes.iter().copied().collect::<Vec<_>>();
+        if ui
+            .add_enabled(!ids.is_empty(), egui::Button::new("折叠选中节点的子树"))
+            .clicked()
+        {
+            ui.close_menu();
+            self.fold_subtrees(&ids, true);
+        }
+        if ui
+            .add_enabled(!ids.is_empty(), egui::Button::new("展开选中节点的子树"))
+            .clicked()
+        {
+            ui.close_menu();
+            self.fold_subtrees(&ids, false);
+        }
+    }
+
+    /// 折叠或展开 ids 及其所有下级节点
+    fn fold_subtrees(&mut self, ids: &[u64], collapse: bool) {
+        let Some(graph) = self.graph.as_ref() else {
+            return;
+        };
+        let snapshot = graph.current_snapshot();
+        if collapse {
+            self.folding.collapse_subtrees(snapshot, ids);
+        } else {
+            self.folding.expand_subtrees(snapshot, ids);
+        }
+    }
+
+    /// 书签下拉菜单：点击书签跳转到记录的视图，也可删除书签或将当前视图添加为书签
+    /// 底图菜单，选择图片并调整其位置、缩放和不透明度，修改后立即写入视图文件
+    fn show_underlay_menu(&mut self, ui: &mut egui::Ui) {
+        let Some(graph) = self.graph.as_ref() else {
+            return;
+        };
+        let before = self.view_sidecar.clone();
+        if ui.button("选择图片").clicked() {
+            ui.close_menu();
+            if let Some(file) = rfd::FileDialog::new()
+                .add_filter("图片", &["png", "jpg", "jpeg"])
+                .pick_file()
+            {
+                let underlay = self
+                    .view_sidecar
+                    .underlay
+                    .get_or_insert_with(Underlay::default);
+                underlay.path = file;
+                underlay.visible = true;
+            }
+        }
+        if let Some(underlay) = self.view_sidecar.underlay.as_mut() {
+            let name = underlay
+                .path
+                .file_name()
+                .map_or_else(String::new, |name| name.to_string_lossy().to_string());
+            ui.label(RichText::new(name).color(Color32::GRAY));
+            ui.checkbox(&mut underlay.visible, "显示底图");
+            ui.horizontal(|ui| {
+                ui.label("缩放:");
+                ui.add(
+                    egui::DragValue::new(&mut underlay.scale)
+                        .speed(0.01)
+                        .range(0.01..=100.0),
+                );
+            });
+            ui.horizontal(|ui| {
+                ui.label("左上角:");
+                ui.add(egui::DragValue::new(&mut underlay.offset.0));
+                ui.add(egui::DragValue::new(&mut underlay.offset.1));
+            });
+            ui.add(egui::Slider::new(&mut underlay.opacity, 0.0..=1.0).text("不透明度"));
+            if ui.button("移除底图").clicked() {
+                self.view_sidecar.underlay = None;
+                ui.close_menu();
+            }
+        }
+
+        if self.view_sidecar != before
+            && let Err(e) = self.view_sidecar.save(&graph.file_path)
+        {
+            self.notifications
+                .warning(format!("保存视图设置失败：{}", e.zh_message()));
+        }
+    }
+
+    /// 底图图片变化时重新读取并上传纹理，读取失败时隐藏底图并提示
+    fn update_underlay_texture(&mut self, ctx: &Context) {
+        let Some(underlay) = self.view_sidecar.underlay.as_mut() else {
+            self.underlay_texture = None;
+            return;
+        };
+        if !underlay.visible
+            || self
+                .underlay_texture
+                .as_ref()
+                .is_some_and(|(path, _)| *path == underlay.path)
+        {
+            return;
+        }
+        match underlay::load_image(&underlay.path) {
+            Ok(image) => {
+                let texture = ctx.load_texture("底图", image, egui::TextureOptions::LINEAR);
+                self.underlay_texture = Some((underlay.path.clone(), texture));
+            }
+            Err(e) => {
+                underlay.visible = false;
+                self.notifications
+                    .warning(format!("读取底图失败：{}", e.zh_message()));
+            }
+        }
+    }
+
+    /// 以与节点相同的变换绘制底图，随画布一起平移和缩放
+    fn draw_underlay(&self, painter: &Painter) {
+        let (Some(underlay), Some((_, texture))) = (
+            self.view_sidecar.underlay.as_ref(),
+            self.underlay_texture.as_ref(),
+        ) else {
+            return;
+        };
+        if !underlay.visible {
+            return;
+        }
+        let min = self.logical_to_screen((underlay.offset.0 as f64, underlay.offset.1 as f64));
+        let size = texture.size_vec2() * underlay.scale * self.zoom_factor;
+        painter.image(
+            texture.id(),
+            Rect::from_min_size(min, size),
+            Rect::from_min_max(Pos2::ZERO, Pos2::new(1.0, 1.0)),
+            Color32::WHITE.gamma_multiply(underlay.opacity),
+        );
+    }
+
+    fn show_bookmark_menu(&mut self, ui: &mut egui::Ui) {
+        let Some(graph) = self.graph.as_ref() else {
+            return;
+        };
+        let bookmarks = graph.current_snapshot().metadata.bookmarks.clone();
+
+        let mut removed = None;
+        for (index, bookmark) in bookmarks.iter().enumerate() {
+            ui.horizontal(|ui| {
+                if ui.button(&bookmark.name).clicked() {
+                    ui.close_menu();
+                    self.zoom_factor = bookmark.zoom;
+                    self.scroll_offset = bookmark.scroll_offset.into();
+                    self.pending_scroll = Some(self.scroll_offset);
+                }
+                if ui.small_button("🗑").on_hover_text("删除书签").clicked() {
+                    removed = Some(index);
+                }
+            });
+        }
+        if !bookmarks.is_empty() {
+            ui.separator();
+        }
+        if ui.button("添加当前视图").clicked() {
+            ui.close_menu();
+            self.naming_bookmark = Some(format!("书签 {}", bookmarks.len() + 1));
+        }
+
+        if let Some(index) = removed
+            && let Some(graph) = self.graph.as_mut()
+        {
+            let mut metadata = graph.current_snapshot().metadata.clone();
+            metadata.bookmarks.remove(index);
+            graph.set_metadata(metadata);
+        }
+    }
+
+    /// 书签命名窗口，确定后将当前视图保存为书签，与已有书签重名时覆盖该书签
+    fn show_bookmark_window(&mut self, ctx: &Context) {
+        let Some(name) = self.naming_bookmark.as_mut() else {
+            return;
+        };
+
+        let mut confirmed = false;
+        let mut cancelled = false;
+        egui::Window::new("添加书签")
+            .collapsible(false)
+            .resizable(false)
+            .anchor(Align2::CENTER_CENTER, [0.0, 0.0])
+            .show(ctx, |ui| {
+                let response = ui.text_edit_singleline(name);
+                response.request_focus();
+                let entered =
+                    response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter));
+
+                ui.horizontal(|ui| {
+                    confirmed = (ui.button("确定").clicked() || entered) && !name.trim().is_empty();
+                    cancelled = ui.button("取消").clicked();
+                });
+            });
+
+        if confirmed && let Some(name) = self.naming_bookmark.take() {
+            if let Some(graph) = self.graph.as_mut() {
+                let bookmark = Bookmark {
+                    name: name.trim().to_string(),
+                    scroll_offset: self.scroll_offset.into(),
+                    zoom: self.zoom_factor,
+                };
+                let mut metadata = graph.current_snapshot().metadata.clone();
+                match metadata
+                    .bookmarks
+                    .iter_mut()
+                    .find(|b| b.name == bookmark.name)
+                {
+                    Some(existing) => *existing = bookmark,
+                    None => metadata.bookmarks.push(bookmark),
+                }
+                graph.set_metadata(metadata);
+            }
+        } else if cancelled {
+            self.naming_bookmark = None;
+        }
+    }
+
+    /// 编辑分组框的标签和颜色，新建的分组框在确定时才添加
+    fn show_group_edit_window(&mut self, ctx: &Context) {
+        let Some(group) = self.editing_group.as_mut() else {
+            return;
+        };
+
+        let mut confirmed = false;
+        let mut cancelled = false;
+        let title = if group.id == 0 {
+            "新建分组框"
+        } else {
+            "编辑分组框"
+        };
+        egui::Window::new(title)
+            .collapsible(false)
+            .resizable(false)
+            .anchor(Align2::CENTER_CENTER, [0.0, 0.0])
+            .show(ctx, |ui| {
+                let mut entered = false;
+                ui.horizontal(|ui| {
+                    ui.label("标签:");
+                    let response = ui.text_edit_singleline(&mut group.label);
+                    entered =
+                        response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter));
+                });
+                ui.horizontal(|ui| {
+                    ui.label("颜色:");
+                    ui.color_edit_button_srgb(&mut group.color);
+                });
+                if !group.members.is_empty() {
+                    ui.label(format!(
+                        "包含 {} 个节点，范围随节点自动调整",
+                        group.members.len()
+                    ));
+                }
+
+                ui.horizontal(|ui| {
+                    confirmed = ui.button("确定").clicked() || entered;
+                    cancelled = ui.button("取消").clicked()
+                        || ui.input(|i| i.key_pressed(egui::Key::Escape));
+                });
+            });
+
+        if confirmed && let Some(group) = self.editing_group.take() {
+            let Some(graph) = self.graph.as_mut() else {
+                return;
+            };
+            let label = group.label.trim().to_string();
+            if group.id == 0 {
+                match graph.add_group(label, group.rect, group.color, &group.members) {
+                    Ok(id) => self.selected_group = Some(id),
+                    Err(e) => self.error = Some(("新建分组框失败".to_string(), e.zh_message())),
+                }
+            } else {
+                dialog_error!(
+                    self,
+                    graph.update_group(GroupFrame { label, ..group }),
+                    &[],
+                    "修改分组框失败"
+                );
+            }
+        } else if cancelled {
+            self.editing_group = None;
+        }
+    }
+
+    fn show_metadata_window(&mut self, ctx: &Context) {
+        let Some(metadata) = self.editing_metadata.as_mut() else {
+            return;
+        };
+
+        let mut confirmed = false;
+        let mut cancelled = false;
+        egui::Window::new("图谱信息")
+            .collapsible(false)
+            .resizable(false)
+            .anchor(Align2::CENTER_CENTER, [0.0, 0.0])
+            .show(ctx, |ui| {
+                limit_editor(ui, "限制节点数", &mut metadata.max_nodes, DEFAULT_MAX_NODES);
+                limit_editor(ui, "限制边数", &mut metadata.max_edges, DEFAULT_MAX_EDGES);
+                ui.horizontal(|ui| {
+                    let mut limit = metadata.content_limit();
+                    ui.label("节点内容建议字数上限:");
+                    if ui
+                        .add(egui::DragValue::new(&mut limit).range(1..=usize::MAX))
+                        .changed()
+                    {
+                        metadata.max_content_chars =
+                            (limit != DEFAULT_CONTENT_LIMIT).then_some(limit);
+                    }
+                });
+                ui.horizontal(|ui| {
+                    let mut bounds = metadata.coordinate_bounds();
+                    let max = bounds.max;
+                    let min = bounds.min;
+                    ui.label("节点坐标范围:");
+                    let changed = [
+                        ui.add(egui::DragValue::new(&mut bounds.min.0).range(f64::MIN..=max.0)),
+                        ui.add(egui::DragValue::new(&mut bounds.min.1).range(f64::MIN..=max.1)),
+                        ui.label("至"),
+                        ui.add(egui::DragValue::new(&mut bounds.max.0).range(min.0..=f64::MAX)),
+                        ui.add(egui::DragValue::new(&mut bounds.max.1).range(min.1..=f64::MAX)),
+                    ]
+                    .iter()
+                    .any(|response| response.changed());
+                    if changed {
+                        metadata.coordinate_bounds =
+                            (bounds != DEFAULT_COORDINATE_BOUNDS).then_some(bounds);
+                    }
+                });
+                ui.checkbox(&mut metadata.restrict_charset, "检查对接平台不支持的字符")
+                    .on_hover_text("如 emoji 和控制字符，导入对接平台后会显示为乱码");
+
+                ui.horizontal(|ui| {
+                    confirmed = ui.button("确定").clicked();
+                    cancelled = ui.button("取消").clicked();
+                });
+            });
+
+        if confirmed && let Some(metadata) = self.editing_metadata.take() {
+            if let Some(graph) = self.graph.as_mut()
+                && graph.current_snapshot().metadata != metadata
+            {
+                graph.set_metadata(metadata);
+            }
+        } else if cancelled {
+            self.editing_metadata = None;
+        }
+    }
+}
+
+impl DistinctEntityType {
+    fn class_name_abbr(&self) -> &str {
+        match *self {
+            DistinctEntityType::KnowledgeArena => "知识领域",
+            DistinctEntityType::KnowledgePoint => "知识点",
+            DistinctEntityType::KnowledgeDetail => "知识细节",
+            DistinctEntityType::KnowledgeUnit => "知识单元",
+        }
+    }
+}
+
+impl Relation {
+    /// 界面中的名称，来自关系类型表
+    fn label(&self) -> Cow<'static, str> {
+        self.name()
+    }
+
+    /// 画布上边的颜色，关系类型表中没有配置颜色时为黑色
+    fn edge_color(&self) -> Color32 {
+        self.color()
+            .map_or(Color32::BLACK, |[r, g, b]| Color32::from_rgb(r, g, b))
+    }
+}
+
+impl AddonEntityType {
+    fn name(&self) -> &str {
+        match *self {
+            AddonEntityType::Example => "示例",
+            AddonEntityType::Question => "问题",
+            AddonEntityType::Practice => "练习",
+            AddonEntityType::Thinking => "思考",
+            AddonEntityType::Knowledge => "知识",
+            AddonEntityType::Political => "思政",
+        }
+    }
+}
+
+impl Flag {
+    fn label(&self) -> &'static str {
+        match *self {
+            Flag::Todo => "待办",
+            Flag::Question => "疑问",
+            Flag::Approved => "已审核",
+        }
+    }
+
+    /// 画布上徽章中的符号，与颜色一起区分标记
+    fn symbol(&self) -> &'static str {
+        match *self {
+            Flag::Todo => "!",
+            Flag::Question => "?",
+            Flag::Approved => "✔",
+        }
+    }
+
+    fn color(&self) -> Color32 {
+        match *self {
+            Flag::Todo => Color32::from_rgb(230, 140, 20),
+            Flag::Question => Color32::from_rgb(54, 131, 248),
+            Flag::Approved => Color32::from_rgb(46, 160, 67),
+        }
+    }
+}
+
+/// 绘制选择节点类型的单选框
+/// 编辑框中的链接去掉首尾空白，忽略空行
+fn edited_links(links: &[String]) -> Vec<String> {
+    links
+        .iter()
+        .map(|link| link.trim().to_string())
+        .filter(|link| !link.is_empty())
+        .collect()
+}
+
+fn issue_color(level: IssueLevel) -> Color32 {
+    match level {
+        IssueLevel::Info => Color32::from_rgb(54, 131, 248),
+        IssueLevel::Warning => Color32::from_rgb(230, 140, 20),
+        IssueLevel::Error => Color32::from_rgb(220, 50, 50),
+    }
+}
+
+fn distinct_type_radios(ui: &mut egui::Ui, value: &mut DistinctEntityType) {
+    ui.radio_value(value, DistinctEntityType::KnowledgeArena, "知识领域");
+    ui.radio_value(value, DistinctEntityType::KnowledgePoint, "知识点");
+    ui.radio_value(value, DistinctEntityType::KnowledgeDetail, "知识细节");
+    ui.radio_value(value, DistinctEntityType::KnowledgeUnit, "知识单元");
+}
+
+/// 绘制边的折点的拖动柄
+fn draw_waypoint_handle(painter: &Painter, pos: Pos2) {
+    painter.circle(
+        pos,
+        WAYPOINT_HANDLE_RADIUS,
+        Color32::WHITE,
+        Stroke::new(2.0, Color32::RED),
+    );
+}
+
+/// 折线上绘制关系标记的一段：箭头在最后一段，其余在中间的一段
+fn marker_segment(path: &[Pos2], marker: MarkerStyle) -> (Pos2, Pos2) {
+    let segments = path.len().saturating_sub(1).max(1);
+    let index = match marker {
+        MarkerStyle::Arrow => segments - 1,
+        MarkerStyle::Semicircle | MarkerStyle::Circle | MarkerStyle::Plain => (segments - 1) / 2,
+    };
+    match (path.get(index), path.get(index + 1)) {
+        (Some(start), Some(end)) => (*start, *end),
+        _ => (Pos2::ZERO, Pos2::ZERO),
+    }
+}
+
+/// 关系符号可沿边错开的范围：箭头尖端从终点沿最后一段后退，其余的从中间一段的中点前后移动，
+/// 都不越过该段的两端。边标签按不绘制符号的样式取中间一段
+fn marker_anchor(path: &[Pos2], marker: MarkerStyle) -> Anchor {
+    let (start, end) = marker_segment(path, marker);
+    let length = start.distance(end);
+    let dir = (end - start).normalized();
+    match marker {
+        MarkerStyle::Arrow => Anchor {
+            pos: end,
+            dir: -dir,
+            range: (0.0, (length - 2.0 * MARKER_SIZE).max(0.0)),
+        },
+        MarkerStyle::Semicircle | MarkerStyle::Circle | MarkerStyle::Plain => {
+            let half = (length / 2.0 - MARKER_SIZE).max(0.0);
+            Anchor {
+                pos: start + (end - start) / 2.0,
+                dir,
+                range: (-half, half),
+            }
+        }
+    }
+}
+
+/// 关系符号错开 shift 后传给 draw_relation_marker 的一段：箭头尖端后退 shift，
+/// 其余的整段平移 shift 使中点移动
+fn shifted_marker_segment(path: &[Pos2], marker: MarkerStyle, shift: f32) -> (Pos2, Pos2) {
+    let (start, end) = marker_segment(path, marker);
+    let offset = (end - start).normalized() * shift;
+    match marker {
+        MarkerStyle::Arrow => (start, end - offset),
+        MarkerStyle::Semicircle | MarkerStyle::Circle | MarkerStyle::Plain => {
+            (start + offset, end + offset)
+        }
+    }
+}
+
+/// 在 start 到 end 的连线上按样式绘制关系的标记：箭头在终点处，半圆和空心圆在中点处。
+/// 内置关系中次序为箭头，包含为半圆，扩展为空心圆
+fn draw_relation_marker(
+    painter: &Painter,
+    start: Pos2,
+    end: Pos2,
+    marker: MarkerStyle,
+    stroke: Stroke,
+) {
+    let tip_length = MARKER_SIZE;
+    match marker {
+        MarkerStyle::Arrow => {
+            // 绘制箭头，箭头尖端停在后继节点的边缘
+            let rot = Rot2::from_angle(std::f32::consts::TAU / 10.0);
+            let dir = (end - start).normalized();
+            painter.line_segment([end, end - tip_length * (rot * dir)], stroke);
+            painter.line_segment([end, end - tip_length * (rot.inverse() * dir)], stroke);
+        }
+        MarkerStyle::Circle => {
+            // 在边中点绘制空心圆
+            let mid = start + (end - start) / 2.0;
+            painter.circle_stroke(mid, tip_length / 2.0, stroke);
+        }
+        MarkerStyle::Semicircle => {
+            // 绘制半圆
+            // 以边中点作为半圆中心，半径可以根据需要调整（这里使用 tip_length 作为半径示例）
+            let radius = tip_length;
+            // 计算边的方向角
+            let line_angle = (end - start).angle();
+            // 设定起始角度，使半圆向上凸出（相对于线段方向）
+            let start_angle = line_angle - std::f32::consts::FRAC_PI_2;
+            let end_angle = start_angle + std::f32::consts::PI;
+            let steps = 20; // 分段数，可调节平滑程度
+            let mut arc_points = Vec::with_capacity(steps + 1);
+            let mid = Pos2::new(start.x * 0.5 + end.x * 0.5, start.y * 0.5 + end.y * 0.5);
+            for i in 0..=steps {
+                let a = start_angle + (end_angle - start_angle) * (i as f32 / steps as f32);
+                // 使用 mid 作为圆弧中心
+                let p = mid + Vec2::new(a.cos(), a.sin()) * radius;
+                arc_points.push(p);
+            }
+            painter.add(egui::Shape::line(arc_points, stroke));
+        }
+        MarkerStyle::Plain => {}
+    }
+}
+
+fn relation_radios(ui: &mut egui::Ui, value: &mut Relation) {
+    for relation in relation_registry().relations() {
+        ui.radio_value(value, relation, relation.label());
+    }
+}
+
+/// 节点右下角显示的附加类型文字。未展开时，缩放过小只显示数量，
+/// 超过 ADDON_COLLAPSE_COUNT 个时只显示前两个和剩余数量
+fn addon_label(names: &[&str], expanded: bool, zoom: f32) -> String {
+    if expanded {
+        names.join(" ")
+    } else if zoom < ADDON_BADGE_ZOOM {
+        names.len().to_string()
+    } else if names.len() > ADDON_COLLAPSE_COUNT {
+        format!("{} +{}", names[..2].join(" "), names.len() - 2)
+    } else {
+        names.join(" ")
+    }
+}
+
+/// 当前各节点的位置
+fn node_positions(graph: &FiledKnowledgeGraph) -> HashMap<u64, (f64, f64)> {
+    graph
+        .current_snapshot()
+        .nodes
+        .values()
+        .map(|node| (node.id, node.coor))
+        .collect()
+}
+
+/// 将逻辑坐标对齐到逻辑网格，避免保存的坐标因 DPI 缩放带有小数误差
+#[inline]
+fn snap_to_grid(coor: (f64, f64)) -> (f64, f64) {
+    (
+        (coor.0 / LOGICAL_GRID).round() * LOGICAL_GRID,
+        (coor.1 / LOGICAL_GRID).round() * LOGICAL_GRID,
+    )
+}
+
+/// 绘制可选的数量上限，勾选后才可编辑数值
+fn limit_editor(ui: &mut egui::Ui, label: &str, value: &mut Option<usize>, default: usize) {
+    ui.horizontal(|ui| {
+        let mut enabled = value.is_some();
+        if ui.checkbox(&mut enabled, label).changed() {
+            *value = enabled.then_some(default);
+        }
+        if let Some(max) = value.as_mut() {
+            ui.add(egui::DragValue::new(max).range(1..=usize::MAX));
+        }
+    });
+}
+
+/// 将粘贴的多行文本拆分为 (内容, 备注)，忽略空行。
+/// 超过长度限制的行截断为内容，剩余部分放入备注。
+fn split_pasted_text(text: &str) -> Vec<(String, String)> {
+    text.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .map(|line| {
+            let mut chars = line.chars();
+            let content = chars.by_ref().take(PASTE_CONTENT_LIMIT).collect();
+            let notes = chars.collect();
+            (content, notes)
+        })
+        .collect()
+}
+
+/// 从矩形中心 center 指向 toward 的射线与矩形边框的交点，half_size 为矩形的半宽和半高。
+/// toward 与中心重合时返回中心。
+fn rect_boundary_point(center: Pos2, toward: Pos2, half_size: Vec2) -> Pos2 {
+    let dir = toward - center;
+    let scale_x = if dir.x.abs() > f32::EPSILON {
+        half_size.x / dir.x.abs()
+    } else {
+        f32::INFINITY
+    };
+    let scale_y = if dir.y.abs() > f32::EPSILON {
+        half_size.y / dir.y.abs()
+    } else {
+        f32::INFINITY
+    };
+    let scale = scale_x.min(scale_y);
+    if scale.is_finite() {
+        center + dir * scale
+    } else {
+        center
+    }
+}
+
+fn distance_point_to_segment(point: Pos2, start: Pos2, end: Pos2) -> f32 {
+    let dx = end.x - start.x;
+    let dy = end.y - start.y;
+
+    if dx == 0.0 && dy == 0.0 {
+        // 线段是一个点
+        return point.distance(start);
+    }
+
+    let t = ((point.x - start.x) * dx + (point.y - start.y) * dy) / (dx * dx + dy * dy);
+
+    if t <= 0.0 {
+        // 投影点在线段起点之前
+        point.distance(start)
+    } else if t >= 1.0 {
+        // 投影点在线段终点之后
+        point.distance(end)
+    } else {
+        // 投影点在线段上
+        let projection = Pos2 {
+            x: start.x + t * dx,
+            y: start.y + t * dy,
+        };
+        point.distance(projection)
+    }
+}
@@ -0,0 +1,148 @@
+//! 非阻塞通知模块，用于在界面角落短暂显示提示和警告，自动消失且可堆叠多条。
+//! 真正的失败仍然使用模态窗口显示。
+
+use std::{
+    collections::VecDeque,
+    time::{Duration, Instant},
+};
+
+/// 同时显示的通知数量上限
+const MAX_NOTIFICATIONS: usize = 5;
+
+/// 通知的严重程度
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    Info,    // 提示
+    Warning, // 警告
+}
+
+impl Severity {
+    /// 通知显示的时长
+    pub fn duration(&self) -> Duration {
+        match *self {
+            Severity::Info => Duration::from_secs(2),
+            Severity::Warning => Duration::from_secs(4),
+        }
+    }
+}
+
+/// 一条通知
+#[derive(Debug, Clone)]
+pub struct Notification {
+    pub severity: Severity,
+    pub message: String,
+    created: Instant,
+}
+
+impl Notification {
+    /// 通知是否已过期
+    #[inline]
+    fn is_expired(&self, now: Instant) -> bool {
+        now.saturating_duration_since(self.created) >= self.severity.duration()
+    }
+}
+
+/// 通知队列
+#[derive(Debug, Default)]
+pub struct Notifications {
+    queue: VecDeque<Notification>,
+}
+
+impl Notifications {
+    /// 添加一条提示
+    #[inline]
+    pub fn info(&mut self, message: impl Into<String>) {
+        self.push(Severity::Info, message.into(), Instant::now());
+    }
+
+    /// 添加一条警告
+    #[inline]
+    pub fn warning(&mut self, message: impl Into<String>) {
+        self.push(Severity::Warning, message.into(), Instant::now());
+    }
+
+    /// 添加一条通知。
+    /// 如果最新的通知与其相同，则只刷新其时间，避免重复堆叠。
+    fn push(&mut self, severity: Severity, message: String, now: Instant) {
+        if let Some(last) = self.queue.back_mut()
+            && last.severity == severity
+            && last.message == message
+        {
+            last.created = now;
+            return;
+        }
+
+        // 超过上限时删除最早的通知
+        if self.queue.len() >= MAX_NOTIFICATIONS {
+            self.queue.pop_front();
+        }
+        self.queue.push_back(Notification {
+            severity,
+            message,
+            created: now,
+        });
+    }
+
+    /// 删除已过期的通知
+    pub fn remove_expired(&mut self, now: Instant) {
+        self.queue.retain(|n| !n.is_expired(now));
+    }
+
+    /// 遍历当前的通知，从旧到新
+    #[inline]
+    pub fn iter(&self) -> impl Iterator<Item = &Notification> {
+        self.queue.iter()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.queue.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_expire() {
+        let now = Instant::now();
+        let mut notifications = Notifications::default();
+        notifications.push(Severity::Info, "info".to_string(), now);
+        notifications.push(Severity::Warning, "warning".to_string(), now);
+
+        // 提示比警告先过期
+        notifications.remove_expired(now + Duration::from_secs(3));
+        let messages = notifications
+            .iter()
+            .map(|n| n.message.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(messages, vec!["warning"]);
+
+        notifications.remove_expired(now + Duration::from_secs(5));
+        assert!(notifications.is_empty());
+    }
+
+    #[test]
+    fn test_stack_and_dedup() {
+        let now = Instant::now();
+        let mut notifications = Notifications::default();
+        for i in 0..(MAX_NOTIFICATIONS + 2) {
+            notifications.push(Severity::Info, format!("info {i}"), now);
+        }
+        // 超过上限时丢弃最早的
+        assert_eq!(notifications.iter().count(), MAX_NOTIFICATIONS);
+        assert_eq!(notifications.iter().next().unwrap().message, "info 2");
+
+        // 与最新通知相同时只刷新时间
+        let later = now + Duration::from_secs(1);
+        notifications.push(
+            Severity::Info,
+            format!("info {}", MAX_NOTIFICATIONS + 1),
+            later,
+        );
+        assert_eq!(notifications.iter().count(), MAX_NOTIFICATIONS);
+        notifications.remove_expired(now + Duration::from_millis(2500));
+        assert_eq!(notifications.iter().count(), 1);
+    }
+}
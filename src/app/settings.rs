@@ -0,0 +1,253 @@
+//! 设置模块，保存用户的偏好设置。设置由 eframe 持久化，下次启动时恢复。
+
+use std::{ops::Range, path::PathBuf, time::Duration};
+
+use serde::{Deserialize, Serialize};
+
+use super::theme::{Palette, Theme};
+use crate::{
+    error::SerdeError,
+    file::AutosaveTiming,
+    graph::{AddonEntityType, DistinctEntityType, LayoutOptions},
+};
+
+/// 持久化设置使用的键
+pub const SETTINGS_KEY: &str = "settings";
+
+/// 最多记录的最近文件数
+const RECENT_FILES_LIMIT: usize = 10;
+
+/// 节点绘制的细节层次，缩放越小绘制的内容越少
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Detail {
+    Blocks, // 只绘制色块和边框
+    Labels, // 另绘制类型、附加类型等标签框，但不绘制文字
+    Full,   // 全部绘制
+}
+
+/// 用户设置
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)] // 旧版本保存的设置缺少字段时使用默认值
+pub struct Settings {
+    pub palette: Palette,                     // 节点配色方案
+    pub animations: bool,                     // 节点位置批量变化时是否显示过渡动画
+    pub flow_animation: bool,                 // 是否沿次序边显示流向动画，关闭可省电
+    pub inertial_scroll: bool,                // 双指或滚轮平移画布后是否继续惯性滑动
+    pub zoom_around_selection: bool, // 只选中一个节点时是否以该节点为中心缩放，否则以鼠标位置为中心
+    pub stagger_markers: bool,       // 是否沿边错开多条边交汇处重叠的关系符号和边标签
+    pub lod_text_zoom: f32,          // 缩放低于此值时节点不绘制文字，只绘制色块和标签框
+    pub lod_label_zoom: f32,         // 缩放低于此值时连标签框也不绘制，只绘制色块
+    pub double_click_ms: u64,        // 两次单击的最大间隔，小于此值视为双击
+    pub double_click_distance: f32,  // 鼠标双击两次位置的最大距离
+    pub long_press_ms: u64,          // 触摸时长按多久视为右键点击
+    pub double_tap_tolerance: f32,   // 触摸时双击两次位置的最大距离，也是长按允许的移动距离
+    pub templates: Vec<NodeTemplate>, // 节点模板，可从模板新建节点，或编辑节点时一键填充类型
+    pub layout: LayoutOptions,       // 自动排版的方向和间距
+    pub unit_export_arena: bool,     // 批量导出单元时是否包含所属的知识领域节点
+    pub glossary_path: Option<PathBuf>, // 标准术语表文件，设置后可检查节点内容中的非标准术语
+    pub relation_types_path: Option<PathBuf>, // 关系类型表文件，设置后可使用其中的自定义关系
+    pub journal: bool,               // 是否将每次修改记录到图谱文件旁的操作日志
+    pub delta_log: bool,             // 是否将未保存的修改记录到增量日志，供崩溃后恢复
+    pub thumbnail: bool,             // 保存时是否生成缩略图，供文件管理器等预览
+    pub move_long_content: bool,     // 打开文件时是否将超长的节点内容移到备注
+    pub autosave_quiet_ms: u64,      // 最后一次修改后经过多久没有新的修改才自动保存
+    pub autosave_max_delay_ms: u64,  // 持续修改时最迟多久自动保存一次
+    pub recent_files: Vec<PathBuf>,  // 最近打开的文件，最近的在前
+}
+
+/// 节点模板，保存常用的节点类型、附加类型组合和内容片段
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct NodeTemplate {
+    pub name: String,
+    pub distinct_type: DistinctEntityType,
+    pub addon_types: Vec<AddonEntityType>,
+    #[serde(default)] // 旧版本保存的模板没有内容
+    pub content: String, // 预填的内容，其中的占位符在新建节点时被选中
+}
+
+impl NodeTemplate {
+    /// 内容中占位符的字符范围，即第一段至少两个连续的下划线，如“实验：____”中的“____”
+    pub fn placeholder(&self) -> Option<Range<usize>> {
+        let chars = self.content.chars().collect::<Vec<_>>();
+        let mut start = 0;
+        while start < chars.len() {
+            if chars[start] != '_' {
+                start += 1;
+                continue;
+            }
+            let end = start + chars[start..].iter().take_while(|c| **c == '_').count();
+            if end - start >= 2 {
+                return Some(start..end);
+            }
+            start = end;
+        }
+        None
+    }
+
+    /// 将模板列表导出为 JSON，便于与他人共享
+    pub fn list_to_json(templates: &[NodeTemplate]) -> Result<String, SerdeError> {
+        Ok(serde_json::to_string_pretty(templates)?)
+    }
+
+    /// 从 JSON 读取模板列表
+    pub fn list_from_json(json: &str) -> Result<Vec<NodeTemplate>, SerdeError> {
+        Ok(serde_json::from_str(json)?)
+    }
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            palette: Palette::default(),
+            animations: true,
+            flow_animation: true,
+            inertial_scroll: true,
+            zoom_around_selection: false,
+            stagger_markers: true,
+            lod_text_zoom: 0.7,
+            lod_label_zoom: 0.6,
+            double_click_ms: 300,
+            double_click_distance: 5.0,
+            long_press_ms: 500,
+            double_tap_tolerance: 20.0,
+            templates: Vec::new(),
+            layout: LayoutOptions::default(),
+            unit_export_arena: true,
+            glossary_path: None,
+            relation_types_path: None,
+            journal: false,
+            delta_log: false,
+            thumbnail: true,
+            move_long_content: false,
+            autosave_quiet_ms: 2000,
+            autosave_max_delay_ms: 30_000,
+            recent_files: Vec::new(),
+        }
+    }
+}
+
+impl Settings {
+    /// 根据设置生成绘制使用的主题
+    #[inline]
+    pub fn theme(&self) -> Theme {
+        self.palette.into()
+    }
+
+    /// 按简化渲染的阈值确定缩放为 zoom 时的细节层次
+    pub fn detail(&self, zoom: f32) -> Detail {
+        if zoom >= self.lod_text_zoom {
+            Detail::Full
+        } else if zoom >= self.lod_label_zoom {
+            Detail::Labels
+        } else {
+            Detail::Blocks
+        }
+    }
+
+    /// 自动保存的时机
+    pub fn autosave_timing(&self) -> AutosaveTiming {
+        AutosaveTiming {
+            quiet_period: Duration::from_millis(self.autosave_quiet_ms),
+            max_delay: Duration::from_millis(self.autosave_max_delay_ms),
+        }
+    }
+
+    /// 将文件记录为最近打开的文件，已有时移到最前
+    pub fn add_recent_file(&mut self, path: PathBuf) {
+        self.recent_files.retain(|recent| *recent != path);
+        self.recent_files.insert(0, path);
+        self.recent_files.truncate(RECENT_FILES_LIMIT);
+    }
+
+    /// 保存模板，已有同名模板时替换
+    pub fn save_template(&mut self, template: NodeTemplate) {
+        match self.templates.iter_mut().find(|t| t.name == template.name) {
+            Some(existing) => *existing = template,
+            None => self.templates.push(template),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn template(name: &str, content: &str) -> NodeTemplate {
+        NodeTemplate {
+            name: name.to_string(),
+            distinct_type: DistinctEntityType::KnowledgePoint,
+            addon_types: vec![AddonEntityType::Practice],
+            content: content.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_template_placeholder() {
+        assert_eq!(
+            template("实验", "实验：____（含评分标准）").placeholder(),
+            Some(3..7)
+        );
+        // 单个下划线不是占位符
+        assert_eq!(template("变量", "a_b：__").placeholder(), Some(4..6));
+        assert_eq!(template("空", "").placeholder(), None);
+        assert_eq!(template("无占位符", "定义_").placeholder(), None);
+    }
+
+    #[test]
+    fn test_detail() {
+        let settings = Settings::default();
+        assert_eq!(settings.detail(1.0), Detail::Full);
+        assert_eq!(settings.detail(settings.lod_text_zoom), Detail::Full);
+        assert_eq!(settings.detail(0.65), Detail::Labels);
+        assert_eq!(settings.detail(0.5), Detail::Blocks);
+    }
+
+    #[test]
+    fn test_recent_files() {
+        let mut settings = Settings::default();
+        for i in 0..=RECENT_FILES_LIMIT {
+            settings.add_recent_file(PathBuf::from(format!("{i}.xml")));
+        }
+        assert_eq!(settings.recent_files.len(), RECENT_FILES_LIMIT);
+        assert_eq!(
+            settings.recent_files[0],
+            PathBuf::from(format!("{RECENT_FILES_LIMIT}.xml"))
+        );
+
+        // 再次打开时移到最前，不重复记录
+        settings.add_recent_file(PathBuf::from("5.xml"));
+        assert_eq!(settings.recent_files.len(), RECENT_FILES_LIMIT);
+        assert_eq!(settings.recent_files[0], PathBuf::from("5.xml"));
+        assert_eq!(
+            settings
+                .recent_files
+                .iter()
+                .filter(|path| path.as_path() == std::path::Path::new("5.xml"))
+                .count(),
+            1
+        );
+    }
+
+    #[test]
+    fn test_template_persistence() {
+        let mut settings = Settings::default();
+        settings.save_template(template("实验", "实验：____"));
+        settings.save_template(template("实验", "实验：____（含评分标准）"));
+        assert_eq!(settings.templates.len(), 1);
+
+        let json = serde_json::to_string(&settings).unwrap();
+        let restored = serde_json::from_str::<Settings>(&json).unwrap();
+        assert_eq!(restored, settings);
+
+        let exported = NodeTemplate::list_to_json(&settings.templates).unwrap();
+        assert_eq!(
+            NodeTemplate::list_from_json(&exported).unwrap(),
+            settings.templates
+        );
+
+        // 旧版本保存的模板没有内容
+        let old = r#"[{"name":"习题","distinct_type":"KnowledgePoint","addon_types":[]}]"#;
+        assert_eq!(NodeTemplate::list_from_json(old).unwrap()[0].content, "");
+        assert!(NodeTemplate::list_from_json("{").is_err());
+    }
+}
@@ -0,0 +1,88 @@
+//! 符号错开模块。多条边交汇于同一节点时，它们的箭头、半圆等关系符号和边标签会挤在一起，
+//! 绘制前将位置相近的符号沿各自的边错开，使高入度节点周围的符号清晰可辨。只影响绘制位置。
+
+use std::collections::HashMap;
+
+use eframe::egui::{Pos2, Vec2};
+
+/// 每个符号最多向一侧错开的间距数
+const MAX_STEPS: i32 = 4;
+
+/// 可沿边移动的符号
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Anchor {
+    pub pos: Pos2,         // 不错开时的位置
+    pub dir: Vec2,         // 可移动的单位方向
+    pub range: (f32, f32), // 沿 dir 允许的位移范围，包含 0
+}
+
+/// 依次为每个符号选择沿其方向的位移，使它与之前放置的符号的距离不小于 gap。
+/// 优先不移动，其次按距离由近到远尝试 ±gap、±2gap……，超出范围的跳过，都不满足时不移动
+pub fn stagger(anchors: &[Anchor], gap: f32) -> Vec<f32> {
+    if gap.is_nan() || gap <= 0.0 {
+        return vec![0.0; anchors.len()];
+    }
+    // 按 gap 大小的网格索引已放置的符号，只需检查相邻的格子
+    let cell = |pos: Pos2| ((pos.x / gap).floor() as i64, (pos.y / gap).floor() as i64);
+    let mut placed = HashMap::<(i64, i64), Vec<Pos2>>::new();
+    let is_free = |placed: &HashMap<(i64, i64), Vec<Pos2>>, pos: Pos2| {
+        let (x, y) = cell(pos);
+        (x - 1..=x + 1)
+            .flat_map(|x| (y - 1..=y + 1).map(move |y| (x, y)))
+            .filter_map(|key| placed.get(&key))
+            .flatten()
+            .all(|other| other.distance(pos) >= gap)
+    };
+
+    anchors
+        .iter()
+        .map(|anchor| {
+            let shift = if is_free(&placed, anchor.pos) {
+                0.0
+            } else {
+                (1..=MAX_STEPS)
+                    .flat_map(|step| [step, -step])
+                    .map(|step| step as f32 * gap)
+                    .filter(|shift| (anchor.range.0..=anchor.range.1).contains(shift))
+                    .find(|shift| is_free(&placed, anchor.pos + anchor.dir * *shift))
+                    .unwrap_or(0.0)
+            };
+            let pos = anchor.pos + anchor.dir * shift;
+            placed.entry(cell(pos)).or_default().push(pos);
+            shift
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stagger() {
+        let anchor = |x: f32, y: f32, dir: Vec2, range: (f32, f32)| Anchor {
+            pos: Pos2::new(x, y),
+            dir,
+            range,
+        };
+        // 三个重合的符号依次错开，远处的不动
+        let anchors = [
+            anchor(0.0, 0.0, Vec2::X, (-50.0, 50.0)),
+            anchor(0.0, 0.0, Vec2::X, (-50.0, 50.0)),
+            anchor(0.0, 0.0, Vec2::X, (-50.0, 50.0)),
+            anchor(100.0, 100.0, Vec2::Y, (-50.0, 50.0)),
+        ];
+        assert_eq!(stagger(&anchors, 10.0), vec![0.0, 10.0, -10.0, 0.0]);
+
+        // 只能向一侧移动，范围不足时不移动
+        let anchors = [
+            anchor(0.0, 0.0, Vec2::X, (0.0, 0.0)),
+            anchor(0.0, 0.0, -Vec2::Y, (0.0, 30.0)),
+            anchor(0.0, 0.0, -Vec2::Y, (0.0, 30.0)),
+            anchor(0.0, 0.0, Vec2::X, (0.0, 5.0)),
+        ];
+        assert_eq!(stagger(&anchors, 10.0), vec![0.0, 10.0, 20.0, 0.0]);
+
+        assert_eq!(stagger(&anchors, 0.0), vec![0.0; 4]);
+    }
+}
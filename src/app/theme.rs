@@ -0,0 +1,195 @@
+//! 主题模块，决定节点的配色，以及在色盲友好模式下用于区分节点类型的角标形状。
+
+use eframe::egui::{Color32, Painter, Rect, Shape, Stroke, Vec2};
+use serde::{Deserialize, Serialize};
+
+use crate::graph::DistinctEntityType;
+
+/// 配色方案
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Palette {
+    #[default]
+    Standard, // 默认配色
+    ColorblindSafe, // 色盲友好配色，并用角标形状区分节点类型
+}
+
+impl Palette {
+    pub fn name(&self) -> &str {
+        match *self {
+            Palette::Standard => "默认",
+            Palette::ColorblindSafe => "色盲友好",
+        }
+    }
+}
+
+const DARK_LINK_COLOR: Color32 = Color32::from_rgb(20, 80, 200); // 浅色背景上的链接色
+const LIGHT_LINK_COLOR: Color32 = Color32::from_rgb(150, 200, 255); // 深色背景上的链接色
+
+/// 颜色的 WCAG 相对亮度，黑色为 0，白色为 1
+pub fn relative_luminance(color: Color32) -> f32 {
+    let linear = |channel: u8| {
+        let c = channel as f32 / 255.0;
+        if c <= 0.03928 {
+            c / 12.92
+        } else {
+            ((c + 0.055) / 1.055).powf(2.4)
+        }
+    };
+    0.2126 * linear(color.r()) + 0.7152 * linear(color.g()) + 0.0722 * linear(color.b())
+}
+
+/// 在 fill 上对比度更高的文字颜色，为黑色或白色
+pub fn text_color(fill: Color32) -> Color32 {
+    // 与黑色的对比度为 (L + 0.05) / 0.05，与白色的对比度为 1.05 / (L + 0.05)
+    let luminance = relative_luminance(fill);
+    if (luminance + 0.05) / 0.05 >= 1.05 / (luminance + 0.05) {
+        Color32::BLACK
+    } else {
+        Color32::WHITE
+    }
+}
+
+/// 在 fill 上使用的链接颜色，与 text_color 的深浅一致
+pub fn link_color(fill: Color32) -> Color32 {
+    if text_color(fill) == Color32::BLACK {
+        DARK_LINK_COLOR
+    } else {
+        LIGHT_LINK_COLOR
+    }
+}
+
+/// 绘制节点使用的主题
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Theme {
+    colors: [Color32; 4], // 依次为知识领域、知识单元、知识点、知识细节的填充色
+    type_marks: bool,     // 是否绘制区分节点类型的角标
+}
+
+impl From<Palette> for Theme {
+    fn from(palette: Palette) -> Self {
+        match palette {
+            Palette::Standard => Self {
+                colors: [
+                    Color32::from_rgb(255, 105, 97),
+                    Color32::from_rgb(176, 217, 128),
+                    Color32::from_rgb(189, 181, 225),
+                    Color32::from_rgb(182, 215, 232),
+                ],
+                type_marks: false,
+            },
+            // Okabe-Ito 配色，对各类色觉障碍都可区分
+            Palette::ColorblindSafe => Self {
+                colors: [
+                    Color32::from_rgb(230, 159, 0),
+                    Color32::from_rgb(0, 158, 115),
+                    Color32::from_rgb(86, 180, 233),
+                    Color32::from_rgb(240, 228, 66),
+                ],
+                type_marks: true,
+            },
+        }
+    }
+}
+
+impl Theme {
+    /// 节点的填充色
+    pub fn rect_color(&self, distinct_type: DistinctEntityType) -> Color32 {
+        match distinct_type {
+            DistinctEntityType::KnowledgeArena => self.colors[0],
+            DistinctEntityType::KnowledgeUnit => self.colors[1],
+            DistinctEntityType::KnowledgePoint => self.colors[2],
+            DistinctEntityType::KnowledgeDetail => self.colors[3],
+        }
+    }
+
+    /// 在节点右上角绘制区分类型的角标，使类型不只依靠颜色区分。
+    /// 知识领域为圆形，知识单元为方形，知识点为三角形，知识细节为菱形。
+    pub fn draw_type_mark(
+        &self,
+        painter: &Painter,
+        rect: Rect,
+        distinct_type: DistinctEntityType,
+        zoom: f32,
+    ) {
+        if !self.type_marks {
+            return;
+        }
+
+        let radius = 6.0 * zoom;
+        let center = rect.right_top() + Vec2::new(-radius - 6.0 * zoom, radius + 6.0 * zoom);
+        let stroke = Stroke::new(1.0, Color32::WHITE);
+        let shape = match distinct_type {
+            DistinctEntityType::KnowledgeArena => {
+                Shape::circle_filled(center, radius, Color32::BLACK)
+            }
+            DistinctEntityType::KnowledgeUnit => Shape::rect_filled(
+                Rect::from_center_size(center, Vec2::splat(radius * 1.8)),
+                0.0,
+                Color32::BLACK,
+            ),
+            DistinctEntityType::KnowledgePoint => Shape::convex_polygon(
+                vec![
+                    center + Vec2::new(0.0, -radius),
+                    center + Vec2::new(radius, radius * 0.8),
+                    center + Vec2::new(-radius, radius * 0.8),
+                ],
+                Color32::BLACK,
+                stroke,
+            ),
+            DistinctEntityType::KnowledgeDetail => Shape::convex_polygon(
+                vec![
+                    center + Vec2::new(0.0, -radius),
+                    center + Vec2::new(radius, 0.0),
+                    center + Vec2::new(0.0, radius),
+                    center + Vec2::new(-radius, 0.0),
+                ],
+                Color32::BLACK,
+                stroke,
+            ),
+        };
+        painter.add(shape);
+    }
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Palette::default().into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_palette_colors_distinct() {
+        for palette in [Palette::Standard, Palette::ColorblindSafe] {
+            let theme = Theme::from(palette);
+            for (i, a) in theme.colors.iter().enumerate() {
+                for b in theme.colors.iter().skip(i + 1) {
+                    assert_ne!(a, b);
+                }
+            }
+        }
+        assert!(Theme::from(Palette::ColorblindSafe).type_marks);
+        assert!(!Theme::default().type_marks);
+    }
+
+    #[test]
+    fn test_text_color() {
+        assert_eq!(relative_luminance(Color32::BLACK), 0.0);
+        assert!((relative_luminance(Color32::WHITE) - 1.0).abs() < 1e-6);
+        assert_eq!(text_color(Color32::WHITE), Color32::BLACK);
+        assert_eq!(text_color(Color32::from_rgb(20, 30, 90)), Color32::WHITE);
+        assert_eq!(link_color(Color32::from_rgb(20, 30, 90)), LIGHT_LINK_COLOR);
+        // 中灰色与黑色的对比度更高
+        assert_eq!(text_color(Color32::from_gray(128)), Color32::BLACK);
+
+        // 内置配色都是浅色，正文为黑色
+        for palette in [Palette::Standard, Palette::ColorblindSafe] {
+            for color in Theme::from(palette).colors {
+                assert_eq!(text_color(color), Color32::BLACK);
+            }
+        }
+    }
+}
@@ -0,0 +1,110 @@
+//! 触摸模块，处理触摸屏和触控笔特有的手势：长按、双指平移和捏合缩放。
+//! 鼠标操作不经过此模块。
+
+use std::time::{Duration, Instant};
+
+use eframe::egui::{Pos2, Vec2};
+
+/// 长按检测
+#[derive(Debug, Default)]
+pub struct LongPress {
+    pressed: Option<(Instant, Pos2)>, // 按下的时间和位置
+    fired: bool,                      // 本次按下是否已触发长按
+}
+
+impl LongPress {
+    /// 手指按下时调用
+    pub fn press(&mut self, pos: Pos2, now: Instant) {
+        self.pressed = Some((now, pos));
+        self.fired = false;
+    }
+
+    /// 手指抬起时调用
+    pub fn release(&mut self) {
+        self.pressed = None;
+    }
+
+    /// 是否正在按住且尚未触发长按
+    #[inline]
+    pub fn is_pressed(&self) -> bool {
+        self.pressed.is_some()
+    }
+
+    /// 本次按下是否已触发长按，用于忽略抬起时产生的单击
+    #[inline]
+    pub fn fired(&self) -> bool {
+        self.fired
+    }
+
+    /// 按住期间每帧调用。按住超过 duration 且移动不超过 tolerance 时返回按下的位置，
+    /// 每次按下只触发一次。移动超过 tolerance 时取消本次检测。
+    pub fn update(
+        &mut self,
+        pos: Pos2,
+        now: Instant,
+        duration: Duration,
+        tolerance: f32,
+    ) -> Option<Pos2> {
+        let (start, start_pos) = self.pressed?;
+        if start_pos.distance(pos) > tolerance {
+            self.pressed = None;
+            return None;
+        }
+        if now.saturating_duration_since(start) >= duration {
+            self.pressed = None;
+            self.fired = true;
+            return Some(start_pos);
+        }
+        None
+    }
+}
+
+/// 以 anchor 为中心从 old_zoom 缩放到 new_zoom 后的滚动偏移，使 anchor 下的内容保持不动。
+/// anchor 为相对于画布左上角的位置。
+pub fn zoom_about(offset: Vec2, anchor: Vec2, old_zoom: f32, new_zoom: f32) -> Vec2 {
+    (offset + anchor) * (new_zoom / old_zoom) - anchor
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_long_press() {
+        let now = Instant::now();
+        let duration = Duration::from_millis(500);
+        let pos = Pos2::new(10.0, 10.0);
+
+        let mut long_press = LongPress::default();
+        long_press.press(pos, now);
+        assert_eq!(long_press.update(pos, now, duration, 10.0), None);
+        assert_eq!(
+            long_press.update(pos + Vec2::splat(2.0), now + duration, duration, 10.0),
+            Some(pos)
+        );
+        assert!(long_press.fired());
+        // 只触发一次
+        assert_eq!(
+            long_press.update(pos, now + duration * 2, duration, 10.0),
+            None
+        );
+
+        // 移动过远时取消
+        long_press.press(pos, now);
+        assert!(!long_press.fired());
+        assert_eq!(
+            long_press.update(pos + Vec2::splat(20.0), now, duration, 10.0),
+            None
+        );
+        assert_eq!(long_press.update(pos, now + duration, duration, 10.0), None);
+    }
+
+    #[test]
+    fn test_zoom_about() {
+        let offset = Vec2::new(100.0, 50.0);
+        let anchor = Vec2::new(200.0, 100.0);
+        let new_offset = zoom_about(offset, anchor, 1.0, 2.0);
+        // 缩放前后 anchor 下的内容坐标相同
+        assert_eq!((offset + anchor) / 1.0, (new_offset + anchor) / 2.0);
+    }
+}
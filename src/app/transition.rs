@@ -0,0 +1,79 @@
+//! 过渡动画模块。节点位置批量变化（如撤销、恢复）时，在绘制中将节点从原位置平滑移动到新位置。
+//! 动画只影响绘制，图谱数据和点击检测始终使用最终位置。
+
+use std::{
+    collections::HashMap,
+    time::{Duration, Instant},
+};
+
+/// 过渡动画的时长
+const TRANSITION_DURATION: Duration = Duration::from_millis(250);
+
+/// 一次节点位置的过渡动画
+#[derive(Debug, Clone)]
+pub struct Transition {
+    from: HashMap<u64, (f64, f64)>, // 发生变化的节点的起始位置
+    revision: u64,                  // 动画对应的图谱修订号，图谱再次变化时动画作废
+    start: Instant,
+    progress: f64, // 经过缓动的进度，0 为起始位置，1 为最终位置
+}
+
+impl Transition {
+    pub fn new(from: HashMap<u64, (f64, f64)>, revision: u64, now: Instant) -> Self {
+        Self {
+            from,
+            revision,
+            start: now,
+            progress: 0.0,
+        }
+    }
+
+    /// 更新进度。动画结束时返回 false。
+    pub fn tick(&mut self, now: Instant) -> bool {
+        let t = now.saturating_duration_since(self.start).as_secs_f64()
+            / TRANSITION_DURATION.as_secs_f64();
+        let t = t.min(1.0);
+        // 三次缓出，开始快结束慢
+        self.progress = 1.0 - (1.0 - t).powi(3);
+        t < 1.0
+    }
+
+    #[inline]
+    pub fn revision(&self) -> u64 {
+        self.revision
+    }
+
+    /// 节点当前应绘制的位置，target 为其最终位置
+    pub fn position(&self, id: u64, target: (f64, f64)) -> (f64, f64) {
+        match self.from.get(&id) {
+            Some(from) => (
+                from.0 + (target.0 - from.0) * self.progress,
+                from.1 + (target.1 - from.1) * self.progress,
+            ),
+            None => target,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_transition() {
+        let now = Instant::now();
+        let mut transition = Transition::new(HashMap::from([(1, (0.0, 0.0))]), 1, now);
+        assert!(transition.tick(now));
+        assert_eq!(transition.position(1, (100.0, 50.0)), (0.0, 0.0));
+        // 没有变化的节点直接位于最终位置
+        assert_eq!(transition.position(2, (100.0, 50.0)), (100.0, 50.0));
+
+        assert!(transition.tick(now + TRANSITION_DURATION / 2));
+        let (x, y) = transition.position(1, (100.0, 50.0));
+        assert!(x > 50.0 && x < 100.0);
+        assert!(y > 25.0 && y < 50.0);
+
+        assert!(!transition.tick(now + TRANSITION_DURATION));
+        assert_eq!(transition.position(1, (100.0, 50.0)), (100.0, 50.0));
+    }
+}
@@ -0,0 +1,118 @@
+//! 底图模块。可在画布最底层显示一张图片（如课程大纲的截图），便于按图排布节点。
+//! 底图的设置按文件保存在图谱文件旁的视图文件中，不写入 XML，也不会出现在缩略图中。
+//! 视图文件中还保存节点的快捷位。
+
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use eframe::egui::ColorImage;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    error::{Error, SerdeError},
+    file::view_path,
+};
+
+/// 底图设置
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Underlay {
+    pub path: PathBuf,      // 图片路径
+    pub scale: f32,         // 图片每个像素对应的逻辑长度
+    pub offset: (f32, f32), // 图片左上角的逻辑坐标
+    pub opacity: f32,       // 不透明度，0 到 1
+    pub visible: bool,      // 是否显示
+}
+
+impl Default for Underlay {
+    fn default() -> Self {
+        Self {
+            path: PathBuf::new(),
+            scale: 1.0,
+            offset: (0.0, 0.0),
+            opacity: 0.3,
+            visible: true,
+        }
+    }
+}
+
+/// 按文件保存的视图设置
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ViewSidecar {
+    pub underlay: Option<Underlay>,
+    pub hotkeys: BTreeMap<u8, u64>, // 快捷位 1~9 绑定的节点 ID，按 Ctrl + 数字键跳转
+}
+
+impl ViewSidecar {
+    /// 读取图谱文件对应的视图文件，不存在时返回默认设置
+    pub fn load(graph_path: &Path) -> Result<Self, Error> {
+        match fs::read_to_string(view_path(graph_path)) {
+            Ok(json) => Ok(serde_json::from_str(&json).map_err(SerdeError::from)?),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// 写入图谱文件对应的视图文件，设置为默认值时删除视图文件
+    pub fn save(&self, graph_path: &Path) -> Result<(), Error> {
+        let path = view_path(graph_path);
+        if *self == Self::default() {
+            return match fs::remove_file(path) {
+                Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.into()),
+                _ => Ok(()),
+            };
+        }
+        let json = serde_json::to_string_pretty(self).map_err(SerdeError::from)?;
+        fs::write(path, json)?;
+        Ok(())
+    }
+}
+
+/// 读取底图图片，转换为 egui 可以上传的图像
+pub fn load_image(path: &Path) -> Result<ColorImage, Error> {
+    let image = image::open(path).map_err(SerdeError::from)?.to_rgba8();
+    let size = [image.width() as usize, image.height() as usize];
+    Ok(ColorImage::from_rgba_unmultiplied(size, image.as_raw()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_view_sidecar() {
+        let dir = std::env::temp_dir();
+        let graph_path = dir.join(format!("better_kt_sqep_{}_view.xml", std::process::id()));
+        assert_eq!(
+            view_path(&graph_path).file_name().unwrap(),
+            format!(".better_kt_sqep_{}_view.xml.view.json", std::process::id()).as_str()
+        );
+
+        // 没有视图文件时为默认设置
+        assert_eq!(
+            ViewSidecar::load(&graph_path).unwrap(),
+            ViewSidecar::default()
+        );
+
+        let sidecar = ViewSidecar {
+            underlay: Some(Underlay {
+                path: dir.join("outline.png"),
+                scale: 2.0,
+                ..Default::default()
+            }),
+            hotkeys: BTreeMap::from([(1, 5), (9, 2)]),
+        };
+        sidecar.save(&graph_path).unwrap();
+        assert_eq!(ViewSidecar::load(&graph_path).unwrap(), sidecar);
+
+        // 恢复默认设置时删除视图文件
+        ViewSidecar::default().save(&graph_path).unwrap();
+        assert!(!view_path(&graph_path).exists());
+
+        assert!(load_image(&dir.join("better_kt_sqep_missing.png")).is_err());
+    }
+}
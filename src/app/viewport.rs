@@ -0,0 +1,83 @@
+//! 画布的可交互区域。画布上方是顶部控制栏，右侧和下方可能显示滚动条，在这些位置按下鼠标时
+//! 不应开始拖动节点或新建节点。区域由滚动区域实际占据的位置和当前样式的滚动条宽度计算，
+//! 不依赖窗口大小、是否最大化以及系统缩放比例。
+
+use eframe::egui::{Pos2, Rect, Vec2, style::ScrollStyle};
+
+/// 浮动滚动条覆盖在内容上的最大宽度（鼠标悬停时会变宽）。非浮动的滚动条单独占用空间，
+/// 不覆盖内容，宽度为 0
+pub fn overlay_scrollbar_width(style: &ScrollStyle) -> f32 {
+    let full = style.bar_inner_margin + style.bar_width + style.bar_outer_margin;
+    (full - style.allocated_width()).max(0.0)
+}
+
+/// 可交互的画布区域：滚动区域的内容区域 inner_rect 去掉显示滚动条的一侧被覆盖的部分。
+/// 内容大小 content_size 超出内容区域时才显示对应方向的滚动条
+pub fn interactable_rect(inner_rect: Rect, content_size: Vec2, scrollbar_width: f32) -> Rect {
+    let mut rect = inner_rect;
+    if content_size.y > inner_rect.height() {
+        rect.max.x -= scrollbar_width;
+    }
+    if content_size.x > inner_rect.width() {
+        rect.max.y -= scrollbar_width;
+    }
+    rect
+}
+
+/// 屏幕上的点是否在可交互的画布区域内
+#[inline]
+pub fn is_interactable(pos: Pos2, canvas: Rect) -> bool {
+    canvas.contains(pos)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_interactable() {
+        let floating = overlay_scrollbar_width(&ScrollStyle::floating());
+        assert!(floating > 0.0);
+        assert_eq!(overlay_scrollbar_width(&ScrollStyle::solid()), 0.0);
+        let wide = ScrollStyle {
+            bar_width: 16.0,
+            ..ScrollStyle::floating()
+        };
+        assert!(overlay_scrollbar_width(&wide) > floating);
+
+        // 逻辑尺寸的窗口：1920x1080 像素在 150% 缩放下、最大化的 2560x1400、较小的普通窗口
+        let top = 50.0;
+        for (width, height, bar) in [
+            (1280.0, 720.0, floating),
+            (2560.0, 1400.0, floating),
+            (800.0, 600.0, overlay_scrollbar_width(&wide)),
+        ] {
+            let inner = Rect::from_min_max(Pos2::new(0.0, top), Pos2::new(width, height - 24.0));
+            let content = Vec2::new(width * 2.0, height * 2.0);
+            let canvas = interactable_rect(inner, content, bar);
+
+            // 控制栏和滚动条上不可交互
+            assert!(!is_interactable(Pos2::new(100.0, top - 1.0), canvas));
+            assert!(!is_interactable(Pos2::new(width - 1.0, 300.0), canvas));
+            assert!(!is_interactable(
+                Pos2::new(100.0, inner.max.y - 1.0),
+                canvas
+            ));
+            // 紧挨滚动条和窗口底部的节点仍可拖动，不再有固定的 40 点空白
+            assert!(is_interactable(
+                Pos2::new(100.0, inner.max.y - bar - 1.0),
+                canvas
+            ));
+            assert!(is_interactable(Pos2::new(width - bar - 1.0, 300.0), canvas));
+            assert!(is_interactable(Pos2::new(100.0, top + 1.0), canvas));
+
+            // 内容未超出时不显示滚动条，整个内容区域都可交互
+            let canvas = interactable_rect(inner, inner.size() / 2.0, bar);
+            assert_eq!(canvas, inner);
+            assert!(is_interactable(
+                Pos2::new(width - 1.0, inner.max.y - 1.0),
+                canvas
+            ));
+        }
+    }
+}
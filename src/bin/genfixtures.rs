@@ -0,0 +1,96 @@
+//! 生成大型合成图谱，供性能测试使用。
+//!
+//! 用法：`genfixtures [输出目录]`，默认输出到 `target/fixtures`，生成 1k 和 10k 节点的 XML 文件。
+//! 结果是确定性的，同样的参数每次生成相同的文件。
+
+use std::{fs, path::PathBuf, process::ExitCode};
+
+use py_better_kt_sqep::{
+    error::Error,
+    graph::{AddonEntityType, DistinctEntityType, EntityNode, Relation, Snapshot},
+};
+
+const SIZES: [usize; 2] = [1_000, 10_000];
+const CHILDREN: usize = 4; // 每个节点的子节点数
+const SPACING: f64 = 200.0; // 相邻节点的间距
+
+const ADDON_TYPES: [AddonEntityType; 6] = [
+    AddonEntityType::Knowledge,
+    AddonEntityType::Thinking,
+    AddonEntityType::Example,
+    AddonEntityType::Question,
+    AddonEntityType::Practice,
+    AddonEntityType::Political,
+];
+
+/// 生成 size 个节点的图谱：节点构成完全四叉树，父节点包含子节点，兄弟节点之间为次序关系。
+/// 节点按所在层确定实体类型，坐标排列为网格。
+fn generate(size: usize) -> Snapshot {
+    let mut snapshot = Snapshot::default();
+    let columns = (size as f64).sqrt().ceil() as usize;
+
+    for i in 0..size {
+        let id = i as u64 + 1;
+        let depth = (1..).take_while(|d| depth_start(*d) <= i).count();
+        let distinct_type = match depth {
+            0 => DistinctEntityType::KnowledgeArena,
+            1 => DistinctEntityType::KnowledgeUnit,
+            2 => DistinctEntityType::KnowledgePoint,
+            _ => DistinctEntityType::KnowledgeDetail,
+        };
+        // 以节点序号的二进制位选取附加类型
+        let addon_types = ADDON_TYPES
+            .iter()
+            .enumerate()
+            .filter(|(bit, _)| (i * 7 + 3) >> bit & 1 == 1)
+            .map(|(_, addon)| *addon)
+            .collect::<Vec<_>>();
+        let coor = (
+            (i % columns) as f64 * SPACING,
+            (i / columns) as f64 * SPACING,
+        );
+        let node = EntityNode::new(id, format!("节点 {id}"), distinct_type, &addon_types, coor);
+        snapshot.nodes.insert(id, node);
+
+        if i > 0 {
+            let parent = (i - 1) / CHILDREN;
+            snapshot
+                .edges
+                .insert((parent as u64 + 1, id), Relation::Contain);
+            if (i - 1) % CHILDREN > 0 {
+                snapshot.edges.insert((id - 1, id), Relation::Order);
+            }
+        }
+    }
+
+    snapshot
+}
+
+/// 第 depth 层第一个节点的序号
+fn depth_start(depth: usize) -> usize {
+    (0..depth).map(|d| CHILDREN.pow(d as u32)).sum()
+}
+
+fn run(dir: PathBuf) -> Result<(), Error> {
+    fs::create_dir_all(&dir)?;
+    for size in SIZES {
+        let path = dir.join(format!("synthetic_{}k.xml", size / 1000));
+        fs::write(&path, generate(size).to_xml()?)?;
+        println!("{}", path.display());
+    }
+    Ok(())
+}
+
+fn main() -> ExitCode {
+    let dir = std::env::args()
+        .nth(1)
+        .map_or_else(|| PathBuf::from("target/fixtures"), PathBuf::from);
+
+    match run(dir) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("failed to generate fixtures: {e}");
+            ExitCode::FAILURE
+        }
+    }
+}
@@ -0,0 +1,153 @@
+//! 命令行转换工具，在 XML 和邻接表文本之间转换图谱，也可导出为 PlantUML 思维导图、GEXF、教学要求矩阵或 PDF。
+//!
+//! 用法：`kt_convert [--strict] <输入> <输出>`，按扩展名判断格式：`.xml` 为 XML，`.txt` 为邻接表文本，
+//! `.puml` 为 PlantUML，`.gexf` 为 GEXF，`.csv` 和 `.html` 为教学要求矩阵，`.tsv` 为可在表格软件中编辑的坐标表，
+//! `.anki.tsv` 为 Anki 记忆卡片，`.pdf` 为单页宽的打印版（后七者仅可作为输出）。输入也可以是 `.journal` 操作日志（如用户导出的操作历史），重放后输出最终的图谱。
+//!
+//! 加上 `--strict` 时先进行提交对接平台前的严格检查，存在警告或错误时列出问题且不写入输出；
+//! 通过时 XML 输出为对接平台可直接打开的兼容格式，不含本软件扩展的元素。
+//!
+//! 子命令 `kt_convert units [--no-arena] <输入> <输出目录>` 将每个知识单元及其下级节点导出为输出目录下
+//! 单独的 XML 文件，默认包含单元所属的知识领域节点，加上 `--no-arena` 时不包含。内容为空的单元被跳过。
+
+use std::{fs, path::Path, process::ExitCode};
+
+use py_better_kt_sqep::{
+    error::Error,
+    file::{export_units, task::CancelToken},
+    graph::{IssueLevel, Snapshot, decode_xml},
+    journal::replay_journal,
+};
+
+/// 按扩展名读取快照
+fn read(path: &Path) -> Result<Snapshot, Error> {
+    if path.extension().is_some_and(|ext| ext == "journal") {
+        return Ok(replay_journal(path)?.current_snapshot().clone());
+    }
+    let content = decode_xml(&fs::read(path)?)?;
+    let snapshot = if is_text(path) {
+        Snapshot::from_adjacency_text(&content)?
+    } else {
+        Snapshot::from_xml(&content)?
+    };
+    Ok(snapshot)
+}
+
+/// 按扩展名写入快照，compatible 为 true 时 XML 使用对接平台的兼容格式
+fn write(path: &Path, snapshot: &Snapshot, compatible: bool) -> Result<(), Error> {
+    if path.extension().is_some_and(|ext| ext == "pdf") {
+        fs::write(path, snapshot.to_pdf(1))?;
+        return Ok(());
+    }
+    let content = if is_text(path) {
+        snapshot.to_adjacency_text()
+    } else if path.extension().is_some_and(|ext| ext == "puml") {
+        snapshot.to_plantuml()
+    } else if path.extension().is_some_and(|ext| ext == "gexf") {
+        snapshot.to_gexf()
+    } else if path.extension().is_some_and(|ext| ext == "csv") {
+        snapshot.to_matrix_csv()
+    } else if path.extension().is_some_and(|ext| ext == "html") {
+        snapshot.to_matrix_html()
+    } else if path.to_string_lossy().ends_with(".anki.tsv") {
+        snapshot.to_anki_tsv()
+    } else if path.extension().is_some_and(|ext| ext == "tsv") {
+        snapshot.to_coordinates_tsv()
+    } else if compatible {
+        snapshot.to_compatible_xml()?
+    } else {
+        snapshot.to_xml()?
+    };
+    fs::write(path, content)?;
+    Ok(())
+}
+
+fn is_text(path: &Path) -> bool {
+    path.extension().is_some_and(|ext| ext == "txt")
+}
+
+/// 严格检查快照，打印发现的问题，返回是否通过
+fn check_strict(snapshot: &Snapshot) -> bool {
+    let issues = snapshot.strict_issues();
+    for issue in issues.iter() {
+        let level = match issue.level {
+            IssueLevel::Info => "info",
+            IssueLevel::Warning => "warning",
+            IssueLevel::Error => "error",
+        };
+        eprintln!("{level}: {}", issue.message);
+    }
+    issues.is_empty()
+}
+
+/// units 子命令：按知识单元导出到目录，打印写入的文件和跳过的单元
+fn units(mut args: Vec<String>) -> ExitCode {
+    let with_arena = !args.iter().any(|arg| arg == "--no-arena");
+    args.retain(|arg| arg != "--no-arena");
+    let [input, dir] = args.as_slice() else {
+        eprintln!("usage: kt_convert units [--no-arena] <input.xml|input.txt> <output-dir>");
+        return ExitCode::FAILURE;
+    };
+
+    let snapshot = match read(Path::new(input)) {
+        Ok(snapshot) => snapshot,
+        Err(e) => {
+            eprintln!("failed to read {input}: {e}");
+            return ExitCode::FAILURE;
+        }
+    };
+    if let Err(e) = fs::create_dir_all(dir) {
+        eprintln!("failed to create {dir}: {e}");
+        return ExitCode::FAILURE;
+    }
+    match export_units(&snapshot, dir, with_arena, &CancelToken::default()) {
+        Ok(export) => {
+            for path in export.written {
+                println!("{}", path.display());
+            }
+            for id in export.skipped {
+                eprintln!("skipped unit {id}: empty content");
+            }
+            ExitCode::SUCCESS
+        }
+        Err(e) => {
+            eprintln!("failed to export units of {input} to {dir}: {e}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn main() -> ExitCode {
+    let mut args = std::env::args().skip(1).collect::<Vec<_>>();
+    if args.first().is_some_and(|arg| arg == "units") {
+        return units(args.split_off(1));
+    }
+    let strict = args.iter().any(|arg| arg == "--strict");
+    args.retain(|arg| arg != "--strict");
+    let [input, output] = args.as_slice() else {
+        eprintln!(
+            "usage: kt_convert [--strict] <input.xml|input.txt> <output.xml|output.txt|output.puml|output.gexf|output.csv|output.html|output.tsv|output.pdf>"
+        );
+        return ExitCode::FAILURE;
+    };
+
+    let snapshot = match read(Path::new(input)) {
+        Ok(snapshot) => snapshot,
+        Err(e) => {
+            eprintln!("failed to read {input}: {e}");
+            return ExitCode::FAILURE;
+        }
+    };
+    if strict && !check_strict(&snapshot) {
+        eprintln!("strict check failed, {output} is not written");
+        return ExitCode::FAILURE;
+    }
+
+    match write(Path::new(output), &snapshot, strict) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("failed to convert {input} to {output}: {e}");
+            ExitCode::FAILURE
+        }
+    }
+}
@@ -0,0 +1,231 @@
+//! C ABI 模块，供 C# 等非 Python 的程序读取和校验图谱，仅在启用 capi 特性时编译。
+//!
+//! 所有函数都不会将 panic 传出边界：出错时返回空指针或 -1，并通过 error 参数返回错误信息，
+//! 错误信息需以 kg_free_error 释放。字符串以调用方提供的缓冲区返回，见 kg_to_xml。
+
+use std::{
+    ffi::{CStr, CString, c_char},
+    fs,
+    panic::{self, AssertUnwindSafe},
+    ptr,
+};
+
+use serde_json::json;
+
+use crate::graph::{IssueLevel, Snapshot, XmlOptions, addon_flags, decode_xml};
+
+/// 已加载的图谱，以 kg_load 创建，以 kg_free 释放
+pub struct KgHandle {
+    snapshot: Snapshot,
+}
+
+/// 执行 f 并捕获 panic，出错时将错误信息写入 error（非空时）并返回 default
+unsafe fn guard<T, F>(error: *mut *mut c_char, default: T, f: F) -> T
+where
+    F: FnOnce() -> Result<T, String>,
+{
+    let message = match panic::catch_unwind(AssertUnwindSafe(f)) {
+        Ok(Ok(value)) => return value,
+        Ok(Err(message)) => message,
+        Err(_) => "internal panic".to_string(),
+    };
+    if !error.is_null() {
+        let message = CString::new(message.replace('\0', " ")).unwrap_or_default();
+        // SAFETY: 调用方保证 error 非空时指向可写的指针
+        unsafe { *error = message.into_raw() };
+    }
+    default
+}
+
+/// 将字符串写入调用方的缓冲区并以 NUL 结尾，返回字符串的字节数（不含 NUL）。
+/// 缓冲区为空或长度不足时不写入，调用方可据返回值分配足够的缓冲区后重试
+unsafe fn write_buffer(content: &str, buffer: *mut c_char, len: usize) -> isize {
+    if !buffer.is_null() && len > content.len() {
+        // SAFETY: 调用方保证 buffer 指向至少 len 字节的可写内存
+        unsafe {
+            ptr::copy_nonoverlapping(content.as_ptr(), buffer.cast(), content.len());
+            *buffer.add(content.len()) = 0;
+        }
+    }
+    content.len() as isize
+}
+
+/// 取出句柄中的快照，句柄为空时返回错误
+unsafe fn snapshot<'a>(handle: *const KgHandle) -> Result<&'a Snapshot, String> {
+    // SAFETY: 调用方保证 handle 为空或由 kg_load 返回且未释放
+    unsafe { handle.as_ref() }
+        .map(|handle| &handle.snapshot)
+        .ok_or_else(|| "null handle".to_string())
+}
+
+/// 图谱的 JSON 表示，节点和边均按 ID 排序
+fn snapshot_json(snapshot: &Snapshot) -> serde_json::Value {
+    let mut nodes = snapshot.nodes.values().collect::<Vec<_>>();
+    nodes.sort_unstable_by_key(|node| node.id);
+    let mut edges = snapshot.edges.iter().collect::<Vec<_>>();
+    edges.sort_unstable_by_key(|(edge, _)| **edge);
+
+    json!({
+        "nodes": nodes.into_iter().map(|node| json!({
+            "id": node.id,
+            "content": node.content,
+            "class_name": node.distinct_type.class_name(),
+            "attach": addon_flags(&node.addon_types),
+            "x": node.coor.0,
+            "y": node.coor.1,
+            "notes": node.notes,
+            "links": node.links,
+            "flag": node.flag.map(|flag| flag.name()),
+        })).collect::<Vec<_>>(),
+        "edges": edges.into_iter().map(|((from, to), relation)| json!({
+            "from": from,
+            "to": to,
+            "relation": relation.abbr(),
+        })).collect::<Vec<_>>(),
+    })
+}
+
+/// 从 XML 文件加载图谱，失败时返回空指针
+///
+/// # Safety
+/// path 必须是以 NUL 结尾的 UTF-8 字符串；error 为空或指向可写的指针。
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn kg_load(path: *const c_char, error: *mut *mut c_char) -> *mut KgHandle {
+    unsafe {
+        guard(error, ptr::null_mut(), || {
+            if path.is_null() {
+                return Err("null path".to_string());
+            }
+            let path = CStr::from_ptr(path).to_str().map_err(|e| e.to_string())?;
+            let bytes = fs::read(path).map_err(|e| e.to_string())?;
+            let xml = decode_xml(&bytes).map_err(|e| e.to_string())?;
+            let snapshot = Snapshot::from_xml(&xml).map_err(|e| e.to_string())?;
+            Ok(Box::into_raw(Box::new(KgHandle { snapshot })))
+        })
+    }
+}
+
+/// 节点数，句柄为空时返回 0
+///
+/// # Safety
+/// handle 为空或由 kg_load 返回且未释放。
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn kg_node_count(handle: *const KgHandle) -> usize {
+    unsafe { guard(ptr::null_mut(), 0, || Ok(snapshot(handle)?.nodes.len())) }
+}
+
+/// 边数，句柄为空时返回 0
+///
+/// # Safety
+/// handle 为空或由 kg_load 返回且未释放。
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn kg_edge_count(handle: *const KgHandle) -> usize {
+    unsafe { guard(ptr::null_mut(), 0, || Ok(snapshot(handle)?.edges.len())) }
+}
+
+/// 校验图谱，以 JSON 数组写入发现的问题，每项包含 level、message 和 entities。
+/// 返回值和缓冲区的约定同 kg_to_xml
+///
+/// # Safety
+/// handle 为空或由 kg_load 返回且未释放；buffer 为空或指向至少 len 字节的可写内存；
+/// error 为空或指向可写的指针。
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn kg_validate(
+    handle: *const KgHandle,
+    buffer: *mut c_char,
+    len: usize,
+    error: *mut *mut c_char,
+) -> isize {
+    unsafe {
+        guard(error, -1, || {
+            let issues = snapshot(handle)?
+                .validate()
+                .into_iter()
+                .map(|issue| {
+                    json!({
+                        "level": match issue.level {
+                            IssueLevel::Info => "info",
+                            IssueLevel::Warning => "warning",
+                            IssueLevel::Error => "error",
+                        },
+                        "message": issue.message,
+                        "entities": issue.entities,
+                    })
+                })
+                .collect::<Vec<_>>();
+            Ok(write_buffer(&json!(issues).to_string(), buffer, len))
+        })
+    }
+}
+
+/// 将图谱以 XML 写入缓冲区并以 NUL 结尾，返回 XML 的字节数（不含 NUL），出错时返回 -1。
+/// XML 以 `<KG>` 开头，不含 XML 声明，便于调用方嵌入或拼接。
+/// buffer 为空或 len 不大于返回值时不写入，调用方可按返回值加一分配缓冲区后再次调用
+///
+/// # Safety
+/// handle 为空或由 kg_load 返回且未释放；buffer 为空或指向至少 len 字节的可写内存；
+/// error 为空或指向可写的指针。
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn kg_to_xml(
+    handle: *const KgHandle,
+    buffer: *mut c_char,
+    len: usize,
+    error: *mut *mut c_char,
+) -> isize {
+    unsafe {
+        guard(error, -1, || {
+            let options = XmlOptions {
+                declaration: false,
+                ..Default::default()
+            };
+            let xml = snapshot(handle)?
+                .to_xml_with(options)
+                .map_err(|e| e.to_string())?;
+            Ok(write_buffer(&xml, buffer, len))
+        })
+    }
+}
+
+/// 将图谱以 JSON 写入缓冲区，包含 nodes 和 edges 两个数组。返回值和缓冲区的约定同 kg_to_xml
+///
+/// # Safety
+/// handle 为空或由 kg_load 返回且未释放；buffer 为空或指向至少 len 字节的可写内存；
+/// error 为空或指向可写的指针。
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn kg_to_json(
+    handle: *const KgHandle,
+    buffer: *mut c_char,
+    len: usize,
+    error: *mut *mut c_char,
+) -> isize {
+    unsafe {
+        guard(error, -1, || {
+            let json = snapshot_json(snapshot(handle)?).to_string();
+            Ok(write_buffer(&json, buffer, len))
+        })
+    }
+}
+
+/// 释放 kg_load 返回的句柄，句柄为空时不做任何事
+///
+/// # Safety
+/// handle 为空或由 kg_load 返回且未释放，释放后不可再使用。
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn kg_free(handle: *mut KgHandle) {
+    if !handle.is_null() {
+        // SAFETY: 调用方保证 handle 由 kg_load 通过 Box::into_raw 创建且未释放
+        drop(unsafe { Box::from_raw(handle) });
+    }
+}
+
+/// 释放通过 error 参数返回的错误信息，为空时不做任何事
+///
+/// # Safety
+/// error 为空或由本库返回且未释放，释放后不可再使用。
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn kg_free_error(error: *mut c_char) {
+    if !error.is_null() {
+        // SAFETY: 调用方保证 error 由 guard 通过 CString::into_raw 创建且未释放
+        drop(unsafe { CString::from_raw(error) });
+    }
+}
@@ -10,6 +10,14 @@ pub enum GraphError {
     NothingToUndo,
     #[error("nothing to redo")]
     NothingToRedo,
+    #[error("children order of entity {0} does not match its children")]
+    ChildrenMismatch(u64),
+    #[error("{0} limit of {1} reached")]
+    LimitExceeded(&'static str, usize),
+    #[error("group {0} not found")]
+    GroupNotFound(u64),
+    #[error("entity id {0} already in use")]
+    DuplicateEntityId(u64),
 }
 
 #[derive(Debug, Error)]
@@ -20,10 +28,20 @@ pub enum SerdeError {
     Deserialize(#[from] quick_xml::DeError),
     #[error("failed to add ident")]
     Ident(#[from] quick_xml::Error),
+    #[error("failed to convert json")]
+    Json(#[from] serde_json::Error),
+    #[error("failed to convert binary")]
+    Bincode(#[from] bincode::Error),
+    #[error("failed to encode image")]
+    Image(#[from] image::ImageError),
     #[error("failed to parse utf8 string")]
     Utf8(#[from] std::str::Utf8Error),
+    #[error("file contains {0} <KG> documents, split them into separate files")]
+    MultipleDocuments(usize),
     #[error("unexpected {0}: {1}")]
     Unexpected(&'static str, String),
+    #[error("not accepted by the platform: {}", .0.join("; "))]
+    Incompatible(Vec<String>),
 }
 
 #[derive(Debug, Error)]
@@ -36,4 +54,106 @@ pub enum Error {
     Io(#[from] std::io::Error),
     #[error("poison error: {0}")]
     Poison(String),
+    #[error("save worker stopped")]
+    WorkerStopped,
+    #[error("file {} already exists", .0.display())]
+    AlreadyExists(std::path::PathBuf),
+    #[error("operation cancelled")]
+    Cancelled,
+}
+
+impl GraphError {
+    /// 面向用户的中文消息，用于界面的错误弹窗。英文消息（Display）保留用于日志和调试
+    pub fn zh_message(&self) -> String {
+        match self {
+            GraphError::EntityNotFound(id) => format!("未找到编号为 {id} 的节点"),
+            GraphError::EdgeNotFound(from, to) => format!("未找到节点 {from} 到节点 {to} 的边"),
+            GraphError::NothingToUndo => "没有可以撤销的操作".to_string(),
+            GraphError::NothingToRedo => "没有可以恢复的操作".to_string(),
+            GraphError::ChildrenMismatch(id) => {
+                format!("节点 {id} 的子节点顺序与实际的子节点不一致")
+            }
+            GraphError::LimitExceeded(kind, max) => {
+                let kind = match *kind {
+                    "nodes" => "节点",
+                    "edges" => "边",
+                    kind => kind,
+                };
+                format!("{kind}数已达到上限 {max}")
+            }
+            GraphError::GroupNotFound(id) => format!("未找到编号为 {id} 的分组框"),
+            GraphError::DuplicateEntityId(id) => format!("节点编号 {id} 已被占用"),
+        }
+    }
+}
+
+impl SerdeError {
+    /// 面向用户的中文消息，底层库的错误详情附在括号中
+    pub fn zh_message(&self) -> String {
+        match self {
+            SerdeError::Serialize(e) => format!("无法生成 XML（{e}）"),
+            SerdeError::Deserialize(e) => format!("文件格式错误，无法读取（{e}）"),
+            SerdeError::Ident(e) => format!("XML 格式错误（{e}）"),
+            SerdeError::Json(e) => format!("JSON 数据格式错误（{e}）"),
+            SerdeError::Bincode(e) => format!("二进制数据已损坏（{e}）"),
+            SerdeError::Image(e) => format!("生成图片失败（{e}）"),
+            SerdeError::Utf8(_) => "文件不是有效的 UTF-8 文本".to_string(),
+            SerdeError::MultipleDocuments(count) => {
+                format!("文件包含 {count} 个图谱，请拆分为单独的文件")
+            }
+            SerdeError::Unexpected(what, value) => format!("无法识别的{what}：{value}"),
+            SerdeError::Incompatible(problems) => {
+                format!("不符合对接平台的格式要求：\n{}", problems.join("\n"))
+            }
+        }
+    }
+}
+
+impl Error {
+    /// 面向用户的中文消息，用于界面的错误弹窗
+    pub fn zh_message(&self) -> String {
+        match self {
+            Error::Graph(e) => e.zh_message(),
+            Error::Serde(e) => e.zh_message(),
+            Error::Io(e) => match e.kind() {
+                std::io::ErrorKind::NotFound => "文件或目录不存在".to_string(),
+                std::io::ErrorKind::PermissionDenied => "没有访问该文件的权限".to_string(),
+                std::io::ErrorKind::AlreadyExists => "文件已存在".to_string(),
+                _ => format!("读写文件失败（{e}）"),
+            },
+            Error::Poison(e) => format!("程序内部状态异常，请重新打开文件（{e}）"),
+            Error::WorkerStopped => "后台保存线程已停止，请重新打开文件".to_string(),
+            Error::AlreadyExists(path) => format!("文件 {} 已存在", path.display()),
+            Error::Cancelled => "操作已取消".to_string(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_zh_message() {
+        assert_eq!(
+            GraphError::EntityNotFound(5).zh_message(),
+            "未找到编号为 5 的节点"
+        );
+        assert_eq!(
+            GraphError::EntityNotFound(5).to_string(),
+            "entity 5 not found"
+        );
+        assert_eq!(
+            GraphError::LimitExceeded("edges", 10).zh_message(),
+            "边数已达到上限 10"
+        );
+
+        // 外层错误使用内层错误的中文消息
+        let error = Error::from(GraphError::NothingToUndo);
+        assert_eq!(error.zh_message(), "没有可以撤销的操作");
+        let error = Error::from(SerdeError::MultipleDocuments(2));
+        assert_eq!(error.zh_message(), "文件包含 2 个图谱，请拆分为单独的文件");
+        let error = Error::from(std::io::Error::from(std::io::ErrorKind::NotFound));
+        assert_eq!(error.zh_message(), "文件或目录不存在");
+    }
 }
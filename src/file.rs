@@ -1,114 +1,780 @@
 use std::{
+    collections::{BTreeSet, HashMap},
     fs,
     path::{Path, PathBuf},
     sync::{
-        Mutex,
-        mpsc::{Sender, channel},
+        Arc, Mutex,
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        mpsc::{Receiver, RecvTimeoutError, Sender, channel},
     },
-    thread,
-    time::Duration,
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
 };
 
 use crate::{
     error::{Error, GraphError},
-    graph::{AddonEntityType, DistinctEntityType, KnowledgeGraph, Relation, Snapshot},
+    file::task::CancelToken,
+    graph::{
+        AddonEntityType, Command, CommandResult, CoordinateBounds, DEFAULT_MAX_HISTORY,
+        DistinctEntityType, EntityNode, Flag, GraphMetadata, GroupFrame, KnowledgeGraph,
+        LayoutOptions, LevelPolicy, Macro, NormalizeOptions, Recorder, Relation, RemovePolicy,
+        RepairReport, Snapshot, THUMBNAIL_WIDTH, TermChecker, decode_xml,
+    },
+    journal::{
+        DeltaLog, Journal, Operation, SessionHistory, delta_log_path, journal_path,
+        recover_delta_log,
+    },
 };
 
+pub mod session;
+pub mod task;
+
 static FILE_WRITE_LOCK: Mutex<()> = Mutex::new(());
 
+/// 发送给保存线程的消息
+enum SaveMessage {
+    Save(Snapshot, u64),    // 保存快照及其版本号，短时间内的多个快照只保存最后一个
+    Thumbnail(Snapshot),    // 只生成缩略图，用于显式保存后和缩略图过期时
+    Flush(Sender<()>),      // 立即写入之前收到的快照后回复
+    Interacting(bool),      // 拖动等交互是否正在进行，进行中时暂缓自动保存
+    Timing(AutosaveTiming), // 修改自动保存的时机
+}
+
+/// 自动保存的时机
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AutosaveTiming {
+    pub quiet_period: Duration, // 最后一次修改后经过此时间没有新的修改才保存
+    pub max_delay: Duration,    // 持续修改时，第一次未保存的修改后最迟经过此时间保存
+}
+
+impl Default for AutosaveTiming {
+    fn default() -> Self {
+        Self {
+            quiet_period: Duration::from_secs(2),
+            max_delay: Duration::from_secs(30),
+        }
+    }
+}
+
+/// 等待自动保存的快照
+struct PendingSave {
+    snapshot: Snapshot,
+    revision: u64,
+    first_change: Instant, // 第一次未保存的修改的时间
+    last_change: Instant,  // 最后一次修改的时间
+}
+
+/// 自动保存的调度：只保留最新的快照，静默期结束或达到最长延迟时写入，交互进行中时暂缓。
+/// 时间由调用方传入，便于测试
+#[derive(Default)]
+struct SaveScheduler {
+    timing: AutosaveTiming,
+    pending: Option<PendingSave>,
+    interacting: bool,
+}
+
+impl SaveScheduler {
+    /// 收到新的快照，替换之前尚未写入的快照
+    fn push(&mut self, snapshot: Snapshot, revision: u64, now: Instant) {
+        let first_change = self.pending.as_ref().map_or(now, |p| p.first_change);
+        self.pending = Some(PendingSave {
+            snapshot,
+            revision,
+            first_change,
+            last_change: now,
+        });
+    }
+
+    /// 下一次应当写入的时间，没有待写入的快照或交互进行中时为 None
+    fn deadline(&self) -> Option<Instant> {
+        if self.interacting {
+            return None;
+        }
+        let pending = self.pending.as_ref()?;
+        let quiet = pending.last_change + self.timing.quiet_period;
+        let latest = pending.first_change + self.timing.max_delay;
+        Some(quiet.min(latest))
+    }
+
+    /// 到了写入的时间时取出待写入的快照
+    fn take_due(&mut self, now: Instant) -> Option<(Snapshot, u64)> {
+        if self.deadline().is_some_and(|deadline| deadline <= now) {
+            self.take()
+        } else {
+            None
+        }
+    }
+
+    /// 无论时间，立即取出待写入的快照
+    fn take(&mut self) -> Option<(Snapshot, u64)> {
+        self.pending
+            .take()
+            .map(|pending| (pending.snapshot, pending.revision))
+    }
+}
+
+/// 图谱文件对应的缩略图路径，如 `name.xml` 对应同目录下的 `.name.xml.thumb.png`
+pub fn thumbnail_path<P>(path: P) -> PathBuf
+where
+    P: AsRef<Path>,
+{
+    let path = path.as_ref();
+    let mut name = std::ffi::OsString::from(".");
+    name.push(path.file_name().unwrap_or_default());
+    name.push(".thumb.png");
+    path.with_file_name(name)
+}
+
+/// 图谱文件对应的视图文件路径，如 `name.xml` 对应同目录下的 `.name.xml.view.json`
+pub fn view_path<P>(path: P) -> PathBuf
+where
+    P: AsRef<Path>,
+{
+    let path = path.as_ref();
+    let mut name = std::ffi::OsString::from(".");
+    name.push(path.file_name().unwrap_or_default());
+    name.push(".view.json");
+    path.with_file_name(name)
+}
+
+/// 全量保存了 revision 版本后通知增量日志
+fn mark_saved(delta_log: &Mutex<Option<DeltaLog>>, revision: u64) -> Result<(), Error> {
+    let mut delta_log = delta_log.lock().map_err(|e| Error::Poison(e.to_string()))?;
+    match delta_log.as_mut() {
+        Some(delta_log) => delta_log.saved(revision),
+        None => Ok(()),
+    }
+}
+
+/// 绘制并写入缩略图
+fn write_thumbnail(path: &Path, snapshot: &Snapshot) -> Result<(), Error> {
+    let png = snapshot.to_thumbnail_png(THUMBNAIL_WIDTH)?;
+    fs::write(thumbnail_path(path), png).map_err(Error::Io)
+}
+
+/// 按知识领域拆分图谱（见 Snapshot::split_by_arena），每部分写入 dir 下以其名称命名的 XML 文件，
+/// 同名的已有文件会被覆盖。返回写入的文件路径，按拆分的顺序排列
+pub fn export_split_by_arena<P>(snapshot: &Snapshot, dir: P) -> Result<Vec<PathBuf>, Error>
+where
+    P: AsRef<Path>,
+{
+    let mut paths = Vec::new();
+    for (name, part) in snapshot.split_by_arena() {
+        let path = dir.as_ref().join(format!("{name}.xml"));
+        fs::write(&path, part.to_xml()?)?;
+        paths.push(path);
+    }
+    Ok(paths)
+}
+
+/// 按知识单元导出的结果
+#[derive(Debug, Default, PartialEq)]
+pub struct UnitExport {
+    /// 写入的文件，按单元的 ID 排列
+    pub written: Vec<PathBuf>,
+    /// 因内容为空而跳过的单元
+    pub skipped: Vec<u64>,
+}
+
+/// 按知识单元拆分图谱（见 Snapshot::split_by_unit），每个单元写入 dir 下以其名称命名的 XML 文件，
+/// 同名的已有文件会被覆盖。with_arena 为 true 时包含单元所属的知识领域节点。
+/// 每写入一个文件前检查取消标志，取消时返回 Error::Cancelled，已写入的文件保留
+pub fn export_units<P>(
+    snapshot: &Snapshot,
+    dir: P,
+    with_arena: bool,
+    token: &CancelToken,
+) -> Result<UnitExport, Error>
+where
+    P: AsRef<Path>,
+{
+    let split = snapshot.split_by_unit(with_arena);
+    let mut written = Vec::new();
+    for (name, part) in split.parts {
+        token.check()?;
+        let path = dir.as_ref().join(format!("{name}.xml"));
+        fs::write(&path, part.to_xml()?)?;
+        written.push(path);
+    }
+    Ok(UnitExport {
+        written,
+        skipped: split.skipped,
+    })
+}
+
+/// 将图谱按对接平台的格式（见 Snapshot::to_compatible_xml）导出到 path。
+/// 生成 XML 后检查取消标志，取消时返回 Error::Cancelled，不写入文件
+pub fn export_compatible<P>(snapshot: &Snapshot, path: P, token: &CancelToken) -> Result<(), Error>
+where
+    P: AsRef<Path>,
+{
+    let xml = snapshot.to_compatible_xml()?;
+    token.check()?;
+    fs::write(path, xml)?;
+    Ok(())
+}
+
 pub struct FiledKnowledgeGraph {
     graph: KnowledgeGraph,
     pub file_path: PathBuf,
-    save_sender: Sender<Snapshot>,
+    save_sender: Sender<SaveMessage>,
+    save_result_receiver: Receiver<Result<(), Error>>,
+    save_worker: JoinHandle<()>,
+    autosave: bool,
+    autosave_timing: AutosaveTiming,
+    interacting: bool,                       // 是否正在进行拖动等交互
+    saved_revision: Arc<AtomicU64>,          // 显式保存的版本号，保存线程不再写入更早的快照
+    saved_hash: Arc<Mutex<Option<u64>>>,     // 最后写入文件的快照的内容哈希，与保存线程共享
+    thumbnail: Arc<AtomicBool>,              // 保存时是否生成缩略图，与保存线程共享
+    recorder: Option<Recorder>,              // 正在录制的宏
+    load_warnings: Vec<String>,              // 读取文件时发现的数据问题
+    journal: Option<Journal>,                // 开启时记录每次修改的操作日志
+    history: SessionHistory,                 // 本次打开以来的操作，可导出用于复现问题
+    delta_log: Arc<Mutex<Option<DeltaLog>>>, // 开启时记录未保存的修改，与保存线程共享
+    journal_error: Option<Error>,
 }
 
 impl FiledKnowledgeGraph {
+    /// 打开文件。create 为 true 或文件不存在时创建空图谱；
+    /// create 为 true 但文件已有内容时返回 Error::AlreadyExists，覆盖已有文件需使用 overwrite()。
     pub fn new<P>(path: P, create: bool) -> Result<Self, Error>
     where
         P: AsRef<Path>,
     {
+        if create && Self::has_content(path.as_ref()) {
+            return Err(Error::AlreadyExists(path.as_ref().to_path_buf()));
+        }
+        Self::open(path.as_ref(), create, &CancelToken::default())
+    }
+
+    /// 在后台任务中打开已有的文件，各阶段之间检查取消标志，取消时返回 Error::Cancelled。
+    /// 文件不存在时同 new() 创建空图谱
+    pub fn open_cancellable<P>(path: P, token: &CancelToken) -> Result<Self, Error>
+    where
+        P: AsRef<Path>,
+    {
+        Self::open(path.as_ref(), false, token)
+    }
+
+    /// 创建空图谱，文件已存在时清空其内容
+    pub fn overwrite<P>(path: P) -> Result<Self, Error>
+    where
+        P: AsRef<Path>,
+    {
+        Self::open(path.as_ref(), true, &CancelToken::default())
+    }
+
+    /// 文件是否存在且含有非空白的内容
+    pub fn has_content<P>(path: P) -> bool
+    where
+        P: AsRef<Path>,
+    {
+        fs::read(path).is_ok_and(|content| !content.trim_ascii().is_empty())
+    }
+
+    fn open(path: &Path, create: bool, token: &CancelToken) -> Result<Self, Error> {
         // 如果文件不存在，则创建一个空文件
-        let graph = if !path.as_ref().exists() || create {
-            fs::write(path.as_ref(), "")?;
+        let log_path = delta_log_path(path);
+        let mut recovered = false;
+        let (graph, warnings) = if !path.exists() || create {
+            fs::write(path, "")?;
+            // 旧文件的增量日志不适用于新图谱
+            if log_path.exists() {
+                fs::remove_file(&log_path)?;
+            }
 
-            // 创建一个空的知识图谱
-            KnowledgeGraph::from_snapshot(Snapshot::default())
+            // 创建一个空的知识图谱，以文件名为标题
+            let mut snapshot = Snapshot::default();
+            if let Some(stem) = path.file_stem() {
+                snapshot.title = stem.to_string_lossy().into_owned();
+            }
+            (KnowledgeGraph::from_snapshot(snapshot), Vec::new())
         } else {
-            // 读取文件到字符串
-            let file_content = fs::read_to_string(path.as_ref())?;
-
-            // 解析字符串到知识图谱
-            let snapshot = Snapshot::from_xml(&file_content)?;
-            KnowledgeGraph::from_snapshot(snapshot)
+            let bytes = fs::read(path)?;
+            token.check()?;
+            let content = decode_xml(&bytes)?;
+            token.check()?;
+            let (snapshot, mut warnings) =
+                Snapshot::from_xml_with_policy(&content, LevelPolicy::default())?;
+            token.check()?;
+            let mut graph = KnowledgeGraph::from_snapshot(snapshot);
+            // 上次未正常保存时，将增量日志中的修改重放到文件中的快照上
+            match recover_delta_log(&mut graph, &log_path) {
+                Ok(0) => {}
+                Ok(count) => {
+                    warnings.push(format!("已从增量日志恢复上次未保存的 {count} 项修改"));
+                    recovered = true;
+                }
+                Err(e) => warnings.push(format!(
+                    "增量日志 {} 无法重放，恢复的修改已忽略: {e}",
+                    log_path.display()
+                )),
+            }
+            (graph, warnings)
         };
+        // 之后会启动保存线程并可能写入文件，不再响应取消
+        token.check()?;
+
+        let mut graph = Self::with_graph(path, graph);
+        graph.load_warnings = warnings;
+        // 恢复的修改写入文件后，日志中的内容就不再需要
+        if recovered {
+            graph.save()?;
+            fs::remove_file(&log_path)?;
+        }
+        Ok(graph)
+    }
 
-        let file_path = path.as_ref().to_path_buf();
+    /// 以宽容模式打开文件，忽略无法识别的节点和边，并返回忽略的原因。
+    /// 之后的保存会以标准格式覆盖原文件。
+    pub fn open_lenient<P>(path: P) -> Result<(Self, Vec<String>), Error>
+    where
+        P: AsRef<Path>,
+    {
+        let content = decode_xml(&fs::read(path.as_ref())?)?;
+        let (snapshot, warnings) = Snapshot::from_xml_lenient(&content)?;
+        let graph = KnowledgeGraph::from_snapshot(snapshot);
+        Ok((Self::with_graph(path.as_ref(), graph), warnings))
+    }
+
+    /// 为已读取的图谱启动保存线程
+    fn with_graph(path: &Path, graph: KnowledgeGraph) -> Self {
+        let file_path = path.to_path_buf();
         // 创建保存通知通道
-        let (tx, rx) = channel::<Snapshot>();
+        let (tx, rx) = channel::<SaveMessage>();
+        // 创建保存结果通道，供调用方取出自动保存的结果
+        let (result_tx, result_rx) = channel::<Result<(), Error>>();
 
-        // 启动保存线程（可根据需要调整线程退出策略，此处为永久运行）
+        // 启动保存线程，发送端全部关闭后退出
         let save_file_path = file_path.clone();
-        thread::spawn(move || {
-            // 线程循环等待保存通知
-            while let Ok(snapshot) = rx.recv() {
-                // 等待一段时间，收集短时间内的其它通知
-                thread::sleep(Duration::from_millis(50));
-                let mut latest_snapshot = snapshot;
-                // drain所有当前通道中剩余的快照，取最后一个
-                while let Ok(new_snapshot) = rx.try_recv() {
-                    latest_snapshot = new_snapshot;
-                }
-                // 使用最新的快照进行保存
-                match latest_snapshot.to_xml() {
-                    Ok(xml) => {
-                        // 获取文件写锁
-                        let _lock = match FILE_WRITE_LOCK.lock() {
-                            Ok(lock) => lock,
-                            Err(e) => {
-                                eprintln!("获取文件写锁失败: {}", e);
-                                continue;
-                            }
-                        };
-                        // 写入文件
-                        if let Err(e) = fs::write(&save_file_path, xml) {
-                            eprintln!("自动保存失败: {}", e);
+        let thumbnail = Arc::new(AtomicBool::new(false));
+        let save_thumbnail = thumbnail.clone();
+        let delta_log = Arc::new(Mutex::new(None));
+        let save_delta_log = delta_log.clone();
+        let saved_revision = Arc::new(AtomicU64::new(0));
+        let save_saved_revision = saved_revision.clone();
+        let saved_hash = Arc::new(Mutex::new(None));
+        let save_saved_hash = saved_hash.clone();
+        let save_worker = thread::spawn(move || {
+            // 写入快照，成功时生成缩略图。显式保存过更新的版本时跳过；
+            // 内容与最后写入的相同时（如修改后又撤回）不必重写文件，只通知增量日志
+            let write = |snapshot: Snapshot, revision: u64| {
+                let hash = snapshot.content_hash();
+                let result = (|| {
+                    // 获取文件写锁
+                    let _lock = FILE_WRITE_LOCK
+                        .lock()
+                        .map_err(|e| Error::Poison(e.to_string()))?;
+                    if revision <= save_saved_revision.load(Ordering::Acquire) {
+                        return Ok(false);
+                    }
+                    let mut saved_hash = save_saved_hash
+                        .lock()
+                        .map_err(|e| Error::Poison(e.to_string()))?;
+                    if *saved_hash == Some(hash) {
+                        return mark_saved(&save_delta_log, revision).map(|_| false);
+                    }
+                    // 写入文件
+                    fs::write(&save_file_path, snapshot.to_xml()?).map_err(Error::Io)?;
+                    *saved_hash = Some(hash);
+                    mark_saved(&save_delta_log, revision).map(|_| true)
+                })();
+                match result {
+                    Ok(false) => {}
+                    Ok(true) => {
+                        // 接收端已关闭时忽略结果
+                        let _ = result_tx.send(Ok(()));
+                        // 缩略图只用于预览，生成失败不影响保存，忽略错误
+                        if save_thumbnail.load(Ordering::Relaxed) {
+                            let _ = write_thumbnail(&save_file_path, &snapshot);
                         }
                     }
                     Err(e) => {
-                        eprintln!("序列号失败: {}", e);
+                        let _ = result_tx.send(Err(e));
                     }
                 }
+            };
+
+            // 线程循环等待保存通知，有待写入的快照时最多等到其写入时间
+            let mut scheduler = SaveScheduler::default();
+            loop {
+                let message = match scheduler.deadline() {
+                    Some(deadline) => {
+                        match rx.recv_timeout(deadline.saturating_duration_since(Instant::now())) {
+                            Ok(message) => Some(message),
+                            Err(RecvTimeoutError::Timeout) => None,
+                            Err(RecvTimeoutError::Disconnected) => break,
+                        }
+                    }
+                    None => match rx.recv() {
+                        Ok(message) => Some(message),
+                        Err(_) => break,
+                    },
+                };
+
+                let mut flush = None;
+                match message {
+                    Some(SaveMessage::Save(snapshot, revision)) => {
+                        scheduler.push(snapshot, revision, Instant::now())
+                    }
+                    Some(SaveMessage::Thumbnail(snapshot))
+                        if save_thumbnail.load(Ordering::Relaxed) =>
+                    {
+                        let _ = write_thumbnail(&save_file_path, &snapshot);
+                    }
+                    Some(SaveMessage::Flush(ack)) => flush = Some(ack),
+                    Some(SaveMessage::Interacting(interacting)) => {
+                        scheduler.interacting = interacting
+                    }
+                    Some(SaveMessage::Timing(timing)) => scheduler.timing = timing,
+                    Some(SaveMessage::Thumbnail(_)) | None => {}
+                }
+
+                let due = match flush {
+                    Some(_) => scheduler.take(),
+                    None => scheduler.take_due(Instant::now()),
+                };
+                if let Some((snapshot, revision)) = due {
+                    write(snapshot, revision);
+                }
+                // 之前的快照均已写入，通知等待的调用方
+                if let Some(ack) = flush {
+                    let _ = ack.send(());
+                }
+            }
+
+            // 发送端全部关闭后，写入尚未保存的快照再退出
+            if let Some((snapshot, revision)) = scheduler.take() {
+                write(snapshot, revision);
             }
         });
 
-        Ok(Self {
+        let history = SessionHistory::new(
+            graph.current_snapshot().clone(),
+            graph.revision(),
+            DEFAULT_MAX_HISTORY,
+        );
+        Self {
             graph,
             file_path,
             save_sender: tx,
-        })
+            save_result_receiver: result_rx,
+            save_worker,
+            autosave: true,
+            autosave_timing: AutosaveTiming::default(),
+            interacting: false,
+            saved_revision,
+            saved_hash,
+            thumbnail,
+            recorder: None,
+            load_warnings: Vec::new(),
+            journal: None,
+            history,
+            delta_log,
+            journal_error: None,
+        }
+    }
+
+    /// 取出读取文件时发现的数据问题，如节点的类型与级别不一致
+    pub fn take_load_warnings(&mut self) -> Vec<String> {
+        std::mem::take(&mut self.load_warnings)
+    }
+
+    /// 读取并解析 XML 文件中的快照，不打开为可编辑的图谱
+    pub fn read_snapshot<P>(path: P) -> Result<Snapshot, Error>
+    where
+        P: AsRef<Path>,
+    {
+        // 读取文件到字符串，兼容记事本等编辑器保存的编码
+        let file_content = decode_xml(&fs::read(path.as_ref())?)?;
+
+        // 解析字符串到快照
+        Ok(Snapshot::from_xml(&file_content)?)
     }
 
     pub fn save(&self) -> Result<(), Error> {
         let xml = self.graph.current.to_xml()?;
-        let _lock = match FILE_WRITE_LOCK.lock() {
-            Ok(lock) => lock,
-            Err(e) => return Err(Error::Poison(e.to_string())),
-        };
-        fs::write(&self.file_path, xml).map_err(Error::Io)
+        {
+            let _lock = match FILE_WRITE_LOCK.lock() {
+                Ok(lock) => lock,
+                Err(e) => return Err(Error::Poison(e.to_string())),
+            };
+            fs::write(&self.file_path, xml).map_err(Error::Io)?;
+            // 保存线程中等待写入的快照不会比当前快照更新，不必再写入
+            self.saved_revision
+                .fetch_max(self.graph.revision(), Ordering::Release);
+            // 显式保存总是写入文件，之后自动保存与其比较
+            if let Ok(mut saved_hash) = self.saved_hash.lock() {
+                *saved_hash = Some(self.graph.current.content_hash());
+            }
+        }
+        mark_saved(&self.delta_log, self.graph.revision())?;
+        self.request_thumbnail();
+        Ok(())
+    }
+
+    /// 设置保存时是否在后台生成缩略图，写入 `.<文件名>.thumb.png`。
+    /// 开启时若缩略图不存在或早于图谱文件，则立即重新生成
+    pub fn set_thumbnail(&mut self, enabled: bool) {
+        self.thumbnail.store(enabled, Ordering::Relaxed);
+        let modified = |path: &Path| fs::metadata(path).and_then(|m| m.modified()).ok();
+        let thumbnail = modified(&thumbnail_path(&self.file_path));
+        if enabled && thumbnail.is_none_or(|time| modified(&self.file_path) > Some(time)) {
+            self.request_thumbnail();
+        }
+    }
+
+    #[inline]
+    pub fn is_thumbnail_enabled(&self) -> bool {
+        self.thumbnail.load(Ordering::Relaxed)
+    }
+
+    /// 请求保存线程生成当前快照的缩略图
+    fn request_thumbnail(&self) {
+        if self.thumbnail.load(Ordering::Relaxed) {
+            let snapshot = self.graph.current_snapshot().clone();
+            let _ = self.save_sender.send(SaveMessage::Thumbnail(snapshot));
+        }
+    }
+
+    /// 取出保存线程自上次调用以来的自动保存结果，不会阻塞
+    pub fn take_autosave_results(&self) -> Vec<Result<(), Error>> {
+        self.save_result_receiver.try_iter().collect()
+    }
+
+    /// 设置修改后是否自动保存。关闭后只能通过 save() 显式保存。
+    #[inline]
+    pub fn set_autosave(&mut self, autosave: bool) {
+        self.autosave = autosave;
+    }
+
+    /// 设置自动保存的时机，默认在最后一次修改 2 秒后保存，持续修改时最迟 30 秒保存
+    pub fn set_autosave_timing(&mut self, timing: AutosaveTiming) {
+        self.autosave_timing = timing;
+        let _ = self.save_sender.send(SaveMessage::Timing(timing));
+    }
+
+    #[inline]
+    pub fn autosave_timing(&self) -> AutosaveTiming {
+        self.autosave_timing
+    }
+
+    /// 设置拖动节点、连接边等交互是否正在进行。进行中时暂缓自动保存，结束后再按时机保存
+    pub fn set_interacting(&mut self, interacting: bool) {
+        if self.interacting != interacting {
+            self.interacting = interacting;
+            let _ = self.save_sender.send(SaveMessage::Interacting(interacting));
+        }
+    }
+
+    /// 阻塞直到保存线程写入了之前发送的所有快照，写入结果仍通过 take_autosave_results() 取出
+    pub fn flush(&self) -> Result<(), Error> {
+        let (ack_tx, ack_rx) = channel();
+        self.save_sender
+            .send(SaveMessage::Flush(ack_tx))
+            .map_err(|_| Error::WorkerStopped)?;
+        ack_rx.recv().map_err(|_| Error::WorkerStopped)
+    }
+
+    /// 写入待保存的快照后关闭保存线程，返回尚未取出的自动保存结果
+    pub fn close(self) -> Vec<Result<(), Error>> {
+        let Self {
+            save_sender,
+            save_result_receiver,
+            save_worker,
+            ..
+        } = self;
+        // 关闭发送端，保存线程处理完剩余的消息后退出
+        drop(save_sender);
+        if save_worker.join().is_err() {
+            return vec![Err(Error::WorkerStopped)];
+        }
+        save_result_receiver.try_iter().collect()
     }
 
     /// 在修改图谱后调用此方法，将当前快照发送给保存线程以触发保存操作
     fn notify_save(&self) {
+        if !self.autosave {
+            return;
+        }
         // 发送当前快照（克隆一份数据，避免后续修改影响保存）
         let snapshot = self.graph.current_snapshot().clone();
-        // 如果发送失败，则说明保存线程可能已退出，此处打印错误
-        if let Err(e) = self.save_sender.send(snapshot) {
-            eprintln!("发送保存通知失败: {}", e);
+        let revision = self.graph.revision();
+        // 发送失败说明保存线程已退出，下次 flush() 时会返回错误
+        let _ = self.save_sender.send(SaveMessage::Save(snapshot, revision));
+    }
+
+    /// 开始录制宏，之后的编辑操作会被记录。已在录制时重新开始。
+    pub fn start_recording(&mut self) {
+        self.recorder = Some(Recorder::default());
+    }
+
+    /// 停止录制，返回录制的宏。未在录制时返回 None。
+    pub fn stop_recording(&mut self) -> Option<Macro> {
+        self.recorder.take().map(Recorder::finish)
+    }
+
+    #[inline]
+    pub fn is_recording(&self) -> bool {
+        self.recorder.is_some()
+    }
+
+    /// 开启或关闭操作日志，日志追加到 `<文件名>.journal`。
+    /// 开启时先记录当前快照，使重放可以从任意时刻开启的日志恢复图谱
+    pub fn set_journal(&mut self, enabled: bool) -> Result<(), Error> {
+        if !enabled {
+            self.journal = None;
+            return Ok(());
+        }
+        if self.journal.is_none() {
+            let mut journal = Journal::open(journal_path(&self.file_path))?;
+            let snapshot = self.graph.current_snapshot().clone();
+            journal.append(Operation::Open(snapshot), self.graph.revision())?;
+            self.journal = Some(journal);
+        }
+        Ok(())
+    }
+
+    #[inline]
+    pub fn is_journaling(&self) -> bool {
+        self.journal.is_some()
+    }
+
+    /// 开启或关闭增量日志，未保存的修改追加到 `.<文件名>.log`，全量保存后清空，
+    /// 崩溃后再次打开文件时自动重放。关闭时删除日志文件，避免之后重放过期的修改
+    pub fn set_delta_log(&mut self, enabled: bool) -> Result<(), Error> {
+        let mut delta_log = self
+            .delta_log
+            .lock()
+            .map_err(|e| Error::Poison(e.to_string()))?;
+        let path = delta_log_path(&self.file_path);
+        if !enabled {
+            if delta_log.take().is_some() && path.exists() {
+                fs::remove_file(path)?;
+            }
+            return Ok(());
+        }
+        if delta_log.is_none() {
+            let snapshot = self.graph.current_snapshot().clone();
+            *delta_log = Some(DeltaLog::create(path, snapshot, self.graph.revision())?);
+        }
+        Ok(())
+    }
+
+    #[inline]
+    pub fn is_delta_logging(&self) -> bool {
+        self.delta_log
+            .lock()
+            .is_ok_and(|delta_log| delta_log.is_some())
+    }
+
+    /// 本次打开以来记录的操作数，最多保留与撤回记录相同的条数
+    #[inline]
+    pub fn history_len(&self) -> usize {
+        self.history.len()
+    }
+
+    /// 将每个知识单元及其下级节点和所属的知识领域导出为 dir 下单独的 XML 文件，
+    /// 返回写入的文件路径和因内容为空而跳过的单元
+    pub fn export_units(&self, dir: &Path) -> Result<UnitExport, Error> {
+        export_units(self.current_snapshot(), dir, true, &CancelToken::default())
+    }
+
+    /// 将本次打开以来的操作历史导出到 path，格式与操作日志相同，可由 replay_journal() 重放。
+    /// 历史从打开时的快照开始，包含节点内容等全部修改
+    pub fn export_history<P>(&self, path: P) -> Result<(), Error>
+    where
+        P: AsRef<Path>,
+    {
+        let content = self.history.to_journal()?;
+        fs::write(path, content)?;
+        Ok(())
+    }
+
+    /// 取出写入操作日志或增量日志时发生的错误。出错的日志会被关闭，需重新开启
+    pub fn take_journal_error(&mut self) -> Option<Error> {
+        self.journal_error.take()
+    }
+
+    /// 在修改成功后调用，将操作追加到操作历史，以及开启的操作日志和增量日志。
+    /// 增量日志重放时没有之前的撤回记录，因此撤回和重做以操作后的快照记录
+    fn write_journal<F>(&mut self, operation: F)
+    where
+        F: FnOnce() -> Operation,
+    {
+        let revision = self.graph.revision();
+        let operation = operation();
+        self.history
+            .record(operation.clone(), revision, self.graph.current_snapshot());
+
+        let mut delta_log = match self.delta_log.lock() {
+            Ok(delta_log) => delta_log,
+            Err(e) => e.into_inner(),
+        };
+        if let Some(log) = delta_log.as_mut() {
+            let delta = match operation {
+                Operation::Undo | Operation::Redo => {
+                    Operation::Open(self.graph.current_snapshot().clone())
+                }
+                ref operation => operation.clone(),
+            };
+            if let Err(e) = log.append(delta, revision) {
+                // 不完整的日志在之后的保存中不会再被清空，删除以免下次打开时重放
+                *delta_log = None;
+                let _ = fs::remove_file(delta_log_path(&self.file_path));
+                self.journal_error = Some(e);
+            }
         }
+        if let Some(journal) = self.journal.as_mut()
+            && let Err(e) = journal.append(operation, revision)
+        {
+            self.journal = None;
+            self.journal_error = Some(e);
+        }
+    }
+
+    /// 在修改成功后调用，录制命令、写入日志并触发自动保存
+    fn after_mutation(&mut self, command: Command, result: &CommandResult) {
+        self.write_journal(|| Operation::Apply(command.clone()));
+        if let Some(recorder) = self.recorder.as_mut() {
+            recorder.record(command, result);
+        }
+        self.notify_save();
     }
 
     // 以下方法包装了 KnowledgeGraph 的修改接口，
-    // 并在成功修改后调用 notify_save() 自动保存
+    // 并在成功修改后调用 after_mutation() 录制并自动保存
+
+    pub fn apply(&mut self, command: Command) -> Result<CommandResult, GraphError> {
+        let result = self.graph.apply(command.clone())?;
+        self.after_mutation(command, &result);
+        Ok(result)
+    }
+
+    /// 批量执行命令，整体作为一次可撤回的操作。录制时逐条记录。
+    pub fn apply_batch<I>(&mut self, commands: I) -> Result<Vec<CommandResult>, GraphError>
+    where
+        I: IntoIterator<Item = Command>,
+    {
+        let commands = commands.into_iter().collect::<Vec<_>>();
+        let results = self.graph.apply_batch(commands.clone())?;
+        self.write_journal(|| Operation::ApplyBatch(commands.clone()));
+        if let Some(recorder) = self.recorder.as_mut() {
+            for (command, result) in commands.into_iter().zip(results.iter()) {
+                recorder.record(command, result);
+            }
+        }
+        self.notify_save();
+        Ok(results)
+    }
+
+    /// 回放宏，整体作为一次可撤回的操作。回放不会被录制。
+    pub fn play_macro(&mut self, recorded: &Macro) -> Result<Vec<CommandResult>, GraphError> {
+        let results = self.graph.play_macro(recorded)?;
+        self.write_journal(|| Operation::PlayMacro(recorded.clone()));
+        self.notify_save();
+        Ok(results)
+    }
 
     pub fn add_entity(
         &mut self,
@@ -116,20 +782,36 @@ impl FiledKnowledgeGraph {
         distinct_type: DistinctEntityType,
         addon_types: &[AddonEntityType],
         coor: (f64, f64),
-    ) -> u64 {
-        let id = self
-            .graph
-            .add_entity(content, distinct_type, addon_types, coor);
-        self.notify_save();
-        id
+    ) -> Result<u64, GraphError> {
+        let result = self.apply(Command::AddEntity {
+            content,
+            distinct_type,
+            addon_types: addon_types.to_vec(),
+            coor,
+        })?;
+        Ok(result.created_ids()[0])
+    }
+
+    pub fn add_entities<I>(&mut self, entities: I) -> Result<Vec<u64>, GraphError>
+    where
+        I: IntoIterator<Item = EntityNode>,
+    {
+        let result = self.apply(Command::AddEntities(entities.into_iter().collect()))?;
+        Ok(result.created_ids())
     }
 
     pub fn remove_entity(&mut self, id: u64) -> Result<(), GraphError> {
-        let res = self.graph.remove_entity(id);
-        if res.is_ok() {
-            self.notify_save();
-        }
-        res
+        self.apply(Command::RemoveEntity(id)).map(|_| ())
+    }
+
+    /// 按策略删除节点，整体作为一次可撤回的操作
+    pub fn remove_entity_with_policy(
+        &mut self,
+        id: u64,
+        policy: RemovePolicy,
+    ) -> Result<(), GraphError> {
+        let commands = self.graph.current_snapshot().removal_commands(id, policy)?;
+        self.apply_batch(commands).map(|_| ())
     }
 
     pub fn update_entity_content(
@@ -139,13 +821,13 @@ impl FiledKnowledgeGraph {
         distinct_type: DistinctEntityType,
         addon_types: &[AddonEntityType],
     ) -> Result<(), GraphError> {
-        let res = self
-            .graph
-            .update_entity_content(id, content, distinct_type, addon_types);
-        if res.is_ok() {
-            self.notify_save();
-        }
-        res
+        self.apply(Command::UpdateEntityContent {
+            id,
+            content,
+            distinct_type,
+            addon_types: addon_types.to_vec(),
+        })
+        .map(|_| ())
     }
 
     pub fn update_entity_position(
@@ -153,27 +835,30 @@ impl FiledKnowledgeGraph {
         id: u64,
         new_pos: (f64, f64),
     ) -> Result<(), GraphError> {
-        let res = self.graph.update_entity_position(id, new_pos);
-        if res.is_ok() {
-            self.notify_save();
+        self.apply(Command::UpdateEntityPosition { id, coor: new_pos })
+            .map(|_| ())
+    }
+
+    /// 闭包无法录制，录制时记录为以修改后的节点整体替换
+    pub fn update_entity_with<F>(&mut self, id: u64, f: F) -> Result<(), GraphError>
+    where
+        F: FnOnce(&mut EntityNode),
+    {
+        self.graph.update_entity_with(id, f)?;
+        if let Some(node) = self.graph.current_snapshot().nodes.get(&id) {
+            let command = Command::ReplaceEntity(node.clone());
+            self.after_mutation(command, &CommandResult::None);
         }
-        res
+        Ok(())
     }
 
     pub fn add_edge(&mut self, from: u64, to: u64, relation: Relation) -> Result<(), GraphError> {
-        let res = self.graph.add_edge(from, to, relation);
-        if res.is_ok() {
-            self.notify_save();
-        }
-        res
+        self.apply(Command::AddEdge { from, to, relation })
+            .map(|_| ())
     }
 
     pub fn remove_edge(&mut self, from: u64, to: u64) -> Result<(), GraphError> {
-        let res = self.graph.remove_edge(from, to);
-        if res.is_ok() {
-            self.notify_save();
-        }
-        res
+        self.apply(Command::RemoveEdge { from, to }).map(|_| ())
     }
 
     pub fn update_edge(
@@ -182,31 +867,959 @@ impl FiledKnowledgeGraph {
         to: u64,
         relation: Relation,
     ) -> Result<(), GraphError> {
-        let res = self.graph.update_edge(from, to, relation);
-        if res.is_ok() {
+        self.apply(Command::UpdateEdge { from, to, relation })
+            .map(|_| ())
+    }
+
+    pub fn set_edge_label(&mut self, from: u64, to: u64, label: String) -> Result<(), GraphError> {
+        self.apply(Command::SetEdgeLabel { from, to, label })
+            .map(|_| ())
+    }
+
+    /// 设置边的折点，空表表示清除
+    pub fn set_edge_waypoints(
+        &mut self,
+        from: u64,
+        to: u64,
+        waypoints: Vec<(f64, f64)>,
+    ) -> Result<(), GraphError> {
+        self.apply(Command::SetEdgeWaypoints {
+            from,
+            to,
+            waypoints,
+        })
+        .map(|_| ())
+    }
+
+    /// 对每对有双向边的节点保留 keep 中列出的边，整体作为一次可撤回的操作，返回删除的边数
+    pub fn normalize_edges(&mut self, keep: &[(u64, u64)]) -> Result<usize, GraphError> {
+        let commands = self.graph.current_snapshot().normalize_edges_commands(keep);
+        let count = commands.len();
+        if count > 0 {
+            self.apply_batch(commands)?;
+        }
+        Ok(count)
+    }
+
+    pub fn set_children_order(
+        &mut self,
+        parent: u64,
+        ordered_children: &[u64],
+    ) -> Result<(), GraphError> {
+        self.apply(Command::SetChildrenOrder {
+            parent,
+            children: ordered_children.to_vec(),
+        })
+        .map(|_| ())
+    }
+
+    pub fn set_metadata(&mut self, metadata: GraphMetadata) {
+        // 设置元数据总是成功
+        let _ = self.apply(Command::SetMetadata(metadata));
+    }
+
+    pub fn add_group(
+        &mut self,
+        label: String,
+        rect: CoordinateBounds,
+        color: [u8; 3],
+        members: &[u64],
+    ) -> Result<u64, GraphError> {
+        match self.apply(Command::AddGroup {
+            label,
+            rect,
+            color,
+            members: members.to_vec(),
+        })? {
+            CommandResult::Group(id) => Ok(id),
+            _ => unreachable!("adding a group returns its id"),
+        }
+    }
+
+    pub fn update_group(&mut self, group: GroupFrame) -> Result<(), GraphError> {
+        self.apply(Command::UpdateGroup(group)).map(|_| ())
+    }
+
+    pub fn remove_group(&mut self, id: u64) -> Result<(), GraphError> {
+        self.apply(Command::RemoveGroup(id)).map(|_| ())
+    }
+
+    /// 平移分组框及其中的节点，整体作为一次可撤回的操作。
+    /// 以位置更新命令执行，因此会被录制和记入操作日志
+    pub fn move_group(&mut self, id: u64, offset: (f64, f64)) -> Result<(), GraphError> {
+        let commands = self
+            .graph
+            .current_snapshot()
+            .move_group_commands(id, offset)?;
+        self.apply_batch(commands).map(|_| ())
+    }
+
+    /// 将离群节点从 origin 起向下排成一列，整体作为一次可撤回的操作，返回收回的节点。
+    /// 以位置更新命令执行，因此会被录制和记入操作日志
+    pub fn recall_outliers(
+        &mut self,
+        distance: f64,
+        origin: (f64, f64),
+    ) -> Result<Vec<u64>, GraphError> {
+        let snapshot = self.graph.current_snapshot();
+        let ids = snapshot.outlier_nodes(distance);
+        if !ids.is_empty() {
+            self.apply_batch(snapshot.recall_outliers_commands(distance, origin))?;
+        }
+        Ok(ids)
+    }
+
+    /// 将所有节点压缩到 bounds 内，整体作为一次可撤回的操作，返回移动的节点数。
+    /// 以位置更新命令执行，因此会被录制和记入操作日志
+    pub fn fit_to_bounds(&mut self, bounds: CoordinateBounds) -> Result<usize, GraphError> {
+        let moves = self.graph.current_snapshot().fitted_positions(bounds);
+        let count = moves.len();
+        if count > 0 {
+            self.apply_batch(
+                moves
+                    .into_iter()
+                    .map(|(id, coor)| Command::UpdateEntityPosition { id, coor }),
+            )?;
+        }
+        Ok(count)
+    }
+
+    /// 按坐标表更新节点的坐标，整体作为一次可撤回的操作，返回移动的节点数和无法使用的行的警告。
+    /// 坐标表的格式见 Snapshot::to_coordinates_tsv
+    pub fn import_coordinates(&mut self, tsv: &str) -> Result<(usize, Vec<String>), GraphError> {
+        let (commands, warnings) = self.graph.current_snapshot().coordinates_commands(tsv);
+        let count = commands.len();
+        if count > 0 {
+            self.apply_batch(commands)?;
+        }
+        Ok((count, warnings))
+    }
+
+    /// 将所有坐标乘以 factor，整体作为一次可撤回的操作，返回移动的节点数。
+    /// 以位置和分组框更新命令执行，因此会被录制和记入操作日志
+    pub fn scale_coordinates(&mut self, factor: f64) -> Result<usize, GraphError> {
+        let commands = self
+            .graph
+            .current_snapshot()
+            .scale_coordinates_commands(factor);
+        let count = commands
+            .iter()
+            .filter(|command| matches!(command, Command::UpdateEntityPosition { .. }))
+            .count();
+        if !commands.is_empty() {
+            self.apply_batch(commands)?;
+        }
+        Ok(count)
+    }
+
+    /// 按 options 对整个图谱进行分层布局，整体作为一次可撤回的操作，返回移动的节点数。
+    /// 以位置更新命令执行，因此会被录制和记入操作日志
+    pub fn auto_layout(&mut self, options: &LayoutOptions) -> Result<usize, GraphError> {
+        let moves = self.graph.current_snapshot().layered_positions(options);
+        let count = moves.len();
+        if count > 0 {
+            self.apply_batch(
+                moves
+                    .into_iter()
+                    .map(|(id, coor)| Command::UpdateEntityPosition { id, coor }),
+            )?;
+        }
+        Ok(count)
+    }
+
+    /// 将 remove 节点合并到 keep 节点并删除 remove，concat_content 为 true 时拼接两者的内容，
+    /// 整体作为一次可撤回的操作
+    pub fn merge_nodes_with(
+        &mut self,
+        keep: u64,
+        remove: u64,
+        concat_content: bool,
+    ) -> Result<(), GraphError> {
+        let commands =
+            self.graph
+                .current_snapshot()
+                .merge_commands(keep, remove, concat_content)?;
+        if !commands.is_empty() {
+            self.apply_batch(commands)?;
+        }
+        Ok(())
+    }
+
+    /// 将 remove 中的所有节点合并到 keep 节点并删除，保留 keep 的内容，整体作为一次可撤回的操作
+    pub fn merge_entities(&mut self, keep: u64, remove: &[u64]) -> Result<(), GraphError> {
+        let commands = self
+            .graph
+            .current_snapshot()
+            .merge_entities_commands(keep, remove, false)?;
+        if !commands.is_empty() {
+            self.apply_batch(commands)?;
+        }
+        Ok(())
+    }
+
+    /// 将节点的评审标记设为 flag（None 为清除标记），整体作为一次可撤回的操作，返回修改的节点数
+    pub fn set_flags(&mut self, ids: &[u64], flag: Option<Flag>) -> Result<usize, GraphError> {
+        let commands = self
+            .graph
+            .current_snapshot()
+            .set_flags_commands(ids, flag)?;
+        let count = commands.len();
+        if count > 0 {
+            self.apply_batch(commands)?;
+        }
+        Ok(count)
+    }
+
+    /// 将 parent 设为 child 的主父节点，作为一次可撤回的操作。返回是否有修改
+    pub fn set_primary_parent(&mut self, child: u64, parent: u64) -> Result<bool, GraphError> {
+        let commands = self
+            .graph
+            .current_snapshot()
+            .set_primary_parent_commands(child, parent)?;
+        let changed = !commands.is_empty();
+        if changed {
+            self.apply_batch(commands)?;
+        }
+        Ok(changed)
+    }
+
+    /// 将所有节点内容中的非标准写法替换为标准写法，整体作为一次可撤回的操作，返回修改的节点数
+    pub fn apply_standard_terms(&mut self, checker: &dyn TermChecker) -> Result<usize, GraphError> {
+        let commands = self
+            .graph
+            .current_snapshot()
+            .standard_terms_commands(checker);
+        let count = commands.len();
+        if count > 0 {
+            self.apply_batch(commands)?;
+        }
+        Ok(count)
+    }
+
+    /// 移除所有节点内容中对接平台不支持的字符，整体作为一次可撤回的操作，返回修改的节点数
+    pub fn strip_unsupported_chars(&mut self) -> Result<usize, GraphError> {
+        let commands = self.graph.current_snapshot().strip_unsupported_commands();
+        let count = commands.len();
+        if count > 0 {
+            self.apply_batch(commands)?;
+        }
+        Ok(count)
+    }
+
+    /// 规范化指定节点的内容，整体作为一次可撤回的操作，返回修改的节点数
+    pub fn normalize_contents(
+        &mut self,
+        ids: &BTreeSet<u64>,
+        options: NormalizeOptions,
+    ) -> Result<usize, GraphError> {
+        let commands = self
+            .graph
+            .current_snapshot()
+            .normalize_commands(ids, options);
+        let count = commands.len();
+        if count > 0 {
+            self.apply_batch(commands)?;
+        }
+        Ok(count)
+    }
+
+    /// 紧凑化节点 ID。ID 的变化无法以命令表示，因此不会被录制。
+    pub fn compact_ids(&mut self) -> HashMap<u64, u64> {
+        let map = self.graph.compact_ids();
+        self.write_journal(|| Operation::CompactIds);
+        self.notify_save();
+        map
+    }
+
+    /// 自动修复一致性问题，修复可以撤回。修复无法以命令表示，因此不会被录制。
+    pub fn auto_repair(&mut self) -> RepairReport {
+        let revision = self.graph.revision();
+        let report = self.graph.auto_repair();
+        if self.graph.revision() != revision {
+            let snapshot = self.graph.current_snapshot().clone();
+            self.write_journal(|| Operation::Open(snapshot));
             self.notify_save();
         }
-        res
+        report
+    }
+
+    /// 以给定快照整体替换当前快照，替换可以撤回。快照的变化无法以命令表示，因此不会被录制。
+    pub fn replace_current(&mut self, snapshot: Snapshot) {
+        let revision = self.graph.revision();
+        self.graph.replace_current(snapshot);
+        if self.graph.revision() == revision {
+            return;
+        }
+        let snapshot = self.graph.current_snapshot().clone();
+        self.write_journal(|| Operation::Open(snapshot));
+        self.notify_save();
+    }
+
+    /// 重新读取磁盘上的文件（如被其他程序修改后），替换当前快照。
+    /// 替换前的内容仍可通过撤回找回
+    pub fn reload(&mut self) -> Result<(), Error> {
+        let snapshot = Self::read_snapshot(&self.file_path)?;
+        self.replace_current(snapshot);
+        Ok(())
     }
 
     pub fn undo(&mut self) -> Result<(), GraphError> {
-        let res = self.graph.undo();
-        if res.is_ok() {
-            self.notify_save();
+        self.graph.undo()?;
+        self.write_journal(|| Operation::Undo);
+        if let Some(recorder) = self.recorder.as_mut() {
+            recorder.undo();
         }
-        res
+        self.notify_save();
+        Ok(())
     }
 
     pub fn redo(&mut self) -> Result<(), GraphError> {
-        let res = self.graph.redo();
-        if res.is_ok() {
-            self.notify_save();
+        self.graph.redo()?;
+        self.write_journal(|| Operation::Redo);
+        if let Some(recorder) = self.recorder.as_mut() {
+            recorder.redo();
         }
-        res
+        self.notify_save();
+        Ok(())
+    }
+
+    #[inline]
+    pub fn set_max_history_bytes(&mut self, max_bytes: Option<usize>) {
+        self.graph.set_max_history_bytes(max_bytes);
     }
 
     #[inline]
     pub fn current_snapshot(&self) -> &Snapshot {
         self.graph.current_snapshot()
     }
+
+    #[inline]
+    pub fn revision(&self) -> u64 {
+        self.graph.revision()
+    }
+
+    #[inline]
+    pub fn undo_len(&self) -> usize {
+        self.graph.undo_len()
+    }
+
+    #[inline]
+    pub fn content_versions(&self, id: u64, limit: usize) -> Vec<String> {
+        self.graph.content_versions(id, limit)
+    }
+
+    /// 导出当前快照的只读克隆，可发送到其他线程（如预览服务）使用。
+    /// 快照基于 im 的持久化数据结构，克隆只复制根节点，开销很低，不会阻塞编辑。
+    #[inline]
+    pub fn export_snapshot(&self) -> Snapshot {
+        self.graph.current_snapshot().clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::journal::replay_journal;
+
+    /// 测试专用的临时文件路径，按名称区分以免并行测试互相覆盖
+    fn temp_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("better_kt_sqep_{}_{name}.xml", std::process::id()))
+    }
+
+    #[test]
+    fn test_create_existing_file() -> Result<(), Error> {
+        let path = temp_path("create");
+        let mut graph = FiledKnowledgeGraph::new(&path, true)?;
+        let id = graph.add_entity(
+            "Old".to_string(),
+            DistinctEntityType::KnowledgePoint,
+            &[],
+            (0.0, 0.0),
+        )?;
+        graph.save()?;
+        graph.close();
+
+        // 已有内容时拒绝创建，文件保持不变
+        assert!(matches!(
+            FiledKnowledgeGraph::new(&path, true),
+            Err(Error::AlreadyExists(_))
+        ));
+
+        // 改为打开时读取到原有内容，新建时以文件名为标题
+        let graph = FiledKnowledgeGraph::new(&path, false)?;
+        assert_eq!(graph.current_snapshot().nodes[&id].content, "Old");
+        assert_eq!(
+            graph.current_snapshot().title,
+            format!("better_kt_sqep_{}_create", std::process::id())
+        );
+        graph.close();
+
+        // 显式覆盖时清空文件
+        let graph = FiledKnowledgeGraph::overwrite(&path)?;
+        assert!(graph.current_snapshot().nodes.is_empty());
+        assert!(!FiledKnowledgeGraph::has_content(&path));
+        graph.close();
+
+        // 空文件可以直接创建
+        assert!(FiledKnowledgeGraph::new(&path, true)?.close().is_empty());
+
+        fs::remove_file(&path)?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_save_scheduler() {
+        let start = Instant::now();
+        let at = |ms: u64| start + Duration::from_millis(ms);
+        let mut scheduler = SaveScheduler {
+            timing: AutosaveTiming {
+                quiet_period: Duration::from_millis(2000),
+                max_delay: Duration::from_millis(5000),
+            },
+            ..Default::default()
+        };
+        assert_eq!(scheduler.deadline(), None);
+
+        // 静默期内的新修改推迟保存，只保留最新的快照
+        scheduler.push(Snapshot::default(), 1, at(0));
+        scheduler.push(Snapshot::default(), 2, at(1000));
+        assert_eq!(scheduler.deadline(), Some(at(3000)));
+        assert!(scheduler.take_due(at(2500)).is_none());
+        assert_eq!(scheduler.take_due(at(3000)).map(|(_, r)| r), Some(2));
+        assert_eq!(scheduler.deadline(), None);
+
+        // 持续修改时最迟在第一次修改后的最长延迟保存
+        for (i, ms) in (0..=4500).step_by(500).enumerate() {
+            scheduler.push(Snapshot::default(), 10 + i as u64, at(10_000 + ms));
+            assert!(scheduler.take_due(at(10_000 + ms)).is_none());
+        }
+        assert_eq!(scheduler.deadline(), Some(at(15_000)));
+        assert_eq!(scheduler.take_due(at(15_000)).map(|(_, r)| r), Some(19));
+
+        // 交互进行中时暂缓保存，结束后已超过时间的立即保存
+        scheduler.push(Snapshot::default(), 20, at(20_000));
+        scheduler.interacting = true;
+        assert_eq!(scheduler.deadline(), None);
+        assert!(scheduler.take_due(at(40_000)).is_none());
+        scheduler.interacting = false;
+        assert_eq!(scheduler.take_due(at(40_000)).map(|(_, r)| r), Some(20));
+    }
+
+    #[test]
+    fn test_save_scheduler_never_loses_snapshot() {
+        let start = Instant::now();
+        let timing = AutosaveTiming::default();
+        // 按不同的间隔推送快照，并在每次推送之间的任意时刻检查，最后一个快照总会被写入
+        for step in [1, 150, 1999, 2000, 2001, 7000] {
+            let mut scheduler = SaveScheduler {
+                timing,
+                ..Default::default()
+            };
+            let mut written = Vec::new();
+            let mut now = start;
+            for revision in 1..=40 {
+                scheduler.interacting = revision % 7 == 0;
+                scheduler.push(Snapshot::default(), revision, now);
+                now += Duration::from_millis(step);
+                written.extend(scheduler.take_due(now).map(|(_, r)| r));
+            }
+            scheduler.interacting = false;
+            let deadline = scheduler.deadline();
+            written.extend(deadline.and_then(|d| scheduler.take_due(d)).map(|(_, r)| r));
+
+            assert!(scheduler.pending.is_none());
+            assert_eq!(written.last(), Some(&40));
+            assert!(written.windows(2).all(|w| w[0] < w[1]));
+        }
+    }
+
+    #[test]
+    fn test_explicit_save_supersedes_autosave() -> Result<(), Error> {
+        let path = temp_path("supersede");
+        let mut graph = FiledKnowledgeGraph::new(&path, true)?;
+        graph.set_interacting(true);
+        graph.add_entity(
+            "Node".to_string(),
+            DistinctEntityType::KnowledgePoint,
+            &[],
+            (0.0, 0.0),
+        )?;
+        graph.save()?;
+
+        // 显式保存后，等待中的相同版本不会再被写入
+        graph.flush()?;
+        assert!(graph.take_autosave_results().is_empty());
+        assert!(graph.close().is_empty());
+        fs::remove_file(&path)?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_autosave_skips_unchanged() -> Result<(), Error> {
+        let path = temp_path("unchanged");
+        let mut graph = FiledKnowledgeGraph::new(&path, true)?;
+        let id = graph.add_entity(
+            "Node".to_string(),
+            DistinctEntityType::KnowledgePoint,
+            &[],
+            (0.0, 0.0),
+        )?;
+        graph.flush()?;
+        assert_eq!(graph.take_autosave_results().len(), 1);
+        let written = fs::read(&path)?;
+
+        // 修改后又撤回，内容与已写入的相同，不再写入文件
+        graph.update_entity_position(id, (100.0, 0.0))?;
+        graph.undo()?;
+        graph.flush()?;
+        assert!(graph.take_autosave_results().is_empty());
+        assert_eq!(fs::read(&path)?, written);
+
+        // 内容变化时照常写入
+        graph.update_entity_position(id, (100.0, 0.0))?;
+        graph.flush()?;
+        assert_eq!(graph.take_autosave_results().len(), 1);
+        assert_ne!(fs::read(&path)?, written);
+        assert!(graph.close().is_empty());
+        fs::remove_file(&path)?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_flush_and_close() -> Result<(), Error> {
+        let path = temp_path("flush");
+        let mut graph = FiledKnowledgeGraph::new(&path, true)?;
+        let id = graph.add_entity(
+            "Node".to_string(),
+            DistinctEntityType::KnowledgePoint,
+            &[],
+            (0.0, 0.0),
+        )?;
+        graph.update_entity_position(id, (100.0, 0.0))?;
+
+        // flush 返回时快照已写入文件
+        graph.flush()?;
+        assert!(graph.take_autosave_results().iter().all(Result::is_ok));
+        let saved = FiledKnowledgeGraph::read_snapshot(&path)?;
+        assert_eq!(saved.nodes[&id].coor, (100.0, 0.0));
+
+        // 关闭自动保存后，修改不会写入文件
+        graph.set_autosave(false);
+        graph.remove_entity(id)?;
+        graph.flush()?;
+        assert!(
+            FiledKnowledgeGraph::read_snapshot(&path)?
+                .nodes
+                .contains_key(&id)
+        );
+
+        assert!(graph.close().is_empty());
+        fs::remove_file(&path)?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_export_history() -> Result<(), Error> {
+        let path = temp_path("history");
+        let exported = temp_path("history_export");
+
+        // 未开启操作日志时也记录，打开时已有的内容作为起点
+        let mut graph = FiledKnowledgeGraph::new(&path, true)?;
+        let arena = graph.add_entity(
+            "Arena".to_string(),
+            DistinctEntityType::KnowledgeArena,
+            &[],
+            (0.0, 0.0),
+        )?;
+        graph.close();
+        let mut graph = FiledKnowledgeGraph::new(&path, false)?;
+        assert_eq!(graph.history_len(), 0);
+        let unit = graph.add_entity(
+            "Unit".to_string(),
+            DistinctEntityType::KnowledgeUnit,
+            &[],
+            (0.0, 200.0),
+        )?;
+        graph.add_edge(arena, unit, Relation::Contain)?;
+        graph.undo()?;
+        graph.redo()?;
+        graph.compact_ids();
+        assert!(graph.remove_entity(999).is_err());
+        assert_eq!(graph.history_len(), 5);
+
+        graph.export_history(&exported)?;
+        assert!(!journal_path(&path).exists());
+        let replayed = replay_journal(&exported)?;
+        assert_eq!(replayed.current_snapshot(), graph.current_snapshot());
+
+        graph.close();
+        fs::remove_file(&path)?;
+        fs::remove_file(&exported)?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_history_limit() -> Result<(), Error> {
+        let path = temp_path("history_limit");
+        let exported = temp_path("history_limit_export");
+
+        // 超出撤回记录的条数后丢弃最早的操作，撤回到丢弃的操作之前仍可重放
+        let mut graph = FiledKnowledgeGraph::new(&path, false)?;
+        for i in 0..DEFAULT_MAX_HISTORY + 20 {
+            graph.add_entity(
+                format!("Point {i}"),
+                DistinctEntityType::KnowledgePoint,
+                &[],
+                (0.0, 0.0),
+            )?;
+            assert!(graph.history_len() <= DEFAULT_MAX_HISTORY);
+        }
+        for _ in 0..50 {
+            graph.undo()?;
+        }
+        graph.redo()?;
+        assert_eq!(graph.history_len(), DEFAULT_MAX_HISTORY);
+
+        graph.export_history(&exported)?;
+        let replayed = replay_journal(&exported)?;
+        assert_eq!(replayed.current_snapshot(), graph.current_snapshot());
+
+        graph.close();
+        fs::remove_file(&path)?;
+        fs::remove_file(&exported)?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_journal_replay() -> Result<(), Error> {
+        let path = temp_path("journal");
+        let journal = journal_path(&path);
+        let _ = fs::remove_file(&journal);
+
+        let mut graph = FiledKnowledgeGraph::new(&path, true)?;
+        graph.set_journal(true)?;
+        let arena = graph.add_entity(
+            "Arena".to_string(),
+            DistinctEntityType::KnowledgeArena,
+            &[],
+            (0.0, 0.0),
+        )?;
+        graph.start_recording();
+        let point = graph.add_entity(
+            "Point".to_string(),
+            DistinctEntityType::KnowledgePoint,
+            &[AddonEntityType::Example],
+            (200.0, 0.0),
+        )?;
+        graph.add_edge(arena, point, Relation::Contain)?;
+        let recorded = graph.stop_recording().unwrap();
+        graph.update_entity_with(point, |node| node.notes = "Notes".to_string())?;
+        graph.apply_batch([
+            Command::UpdateEntityPosition {
+                id: arena,
+                coor: (0.0, 100.0),
+            },
+            Command::UpdateEdge {
+                from: arena,
+                to: point,
+                relation: Relation::Order,
+            },
+        ])?;
+        graph.remove_entity(arena)?;
+        graph.undo()?;
+        graph.undo()?;
+        graph.redo()?;
+        graph.play_macro(&recorded)?;
+        graph.compact_ids();
+        // 失败的操作不会被记录
+        assert!(graph.remove_entity(999).is_err());
+        graph.close();
+
+        // 再次打开后继续追加，重放从新的起点继续
+        let mut graph = FiledKnowledgeGraph::new(&path, false)?;
+        graph.set_journal(true)?;
+        graph.set_metadata(GraphMetadata {
+            max_nodes: Some(10),
+            ..Default::default()
+        });
+        let replayed = replay_journal(&journal)?;
+        assert_eq!(replayed.current_snapshot(), graph.current_snapshot());
+
+        // 每行都带有时间、操作和版本号
+        let content = fs::read_to_string(&journal)?;
+        assert!(content.lines().all(|line| {
+            let value: serde_json::Value = serde_json::from_str(line).unwrap();
+            value["time"].is_u64() && value["operation"].is_string() && value["revision"].is_u64()
+        }));
+        assert_eq!(
+            content
+                .lines()
+                .filter(|line| line.contains(r#""operation":"undo""#))
+                .count(),
+            2
+        );
+
+        // 关闭日志后不再追加
+        graph.set_journal(false)?;
+        graph.remove_entity(point)?;
+        assert_eq!(fs::read_to_string(&journal)?, content);
+
+        graph.close();
+        fs::remove_file(&path)?;
+        fs::remove_file(&journal)?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_delta_log_recovery() -> Result<(), Error> {
+        let path = temp_path("delta_log");
+        let log = delta_log_path(&path);
+
+        let mut graph = FiledKnowledgeGraph::new(&path, true)?;
+        graph.set_autosave(false);
+        graph.set_delta_log(true)?;
+        let arena = graph.add_entity(
+            "Arena".to_string(),
+            DistinctEntityType::KnowledgeArena,
+            &[],
+            (0.0, 0.0),
+        )?;
+        // 全量保存后日志被清空
+        graph.save()?;
+        assert!(fs::read_to_string(&log)?.is_empty());
+
+        let point = graph.add_entity(
+            "Point".to_string(),
+            DistinctEntityType::KnowledgePoint,
+            &[],
+            (200.0, 0.0),
+        )?;
+        graph.add_edge(arena, point, Relation::Contain)?;
+        graph.update_entity_with(point, |node| node.content = "Updated".to_string())?;
+        graph.undo()?;
+        graph.compact_ids();
+        let expected = graph.current_snapshot().clone();
+        // 未保存就关闭，模拟崩溃
+        graph.close();
+        assert_eq!(FiledKnowledgeGraph::read_snapshot(&path)?.nodes.len(), 1);
+
+        // 再次打开时重放日志中的修改，写入文件并删除日志
+        let mut graph = FiledKnowledgeGraph::new(&path, false)?;
+        assert_eq!(*graph.current_snapshot(), expected);
+        assert_eq!(graph.take_load_warnings().len(), 1);
+        assert_eq!(FiledKnowledgeGraph::read_snapshot(&path)?, expected);
+        assert!(!log.exists());
+        // 恢复的修改保留了撤回记录，可以撤回到崩溃前保存的内容
+        graph.set_autosave(false);
+        while graph.undo().is_ok() {}
+        assert_eq!(graph.current_snapshot().nodes.len(), 1);
+        while graph.redo().is_ok() {}
+        assert_eq!(*graph.current_snapshot(), expected);
+        graph.close();
+
+        // 自动保存追上最新的修改后日志被清空，关闭后删除日志
+        let mut graph = FiledKnowledgeGraph::new(&path, false)?;
+        assert!(graph.take_load_warnings().is_empty());
+        graph.set_delta_log(true)?;
+        graph.remove_entity(point)?;
+        graph.flush()?;
+        assert!(fs::read_to_string(&log)?.is_empty());
+        graph.set_delta_log(false)?;
+        assert!(!log.exists());
+
+        graph.close();
+        fs::remove_file(&path)?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_reload() -> Result<(), Error> {
+        let path = temp_path("reload");
+        let mut graph = FiledKnowledgeGraph::new(&path, true)?;
+        graph.set_autosave(false);
+        let id = graph.add_entity(
+            "Mine".to_string(),
+            DistinctEntityType::KnowledgePoint,
+            &[],
+            (0.0, 0.0),
+        )?;
+        let mine = graph.current_snapshot().clone();
+
+        // 其他程序修改了文件
+        let mut external = KnowledgeGraph::default();
+        external.add_entity(
+            "External".to_string(),
+            DistinctEntityType::KnowledgeArena,
+            &[],
+            (0.0, 0.0),
+        )?;
+        fs::write(&path, external.current_snapshot().to_xml()?)?;
+
+        // 重新载入后可以撤回到载入前的内容
+        graph.reload()?;
+        assert_eq!(graph.current_snapshot().nodes[&id].content, "External");
+        graph.undo()?;
+        assert_eq!(*graph.current_snapshot(), mine);
+        graph.redo()?;
+        assert_eq!(graph.current_snapshot().nodes[&id].content, "External");
+
+        graph.close();
+        fs::remove_file(&path)?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_thumbnail() -> Result<(), Error> {
+        let path = temp_path("thumbnail");
+        let thumbnail = thumbnail_path(&path);
+        let _ = fs::remove_file(&thumbnail);
+
+        // 未开启时不生成
+        let mut graph = FiledKnowledgeGraph::new(&path, true)?;
+        let id = graph.add_entity(
+            "Node".to_string(),
+            DistinctEntityType::KnowledgePoint,
+            &[],
+            (0.0, 0.0),
+        )?;
+        graph.flush()?;
+        assert!(!thumbnail.exists());
+
+        // 开启时缩略图不存在，立即生成
+        graph.set_thumbnail(true);
+        graph.flush()?;
+        let first = fs::read(&thumbnail)?;
+        assert!(first.starts_with(b"\x89PNG"));
+
+        // 自动保存时重新生成
+        graph.add_entity(
+            "Other".to_string(),
+            DistinctEntityType::KnowledgeArena,
+            &[],
+            (400.0, 0.0),
+        )?;
+        graph.flush()?;
+        let second = fs::read(&thumbnail)?;
+        assert_ne!(first, second);
+
+        // 显式保存时也会重新生成
+        graph.set_autosave(false);
+        graph.remove_entity(id)?;
+        graph.save()?;
+        graph.flush()?;
+        assert_ne!(fs::read(&thumbnail)?, second);
+
+        assert!(graph.close().iter().all(Result::is_ok));
+        fs::remove_file(&path)?;
+        fs::remove_file(&thumbnail)?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_export_split_by_arena() -> Result<(), Error> {
+        let dir = std::env::temp_dir().join(format!("better_kt_sqep_{}_split", std::process::id()));
+        fs::create_dir_all(&dir)?;
+        let mut graph = KnowledgeGraph::default();
+        for (content, distinct_type) in [
+            ("操作系统", DistinctEntityType::KnowledgeArena),
+            ("进程", DistinctEntityType::KnowledgePoint),
+            ("孤立", DistinctEntityType::KnowledgePoint),
+        ] {
+            graph.add_entity(content.to_string(), distinct_type, &[], (0.0, 0.0))?;
+        }
+        graph.add_edge(1, 2, Relation::Contain)?;
+
+        let paths = export_split_by_arena(&graph.current, &dir)?;
+        assert_eq!(paths, vec![dir.join("操作系统.xml"), dir.join("其他.xml")]);
+        let arena = FiledKnowledgeGraph::read_snapshot(&paths[0])?;
+        assert_eq!(arena.nodes.len(), 2);
+        assert_eq!(arena.edges.get(&(1, 2)), Some(&Relation::Contain));
+        let rest = FiledKnowledgeGraph::read_snapshot(&paths[1])?;
+        assert_eq!(rest.nodes[&3].content, "孤立");
+
+        fs::remove_dir_all(&dir)?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_export_units() -> Result<(), Error> {
+        let dir = std::env::temp_dir().join(format!("better_kt_sqep_{}_units", std::process::id()));
+        fs::create_dir_all(&dir)?;
+        let path = dir.join("graph.xml");
+        let mut graph = FiledKnowledgeGraph::new(&path, true)?;
+        for (content, distinct_type) in [
+            ("操作系统", DistinctEntityType::KnowledgeArena),
+            ("进程管理", DistinctEntityType::KnowledgeUnit),
+            ("进程", DistinctEntityType::KnowledgePoint),
+            ("", DistinctEntityType::KnowledgeUnit),
+            ("进程管理", DistinctEntityType::KnowledgeUnit),
+        ] {
+            graph.add_entity(content.to_string(), distinct_type, &[], (0.0, 0.0))?;
+        }
+        graph.add_edge(1, 2, Relation::Contain)?;
+        graph.add_edge(2, 3, Relation::Contain)?;
+
+        let units = dir.join("units");
+        fs::create_dir_all(&units)?;
+        let export = graph.export_units(&units)?;
+        assert_eq!(
+            export.written,
+            vec![units.join("进程管理.xml"), units.join("进程管理_2.xml")]
+        );
+        assert_eq!(export.skipped, vec![4]);
+        let unit = FiledKnowledgeGraph::read_snapshot(&export.written[0])?;
+        assert_eq!(unit.nodes.len(), 3);
+        assert_eq!(unit.edges.len(), 2);
+
+        let export = export_units(
+            graph.current_snapshot(),
+            &units,
+            false,
+            &CancelToken::default(),
+        )?;
+        assert_eq!(export.skipped, vec![4]);
+        let unit = FiledKnowledgeGraph::read_snapshot(&export.written[0])?;
+        assert!(!unit.nodes.contains_key(&1));
+
+        drop(graph);
+        fs::remove_dir_all(&dir)?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_cancellable() -> Result<(), Error> {
+        let dir =
+            std::env::temp_dir().join(format!("better_kt_sqep_{}_cancel", std::process::id()));
+        fs::create_dir_all(&dir)?;
+        let path = dir.join("graph.xml");
+        let mut graph = KnowledgeGraph::default();
+        graph.add_entity(
+            "操作系统".to_string(),
+            DistinctEntityType::KnowledgeArena,
+            &[],
+            (0.0, 0.0),
+        )?;
+
+        let token = CancelToken::default();
+        export_compatible(&graph.current, &path, &token)?;
+        let opened = FiledKnowledgeGraph::open_cancellable(&path, &token)?;
+        assert_eq!(opened.current_snapshot().nodes[&1].content, "操作系统");
+        drop(opened);
+
+        // 取消后不写入文件，也不打开图谱
+        token.cancel();
+        let other = dir.join("other.xml");
+        assert!(matches!(
+            export_compatible(&graph.current, &other, &token),
+            Err(Error::Cancelled)
+        ));
+        assert!(!other.exists());
+        assert!(matches!(
+            FiledKnowledgeGraph::open_cancellable(&path, &token),
+            Err(Error::Cancelled)
+        ));
+
+        fs::remove_dir_all(&dir)?;
+        Ok(())
+    }
 }
@@ -0,0 +1,611 @@
+//! 工作包模块。将图谱、视图文件、底图图片和节点模板打包为一个 zip 文件，
+//! 便于把当前的工作状态原样交给他人；导入时解压到指定目录并恢复视图。
+//!
+//! 工作包中的条目以不压缩（stored）的方式存储，任何解压工具都能打开；导入时还接受 deflate 压缩的条目，
+//! 以便读取用其它工具重新打包的工作包。
+
+use std::{
+    fs,
+    path::{Component, Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+
+use super::view_path;
+use crate::{
+    error::{Error, SerdeError},
+    graph::Snapshot,
+};
+
+/// 工作包的格式版本，格式不兼容时递增
+const BUNDLE_FORMAT: u32 = 1;
+
+/// 清单文件在工作包中的名称
+const MANIFEST_NAME: &str = "manifest.json";
+
+/// 节点模板在工作包中的名称
+const TEMPLATES_NAME: &str = "templates.json";
+
+/// 底图图片在工作包中所在的目录
+const UNDERLAY_DIR: &str = "underlay";
+
+const LOCAL_HEADER_SIGNATURE: u32 = 0x0403_4b50;
+const CENTRAL_HEADER_SIGNATURE: u32 = 0x0201_4b50;
+const END_SIGNATURE: u32 = 0x0605_4b50;
+const END_RECORD_LEN: usize = 22;
+const UTF8_NAME_FLAG: u16 = 0x0800; // 条目名称使用 UTF-8 编码
+const ZIP_VERSION: u16 = 20;
+const METHOD_STORED: u16 = 0; // 不压缩
+const METHOD_DEFLATED: u16 = 8; // deflate 压缩
+const DOS_DATE: u16 = 0x21; // 1980-01-01，不记录修改时间
+
+/// 工作包中保存的视图状态
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct SessionView {
+    pub zoom: f32,                 // 缩放比例
+    pub scroll_offset: (f32, f32), // 画布的平移偏移
+}
+
+/// 导出工作包所需的内容
+#[derive(Debug, Clone, Copy)]
+pub struct Session<'a> {
+    pub graph_path: &'a Path, // 图谱文件的路径，决定包中的文件名和视图文件的位置
+    pub snapshot: &'a Snapshot, // 写入包中的图谱，可以包含尚未保存的修改
+    pub view: Option<SessionView>, // 当前的视图状态
+    pub templates: Option<&'a str>, // 节点模板的 JSON
+}
+
+/// 导入的工作包
+#[derive(Debug, Clone, PartialEq)]
+pub struct ImportedSession {
+    pub graph_path: PathBuf,       // 解压后的图谱文件路径
+    pub view: Option<SessionView>, // 导出时的视图状态
+    pub templates: Option<String>, // 节点模板的 JSON
+    pub missing: Vec<String>,      // 清单中列出但包中缺少的可选条目
+}
+
+/// 工作包的清单
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct Manifest {
+    format: u32,         // 工作包的格式版本
+    app_version: String, // 导出时的软件版本
+    graph: String,       // 图谱文件的条目名称
+    #[serde(default)]
+    sidecar: Option<String>, // 视图文件的条目名称
+    #[serde(default)]
+    underlay: Option<String>, // 底图图片的条目名称
+    #[serde(default)]
+    templates: Option<String>, // 节点模板的条目名称
+    #[serde(default)]
+    view: Option<SessionView>,
+}
+
+/// 将工作状态导出为 path 处的工作包。视图文件引用的底图图片不存在时只打包视图文件
+pub fn export_bundle<P>(path: P, session: &Session) -> Result<(), Error>
+where
+    P: AsRef<Path>,
+{
+    let file_name = |path: &Path| {
+        path.file_name()
+            .map(|name| name.to_string_lossy().to_string())
+    };
+    let graph = file_name(session.graph_path).unwrap_or_else(|| "graph.xml".to_string());
+    let mut entries = vec![(graph.clone(), session.snapshot.to_xml()?.into_bytes())];
+    let mut manifest = Manifest {
+        format: BUNDLE_FORMAT,
+        app_version: env!("CARGO_PKG_VERSION").to_string(),
+        graph,
+        sidecar: None,
+        underlay: None,
+        templates: None,
+        view: session.view,
+    };
+
+    // 视图文件中的底图路径改为包中的相对路径，导入时再改为解压后的路径
+    let sidecar_path = view_path(session.graph_path);
+    if let Ok(json) = fs::read_to_string(&sidecar_path) {
+        let mut sidecar =
+            serde_json::from_str::<serde_json::Value>(&json).map_err(SerdeError::from)?;
+        if let Some(path) = underlay_path(&mut sidecar)
+            && let Some(name) = path.as_str().map(Path::new).and_then(file_name)
+            && let Ok(image) = fs::read(path.as_str().unwrap_or_default())
+        {
+            let entry = format!("{UNDERLAY_DIR}/{name}");
+            *path = entry.clone().into();
+            entries.push((entry.clone(), image));
+            manifest.underlay = Some(entry);
+        }
+        let name = file_name(&sidecar_path).unwrap_or_default();
+        let json = serde_json::to_string_pretty(&sidecar).map_err(SerdeError::from)?;
+        entries.push((name.clone(), json.into_bytes()));
+        manifest.sidecar = Some(name);
+    }
+    if let Some(templates) = session.templates {
+        entries.push((TEMPLATES_NAME.to_string(), templates.as_bytes().to_vec()));
+        manifest.templates = Some(TEMPLATES_NAME.to_string());
+    }
+
+    let json = serde_json::to_string_pretty(&manifest).map_err(SerdeError::from)?;
+    entries.insert(0, (MANIFEST_NAME.to_string(), json.into_bytes()));
+    fs::write(path, write_zip(&entries)?)?;
+    Ok(())
+}
+
+/// 将 path 处的工作包解压到目录 dir 并返回其中的内容。图谱文件必须存在，
+/// 视图文件、底图和模板缺少时记入 missing；条目路径试图写到 dir 之外时拒绝导入，
+/// dir 中已有同名的图谱文件时返回 Error::AlreadyExists
+pub fn import_bundle<P, Q>(path: P, dir: Q) -> Result<ImportedSession, Error>
+where
+    P: AsRef<Path>,
+    Q: AsRef<Path>,
+{
+    let entries = read_zip(&fs::read(path)?)?;
+    let dir = dir.as_ref();
+    // 先检查所有条目的路径，避免写入一部分后才发现问题
+    for (name, _) in entries.iter() {
+        entry_path(name)?;
+    }
+    let entry = |name: &str| {
+        entries
+            .iter()
+            .find(|(entry, _)| entry == name)
+            .map(|(_, data)| data.as_slice())
+    };
+
+    let manifest = entry(MANIFEST_NAME)
+        .ok_or_else(|| SerdeError::Unexpected("工作包", "缺少 manifest.json".to_string()))?;
+    let manifest = serde_json::from_slice::<Manifest>(manifest).map_err(SerdeError::from)?;
+    if manifest.format > BUNDLE_FORMAT {
+        return Err(SerdeError::Unexpected("工作包版本", manifest.format.to_string()).into());
+    }
+    let graph = entry(&manifest.graph)
+        .ok_or_else(|| SerdeError::Unexpected("工作包", format!("缺少 {}", manifest.graph)))?;
+    let graph_path = dir.join(entry_path(&manifest.graph)?);
+    if graph_path.exists() {
+        return Err(Error::AlreadyExists(graph_path));
+    }
+    fs::create_dir_all(dir)?;
+
+    let mut missing = Vec::new();
+    let underlay = match manifest.underlay.as_deref() {
+        Some(name) => match entry(name) {
+            Some(image) => {
+                let path = dir.join(entry_path(name)?);
+                if let Some(parent) = path.parent() {
+                    fs::create_dir_all(parent)?;
+                }
+                fs::write(&path, image)?;
+                Some(path)
+            }
+            None => {
+                missing.push(name.to_string());
+                None
+            }
+        },
+        None => None,
+    };
+    if let Some(name) = manifest.sidecar.as_deref() {
+        match entry(name) {
+            Some(json) => {
+                let mut sidecar =
+                    serde_json::from_slice::<serde_json::Value>(json).map_err(SerdeError::from)?;
+                if let (Some(path), Some(underlay)) = (underlay_path(&mut sidecar), underlay) {
+                    *path = underlay.to_string_lossy().to_string().into();
+                }
+                let json = serde_json::to_string_pretty(&sidecar).map_err(SerdeError::from)?;
+                fs::write(view_path(&graph_path), json)?;
+            }
+            None => missing.push(name.to_string()),
+        }
+    }
+    let templates = match manifest.templates.as_deref() {
+        Some(name) => match entry(name) {
+            Some(json) => Some(
+                std::str::from_utf8(json)
+                    .map_err(SerdeError::from)?
+                    .to_string(),
+            ),
+            None => {
+                missing.push(name.to_string());
+                None
+            }
+        },
+        None => None,
+    };
+    fs::write(&graph_path, graph)?;
+
+    Ok(ImportedSession {
+        graph_path,
+        view: manifest.view,
+        templates,
+        missing,
+    })
+}
+
+/// 视图文件中底图路径所在的值
+fn underlay_path(sidecar: &mut serde_json::Value) -> Option<&mut serde_json::Value> {
+    sidecar
+        .get_mut("underlay")?
+        .get_mut("path")
+        .filter(|path| path.is_string())
+}
+
+/// 条目名称对应的相对路径。绝对路径、含有 `..`、盘符或反斜杠的名称可能写到目录之外，返回错误
+fn entry_path(name: &str) -> Result<PathBuf, Error> {
+    let path = Path::new(name);
+    let safe = !name.is_empty()
+        && !name.contains('\\')
+        && path
+            .components()
+            .all(|component| matches!(component, Component::Normal(_)));
+    if safe {
+        Ok(path.to_path_buf())
+    } else {
+        Err(SerdeError::Unexpected("工作包中的路径", name.to_string()).into())
+    }
+}
+
+/// zip 文件中以 16 位记录的长度或数量，超出时返回错误（不支持 zip64）
+fn zip_u16(value: usize, what: &'static str) -> Result<u16, Error> {
+    u16::try_from(value)
+        .map_err(|_| SerdeError::Unexpected(what, format!("{value} 超出 zip 格式的上限")).into())
+}
+
+/// zip 文件中以 32 位记录的大小或偏移，超出时返回错误（不支持 zip64）
+fn zip_u32(value: usize, what: &'static str) -> Result<u32, Error> {
+    u32::try_from(value)
+        .map_err(|_| SerdeError::Unexpected(what, format!("{value} 超出 zip 格式的上限")).into())
+}
+
+/// 生成以不压缩的方式存储各条目的 zip 文件。条目名称、大小或条目数超出 zip 格式的上限时返回错误
+fn write_zip(entries: &[(String, Vec<u8>)]) -> Result<Vec<u8>, Error> {
+    let mut zip = Vec::new();
+    let mut central = Vec::new();
+    for (name, data) in entries {
+        let offset = zip_u32(zip.len(), "工作包大小")?;
+        let size = zip_u32(data.len(), "工作包条目大小")?;
+        let name_len = zip_u16(name.len(), "工作包条目名称长度")?;
+        let crc = crc32fast::hash(data);
+        let header = |buffer: &mut Vec<u8>| {
+            buffer.extend(UTF8_NAME_FLAG.to_le_bytes());
+            buffer.extend(METHOD_STORED.to_le_bytes());
+            buffer.extend(0u16.to_le_bytes()); // 修改时间
+            buffer.extend(DOS_DATE.to_le_bytes());
+            buffer.extend(crc.to_le_bytes());
+            buffer.extend(size.to_le_bytes()); // 压缩后的大小
+            buffer.extend(size.to_le_bytes());
+            buffer.extend(name_len.to_le_bytes());
+            buffer.extend(0u16.to_le_bytes()); // 扩展字段长度
+        };
+
+        zip.extend(LOCAL_HEADER_SIGNATURE.to_le_bytes());
+        zip.extend(ZIP_VERSION.to_le_bytes());
+        header(&mut zip);
+        zip.extend(name.as_bytes());
+        zip.extend(data);
+
+        central.extend(CENTRAL_HEADER_SIGNATURE.to_le_bytes());
+        central.extend(ZIP_VERSION.to_le_bytes()); // 生成的版本
+        central.extend(ZIP_VERSION.to_le_bytes()); // 解压所需的版本
+        header(&mut central);
+        central.extend(0u16.to_le_bytes()); // 注释长度
+        central.extend(0u16.to_le_bytes()); // 所在分卷
+        central.extend(0u16.to_le_bytes()); // 内部属性
+        central.extend(0u32.to_le_bytes()); // 外部属性
+        central.extend(offset.to_le_bytes());
+        central.extend(name.as_bytes());
+    }
+
+    let central_offset = zip_u32(zip.len(), "工作包大小")?;
+    let central_len = zip_u32(central.len(), "工作包目录大小")?;
+    let count = zip_u16(entries.len(), "工作包条目数")?;
+    zip.extend(&central);
+    zip.extend(END_SIGNATURE.to_le_bytes());
+    zip.extend(0u16.to_le_bytes()); // 当前分卷
+    zip.extend(0u16.to_le_bytes()); // 目录所在分卷
+    zip.extend(count.to_le_bytes());
+    zip.extend(count.to_le_bytes());
+    zip.extend(central_len.to_le_bytes());
+    zip.extend(central_offset.to_le_bytes());
+    zip.extend(0u16.to_le_bytes()); // 注释长度
+    Ok(zip)
+}
+
+/// 读取 zip 文件中的所有条目，支持不压缩和 deflate 压缩的条目，并校验 CRC
+fn read_zip(zip: &[u8]) -> Result<Vec<(String, Vec<u8>)>, Error> {
+    let invalid = |reason: &str| Error::from(SerdeError::Unexpected("工作包", reason.to_string()));
+    let u16_at = |offset: usize| {
+        zip.get(offset..offset + 2)
+            .map(|bytes| u16::from_le_bytes([bytes[0], bytes[1]]) as usize)
+            .ok_or_else(|| invalid("文件不完整"))
+    };
+    let u32_at = |offset: usize| {
+        zip.get(offset..offset + 4)
+            .map(|bytes| u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+            .ok_or_else(|| invalid("文件不完整"))
+    };
+
+    // 目录结束记录之后可能还有注释，从后向前查找
+    let end = (0..=zip.len().saturating_sub(END_RECORD_LEN))
+        .rev()
+        .find(|offset| u32_at(*offset).is_ok_and(|signature| signature == END_SIGNATURE))
+        .ok_or_else(|| invalid("不是 zip 文件"))?;
+    let count = u16_at(end + 10)?;
+    let mut offset = u32_at(end + 16)? as usize;
+
+    let mut entries = Vec::with_capacity(count);
+    for _ in 0..count {
+        if u32_at(offset)? != CENTRAL_HEADER_SIGNATURE {
+            return Err(invalid("目录已损坏"));
+        }
+        let method = u16_at(offset + 10)? as u16;
+        let crc = u32_at(offset + 16)?;
+        let compressed_size = u32_at(offset + 20)? as usize;
+        let size = u32_at(offset + 24)? as usize;
+        let name_len = u16_at(offset + 28)?;
+        let skipped = u16_at(offset + 30)? + u16_at(offset + 32)?;
+        let local = u32_at(offset + 42)? as usize;
+        let name = zip
+            .get(offset + 46..offset + 46 + name_len)
+            .ok_or_else(|| invalid("文件不完整"))?;
+        let name = std::str::from_utf8(name)
+            .map_err(SerdeError::from)?
+            .to_string();
+        offset += 46 + name_len + skipped;
+
+        if u32_at(local)? != LOCAL_HEADER_SIGNATURE {
+            return Err(invalid("条目已损坏"));
+        }
+        let start = local + 30 + u16_at(local + 26)? + u16_at(local + 28)?;
+        let raw = zip
+            .get(start..start + compressed_size)
+            .ok_or_else(|| invalid("文件不完整"))?;
+        let data = match method {
+            METHOD_STORED => raw.to_vec(),
+            METHOD_DEFLATED => miniz_oxide::inflate::decompress_to_vec_with_limit(raw, size)
+                .map_err(|e| SerdeError::Unexpected("工作包条目解压", format!("{name}: {e}")))?,
+            method => {
+                return Err(
+                    SerdeError::Unexpected("工作包压缩方式", format!("{name}: {method}")).into(),
+                );
+            }
+        };
+        if data.len() != size || crc32fast::hash(&data) != crc {
+            return Err(SerdeError::Unexpected("工作包条目校验", name).into());
+        }
+        entries.push((name, data));
+    }
+    Ok(entries)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        file::FiledKnowledgeGraph,
+        graph::{DistinctEntityType, KnowledgeGraph, Relation},
+    };
+
+    /// 测试专用的临时目录，按名称区分以免并行测试互相覆盖
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "better_kt_sqep_{}_bundle_{name}",
+            std::process::id()
+        ));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_bundle_round_trip() -> Result<(), Error> {
+        let dir = temp_dir("round_trip");
+        let mut graph = KnowledgeGraph::default();
+        graph.add_entity(
+            "编译原理".to_string(),
+            DistinctEntityType::KnowledgeArena,
+            &[],
+            (0.0, 0.0),
+        )?;
+        graph.add_entity(
+            "词法分析".to_string(),
+            DistinctEntityType::KnowledgeUnit,
+            &[],
+            (0.0, 200.0),
+        )?;
+        graph.add_edge(1, 2, Relation::Contain)?;
+
+        let graph_path = dir.join("课程.xml");
+        let image_path = dir.join("大纲.png");
+        fs::write(&image_path, b"image bytes")?;
+        let sidecar = serde_json::json!({
+            "underlay": { "path": image_path, "scale": 2.0, "opacity": 0.5 }
+        });
+        fs::write(view_path(&graph_path), sidecar.to_string())?;
+
+        let view = SessionView {
+            zoom: 1.5,
+            scroll_offset: (120.0, -40.0),
+        };
+        let bundle = dir.join("work.zip");
+        export_bundle(
+            &bundle,
+            &Session {
+                graph_path: &graph_path,
+                snapshot: &graph.current,
+                view: Some(view),
+                templates: Some("[]"),
+            },
+        )?;
+
+        let target = dir.join("imported");
+        let imported = import_bundle(&bundle, &target)?;
+        assert_eq!(imported.graph_path, target.join("课程.xml"));
+        assert_eq!(imported.view, Some(view));
+        assert_eq!(imported.templates.as_deref(), Some("[]"));
+        assert!(imported.missing.is_empty());
+        let snapshot = FiledKnowledgeGraph::read_snapshot(&imported.graph_path)?;
+        assert_eq!(snapshot.nodes, graph.current.nodes);
+        assert_eq!(snapshot.edges, graph.current.edges);
+
+        // 视图文件中的底图指向解压后的图片，其他设置不变
+        let restored = fs::read_to_string(view_path(&imported.graph_path))?;
+        let restored = serde_json::from_str::<serde_json::Value>(&restored).unwrap();
+        let underlay = target.join(UNDERLAY_DIR).join("大纲.png");
+        assert_eq!(
+            restored["underlay"]["path"],
+            underlay.to_string_lossy().as_ref()
+        );
+        assert_eq!(restored["underlay"]["scale"], 2.0);
+        assert_eq!(fs::read(&underlay)?, b"image bytes");
+
+        // 不覆盖已有的图谱文件
+        assert!(matches!(
+            import_bundle(&bundle, &target),
+            Err(Error::AlreadyExists(_))
+        ));
+
+        fs::remove_dir_all(&dir)?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_bundle_missing_members() -> Result<(), Error> {
+        let dir = temp_dir("missing");
+        let manifest = Manifest {
+            format: BUNDLE_FORMAT,
+            app_version: "0.0.0".to_string(),
+            graph: "graph.xml".to_string(),
+            sidecar: Some(".graph.xml.view.json".to_string()),
+            underlay: Some("underlay/outline.png".to_string()),
+            templates: Some(TEMPLATES_NAME.to_string()),
+            view: None,
+        };
+        let xml = KnowledgeGraph::default().current.to_xml()?;
+        let bundle = dir.join("partial.zip");
+        fs::write(
+            &bundle,
+            write_zip(&[
+                (
+                    MANIFEST_NAME.to_string(),
+                    serde_json::to_vec(&manifest).unwrap(),
+                ),
+                ("graph.xml".to_string(), xml.into_bytes()),
+            ])?,
+        )?;
+
+        let imported = import_bundle(&bundle, dir.join("out"))?;
+        assert!(imported.graph_path.exists());
+        assert_eq!(imported.templates, None);
+        assert_eq!(imported.missing.len(), 3);
+        assert!(!view_path(&imported.graph_path).exists());
+
+        // 缺少图谱文件时无法导入
+        fs::write(
+            &bundle,
+            write_zip(&[(
+                MANIFEST_NAME.to_string(),
+                serde_json::to_vec(&manifest).unwrap(),
+            )])?,
+        )?;
+        assert!(import_bundle(&bundle, dir.join("empty")).is_err());
+
+        fs::remove_dir_all(&dir)?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_bundle_path_traversal() -> Result<(), Error> {
+        let dir = temp_dir("traversal");
+        for name in [
+            "../evil.xml",
+            "/tmp/evil.xml",
+            "a/../../evil.xml",
+            "..\\evil.xml",
+            "",
+        ] {
+            assert!(entry_path(name).is_err(), "{name}");
+            let manifest = Manifest {
+                format: BUNDLE_FORMAT,
+                app_version: "0.0.0".to_string(),
+                graph: name.to_string(),
+                sidecar: None,
+                underlay: None,
+                templates: None,
+                view: None,
+            };
+            let bundle = dir.join("evil.zip");
+            fs::write(
+                &bundle,
+                write_zip(&[
+                    (
+                        MANIFEST_NAME.to_string(),
+                        serde_json::to_vec(&manifest).unwrap(),
+                    ),
+                    (name.to_string(), b"<KG/>".to_vec()),
+                ])?,
+            )?;
+            assert!(import_bundle(&bundle, dir.join("out")).is_err());
+        }
+        assert!(!dir.join("evil.xml").exists());
+        assert!(entry_path("underlay/outline.png").is_ok());
+
+        // 损坏的文件
+        assert!(read_zip(b"not a zip").is_err());
+        let mut zip = write_zip(&[("a.txt".to_string(), b"abc".to_vec())])?;
+        zip[30 + 5] = b'x'; // 修改数据使 CRC 不匹配
+        assert!(read_zip(&zip).is_err());
+
+        fs::remove_dir_all(&dir)?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_deflated_zip() -> Result<(), Error> {
+        // 其它工具打包的文件，图谱以 deflate 压缩，说明文件不压缩
+        let zip = include_bytes!("../../tests/fixtures/deflated.zip");
+        let entries = read_zip(zip)?;
+        assert_eq!(entries.len(), 2);
+        let (name, xml) = &entries[0];
+        assert_eq!(name, "graph.xml");
+        let snapshot = Snapshot::from_xml(std::str::from_utf8(xml).unwrap())?;
+        assert!(!snapshot.nodes.is_empty());
+        assert_eq!(
+            entries[1],
+            (
+                "readme.txt".to_string(),
+                "由其它工具打包".as_bytes().to_vec()
+            )
+        );
+
+        // 压缩的数据损坏时报错
+        let mut corrupted = zip.to_vec();
+        corrupted[30 + "graph.xml".len() + 10] ^= 0xFF;
+        assert!(read_zip(&corrupted).is_err());
+
+        // 不支持的压缩方式
+        let mut unsupported = write_zip(&[("a.txt".to_string(), b"abc".to_vec())])?;
+        let central = unsupported.len() - END_RECORD_LEN - 46 - "a.txt".len();
+        unsupported[central + 10] = 12; // bzip2
+        assert!(read_zip(&unsupported).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_zip_limits() -> Result<(), Error> {
+        assert_eq!(zip_u16(u16::MAX as usize, "长度")?, u16::MAX);
+        assert!(zip_u16(u16::MAX as usize + 1, "长度").is_err());
+        assert_eq!(zip_u32(u32::MAX as usize, "大小")?, u32::MAX);
+        assert!(zip_u32(u32::MAX as usize + 1, "大小").is_err());
+
+        // 名称过长、条目过多时不生成截断的文件
+        let long_name = "a".repeat(u16::MAX as usize + 1);
+        assert!(write_zip(&[(long_name, Vec::new())]).is_err());
+        let entries = (0..=u16::MAX as usize)
+            .map(|i| (i.to_string(), Vec::new()))
+            .collect::<Vec<_>>();
+        assert!(write_zip(&entries).is_err());
+        assert_eq!(read_zip(&write_zip(&entries[..10])?)?, entries[..10]);
+        Ok(())
+    }
+}
@@ -0,0 +1,146 @@
+//! 可取消的后台任务。打开和导出大文件可能需要数秒，放到后台线程中执行，界面保持响应并可中途取消。
+//! 任务在各阶段之间检查取消标志，取消后提前返回 Error::Cancelled，不修改已有的状态。
+
+use std::{
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+        mpsc::{Receiver, TryRecvError, channel},
+    },
+    thread,
+};
+
+use crate::error::Error;
+
+/// 取消令牌，克隆后与后台线程共享同一个取消标志
+#[derive(Debug, Clone, Default)]
+pub struct CancelToken(Arc<AtomicBool>);
+
+impl CancelToken {
+    /// 请求取消
+    #[inline]
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Release);
+    }
+
+    #[inline]
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Acquire)
+    }
+
+    /// 已请求取消时返回 Error::Cancelled，供任务在各阶段之间以 ? 提前返回
+    #[inline]
+    pub fn check(&self) -> Result<(), Error> {
+        if self.is_cancelled() {
+            Err(Error::Cancelled)
+        } else {
+            Ok(())
+        }
+    }
+}
+
+/// 在后台线程中执行的任务，界面每帧以 try_take 取出结果
+#[derive(Debug)]
+pub struct BackgroundTask<T> {
+    /// 显示在进度窗口中的说明
+    pub label: String,
+    token: CancelToken,
+    receiver: Receiver<Result<T, Error>>,
+}
+
+impl<T: Send + 'static> BackgroundTask<T> {
+    /// 启动任务。task 应在耗时的阶段之间调用 CancelToken::check
+    pub fn spawn<F>(label: impl Into<String>, task: F) -> Self
+    where
+        F: FnOnce(&CancelToken) -> Result<T, Error> + Send + 'static,
+    {
+        let token = CancelToken::default();
+        let task_token = token.clone();
+        let (tx, rx) = channel();
+        thread::spawn(move || {
+            let result = task(&task_token).and_then(|value| {
+                // 完成后才取消时同样丢弃结果，保持原状态不变
+                task_token.check()?;
+                Ok(value)
+            });
+            // 任务已被丢弃时无需发送
+            let _ = tx.send(result);
+        });
+        Self {
+            label: label.into(),
+            token,
+            receiver: rx,
+        }
+    }
+
+    /// 请求取消任务。之后 try_take 返回 Error::Cancelled 或不再返回结果
+    #[inline]
+    pub fn cancel(&self) {
+        self.token.cancel();
+    }
+
+    /// 取出任务的结果，尚未完成时返回 None。后台线程意外退出时返回 Error::WorkerStopped
+    pub fn try_take(&self) -> Option<Result<T, Error>> {
+        match self.receiver.try_recv() {
+            Ok(result) => Some(result),
+            Err(TryRecvError::Empty) => None,
+            Err(TryRecvError::Disconnected) => Some(Err(Error::WorkerStopped)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{sync::mpsc::sync_channel, time::Duration};
+
+    use super::*;
+
+    /// 等待任务完成
+    fn wait<T: Send + 'static>(task: &BackgroundTask<T>) -> Result<T, Error> {
+        loop {
+            if let Some(result) = task.try_take() {
+                return result;
+            }
+            thread::sleep(Duration::from_millis(1));
+        }
+    }
+
+    #[test]
+    fn test_background_task() {
+        let task = BackgroundTask::spawn("计算", |token| {
+            token.check()?;
+            Ok(42)
+        });
+        assert_eq!(task.label, "计算");
+        assert_eq!(wait(&task).unwrap(), 42);
+
+        // 任务在阶段之间检查到取消后提前返回
+        let (started_tx, started_rx) = sync_channel(0);
+        let (resume_tx, resume_rx) = sync_channel::<()>(0);
+        let task = BackgroundTask::<()>::spawn("读取", move |token| {
+            started_tx.send(()).unwrap();
+            resume_rx.recv().unwrap();
+            token.check()?;
+            unreachable!("取消后不应继续执行");
+        });
+        started_rx.recv().unwrap();
+        assert!(task.try_take().is_none());
+        task.cancel();
+        resume_tx.send(()).unwrap();
+        assert!(matches!(wait(&task), Err(Error::Cancelled)));
+
+        // 完成后才取消时同样丢弃结果
+        let (resume_tx, resume_rx) = sync_channel::<()>(0);
+        let task = BackgroundTask::spawn("写入", move |_| {
+            resume_rx.recv().unwrap();
+            Ok(())
+        });
+        task.cancel();
+        resume_tx.send(()).unwrap();
+        assert!(matches!(wait(&task), Err(Error::Cancelled)));
+
+        // 线程意外退出
+        let task = BackgroundTask::<()>::spawn("崩溃", |_| panic!("任务出错"));
+        assert!(matches!(wait(&task), Err(Error::WorkerStopped)));
+    }
+}
@@ -0,0 +1,333 @@
+//! 邻接表文本模块，将图谱转换为便于代码评审比对的规范文本。
+//!
+//! 格式：先按 ID 升序每行一个节点 `id<TAB>类型<TAB>附加类型<TAB>内容`，
+//! 再按 (起点, 终点) 升序每行一条边 `起点 -> 终点 : 关系`。
+//! 不包含坐标，因此只调整布局不会产生差异。
+
+use std::collections::HashSet;
+
+use im::HashMap;
+
+use super::{AddonEntityType, DistinctEntityType, EntityNode, MAX_ENTITY_ID, Relation, Snapshot};
+use crate::error::SerdeError;
+
+/// 附加类型及其字符，顺序与 XML 中的一致，即 T Z Q K E P
+const ADDON_CHARS: [(AddonEntityType, char); 6] = [
+    (AddonEntityType::Thinking, 't'),
+    (AddonEntityType::Political, 'z'),
+    (AddonEntityType::Question, 'q'),
+    (AddonEntityType::Knowledge, 'k'),
+    (AddonEntityType::Example, 'e'),
+    (AddonEntityType::Practice, 'p'),
+];
+
+impl DistinctEntityType {
+    /// 邻接表中的类型缩写，与 Python 绑定一致
+    fn abbr(&self) -> &'static str {
+        match *self {
+            DistinctEntityType::KnowledgeArena => "ka",
+            DistinctEntityType::KnowledgeUnit => "ku",
+            DistinctEntityType::KnowledgePoint => "kp",
+            DistinctEntityType::KnowledgeDetail => "kd",
+        }
+    }
+
+    pub(super) fn from_abbr(abbr: &str) -> Option<Self> {
+        match abbr {
+            "ka" => Some(DistinctEntityType::KnowledgeArena),
+            "ku" => Some(DistinctEntityType::KnowledgeUnit),
+            "kp" => Some(DistinctEntityType::KnowledgePoint),
+            "kd" => Some(DistinctEntityType::KnowledgeDetail),
+            _ => None,
+        }
+    }
+}
+
+impl AddonEntityType {
+    /// 邻接表中的附加类型字符，与 Python 绑定一致
+    pub(super) fn abbr(&self) -> char {
+        ADDON_CHARS
+            .iter()
+            .find(|(addon, _)| addon == self)
+            .map_or('?', |(_, c)| *c)
+    }
+}
+
+impl Relation {
+    /// 邻接表中的关系缩写，自定义关系为 custom 加编号，如 custom3
+    pub(crate) fn abbr(&self) -> String {
+        match *self {
+            Relation::Contain => "contain".to_string(),
+            Relation::Order => "order".to_string(),
+            Relation::Extension => "extension".to_string(),
+            Relation::Custom(id) => format!("custom{id}"),
+        }
+    }
+
+    fn from_abbr(abbr: &str) -> Option<Self> {
+        Relation::ALL
+            .into_iter()
+            .find(|relation| relation.abbr() == abbr)
+            .or_else(|| {
+                Some(Relation::from_id(
+                    abbr.strip_prefix("custom")?.parse().ok()?,
+                ))
+            })
+    }
+}
+
+impl Snapshot {
+    /// 转换为邻接表文本
+    pub fn to_adjacency_text(&self) -> String {
+        let mut nodes = self.nodes.values().collect::<Vec<_>>();
+        nodes.sort_unstable_by_key(|node| node.id);
+        let mut edges = self.edges.iter().collect::<Vec<_>>();
+        edges.sort_unstable_by_key(|(key, _)| **key);
+
+        let mut text = String::new();
+        for node in nodes {
+            let addons = ADDON_CHARS
+                .iter()
+                .filter(|(addon, _)| node.addon_types.contains(addon))
+                .map(|(_, c)| *c)
+                .collect::<String>();
+            text.push_str(&format!(
+                "{}\t{}\t{}\t{}\n",
+                node.id,
+                node.distinct_type.abbr(),
+                if addons.is_empty() { "-" } else { &addons },
+                escape(&node.content),
+            ));
+        }
+        for ((from, to), relation) in edges {
+            text.push_str(&format!("{from} -> {to} : {}\n", relation.abbr()));
+        }
+
+        text
+    }
+
+    /// 从邻接表文本解析，坐标由分层布局生成。
+    /// 解析是严格的：格式错误、ID 重复、节点出现在边之后或边引用不存在的节点都会返回错误。
+    pub fn from_adjacency_text(text: &str) -> Result<Self, SerdeError> {
+        let mut nodes = HashMap::new();
+        let mut edges = HashMap::new();
+
+        for (i, line) in text.lines().enumerate() {
+            let unexpected = |reason: &str| {
+                SerdeError::Unexpected("adjacency line", format!("{}: {reason}", i + 1))
+            };
+            if line.is_empty() {
+                continue;
+            }
+
+            if line.contains('\t') {
+                if !edges.is_empty() {
+                    return Err(unexpected("node after edges"));
+                }
+                let node = parse_node(line).ok_or_else(|| unexpected("invalid node"))?;
+                if node.id > MAX_ENTITY_ID {
+                    return Err(unexpected("node id out of range"));
+                }
+                if nodes.insert(node.id, node).is_some() {
+                    return Err(unexpected("duplicate node id"));
+                }
+            } else {
+                let (from, to, relation) =
+                    parse_edge(line).ok_or_else(|| unexpected("invalid edge"))?;
+                if !nodes.contains_key(&from) || !nodes.contains_key(&to) {
+                    return Err(unexpected("edge references unknown node"));
+                }
+                if edges.insert((from, to), relation).is_some() {
+                    return Err(unexpected("duplicate edge"));
+                }
+            }
+        }
+
+        let mut snapshot = Snapshot {
+            nodes,
+            edges,
+            ..Default::default()
+        };
+        snapshot.repair_latest_id();
+        snapshot.apply_layered_layout();
+
+        Ok(snapshot)
+    }
+}
+
+/// 解析节点行
+fn parse_node(line: &str) -> Option<EntityNode> {
+    let mut fields = line.splitn(4, '\t');
+    let id = fields.next()?.parse().ok()?;
+    let distinct_type = DistinctEntityType::from_abbr(fields.next()?)?;
+    let addons = fields.next()?;
+    let content = unescape(fields.next()?)?;
+
+    let addon_types = if addons == "-" {
+        HashSet::new()
+    } else {
+        addons
+            .chars()
+            .map(|c| {
+                ADDON_CHARS
+                    .iter()
+                    .find(|(_, ch)| *ch == c)
+                    .map(|(addon, _)| *addon)
+            })
+            .collect::<Option<HashSet<_>>>()?
+    };
+
+    let mut node = EntityNode::new(id, content, distinct_type, &[], (0.0, 0.0));
+    node.addon_types = addon_types;
+    Some(node)
+}
+
+/// 解析边行
+fn parse_edge(line: &str) -> Option<(u64, u64, Relation)> {
+    let (from, rest) = line.split_once(" -> ")?;
+    let (to, relation) = rest.split_once(" : ")?;
+    Some((
+        from.parse().ok()?,
+        to.parse().ok()?,
+        Relation::from_abbr(relation)?,
+    ))
+}
+
+/// 转义内容中的反斜杠、制表符和换行，使每个节点占一行
+fn escape(content: &str) -> String {
+    let mut escaped = String::with_capacity(content.len());
+    for c in content.chars() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            '\t' => escaped.push_str("\\t"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// 还原转义的内容，遇到无效的转义时返回 None
+fn unescape(escaped: &str) -> Option<String> {
+    let mut content = String::with_capacity(escaped.len());
+    let mut chars = escaped.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            content.push(c);
+            continue;
+        }
+        content.push(match chars.next()? {
+            '\\' => '\\',
+            't' => '\t',
+            'n' => '\n',
+            'r' => '\r',
+            _ => return None,
+        });
+    }
+    Some(content)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::graph::KnowledgeGraph;
+
+    use super::*;
+
+    fn create_graph() -> KnowledgeGraph {
+        let mut graph = KnowledgeGraph::default();
+        let a = graph
+            .add_entity(
+                "领域\t一".to_string(),
+                DistinctEntityType::KnowledgeArena,
+                &[],
+                (10.0, 20.0),
+            )
+            .unwrap();
+        let b = graph
+            .add_entity(
+                "知识点\n二".to_string(),
+                DistinctEntityType::KnowledgePoint,
+                &[AddonEntityType::Example, AddonEntityType::Thinking],
+                (30.0, 40.0),
+            )
+            .unwrap();
+        let c = graph
+            .add_entity(
+                "细节 -> 三".to_string(),
+                DistinctEntityType::KnowledgeDetail,
+                &[],
+                (50.0, 60.0),
+            )
+            .unwrap();
+        graph.add_edge(a, b, Relation::Contain).unwrap();
+        graph.add_edge(b, c, Relation::Order).unwrap();
+        graph
+    }
+
+    #[test]
+    fn test_to_adjacency_text() {
+        let graph = create_graph();
+        assert_eq!(
+            graph.current.to_adjacency_text(),
+            "1\tka\t-\t领域\\t一\n\
+             2\tkp\tte\t知识点\\n二\n\
+             3\tkd\t-\t细节 -> 三\n\
+             1 -> 2 : contain\n\
+             2 -> 3 : order\n"
+        );
+    }
+
+    #[test]
+    fn test_diff_friendly() {
+        let mut graph = create_graph();
+        let original = graph.current.to_adjacency_text();
+
+        // 只修改坐标时文本不变
+        graph.update_entity_position(2, (500.0, 500.0)).unwrap();
+        assert_eq!(graph.current.to_adjacency_text(), original);
+
+        // 修改内容时恰好一行不同
+        graph
+            .update_entity_with(3, |node| node.content = "修改".to_string())
+            .unwrap();
+        let modified = graph.current.to_adjacency_text();
+        let diff = original
+            .lines()
+            .zip(modified.lines())
+            .filter(|(a, b)| a != b)
+            .count();
+        assert_eq!(original.lines().count(), modified.lines().count());
+        assert_eq!(diff, 1);
+    }
+
+    #[test]
+    fn test_from_adjacency_text() -> Result<(), SerdeError> {
+        let graph = create_graph();
+        let text = graph.current.to_adjacency_text();
+        let snapshot = Snapshot::from_adjacency_text(&text)?;
+
+        // 除坐标外与原图谱一致
+        assert_eq!(snapshot.to_adjacency_text(), text);
+        assert_eq!(snapshot.nodes[&2].content, "知识点\n二");
+        assert_eq!(snapshot.latest_id, 4);
+        // 坐标由分层布局生成
+        assert_eq!(snapshot.nodes[&1].coor, (0.0, 0.0));
+        assert_eq!(snapshot.nodes[&3].coor, (0.0, 400.0));
+
+        // 严格解析
+        for invalid in [
+            "1\tkx\t-\tA\n",
+            "1\tka\tx\tA\n",
+            "1\tka\t-\tA\\x\n",
+            "1\tka\t-\tA\n1\tka\t-\tB\n",
+            "1\tka\t-\tA\n1 -> 2 : contain\n",
+            "1\tka\t-\tA\n2\tka\t-\tB\n1 -> 2 : include\n",
+            "1\tka\t-\tA\n2\tka\t-\tB\n1 -> 2 : order\n3\tka\t-\tC\n",
+        ] {
+            assert!(Snapshot::from_adjacency_text(invalid).is_err(), "{invalid}");
+        }
+
+        Ok(())
+    }
+}
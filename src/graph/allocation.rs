@@ -0,0 +1,255 @@
+//! ID 分配模块。默认按顺序分配节点 ID；按内容分配时由节点的内容和类型计算稳定的 ID，
+//! 使同一份源数据重复生成的图谱 ID 一致，不受中间的删除和撤回影响。
+//!
+//! 同一个图谱可以混用两种方式：按内容分配的 ID 遇到冲突时会换用下一个候选值，
+//! 而顺序分配的 ID 与已有节点冲突时返回 GraphError::DuplicateEntityId。
+
+use im::HashMap;
+
+use super::{DistinctEntityType, EntityNode, Snapshot};
+use crate::error::{GraphError, SerdeError};
+
+/// 节点 ID 的上限。超过 2^53 的整数在 JSON 和 JavaScript 中无法精确表示，读取文件时拒绝或忽略
+pub const MAX_ENTITY_ID: u64 = (1 << 53) - 1;
+
+/// 按内容分配的 ID 的范围，下限以下留给顺序分配，上限保证 ID 可用 32 位有符号整数表示
+const CONTENT_ID_MIN: u64 = 1 << 20;
+const CONTENT_ID_MAX: u64 = 1 << 31;
+
+/// 节点 ID 的分配方式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum IdAllocation {
+    #[default]
+    Sequential, // 按添加的顺序递增
+    Content, // 由内容和类型的哈希值计算
+}
+
+/// 按内容分配的第 attempt 个候选 ID。使用 FNV-1a 哈希，保证不同平台和版本的结果一致
+fn content_id(content: &str, distinct_type: DistinctEntityType, attempt: u64) -> u64 {
+    let bytes = distinct_type
+        .class_name()
+        .bytes()
+        .chain([0xff]) // 分隔类型和内容
+        .chain(content.bytes())
+        .chain(attempt.to_le_bytes());
+    let hash = bytes.fold(0xcbf29ce484222325u64, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+    });
+    CONTENT_ID_MIN + hash % (CONTENT_ID_MAX - CONTENT_ID_MIN)
+}
+
+/// 检查节点 ID 是否在允许的范围内
+pub(super) fn check_entity_id(id: u64) -> Result<(), SerdeError> {
+    if id > MAX_ENTITY_ID {
+        return Err(SerdeError::Unexpected("节点 ID", id.to_string()));
+    }
+    Ok(())
+}
+
+/// 现有节点之后的下一个可用 ID，即最大 ID 加一，没有节点时为 1。溢出时返回错误
+pub(super) fn next_id_after(nodes: &HashMap<u64, EntityNode>) -> Result<u64, SerdeError> {
+    let max_id = nodes.keys().max().copied().unwrap_or(0);
+    max_id
+        .checked_add(1)
+        .ok_or_else(|| SerdeError::Unexpected("节点 ID", max_id.to_string()))
+}
+
+impl Snapshot {
+    /// 将下一个按顺序分配的 ID 修正为最大 ID 加一，回收最大 ID 之后未使用的 ID，返回是否进行了修正。
+    /// 最大 ID 加一溢出时保持不变并返回 false，应先移除超出范围的节点
+    pub fn repair_latest_id(&mut self) -> bool {
+        match next_id_after(&self.nodes) {
+            Ok(latest_id) if latest_id != self.latest_id => {
+                self.latest_id = latest_id;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// 为内容和类型给定的新节点分配 ID。顺序分配时推进下一个可用的 ID，
+    /// 按内容分配时不影响顺序分配的计数
+    pub(super) fn allocate_id(
+        &mut self,
+        allocation: IdAllocation,
+        content: &str,
+        distinct_type: DistinctEntityType,
+    ) -> Result<u64, GraphError> {
+        match allocation {
+            IdAllocation::Sequential => {
+                let id = self.latest_id;
+                if self.nodes.contains_key(&id) {
+                    return Err(GraphError::DuplicateEntityId(id));
+                }
+                self.latest_id += 1;
+                Ok(id)
+            }
+            IdAllocation::Content => Ok((0..)
+                .map(|attempt| content_id(content, distinct_type, attempt))
+                .find(|id| !self.nodes.contains_key(id))
+                .expect("candidate ids are unbounded")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::{EntityNode, KnowledgeGraph, Relation};
+
+    /// 模拟生成图谱的流水线，extra 为 true 时中途添加又删除一个节点，并撤回一次修改
+    fn pipeline(
+        allocation: IdAllocation,
+        extra: bool,
+    ) -> Result<String, Box<dyn std::error::Error>> {
+        let mut graph = KnowledgeGraph::with_id_allocation(allocation);
+        let arena = graph.add_entity(
+            "Arena".to_string(),
+            DistinctEntityType::KnowledgeArena,
+            &[],
+            (0.0, 0.0),
+        )?;
+        if extra {
+            let temp = graph.add_entity(
+                "Temp".to_string(),
+                DistinctEntityType::KnowledgePoint,
+                &[],
+                (0.0, 0.0),
+            )?;
+            graph.remove_entity(temp)?;
+            graph.add_entity(
+                "Undone".to_string(),
+                DistinctEntityType::KnowledgePoint,
+                &[],
+                (0.0, 0.0),
+            )?;
+            graph.undo()?;
+        }
+        let points = graph.add_entities(["Point A", "Point B", "Point A"].map(|content| {
+            EntityNode::new(
+                0,
+                content.to_string(),
+                DistinctEntityType::KnowledgePoint,
+                &[],
+                (200.0, 0.0),
+            )
+        }))?;
+        for point in points {
+            graph.add_edge(arena, point, Relation::Contain)?;
+        }
+        Ok(graph.current.to_xml()?)
+    }
+
+    #[test]
+    fn test_content_allocation_reproducible() -> Result<(), Box<dyn std::error::Error>> {
+        // 相同的流水线生成完全相同的 XML，中间的删除和撤回不影响 ID
+        let xml = pipeline(IdAllocation::Content, false)?;
+        assert_eq!(xml, pipeline(IdAllocation::Content, false)?);
+        assert_eq!(xml, pipeline(IdAllocation::Content, true)?);
+        // 顺序分配时中间的操作会改变 ID
+        assert_ne!(
+            pipeline(IdAllocation::Sequential, false)?,
+            pipeline(IdAllocation::Sequential, true)?
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_repair_latest_id() {
+        let mut snapshot = Snapshot::default();
+        assert!(!snapshot.repair_latest_id());
+
+        for id in [1, 5, 9] {
+            let node = EntityNode::new(
+                id,
+                String::new(),
+                DistinctEntityType::KnowledgePoint,
+                &[],
+                (0.0, 0.0),
+            );
+            snapshot.nodes.insert(id, node);
+        }
+        assert!(snapshot.repair_latest_id());
+        assert_eq!(snapshot.next_id(), 10);
+
+        // 删除最大的节点后回收其后的 ID
+        snapshot.nodes.remove(&9);
+        assert!(snapshot.repair_latest_id());
+        assert_eq!(snapshot.next_id(), 6);
+
+        // 溢出时保持不变
+        let node = EntityNode::new(
+            u64::MAX,
+            String::new(),
+            DistinctEntityType::KnowledgePoint,
+            &[],
+            (0.0, 0.0),
+        );
+        snapshot.nodes.insert(u64::MAX, node);
+        assert!(next_id_after(&snapshot.nodes).is_err());
+        assert!(!snapshot.repair_latest_id());
+        assert!(check_entity_id(u64::MAX).is_err());
+        assert!(check_entity_id(MAX_ENTITY_ID).is_ok());
+    }
+
+    #[test]
+    fn test_content_allocation_collisions() -> Result<(), GraphError> {
+        let mut graph = KnowledgeGraph::with_id_allocation(IdAllocation::Content);
+        let a = graph.add_entity(
+            "Same".to_string(),
+            DistinctEntityType::KnowledgePoint,
+            &[],
+            (0.0, 0.0),
+        )?;
+        // 相同内容和类型的节点换用下一个候选值
+        let b = graph.add_entity(
+            "Same".to_string(),
+            DistinctEntityType::KnowledgePoint,
+            &[],
+            (0.0, 0.0),
+        )?;
+        assert_eq!(a, content_id("Same", DistinctEntityType::KnowledgePoint, 0));
+        assert_eq!(b, content_id("Same", DistinctEntityType::KnowledgePoint, 1));
+        // 类型不同时 ID 不同
+        let c = graph.add_entity(
+            "Same".to_string(),
+            DistinctEntityType::KnowledgeDetail,
+            &[],
+            (0.0, 0.0),
+        )?;
+        assert!(c != a && c != b);
+        assert!(
+            [a, b, c]
+                .iter()
+                .all(|id| (CONTENT_ID_MIN..CONTENT_ID_MAX).contains(id))
+        );
+
+        // 按内容分配不推进顺序分配的计数，混用时顺序分配的 ID 冲突会返回错误
+        graph.set_id_allocation(IdAllocation::Sequential);
+        let first = graph.add_entity(
+            "Sequential".to_string(),
+            DistinctEntityType::KnowledgePoint,
+            &[],
+            (0.0, 0.0),
+        )?;
+        assert_eq!(first, 1);
+        let mut snapshot = graph.current.clone();
+        let node = snapshot.nodes[&a].clone();
+        snapshot.nodes.insert(2, node);
+        let mut graph = KnowledgeGraph::from_snapshot(snapshot);
+        let undo_len = graph.undo_len();
+        assert_eq!(
+            graph.add_entity(
+                "Sequential".to_string(),
+                DistinctEntityType::KnowledgePoint,
+                &[],
+                (0.0, 0.0),
+            ),
+            Err(GraphError::DuplicateEntityId(2))
+        );
+        // 失败时不产生撤回记录
+        assert_eq!(graph.undo_len(), undo_len);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,89 @@
+//! 层级路径模块，沿包含关系向上追溯节点所属的各级祖先，用于显示面包屑。
+
+use super::Snapshot;
+
+/// 最多返回的路径数，多个父节点层层叠加时路径数可能成倍增长
+const MAX_ANCESTOR_PATHS: usize = 16;
+
+impl Snapshot {
+    /// 从没有父节点的根节点沿包含关系到指定节点的所有路径，每条路径以根节点开始、以节点自身结束。
+    /// 父节点按主父节点在前、其余按 ID 的顺序追溯，第一条路径即沿主父节点的路径；
+    /// 包含关系成环时在重复的节点处停止；最多返回 16 条，节点不存在时为空
+    pub fn ancestor_paths(&self, id: u64) -> Vec<Vec<u64>> {
+        if !self.nodes.contains_key(&id) {
+            return Vec::new();
+        }
+
+        let mut paths = Vec::new();
+        // 栈中的路径从节点自身开始向上
+        let mut stack = vec![vec![id]];
+        while let Some(path) = stack.pop() {
+            if paths.len() >= MAX_ANCESTOR_PATHS {
+                break;
+            }
+            let top = path[path.len() - 1];
+            let mut parents = self
+                .parents(top)
+                .into_iter()
+                .filter(|parent| !path.contains(parent))
+                .collect::<Vec<_>>();
+            if let Some(primary) = self.primary_parent(top)
+                && let Some(index) = parents.iter().position(|parent| *parent == primary)
+            {
+                parents[..=index].rotate_right(1);
+            }
+            if parents.is_empty() {
+                paths.push(path.into_iter().rev().collect());
+                continue;
+            }
+            for parent in parents.into_iter().rev() {
+                let mut extended = path.clone();
+                extended.push(parent);
+                stack.push(extended);
+            }
+        }
+        paths
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::graph::{DistinctEntityType, KnowledgeGraph, Relation};
+
+    #[test]
+    fn test_ancestor_paths() {
+        let mut graph = KnowledgeGraph::default();
+        for content in ["计算思维", "典型思维", "小白鼠问题", "另一单元", "孤立节点"]
+        {
+            graph
+                .add_entity(
+                    content.to_string(),
+                    DistinctEntityType::KnowledgePoint,
+                    &[],
+                    (0.0, 0.0),
+                )
+                .unwrap();
+        }
+        graph.add_edge(1, 2, Relation::Contain).unwrap();
+        graph.add_edge(2, 3, Relation::Contain).unwrap();
+        graph.add_edge(4, 3, Relation::Contain).unwrap();
+        graph.add_edge(5, 3, Relation::Order).unwrap(); // 次序关系不参与追溯
+
+        let snapshot = graph.current_snapshot();
+        assert_eq!(snapshot.ancestor_paths(3), vec![vec![1, 2, 3], vec![4, 3]]);
+        assert_eq!(snapshot.ancestor_paths(5), vec![vec![5]]);
+
+        // 主父节点的路径在前
+        graph.set_primary_parent(3, 4).unwrap();
+        let snapshot = graph.current_snapshot();
+        assert_eq!(snapshot.ancestor_paths(3), vec![vec![4, 3], vec![1, 2, 3]]);
+        graph.undo().unwrap();
+        let snapshot = graph.current_snapshot();
+        assert!(snapshot.ancestor_paths(9).is_empty());
+
+        // 包含关系成环时不会无限追溯
+        graph.add_edge(2, 1, Relation::Contain).unwrap();
+        let snapshot = graph.current_snapshot();
+        assert_eq!(snapshot.ancestor_paths(3), vec![vec![1, 2, 3], vec![4, 3]]);
+    }
+}
@@ -0,0 +1,270 @@
+//! 坐标范围模块，检查节点是否超出对接平台接受的坐标范围，并将图谱整体压缩到范围内；
+//! 也可找出因误操作跑到极远处的离群节点，单独收回到指定位置。
+
+use std::collections::HashMap;
+
+use super::{Command, CoordinateBounds, KnowledgeGraph, NODE_SIZE, Snapshot};
+use crate::error::GraphError;
+
+/// 判断离群节点的默认距离，节点任一方向上与其余节点中心的距离超过此值时视为离群
+pub const DEFAULT_OUTLIER_DISTANCE: f64 = 20000.0;
+
+/// 收回的离群节点排成一列时的纵向间隔
+const RECALL_GAP: f64 = 30.0;
+
+/// 数值的中位数，数量为偶数时取两个中间值的平均。values 为空时返回 0
+pub(super) fn median(mut values: Vec<f64>) -> f64 {
+    if values.is_empty() {
+        return 0.0;
+    }
+    values.sort_unstable_by(f64::total_cmp);
+    let mid = values.len() / 2;
+    if values.len().is_multiple_of(2) {
+        (values[mid - 1] + values[mid]) / 2.0
+    } else {
+        values[mid]
+    }
+}
+
+impl Snapshot {
+    /// 坐标超出元数据中坐标范围的节点，按 ID 排序
+    pub fn out_of_bounds_nodes(&self) -> Vec<u64> {
+        let bounds = self.metadata.coordinate_bounds();
+        let mut ids = self
+            .nodes
+            .values()
+            .filter(|node| !bounds.contains(node.coor))
+            .map(|node| node.id)
+            .collect::<Vec<_>>();
+        ids.sort_unstable();
+        ids
+    }
+
+    /// 将所有节点线性变换到 bounds 内时各节点的新位置，只包含位置改变的节点。
+    /// 两个方向按相同的比例缩小以保持长宽比和相对布局，图谱本身能放下时不缩放，只做最小的平移；
+    /// 所有节点都已在范围内时返回空表
+    pub fn fitted_positions(&self, bounds: CoordinateBounds) -> HashMap<u64, (f64, f64)> {
+        if self.nodes.values().all(|node| bounds.contains(node.coor)) {
+            return HashMap::new();
+        }
+
+        let (mut min_x, mut min_y) = (f64::INFINITY, f64::INFINITY);
+        let (mut max_x, mut max_y) = (f64::NEG_INFINITY, f64::NEG_INFINITY);
+        for node in self.nodes.values() {
+            min_x = min_x.min(node.coor.0);
+            min_y = min_y.min(node.coor.1);
+            max_x = max_x.max(node.coor.0);
+            max_y = max_y.max(node.coor.1);
+        }
+
+        // 所有节点在同一直线或同一点上时，该方向的跨度为 0，不限制缩放比例
+        let ratio = |available: f64, span: f64| {
+            if span > 0.0 {
+                available.max(0.0) / span
+            } else {
+                f64::INFINITY
+            }
+        };
+        let width = bounds.max.0 - bounds.min.0;
+        let height = bounds.max.1 - bounds.min.1;
+        let scale = ratio(width, max_x - min_x)
+            .min(ratio(height, max_y - min_y))
+            .min(1.0);
+
+        // 缩放后尽量保持原来的位置，超出时贴着范围的边缘
+        let origin =
+            |min: f64, span: f64, lower: f64, upper: f64| min.min(upper - span * scale).max(lower);
+        let origin_x = origin(min_x, max_x - min_x, bounds.min.0, bounds.max.0);
+        let origin_y = origin(min_y, max_y - min_y, bounds.min.1, bounds.max.1);
+
+        self.nodes
+            .values()
+            .filter_map(|node| {
+                let coor = (
+                    origin_x + (node.coor.0 - min_x) * scale,
+                    origin_y + (node.coor.1 - min_y) * scale,
+                );
+                (coor != node.coor).then_some((node.id, coor))
+            })
+            .collect()
+    }
+
+    /// 离群节点，按 ID 排序。以所有节点坐标的中位数为中心，少数离群节点不影响中心的位置；
+    /// 节点在横向或纵向上与中心的距离超过 distance，或坐标不是有限数时视为离群
+    pub fn outlier_nodes(&self, distance: f64) -> Vec<u64> {
+        let (xs, ys) = self
+            .nodes
+            .values()
+            .filter(|node| node.coor.0.is_finite() && node.coor.1.is_finite())
+            .map(|node| node.coor)
+            .unzip();
+        let (center_x, center_y) = (median(xs), median(ys));
+        let mut ids = self
+            .nodes
+            .values()
+            .filter(|node| {
+                !((node.coor.0 - center_x).abs() <= distance
+                    && (node.coor.1 - center_y).abs() <= distance)
+            })
+            .map(|node| node.id)
+            .collect::<Vec<_>>();
+        ids.sort_unstable();
+        ids
+    }
+
+    /// 将离群节点按 ID 从 origin 起向下排成一列所需的命令，没有离群节点时为空
+    pub fn recall_outliers_commands(&self, distance: f64, origin: (f64, f64)) -> Vec<Command> {
+        self.outlier_nodes(distance)
+            .into_iter()
+            .enumerate()
+            .map(|(i, id)| Command::UpdateEntityPosition {
+                id,
+                coor: (origin.0, origin.1 + i as f64 * (NODE_SIZE.1 + RECALL_GAP)),
+            })
+            .collect()
+    }
+}
+
+impl KnowledgeGraph {
+    /// 将离群节点从 origin 起向下排成一列，整体作为一次可撤回的操作，返回收回的节点
+    pub fn recall_outliers(
+        &mut self,
+        distance: f64,
+        origin: (f64, f64),
+    ) -> Result<Vec<u64>, GraphError> {
+        let ids = self.current.outlier_nodes(distance);
+        if !ids.is_empty() {
+            self.apply_batch(self.current.recall_outliers_commands(distance, origin))?;
+        }
+        Ok(ids)
+    }
+
+    /// 将所有节点压缩到 bounds 内，整体作为一次可撤回的操作，返回移动的节点数。
+    /// 所有节点都已在范围内时不做修改，也不产生撤回记录
+    pub fn fit_to_bounds(&mut self, bounds: CoordinateBounds) -> Result<usize, GraphError> {
+        let moves = self.current.fitted_positions(bounds);
+        let count = moves.len();
+        if count > 0 {
+            self.apply_batch(
+                moves
+                    .into_iter()
+                    .map(|(id, coor)| Command::UpdateEntityPosition { id, coor }),
+            )?;
+        }
+        Ok(count)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::graph::{DEFAULT_COORDINATE_BOUNDS, DistinctEntityType};
+
+    use super::*;
+
+    fn graph_with(coors: &[(f64, f64)]) -> Result<KnowledgeGraph, GraphError> {
+        let mut graph = KnowledgeGraph::default();
+        for (i, coor) in coors.iter().enumerate() {
+            graph.add_entity(
+                format!("Node {i}"),
+                DistinctEntityType::KnowledgePoint,
+                &[],
+                *coor,
+            )?;
+        }
+        Ok(graph)
+    }
+
+    fn coors(graph: &KnowledgeGraph) -> Vec<(f64, f64)> {
+        let mut nodes = graph.current_snapshot().nodes.values().collect::<Vec<_>>();
+        nodes.sort_unstable_by_key(|node| node.id);
+        nodes.into_iter().map(|node| node.coor).collect()
+    }
+
+    #[test]
+    fn test_out_of_bounds_nodes() -> Result<(), GraphError> {
+        let graph = graph_with(&[(0.0, 0.0), (4000.0, 4000.0), (12800.0, 100.0), (-1.0, 0.0)])?;
+        assert_eq!(graph.current_snapshot().out_of_bounds_nodes(), vec![3, 4]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_fit_to_bounds() -> Result<(), GraphError> {
+        let bounds = DEFAULT_COORDINATE_BOUNDS;
+
+        // 已在范围内时不修改，也不产生撤回记录
+        let mut graph = graph_with(&[(100.0, 100.0), (3900.0, 200.0)])?;
+        let undo_len = graph.undo_len();
+        assert_eq!(graph.fit_to_bounds(bounds)?, 0);
+        assert_eq!(coors(&graph), vec![(100.0, 100.0), (3900.0, 200.0)]);
+        assert_eq!(graph.undo_len(), undo_len);
+
+        // 横向跨度为范围的两倍，按 1/2 等比缩小，并贴着左边缘
+        let mut graph = graph_with(&[(0.0, 0.0), (8000.0, 400.0), (4000.0, 200.0)])?;
+        let undo_len = graph.undo_len();
+        assert_eq!(graph.fit_to_bounds(bounds)?, 2);
+        assert_eq!(
+            coors(&graph),
+            vec![(0.0, 0.0), (4000.0, 200.0), (2000.0, 100.0)]
+        );
+        assert!(graph.current_snapshot().out_of_bounds_nodes().is_empty());
+        // 整体作为一次撤回
+        assert_eq!(graph.undo_len(), undo_len + 1);
+        graph.undo()?;
+        assert_eq!(coors(&graph)[1], (8000.0, 400.0));
+
+        // 跨度能放下时只平移
+        let mut graph = graph_with(&[(-500.0, 100.0), (500.0, 4100.0)])?;
+        graph.fit_to_bounds(bounds)?;
+        assert_eq!(coors(&graph), vec![(0.0, 0.0), (1000.0, 4000.0)]);
+
+        // 所有节点在同一点上时移到范围内，仍在同一点上
+        let mut graph = graph_with(&[(12800.0, -50.0), (12800.0, -50.0)])?;
+        graph.fit_to_bounds(bounds)?;
+        assert_eq!(coors(&graph), vec![(4000.0, 0.0), (4000.0, 0.0)]);
+
+        // 所有节点在同一竖线上时只按纵向缩放
+        let mut graph = graph_with(&[(5000.0, 0.0), (5000.0, 8000.0)])?;
+        graph.fit_to_bounds(bounds)?;
+        assert_eq!(coors(&graph), vec![(4000.0, 0.0), (4000.0, 4000.0)]);
+
+        // 空图谱不做修改
+        let mut graph = KnowledgeGraph::default();
+        assert_eq!(graph.fit_to_bounds(bounds)?, 0);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_recall_outliers() -> Result<(), GraphError> {
+        let mut graph = graph_with(&[
+            (0.0, 0.0),
+            (90000.0, 100.0),
+            (1000.0, 500.0),
+            (2000.0, -60000.0),
+            (500.0, 800.0),
+        ])?;
+        let distance = DEFAULT_OUTLIER_DISTANCE;
+        assert_eq!(graph.current.outlier_nodes(distance), vec![2, 4]);
+        assert!(graph.current.outlier_nodes(f64::INFINITY).is_empty());
+
+        // 按 ID 排成一列，整体作为一次撤回
+        let undo_len = graph.undo_len();
+        assert_eq!(graph.recall_outliers(distance, (100.0, 200.0))?, vec![2, 4]);
+        assert_eq!(graph.undo_len(), undo_len + 1);
+        let coors = coors(&graph);
+        assert_eq!(coors[1], (100.0, 200.0));
+        assert_eq!(coors[3], (100.0, 200.0 + NODE_SIZE.1 + RECALL_GAP));
+        assert_eq!(coors[2], (1000.0, 500.0));
+
+        // 没有离群节点时不做修改
+        assert!(graph.recall_outliers(distance, (0.0, 0.0))?.is_empty());
+        assert_eq!(graph.undo_len(), undo_len + 1);
+        assert!(
+            KnowledgeGraph::default()
+                .recall_outliers(distance, (0.0, 0.0))?
+                .is_empty()
+        );
+
+        Ok(())
+    }
+}
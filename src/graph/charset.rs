@@ -0,0 +1,160 @@
+//! 字符集检查模块。对接平台无法显示 emoji 等基本多文种平面以外的字符和控制字符，
+//! 导入后会显示为乱码。图谱信息中开启检查后，校验时列出含有这些字符的节点，并可一键移除。
+
+use std::collections::BTreeSet;
+
+use super::{Command, IssueLevel, KnowledgeGraph, Snapshot, ValidationIssue};
+use crate::error::GraphError;
+
+/// 问题说明中最多列出的字符数
+const SHOWN_CHARS: usize = 5;
+
+/// 对接平台是否支持该字符。不支持基本多文种平面以外的字符（包括大部分 emoji）、
+/// 除换行和制表符以外的控制字符、私用区字符，以及组成 emoji 序列的零宽连接符和变体选择符
+///
+/// ```
+/// use py_better_kt_sqep::graph::is_supported_char;
+///
+/// assert!(is_supported_char('图'));
+/// assert!(is_supported_char('\n'));
+/// assert!(!is_supported_char('🦀'));
+/// assert!(!is_supported_char('\u{7}'));
+/// ```
+pub fn is_supported_char(c: char) -> bool {
+    match c {
+        '\n' | '\t' => true,
+        '\u{200d}' | '\u{fe00}'..='\u{fe0f}' | '\u{e000}'..='\u{f8ff}' => false,
+        '\u{fffe}' | '\u{ffff}' => false,
+        _ => !c.is_control() && (c as u32) <= 0xffff,
+    }
+}
+
+/// 移除内容中对接平台不支持的字符
+pub fn strip_unsupported(content: &str) -> String {
+    content.chars().filter(|c| is_supported_char(*c)).collect()
+}
+
+/// 在问题说明中显示的字符，不可见的字符显示为码位
+fn display_char(c: char) -> String {
+    if c.is_control() || matches!(c, '\u{200d}' | '\u{fe00}'..='\u{fe0f}') {
+        format!("U+{:04X}", c as u32)
+    } else {
+        c.to_string()
+    }
+}
+
+impl Snapshot {
+    /// 节点内容含有对接平台不支持的字符时的警告。图谱信息中未开启检查或没有这样的节点时返回 None
+    pub fn charset_issue(&self) -> Option<ValidationIssue> {
+        if !self.metadata.restrict_charset {
+            return None;
+        }
+        let mut entities = Vec::new();
+        let mut chars = BTreeSet::new();
+        for node in self.nodes.values() {
+            let unsupported = node
+                .content
+                .chars()
+                .filter(|c| !is_supported_char(*c))
+                .collect::<Vec<_>>();
+            if !unsupported.is_empty() {
+                entities.push(node.id);
+                chars.extend(unsupported);
+            }
+        }
+        if entities.is_empty() {
+            return None;
+        }
+
+        entities.sort_unstable();
+        let mut shown = chars
+            .iter()
+            .take(SHOWN_CHARS)
+            .map(|c| display_char(*c))
+            .collect::<Vec<_>>();
+        if chars.len() > SHOWN_CHARS {
+            shown.push("…".to_string());
+        }
+        Some(ValidationIssue::new(
+            IssueLevel::Warning,
+            format!(
+                "有 {} 个节点的内容含有对接平台不支持的字符（{}），导入后会显示异常",
+                entities.len(),
+                shown.join(" ")
+            ),
+            entities,
+        ))
+    }
+
+    /// 移除所有节点内容中不支持的字符所需的命令，内容不变的节点不生成命令
+    pub fn strip_unsupported_commands(&self) -> Vec<Command> {
+        let mut nodes = self.nodes.values().collect::<Vec<_>>();
+        nodes.sort_unstable_by_key(|node| node.id);
+        nodes
+            .into_iter()
+            .filter_map(|node| {
+                let content = strip_unsupported(&node.content);
+                (content != node.content).then(|| Command::UpdateEntityContent {
+                    id: node.id,
+                    content,
+                    distinct_type: node.distinct_type,
+                    addon_types: node.addon_types.iter().copied().collect(),
+                })
+            })
+            .collect()
+    }
+}
+
+impl KnowledgeGraph {
+    /// 移除所有节点内容中对接平台不支持的字符，整体作为一次可撤回的操作，返回修改的节点数
+    pub fn strip_unsupported_chars(&mut self) -> Result<usize, GraphError> {
+        let commands = self.current.strip_unsupported_commands();
+        let count = commands.len();
+        if count > 0 {
+            self.apply_batch(commands)?;
+        }
+        Ok(count)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::{DistinctEntityType, GraphMetadata};
+
+    #[test]
+    fn test_charset_issue() {
+        let mut graph = KnowledgeGraph::default();
+        for content in ["Rust 🦀 入门", "正常内容\n第二行", "笑脸☺\u{fe0f}\u{7}"] {
+            graph
+                .add_entity(
+                    content.to_string(),
+                    DistinctEntityType::KnowledgePoint,
+                    &[],
+                    (0.0, 0.0),
+                )
+                .unwrap();
+        }
+
+        // 未开启检查时不报告
+        assert_eq!(graph.current.charset_issue(), None);
+        graph.set_metadata(GraphMetadata {
+            restrict_charset: true,
+            ..Default::default()
+        });
+        let issue = graph.current.charset_issue().unwrap();
+        assert_eq!(issue.entities, vec![1, 3]);
+        assert!(issue.message.contains("🦀"));
+        assert!(issue.message.contains("U+FE0F"));
+        assert!(graph.current.validate().contains(&issue));
+
+        let undo_len = graph.undo_len();
+        assert_eq!(graph.strip_unsupported_chars(), Ok(2));
+        assert_eq!(graph.current.nodes[&1].content, "Rust  入门");
+        assert_eq!(graph.current.nodes[&2].content, "正常内容\n第二行");
+        assert_eq!(graph.current.nodes[&3].content, "笑脸☺");
+        assert_eq!(graph.undo_len(), undo_len + 1);
+        assert_eq!(graph.current.charset_issue(), None);
+        assert_eq!(graph.strip_unsupported_chars(), Ok(0));
+    }
+}
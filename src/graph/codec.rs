@@ -1,16 +1,181 @@
 //! 知识图谱编解码 XML 格式的定义与实现
 
-use std::{collections::HashSet, io::Cursor};
+use std::{
+    collections::{BTreeMap, BTreeSet, HashSet},
+    fs,
+    io::Cursor,
+    ops::Range,
+    path::Path,
+};
 
 use im::HashMap;
-use quick_xml::{Reader, Writer, events::Event};
+use quick_xml::{
+    Reader, Writer,
+    escape::escape,
+    events::{BytesText, Event},
+};
 use serde::{Deserialize, Serialize};
 
-use crate::error::SerdeError;
+use crate::error::{Error, SerdeError};
+
+use super::{
+    AddonEntityType, Bookmark, CoordinateBounds, DEFAULT_GROUP_COLOR, DistinctEntityType,
+    EntityNode, Flag, GraphMetadata, GroupFrame, IssueLevel, MAX_ENTITY_ID, Relation, Snapshot,
+    ValidationIssue,
+    allocation::{check_entity_id, next_id_after},
+    relation_registry,
+};
+
+/// 对接平台以 32 位有符号整数保存节点 ID
+const MAX_PLATFORM_ID: u64 = i32::MAX as u64;
+
+/// 保存时写入的图谱标题
+pub const DEFAULT_TITLE: &str = "教学知识图谱";
+
+/// XML 输出选项，默认值与对接平台导出的格式一致，并添加 XML 声明以兼容要求声明的严格解析器。
+/// 声明的编码总为 UTF-8：输出的字符串以 UTF-8 写入文件，转义后只含 ASCII 时同样是合法的 UTF-8
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct XmlOptions {
+    pub indent: bool,           // 是否缩进
+    pub escape_non_ascii: bool, // 是否将非 ASCII 字符转义为数字实体
+    pub declaration: bool,      // 是否添加 XML 声明
+    pub crlf: bool,             // 是否使用 Windows 换行符
+}
+
+impl Default for XmlOptions {
+    fn default() -> Self {
+        Self {
+            indent: true,
+            escape_non_ascii: true,
+            declaration: true,
+            crlf: false,
+        }
+    }
+}
+
+/// 将文件内容解码为字符串，支持带 BOM 的 UTF-8 和 UTF-16（如记事本保存的文件）
+pub fn decode_xml(bytes: &[u8]) -> Result<String, SerdeError> {
+    let decode_utf16 = |rest: &[u8], from_bytes: fn([u8; 2]) -> u16| {
+        let units = rest
+            .chunks_exact(2)
+            .map(|pair| from_bytes([pair[0], pair[1]]))
+            .collect::<Vec<_>>();
+        String::from_utf16(&units).map_err(|e| SerdeError::Unexpected("UTF-16 内容", e.to_string()))
+    };
+
+    match bytes {
+        [0xEF, 0xBB, 0xBF, rest @ ..] => Ok(std::str::from_utf8(rest)?.to_string()),
+        [0xFF, 0xFE, rest @ ..] => decode_utf16(rest, u16::from_le_bytes),
+        [0xFE, 0xFF, rest @ ..] => decode_utf16(rest, u16::from_be_bytes),
+        _ => Ok(std::str::from_utf8(bytes)?.to_string()),
+    }
+}
+
+/// 按 XML 规范将 CRLF 和单独的 CR 换行统一为 LF，文本中的 `&#13;` 不受影响
+fn normalize_line_endings(xml: &str) -> std::borrow::Cow<'_, str> {
+    if xml.contains('\r') {
+        xml.replace("\r\n", "\n").replace('\r', "\n").into()
+    } else {
+        xml.into()
+    }
+}
+
+/// 查找 XML 中最外层的 KG 元素（不在其他 KG 元素内部）的位置，可位于任意包装元素中。
+/// 遇到格式错误时停止查找，只返回此前完整的 KG 元素，由反序列化报告错误
+fn kg_documents(xml: &str) -> Vec<Range<usize>> {
+    let mut reader = Reader::from_str(xml);
+    let mut documents = Vec::new();
+    let mut start = None;
+    let mut depth = 0; // 在 KG 元素内的嵌套深度
+    loop {
+        let position = reader.buffer_position() as usize;
+        match reader.read_event() {
+            Ok(Event::Start(e)) if start.is_none() && e.name().as_ref() == b"KG" => {
+                start = Some(position);
+            }
+            Ok(Event::Empty(e)) if start.is_none() && e.name().as_ref() == b"KG" => {
+                documents.push(position..reader.buffer_position() as usize);
+            }
+            Ok(Event::Start(_)) if start.is_some() => depth += 1,
+            Ok(Event::End(_)) if depth > 0 => depth -= 1,
+            Ok(Event::End(_)) => {
+                if let Some(start) = start.take() {
+                    documents.push(start..reader.buffer_position() as usize);
+                }
+            }
+            Ok(Event::Eof) | Err(_) => break,
+            Ok(_) => {}
+        }
+    }
+    documents
+}
+
+/// 读取 XML 中第一个 KG 元素的标题和节点数，不构建快照，读完节点列表即停止。
+/// 与宽容模式一样，KG 元素可位于任意包装元素中；只检查 XML 格式，不检查节点的内容
+///
+/// ```
+/// use py_better_kt_sqep::graph::peek_xml_title;
+///
+/// let xml = "<KG>数据结构第3章<entities><entity><id>1</id></entity></entities></KG>";
+/// assert_eq!(peek_xml_title(xml).unwrap(), ("数据结构第3章".to_string(), 1));
+/// ```
+pub fn peek_xml_title(xml: &str) -> Result<(String, usize), SerdeError> {
+    let mut reader = Reader::from_str(xml);
+    let mut title = String::new();
+    let mut count = 0;
+    let mut depth = 0; // 在 KG 元素内的嵌套深度，0 表示尚未进入
+    let mut in_entities = false;
+    loop {
+        match reader.read_event()? {
+            Event::Start(e) if depth == 0 && e.name().as_ref() == b"KG" => depth = 1,
+            Event::Empty(e) if depth == 0 && e.name().as_ref() == b"KG" => {
+                return Ok((String::new(), 0));
+            }
+            Event::Eof if depth == 0 => {
+                return Err(SerdeError::Unexpected(
+                    "图谱",
+                    "没有找到 KG 元素".to_string(),
+                ));
+            }
+            // 跳过 KG 之外的包装元素
+            _ if depth == 0 => {}
+            Event::Start(e) => {
+                if depth == 1 && e.name().as_ref() == b"entities" {
+                    in_entities = true;
+                } else if depth == 2 && in_entities && e.name().as_ref() == b"entity" {
+                    count += 1;
+                }
+                depth += 1;
+            }
+            Event::Empty(e) if depth == 2 && in_entities && e.name().as_ref() == b"entity" => {
+                count += 1;
+            }
+            Event::Text(e) if depth == 1 => title.push_str(&e.unescape()?),
+            Event::CData(e) if depth == 1 => {
+                title.push_str(&String::from_utf8_lossy(&e.into_inner()));
+            }
+            // 节点列表或 KG 元素结束时已得到所需的信息
+            Event::End(_) if depth == 2 && in_entities => break,
+            Event::End(_) if depth == 1 => break,
+            Event::End(_) => depth -= 1,
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+    Ok((title.trim().to_string(), count))
+}
 
-use super::{AddonEntityType, DistinctEntityType, EntityNode, Relation, Snapshot};
+/// 读取图谱文件的标题和节点数，用于在文件列表中区分文件，比完整读取快得多
+pub fn peek_title<P>(path: P) -> Result<(String, usize), Error>
+where
+    P: AsRef<Path>,
+{
+    let xml = decode_xml(&fs::read(path)?)?;
+    Ok(peek_xml_title(&xml)?)
+}
 
-/// 转义非 ASCII 字符
+/// 将非 ASCII 字符转义为数字实体。只能作用于已序列化的 XML：其中的 `&` 均已转义为 `&amp;`，
+/// 新增的数字实体读取时只还原一层，内容中原有的 `&#20013;` 等写法不会被误当作实体
 fn escape_non_ascii(input: &str) -> String {
     input
         .chars()
@@ -30,18 +195,37 @@ fn escape_non_ascii(input: &str) -> String {
 struct SerializableEntity {
     id: u64,
     class_name: String,
+    // classification、identity、level 和 opentool 可由实体类型推出，缺失时不影响读取
+    #[serde(default)]
     classification: String,
+    #[serde(default)]
     identity: String,
+    #[serde(default)]
     level: String,
     #[serde(
+        default,
         serialize_with = "serialize_addon_types",
         deserialize_with = "deserialize_addon_types"
     )]
     attach: HashSet<AddonEntityType>,
+    #[serde(default)]
     opentool: String,
+    #[serde(default)]
     content: String,
     x: f64,
     y: f64,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    notes: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    order_index: Option<u32>,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    reference: bool,
+    #[serde(rename = "link", default, skip_serializing_if = "Vec::is_empty")]
+    links: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    flag: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    primary_parent: Option<u64>,
 }
 
 impl Default for SerializableEntity {
@@ -57,6 +241,12 @@ impl Default for SerializableEntity {
             content: String::new(),
             x: 0.0,
             y: 0.0,
+            notes: String::new(),
+            order_index: None,
+            reference: false,
+            links: Vec::new(),
+            flag: None,
+            primary_parent: None,
         }
     }
 }
@@ -74,6 +264,12 @@ impl From<&EntityNode> for SerializableEntity {
             content: node.content.to_string(),
             x: coor.0,
             y: coor.1,
+            notes: node.notes.clone(),
+            order_index: node.order_index,
+            reference: node.reference,
+            links: node.links.clone(),
+            flag: node.flag.map(|flag| flag.name().to_string()),
+            primary_parent: node.primary_parent,
             ..Default::default()
         }
     }
@@ -83,30 +279,97 @@ impl TryFrom<SerializableEntity> for EntityNode {
     type Error = SerdeError;
     fn try_from(value: SerializableEntity) -> Result<Self, Self::Error> {
         // 根据 class_name 确定实体类型
-        let distinct_type = match value.class_name.as_str() {
-            "知识领域" => DistinctEntityType::KnowledgeArena,
-            "知识单元" => DistinctEntityType::KnowledgeUnit,
-            "知识点" => DistinctEntityType::KnowledgePoint,
-            "关键知识细节" => DistinctEntityType::KnowledgeDetail,
-            value_name => {
-                return Err(SerdeError::Unexpected("实体类型", value_name.to_string()));
-            }
-        };
+        let distinct_type = DistinctEntityType::from_class_name(&value.class_name)
+            .ok_or_else(|| SerdeError::Unexpected("实体类型", value.class_name.clone()))?;
 
-        Ok(Self::new(
+        let mut node = Self::new(
             value.id,
             value.content,
             distinct_type,
             &value.attach.iter().copied().collect::<Vec<_>>(),
             (value.x, value.y),
+        );
+        node.notes = value.notes;
+        node.order_index = value.order_index;
+        node.reference = value.reference;
+        node.links = value.links;
+        node.flag = value.flag.as_deref().and_then(Flag::from_name);
+        node.primary_parent = value.primary_parent;
+
+        Ok(node)
+    }
+}
+
+impl SerializableEntity {
+    /// 去掉无法识别的评审标记并返回说明。评审标记只用于编辑过程，其它版本写入的标记不影响读取图谱
+    fn drop_unknown_flag(&mut self) -> Option<String> {
+        let flag = self.flag.take_if(|flag| Flag::from_name(flag).is_none())?;
+        Some(format!(
+            "节点 {} 的评审标记“{flag}”无法识别，已忽略",
+            self.id
         ))
     }
+
+    /// 检查 level 与 class_name 是否一致，不一致时按策略处理并返回说明。
+    /// level 缺失时视为一致；class_name 无法识别时由之后的转换报错。
+    fn check_level(&mut self, policy: LevelPolicy) -> Result<Option<String>, SerdeError> {
+        let level = self.level.trim();
+        let by_class_name = DistinctEntityType::from_class_name(&self.class_name);
+        let by_level = DistinctEntityType::from_level(level);
+        if level.is_empty() || by_class_name.is_none() || by_class_name == by_level {
+            return Ok(None);
+        }
+
+        let message = format!(
+            "节点 {} 的类型“{}”与级别“{}”不一致",
+            self.id, self.class_name, level
+        );
+        match (policy, by_level) {
+            (LevelPolicy::Reject, _) => Err(SerdeError::Unexpected("实体级别", message)),
+            (LevelPolicy::Level, Some(distinct_type)) => {
+                self.class_name = distinct_type.class_name().to_string();
+                Ok(Some(format!("{message}，已按级别读取")))
+            }
+            _ => Ok(Some(format!("{message}，已按类型读取"))),
+        }
+    }
+}
+
+/// class_name 与 level 矛盾时的处理策略
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum LevelPolicy {
+    #[default]
+    ClassName, // 以 class_name 为准
+    Level,  // 以 level 为准，level 无法识别时仍以 class_name 为准
+    Reject, // 返回错误
 }
 
 /// 实体的 class_name, classification, identity, level, opentool 和实体类型是一一对应的
 impl DistinctEntityType {
+    /// 由 class_name 获取实体类型
+    fn from_class_name(class_name: &str) -> Option<Self> {
+        match class_name {
+            "知识领域" => Some(DistinctEntityType::KnowledgeArena),
+            "知识单元" => Some(DistinctEntityType::KnowledgeUnit),
+            "知识点" => Some(DistinctEntityType::KnowledgePoint),
+            "关键知识细节" => Some(DistinctEntityType::KnowledgeDetail),
+            _ => None,
+        }
+    }
+
+    /// 由 level 获取实体类型
+    fn from_level(level: &str) -> Option<Self> {
+        match level {
+            "一级" => Some(DistinctEntityType::KnowledgeArena),
+            "二级" => Some(DistinctEntityType::KnowledgeUnit),
+            "归纳级" => Some(DistinctEntityType::KnowledgePoint),
+            "内容级" => Some(DistinctEntityType::KnowledgeDetail),
+            _ => None,
+        }
+    }
+
     /// 获取实体类型 class_name
-    fn class_name(&self) -> &'static str {
+    pub(crate) fn class_name(&self) -> &'static str {
         match *self {
             DistinctEntityType::KnowledgeArena => "知识领域",
             DistinctEntityType::KnowledgeUnit => "知识单元",
@@ -127,7 +390,7 @@ impl DistinctEntityType {
 }
 
 /// 附加实体类型，顺序是固定的，即 T Z Q K E P
-const ADDON_TYPES: [AddonEntityType; 6] = [
+pub(crate) const ADDON_TYPES: [AddonEntityType; 6] = [
     AddonEntityType::Thinking,
     AddonEntityType::Political,
     AddonEntityType::Question,
@@ -144,6 +407,11 @@ fn serialize_addon_types<S>(
 where
     S: serde::Serializer,
 {
+    serializer.serialize_str(&addon_flags(addon_types))
+}
+
+/// 将附加实体类型编码为由 0 和 1 组成的字符串
+pub(crate) fn addon_flags(addon_types: &HashSet<AddonEntityType>) -> String {
     let mut result = String::with_capacity(6);
 
     // 根据 addon 是否在 addon_types 中决定是否添加对应的字符
@@ -155,7 +423,7 @@ where
         });
     }
 
-    serializer.serialize_str(&result)
+    result
 }
 
 fn deserialize_addon_types<'de, D>(deserializer: D) -> Result<HashSet<AddonEntityType>, D::Error>
@@ -179,14 +447,23 @@ where
 #[derive(Debug, Serialize, Deserialize)]
 #[serde(rename = "relation")]
 struct SerializableEdge {
+    // name 为关系名或边的自定义标签，其余除起点、终点和 class_name 外的字段均为固定值，
+    // 缺失时不影响读取
+    #[serde(default)]
     name: String,
     headnodeid: u64,
     tailnodeid: u64,
     class_name: String,
+    #[serde(default)]
     mask: String,
+    #[serde(default)]
     classification: String,
+    #[serde(default)]
     head_need: String,
+    #[serde(default)]
     tail_need: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    waypoints: String, // 扩展的折点，以空格分隔的 `x,y`
 }
 
 impl Default for SerializableEdge {
@@ -200,51 +477,74 @@ impl Default for SerializableEdge {
             classification: String::new(),
             head_need: "内容方法型节点".to_string(),
             tail_need: "内容方法型节点".to_string(),
+            waypoints: String::new(),
         }
     }
 }
 
 impl SerializableEdge {
-    /// 从边创建可序列化的边
-    pub fn from_edge(from: u64, to: u64, relation: Relation) -> Self {
+    /// 从边创建可序列化的边，name 为边的自定义标签，没有标签时为关系名
+    pub fn from_edge(from: u64, to: u64, relation: Relation, label: Option<&str>) -> Self {
         Self {
+            name: label.map_or_else(|| relation.name().into_owned(), str::to_string),
             headnodeid: from,
             tailnodeid: to,
-            class_name: relation.class_name().to_string(),
-            classification: relation.classification().to_string(),
+            class_name: relation.class_name().into_owned(),
+            classification: relation.class_name().into_owned(),
             ..Default::default()
         }
     }
 
     /// 将可序列化的边转换为边
     pub fn to_edge(&self) -> Result<(u64, u64, Relation), SerdeError> {
-        let relation = match self.class_name.as_str() {
-            "包含关系" => Relation::Contain,
-            "次序关系" | "次序：次序关系" => Relation::Order,
-            _ => {
-                return Err(SerdeError::Unexpected("关系名", self.class_name.clone()));
-            }
+        // 旧版平台导出的次序关系带有前缀
+        let class_name = match self.class_name.as_str() {
+            "次序：次序关系" => "次序关系",
+            class_name => class_name,
         };
+        let relation = relation_registry()
+            .find_class_name(class_name)
+            .ok_or_else(|| SerdeError::Unexpected("关系名", self.class_name.clone()))?;
 
         Ok((self.headnodeid, self.tailnodeid, relation))
     }
-}
 
-impl Relation {
-    /// 获取关系 class_name
-    fn class_name(&self) -> &'static str {
-        match *self {
-            Relation::Contain => "包含关系",
-            Relation::Order => "次序关系",
-        }
+    /// 边的自定义标签。name 为空或为关系类型表中任一关系名时没有标签，
+    /// 旧版本对所有边都写入“包含”，这样读取时不会误当作标签
+    pub fn label(&self) -> Option<String> {
+        let name = self.name.trim();
+        (!name.is_empty() && !relation_registry().is_name(name)).then(|| name.to_string())
     }
 
-    /// 获取关系 classification
-    fn classification(&self) -> &'static str {
-        match *self {
-            Relation::Contain => "包含关系",
-            Relation::Order => "次序关系",
-        }
+    /// 设置折点，写为以空格分隔的 `x,y`
+    pub fn set_waypoints(&mut self, waypoints: &[(f64, f64)]) {
+        self.waypoints = waypoints
+            .iter()
+            .map(|(x, y)| format!("{x},{y}"))
+            .collect::<Vec<_>>()
+            .join(" ");
+    }
+
+    /// 边的折点，坐标无法识别或不是有限数时返回错误
+    pub fn waypoints(&self) -> Result<Vec<(f64, f64)>, SerdeError> {
+        self.waypoints
+            .split_whitespace()
+            .map(|point| {
+                point
+                    .split_once(',')
+                    .and_then(|(x, y)| Some((x.parse::<f64>().ok()?, y.parse::<f64>().ok()?)))
+                    .filter(|(x, y)| x.is_finite() && y.is_finite())
+                    .ok_or_else(|| SerdeError::Unexpected("折点", self.waypoints.clone()))
+            })
+            .collect()
+    }
+}
+
+/// 去掉首尾空白的标题，为空时使用默认标题，对接平台不接受空标题
+fn title_or_default(title: &str) -> String {
+    match title.trim() {
+        "" => DEFAULT_TITLE.to_string(),
+        title => title.to_string(),
     }
 }
 
@@ -252,21 +552,206 @@ impl Relation {
 #[derive(Debug, Serialize, Deserialize)]
 #[serde(rename = "KG")]
 pub struct SerializableSnapshot {
-    #[serde(rename = "$value")]
+    #[serde(rename = "$value", default)]
     title: String,
+    #[serde(default)]
     entities: Entities,
+    #[serde(default)]
     relations: Relations,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    metadata: Option<SerializableMetadata>,
 }
 
-/// 实体包装器
+/// 可序列化的图谱元数据，作为扩展元素保存
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct SerializableMetadata {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    max_nodes: Option<usize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    max_edges: Option<usize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    max_content_chars: Option<usize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    coordinate_bounds: Option<SerializableBounds>,
+    #[serde(rename = "bookmark", default, skip_serializing_if = "Vec::is_empty")]
+    bookmarks: Vec<SerializableBookmark>,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    restrict_charset: bool,
+    #[serde(rename = "group", default, skip_serializing_if = "Vec::is_empty")]
+    groups: Vec<SerializableGroup>,
+}
+
+/// 可序列化的坐标范围
+#[derive(Debug, Serialize, Deserialize)]
+struct SerializableBounds {
+    #[serde(rename = "@min_x")]
+    min_x: f64,
+    #[serde(rename = "@min_y")]
+    min_y: f64,
+    #[serde(rename = "@max_x")]
+    max_x: f64,
+    #[serde(rename = "@max_y")]
+    max_y: f64,
+}
+
+/// 可序列化的画布书签
+#[derive(Debug, Serialize, Deserialize)]
+struct SerializableBookmark {
+    #[serde(rename = "@name")]
+    name: String,
+    #[serde(rename = "@x")]
+    x: f32,
+    #[serde(rename = "@y")]
+    y: f32,
+    #[serde(rename = "@zoom")]
+    zoom: f32,
+}
+
+/// 可序列化的分组框，颜色保存为 `#rrggbb`，成员保存为以空格分隔的节点 ID
 #[derive(Debug, Serialize, Deserialize)]
+struct SerializableGroup {
+    #[serde(rename = "@id")]
+    id: u64,
+    #[serde(rename = "@label", default)]
+    label: String,
+    #[serde(rename = "@min_x")]
+    min_x: f64,
+    #[serde(rename = "@min_y")]
+    min_y: f64,
+    #[serde(rename = "@max_x")]
+    max_x: f64,
+    #[serde(rename = "@max_y")]
+    max_y: f64,
+    #[serde(rename = "@color", default)]
+    color: String,
+    #[serde(rename = "@members", default, skip_serializing_if = "String::is_empty")]
+    members: String,
+}
+
+impl From<&GroupFrame> for SerializableGroup {
+    fn from(value: &GroupFrame) -> Self {
+        let [r, g, b] = value.color;
+        Self {
+            id: value.id,
+            label: value.label.clone(),
+            min_x: value.rect.min.0,
+            min_y: value.rect.min.1,
+            max_x: value.rect.max.0,
+            max_y: value.rect.max.1,
+            color: format!("#{r:02x}{g:02x}{b:02x}"),
+            members: value
+                .members
+                .iter()
+                .map(u64::to_string)
+                .collect::<Vec<_>>()
+                .join(" "),
+        }
+    }
+}
+
+impl From<SerializableGroup> for GroupFrame {
+    fn from(value: SerializableGroup) -> Self {
+        // 颜色只影响显示，无法识别时使用默认颜色
+        let color = value
+            .color
+            .strip_prefix('#')
+            .filter(|hex| hex.len() == 6)
+            .and_then(|hex| u32::from_str_radix(hex, 16).ok())
+            .map_or(DEFAULT_GROUP_COLOR, |rgb| {
+                let [_, r, g, b] = rgb.to_be_bytes();
+                [r, g, b]
+            });
+        Self {
+            id: value.id,
+            label: value.label,
+            rect: CoordinateBounds {
+                min: (value.min_x, value.min_y),
+                max: (value.max_x, value.max_y),
+            },
+            color,
+            // 成员同样只影响显示，忽略无法识别的 ID
+            members: value
+                .members
+                .split_whitespace()
+                .filter_map(|id| id.parse().ok())
+                .collect(),
+        }
+    }
+}
+
+impl From<&GraphMetadata> for SerializableMetadata {
+    fn from(value: &GraphMetadata) -> Self {
+        Self {
+            max_nodes: value.max_nodes,
+            max_edges: value.max_edges,
+            max_content_chars: value.max_content_chars,
+            coordinate_bounds: value.coordinate_bounds.map(|bounds| SerializableBounds {
+                min_x: bounds.min.0,
+                min_y: bounds.min.1,
+                max_x: bounds.max.0,
+                max_y: bounds.max.1,
+            }),
+            bookmarks: value
+                .bookmarks
+                .iter()
+                .map(|bookmark| SerializableBookmark {
+                    name: bookmark.name.clone(),
+                    x: bookmark.scroll_offset.0,
+                    y: bookmark.scroll_offset.1,
+                    zoom: bookmark.zoom,
+                })
+                .collect(),
+            restrict_charset: value.restrict_charset,
+            groups: Vec::new(),
+        }
+    }
+}
+
+impl From<SerializableMetadata> for GraphMetadata {
+    fn from(value: SerializableMetadata) -> Self {
+        Self {
+            max_nodes: value.max_nodes,
+            max_edges: value.max_edges,
+            max_content_chars: value.max_content_chars,
+            coordinate_bounds: value.coordinate_bounds.map(|bounds| CoordinateBounds {
+                min: (bounds.min_x, bounds.min_y),
+                max: (bounds.max_x, bounds.max_y),
+            }),
+            bookmarks: value
+                .bookmarks
+                .into_iter()
+                .map(|bookmark| Bookmark {
+                    name: bookmark.name,
+                    scroll_offset: (bookmark.x, bookmark.y),
+                    zoom: bookmark.zoom,
+                })
+                .collect(),
+            restrict_charset: value.restrict_charset,
+        }
+    }
+}
+
+/// 将扩展元素拆分为图谱元数据和分组框，没有扩展元素时均为默认值
+fn split_metadata(metadata: Option<SerializableMetadata>) -> (GraphMetadata, Vec<GroupFrame>) {
+    let Some(mut metadata) = metadata else {
+        return Default::default();
+    };
+    let groups = std::mem::take(&mut metadata.groups)
+        .into_iter()
+        .map(GroupFrame::from)
+        .collect();
+    (GraphMetadata::from(metadata), groups)
+}
+
+/// 实体包装器
+#[derive(Debug, Default, Serialize, Deserialize)]
 struct Entities {
     #[serde(rename = "entity", default)]
     entities: Vec<SerializableEntity>,
 }
 
 /// 关系包装器
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Default, Serialize, Deserialize)]
 struct Relations {
     #[serde(rename = "relation", default)]
     pub items: Vec<SerializableEdge>,
@@ -274,62 +759,125 @@ struct Relations {
 
 impl From<&Snapshot> for SerializableSnapshot {
     fn from(value: &Snapshot) -> Self {
-        // 将实体节点转换为可序列化的实体节点
-        let entities = value
-            .nodes
-            .iter()
-            .map(|(_, node)| SerializableEntity::from(node))
+        // 将实体节点转换为可序列化的实体节点，按 (次序, ID) 排序使输出稳定，
+        // 对接平台按出现的顺序显示子节点。没有次序的节点排在最后
+        let mut nodes = value.nodes.values().collect::<Vec<_>>();
+        nodes.sort_unstable_by_key(|node| (node.order_index.unwrap_or(u32::MAX), node.id));
+        let entities = nodes.into_iter().map(SerializableEntity::from).collect();
+
+        // 将边转换为可序列化的边，按 (起点, 终点) 排序
+        let mut edges = value.edges.iter().collect::<Vec<_>>();
+        edges.sort_unstable_by_key(|(key, _)| **key);
+        let relations = edges
+            .into_iter()
+            .map(|(&(head, tail), relation)| {
+                let label = value.edge_labels.get(&(head, tail)).map(String::as_str);
+                let mut edge = SerializableEdge::from_edge(head, tail, *relation, label);
+                edge.set_waypoints(value.waypoints(head, tail));
+                edge
+            })
             .collect();
 
-        // 将边转换为可序列化的边
-        let relations = value
-            .edges
-            .iter()
-            .map(|(&(head, tail), relation)| SerializableEdge::from_edge(head, tail, *relation))
-            .collect();
+        // 元数据为默认值且没有分组框时不输出，保持与对接平台的格式一致
+        let metadata = (!value.metadata.is_default() || !value.groups.is_empty()).then(|| {
+            let mut metadata = SerializableMetadata::from(&value.metadata);
+            metadata.groups = value.groups.iter().map(SerializableGroup::from).collect();
+            metadata
+        });
 
         Self {
-            title: "教学知识图谱".to_string(),
+            title: title_or_default(&value.title),
             entities: Entities { entities },
             relations: Relations { items: relations },
+            metadata,
         }
     }
 }
 
-impl TryFrom<SerializableSnapshot> for Snapshot {
-    type Error = SerdeError;
+impl SerializableSnapshot {
+    /// 按对接平台的规则创建可序列化的快照：去掉扩展的备注、次序、链接、评审标记、主父节点、折点和元数据元素，
+    /// 边的 name 按关系填写，head_need 和 tail_need 填写起点和终点的 classification
+    fn compatible(snapshot: &Snapshot) -> Self {
+        let mut serializable = Self::from(snapshot);
+        serializable.metadata = None;
+
+        let mut classifications = std::collections::HashMap::new();
+        for entity in serializable.entities.entities.iter_mut() {
+            entity.notes.clear();
+            entity.order_index = None;
+            entity.links.clear();
+            entity.flag = None;
+            entity.primary_parent = None;
+            classifications.insert(entity.id, entity.classification.clone());
+        }
+        for edge in serializable.relations.items.iter_mut() {
+            let relation = snapshot.edges[&(edge.headnodeid, edge.tailnodeid)];
+            edge.name = relation.name().to_string();
+            edge.waypoints.clear();
+            if let Some(classification) = classifications.get(&edge.headnodeid) {
+                edge.head_need = classification.clone();
+            }
+            if let Some(classification) = classifications.get(&edge.tailnodeid) {
+                edge.tail_need = classification.clone();
+            }
+        }
+        serializable
+    }
+}
+
+impl Snapshot {
+    /// 按策略转换反序列化的快照，返回 class_name 与 level 不一致的说明
+    fn from_serializable(
+        value: SerializableSnapshot,
+        policy: LevelPolicy,
+    ) -> Result<(Self, Vec<String>), SerdeError> {
+        let mut warnings = Vec::new();
 
-    fn try_from(value: SerializableSnapshot) -> Result<Self, Self::Error> {
         // 将实体节点转换为哈希表
         let nodes: HashMap<_, _> = value
             .entities
             .entities
             .into_iter()
-            .map(|entity| {
+            .map(|mut entity| {
+                check_entity_id(entity.id)?;
+                warnings.extend(entity.check_level(policy)?);
+                warnings.extend(entity.drop_unknown_flag());
                 let entity = EntityNode::try_from(entity)?;
                 Ok::<_, SerdeError>((entity.id, entity))
             })
             .collect::<Result<_, _>>()?;
 
-        // 将边转换为哈希表
-        let edges = value
-            .relations
-            .items
-            .into_iter()
-            .map(|edge| {
-                let (from, to, relation) = edge.to_edge()?;
-                Ok::<_, SerdeError>(((from, to), relation))
-            })
-            .collect::<Result<_, _>>()?;
+        // 将边和边的自定义标签、折点转换为哈希表
+        let mut edges = HashMap::new();
+        let mut edge_labels = HashMap::new();
+        let mut edge_waypoints = HashMap::new();
+        for edge in value.relations.items {
+            let (from, to, relation) = edge.to_edge()?;
+            edges.insert((from, to), relation);
+            if let Some(label) = edge.label() {
+                edge_labels.insert((from, to), label);
+            }
+            let waypoints = edge.waypoints()?;
+            if !waypoints.is_empty() {
+                edge_waypoints.insert((from, to), waypoints);
+            }
+        }
 
         // 获取最大的节点 ID
-        let latest_id = nodes.keys().max().copied().unwrap_or(0) + 1;
+        let latest_id = next_id_after(&nodes)?;
 
-        Ok(Self {
+        let (metadata, groups) = split_metadata(value.metadata);
+        let snapshot = Self {
+            title: title_or_default(&value.title),
             nodes,
             edges,
+            edge_labels,
+            edge_waypoints,
+            metadata,
+            groups,
             latest_id,
-        })
+        };
+        Ok((snapshot, warnings))
     }
 }
 
@@ -353,17 +901,69 @@ fn indent_xml(xml_string: &str) -> Result<String, quick_xml::Error> {
     Ok(String::from_utf8(writer.into_inner().into_inner()).unwrap())
 }
 
+/// 将文本首尾的空白字符转义为数字实体。读取时会去掉文本首尾未转义的空白，
+/// 转义后只含空白或以空白开头、结尾的内容也能原样读回
+fn escape_edge_whitespace(xml_string: &str) -> Result<String, quick_xml::Error> {
+    let is_whitespace = |c: char| matches!(c, ' ' | '\t' | '\n' | '\r');
+    let escape_all = |s: &str| {
+        s.chars()
+            .map(|c| format!("&#{};", c as u32))
+            .collect::<String>()
+    };
+
+    let mut reader = Reader::from_str(xml_string);
+    let mut writer = Writer::new(Cursor::new(Vec::new()));
+    loop {
+        match reader.read_event()? {
+            Event::Eof => break,
+            Event::Text(text) => {
+                let raw = std::str::from_utf8(&text).expect("input is a str");
+                let body = raw.trim_matches(is_whitespace);
+                let start = raw.len() - raw.trim_start_matches(is_whitespace).len();
+                let (leading, trailing) = if body.is_empty() {
+                    (raw, "")
+                } else {
+                    (&raw[..start], &raw[start + body.len()..])
+                };
+                let escaped = format!("{}{body}{}", escape_all(leading), escape_all(trailing));
+                writer.write_event(Event::Text(BytesText::from_escaped(escaped)))?;
+            }
+            event => writer.write_event(event)?,
+        }
+    }
+
+    Ok(String::from_utf8(writer.into_inner().into_inner()).unwrap())
+}
+
 impl SerializableSnapshot {
-    /// 将快照转换为 XML 格式
-    pub fn to_xml(&self) -> Result<String, SerdeError> {
+    /// 按选项将快照转换为 XML 格式
+    pub fn to_xml_with(&self, options: XmlOptions) -> Result<String, SerdeError> {
         // 序列化为 XML 字符串
-        let content = quick_xml::se::to_string(self)?;
+        let mut content = quick_xml::se::to_string(self)?;
+        content = escape_edge_whitespace(&content)?;
 
         // 添加缩进
-        let indented_content = indent_xml(&content)?;
+        if options.indent {
+            content = indent_xml(&content)?;
+        }
 
         // 转义非 ASCII 字符
-        Ok(escape_non_ascii(&indented_content))
+        if options.escape_non_ascii {
+            content = escape_non_ascii(&content);
+        }
+
+        // 标记之间不含 CR，文本中的 CR 转义后不会在读取时被当作换行
+        if content.contains('\r') {
+            content = content.replace('\r', "&#13;");
+        }
+        if options.declaration {
+            content.insert_str(0, "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+        }
+        if options.crlf {
+            content = content.replace('\n', "\r\n");
+        }
+
+        Ok(content)
     }
 
     /// 从 XML 字符串解析快照
@@ -373,75 +973,629 @@ impl SerializableSnapshot {
     }
 }
 
+impl SerializableSnapshot {
+    /// 检查对接平台对 XML 格式的要求
+    fn export_checks(&self) -> Vec<ValidationIssue> {
+        let mut issues = Vec::new();
+        let mut error = |message: String, entities: Vec<u64>| {
+            issues.push(ValidationIssue::new(IssueLevel::Error, message, entities));
+        };
+
+        if self.title.trim().is_empty() {
+            error("图谱标题为空".to_string(), Vec::new());
+        }
+
+        let mut ids = HashSet::new();
+        for entity in self.entities.entities.iter() {
+            let id = entity.id;
+            if id == 0 {
+                error("节点 ID 必须为正整数".to_string(), vec![id]);
+            } else if id > MAX_PLATFORM_ID {
+                error(
+                    format!("节点 ID {id} 超出对接平台接受的范围（不大于 {MAX_PLATFORM_ID}）"),
+                    vec![id],
+                );
+            } else if !ids.insert(id) {
+                error(format!("节点 ID {id} 重复"), vec![id]);
+            }
+            if DistinctEntityType::from_class_name(&entity.class_name).is_none() {
+                error(
+                    format!("节点 {id} 的类型“{}”不被接受", entity.class_name),
+                    vec![id],
+                );
+            }
+            let attach = addon_flags(&entity.attach);
+            if attach.len() != ADDON_TYPES.len() || attach.chars().any(|c| c != '0' && c != '1') {
+                error(format!("节点 {id} 的附加类型“{attach}”格式错误"), vec![id]);
+            }
+        }
+
+        for edge in self.relations.items.iter() {
+            let (from, to) = (edge.headnodeid, edge.tailnodeid);
+            if let Err(e) = edge.to_edge() {
+                error(format!("边 ({from}, {to}) 无法识别：{e}"), vec![from, to]);
+            }
+            if !ids.contains(&from) || !ids.contains(&to) {
+                error(
+                    format!("边 ({from}, {to}) 引用了不存在的节点"),
+                    [from, to]
+                        .into_iter()
+                        .filter(|id| ids.contains(id))
+                        .collect(),
+                );
+            }
+        }
+
+        issues
+    }
+}
+
+/// 检查快照导出的 XML 是否满足对接平台的格式要求：标题非空、节点 ID 为正、不重复且不超过 32 位整数、
+/// 类型和关系名可被接受、附加类型为 6 个字符、边不引用不存在的节点。发现的问题均为错误
+pub fn export_checks(snapshot: &Snapshot) -> Vec<ValidationIssue> {
+    SerializableSnapshot::from(snapshot).export_checks()
+}
+
 impl Snapshot {
+    /// 去掉仅作参考的节点及其边后的快照，用于各种导出；没有这样的节点时返回 None，避免复制
+    pub fn without_references(&self) -> Option<Snapshot> {
+        if !self.nodes.values().any(|node| node.reference) {
+            return None;
+        }
+        let ids = self
+            .nodes
+            .values()
+            .filter(|node| !node.reference)
+            .map(|node| node.id)
+            .collect::<BTreeSet<_>>();
+        let mut snapshot = self.subgraph(&ids);
+        snapshot.groups = self.groups.clone();
+        for group in snapshot.groups.iter_mut() {
+            group.members.retain(|id| ids.contains(id));
+        }
+        Some(snapshot)
+    }
+
+    /// 提交对接平台前的严格检查：校验图谱并检查格式要求，返回所有警告和错误，为空时才可导出。
+    /// 仅作参考的节点不导出，因此不参与检查
+    pub fn strict_issues(&self) -> Vec<ValidationIssue> {
+        if let Some(snapshot) = self.without_references() {
+            return snapshot.strict_issues();
+        }
+        let mut issues = self.validate();
+        issues.extend(export_checks(self));
+        issues.retain(|issue| issue.level >= IssueLevel::Warning);
+        issues
+    }
+
+    /// 将快照转换为对接平台可直接打开的 XML 格式。
+    /// 不输出本软件扩展的元素，按平台的规则填写边的 name、head_need 和 tail_need；
+    /// 不满足格式要求（见 export_checks）时不输出，返回列出所有问题的 Incompatible 错误。
+    /// 仅作参考的节点及其边不输出
+    pub fn to_compatible_xml(&self) -> Result<String, SerdeError> {
+        if let Some(snapshot) = self.without_references() {
+            return snapshot.to_compatible_xml();
+        }
+        let serializable = SerializableSnapshot::compatible(self);
+        let problems = serializable
+            .export_checks()
+            .into_iter()
+            .map(|issue| issue.message)
+            .collect::<Vec<_>>();
+        if !problems.is_empty() {
+            return Err(SerdeError::Incompatible(problems));
+        }
+        serializable.to_xml_with(XmlOptions::default())
+    }
+
     /// 将快照转换为 XML 格式
     #[inline]
     pub fn to_xml(&self) -> Result<String, SerdeError> {
-        SerializableSnapshot::from(self).to_xml()
+        self.to_xml_with(XmlOptions::default())
     }
 
-    /// 从 XML 字符串解析快照
+    /// 按选项将快照转换为 XML 格式
     #[inline]
-    pub fn from_xml(xml: &str) -> Result<Self, SerdeError> {
-        let s = SerializableSnapshot::from_xml(xml).map_err(SerdeError::Deserialize)?;
-        Snapshot::try_from(s)
+    pub fn to_xml_with(&self, options: XmlOptions) -> Result<String, SerdeError> {
+        SerializableSnapshot::from(self).to_xml_with(options)
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use crate::graph::KnowledgeGraph;
+    /// 从 XML 字符串解析快照。
+    /// 文本中的 CDATA 段、数字实体以及 `&lt; &gt; &amp; &quot; &apos;` 均还原为原字符。
+    /// 接受有无缩进、XML 声明和 Windows 换行符的各种写法，KG 元素可包在外层元素中。
+    /// 包含多个 KG 元素时返回 MultipleDocuments 错误。
+    /// class_name 与 level 矛盾时以 class_name 为准。
+    #[inline]
+    pub fn from_xml(xml: &str) -> Result<Self, SerdeError> {
+        Self::from_xml_with_policy(xml, LevelPolicy::default()).map(|(snapshot, _)| snapshot)
+    }
 
-    use super::*;
+    /// 从 XML 字符串解析快照，class_name 与 level 矛盾时按策略处理，并在返回的警告中说明。
+    pub fn from_xml_with_policy(
+        xml: &str,
+        policy: LevelPolicy,
+    ) -> Result<(Self, Vec<String>), SerdeError> {
+        let xml = normalize_line_endings(xml);
+        let documents = kg_documents(&xml);
+        if documents.len() > 1 {
+            return Err(SerdeError::MultipleDocuments(documents.len()));
+        }
+        let document = documents.first().map_or(&*xml, |range| &xml[range.clone()]);
+        let s = SerializableSnapshot::from_xml(document).map_err(SerdeError::Deserialize)?;
+        Snapshot::from_serializable(s, policy)
+    }
 
-    use quick_xml::se::to_string as serialize_xml;
+    /// 从 XML 字符串解析快照，并将内容超过 limit 个字符的节点的超出部分移到备注，
+    /// 在返回的警告中说明。用于导入他人粘贴了大段文字的图谱
+    pub fn from_xml_with_content_limit(
+        xml: &str,
+        limit: usize,
+    ) -> Result<(Self, Vec<String>), SerdeError> {
+        let (mut snapshot, mut warnings) = Self::from_xml_with_policy(xml, LevelPolicy::default())?;
+        let mut ids = snapshot.nodes.keys().copied().collect::<Vec<_>>();
+        ids.sort_unstable();
+        for id in ids {
+            if let Some(node) = snapshot.nodes.get_mut(&id)
+                && node.move_excess_content(limit)
+            {
+                warnings.push(format!(
+                    "节点 {id} 的内容超过 {limit} 字，超出部分已移到备注"
+                ));
+            }
+        }
+        Ok((snapshot, warnings))
+    }
 
-    fn to_xml(value: impl Serialize) -> Result<String, quick_xml::SeError> {
-        let content = serialize_xml(&value)?;
-        Ok(escape_non_ascii(&content))
+    /// 转换为紧凑的二进制格式，用于本地草稿或历史记录等快速存取，不可用于对接平台
+    #[inline]
+    pub fn to_bytes(&self) -> Result<Vec<u8>, SerdeError> {
+        Ok(bincode::serialize(self)?)
     }
 
-    #[test]
-    fn test_encode_entity_node() {
-        let default_id = 114514;
-        let default_content = "Hello 世界！🦀@#& ";
-        let default_addons = vec![
-            AddonEntityType::Thinking,
-            AddonEntityType::Political,
-            AddonEntityType::Question,
-        ];
-        let default_coordinate = (1.0, 2.0);
+    /// 从 to_bytes 生成的二进制数据解析快照
+    #[inline]
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, SerdeError> {
+        Ok(bincode::deserialize(bytes)?)
+    }
 
-        let distinct_types = [
-            DistinctEntityType::KnowledgeArena,
-            DistinctEntityType::KnowledgeUnit,
-            DistinctEntityType::KnowledgePoint,
-            DistinctEntityType::KnowledgeDetail,
-        ];
+    /// 宽容地从 XML 字符串解析快照：无法识别的节点和边、重复的节点和边、
+    /// 引用不存在节点的边均被忽略，并在返回的警告中说明。XML 结构本身错误时仍返回错误。
+    /// class_name 与 level 矛盾时以 class_name 为准，包含多个 KG 元素时只读取第一个，同样给出警告。
+    pub fn from_xml_lenient(xml: &str) -> Result<(Self, Vec<String>), SerdeError> {
+        let xml = normalize_line_endings(xml);
+        let documents = kg_documents(&xml);
+        let mut warnings = Vec::new();
+        if let [first, rest @ ..] = documents.as_slice()
+            && !rest.is_empty()
+        {
+            let line = xml[..first.start].matches('\n').count() + 1;
+            warnings.push(format!(
+                "文件包含 {} 个图谱，只读取了第 1 个（从第 {line} 行开始），其余已忽略",
+                documents.len()
+            ));
+        }
+        let document = documents.first().map_or(&*xml, |range| &xml[range.clone()]);
+        let value = SerializableSnapshot::from_xml(document).map_err(SerdeError::Deserialize)?;
+
+        let mut nodes = HashMap::new();
+        for mut entity in value.entities.entities {
+            let id = entity.id;
+            if check_entity_id(id).is_err() {
+                warnings.push(format!(
+                    "节点 {id} 的 ID 超出范围（最大为 {MAX_ENTITY_ID}），已忽略"
+                ));
+                continue;
+            }
+            if let Ok(Some(warning)) = entity.check_level(LevelPolicy::ClassName) {
+                warnings.push(warning);
+            }
+            warnings.extend(entity.drop_unknown_flag());
+            match EntityNode::try_from(entity) {
+                Ok(_) if nodes.contains_key(&id) => {
+                    warnings.push(format!("节点 {id} 重复，已忽略后出现的节点"));
+                }
+                Ok(node) => {
+                    nodes.insert(id, node);
+                }
+                Err(e) => warnings.push(format!("节点 {id} 无法识别，已忽略：{e}")),
+            }
+        }
 
-        let xmls = [
-            "<entity><id>114514</id><class_name>&#30693;&#35782;&#39046;&#22495;</class_name><classification>&#20869;&#23481;&#26041;&#27861;&#22411;&#33410;&#28857;</classification><identity>&#30693;&#35782;</identity><level>&#19968;&#32423;</level><attach>111000</attach><opentool>&#26080;</opentool><content>Hello &#19990;&#30028;&#65281;&#129408;@#&amp; </content><x>1</x><y>2</y></entity>",
-            "<entity><id>114514</id><class_name>&#30693;&#35782;&#21333;&#20803;</class_name><classification>&#20869;&#23481;&#26041;&#27861;&#22411;&#33410;&#28857;</classification><identity>&#30693;&#35782;</identity><level>&#20108;&#32423;</level><attach>111000</attach><opentool>&#26080;</opentool><content>Hello &#19990;&#30028;&#65281;&#129408;@#&amp; </content><x>1</x><y>2</y></entity>",
-            "<entity><id>114514</id><class_name>&#30693;&#35782;&#28857;</class_name><classification>&#20869;&#23481;&#26041;&#27861;&#22411;&#33410;&#28857;</classification><identity>&#30693;&#35782;</identity><level>&#24402;&#32435;&#32423;</level><attach>111000</attach><opentool>&#26080;</opentool><content>Hello &#19990;&#30028;&#65281;&#129408;@#&amp; </content><x>1</x><y>2</y></entity>",
-            "<entity><id>114514</id><class_name>&#20851;&#38190;&#30693;&#35782;&#32454;&#33410;</class_name><classification>&#20869;&#23481;&#26041;&#27861;&#22411;&#33410;&#28857;</classification><identity>&#30693;&#35782;</identity><level>&#20869;&#23481;&#32423;</level><attach>111000</attach><opentool>&#26080;</opentool><content>Hello &#19990;&#30028;&#65281;&#129408;@#&amp; </content><x>1</x><y>2</y></entity>",
-        ];
+        let mut edges = HashMap::new();
+        let mut edge_labels = HashMap::new();
+        let mut edge_waypoints = HashMap::new();
+        for edge in value.relations.items {
+            let (from, to) = (edge.headnodeid, edge.tailnodeid);
+            match edge.to_edge() {
+                Ok(_) if !nodes.contains_key(&from) || !nodes.contains_key(&to) => {
+                    warnings.push(format!("边 ({from}, {to}) 引用了不存在的节点，已忽略"));
+                }
+                Ok(_) if edges.contains_key(&(from, to)) => {
+                    warnings.push(format!("边 ({from}, {to}) 重复，已忽略后出现的边"));
+                }
+                Ok((from, to, relation)) => {
+                    edges.insert((from, to), relation);
+                    if let Some(label) = edge.label() {
+                        edge_labels.insert((from, to), label);
+                    }
+                    match edge.waypoints() {
+                        Ok(waypoints) if waypoints.is_empty() => {}
+                        Ok(waypoints) => {
+                            edge_waypoints.insert((from, to), waypoints);
+                        }
+                        Err(e) => warnings.push(format!("边 ({from}, {to}) 的折点已忽略：{e}")),
+                    }
+                }
+                Err(e) => warnings.push(format!("边 ({from}, {to}) 无法识别，已忽略：{e}")),
+            }
+        }
 
-        for (distinct_type, xml_gt) in distinct_types.iter().zip(xmls.iter()) {
-            let node = EntityNode::new(
-                default_id,
-                default_content.to_string(),
-                *distinct_type,
-                &default_addons,
-                default_coordinate,
-            );
+        let (metadata, groups) = split_metadata(value.metadata);
+        let mut snapshot = Self {
+            title: title_or_default(&value.title),
+            nodes,
+            edges,
+            edge_labels,
+            edge_waypoints,
+            metadata,
+            groups,
+            latest_id: 1,
+        };
+        snapshot.repair_latest_id();
+        Ok((snapshot, warnings))
+    }
 
-            let xml = to_xml(SerializableEntity::from(&node)).unwrap();
-            assert_eq!(xml, *xml_gt);
+    /// 转换为 PlantUML 思维导图，可直接粘贴到 PlantUML 中渲染。
+    /// 按包含关系生成层级，次序关系不体现，兄弟节点按 (次序, ID) 排列，与保存的顺序一致。
+    /// 多个节点包含同一节点时，只出现在最先遍历到的父节点下；
+    /// 有多个根节点时，以图谱标题作为总的根节点。仅作参考的节点不输出。
+    pub fn to_plantuml(&self) -> String {
+        if let Some(snapshot) = self.without_references() {
+            return snapshot.to_plantuml();
         }
-    }
+        // 每个节点包含的子节点，按 (次序, ID) 排序
+        let mut children: BTreeMap<u64, Vec<u64>> = BTreeMap::new();
+        for ((from, to), relation) in self.edges.iter() {
+            if *relation == Relation::Contain {
+                children.entry(*from).or_default().push(*to);
+            }
+        }
+        children.values_mut().for_each(|ids| {
+            ids.sort_unstable_by_key(|id| {
+                let order_index = self.nodes.get(id).and_then(|node| node.order_index);
+                (order_index.unwrap_or(u32::MAX), *id)
+            })
+        });
 
-    #[test]
-    fn test_encode_relation() {
+        // 先从未被包含的节点出发，再从仅在环中的节点出发，深度优先得到每个节点及其层数
+        let contained = children.values().flatten().collect::<HashSet<_>>();
+        let mut ids = self.nodes.keys().copied().collect::<Vec<_>>();
+        ids.sort_unstable();
+        let starts = ids
+            .iter()
+            .filter(|id| !contained.contains(id))
+            .chain(ids.iter().filter(|id| contained.contains(id)));
+        let mut visited = HashSet::new();
+        let mut entries = Vec::with_capacity(ids.len());
+        let mut roots = 0;
+        for start in starts {
+            if visited.contains(start) {
+                continue;
+            }
+            roots += 1;
+            let mut stack = vec![(*start, 0)];
+            while let Some((id, depth)) = stack.pop() {
+                if !visited.insert(id) {
+                    continue;
+                }
+                entries.push((id, depth));
+                if let Some(ids) = children.get(&id) {
+                    stack.extend(ids.iter().rev().map(|child| (*child, depth + 1)));
+                }
+            }
+        }
+
+        let mut lines = vec!["@startmindmap".to_string()];
+        let offset = if roots > 1 {
+            lines.push(format!(
+                "* {}",
+                title_or_default(&self.title).replace('\n', " ")
+            ));
+            2
+        } else {
+            1
+        };
+        for (id, depth) in entries {
+            let stars = "*".repeat(depth + offset);
+            let content = self.nodes[&id].content.trim();
+            lines.push(if content.is_empty() {
+                format!("{stars} （空）")
+            } else if content.contains('\n') {
+                // 多行内容使用 :...; 语法
+                format!("{stars}:{content};")
+            } else {
+                format!("{stars} {content}")
+            });
+        }
+        lines.push("@endmindmap".to_string());
+
+        lines.join("\n") + "\n"
+    }
+
+    /// 转换为层级缩进的纯文本树，可直接复制到聊天或邮件中，子节点不加序号，见 to_text_tree_with
+    #[inline]
+    pub fn to_text_tree(&self) -> String {
+        self.to_text_tree_with(false)
+    }
+
+    /// 转换为层级缩进的纯文本树，每级缩进两个空格，numbered 为 true 时每个节点前加上在兄弟节点中的序号。
+    /// 以没有父节点的知识领域为根，再依次为其他有子节点但没有父节点的节点、只在包含关系的环中的节点，
+    /// 按包含关系递归列出，子节点按设置的次序排列；每个节点只出现一次，多个父节点时在先列出的父节点下。
+    /// 没有任何包含关系的孤立节点在末尾空一行后列出。多行内容合并为一行，仅作参考的节点不输出
+    pub fn to_text_tree_with(&self, numbered: bool) -> String {
+        if let Some(snapshot) = self.without_references() {
+            return snapshot.to_text_tree_with(numbered);
+        }
+        let mut parents = HashSet::new();
+        let mut has_children = HashSet::new();
+        for ((from, to), relation) in self.edges.iter() {
+            if *relation == Relation::Contain {
+                has_children.insert(*from);
+                parents.insert(*to);
+            }
+        }
+        let mut ids = self.nodes.keys().copied().collect::<Vec<_>>();
+        ids.sort_unstable();
+        let is_arena =
+            |id: &u64| self.nodes[id].distinct_type == DistinctEntityType::KnowledgeArena;
+        let (isolated, ids): (Vec<_>, Vec<_>) = ids
+            .into_iter()
+            .partition(|id| !parents.contains(id) && !has_children.contains(id));
+        let starts = ids
+            .iter()
+            .filter(|id| !parents.contains(id) && is_arena(id))
+            .chain(
+                ids.iter()
+                    .filter(|id| !parents.contains(id) && !is_arena(id)),
+            )
+            .chain(ids.iter().filter(|id| parents.contains(id)))
+            .copied()
+            .collect::<Vec<_>>();
+
+        let line = |id: u64, depth: usize, index: usize| {
+            let content = self.nodes[&id]
+                .content
+                .split_whitespace()
+                .collect::<Vec<_>>();
+            let number = if numbered {
+                format!("{index}. ")
+            } else {
+                String::new()
+            };
+            format!("{}{number}{}", "  ".repeat(depth), content.join(" "))
+        };
+        let mut visited = HashSet::new();
+        let mut lines = Vec::with_capacity(self.nodes.len() + 1);
+        let mut index = 0;
+        for start in starts {
+            if !visited.insert(start) {
+                continue;
+            }
+            index += 1;
+            lines.push(line(start, 0, index));
+            // 深度优先，栈中为 (节点, 层数, 在兄弟节点中的序号)
+            let mut stack = Vec::new();
+            let push_children = |stack: &mut Vec<_>, id: u64, depth: usize| {
+                let children = self.children(id);
+                stack.extend(
+                    children
+                        .into_iter()
+                        .enumerate()
+                        .rev()
+                        .map(|(i, child)| (child, depth + 1, i + 1)),
+                );
+            };
+            push_children(&mut stack, start, 0);
+            while let Some((id, depth, i)) = stack.pop() {
+                if !visited.insert(id) {
+                    continue;
+                }
+                lines.push(line(id, depth, i));
+                push_children(&mut stack, id, depth);
+            }
+        }
+        if !isolated.is_empty() && !lines.is_empty() {
+            lines.push(String::new());
+        }
+        for id in isolated {
+            index += 1;
+            lines.push(line(id, 0, index));
+        }
+
+        lines.join("\n") + "\n"
+    }
+
+    /// 转换为 GEXF 1.3，可在 Gephi 或 NetworkX 中打开。图谱标题作为描述，节点以内容为 label，以坐标为 viz:position
+    /// 以保留布局，并带有 class_name 和 attach 属性；边为有向边，带有 relation 属性。
+    /// 仅作参考的节点不输出。
+    pub fn to_gexf(&self) -> String {
+        if let Some(snapshot) = self.without_references() {
+            return snapshot.to_gexf();
+        }
+        let mut ids = self.nodes.keys().copied().collect::<Vec<_>>();
+        ids.sort_unstable();
+        let mut edges = self.edges.iter().collect::<Vec<_>>();
+        edges.sort_unstable_by_key(|(edge, _)| **edge);
+
+        let mut lines = vec![
+            r#"<?xml version="1.0" encoding="UTF-8"?>"#.to_string(),
+            r#"<gexf xmlns="http://gexf.net/1.3" xmlns:viz="http://gexf.net/1.3/viz" version="1.3">"#
+                .to_string(),
+            format!(
+                "  <meta><description>{}</description></meta>",
+                escape(title_or_default(&self.title).as_str())
+            ),
+            r#"  <graph defaultedgetype="directed" mode="static">"#.to_string(),
+            r#"    <attributes class="node">"#.to_string(),
+            r#"      <attribute id="class_name" title="class_name" type="string"/>"#.to_string(),
+            r#"      <attribute id="attach" title="attach" type="string"/>"#.to_string(),
+            "    </attributes>".to_string(),
+            r#"    <attributes class="edge">"#.to_string(),
+            r#"      <attribute id="relation" title="relation" type="string"/>"#.to_string(),
+            "    </attributes>".to_string(),
+            "    <nodes>".to_string(),
+        ];
+        for id in ids {
+            let node = &self.nodes[&id];
+            // 属性值中的换行会被规范化为空格，因此转义
+            let label = escape(node.content.as_str()).replace('\n', "&#10;");
+            lines.push(format!(r#"      <node id="{id}" label="{label}">"#));
+            lines.push("        <attvalues>".to_string());
+            lines.push(format!(
+                r#"          <attvalue for="class_name" value="{}"/>"#,
+                node.distinct_type.class_name()
+            ));
+            lines.push(format!(
+                r#"          <attvalue for="attach" value="{}"/>"#,
+                addon_flags(&node.addon_types)
+            ));
+            lines.push("        </attvalues>".to_string());
+            lines.push(format!(
+                r#"        <viz:position x="{}" y="{}" z="0"/>"#,
+                node.coor.0, node.coor.1
+            ));
+            lines.push("      </node>".to_string());
+        }
+        lines.push("    </nodes>".to_string());
+        lines.push("    <edges>".to_string());
+        for (i, ((from, to), relation)) in edges.into_iter().enumerate() {
+            lines.push(format!(
+                r#"      <edge id="{i}" source="{from}" target="{to}">"#
+            ));
+            lines.push(format!(
+                r#"        <attvalues><attvalue for="relation" value="{}"/></attvalues>"#,
+                relation.class_name()
+            ));
+            lines.push("      </edge>".to_string());
+        }
+        lines.push("    </edges>".to_string());
+        lines.push("  </graph>".to_string());
+        lines.push("</gexf>".to_string());
+
+        lines.join("\n") + "\n"
+    }
+
+    /// 转换为 Anki 可导入的 TSV 记忆卡片。每个带有问题或练习附加类型的节点为一张卡片，
+    /// 正面为节点内容，背面为其包含的子节点的内容，按子节点顺序逐行排列；没有子节点时以备注为背面，
+    /// 两者都没有的节点不输出。第三列为附加类型的标签。内容按 HTML 转义，换行转换为 `<br>`。
+    /// 仅作参考的节点不输出
+    pub fn to_anki_tsv(&self) -> String {
+        if let Some(snapshot) = self.without_references() {
+            return snapshot.to_anki_tsv();
+        }
+        // 制表符会分隔字段，替换为空格
+        let field = |text: &str| {
+            escape(text.trim())
+                .replace('\t', " ")
+                .replace("\r\n", "<br>")
+                .replace(['\r', '\n'], "<br>")
+        };
+
+        let mut ids = self.nodes.keys().copied().collect::<Vec<_>>();
+        ids.sort_unstable();
+        let mut lines = vec![
+            "#separator:tab".to_string(),
+            "#html:true".to_string(),
+            "#tags column:3".to_string(),
+        ];
+        for id in ids {
+            let node = &self.nodes[&id];
+            let tags = [AddonEntityType::Question, AddonEntityType::Practice]
+                .into_iter()
+                .filter(|addon_type| node.addon_types.contains(addon_type))
+                .map(|addon_type| addon_type.to_string())
+                .collect::<Vec<_>>();
+            if tags.is_empty() {
+                continue;
+            }
+            let children = self
+                .children(id)
+                .into_iter()
+                .filter_map(|child| self.nodes.get(&child))
+                .map(|child| field(&child.content))
+                .collect::<Vec<_>>();
+            let back = if children.is_empty() {
+                field(&node.notes)
+            } else {
+                children.join("<br>")
+            };
+            if back.is_empty() {
+                continue;
+            }
+            lines.push(format!(
+                "{}\t{back}\t{}",
+                field(&node.content),
+                tags.join(" ")
+            ));
+        }
+        lines.join("\n") + "\n"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::graph::KnowledgeGraph;
+
+    use super::*;
+
+    use quick_xml::se::to_string as serialize_xml;
+
+    fn to_xml(value: impl Serialize) -> Result<String, quick_xml::SeError> {
+        let content = serialize_xml(&value)?;
+        Ok(escape_non_ascii(&content))
+    }
+
+    #[test]
+    fn test_encode_entity_node() {
+        let default_id = 114514;
+        let default_content = "Hello 世界！🦀@#& ";
+        let default_addons = vec![
+            AddonEntityType::Thinking,
+            AddonEntityType::Political,
+            AddonEntityType::Question,
+        ];
+        let default_coordinate = (1.0, 2.0);
+
+        let distinct_types = [
+            DistinctEntityType::KnowledgeArena,
+            DistinctEntityType::KnowledgeUnit,
+            DistinctEntityType::KnowledgePoint,
+            DistinctEntityType::KnowledgeDetail,
+        ];
+
+        let xmls = [
+            "<entity><id>114514</id><class_name>&#30693;&#35782;&#39046;&#22495;</class_name><classification>&#20869;&#23481;&#26041;&#27861;&#22411;&#33410;&#28857;</classification><identity>&#30693;&#35782;</identity><level>&#19968;&#32423;</level><attach>111000</attach><opentool>&#26080;</opentool><content>Hello &#19990;&#30028;&#65281;&#129408;@#&amp; </content><x>1</x><y>2</y></entity>",
+            "<entity><id>114514</id><class_name>&#30693;&#35782;&#21333;&#20803;</class_name><classification>&#20869;&#23481;&#26041;&#27861;&#22411;&#33410;&#28857;</classification><identity>&#30693;&#35782;</identity><level>&#20108;&#32423;</level><attach>111000</attach><opentool>&#26080;</opentool><content>Hello &#19990;&#30028;&#65281;&#129408;@#&amp; </content><x>1</x><y>2</y></entity>",
+            "<entity><id>114514</id><class_name>&#30693;&#35782;&#28857;</class_name><classification>&#20869;&#23481;&#26041;&#27861;&#22411;&#33410;&#28857;</classification><identity>&#30693;&#35782;</identity><level>&#24402;&#32435;&#32423;</level><attach>111000</attach><opentool>&#26080;</opentool><content>Hello &#19990;&#30028;&#65281;&#129408;@#&amp; </content><x>1</x><y>2</y></entity>",
+            "<entity><id>114514</id><class_name>&#20851;&#38190;&#30693;&#35782;&#32454;&#33410;</class_name><classification>&#20869;&#23481;&#26041;&#27861;&#22411;&#33410;&#28857;</classification><identity>&#30693;&#35782;</identity><level>&#20869;&#23481;&#32423;</level><attach>111000</attach><opentool>&#26080;</opentool><content>Hello &#19990;&#30028;&#65281;&#129408;@#&amp; </content><x>1</x><y>2</y></entity>",
+        ];
+
+        for (distinct_type, xml_gt) in distinct_types.iter().zip(xmls.iter()) {
+            let node = EntityNode::new(
+                default_id,
+                default_content.to_string(),
+                *distinct_type,
+                &default_addons,
+                default_coordinate,
+            );
+
+            let xml = to_xml(SerializableEntity::from(&node)).unwrap();
+            assert_eq!(xml, *xml_gt);
+        }
+    }
+
+    #[test]
+    fn test_encode_relation() {
         let (id_1, id_2) = (114514, 1919810);
         let relations = [
             ((id_1, id_2), Relation::Contain),
@@ -449,10 +1603,10 @@ mod tests {
         ];
         let xmls = [
             "<relation><name>&#21253;&#21547;</name><headnodeid>114514</headnodeid><tailnodeid>1919810</tailnodeid><class_name>&#21253;&#21547;&#20851;&#31995;</class_name><mask>&#30693;&#35782;&#36830;&#32447;</mask><classification>&#21253;&#21547;&#20851;&#31995;</classification><head_need>&#20869;&#23481;&#26041;&#27861;&#22411;&#33410;&#28857;</head_need><tail_need>&#20869;&#23481;&#26041;&#27861;&#22411;&#33410;&#28857;</tail_need></relation>",
-            "<relation><name>&#21253;&#21547;</name><headnodeid>114514</headnodeid><tailnodeid>1919810</tailnodeid><class_name>&#27425;&#24207;&#20851;&#31995;</class_name><mask>&#30693;&#35782;&#36830;&#32447;</mask><classification>&#27425;&#24207;&#20851;&#31995;</classification><head_need>&#20869;&#23481;&#26041;&#27861;&#22411;&#33410;&#28857;</head_need><tail_need>&#20869;&#23481;&#26041;&#27861;&#22411;&#33410;&#28857;</tail_need></relation>",
+            "<relation><name>&#27425;&#24207;</name><headnodeid>114514</headnodeid><tailnodeid>1919810</tailnodeid><class_name>&#27425;&#24207;&#20851;&#31995;</class_name><mask>&#30693;&#35782;&#36830;&#32447;</mask><classification>&#27425;&#24207;&#20851;&#31995;</classification><head_need>&#20869;&#23481;&#26041;&#27861;&#22411;&#33410;&#28857;</head_need><tail_need>&#20869;&#23481;&#26041;&#27861;&#22411;&#33410;&#28857;</tail_need></relation>",
         ];
         for (((head, tail), relation), xml_gt) in relations.iter().zip(xmls.iter()) {
-            let xml = to_xml(SerializableEdge::from_edge(*head, *tail, *relation)).unwrap();
+            let xml = to_xml(SerializableEdge::from_edge(*head, *tail, *relation, None)).unwrap();
             assert_eq!(xml, *xml_gt);
         }
     }
@@ -465,7 +1619,7 @@ mod tests {
             DistinctEntityType::KnowledgeArena,
             &[AddonEntityType::Thinking],
             (0.0, 0.0),
-        );
+        )?;
         let id_2 = knowledge_graph.add_entity(
             "典型的计算思维".to_string(),
             DistinctEntityType::KnowledgePoint,
@@ -475,7 +1629,7 @@ mod tests {
                 AddonEntityType::Question,
             ],
             (1.0, 1.0),
-        );
+        )?;
         let id_3 = knowledge_graph.add_entity(
             "小白鼠检验毒水瓶问题,怎样求解？".to_string(),
             DistinctEntityType::KnowledgeDetail,
@@ -485,7 +1639,7 @@ mod tests {
                 AddonEntityType::Question,
             ],
             (2.0, 2.0),
-        );
+        )?;
         let id_4 = knowledge_graph.add_entity(
             "水瓶编号：由十进制编号到二进制编号".to_string(),
             DistinctEntityType::KnowledgeDetail,
@@ -495,7 +1649,7 @@ mod tests {
                 AddonEntityType::Thinking,
             ],
             (3.0, 3.0),
-        );
+        )?;
         knowledge_graph.add_edge(id_1, id_2, Relation::Contain)?;
         knowledge_graph.add_edge(id_1, id_3, Relation::Contain)?;
         knowledge_graph.add_edge(id_3, id_4, Relation::Order)?;
@@ -512,9 +1666,19 @@ mod tests {
             .replace(['\n', ' '], "");
 
         // 检查 XML 结构是否正确
-        let pattern = r"^<KG>&#25945;&#23398;&#30693;&#35782;&#22270;&#35889;<entities>(?:<entity>.*?</entity>)+</entities><relations>(?:<relation>.*?</relation>)+</relations></KG>$";
+        let pattern = r#"^<\?xmlversion="1\.0"encoding="UTF-8"\?><KG>&#25945;&#23398;&#30693;&#35782;&#22270;&#35889;<entities>(?:<entity>.*?</entity>)+</entities><relations>(?:<relation>.*?</relation>)+</relations></KG>$"#;
         assert!(regex::Regex::new(pattern)?.is_match(&xml));
 
+        // 声明可以关闭，有无声明都能读取
+        let snapshot = knowledge_graph.current_snapshot();
+        let bare = snapshot.to_xml_with(XmlOptions {
+            declaration: false,
+            ..Default::default()
+        })?;
+        assert!(bare.starts_with("<KG>"));
+        assert_eq!(*snapshot, Snapshot::from_xml(&bare)?);
+        assert_eq!(*snapshot, Snapshot::from_xml(&snapshot.to_xml()?)?);
+
         // 检查 XML 内容是否正确
         assert!(xml.contains("<entity><id>1</id><class_name>&#30693;&#35782;&#39046;&#22495;</class_name><classification>&#20869;&#23481;&#26041;&#27861;&#22411;&#33410;&#28857;</classification><identity>&#30693;&#35782;</identity><level>&#19968;&#32423;</level><attach>100000</attach><opentool>&#26080;</opentool><content>&#20160;&#20040;&#26159;&#35745;&#31639;&#24605;&#32500;</content><x>0</x><y>0</y></entity>"));
         assert!(xml.contains("<entity><id>2</id><class_name>&#30693;&#35782;&#28857;</class_name><classification>&#20869;&#23481;&#26041;&#27861;&#22411;&#33410;&#28857;</classification><identity>&#30693;&#35782;</identity><level>&#24402;&#32435;&#32423;</level><attach>101010</attach><opentool>&#26080;</opentool><content>&#20856;&#22411;&#30340;&#35745;&#31639;&#24605;&#32500;</content><x>1</x><y>1</y></entity>"));
@@ -522,7 +1686,861 @@ mod tests {
         assert!(xml.contains("<entity><id>4</id><class_name>&#20851;&#38190;&#30693;&#35782;&#32454;&#33410;</class_name><classification>&#20869;&#23481;&#26041;&#27861;&#22411;&#33410;&#28857;</classification><identity>&#30693;&#35782;</identity><level>&#20869;&#23481;&#32423;</level><attach>100011</attach><opentool>&#26080;</opentool><content>&#27700;&#29942;&#32534;&#21495;&#65306;&#30001;&#21313;&#36827;&#21046;&#32534;&#21495;&#21040;&#20108;&#36827;&#21046;&#32534;&#21495;</content><x>3</x><y>3</y></entity>"));
         assert!(xml.contains("<relation><name>&#21253;&#21547;</name><headnodeid>1</headnodeid><tailnodeid>2</tailnodeid><class_name>&#21253;&#21547;&#20851;&#31995;</class_name><mask>&#30693;&#35782;&#36830;&#32447;</mask><classification>&#21253;&#21547;&#20851;&#31995;</classification><head_need>&#20869;&#23481;&#26041;&#27861;&#22411;&#33410;&#28857;</head_need><tail_need>&#20869;&#23481;&#26041;&#27861;&#22411;&#33410;&#28857;</tail_need></relation>"));
         assert!(xml.contains("<relation><name>&#21253;&#21547;</name><headnodeid>1</headnodeid><tailnodeid>3</tailnodeid><class_name>&#21253;&#21547;&#20851;&#31995;</class_name><mask>&#30693;&#35782;&#36830;&#32447;</mask><classification>&#21253;&#21547;&#20851;&#31995;</classification><head_need>&#20869;&#23481;&#26041;&#27861;&#22411;&#33410;&#28857;</head_need><tail_need>&#20869;&#23481;&#26041;&#27861;&#22411;&#33410;&#28857;</tail_need></relation>"));
-        assert!(xml.contains("<relation><name>&#21253;&#21547;</name><headnodeid>3</headnodeid><tailnodeid>4</tailnodeid><class_name>&#27425;&#24207;&#20851;&#31995;</class_name><mask>&#30693;&#35782;&#36830;&#32447;</mask><classification>&#27425;&#24207;&#20851;&#31995;</classification><head_need>&#20869;&#23481;&#26041;&#27861;&#22411;&#33410;&#28857;</head_need><tail_need>&#20869;&#23481;&#26041;&#27861;&#22411;&#33410;&#28857;</tail_need></relation>"));
+        assert!(xml.contains("<relation><name>&#27425;&#24207;</name><headnodeid>3</headnodeid><tailnodeid>4</tailnodeid><class_name>&#27425;&#24207;&#20851;&#31995;</class_name><mask>&#30693;&#35782;&#36830;&#32447;</mask><classification>&#27425;&#24207;&#20851;&#31995;</classification><head_need>&#20869;&#23481;&#26041;&#27861;&#22411;&#33410;&#28857;</head_need><tail_need>&#20869;&#23481;&#26041;&#27861;&#22411;&#33410;&#28857;</tail_need></relation>"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_notes_round_trip() -> Result<(), Box<dyn std::error::Error>> {
+        let mut knowledge_graph = create_knowledge_graph()?;
+        knowledge_graph.update_entity_with(1, |node| node.notes = "备注 <1>".to_string())?;
+
+        let snapshot = knowledge_graph.current_snapshot();
+        let xml = snapshot.to_xml()?;
+        assert_eq!(xml.matches("<notes>").count(), 1); // 空备注不输出
+
+        let snapshot_decoded = Snapshot::from_xml(&xml)?;
+        assert_eq!(*snapshot, snapshot_decoded);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_links_round_trip() -> Result<(), Box<dyn std::error::Error>> {
+        let mut knowledge_graph = create_knowledge_graph()?;
+        let links = vec![
+            "https://www.icourse163.org/course/PKU-1001?a=1&b=2".to_string(),
+            r"C:\课件\第一章.pptx".to_string(),
+        ];
+        knowledge_graph.update_entity_with(1, |node| node.links = links.clone())?;
+
+        // 每个链接为一个 link 元素，没有链接时不输出
+        let snapshot = knowledge_graph.current_snapshot();
+        let xml = snapshot.to_xml()?;
+        assert_eq!(xml.matches("<link>").count(), 2);
+        let decoded = Snapshot::from_xml(&xml)?;
+        assert_eq!(decoded.nodes[&1].links, links);
+        assert_eq!(*snapshot, decoded);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_flag_round_trip() -> Result<(), Box<dyn std::error::Error>> {
+        let mut knowledge_graph = create_knowledge_graph()?;
+        knowledge_graph.update_entity_with(1, |node| node.flag = Some(Flag::Question))?;
+
+        // 有标记时输出 flag 元素，没有时不输出
+        let snapshot = knowledge_graph.current_snapshot();
+        let xml = snapshot.to_xml()?;
+        assert_eq!(xml.matches("<flag>question</flag>").count(), 1);
+        let decoded = Snapshot::from_xml(&xml)?;
+        assert_eq!(decoded.nodes[&1].flag, Some(Flag::Question));
+        assert_eq!(*snapshot, decoded);
+
+        // 无法识别的标记被忽略并给出警告，不影响读取图谱
+        let xml = xml.replace("<flag>question</flag>", "<flag>later</flag>");
+        let (decoded, warnings) = Snapshot::from_xml_with_policy(&xml, LevelPolicy::default())?;
+        assert_eq!(decoded.nodes[&1].flag, None);
+        assert_eq!(decoded.nodes.len(), snapshot.nodes.len());
+        assert_eq!(warnings, ["节点 1 的评审标记“later”无法识别，已忽略"]);
+        let (decoded, warnings) = Snapshot::from_xml_lenient(&xml)?;
+        assert_eq!(decoded.nodes[&1].flag, None);
+        assert_eq!(warnings.len(), 1);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_primary_parent_round_trip() -> Result<(), Box<dyn std::error::Error>> {
+        let mut knowledge_graph = create_knowledge_graph()?;
+        knowledge_graph.add_edge(2, 4, Relation::Contain)?;
+        knowledge_graph.set_primary_parent(4, 2)?;
+
+        // 记录了主父节点时输出 primary_parent 元素，没有时不输出
+        let snapshot = knowledge_graph.current_snapshot();
+        let xml = snapshot.to_xml()?;
+        assert_eq!(xml.matches("<primary_parent>2</primary_parent>").count(), 1);
+        let decoded = Snapshot::from_xml(&xml)?;
+        assert_eq!(decoded.primary_parent(4), Some(2));
+        assert_eq!(*snapshot, decoded);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_reference_nodes_not_exported() -> Result<(), Box<dyn std::error::Error>> {
+        let mut knowledge_graph = create_knowledge_graph()?;
+        knowledge_graph.update_entity_with(4, |node| node.reference = true)?;
+
+        // 保存的 XML 保留标记
+        let snapshot = knowledge_graph.current_snapshot();
+        let xml = snapshot.to_xml()?;
+        assert_eq!(xml.matches("<reference>true</reference>").count(), 1);
+        assert_eq!(Snapshot::from_xml(&xml)?, *snapshot);
+
+        // 导出时跳过节点及其边
+        let exported = Snapshot::from_xml(&snapshot.to_compatible_xml()?)?;
+        assert!(!exported.nodes.contains_key(&4));
+        assert!(
+            exported
+                .edges
+                .keys()
+                .all(|(from, to)| *from != 4 && *to != 4)
+        );
+        assert_eq!(exported.nodes.len(), snapshot.nodes.len() - 1);
+        assert!(!snapshot.to_plantuml().contains("水瓶编号"));
+        assert!(!snapshot.to_gexf().contains(r#"<node id="4""#));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_relation_round_trip() -> Result<(), Box<dyn std::error::Error>> {
+        let mut knowledge_graph = create_knowledge_graph()?;
+        knowledge_graph.add_edge(2, 4, Relation::Extension)?;
+
+        let snapshot = knowledge_graph.current_snapshot();
+        let xml = snapshot.to_xml()?;
+        assert_eq!(Snapshot::from_xml(&xml)?, *snapshot);
+
+        // 旧版平台导出的带前缀的次序关系仍可读取
+        let edge = SerializableEdge {
+            class_name: "次序：次序关系".to_string(),
+            ..SerializableEdge::from_edge(1, 2, Relation::Order, None)
+        };
+        assert_eq!(edge.to_edge()?, (1, 2, Relation::Order));
+
+        let edge = SerializableEdge {
+            class_name: "前驱关系".to_string(),
+            ..SerializableEdge::from_edge(1, 2, Relation::Order, None)
+        };
+        assert!(edge.to_edge().is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_edge_label_round_trip() -> Result<(), Box<dyn std::error::Error>> {
+        let mut knowledge_graph = create_knowledge_graph()?;
+        knowledge_graph.set_edge_label(1, 2, "前置知识".to_string())?;
+
+        let snapshot = knowledge_graph.current_snapshot();
+        let decoded = Snapshot::from_xml(&snapshot.to_xml()?)?;
+        assert_eq!(decoded, *snapshot);
+        assert_eq!(decoded.edge_labels.len(), 1);
+
+        // 对接平台的格式按关系填写 name，不保留标签
+        let decoded = Snapshot::from_xml(&snapshot.to_compatible_xml()?)?;
+        assert!(decoded.edge_labels.is_empty());
+
+        // 旧版本对次序关系也写入“包含”，不应读作标签
+        let edge = SerializableEdge {
+            name: "包含".to_string(),
+            ..SerializableEdge::from_edge(1, 2, Relation::Order, None)
+        };
+        assert_eq!(edge.label(), None);
+        let edge = SerializableEdge::from_edge(1, 2, Relation::Order, Some(" 前置知识 "));
+        assert_eq!(edge.label().as_deref(), Some("前置知识"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_edge_waypoints_round_trip() -> Result<(), Box<dyn std::error::Error>> {
+        let mut knowledge_graph = create_knowledge_graph()?;
+        knowledge_graph.set_edge_waypoints(1, 2, vec![(10.5, -20.0), (30.0, 40.0)])?;
+
+        let snapshot = knowledge_graph.current_snapshot();
+        let xml = snapshot.to_xml()?;
+        assert_eq!(
+            xml.matches("<waypoints>10.5,-20 30,40</waypoints>").count(),
+            1
+        );
+        let decoded = Snapshot::from_xml(&xml)?;
+        assert_eq!(decoded, *snapshot);
+
+        // 对接平台的格式不保留折点
+        let compatible = snapshot.to_compatible_xml()?;
+        assert!(!compatible.contains("<waypoints>"));
+        assert!(Snapshot::from_xml(&compatible)?.edge_waypoints.is_empty());
+
+        // 无法识别的折点在严格模式下报错，宽容模式下忽略并给出警告
+        let xml = xml.replace("10.5,-20 30,40", "10.5,-20 30");
+        assert!(Snapshot::from_xml(&xml).is_err());
+        let (decoded, warnings) = Snapshot::from_xml_lenient(&xml)?;
+        assert!(decoded.edge_waypoints.is_empty());
+        assert_eq!(decoded.edges.len(), snapshot.edges.len());
+        assert_eq!(warnings.len(), 1);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_custom_relation_round_trip() -> Result<(), Box<dyn std::error::Error>> {
+        // 关系类型表在进程内共享，测试结束时恢复
+        let _registry =
+            crate::graph::relation::ScopedRegistry::new(crate::graph::RelationRegistry::from_json(
+                r#"[{ "id": 3, "name": "类比", "class_name": "类比关系" }]"#,
+            )?);
+        let mut knowledge_graph = create_knowledge_graph()?;
+        knowledge_graph.update_edge(1, 2, Relation::Custom(3))?;
+
+        // 关系名不会被当作标签
+        let snapshot = knowledge_graph.current_snapshot();
+        let decoded = Snapshot::from_xml(&snapshot.to_xml()?)?;
+        assert_eq!(decoded.edges[&(1, 2)], Relation::Custom(3));
+        assert!(decoded.edge_labels.is_empty());
+
+        // 关系类型表中没有的关系无法读取
+        knowledge_graph.update_edge(1, 2, Relation::Custom(9))?;
+        let xml = knowledge_graph.current_snapshot().to_xml()?;
+        assert!(Snapshot::from_xml(&xml).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_special_characters_round_trip() -> Result<(), Box<dyn std::error::Error>> {
+        let mut knowledge_graph = create_knowledge_graph()?;
+        let content = "a<b> & \"c\" 'd' 中";
+        knowledge_graph.update_entity_with(1, |node| node.content = content.to_string())?;
+
+        // 保存时转义特殊符号
+        let snapshot = knowledge_graph.current_snapshot();
+        let xml = snapshot.to_xml()?;
+        assert!(xml.contains("<content>a&lt;b&gt; &amp; \"c\" 'd' &#20013;</content>"));
+        assert_eq!(*snapshot, Snapshot::from_xml(&xml)?);
+
+        // 对接系统写出的 CDATA 段和命名实体
+        for written in [
+            "<content><![CDATA[a<b> & \"c\" 'd' 中]]></content>",
+            "<content>a&lt;b&gt; &amp; &quot;c&quot; &apos;d&apos; &#x4E2D;</content>",
+            "<content><![CDATA[a<b>]]> &amp; &quot;c&quot; 'd' &#20013;</content>",
+        ] {
+            let xml = xml.replace(
+                "<content>a&lt;b&gt; &amp; \"c\" 'd' &#20013;</content>",
+                written,
+            );
+            assert_eq!(Snapshot::from_xml(&xml)?.nodes[&1].content, content);
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_entity_reference_round_trip() -> Result<(), Box<dyn std::error::Error>> {
+        // 内容本身含有实体引用的写法时，保存和读取都只转义一层
+        let contents = [
+            "&",
+            "&#123;",
+            "&#20013; 表示“中”",
+            "&amp;",
+            "&amp;#20013;",
+            "<tag>",
+            "&lt;tag&gt;",
+            "Rust 🦀 &#x1F980;",
+            "]]> &",
+            "第一行\n&#10;第二行\t\"引号\"",
+        ];
+        let mut knowledge_graph = create_knowledge_graph()?;
+        let group = knowledge_graph.add_group(
+            String::new(),
+            CoordinateBounds {
+                min: (0.0, 0.0),
+                max: (100.0, 100.0),
+            },
+            DEFAULT_GROUP_COLOR,
+            &[],
+        )?;
+        for options in [
+            XmlOptions::default(),
+            XmlOptions {
+                escape_non_ascii: false,
+                ..Default::default()
+            },
+        ] {
+            for content in contents {
+                knowledge_graph.update_entity_with(1, |node| {
+                    node.content = content.to_string();
+                    node.notes = content.to_string();
+                })?;
+                knowledge_graph.set_edge_label(1, 2, content.to_string())?;
+                // 书签名和分组框标签保存在属性中
+                knowledge_graph.set_metadata(GraphMetadata {
+                    bookmarks: vec![Bookmark {
+                        name: content.to_string(),
+                        scroll_offset: (0.0, 0.0),
+                        zoom: 1.0,
+                    }],
+                    ..Default::default()
+                });
+                let mut frame = knowledge_graph.current.groups[0].clone();
+                assert_eq!(frame.id, group);
+                frame.label = content.to_string();
+                knowledge_graph.update_group(frame)?;
+                let snapshot = knowledge_graph.current_snapshot();
+                let xml = snapshot.to_xml_with(options)?;
+                assert_eq!(Snapshot::from_xml(&xml)?, *snapshot, "{content}");
+                assert_eq!(Snapshot::from_xml_lenient(&xml)?.0, *snapshot, "{content}");
+
+                // 再次保存得到相同的文本
+                assert_eq!(Snapshot::from_xml(&xml)?.to_xml_with(options)?, xml);
+            }
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_to_plantuml() -> Result<(), Box<dyn std::error::Error>> {
+        let mut knowledge_graph = create_knowledge_graph()?;
+
+        // 次序关系不体现在层级中，因此有多个根节点，此时添加总的根节点
+        assert_eq!(
+            knowledge_graph.current.to_plantuml(),
+            "@startmindmap\n\
+             * 教学知识图谱\n\
+             ** 什么是计算思维\n\
+             *** 典型的计算思维\n\
+             *** 小白鼠检验毒水瓶问题,怎样求解？\n\
+             ** 水瓶编号：由十进制编号到二进制编号\n\
+             @endmindmap\n"
+        );
+
+        // 只有一个根节点，多行内容使用 :...; 语法
+        knowledge_graph.update_edge(3, 4, Relation::Contain)?;
+        knowledge_graph
+            .update_entity_with(4, |node| node.content = "第一行\n第二行".to_string())?;
+        assert_eq!(
+            knowledge_graph.current.to_plantuml(),
+            "@startmindmap\n\
+             * 什么是计算思维\n\
+             ** 典型的计算思维\n\
+             ** 小白鼠检验毒水瓶问题,怎样求解？\n\
+             ***:第一行\n第二行;\n\
+             @endmindmap\n"
+        );
+
+        // 有多个根节点时以图谱标题作为总的根节点
+        knowledge_graph.remove_edge(3, 4)?;
+        knowledge_graph.current.title = "计算思维".to_string();
+        assert!(
+            knowledge_graph
+                .current
+                .to_plantuml()
+                .starts_with("@startmindmap\n* 计算思维\n** 什么是计算思维\n")
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_children_order() -> Result<(), Box<dyn std::error::Error>> {
+        let mut knowledge_graph = create_knowledge_graph()?;
+        knowledge_graph.set_children_order(1, &[3, 2])?;
+        let xml = knowledge_graph.current.to_xml_with(XmlOptions {
+            escape_non_ascii: false,
+            ..Default::default()
+        })?;
+
+        // 有次序的节点按次序排在前面，并保存次序
+        let positions = [3, 2, 1, 4].map(|id| xml.find(&format!("<id>{id}</id>")).unwrap());
+        assert!(positions.is_sorted());
+        assert!(xml.contains("<order_index>0</order_index>"));
+
+        // 思维导图中兄弟节点的顺序与保存的顺序一致
+        let plantuml = knowledge_graph.current.to_plantuml();
+        let content = |id: u64| knowledge_graph.current.nodes[&id].content.clone();
+        assert!(plantuml.find(&content(3)).unwrap() < plantuml.find(&content(2)).unwrap());
+
+        let snapshot = Snapshot::from_xml(&xml)?;
+        assert_eq!(&snapshot, knowledge_graph.current_snapshot());
+        assert_eq!(snapshot.children(1), vec![3, 2]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_export_checks() -> Result<(), Box<dyn std::error::Error>> {
+        let knowledge_graph = create_knowledge_graph()?;
+        let snapshot = knowledge_graph.current_snapshot();
+        assert!(export_checks(snapshot).is_empty());
+        // 严格检查还包括图谱校验的警告，示例节点互相重叠
+        let issues = snapshot.strict_issues();
+        assert_eq!(issues.len(), 1);
+        assert_eq!(issues[0].level, IssueLevel::Warning);
+
+        // ID 为 0、ID 重复、边引用不存在的节点
+        let mut broken = snapshot.clone();
+        let mut node = broken.nodes[&1].clone();
+        node.id = 0;
+        broken.nodes.insert(100, node);
+        let mut node = broken.nodes[&2].clone();
+        node.id = 3;
+        broken.nodes.insert(2, node);
+        broken.edges.insert((3, 200), Relation::Order);
+        let messages = export_checks(&broken)
+            .into_iter()
+            .map(|issue| issue.message)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            messages,
+            [
+                "节点 ID 必须为正整数",
+                "节点 ID 3 重复",
+                "边 (1, 2) 引用了不存在的节点",
+                "边 (3, 200) 引用了不存在的节点",
+            ]
+        );
+
+        // 标题为空、类型和关系名不被接受
+        let mut serializable = SerializableSnapshot::from(snapshot);
+        serializable.title.clear();
+        serializable.entities.entities[0].class_name = "资源".to_string();
+        serializable.relations.items[0].class_name = "依赖关系".to_string();
+        let issues = serializable.export_checks();
+        assert_eq!(issues.len(), 3);
+        assert!(issues.iter().all(|issue| issue.level == IssueLevel::Error));
+        assert_eq!(issues[1].entities, vec![1]);
+        assert_eq!(issues[2].entities, vec![1, 2]);
+
+        // ID 超出 32 位整数
+        let mut broken = snapshot.clone();
+        let mut node = broken.nodes[&4].clone();
+        node.id = 1 << 31;
+        broken.nodes.insert(1 << 31, node);
+        let issues = export_checks(&broken);
+        assert_eq!(issues.len(), 1);
+        assert_eq!(issues[0].entities, vec![1 << 31]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_to_compatible_xml() -> Result<(), Box<dyn std::error::Error>> {
+        let mut knowledge_graph = create_knowledge_graph()?;
+        knowledge_graph.update_entity_with(2, |node| {
+            node.notes = "备注".to_string();
+            node.order_index = Some(0);
+            node.links = vec!["https://example.com".to_string()];
+            node.flag = Some(Flag::Todo);
+            node.primary_parent = Some(1);
+        })?;
+        knowledge_graph.set_metadata(GraphMetadata {
+            max_nodes: Some(100),
+            ..Default::default()
+        });
+        let snapshot = knowledge_graph.current_snapshot();
+
+        // 不含扩展元素，次序关系的 name 为“次序”，读回后除扩展内容外与原图谱一致
+        let xml = snapshot.to_compatible_xml()?;
+        for extension in [
+            "<notes>",
+            "<order_index>",
+            "<link>",
+            "<flag>",
+            "<primary_parent>",
+            "<metadata>",
+        ] {
+            assert!(!xml.contains(extension), "{extension}");
+        }
+        assert!(xml.contains("<name>&#27425;&#24207;</name>"));
+        let decoded = Snapshot::from_xml(&xml)?;
+        assert_eq!(decoded.nodes.len(), snapshot.nodes.len());
+        assert_eq!(decoded.edges, snapshot.edges);
+        assert!(decoded.nodes.values().all(|node| node.notes.is_empty()));
+        assert!(decoded.metadata.is_default());
+
+        // 不满足格式要求时报错并指出问题
+        let mut broken = snapshot.clone();
+        let mut node = broken.nodes[&4].clone();
+        node.id = 1 << 31;
+        broken.nodes.insert(1 << 31, node);
+        match broken.to_compatible_xml() {
+            Err(SerdeError::Incompatible(problems)) => {
+                assert_eq!(problems.len(), 1);
+                assert!(problems[0].contains("2147483648"));
+            }
+            other => panic!("unexpected {other:?}"),
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_bytes_round_trip() -> Result<(), Box<dyn std::error::Error>> {
+        let mut knowledge_graph = create_knowledge_graph()?;
+        knowledge_graph.update_entity_with(2, |node| node.notes = "备注".to_string())?;
+        knowledge_graph.set_children_order(1, &[3, 2])?;
+        let snapshot = knowledge_graph.current_snapshot();
+
+        let bytes = snapshot.to_bytes()?;
+        assert_eq!(&Snapshot::from_bytes(&bytes)?, snapshot);
+        assert!(Snapshot::from_bytes(&bytes[..bytes.len() / 2]).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_to_gexf() -> Result<(), Box<dyn std::error::Error>> {
+        let mut knowledge_graph = create_knowledge_graph()?;
+        knowledge_graph.update_entity_with(2, |node| node.content = "<甲>\n乙".to_string())?;
+        let gexf = knowledge_graph.current.to_gexf();
+
+        // 可被 XML 解析器完整读取
+        let mut reader = Reader::from_str(&gexf);
+        let (mut nodes, mut edges) = (0, 0);
+        loop {
+            match reader.read_event()? {
+                Event::Start(e) if e.name().as_ref() == b"node" => nodes += 1,
+                Event::Start(e) if e.name().as_ref() == b"edge" => edges += 1,
+                Event::Eof => break,
+                _ => {}
+            }
+        }
+        assert_eq!(nodes, knowledge_graph.current.nodes.len());
+        assert_eq!(edges, knowledge_graph.current.edges.len());
+
+        assert!(gexf.contains(r#"<node id="2" label="&lt;甲&gt;&#10;乙">"#));
+        assert!(gexf.contains("<description>教学知识图谱</description>"));
+        knowledge_graph.current.title = "数据结构 & 算法".to_string();
+        assert!(
+            knowledge_graph
+                .current
+                .to_gexf()
+                .contains("<description>数据结构 &amp; 算法</description>")
+        );
+        let node = &knowledge_graph.current.nodes[&1];
+        assert!(gexf.contains(&format!(
+            r#"<viz:position x="{}" y="{}" z="0"/>"#,
+            node.coor.0, node.coor.1
+        )));
+        assert!(gexf.contains(r#"<attvalue for="relation" value="包含关系"/>"#));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_to_text_tree() -> Result<(), Box<dyn std::error::Error>> {
+        let mut knowledge_graph = create_knowledge_graph()?;
+
+        // 节点 4 只有次序关系，是孤立节点
+        assert_eq!(
+            knowledge_graph.current.to_text_tree(),
+            "什么是计算思维\n  \
+             典型的计算思维\n  \
+             小白鼠检验毒水瓶问题,怎样求解？\n\
+             \n\
+             水瓶编号：由十进制编号到二进制编号\n"
+        );
+
+        // 子节点按设置的次序排列，多行内容合并为一行
+        knowledge_graph.update_edge(3, 4, Relation::Contain)?;
+        knowledge_graph.set_children_order(1, &[3, 2])?;
+        knowledge_graph
+            .update_entity_with(4, |node| node.content = "第一行\n第二行".to_string())?;
+        assert_eq!(
+            knowledge_graph.current.to_text_tree_with(true),
+            "1. 什么是计算思维\n  \
+             1. 小白鼠检验毒水瓶问题,怎样求解？\n    \
+             1. 第一行 第二行\n  \
+             2. 典型的计算思维\n"
+        );
+
+        // 包含关系成环时每个节点只列出一次
+        knowledge_graph.add_edge(4, 1, Relation::Contain)?;
+        let tree = knowledge_graph.current.to_text_tree();
+        assert_eq!(tree.lines().count(), 4);
+        assert!(tree.starts_with("什么是计算思维\n"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_to_anki_tsv() -> Result<(), Box<dyn std::error::Error>> {
+        let mut knowledge_graph = create_knowledge_graph()?;
+
+        // 节点 2 以子节点为背面，节点 4 没有子节点时以备注为背面，节点 3 两者都没有
+        let id = knowledge_graph.add_entity(
+            "<b>计算思维</b>\n的特征".to_string(),
+            DistinctEntityType::KnowledgeDetail,
+            &[],
+            (4.0, 4.0),
+        )?;
+        knowledge_graph.add_edge(2, id, Relation::Contain)?;
+        knowledge_graph.update_entity_with(4, |node| node.notes = "按位\t编号".to_string())?;
+        assert_eq!(
+            knowledge_graph.current.to_anki_tsv(),
+            "#separator:tab\n#html:true\n#tags column:3\n\
+             典型的计算思维\t&lt;b&gt;计算思维&lt;/b&gt;<br>的特征\t问题\n\
+             水瓶编号：由十进制编号到二进制编号\t按位 编号\t练习\n"
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_author_variations() -> Result<(), Box<dyn std::error::Error>> {
+        let mut knowledge_graph = create_knowledge_graph()?;
+        knowledge_graph.update_entity_with(2, |node| {
+            node.content = "多行\n内容\r与 <符号> &".to_string()
+        })?;
+        // 首尾的空白和只含空白的文本
+        knowledge_graph.update_entity_with(3, |node| {
+            node.content = "\t 首尾空白\r\n".to_string();
+            node.notes = " \r".to_string();
+        })?;
+        let snapshot = knowledge_graph.current_snapshot();
+
+        // 手动修改的空白：去掉标记之间的空白、改用制表符缩进、首尾添加空行
+        let manglings: [fn(&str) -> String; 4] = [
+            |xml| xml.to_string(),
+            |xml| {
+                regex::Regex::new(r">\s+<")
+                    .unwrap()
+                    .replace_all(xml, "><")
+                    .to_string()
+            },
+            |xml| xml.replace("    ", "\t"),
+            |xml| format!("\n\n{xml}\n  \n"),
+        ];
+
+        for bits in 0..16 {
+            let options = XmlOptions {
+                indent: bits & 1 != 0,
+                escape_non_ascii: bits & 2 != 0,
+                declaration: bits & 4 != 0,
+                crlf: bits & 8 != 0,
+            };
+            let xml = snapshot.to_xml_with(options)?;
+            for mangle in manglings {
+                let xml = mangle(&xml);
+
+                // UTF-8、带 BOM 的 UTF-8 和 UTF-16
+                let mut with_bom = vec![0xEF, 0xBB, 0xBF];
+                with_bom.extend_from_slice(xml.as_bytes());
+                let mut utf16 = vec![0xFF, 0xFE];
+                utf16.extend(xml.encode_utf16().flat_map(u16::to_le_bytes));
+
+                for bytes in [xml.as_bytes().to_vec(), with_bom, utf16] {
+                    let decoded = Snapshot::from_xml(&decode_xml(&bytes)?)?;
+                    assert_eq!(*snapshot, decoded, "{options:?}\n{xml}");
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_missing_derived_fields() -> Result<(), Box<dyn std::error::Error>> {
+        let knowledge_graph = create_knowledge_graph()?;
+        let snapshot = knowledge_graph.current_snapshot();
+
+        // 去掉可由类型推出的字段和标题后仍可读取
+        let xml = snapshot.to_xml_with(XmlOptions {
+            escape_non_ascii: false,
+            ..Default::default()
+        })?;
+        let xml = regex::Regex::new(
+            r"\s*<(classification|identity|level|opentool|name|mask|head_need|tail_need)>[^<]*</\w+>",
+        )?
+        .replace_all(&xml, "")
+        .replace("教学知识图谱", "");
+        assert!(!xml.contains("<opentool>"));
+        assert_eq!(*snapshot, Snapshot::from_xml(&xml)?);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_from_xml_lenient() -> Result<(), Box<dyn std::error::Error>> {
+        let knowledge_graph = create_knowledge_graph()?;
+        let snapshot = knowledge_graph.current_snapshot();
+        let xml = snapshot.to_xml_with(XmlOptions {
+            escape_non_ascii: false,
+            ..Default::default()
+        })?;
+
+        // 没有问题时与严格模式一致
+        let (decoded, warnings) = Snapshot::from_xml_lenient(&xml)?;
+        assert_eq!(*snapshot, decoded);
+        assert!(warnings.is_empty());
+
+        // 无法识别的节点及引用它的边被忽略，其余内容保留
+        let xml = xml.replacen(
+            "<class_name>知识领域</class_name>",
+            "<class_name>未知</class_name>",
+            1,
+        );
+        assert!(Snapshot::from_xml(&xml).is_err());
+        let (decoded, warnings) = Snapshot::from_xml_lenient(&xml)?;
+        assert_eq!(decoded.nodes.len(), snapshot.nodes.len() - 1);
+        assert!(!decoded.nodes.contains_key(&1));
+        assert!(decoded.edges.keys().all(|(from, _)| *from != 1));
+        assert_eq!(warnings.len(), 3); // 一个节点和两条边
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_level_policy() -> Result<(), Box<dyn std::error::Error>> {
+        let knowledge_graph = create_knowledge_graph()?;
+        let snapshot = knowledge_graph.current_snapshot();
+        let xml = snapshot
+            .to_xml_with(XmlOptions {
+                escape_non_ascii: false,
+                ..Default::default()
+            })?
+            .replacen("<level>一级</level>", "<level>二级</level>", 1);
+
+        // 默认以 class_name 为准，并给出警告
+        assert_eq!(*snapshot, Snapshot::from_xml(&xml)?);
+        let (decoded, warnings) = Snapshot::from_xml_with_policy(&xml, LevelPolicy::ClassName)?;
+        assert_eq!(*snapshot, decoded);
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].contains("节点 1"));
+
+        // 以 level 为准
+        let (decoded, _) = Snapshot::from_xml_with_policy(&xml, LevelPolicy::Level)?;
+        assert_eq!(
+            decoded.nodes[&1].distinct_type,
+            DistinctEntityType::KnowledgeUnit
+        );
+
+        // 拒绝不一致的数据
+        assert!(Snapshot::from_xml_with_policy(&xml, LevelPolicy::Reject).is_err());
+
+        // 宽容模式同样给出警告
+        let (_, warnings) = Snapshot::from_xml_lenient(&xml)?;
+        assert_eq!(warnings.len(), 1);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_long_content() -> Result<(), Box<dyn std::error::Error>> {
+        let content = "知识图谱<&>".repeat(2000);
+        let mut knowledge_graph = KnowledgeGraph::default();
+        let long = knowledge_graph.add_entity(
+            content.clone(),
+            DistinctEntityType::KnowledgePoint,
+            &[],
+            (0.0, 0.0),
+        )?;
+        let short = knowledge_graph.add_entity(
+            "短".to_string(),
+            DistinctEntityType::KnowledgePoint,
+            &[],
+            (200.0, 0.0),
+        )?;
+        knowledge_graph.update_entity_with(long, |node| node.notes = "原有备注".to_string())?;
+
+        // 超长内容可以完整往返
+        let xml = knowledge_graph.current_snapshot().to_xml()?;
+        let decoded = Snapshot::from_xml(&xml)?;
+        assert_eq!(decoded.nodes[&long].content, content);
+        assert_eq!(*knowledge_graph.current_snapshot(), decoded);
+
+        // 按上限读取时超出部分移到备注开头，其余节点不变
+        let (decoded, warnings) = Snapshot::from_xml_with_content_limit(&xml, 500)?;
+        let node = &decoded.nodes[&long];
+        assert_eq!(node.content.chars().count(), 500);
+        assert_eq!(
+            format!("{}{}", node.content, node.notes),
+            format!("{content}\n原有备注")
+        );
+        assert_eq!(decoded.nodes[&short].content, "短");
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].contains(&long.to_string()));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_metadata_round_trip() -> Result<(), Box<dyn std::error::Error>> {
+        let mut knowledge_graph = create_knowledge_graph()?;
+
+        // 默认元数据不输出
+        assert!(!knowledge_graph.current.to_xml()?.contains("<metadata>"));
+
+        knowledge_graph.set_metadata(GraphMetadata {
+            max_nodes: Some(500),
+            ..Default::default()
+        });
+        let snapshot = knowledge_graph.current_snapshot();
+        let xml = snapshot.to_xml()?;
+        assert!(xml.contains("<max_nodes>500</max_nodes>"));
+        assert!(!xml.contains("<max_edges>"));
+        assert!(!xml.contains("<bookmark"));
+
+        let snapshot_decoded = Snapshot::from_xml(&xml)?;
+        assert_eq!(*snapshot, snapshot_decoded);
+
+        // 书签按顺序保存
+        knowledge_graph.set_metadata(GraphMetadata {
+            bookmarks: vec![
+                Bookmark {
+                    name: "概述".to_string(),
+                    scroll_offset: (120.0, 40.5),
+                    zoom: 1.5,
+                },
+                Bookmark {
+                    name: "实践 & 案例".to_string(),
+                    scroll_offset: (0.0, 800.0),
+                    zoom: 0.75,
+                },
+            ],
+            coordinate_bounds: Some(CoordinateBounds {
+                min: (-100.0, 0.0),
+                max: (8000.0, 6000.5),
+            }),
+            ..Default::default()
+        });
+        let snapshot = knowledge_graph.current_snapshot();
+        let xml = snapshot.to_xml()?;
+        assert!(xml.contains("<metadata>"));
+        assert!(!xml.contains("<max_nodes>"));
+
+        let snapshot_decoded = Snapshot::from_xml(&xml)?;
+        assert_eq!(*snapshot, snapshot_decoded);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_groups_round_trip() -> Result<(), Box<dyn std::error::Error>> {
+        let mut knowledge_graph = create_knowledge_graph()?;
+
+        // 只有分组框时也输出扩展元素
+        let rect = CoordinateBounds {
+            min: (-10.0, 0.0),
+            max: (400.5, 300.0),
+        };
+        knowledge_graph.add_group("第3周 & 实验".to_string(), rect, [0x12, 0xab, 0xef], &[])?;
+        knowledge_graph.add_group(String::new(), rect, DEFAULT_GROUP_COLOR, &[1, 3])?;
+        let snapshot = knowledge_graph.current_snapshot();
+        let xml = snapshot.to_xml()?;
+        assert!(xml.contains("<metadata>"));
+        assert!(xml.contains("color=\"#12abef\""));
+        assert!(xml.contains("members=\"1 3\""));
+        assert_eq!(xml.matches("members=").count(), 1);
+        assert_eq!(*snapshot, Snapshot::from_xml(&xml)?);
+
+        // 分组框不是节点，兼容格式不输出
+        assert_eq!(Snapshot::from_xml(&xml)?.nodes.len(), snapshot.nodes.len());
+        assert!(!snapshot.to_compatible_xml()?.contains("<group"));
+
+        // 无法识别的颜色使用默认颜色
+        let xml = xml.replace("#12abef", "red");
+        let decoded = Snapshot::from_xml(&xml)?;
+        assert_eq!(decoded.groups[0].color, DEFAULT_GROUP_COLOR);
+
+        // 无法识别的成员被忽略
+        let xml = xml.replace("members=\"1 3\"", "members=\"1 x 3\"");
+        assert_eq!(Snapshot::from_xml(&xml)?.groups[1].members, [1, 3]);
 
         Ok(())
     }
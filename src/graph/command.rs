@@ -0,0 +1,347 @@
+//! 命令模块，将图谱的所有编辑操作统一为可存储、可重放的命令。
+
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use super::{
+    AddonEntityType, CoordinateBounds, DistinctEntityType, EntityNode, GraphMetadata, GroupFrame,
+    KnowledgeGraph, Relation,
+};
+use crate::error::GraphError;
+
+/// 图谱编辑命令，与 KnowledgeGraph 的修改接口一一对应
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum Command {
+    AddEntity {
+        content: String,
+        distinct_type: DistinctEntityType,
+        addon_types: Vec<AddonEntityType>,
+        coor: (f64, f64),
+    },
+    AddEntities(Vec<EntityNode>),
+    RemoveEntity(u64),
+    UpdateEntityContent {
+        id: u64,
+        content: String,
+        distinct_type: DistinctEntityType,
+        addon_types: Vec<AddonEntityType>,
+    },
+    UpdateEntityPosition {
+        id: u64,
+        coor: (f64, f64),
+    },
+    UpdateEntityNotes {
+        id: u64,
+        notes: String,
+    },
+    ReplaceEntity(EntityNode), // 以给定节点整体替换同 ID 的节点
+    AddEdge {
+        from: u64,
+        to: u64,
+        relation: Relation,
+    },
+    RemoveEdge {
+        from: u64,
+        to: u64,
+    },
+    UpdateEdge {
+        from: u64,
+        to: u64,
+        relation: Relation,
+    },
+    SetEdgeLabel {
+        from: u64,
+        to: u64,
+        label: String,
+    },
+    SetEdgeWaypoints {
+        from: u64,
+        to: u64,
+        waypoints: Vec<(f64, f64)>,
+    },
+    SetChildrenOrder {
+        parent: u64,
+        children: Vec<u64>,
+    },
+    SetMetadata(GraphMetadata),
+    AddGroup {
+        label: String,
+        rect: CoordinateBounds,
+        color: [u8; 3],
+        #[serde(default)]
+        members: Vec<u64>,
+    },
+    UpdateGroup(GroupFrame), // 以给定分组框整体替换同 ID 的分组框
+    RemoveGroup(u64),
+}
+
+/// 命令的执行结果
+#[derive(Debug, Clone, PartialEq)]
+pub enum CommandResult {
+    None,
+    Entity(u64),        // 新添加节点的 ID
+    Entities(Vec<u64>), // 批量添加的节点 ID
+    Group(u64),         // 新添加分组框的 ID
+}
+
+impl CommandResult {
+    /// 命令新添加的节点 ID
+    pub fn created_ids(&self) -> Vec<u64> {
+        match self {
+            CommandResult::None | CommandResult::Group(_) => Vec::new(),
+            CommandResult::Entity(id) => vec![*id],
+            CommandResult::Entities(ids) => ids.clone(),
+        }
+    }
+}
+
+impl Command {
+    /// 按映射替换命令引用的节点 ID，不在映射中的 ID 保持不变
+    pub fn remap_ids(&mut self, map: &HashMap<u64, u64>) {
+        let remap = |id: &mut u64| {
+            if let Some(new_id) = map.get(id) {
+                *id = *new_id;
+            }
+        };
+        match self {
+            Command::AddEntity { .. }
+            | Command::AddEntities(_)
+            | Command::SetMetadata(_)
+            | Command::RemoveGroup(_) => {}
+            Command::AddGroup { members, .. }
+            | Command::UpdateGroup(GroupFrame { members, .. }) => {
+                members.iter_mut().for_each(remap)
+            }
+            Command::RemoveEntity(id)
+            | Command::UpdateEntityContent { id, .. }
+            | Command::UpdateEntityPosition { id, .. }
+            | Command::UpdateEntityNotes { id, .. } => remap(id),
+            Command::ReplaceEntity(node) => {
+                remap(&mut node.id);
+                if let Some(parent) = node.primary_parent.as_mut() {
+                    remap(parent);
+                }
+            }
+            Command::SetChildrenOrder { parent, children } => {
+                remap(parent);
+                children.iter_mut().for_each(remap);
+            }
+            Command::AddEdge { from, to, .. }
+            | Command::RemoveEdge { from, to }
+            | Command::UpdateEdge { from, to, .. }
+            | Command::SetEdgeLabel { from, to, .. }
+            | Command::SetEdgeWaypoints { from, to, .. } => {
+                remap(from);
+                remap(to);
+            }
+        }
+    }
+}
+
+impl KnowledgeGraph {
+    /// 执行一条命令，作为一次可撤回的操作
+    pub fn apply(&mut self, command: Command) -> Result<CommandResult, GraphError> {
+        match command {
+            Command::AddEntity {
+                content,
+                distinct_type,
+                addon_types,
+                coor,
+            } => self
+                .add_entity(content, distinct_type, &addon_types, coor)
+                .map(CommandResult::Entity),
+            Command::AddEntities(entities) => {
+                self.add_entities(entities).map(CommandResult::Entities)
+            }
+            Command::RemoveEntity(id) => self.remove_entity(id).map(|_| CommandResult::None),
+            Command::UpdateEntityContent {
+                id,
+                content,
+                distinct_type,
+                addon_types,
+            } => self
+                .update_entity_content(id, content, distinct_type, &addon_types)
+                .map(|_| CommandResult::None),
+            Command::UpdateEntityPosition { id, coor } => self
+                .update_entity_position(id, coor)
+                .map(|_| CommandResult::None),
+            Command::UpdateEntityNotes { id, notes } => self
+                .update_entity_with(id, |node| node.notes = notes)
+                .map(|_| CommandResult::None),
+            Command::ReplaceEntity(entity) => self
+                .update_entity_with(entity.id, |node| *node = entity)
+                .map(|_| CommandResult::None),
+            Command::AddEdge { from, to, relation } => self
+                .add_edge(from, to, relation)
+                .map(|_| CommandResult::None),
+            Command::RemoveEdge { from, to } => {
+                self.remove_edge(from, to).map(|_| CommandResult::None)
+            }
+            Command::UpdateEdge { from, to, relation } => self
+                .update_edge(from, to, relation)
+                .map(|_| CommandResult::None),
+            Command::SetEdgeLabel { from, to, label } => self
+                .set_edge_label(from, to, label)
+                .map(|_| CommandResult::None),
+            Command::SetEdgeWaypoints {
+                from,
+                to,
+                waypoints,
+            } => self
+                .set_edge_waypoints(from, to, waypoints)
+                .map(|_| CommandResult::None),
+            Command::SetChildrenOrder { parent, children } => self
+                .set_children_order(parent, &children)
+                .map(|_| CommandResult::None),
+            Command::SetMetadata(metadata) => {
+                self.set_metadata(metadata);
+                Ok(CommandResult::None)
+            }
+            Command::AddGroup {
+                label,
+                rect,
+                color,
+                members,
+            } => self
+                .add_group(label, rect, color, &members)
+                .map(CommandResult::Group),
+            Command::UpdateGroup(group) => self.update_group(group).map(|_| CommandResult::None),
+            Command::RemoveGroup(id) => self.remove_group(id).map(|_| CommandResult::None),
+        }
+    }
+
+    /// 依次执行多条命令，整体作为一次可撤回的操作。
+    /// 任意一条命令失败时，撤销已执行的命令并返回错误，图谱和历史记录保持不变。
+    pub fn apply_batch<I>(&mut self, commands: I) -> Result<Vec<CommandResult>, GraphError>
+    where
+        I: IntoIterator<Item = Command>,
+    {
+        self.transaction(|graph| {
+            commands
+                .into_iter()
+                .map(|command| graph.apply(command))
+                .collect()
+        })
+    }
+
+    /// 在闭包中进行任意修改，整体作为一次可撤回的操作。
+    /// 闭包返回错误时，恢复图谱和历史记录并返回错误。
+    pub(super) fn transaction<T, F>(&mut self, f: F) -> Result<T, GraphError>
+    where
+        F: FnOnce(&mut Self) -> Result<T, GraphError>,
+    {
+        let before = self.current.clone();
+        let undo_stack = self.undo_stack.clone();
+        let redo_stack = self.redo_stack.clone();
+        let history_bytes = self.history_bytes;
+        let revision = self.revision;
+
+        let result = f(self);
+
+        // 恢复执行前的历史记录，再将执行前的快照作为一条记录压入
+        self.undo_stack = undo_stack;
+        self.history_bytes = history_bytes;
+        match result {
+            Ok(value) => {
+                if self.revision != revision {
+                    self.before_mutation_with(before);
+                }
+                Ok(value)
+            }
+            Err(e) => {
+                self.current = before;
+                self.redo_stack = redo_stack;
+                self.revision += 1;
+                Err(e)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn add_command(content: &str) -> Command {
+        Command::AddEntity {
+            content: content.to_string(),
+            distinct_type: DistinctEntityType::KnowledgePoint,
+            addon_types: vec![AddonEntityType::Example],
+            coor: (0.0, 0.0),
+        }
+    }
+
+    #[test]
+    fn test_apply() {
+        let mut graph = KnowledgeGraph::default();
+        let CommandResult::Entity(a) = graph.apply(add_command("A")).unwrap() else {
+            panic!("Expected entity id");
+        };
+        let CommandResult::Entity(b) = graph.apply(add_command("B")).unwrap() else {
+            panic!("Expected entity id");
+        };
+        graph
+            .apply(Command::AddEdge {
+                from: a,
+                to: b,
+                relation: Relation::Order,
+            })
+            .unwrap();
+        graph
+            .apply(Command::UpdateEntityNotes {
+                id: a,
+                notes: "notes".to_string(),
+            })
+            .unwrap();
+
+        assert_eq!(graph.current.nodes[&a].notes, "notes");
+        assert_eq!(graph.current.edges[&(a, b)], Relation::Order);
+
+        // 每条命令都是一次可撤回的操作
+        graph.undo().unwrap();
+        assert!(graph.current.nodes[&a].notes.is_empty());
+
+        assert_eq!(
+            graph.apply(Command::RemoveEntity(100)),
+            Err(GraphError::EntityNotFound(100))
+        );
+    }
+
+    #[test]
+    fn test_apply_batch() {
+        let mut graph = KnowledgeGraph::default();
+        graph.apply(add_command("A")).unwrap();
+
+        let results = graph
+            .apply_batch([
+                add_command("B"),
+                add_command("C"),
+                Command::AddEdge {
+                    from: 2,
+                    to: 3,
+                    relation: Relation::Contain,
+                },
+            ])
+            .unwrap();
+        assert_eq!(results.len(), 3);
+        assert_eq!(graph.current.nodes.len(), 3);
+        assert_eq!(graph.current.edges.len(), 1);
+
+        // 整体作为一次撤回
+        graph.undo().unwrap();
+        assert_eq!(graph.current.nodes.len(), 1);
+        assert!(graph.current.edges.is_empty());
+        graph.redo().unwrap();
+        assert_eq!(graph.current.nodes.len(), 3);
+
+        // 失败时不做任何修改，重做栈也保持不变
+        graph.undo().unwrap();
+        let before = graph.current.clone();
+        let result = graph.apply_batch([add_command("D"), Command::RemoveEdge { from: 1, to: 9 }]);
+        assert_eq!(result, Err(GraphError::EdgeNotFound(1, 9)));
+        assert_eq!(graph.current, before);
+        assert!(graph.redo().is_ok());
+        assert_eq!(graph.current.nodes.len(), 3);
+    }
+}
@@ -0,0 +1,119 @@
+//! ID 紧凑化模块，将反复增删后稀疏的节点 ID 重新编号为从 1 开始的连续整数。
+
+use std::collections::HashMap;
+
+use super::KnowledgeGraph;
+
+impl KnowledgeGraph {
+    /// 按原 ID 的大小顺序将所有节点重新编号为 1..=n，同步重映射所有边，并重置下一个可用的 ID。
+    /// 返回旧 ID 到新 ID 的映射，整体作为一次可撤回的操作；ID 已经紧凑时不会产生撤回记录。
+    pub fn compact_ids(&mut self) -> HashMap<u64, u64> {
+        let mut ids = self.current.nodes.keys().copied().collect::<Vec<_>>();
+        ids.sort_unstable();
+        let map = ids.into_iter().zip(1..).collect::<HashMap<u64, u64>>();
+
+        let latest_id = map.len() as u64 + 1;
+        if map.iter().all(|(old, new)| old == new) && self.current.latest_id == latest_id {
+            return map;
+        }
+
+        self.before_mutation(); // 记录快照
+
+        let current = &mut self.current;
+        current.nodes = current
+            .nodes
+            .values()
+            .map(|node| {
+                let mut node = node.clone();
+                node.id = map[&node.id];
+                node.primary_parent = node.primary_parent.and_then(|id| map.get(&id).copied());
+                (node.id, node)
+            })
+            .collect();
+        current.edges = current
+            .edges
+            .iter()
+            .map(|((from, to), relation)| ((map[from], map[to]), *relation))
+            .collect();
+        current.edge_labels = current
+            .edge_labels
+            .iter()
+            .map(|((from, to), label)| ((map[from], map[to]), label.clone()))
+            .collect();
+        current.edge_waypoints = current
+            .edge_waypoints
+            .iter()
+            .map(|((from, to), waypoints)| ((map[from], map[to]), waypoints.clone()))
+            .collect();
+        for group in current.groups.iter_mut() {
+            group.members = group
+                .members
+                .iter()
+                .filter_map(|id| map.get(id).copied())
+                .collect();
+        }
+        current.latest_id = latest_id;
+
+        map
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::graph::{DistinctEntityType, Relation};
+
+    use super::*;
+
+    #[test]
+    fn test_compact_ids() {
+        let mut graph = KnowledgeGraph::default();
+        let ids = (0..5)
+            .map(|i| {
+                graph
+                    .add_entity(
+                        format!("Node {i}"),
+                        DistinctEntityType::KnowledgePoint,
+                        &[],
+                        (i as f64 * 200.0, 0.0),
+                    )
+                    .unwrap()
+            })
+            .collect::<Vec<_>>();
+        graph.add_edge(ids[1], ids[3], Relation::Contain).unwrap();
+        graph.add_edge(ids[4], ids[1], Relation::Order).unwrap();
+        graph.remove_entity(ids[0]).unwrap();
+        graph.remove_entity(ids[2]).unwrap();
+        let before = graph.current_snapshot().clone();
+
+        let map = graph.compact_ids();
+        assert_eq!(map, HashMap::from([(2, 1), (4, 2), (5, 3)]));
+        let snapshot = graph.current_snapshot();
+        assert_eq!(snapshot.latest_id, 4);
+        assert_eq!(snapshot.nodes[&1].content, "Node 1");
+        assert_eq!(snapshot.nodes[&3].content, "Node 4");
+        assert_eq!(snapshot.edges.len(), 2);
+        assert_eq!(snapshot.edges.get(&(1, 2)), Some(&Relation::Contain));
+        assert_eq!(snapshot.edges.get(&(3, 1)), Some(&Relation::Order));
+
+        // 新节点从紧凑后的 ID 继续编号
+        let id = graph
+            .add_entity(
+                "New".to_string(),
+                DistinctEntityType::KnowledgePoint,
+                &[],
+                (0.0, 500.0),
+            )
+            .unwrap();
+        assert_eq!(id, 4);
+
+        // 已经紧凑时不产生撤回记录
+        graph.undo().unwrap();
+        let revision = graph.revision();
+        graph.compact_ids();
+        assert_eq!(graph.revision(), revision);
+
+        // 紧凑化整体作为一次撤回
+        graph.undo().unwrap();
+        assert_eq!(graph.current_snapshot(), &before);
+    }
+}
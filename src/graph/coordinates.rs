@@ -0,0 +1,134 @@
+//! 坐标表模块。将节点坐标导出为制表符分隔的表格（TSV），在 Excel 等表格软件中精确编辑后再导回，
+//! 按 ID 批量更新坐标，内容和类型不变。
+
+use super::{Command, KnowledgeGraph, Snapshot};
+use crate::error::GraphError;
+
+/// 坐标表的表头
+const TSV_HEADER: &str = "id\tcontent\tx\ty";
+
+impl Snapshot {
+    /// 导出坐标表，每行为 ID、内容、x、y，按 ID 排列。内容仅供对照，其中的制表符和换行替换为空格。
+    /// 开头带有 UTF-8 BOM，使 Excel 能正确识别中文
+    pub fn to_coordinates_tsv(&self) -> String {
+        let mut nodes = self.nodes.values().collect::<Vec<_>>();
+        nodes.sort_unstable_by_key(|node| node.id);
+
+        let mut lines = vec![format!("\u{feff}{TSV_HEADER}")];
+        for node in nodes {
+            let content = node.content.replace(['\t', '\n', '\r'], " ");
+            lines.push(format!(
+                "{}\t{content}\t{}\t{}",
+                node.id, node.coor.0, node.coor.1
+            ));
+        }
+        lines.join("\n") + "\n"
+    }
+
+    /// 由编辑后的坐标表生成位置更新命令，以及无法使用的行的警告。
+    /// 每行的第一列为 ID，最后两列为 x 和 y，中间的内容列不读取，因此表格软件给内容加上引号也不影响；
+    /// 以 id 开头的表头行和空行跳过，坐标不变的节点不生成命令
+    pub fn coordinates_commands(&self, tsv: &str) -> (Vec<Command>, Vec<String>) {
+        let mut commands = Vec::new();
+        let mut warnings = Vec::new();
+        let tsv = tsv.trim_start_matches('\u{feff}');
+        for (index, line) in tsv.lines().enumerate() {
+            let line_no = index + 1;
+            let fields = line.split('\t').map(str::trim).collect::<Vec<_>>();
+            if line.trim().is_empty() || fields[0].eq_ignore_ascii_case("id") {
+                continue;
+            }
+            let [id, .., x, y] = fields[..] else {
+                warnings.push(format!("第 {line_no} 行缺少坐标"));
+                continue;
+            };
+            let Ok(id) = id.parse::<u64>() else {
+                warnings.push(format!("第 {line_no} 行的 ID“{id}”无法识别"));
+                continue;
+            };
+            let (Ok(x), Ok(y)) = (x.parse::<f64>(), y.parse::<f64>()) else {
+                warnings.push(format!("第 {line_no} 行的坐标“{x}, {y}”无法识别"));
+                continue;
+            };
+            if !x.is_finite() || !y.is_finite() {
+                warnings.push(format!("第 {line_no} 行的坐标“{x}, {y}”无效"));
+                continue;
+            }
+            let Some(node) = self.nodes.get(&id) else {
+                warnings.push(format!("第 {line_no} 行的节点 {id} 不存在"));
+                continue;
+            };
+            if node.coor != (x, y) {
+                commands.push(Command::UpdateEntityPosition { id, coor: (x, y) });
+            }
+        }
+        (commands, warnings)
+    }
+}
+
+impl KnowledgeGraph {
+    /// 按坐标表更新节点的坐标，整体作为一次可撤回的操作，返回移动的节点数和无法使用的行的警告
+    pub fn import_coordinates(&mut self, tsv: &str) -> Result<(usize, Vec<String>), GraphError> {
+        let (commands, warnings) = self.current.coordinates_commands(tsv);
+        let count = commands.len();
+        if count > 0 {
+            self.apply_batch(commands)?;
+        }
+        Ok((count, warnings))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::DistinctEntityType;
+
+    #[test]
+    fn test_coordinates_tsv() {
+        let mut graph = KnowledgeGraph::default();
+        for (content, coor) in [("线性表", (0.0, 0.0)), ("栈\t与\n队列", (10.5, -20.0))] {
+            graph
+                .add_entity(
+                    content.to_string(),
+                    DistinctEntityType::KnowledgePoint,
+                    &[],
+                    coor,
+                )
+                .unwrap();
+        }
+        let tsv = graph.current.to_coordinates_tsv();
+        assert_eq!(
+            tsv,
+            "\u{feff}id\tcontent\tx\ty\n1\t线性表\t0\t0\n2\t栈 与 队列\t10.5\t-20\n"
+        );
+
+        // 原样导回时没有修改
+        assert_eq!(graph.import_coordinates(&tsv), Ok((0, Vec::new())));
+
+        // 只修改坐标，内容被加上引号或改动也不影响；无法使用的行给出警告
+        let edited = "id\tcontent\tx\ty\r\n\
+                      1\t\"线性表\"\t100\t200\r\n\
+                      2\t改过的内容\t10.5\t-20\r\n\
+                      \r\n\
+                      3\t不存在\t0\t0\r\n\
+                      abc\t\t0\t0\r\n\
+                      1\t\tx\t0\r\n\
+                      2\r\n";
+        let undo_len = graph.undo_len();
+        let (count, warnings) = graph.import_coordinates(edited).unwrap();
+        assert_eq!(count, 1);
+        assert_eq!(graph.undo_len(), undo_len + 1);
+        assert_eq!(graph.current.nodes[&1].coor, (100.0, 200.0));
+        assert_eq!(graph.current.nodes[&1].content, "线性表");
+        assert_eq!(graph.current.nodes[&2].content, "栈\t与\n队列");
+        assert_eq!(
+            warnings,
+            vec![
+                "第 5 行的节点 3 不存在",
+                "第 6 行的 ID“abc”无法识别",
+                "第 7 行的坐标“x, 0”无法识别",
+                "第 8 行缺少坐标",
+            ]
+        );
+    }
+}
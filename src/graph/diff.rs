@@ -0,0 +1,346 @@
+//! 差异模块，比较两个快照中节点和边的增删改。
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use super::Snapshot;
+
+/// 节点或边的变化
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Change {
+    Added,    // 新增
+    Removed,  // 删除
+    Modified, // 修改
+}
+
+/// 两个快照之间的差异，未变化的节点和边不包含在内
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SnapshotDiff {
+    pub nodes: BTreeMap<u64, Change>,
+    pub edges: BTreeMap<(u64, u64), Change>,
+}
+
+impl SnapshotDiff {
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty() && self.edges.is_empty()
+    }
+
+    /// 节点和边中发生 change 变化的数量
+    pub fn count(&self, change: Change) -> usize {
+        self.nodes
+            .values()
+            .chain(self.edges.values())
+            .filter(|c| **c == change)
+            .count()
+    }
+}
+
+impl Snapshot {
+    /// 以自身为基准，计算 newer 相对自身的差异。
+    /// 节点按 ID 对应，仅坐标不同不视为修改，与邻接表文本一致；边的关系不同视为修改。
+    pub fn diff(&self, newer: &Snapshot) -> SnapshotDiff {
+        let mut diff = SnapshotDiff::default();
+
+        for (id, node) in newer.nodes.iter() {
+            match self.nodes.get(id) {
+                None => {
+                    diff.nodes.insert(*id, Change::Added);
+                }
+                Some(old)
+                    if old.content != node.content
+                        || old.distinct_type != node.distinct_type
+                        || old.addon_types != node.addon_types
+                        || old.notes != node.notes =>
+                {
+                    diff.nodes.insert(*id, Change::Modified);
+                }
+                Some(_) => {}
+            }
+        }
+        for id in self.nodes.keys() {
+            if !newer.nodes.contains_key(id) {
+                diff.nodes.insert(*id, Change::Removed);
+            }
+        }
+
+        for (key, relation) in newer.edges.iter() {
+            match self.edges.get(key) {
+                None => {
+                    diff.edges.insert(*key, Change::Added);
+                }
+                Some(old)
+                    if old != relation
+                        || self.edge_labels.get(key) != newer.edge_labels.get(key)
+                        || self.edge_waypoints.get(key) != newer.edge_waypoints.get(key) =>
+                {
+                    diff.edges.insert(*key, Change::Modified);
+                }
+                Some(_) => {}
+            }
+        }
+        for key in self.edges.keys() {
+            if !newer.edges.contains_key(key) {
+                diff.edges.insert(*key, Change::Removed);
+            }
+        }
+
+        diff
+    }
+}
+
+impl Snapshot {
+    /// 将自身的节点与 newer 中的节点对应，返回自身 ID 到 newer 中 ID 的映射。
+    /// 依次按以下规则匹配，已匹配的节点不再参与之后的规则：
+    /// 1. ID 相同且内容相同；
+    /// 2. 内容和实体类型相同，且在双方剩余的节点中都唯一，用于应对重新编号，match_content 为 false 时跳过；
+    /// 3. ID 相同，之后在差异中视为修改。
+    pub fn match_nodes(&self, newer: &Snapshot, match_content: bool) -> BTreeMap<u64, u64> {
+        let mut matches = BTreeMap::new();
+        let mut unmatched_old = self.nodes.keys().copied().collect::<BTreeSet<_>>();
+        let mut unmatched_new = newer.nodes.keys().copied().collect::<BTreeSet<_>>();
+
+        let same_content = |old: u64, new: u64| {
+            let (old, new) = (&self.nodes[&old], &newer.nodes[&new]);
+            old.content == new.content && old.distinct_type == new.distinct_type
+        };
+
+        for id in unmatched_old.clone() {
+            if unmatched_new.contains(&id) && same_content(id, id) {
+                matches.insert(id, id);
+                unmatched_old.remove(&id);
+                unmatched_new.remove(&id);
+            }
+        }
+
+        if match_content {
+            // 按内容分组，只匹配双方各只有一个节点的内容
+            let group = |snapshot: &Snapshot, ids: &BTreeSet<u64>| {
+                let mut groups = BTreeMap::<_, Vec<u64>>::new();
+                for id in ids {
+                    let node = &snapshot.nodes[id];
+                    groups
+                        .entry((node.content.clone(), node.distinct_type as u8))
+                        .or_default()
+                        .push(*id);
+                }
+                groups
+            };
+            let new_groups = group(newer, &unmatched_new);
+            for (key, old_ids) in group(self, &unmatched_old) {
+                if let ([old], Some([new])) =
+                    (old_ids.as_slice(), new_groups.get(&key).map(Vec::as_slice))
+                {
+                    matches.insert(*old, *new);
+                    unmatched_old.remove(old);
+                    unmatched_new.remove(new);
+                }
+            }
+        }
+
+        for id in unmatched_old {
+            if unmatched_new.contains(&id) {
+                matches.insert(id, id);
+            }
+        }
+
+        matches
+    }
+
+    /// 按 match_nodes 的结果将自身的节点重新编号为 newer 中对应节点的 ID，使两者可以按 ID 比较。
+    /// 未匹配的节点保留原 ID，与 newer 或已匹配的节点冲突时改用新的 ID。
+    pub fn aligned_to(&self, newer: &Snapshot, match_content: bool) -> Snapshot {
+        let mut map = self.match_nodes(newer, match_content);
+        let mut used = newer
+            .nodes
+            .keys()
+            .chain(map.values())
+            .copied()
+            .collect::<BTreeSet<_>>();
+        let mut next_id = self
+            .nodes
+            .keys()
+            .chain(newer.nodes.keys())
+            .max()
+            .map_or(1, |id| id + 1);
+
+        let mut ids = self.nodes.keys().copied().collect::<Vec<_>>();
+        ids.sort_unstable();
+        for id in ids {
+            if map.contains_key(&id) {
+                continue;
+            }
+            let new_id = if used.contains(&id) {
+                next_id += 1;
+                next_id - 1
+            } else {
+                id
+            };
+            used.insert(new_id);
+            map.insert(id, new_id);
+        }
+
+        let mut aligned = self.clone();
+        aligned.nodes = self
+            .nodes
+            .values()
+            .map(|node| {
+                let mut node = node.clone();
+                node.id = map[&node.id];
+                node.primary_parent = node.primary_parent.and_then(|id| map.get(&id).copied());
+                (node.id, node)
+            })
+            .collect();
+        aligned.edges = self
+            .edges
+            .iter()
+            .filter_map(|((from, to), relation)| {
+                Some(((*map.get(from)?, *map.get(to)?), *relation))
+            })
+            .collect();
+        aligned.edge_labels = self
+            .edge_labels
+            .iter()
+            .filter_map(|((from, to), label)| {
+                Some(((*map.get(from)?, *map.get(to)?), label.clone()))
+            })
+            .collect();
+        aligned.edge_waypoints = self
+            .edge_waypoints
+            .iter()
+            .filter_map(|((from, to), waypoints)| {
+                Some(((*map.get(from)?, *map.get(to)?), waypoints.clone()))
+            })
+            .collect();
+        aligned.latest_id = aligned.latest_id.max(next_id);
+        aligned
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::graph::{DistinctEntityType, KnowledgeGraph, Relation};
+
+    use super::*;
+
+    #[test]
+    fn test_diff() -> Result<(), Box<dyn std::error::Error>> {
+        let mut graph = KnowledgeGraph::default();
+        let a = graph.add_entity(
+            "A".to_string(),
+            DistinctEntityType::KnowledgeUnit,
+            &[],
+            (0.0, 0.0),
+        )?;
+        let b = graph.add_entity(
+            "B".to_string(),
+            DistinctEntityType::KnowledgePoint,
+            &[],
+            (0.0, 100.0),
+        )?;
+        let c = graph.add_entity(
+            "C".to_string(),
+            DistinctEntityType::KnowledgePoint,
+            &[],
+            (100.0, 100.0),
+        )?;
+        graph.add_edge(a, b, Relation::Contain)?;
+        graph.add_edge(b, c, Relation::Order)?;
+        let base = graph.current.clone();
+        assert!(base.diff(&base).is_empty());
+
+        // 移动不视为修改
+        graph.update_entity_position(a, (50.0, 50.0))?;
+        graph.update_entity_content(
+            b,
+            "B2".to_string(),
+            DistinctEntityType::KnowledgePoint,
+            &[],
+        )?;
+        graph.remove_entity(c)?;
+        let d = graph.add_entity(
+            "D".to_string(),
+            DistinctEntityType::KnowledgePoint,
+            &[],
+            (200.0, 100.0),
+        )?;
+        graph.update_edge(a, b, Relation::Order)?;
+        graph.add_edge(a, d, Relation::Contain)?;
+
+        let diff = base.diff(&graph.current);
+        assert_eq!(
+            diff.nodes,
+            BTreeMap::from([
+                (b, Change::Modified),
+                (c, Change::Removed),
+                (d, Change::Added)
+            ])
+        );
+        assert_eq!(
+            diff.edges,
+            BTreeMap::from([
+                ((a, b), Change::Modified),
+                ((a, d), Change::Added),
+                ((b, c), Change::Removed)
+            ])
+        );
+        assert_eq!(diff.count(Change::Removed), 2);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_aligned_to() -> Result<(), Box<dyn std::error::Error>> {
+        let mut graph = KnowledgeGraph::default();
+        let mut ids = Vec::new();
+        for content in ["X", "A", "B", "C", "C"] {
+            ids.push(graph.add_entity(
+                content.to_string(),
+                DistinctEntityType::KnowledgePoint,
+                &[],
+                (0.0, 0.0),
+            )?);
+        }
+        graph.add_edge(ids[1], ids[2], Relation::Contain)?;
+        graph.remove_entity(ids[0])?;
+        let base = graph.current.clone();
+
+        // 重新编号后按 ID 比较几乎全部不同，按内容匹配后没有差异
+        graph.compact_ids();
+        let current = graph.current.clone();
+        assert!(!base.diff(&current).is_empty());
+        assert!(base.aligned_to(&current, true).diff(&current).is_empty());
+
+        // 不按内容匹配时与按 ID 比较一致
+        assert_eq!(
+            base.aligned_to(&current, false).diff(&current),
+            base.diff(&current)
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_match_ambiguous_content() -> Result<(), Box<dyn std::error::Error>> {
+        let mut graph = KnowledgeGraph::default();
+        for _ in 0..4 {
+            graph.add_entity(
+                "C".to_string(),
+                DistinctEntityType::KnowledgePoint,
+                &[],
+                (0.0, 0.0),
+            )?;
+        }
+        let mut base = graph.current.clone();
+        base.nodes.retain(|id, _| *id <= 2);
+        let mut newer = graph.current.clone();
+        newer.nodes.retain(|id, _| *id > 2);
+
+        // 内容重复的节点无法确定对应关系，不进行匹配
+        assert!(base.match_nodes(&newer, true).is_empty());
+        let diff = base.aligned_to(&newer, true).diff(&newer);
+        assert_eq!(diff.count(Change::Removed), 2);
+        assert_eq!(diff.count(Change::Added), 2);
+
+        Ok(())
+    }
+}
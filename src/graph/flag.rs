@@ -0,0 +1,92 @@
+//! 评审标记模块。评审会上为节点标注待办、疑问或已审核，会后按标记逐个处理。
+
+use super::{Command, Flag, KnowledgeGraph, Snapshot};
+use crate::error::GraphError;
+
+impl Snapshot {
+    /// 带有指定标记的节点，按 ID 排列
+    pub fn flagged(&self, flag: Flag) -> Vec<u64> {
+        let mut ids = self
+            .nodes
+            .values()
+            .filter(|node| node.flag == Some(flag))
+            .map(|node| node.id)
+            .collect::<Vec<_>>();
+        ids.sort_unstable();
+        ids
+    }
+
+    /// 将节点的标记设为 flag 所需的命令，标记不变的节点不生成命令。
+    /// 有节点不存在时返回错误
+    pub fn set_flags_commands(
+        &self,
+        ids: &[u64],
+        flag: Option<Flag>,
+    ) -> Result<Vec<Command>, GraphError> {
+        let mut commands = Vec::new();
+        for id in ids {
+            let node = self.nodes.get(id).ok_or(GraphError::EntityNotFound(*id))?;
+            if node.flag != flag {
+                let mut node = node.clone();
+                node.flag = flag;
+                commands.push(Command::ReplaceEntity(node));
+            }
+        }
+        Ok(commands)
+    }
+}
+
+impl KnowledgeGraph {
+    /// 将节点的标记设为 flag（None 为清除标记），整体作为一次可撤回的操作，返回修改的节点数
+    pub fn set_flags(&mut self, ids: &[u64], flag: Option<Flag>) -> Result<usize, GraphError> {
+        let commands = self.current.set_flags_commands(ids, flag)?;
+        let count = commands.len();
+        if count > 0 {
+            self.apply_batch(commands)?;
+        }
+        Ok(count)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::DistinctEntityType;
+
+    #[test]
+    fn test_set_flags() {
+        let mut graph = KnowledgeGraph::default();
+        for _ in 0..3 {
+            graph
+                .add_entity(
+                    String::new(),
+                    DistinctEntityType::KnowledgePoint,
+                    &[],
+                    (0.0, 0.0),
+                )
+                .unwrap();
+        }
+
+        let undo_len = graph.undo_len();
+        assert_eq!(graph.set_flags(&[3, 1], Some(Flag::Todo)), Ok(2));
+        assert_eq!(graph.undo_len(), undo_len + 1);
+        assert_eq!(graph.current.flagged(Flag::Todo), vec![1, 3]);
+        assert_eq!(graph.current.statistics().flag_counts[&Flag::Todo], 2);
+
+        // 标记不变的节点不修改，没有修改时不记录操作
+        assert_eq!(graph.set_flags(&[1, 2], Some(Flag::Todo)), Ok(1));
+        assert_eq!(graph.set_flags(&[1], Some(Flag::Todo)), Ok(0));
+        assert_eq!(graph.undo_len(), undo_len + 2);
+
+        assert_eq!(graph.set_flags(&[2], None), Ok(1));
+        assert_eq!(graph.current.flagged(Flag::Todo), vec![1, 3]);
+        graph.undo().unwrap();
+        assert_eq!(graph.current.flagged(Flag::Todo), vec![1, 2, 3]);
+
+        assert_eq!(
+            graph.set_flags(&[1, 9], Some(Flag::Approved)),
+            Err(GraphError::EntityNotFound(9))
+        );
+        assert!(graph.current.flagged(Flag::Approved).is_empty());
+    }
+}
@@ -0,0 +1,311 @@
+//! 术语检查模块。教研团队要求术语统一时，可加载标准术语表，列出内容中使用了非标准写法的节点。
+//! 检查逻辑由 TermChecker 抽象，可替换为其他实现或直接使用闭包。
+
+use std::ops::Range;
+
+use super::{Command, IssueLevel, KnowledgeGraph, Snapshot, ValidationIssue};
+use crate::error::{GraphError, SerdeError};
+
+/// 问题说明中每个节点最多列出的术语数
+const SHOWN_TERMS: usize = 3;
+
+/// 内容中使用了非标准写法的一处术语
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TermMatch {
+    pub range: Range<usize>, // 在内容中的字节范围
+    pub found: String,       // 内容中的写法
+    pub standard: String,    // 标准写法
+}
+
+/// 术语检查，返回内容中所有非标准写法的术语，按出现的位置排列
+pub trait TermChecker {
+    fn check(&self, content: &str) -> Vec<TermMatch>;
+}
+
+impl<F> TermChecker for F
+where
+    F: Fn(&str) -> Vec<TermMatch>,
+{
+    fn check(&self, content: &str) -> Vec<TermMatch> {
+        self(content)
+    }
+}
+
+/// 标准术语表中的一项
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Term {
+    standard: String,
+    variants: Vec<String>,
+}
+
+/// 标准术语表。文本格式每行一项，`#` 开头的行和空行忽略：
+/// `标准写法 = 变体1, 变体2` 列出应改为标准写法的变体（逗号可为全角），也可只写标准写法。
+/// 含英文字母的术语，大小写不同的写法也视为非标准，如 `TCP/IP` 对应 `tcp/ip`
+///
+/// ```
+/// use py_better_kt_sqep::graph::{Glossary, TermChecker};
+///
+/// let glossary = Glossary::from_text("数据结构 = 数据结够\nTCP/IP").unwrap();
+/// let matches = glossary.check("数据结够与 tcp/ip 协议");
+/// assert_eq!(matches.len(), 2);
+/// assert_eq!(matches[0].standard, "数据结构");
+/// assert_eq!(matches[1].found, "tcp/ip");
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Glossary {
+    terms: Vec<Term>,
+}
+
+impl Glossary {
+    /// 从文本解析术语表。等号前的标准写法为空时返回错误
+    pub fn from_text(text: &str) -> Result<Self, SerdeError> {
+        let mut terms = Vec::new();
+        for line in text.lines().map(str::trim) {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (standard, variants) = line.split_once('=').unwrap_or((line, ""));
+            let standard = standard.trim();
+            if standard.is_empty() {
+                return Err(SerdeError::Unexpected("术语表的行", line.to_string()));
+            }
+            let variants = variants
+                .split([',', '，'])
+                .map(str::trim)
+                .filter(|variant| !variant.is_empty() && *variant != standard)
+                .map(str::to_string)
+                .collect();
+            terms.push(Term {
+                standard: standard.to_string(),
+                variants,
+            });
+        }
+        Ok(Self { terms })
+    }
+
+    /// 术语表中的条目数
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.terms.len()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.terms.is_empty()
+    }
+}
+
+impl TermChecker for Glossary {
+    fn check(&self, content: &str) -> Vec<TermMatch> {
+        let lowercase = content.to_ascii_lowercase();
+        let mut matches = Vec::new();
+        for term in self.terms.iter() {
+            // 标准写法出现的位置，其中包含的变体不算非标准写法
+            let standard = content
+                .match_indices(&term.standard)
+                .map(|(start, s)| start..start + s.len())
+                .collect::<Vec<_>>();
+            let covered = |range: &Range<usize>| {
+                standard
+                    .iter()
+                    .any(|s| s.start <= range.start && range.end <= s.end)
+            };
+
+            for variant in term.variants.iter() {
+                for (start, found) in content.match_indices(variant.as_str()) {
+                    let range = start..start + found.len();
+                    if !covered(&range) {
+                        matches.push(TermMatch {
+                            range,
+                            found: found.to_string(),
+                            standard: term.standard.clone(),
+                        });
+                    }
+                }
+            }
+
+            // 大小写不同的写法。转换为小写不改变字节位置
+            if term.standard.bytes().any(|b| b.is_ascii_alphabetic()) {
+                let needle = term.standard.to_ascii_lowercase();
+                for (start, _) in lowercase.match_indices(&needle) {
+                    let range = start..start + needle.len();
+                    if content[range.clone()] != term.standard {
+                        matches.push(TermMatch {
+                            found: content[range.clone()].to_string(),
+                            range,
+                            standard: term.standard.clone(),
+                        });
+                    }
+                }
+            }
+        }
+
+        // 按位置排列，与先前的匹配重叠的去掉
+        matches.sort_by_key(|m| (m.range.start, std::cmp::Reverse(m.range.end)));
+        let mut end = 0;
+        matches.retain(|m| {
+            let keep = m.range.start >= end;
+            if keep {
+                end = m.range.end;
+            }
+            keep
+        });
+        matches
+    }
+}
+
+/// 将内容中的非标准写法替换为标准写法。matches 应为 check 对同一内容返回的结果
+pub fn apply_term_matches(content: &str, matches: &[TermMatch]) -> String {
+    let mut result = content.to_string();
+    for m in matches.iter().rev() {
+        result.replace_range(m.range.clone(), &m.standard);
+    }
+    result
+}
+
+impl Snapshot {
+    /// 使用了非标准写法术语的节点，每个节点一条警告，按节点 ID 排列
+    pub fn term_issues(&self, checker: &dyn TermChecker) -> Vec<ValidationIssue> {
+        let mut nodes = self.nodes.values().collect::<Vec<_>>();
+        nodes.sort_unstable_by_key(|node| node.id);
+        nodes
+            .into_iter()
+            .filter_map(|node| {
+                let matches = checker.check(&node.content);
+                if matches.is_empty() {
+                    return None;
+                }
+                let mut shown = matches
+                    .iter()
+                    .take(SHOWN_TERMS)
+                    .map(|m| format!("“{}”应写作“{}”", m.found, m.standard))
+                    .collect::<Vec<_>>();
+                if matches.len() > SHOWN_TERMS {
+                    shown.push(format!("等 {} 处", matches.len()));
+                }
+                Some(ValidationIssue::new(
+                    IssueLevel::Warning,
+                    format!("节点 {} 的术语不规范：{}", node.id, shown.join("，")),
+                    vec![node.id],
+                ))
+            })
+            .collect()
+    }
+
+    /// 将所有节点内容中的非标准写法替换为标准写法所需的命令，内容不变的节点不生成命令
+    pub fn standard_terms_commands(&self, checker: &dyn TermChecker) -> Vec<Command> {
+        let mut nodes = self.nodes.values().collect::<Vec<_>>();
+        nodes.sort_unstable_by_key(|node| node.id);
+        nodes
+            .into_iter()
+            .filter_map(|node| {
+                let matches = checker.check(&node.content);
+                (!matches.is_empty()).then(|| Command::UpdateEntityContent {
+                    id: node.id,
+                    content: apply_term_matches(&node.content, &matches),
+                    distinct_type: node.distinct_type,
+                    addon_types: node.addon_types.iter().copied().collect(),
+                })
+            })
+            .collect()
+    }
+}
+
+impl KnowledgeGraph {
+    /// 将所有节点内容中的非标准写法替换为标准写法，整体作为一次可撤回的操作，返回修改的节点数
+    pub fn apply_standard_terms(&mut self, checker: &dyn TermChecker) -> Result<usize, GraphError> {
+        let commands = self.current.standard_terms_commands(checker);
+        let count = commands.len();
+        if count > 0 {
+            self.apply_batch(commands)?;
+        }
+        Ok(count)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::{DistinctEntityType, KnowledgeGraph};
+
+    #[test]
+    fn test_glossary() {
+        let glossary = Glossary::from_text(
+            "# 术语表\n\n操作系统 = 作业系统，操作系\n数据库 = 資料庫, 数据库\nLinux\n",
+        )
+        .unwrap();
+        assert_eq!(glossary.len(), 3);
+        assert!(matches!(
+            Glossary::from_text(" = 变体"),
+            Err(SerdeError::Unexpected(..))
+        ));
+
+        // 标准写法中包含的变体不算非标准写法
+        assert!(glossary.check("操作系统与数据库").is_empty());
+        let content = "作业系统和操作系的区别，LINUX 与 Linux";
+        let matches = glossary.check(content);
+        assert_eq!(
+            matches
+                .iter()
+                .map(|m| (m.found.as_str(), m.standard.as_str()))
+                .collect::<Vec<_>>(),
+            vec![
+                ("作业系统", "操作系统"),
+                ("操作系", "操作系统"),
+                ("LINUX", "Linux")
+            ]
+        );
+        assert_eq!(&content[matches[1].range.clone()], "操作系");
+        assert_eq!(
+            apply_term_matches(content, &matches),
+            "操作系统和操作系统的区别，Linux 与 Linux"
+        );
+    }
+
+    #[test]
+    fn test_term_issues() {
+        let mut graph = KnowledgeGraph::default();
+        for content in ["数据结够", "数据结构", "数据结够与数据结够"] {
+            graph
+                .add_entity(
+                    content.to_string(),
+                    DistinctEntityType::KnowledgePoint,
+                    &[],
+                    (0.0, 0.0),
+                )
+                .unwrap();
+        }
+
+        let glossary = Glossary::from_text("数据结构 = 数据结够").unwrap();
+        let issues = graph.current.term_issues(&glossary);
+        assert_eq!(
+            issues
+                .iter()
+                .map(|i| i.entities.clone())
+                .collect::<Vec<_>>(),
+            vec![vec![1], vec![3]]
+        );
+        assert!(issues[0].message.contains("“数据结够”应写作“数据结构”"));
+
+        let undo_len = graph.undo_len();
+        assert_eq!(graph.apply_standard_terms(&glossary), Ok(2));
+        assert_eq!(graph.current.nodes[&3].content, "数据结构与数据结构");
+        assert_eq!(graph.undo_len(), undo_len + 1);
+        assert!(graph.current.term_issues(&glossary).is_empty());
+
+        // 以闭包替换检查逻辑，替换后每个节点都含有“结构”
+        let checker = |content: &str| -> Vec<TermMatch> {
+            content
+                .match_indices("结构")
+                .map(|(start, found)| TermMatch {
+                    range: start..start + found.len(),
+                    found: found.to_string(),
+                    standard: "构造".to_string(),
+                })
+                .collect()
+        };
+        let issues = graph.current.term_issues(&checker);
+        assert_eq!(issues.len(), 3);
+        assert!(issues[0].message.contains("“结构”应写作“构造”"));
+    }
+}
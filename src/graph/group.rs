@@ -0,0 +1,327 @@
+//! 分组框模块。分组框是画在节点后面的带标签的矩形，如“第3周”“实验部分”，只用于展示，
+//! 不属于图谱的节点和边。分组框以扩展元素保存在 XML 的元数据中，旧版本和对接平台读取时会忽略。
+//!
+//! 分组框有两种：没有成员时范围固定，中心位于其中的节点随其移动；
+//! 有成员时范围自动包住所有成员节点，只有成员随其移动，如将“第一章”的节点归为一组。
+
+use serde::{Deserialize, Serialize};
+
+use super::{Command, CoordinateBounds, KnowledgeGraph, NODE_SIZE, Snapshot};
+use crate::error::GraphError;
+
+/// 新建分组框的默认颜色
+pub const DEFAULT_GROUP_COLOR: [u8; 3] = [0x90, 0xa4, 0xae];
+
+/// 有成员的分组框在成员节点的中心四周留出的距离，使节点完整位于框内并留出标签栏
+const MEMBER_MARGIN: (f64, f64) = NODE_SIZE;
+
+/// 分组框
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GroupFrame {
+    pub id: u64,
+    pub label: String,
+    pub rect: CoordinateBounds, // 分组框的逻辑坐标范围
+    pub color: [u8; 3],         // 边框和填充的 RGB 颜色
+    #[serde(default)]
+    pub members: Vec<u64>, // 成员节点，非空时范围自动包住所有成员，rect 只在成员都被删除后使用
+}
+
+/// 包围所有节点的范围，节点坐标为中心，四周留出 margin。没有节点时返回 None
+pub fn frame_around<I>(coors: I, margin: (f64, f64)) -> Option<CoordinateBounds>
+where
+    I: IntoIterator<Item = (f64, f64)>,
+{
+    coors
+        .into_iter()
+        .map(|(x, y)| CoordinateBounds {
+            min: (x - margin.0, y - margin.1),
+            max: (x + margin.0, y + margin.1),
+        })
+        .reduce(|a, b| CoordinateBounds {
+            min: (a.min.0.min(b.min.0), a.min.1.min(b.min.1)),
+            max: (a.max.0.max(b.max.0), a.max.1.max(b.max.1)),
+        })
+}
+
+impl Snapshot {
+    /// 按 ID 查找分组框
+    #[inline]
+    pub fn group(&self, id: u64) -> Option<&GroupFrame> {
+        self.groups.iter().find(|group| group.id == id)
+    }
+
+    /// 分组框的实际范围：有成员时为包住所有成员的范围，否则为保存的 rect
+    pub fn group_bounds(&self, group: &GroupFrame) -> CoordinateBounds {
+        let members = group
+            .members
+            .iter()
+            .filter_map(|id| self.nodes.get(id))
+            .map(|node| node.coor);
+        frame_around(members, MEMBER_MARGIN).unwrap_or(group.rect)
+    }
+
+    /// 分组框中的节点，按 ID 排序。有成员时为成员，否则为中心位于分组框内的节点。
+    /// 分组框不存在时返回空列表
+    pub fn nodes_in_group(&self, id: u64) -> Vec<u64> {
+        let Some(group) = self.group(id) else {
+            return Vec::new();
+        };
+        let mut ids = if group.members.is_empty() {
+            self.nodes
+                .values()
+                .filter(|node| group.rect.contains(node.coor))
+                .map(|node| node.id)
+                .collect::<Vec<_>>()
+        } else {
+            group
+                .members
+                .iter()
+                .copied()
+                .filter(|id| self.nodes.contains_key(id))
+                .collect()
+        };
+        ids.sort_unstable();
+        ids.dedup();
+        ids
+    }
+
+    /// 检查分组框的成员都存在
+    fn check_members(&self, members: &[u64]) -> Result<(), GraphError> {
+        match members.iter().find(|id| !self.nodes.contains_key(id)) {
+            Some(id) => Err(GraphError::EntityNotFound(*id)),
+            None => Ok(()),
+        }
+    }
+
+    /// 将分组框及其中的节点平移 offset 的命令
+    pub fn move_group_commands(
+        &self,
+        id: u64,
+        offset: (f64, f64),
+    ) -> Result<Vec<Command>, GraphError> {
+        let group = self.group(id).ok_or(GraphError::GroupNotFound(id))?;
+        let shift = |(x, y): (f64, f64)| (x + offset.0, y + offset.1);
+
+        let mut moved = group.clone();
+        moved.rect = CoordinateBounds {
+            min: shift(group.rect.min),
+            max: shift(group.rect.max),
+        };
+        let moves = self.nodes_in_group(id).into_iter().map(|id| {
+            let coor = shift(self.nodes[&id].coor);
+            Command::UpdateEntityPosition { id, coor }
+        });
+
+        Ok(std::iter::once(Command::UpdateGroup(moved))
+            .chain(moves)
+            .collect())
+    }
+}
+
+impl KnowledgeGraph {
+    /// 添加一个分组框，返回其 ID。members 非空时分组框自动包住这些节点，
+    /// 成员不存在时返回错误。
+    pub fn add_group(
+        &mut self,
+        label: String,
+        rect: CoordinateBounds,
+        color: [u8; 3],
+        members: &[u64],
+    ) -> Result<u64, GraphError> {
+        self.current.check_members(members)?;
+
+        self.before_mutation(); // 记录快照
+
+        let groups = &mut self.current.groups;
+        let id = groups.iter().map(|group| group.id).max().unwrap_or(0) + 1;
+        groups.push(GroupFrame {
+            id,
+            label,
+            rect,
+            color,
+            members: members.to_vec(),
+        });
+
+        Ok(id)
+    }
+
+    /// 以给定分组框整体替换同 ID 的分组框，用于修改标签、颜色、大小和成员。
+    /// 如果分组框或成员不存在，返回错误。
+    pub fn update_group(&mut self, group: GroupFrame) -> Result<(), GraphError> {
+        let Some(index) = self.current.groups.iter().position(|g| g.id == group.id) else {
+            return Err(GraphError::GroupNotFound(group.id));
+        };
+        self.current.check_members(&group.members)?;
+
+        self.before_mutation(); // 记录快照
+
+        self.current.groups[index] = group;
+        Ok(())
+    }
+
+    /// 删除一个分组框，其中的节点不受影响。
+    /// 如果分组框不存在，返回错误。
+    pub fn remove_group(&mut self, id: u64) -> Result<(), GraphError> {
+        let Some(index) = self.current.groups.iter().position(|g| g.id == id) else {
+            return Err(GraphError::GroupNotFound(id));
+        };
+
+        self.before_mutation(); // 记录快照
+
+        self.current.groups.remove(index);
+        Ok(())
+    }
+
+    /// 平移分组框及其中的节点，整体作为一次可撤回的操作。
+    /// 如果分组框不存在，返回错误。
+    pub fn move_group(&mut self, id: u64, offset: (f64, f64)) -> Result<(), GraphError> {
+        let commands = self.current.move_group_commands(id, offset)?;
+        self.apply_batch(commands).map(|_| ())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::DistinctEntityType;
+
+    fn rect(min: (f64, f64), max: (f64, f64)) -> CoordinateBounds {
+        CoordinateBounds { min, max }
+    }
+
+    #[test]
+    fn test_groups() -> Result<(), GraphError> {
+        let mut graph = KnowledgeGraph::default();
+        for coor in [(100.0, 100.0), (300.0, 200.0), (800.0, 800.0)] {
+            graph.add_entity(
+                "Node".to_string(),
+                DistinctEntityType::KnowledgePoint,
+                &[],
+                coor,
+            )?;
+        }
+
+        let week = graph.add_group(
+            "第3周".to_string(),
+            rect((0.0, 0.0), (400.0, 400.0)),
+            [255, 0, 0],
+            &[],
+        )?;
+        let lab = graph.add_group(
+            "实验部分".to_string(),
+            rect((600.0, 600.0), (1000.0, 1000.0)),
+            [0, 0, 255],
+            &[],
+        )?;
+        assert_eq!((week, lab), (1, 2));
+        assert_eq!(graph.current.nodes_in_group(week), vec![1, 2]);
+        assert_eq!(graph.current.nodes_in_group(lab), vec![3]);
+
+        // 分组框不是节点
+        assert_eq!(graph.current.nodes.len(), 3);
+
+        // 调整大小
+        let mut resized = graph.current.group(week).unwrap().clone();
+        resized.rect = rect((0.0, 0.0), (200.0, 200.0));
+        graph.update_group(resized)?;
+        assert_eq!(graph.current.nodes_in_group(week), vec![1]);
+        graph.undo()?;
+        assert_eq!(graph.current.nodes_in_group(week), vec![1, 2]);
+
+        // 平移分组框及其中的节点，整体作为一次撤回
+        let undo_len = graph.undo_len();
+        graph.move_group(week, (1000.0, 50.0))?;
+        assert_eq!(graph.undo_len(), undo_len + 1);
+        assert_eq!(
+            graph.current.group(week).unwrap().rect,
+            rect((1000.0, 50.0), (1400.0, 450.0))
+        );
+        assert_eq!(graph.current.nodes[&1].coor, (1100.0, 150.0));
+        assert_eq!(graph.current.nodes[&2].coor, (1300.0, 250.0));
+        assert_eq!(graph.current.nodes[&3].coor, (800.0, 800.0));
+        graph.undo()?;
+        assert_eq!(graph.current.nodes[&1].coor, (100.0, 100.0));
+
+        // 删除分组框不影响节点，删除后 ID 不会与已有的分组框重复
+        graph.remove_group(week)?;
+        assert!(graph.current.group(week).is_none());
+        assert_eq!(graph.current.nodes.len(), 3);
+        let id = graph.add_group(
+            "新分组".to_string(),
+            rect((0.0, 0.0), (1.0, 1.0)),
+            [0; 3],
+            &[],
+        )?;
+        assert_eq!(id, 3);
+
+        assert_eq!(graph.remove_group(100), Err(GraphError::GroupNotFound(100)));
+        assert_eq!(
+            graph.move_group(100, (1.0, 1.0)),
+            Err(GraphError::GroupNotFound(100))
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_member_groups() -> Result<(), GraphError> {
+        let mut graph = KnowledgeGraph::default();
+        let ids = [(100.0, 100.0), (500.0, 300.0), (900.0, 100.0)]
+            .into_iter()
+            .map(|coor| {
+                graph.add_entity(
+                    "Node".to_string(),
+                    DistinctEntityType::KnowledgePoint,
+                    &[],
+                    coor,
+                )
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        // 成员不存在时不添加
+        let empty = rect((0.0, 0.0), (0.0, 0.0));
+        let undo_len = graph.undo_len();
+        assert_eq!(
+            graph.add_group("第一章".to_string(), empty, [0; 3], &[ids[0], 100]),
+            Err(GraphError::EntityNotFound(100))
+        );
+        assert_eq!(graph.undo_len(), undo_len);
+
+        // 范围包住所有成员，不包括范围内的其他节点
+        let chapter = graph.add_group("第一章".to_string(), empty, [0; 3], &[ids[0], ids[2]])?;
+        let group = graph.current.group(chapter).unwrap().clone();
+        let (w, h) = MEMBER_MARGIN;
+        assert_eq!(
+            graph.current.group_bounds(&group),
+            rect((100.0 - w, 100.0 - h), (900.0 + w, 100.0 + h))
+        );
+        assert_eq!(graph.current.nodes_in_group(chapter), vec![ids[0], ids[2]]);
+
+        // 成员移动后范围随之变化，移动分组框时只移动成员
+        graph.update_entity_position(ids[2], (1100.0, 100.0))?;
+        assert_eq!(graph.current.group_bounds(&group).max.0, 1100.0 + w);
+        graph.move_group(chapter, (0.0, 100.0))?;
+        assert_eq!(graph.current.nodes[&ids[0]].coor, (100.0, 200.0));
+        assert_eq!(graph.current.nodes[&ids[1]].coor, (500.0, 300.0));
+
+        // 删除节点时从成员中移除，成员都被删除后使用保存的范围
+        graph.remove_entity(ids[0])?;
+        graph.remove_entity(ids[2])?;
+        let group = graph.current.group(chapter).unwrap();
+        assert!(group.members.is_empty());
+        assert_eq!(
+            graph.current.group_bounds(group),
+            rect((0.0, 100.0), (0.0, 100.0))
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_frame_around() {
+        assert_eq!(frame_around([], (10.0, 10.0)), None);
+        let frame = frame_around([(100.0, 100.0), (300.0, 50.0)], (50.0, 20.0)).unwrap();
+        assert_eq!(frame.min, (50.0, 30.0));
+        assert_eq!(frame.max, (350.0, 120.0));
+    }
+}
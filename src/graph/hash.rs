@@ -0,0 +1,109 @@
+//! 快照的内容哈希。自动保存时与上次写入文件的哈希比较，内容未变时跳过写入，
+//! 比每次序列化后比较字符串开销小得多。
+
+use std::hash::{DefaultHasher, Hash, Hasher};
+
+use super::{AddonEntityType, Snapshot};
+
+impl Snapshot {
+    /// 内容哈希：节点、边、边的标签和折点按 ID 排序后逐项哈希，与哈希表的内部顺序无关；
+    /// 元数据和分组框较小，以 JSON 形式参与哈希。latest_id 在读取时重新计算，不参与哈希。
+    /// 同一程序中内容相同的快照哈希相同，不同版本的程序之间不保证一致，不应保存到文件
+    pub fn content_hash(&self) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        self.title.hash(&mut hasher);
+
+        let mut nodes = self.nodes.values().collect::<Vec<_>>();
+        nodes.sort_unstable_by_key(|node| node.id);
+        nodes.len().hash(&mut hasher);
+        for node in nodes {
+            node.id.hash(&mut hasher);
+            node.content.hash(&mut hasher);
+            node.distinct_type.hash(&mut hasher);
+            for addon_type in AddonEntityType::ALL {
+                node.addon_types.contains(&addon_type).hash(&mut hasher);
+            }
+            node.coor.0.to_bits().hash(&mut hasher);
+            node.coor.1.to_bits().hash(&mut hasher);
+            node.notes.hash(&mut hasher);
+            node.order_index.hash(&mut hasher);
+            node.reference.hash(&mut hasher);
+            node.links.hash(&mut hasher);
+            node.flag.hash(&mut hasher);
+        }
+
+        let mut edges = self.edges.iter().collect::<Vec<_>>();
+        edges.sort_unstable_by_key(|(edge, _)| **edge);
+        edges.hash(&mut hasher);
+        let mut labels = self.edge_labels.iter().collect::<Vec<_>>();
+        labels.sort_unstable_by_key(|(edge, _)| **edge);
+        labels.hash(&mut hasher);
+        let mut waypoints = self.edge_waypoints.iter().collect::<Vec<_>>();
+        waypoints.sort_unstable_by_key(|(edge, _)| **edge);
+        for (edge, points) in waypoints {
+            edge.hash(&mut hasher);
+            for (x, y) in points {
+                x.to_bits().hash(&mut hasher);
+                y.to_bits().hash(&mut hasher);
+            }
+        }
+
+        // 序列化这两项不会失败，失败时也只是少哈希一部分内容
+        serde_json::to_vec(&self.metadata)
+            .unwrap_or_default()
+            .hash(&mut hasher);
+        serde_json::to_vec(&self.groups)
+            .unwrap_or_default()
+            .hash(&mut hasher);
+
+        hasher.finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::{DistinctEntityType, GraphMetadata, KnowledgeGraph, Relation};
+
+    #[test]
+    fn test_content_hash() {
+        let mut graph = KnowledgeGraph::default();
+        assert_eq!(
+            graph.current.content_hash(),
+            KnowledgeGraph::default().current.content_hash()
+        );
+        for content in ["线性表", "栈", "队列"] {
+            graph
+                .add_entity(
+                    content.to_string(),
+                    DistinctEntityType::KnowledgePoint,
+                    &[AddonEntityType::Knowledge],
+                    (0.0, 0.0),
+                )
+                .unwrap();
+        }
+        graph.add_edge(1, 2, Relation::Contain).unwrap();
+        graph.add_edge(1, 3, Relation::Contain).unwrap();
+        let hash = graph.current.content_hash();
+
+        // 读回的快照与原快照的哈希表顺序可能不同，哈希仍相同
+        let decoded = Snapshot::from_xml(&graph.current.to_xml().unwrap()).unwrap();
+        assert_eq!(decoded.content_hash(), hash);
+
+        // 每项修改都改变哈希，撤回后恢复
+        graph.update_entity_position(2, (1.0, 0.0)).unwrap();
+        assert_ne!(graph.current.content_hash(), hash);
+        graph.undo().unwrap();
+        assert_eq!(graph.current.content_hash(), hash);
+
+        graph.set_edge_label(1, 2, "前置".to_string()).unwrap();
+        assert_ne!(graph.current.content_hash(), hash);
+        graph.undo().unwrap();
+
+        graph.set_metadata(GraphMetadata {
+            max_nodes: Some(10),
+            ..Default::default()
+        });
+        assert_ne!(graph.current.content_hash(), hash);
+    }
+}
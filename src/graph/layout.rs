@@ -0,0 +1,225 @@
+//! 布局模块，为没有坐标的图谱自动生成节点位置，也可按用户的偏好重新排列整个图谱。
+
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use super::{Command, KnowledgeGraph, Snapshot};
+use crate::error::GraphError;
+
+const LAYER_SPACING: f64 = 200.0; // 相邻层的默认间距
+const NODE_SPACING: f64 = 200.0; // 同一层内相邻节点的默认间距
+
+/// 分层布局的方向
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum LayoutDirection {
+    #[default]
+    TopDown, // 层从上到下排列，同层节点从左到右
+    LeftRight, // 层从左到右排列，同层节点从上到下
+}
+
+impl LayoutDirection {
+    /// 所有方向，按界面中的顺序排列
+    pub const ALL: [LayoutDirection; 2] = [LayoutDirection::TopDown, LayoutDirection::LeftRight];
+
+    /// 方向在界面中的名称
+    pub fn name(self) -> &'static str {
+        match self {
+            LayoutDirection::TopDown => "自上而下",
+            LayoutDirection::LeftRight => "从左到右",
+        }
+    }
+}
+
+/// 分层布局的选项
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)] // 旧版本保存的设置缺少字段时使用默认值
+pub struct LayoutOptions {
+    pub direction: LayoutDirection, // 层排列的方向
+    pub layer_spacing: f64,         // 相邻层之间的距离
+    pub node_spacing: f64,          // 同一层内相邻节点之间的距离
+}
+
+impl Default for LayoutOptions {
+    fn default() -> Self {
+        Self {
+            direction: LayoutDirection::default(),
+            layer_spacing: LAYER_SPACING,
+            node_spacing: NODE_SPACING,
+        }
+    }
+}
+
+impl Snapshot {
+    /// 以默认选项进行分层布局，见 apply_layered_layout_with
+    #[inline]
+    pub fn apply_layered_layout(&mut self) {
+        self.apply_layered_layout_with(&LayoutOptions::default());
+    }
+
+    /// 分层布局：沿边的方向将节点分层，每层按 ID 排列，层按 options 的方向依次排列。
+    /// 节点的层数为从没有入边的节点出发的最长路径长度。存在环时，层数以节点数为上限。
+    pub fn apply_layered_layout_with(&mut self, options: &LayoutOptions) {
+        for (id, coor) in self.layered_positions(options) {
+            if let Some(node) = self.nodes.get_mut(&id) {
+                node.coor = coor;
+            }
+        }
+    }
+
+    /// 分层布局后各节点的新位置，只包含位置改变的节点
+    pub fn layered_positions(&self, options: &LayoutOptions) -> HashMap<u64, (f64, f64)> {
+        // 每层按 ID 排序后依次排列
+        let mut by_layer: HashMap<usize, Vec<u64>> = HashMap::new();
+        for (id, layer) in self.layers() {
+            by_layer.entry(layer).or_default().push(id);
+        }
+
+        let mut positions = HashMap::new();
+        for (layer, mut ids) in by_layer {
+            ids.sort_unstable();
+            for (i, id) in ids.into_iter().enumerate() {
+                let along = layer as f64 * options.layer_spacing;
+                let across = i as f64 * options.node_spacing;
+                let coor = match options.direction {
+                    LayoutDirection::TopDown => (across, along),
+                    LayoutDirection::LeftRight => (along, across),
+                };
+                if self.nodes.get(&id).is_some_and(|node| node.coor != coor) {
+                    positions.insert(id, coor);
+                }
+            }
+        }
+        positions
+    }
+
+    /// 计算每个节点所在的层
+    fn layers(&self) -> HashMap<u64, usize> {
+        let mut layers = self
+            .nodes
+            .keys()
+            .map(|id| (*id, 0))
+            .collect::<HashMap<_, _>>();
+
+        // 反复松弛，最长路径不超过节点数，从而保证环也能结束
+        let max_layer = self.nodes.len().saturating_sub(1);
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for (from, to) in self.edges.keys() {
+                let (Some(&from_layer), Some(&to_layer)) = (layers.get(from), layers.get(to))
+                else {
+                    continue;
+                };
+                let layer = (from_layer + 1).min(max_layer);
+                if layer > to_layer {
+                    layers.insert(*to, layer);
+                    changed = true;
+                }
+            }
+        }
+
+        layers
+    }
+}
+
+impl KnowledgeGraph {
+    /// 按 options 对整个图谱进行分层布局，整体作为一次可撤回的操作，返回移动的节点数
+    pub fn auto_layout(&mut self, options: &LayoutOptions) -> Result<usize, GraphError> {
+        let moves = self.current.layered_positions(options);
+        let count = moves.len();
+        if count > 0 {
+            self.apply_batch(
+                moves
+                    .into_iter()
+                    .map(|(id, coor)| Command::UpdateEntityPosition { id, coor }),
+            )?;
+        }
+        Ok(count)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::{DistinctEntityType, Relation};
+
+    #[test]
+    fn test_layered_layout() {
+        let mut graph = KnowledgeGraph::default();
+        let ids = (0..4)
+            .map(|i| {
+                graph
+                    .add_entity(
+                        format!("Node {i}"),
+                        DistinctEntityType::KnowledgePoint,
+                        &[],
+                        (0.0, 0.0),
+                    )
+                    .unwrap()
+            })
+            .collect::<Vec<_>>();
+        graph.add_edge(ids[0], ids[1], Relation::Contain).unwrap();
+        graph.add_edge(ids[0], ids[2], Relation::Contain).unwrap();
+        graph.add_edge(ids[2], ids[3], Relation::Order).unwrap();
+        graph.add_edge(ids[3], ids[2], Relation::Order).unwrap(); // 环
+
+        let mut snapshot = graph.current.clone();
+        snapshot.apply_layered_layout();
+        let coor = |id: u64| snapshot.nodes[&id].coor;
+        assert_eq!(coor(ids[0]), (0.0, 0.0));
+        assert_eq!(coor(ids[1]).1, 200.0);
+        assert_eq!(coor(ids[1]).0, 0.0);
+        // 不同节点的位置互不重叠
+        let mut positions = ids
+            .iter()
+            .map(|id| format!("{:?}", coor(*id)))
+            .collect::<Vec<_>>();
+        positions.sort();
+        positions.dedup();
+        assert_eq!(positions.len(), 4);
+    }
+
+    #[test]
+    fn test_auto_layout() {
+        let mut graph = KnowledgeGraph::default();
+        for i in 0..3 {
+            graph
+                .add_entity(
+                    format!("Node {i}"),
+                    DistinctEntityType::KnowledgePoint,
+                    &[],
+                    (0.0, 0.0),
+                )
+                .unwrap();
+        }
+        graph.add_edge(1, 2, Relation::Contain).unwrap();
+        graph.add_edge(1, 3, Relation::Contain).unwrap();
+
+        let options = LayoutOptions {
+            direction: LayoutDirection::LeftRight,
+            layer_spacing: 300.0,
+            node_spacing: 150.0,
+        };
+        let undo_len = graph.undo_len();
+        // 节点 1 已在原点，不需要移动
+        assert_eq!(graph.auto_layout(&options), Ok(2));
+        let coor = |graph: &KnowledgeGraph, id: u64| graph.current.nodes[&id].coor;
+        assert_eq!(coor(&graph, 1), (0.0, 0.0));
+        assert_eq!(coor(&graph, 2), (300.0, 0.0));
+        assert_eq!(coor(&graph, 3), (300.0, 150.0));
+        assert_eq!(graph.undo_len(), undo_len + 1);
+        assert_eq!(graph.auto_layout(&options), Ok(0));
+
+        let top_down = LayoutOptions {
+            direction: LayoutDirection::TopDown,
+            ..options
+        };
+        assert_eq!(graph.auto_layout(&top_down), Ok(2));
+        assert_eq!(coor(&graph, 2), (0.0, 300.0));
+        assert_eq!(coor(&graph, 3), (150.0, 300.0));
+
+        graph.undo().unwrap();
+        assert_eq!(coor(&graph, 3), (300.0, 150.0));
+    }
+}
@@ -0,0 +1,212 @@
+//! 教学要求矩阵模块。每行为一个知识单元，每列为一种附加类型，
+//! 单元格为该知识单元通过包含关系直接或间接包含的节点中带有该附加类型的数量。
+//!
+//! 节点只沿主父节点归属，多个父节点包含同一节点时只计入主父节点所在的知识单元。
+//! 知识单元嵌套时，外层单元同样统计内层单元及其包含的节点；
+//! 不在任何知识单元下的其他节点汇总为“未分配”一行。仅作参考的节点不参与统计。
+
+use std::collections::BTreeSet;
+
+use quick_xml::escape::escape;
+
+use super::{
+    AddonEntityType, DistinctEntityType, Snapshot,
+    primary::{primary_children, primary_descendants},
+};
+
+/// “未分配”一行使用的 ID。节点 ID 从 1 开始分配，不会与之冲突
+pub const UNASSIGNED_ROW: u64 = 0;
+
+/// “未分配”一行的名称
+const UNASSIGNED_LABEL: &str = "未分配";
+
+impl Snapshot {
+    /// 教学要求矩阵，每行为知识单元的 ID 和按 AddonEntityType::ALL 顺序的计数，按 ID 排序。
+    /// 多个父节点包含同一节点时，只计入主父节点所在的知识单元。
+    /// 存在不在任何知识单元下的节点时，最后一行为 ID 为 UNASSIGNED_ROW 的“未分配”
+    pub fn addon_matrix(&self) -> Vec<(u64, [usize; 6])> {
+        if let Some(snapshot) = self.without_references() {
+            return snapshot.addon_matrix();
+        }
+        let mut units = self
+            .nodes
+            .values()
+            .filter(|node| node.distinct_type == DistinctEntityType::KnowledgeUnit)
+            .map(|node| node.id)
+            .collect::<Vec<_>>();
+        units.sort_unstable();
+
+        let children = primary_children(&self.primary_parents());
+        let mut assigned = units.iter().copied().collect::<BTreeSet<_>>();
+        let mut rows = Vec::with_capacity(units.len() + 1);
+        for unit in units {
+            let descendants = primary_descendants(&children, unit);
+            rows.push((unit, self.count_addons(descendants.iter().copied())));
+            assigned.extend(descendants);
+        }
+
+        let unassigned = self
+            .nodes
+            .keys()
+            .filter(|id| !assigned.contains(id))
+            .copied()
+            .collect::<Vec<_>>();
+        if !unassigned.is_empty() {
+            rows.push((UNASSIGNED_ROW, self.count_addons(unassigned.into_iter())));
+        }
+        rows
+    }
+
+    /// 统计节点中带有每种附加类型的数量
+    fn count_addons(&self, ids: impl Iterator<Item = u64>) -> [usize; 6] {
+        let mut counts = [0; 6];
+        for node in ids.filter_map(|id| self.nodes.get(&id)) {
+            for (count, addon) in counts.iter_mut().zip(AddonEntityType::ALL) {
+                if node.addon_types.contains(&addon) {
+                    *count += 1;
+                }
+            }
+        }
+        counts
+    }
+
+    /// 矩阵中一行的名称，知识单元为其内容
+    fn matrix_row_label(&self, id: u64) -> &str {
+        if id == UNASSIGNED_ROW {
+            return UNASSIGNED_LABEL;
+        }
+        self.nodes.get(&id).map_or("", |node| node.content.as_str())
+    }
+
+    /// 将教学要求矩阵导出为 CSV，首列为 ID（“未分配”一行为空），第二列为知识单元
+    pub fn to_matrix_csv(&self) -> String {
+        let header = ["ID", "知识单元"]
+            .into_iter()
+            .map(str::to_string)
+            .chain(AddonEntityType::ALL.iter().map(|addon| addon.to_string()))
+            .collect::<Vec<_>>();
+        let mut lines = vec![header.join(",")];
+        for (id, counts) in self.addon_matrix() {
+            let id_field = if id == UNASSIGNED_ROW {
+                String::new()
+            } else {
+                id.to_string()
+            };
+            let fields = [id_field, csv_field(self.matrix_row_label(id))]
+                .into_iter()
+                .chain(counts.iter().map(usize::to_string))
+                .collect::<Vec<_>>();
+            lines.push(fields.join(","));
+        }
+        lines.join("\n") + "\n"
+    }
+
+    /// 将教学要求矩阵导出为可直接在浏览器中打开的 HTML 表格
+    pub fn to_matrix_html(&self) -> String {
+        let mut lines = vec![
+            "<!DOCTYPE html>".to_string(),
+            r#"<html lang="zh-CN">"#.to_string(),
+            r#"<head><meta charset="UTF-8"><title>教学要求矩阵</title></head>"#.to_string(),
+            "<body>".to_string(),
+            r#"<table border="1">"#.to_string(),
+        ];
+        let header = AddonEntityType::ALL
+            .iter()
+            .map(|addon| format!("<th>{addon}</th>"))
+            .collect::<String>();
+        lines.push(format!("  <tr><th>知识单元</th>{header}</tr>"));
+        for (id, counts) in self.addon_matrix() {
+            let cells = counts
+                .iter()
+                .map(|count| format!("<td>{count}</td>"))
+                .collect::<String>();
+            let label = escape(self.matrix_row_label(id)).replace('\n', "<br>");
+            lines.push(format!("  <tr><th>{label}</th>{cells}</tr>"));
+        }
+        lines.push("</table>".to_string());
+        lines.push("</body>".to_string());
+        lines.push("</html>".to_string());
+
+        lines.join("\n") + "\n"
+    }
+}
+
+/// 含有逗号、引号或换行的字段用引号包围，其中的引号加倍
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::graph::{KnowledgeGraph, Relation};
+
+    use super::*;
+
+    #[test]
+    fn test_addon_matrix() {
+        let mut graph = KnowledgeGraph::default();
+        let mut add = |content: &str, distinct_type, addon_types: &[AddonEntityType]| {
+            graph
+                .add_entity(content.to_string(), distinct_type, addon_types, (0.0, 0.0))
+                .unwrap()
+        };
+        let outer = add("外层, 单元", DistinctEntityType::KnowledgeUnit, &[]);
+        let inner = add(
+            "内层单元",
+            DistinctEntityType::KnowledgeUnit,
+            &[AddonEntityType::Thinking],
+        );
+        let a = add(
+            "A",
+            DistinctEntityType::KnowledgePoint,
+            &[AddonEntityType::Example],
+        );
+        let b = add(
+            "B",
+            DistinctEntityType::KnowledgePoint,
+            &[AddonEntityType::Example, AddonEntityType::Political],
+        );
+        let c = add(
+            "C",
+            DistinctEntityType::KnowledgePoint,
+            &[AddonEntityType::Question],
+        );
+        graph.add_edge(outer, inner, Relation::Contain).unwrap();
+        graph.add_edge(outer, a, Relation::Contain).unwrap();
+        graph.add_edge(inner, b, Relation::Contain).unwrap();
+        graph.add_edge(a, b, Relation::Contain).unwrap(); // b 有两个父节点
+        graph.add_edge(b, c, Relation::Order).unwrap(); // c 不在任何单元下
+
+        let matrix = graph.current.addon_matrix();
+        assert_eq!(
+            matrix,
+            vec![
+                (outer, [0, 1, 2, 0, 0, 1]),
+                (inner, [0, 0, 1, 0, 0, 1]),
+                (UNASSIGNED_ROW, [0, 0, 0, 1, 0, 0]),
+            ]
+        );
+
+        let csv = graph.current.to_matrix_csv();
+        let lines = csv.lines().collect::<Vec<_>>();
+        assert_eq!(lines[0], "ID,知识单元,知识,思维,示例,问题,练习,思政");
+        assert_eq!(lines[1], format!("{outer},\"外层, 单元\",0,1,2,0,0,1"));
+        assert_eq!(lines[3], ",未分配,0,0,0,1,0,0");
+        assert!(graph.current.to_matrix_html().contains("<th>未分配</th>"));
+
+        // 以 a 为 b 的主父节点后，b 不再计入内层单元
+        graph.set_primary_parent(b, a).unwrap();
+        let matrix = graph.current.addon_matrix();
+        assert_eq!(matrix[0], (outer, [0, 1, 2, 0, 0, 1]));
+        assert_eq!(matrix[1], (inner, [0; 6]));
+        graph.undo().unwrap();
+
+        // 所有节点都在单元下时没有“未分配”一行
+        graph.add_edge(inner, c, Relation::Contain).unwrap();
+        assert_eq!(graph.current.addon_matrix().len(), 2);
+    }
+}
@@ -0,0 +1,253 @@
+//! 节点合并模块，将重复的节点合并为一个。
+
+use std::collections::BTreeSet;
+
+use super::{Command, KnowledgeGraph, Snapshot};
+use crate::error::GraphError;
+
+/// 拼接内容时两个节点内容之间的分隔符
+const CONTENT_SEPARATOR: &str = "；";
+
+impl Snapshot {
+    /// 将 remove 合并到 keep 所需的命令：remove 的边改为连接 keep，
+    /// 会成为自环或 keep 已有同向的边时跳过；remove 的备注追加到 keep 的备注之后，
+    /// concat_content 为 true 时内容也以“；”拼接。keep 与 remove 相同时不需要任何命令
+    #[inline]
+    pub fn merge_commands(
+        &self,
+        keep: u64,
+        remove: u64,
+        concat_content: bool,
+    ) -> Result<Vec<Command>, GraphError> {
+        self.merge_entities_commands(keep, &[remove], concat_content)
+    }
+
+    /// 将 remove 中的所有节点合并到 keep 所需的命令，规则同 merge_commands：
+    /// 各节点的边改为连接 keep，成为自环或与已有的边、先前改接的边重复时跳过，
+    /// 备注和（concat_content 为 true 时）内容按 ID 顺序追加。remove 中与 keep 相同或重复的 ID 被忽略
+    pub fn merge_entities_commands(
+        &self,
+        keep: u64,
+        remove: &[u64],
+        concat_content: bool,
+    ) -> Result<Vec<Command>, GraphError> {
+        let kept = self
+            .nodes
+            .get(&keep)
+            .ok_or(GraphError::EntityNotFound(keep))?;
+        let remove = remove
+            .iter()
+            .copied()
+            .filter(|id| *id != keep)
+            .collect::<BTreeSet<_>>();
+        let mut removed = Vec::new();
+        for id in remove.iter() {
+            removed.push(self.nodes.get(id).ok_or(GraphError::EntityNotFound(*id))?);
+        }
+        if removed.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let mut commands = Vec::new();
+        let mut added = BTreeSet::new();
+        let mut edges = self.edges.iter().collect::<Vec<_>>();
+        edges.sort_unstable_by_key(|(key, _)| **key);
+        for (&(from, to), relation) in edges {
+            if !remove.contains(&from) && !remove.contains(&to) {
+                continue;
+            }
+            let redirect = |id: u64| if remove.contains(&id) { keep } else { id };
+            let (new_from, new_to) = (redirect(from), redirect(to));
+            if new_from == new_to
+                || self.edges.contains_key(&(new_from, new_to))
+                || !added.insert((new_from, new_to))
+            {
+                continue;
+            }
+            commands.push(Command::AddEdge {
+                from: new_from,
+                to: new_to,
+                relation: *relation,
+            });
+            if let Some(label) = self.edge_labels.get(&(from, to)) {
+                commands.push(Command::SetEdgeLabel {
+                    from: new_from,
+                    to: new_to,
+                    label: label.clone(),
+                });
+            }
+            let waypoints = self.waypoints(from, to);
+            if !waypoints.is_empty() {
+                commands.push(Command::SetEdgeWaypoints {
+                    from: new_from,
+                    to: new_to,
+                    waypoints: waypoints.to_vec(),
+                });
+            }
+        }
+
+        let mut content = kept.content.clone();
+        let mut notes = kept.notes.clone();
+        for node in removed {
+            if concat_content && !node.content.is_empty() && node.content != kept.content {
+                if !content.is_empty() {
+                    content.push_str(CONTENT_SEPARATOR);
+                }
+                content.push_str(&node.content);
+            }
+            if !node.notes.is_empty() {
+                if !notes.is_empty() {
+                    notes.push('\n');
+                }
+                notes.push_str(&node.notes);
+            }
+        }
+        if content != kept.content {
+            commands.push(Command::UpdateEntityContent {
+                id: keep,
+                content,
+                distinct_type: kept.distinct_type,
+                addon_types: kept.addon_types.iter().copied().collect(),
+            });
+        }
+        if notes != kept.notes {
+            commands.push(Command::UpdateEntityNotes { id: keep, notes });
+        }
+
+        commands.extend(remove.into_iter().map(Command::RemoveEntity));
+        Ok(commands)
+    }
+}
+
+impl KnowledgeGraph {
+    /// 将 remove 节点合并到 keep 节点并删除 remove，保留 keep 的内容，整体作为一次可撤回的操作
+    #[inline]
+    pub fn merge_nodes(&mut self, keep: u64, remove: u64) -> Result<(), GraphError> {
+        self.merge_nodes_with(keep, remove, false)
+    }
+
+    /// 同 merge_nodes，concat_content 为 true 时将两个节点的内容拼接
+    pub fn merge_nodes_with(
+        &mut self,
+        keep: u64,
+        remove: u64,
+        concat_content: bool,
+    ) -> Result<(), GraphError> {
+        let commands = self.current.merge_commands(keep, remove, concat_content)?;
+        if !commands.is_empty() {
+            self.apply_batch(commands)?;
+        }
+        Ok(())
+    }
+
+    /// 将 remove 中的所有节点合并到 keep 节点并删除，保留 keep 的内容，
+    /// 重复的边和自环被跳过，整体作为一次可撤回的操作
+    pub fn merge_entities(&mut self, keep: u64, remove: &[u64]) -> Result<(), GraphError> {
+        let commands = self.current.merge_entities_commands(keep, remove, false)?;
+        if !commands.is_empty() {
+            self.apply_batch(commands)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::{DistinctEntityType, Relation};
+
+    #[test]
+    fn test_merge_nodes() {
+        let mut graph = KnowledgeGraph::default();
+        for content in ["父节点", "算法", "算法（重复）", "子节点"] {
+            graph
+                .add_entity(
+                    content.to_string(),
+                    DistinctEntityType::KnowledgePoint,
+                    &[],
+                    (0.0, 0.0),
+                )
+                .unwrap();
+        }
+        graph.add_edge(1, 2, Relation::Contain).unwrap();
+        graph.add_edge(1, 3, Relation::Order).unwrap(); // 与已有的边重复
+        graph.add_edge(3, 4, Relation::Contain).unwrap();
+        graph.add_edge(2, 3, Relation::Order).unwrap(); // 合并后成为自环
+        graph.set_edge_label(3, 4, "标签".to_string()).unwrap();
+        graph
+            .update_entity_with(3, |node| node.notes = "来自重复节点".to_string())
+            .unwrap();
+
+        let undo_len = graph.undo_len();
+        graph.merge_nodes_with(2, 3, true).unwrap();
+        let snapshot = graph.current_snapshot();
+        assert!(!snapshot.nodes.contains_key(&3));
+        assert_eq!(snapshot.nodes[&2].content, "算法；算法（重复）");
+        assert_eq!(snapshot.nodes[&2].notes, "来自重复节点");
+        assert_eq!(snapshot.edges.len(), 2);
+        assert_eq!(snapshot.edges[&(1, 2)], Relation::Contain);
+        assert_eq!(snapshot.edges[&(2, 4)], Relation::Contain);
+        assert_eq!(snapshot.edge_labels[&(2, 4)], "标签");
+        assert_eq!(graph.undo_len(), undo_len + 1);
+
+        // 默认保留 keep 的内容
+        graph.undo().unwrap();
+        graph.merge_nodes(2, 3).unwrap();
+        assert_eq!(graph.current_snapshot().nodes[&2].content, "算法");
+
+        assert_eq!(graph.merge_nodes(2, 3), Err(GraphError::EntityNotFound(3)));
+        assert_eq!(graph.merge_nodes(2, 2), Ok(()));
+    }
+
+    #[test]
+    fn test_merge_entities() {
+        let mut graph = KnowledgeGraph::default();
+        for content in ["单元一", "单元二", "栈", "栈", "栈", "出栈"] {
+            graph
+                .add_entity(
+                    content.to_string(),
+                    DistinctEntityType::KnowledgePoint,
+                    &[],
+                    (0.0, 0.0),
+                )
+                .unwrap();
+        }
+        graph.add_edge(1, 3, Relation::Contain).unwrap();
+        graph.add_edge(2, 4, Relation::Contain).unwrap();
+        graph.add_edge(1, 5, Relation::Contain).unwrap(); // 与已有的边重复
+        graph.add_edge(4, 6, Relation::Contain).unwrap();
+        graph.add_edge(5, 6, Relation::Order).unwrap(); // 与先前改接的边重复
+        graph.add_edge(4, 5, Relation::Order).unwrap(); // 合并后成为自环
+        graph
+            .update_entity_with(4, |node| node.notes = "来自单元二".to_string())
+            .unwrap();
+        graph
+            .update_entity_with(5, |node| node.notes = "另一处".to_string())
+            .unwrap();
+
+        let undo_len = graph.undo_len();
+        graph.merge_entities(3, &[5, 4, 3, 4]).unwrap();
+        let snapshot = graph.current_snapshot();
+        assert_eq!(
+            snapshot.nodes.keys().copied().collect::<BTreeSet<_>>(),
+            BTreeSet::from([1, 2, 3, 6])
+        );
+        assert_eq!(snapshot.nodes[&3].content, "栈");
+        assert_eq!(snapshot.nodes[&3].notes, "来自单元二\n另一处");
+        assert_eq!(snapshot.edges.len(), 3);
+        assert_eq!(snapshot.edges[&(1, 3)], Relation::Contain);
+        assert_eq!(snapshot.edges[&(2, 3)], Relation::Contain);
+        assert_eq!(snapshot.edges[&(3, 6)], Relation::Contain);
+        assert_eq!(graph.undo_len(), undo_len + 1);
+
+        graph.undo().unwrap();
+        assert_eq!(graph.current_snapshot().nodes.len(), 6);
+        assert_eq!(
+            graph.merge_entities(3, &[4, 99]),
+            Err(GraphError::EntityNotFound(99))
+        );
+        assert_eq!(graph.current_snapshot().nodes.len(), 6);
+        assert_eq!(graph.merge_entities(3, &[3]), Ok(()));
+        assert_eq!(graph.undo_len(), undo_len);
+    }
+}
@@ -0,0 +1,79 @@
+//! 图谱元数据模块，记录对接平台的限制、画布书签等不属于节点和边的信息。
+//! 元数据以可选的扩展元素保存在 XML 中，旧版本读取时会忽略。
+
+use serde::{Deserialize, Serialize};
+
+/// 节点内容字数的默认建议上限
+pub const DEFAULT_CONTENT_LIMIT: usize = 500;
+
+/// 对接平台默认接受的坐标范围，超出的节点在平台上会被裁掉
+pub const DEFAULT_COORDINATE_BOUNDS: CoordinateBounds = CoordinateBounds {
+    min: (0.0, 0.0),
+    max: (4000.0, 4000.0),
+};
+
+/// 图谱元数据
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct GraphMetadata {
+    pub max_nodes: Option<usize>,         // 最大节点数，None 表示不限制
+    pub max_edges: Option<usize>,         // 最大边数，None 表示不限制
+    pub max_content_chars: Option<usize>, // 节点内容字数的建议上限，None 表示使用默认值
+    pub coordinate_bounds: Option<CoordinateBounds>, // 节点坐标的范围，None 表示使用默认值
+    pub bookmarks: Vec<Bookmark>,         // 画布书签，按添加的顺序排列
+    pub restrict_charset: bool,           // 是否检查节点内容中对接平台不支持的字符
+}
+
+/// 节点坐标的范围，包含边界
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct CoordinateBounds {
+    pub min: (f64, f64), // 左上角
+    pub max: (f64, f64), // 右下角
+}
+
+impl CoordinateBounds {
+    /// 坐标是否在范围内
+    #[inline]
+    pub fn contains(&self, (x, y): (f64, f64)) -> bool {
+        (self.min.0..=self.max.0).contains(&x) && (self.min.1..=self.max.1).contains(&y)
+    }
+}
+
+/// 画布书签，记录一个命名的视图位置
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Bookmark {
+    pub name: String,
+    pub scroll_offset: (f32, f32), // 画布的滚动偏移
+    pub zoom: f32,                 // 缩放比例
+}
+
+impl GraphMetadata {
+    /// 是否为默认值，默认值不需要保存
+    #[inline]
+    pub fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// 节点内容字数的建议上限，超过时只给出警告
+    #[inline]
+    pub fn content_limit(&self) -> usize {
+        self.max_content_chars.unwrap_or(DEFAULT_CONTENT_LIMIT)
+    }
+
+    /// 节点坐标的范围
+    #[inline]
+    pub fn coordinate_bounds(&self) -> CoordinateBounds {
+        self.coordinate_bounds.unwrap_or(DEFAULT_COORDINATE_BOUNDS)
+    }
+
+    /// 在已有 count 个节点时，还可添加的节点数。不限制时返回 None。
+    #[inline]
+    pub fn remaining_nodes(&self, count: usize) -> Option<usize> {
+        self.max_nodes.map(|max| max.saturating_sub(count))
+    }
+
+    /// 在已有 count 条边时，还可添加的边数。不限制时返回 None。
+    #[inline]
+    pub fn remaining_edges(&self, count: usize) -> Option<usize> {
+        self.max_edges.map(|max| max.saturating_sub(count))
+    }
+}
@@ -4,34 +4,263 @@
 //! - 只支持教学知识图谱，不支持能力知识图谱；
 //! - 节点不支持资源型独立实体类型；
 
+use std::mem::size_of;
+
 use im::{HashMap, Vector};
+use serde::{Deserialize, Serialize};
 
 use crate::error::GraphError;
-pub use node::{AddonEntityType, DistinctEntityType, EntityNode, Relation};
-
+pub use allocation::{IdAllocation, MAX_ENTITY_ID};
+pub use bounds::DEFAULT_OUTLIER_DISTANCE;
+pub use charset::{is_supported_char, strip_unsupported};
+#[cfg(feature = "gui")]
+pub(crate) use codec::ADDON_TYPES as ATTACH_ORDER;
+#[cfg(any(feature = "gui", feature = "capi"))]
+pub(crate) use codec::addon_flags;
+pub use codec::{
+    DEFAULT_TITLE, LevelPolicy, XmlOptions, decode_xml, export_checks, peek_title, peek_xml_title,
+};
+pub use command::{Command, CommandResult};
+pub use diff::{Change, SnapshotDiff};
+pub use glossary::{Glossary, TermChecker, TermMatch, apply_term_matches};
+pub use group::{DEFAULT_GROUP_COLOR, GroupFrame};
+pub use layout::{LayoutDirection, LayoutOptions};
+pub use matrix::UNASSIGNED_ROW;
+pub use metadata::{
+    Bookmark, CoordinateBounds, DEFAULT_CONTENT_LIMIT, DEFAULT_COORDINATE_BOUNDS, GraphMetadata,
+};
+pub use node::{
+    AddonEntityType, DistinctEntityType, EntityNode, EntityNodeBuilder, Flag, Relation,
+};
+pub use normalize::{NormalizeOptions, normalize_content};
+pub use overlap::NODE_SIZE;
+pub use placement::suggest_child_position;
+pub use reciprocal::ReciprocalEdge;
+pub use recording::{Macro, MacroStep, Recorder};
+pub use relation::{
+    CUSTOM_RELATION_START, MarkerStyle, RelationRegistry, RelationType, relation_registry,
+    set_relation_registry,
+};
+pub use removal::RemovePolicy;
+pub use repair::RepairReport;
+pub use split::{UNASSIGNED_PART, UnitSplit, file_stem};
+pub use stats::Statistics;
+pub use thumbnail::THUMBNAIL_WIDTH;
+pub use validate::{IssueLevel, ValidationIssue};
+
+mod adjacency;
+mod allocation;
+mod ancestry;
+mod bounds;
+mod charset;
 mod codec;
+mod command;
+mod compact;
+mod coordinates;
+mod diff;
+mod flag;
+mod glossary;
+mod group;
+mod hash;
+mod layout;
+mod matrix;
+mod merge;
+mod metadata;
 mod node;
+mod normalize;
+mod overlap;
+mod pdf;
+mod placement;
+mod primary;
+mod reciprocal;
+mod recording;
+mod relation;
+mod removal;
+mod repair;
+mod scale;
+mod split;
+mod stats;
+mod text;
+mod thumbnail;
+mod validate;
+mod versions;
+mod waypoint;
+
+/// 撤回栈默认保存的最大快照数
+pub const DEFAULT_MAX_HISTORY: usize = 100;
 
 /// 知识图谱快照，用于撤回和重做。
 /// 使用了 im crate 提供的持久化数据结构，避免了不必要的数据复制，提高了性能。
 /// 详见：https://docs.rs/im/15.0.0/im/
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Snapshot {
+    #[serde(default)]
+    pub title: String, // 图谱标题，即 XML 中 KG 元素的文本
+    #[serde(with = "entry_list")]
     pub nodes: HashMap<u64, EntityNode>,
+    #[serde(with = "entry_list")]
     pub edges: HashMap<(u64, u64), Relation>,
+    #[serde(default, with = "entry_list")]
+    pub edge_labels: HashMap<(u64, u64), String>, // 边的自定义标签，只保存非空的标签
+    #[serde(default, with = "entry_list")]
+    pub edge_waypoints: HashMap<(u64, u64), Vec<(f64, f64)>>, // 边的手动折点，从起点到终点排列，只保存非空的
+    pub metadata: GraphMetadata,
+    #[serde(default)]
+    pub groups: Vec<GroupFrame>, // 分组框，只用于展示，按添加的顺序绘制
     latest_id: u64,
 }
 
+/// 哈希表以 (键, 值) 的列表序列化，使快照也能保存为 JSON 等键只能是字符串的格式
+mod entry_list {
+    use std::hash::Hash;
+
+    use im::HashMap;
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    pub fn serialize<K, V, S>(map: &HashMap<K, V>, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        K: Serialize + Hash + Eq + Clone,
+        V: Serialize + Clone,
+        S: Serializer,
+    {
+        serializer.collect_seq(map.iter())
+    }
+
+    pub fn deserialize<'de, K, V, D>(deserializer: D) -> Result<HashMap<K, V>, D::Error>
+    where
+        K: Deserialize<'de> + Hash + Eq + Clone,
+        V: Deserialize<'de> + Clone,
+        D: Deserializer<'de>,
+    {
+        let entries = Vec::<(K, V)>::deserialize(deserializer)?;
+        Ok(entries.into_iter().collect())
+    }
+}
+
 impl Default for Snapshot {
     fn default() -> Self {
         Self {
+            title: DEFAULT_TITLE.to_string(),
             nodes: HashMap::new(),
             edges: HashMap::new(),
+            edge_labels: HashMap::new(),
+            edge_waypoints: HashMap::new(),
+            metadata: GraphMetadata::default(),
+            groups: Vec::new(),
             latest_id: 1, // 从 1 开始避免兼容问题
         }
     }
 }
 
+impl Snapshot {
+    /// 检查再添加 additional 个节点是否会超过上限
+    fn check_node_limit(&self, additional: usize) -> Result<(), GraphError> {
+        match self.metadata.max_nodes {
+            Some(max) if self.nodes.len() + additional > max => {
+                Err(GraphError::LimitExceeded("nodes", max))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// 下一个按顺序分配的节点将使用的 ID，批量执行命令时可据此引用前面的命令添加的节点
+    #[inline]
+    pub fn next_id(&self) -> u64 {
+        self.latest_id
+    }
+
+    /// 按 ID 升序遍历节点
+    ///
+    /// ```
+    /// use py_better_kt_sqep::graph::{DistinctEntityType, KnowledgeGraph};
+    ///
+    /// let mut graph = KnowledgeGraph::default();
+    /// graph.add_entity("节点".to_string(), DistinctEntityType::KnowledgePoint, &[], (0.0, 0.0))?;
+    /// for (id, node) in graph.current_snapshot().iter_nodes() {
+    ///     println!("{id}: {} ({})", node.content, node.distinct_type);
+    /// }
+    /// # Ok::<(), py_better_kt_sqep::error::GraphError>(())
+    /// ```
+    pub fn iter_nodes(&self) -> impl Iterator<Item = (u64, &EntityNode)> {
+        let mut nodes = self
+            .nodes
+            .iter()
+            .map(|(id, node)| (*id, node))
+            .collect::<Vec<_>>();
+        nodes.sort_unstable_by_key(|(id, _)| *id);
+        nodes.into_iter()
+    }
+
+    /// 按 (起点, 终点) 升序遍历边
+    ///
+    /// ```
+    /// use py_better_kt_sqep::graph::{DistinctEntityType, KnowledgeGraph, Relation};
+    ///
+    /// let mut graph = KnowledgeGraph::default();
+    /// let kind = DistinctEntityType::KnowledgePoint;
+    /// let a = graph.add_entity("A".to_string(), kind, &[], (0.0, 0.0))?;
+    /// let b = graph.add_entity("B".to_string(), kind, &[], (200.0, 0.0))?;
+    /// graph.add_edge(a, b, Relation::Order)?;
+    ///
+    /// let edges = graph.current_snapshot().iter_edges().collect::<Vec<_>>();
+    /// assert_eq!(edges, [(a, b, Relation::Order)]);
+    /// # Ok::<(), py_better_kt_sqep::error::GraphError>(())
+    /// ```
+    pub fn iter_edges(&self) -> impl Iterator<Item = (u64, u64, Relation)> {
+        let mut edges = self
+            .edges
+            .iter()
+            .map(|((from, to), relation)| (*from, *to, *relation))
+            .collect::<Vec<_>>();
+        edges.sort_unstable_by_key(|(from, to, _)| (*from, *to));
+        edges.into_iter()
+    }
+
+    /// 估算快照占用的内存字节数。
+    /// 快照之间共享未修改的数据，因此撤回栈的实际占用通常小于估算值之和。
+    pub fn estimated_size(&self) -> usize {
+        let nodes = self
+            .nodes
+            .values()
+            .map(|node| {
+                size_of::<(u64, EntityNode)>()
+                    + node.content.len()
+                    + node.notes.len()
+                    + node.addon_types.len() * size_of::<AddonEntityType>()
+            })
+            .sum::<usize>();
+        let edges = self.edges.len() * size_of::<((u64, u64), Relation)>()
+            + self
+                .edge_labels
+                .values()
+                .map(|label| size_of::<((u64, u64), String)>() + label.len())
+                .sum::<usize>()
+            + self
+                .edge_waypoints
+                .values()
+                .map(|points| {
+                    size_of::<((u64, u64), Vec<(f64, f64)>)>()
+                        + points.len() * size_of::<(f64, f64)>()
+                })
+                .sum::<usize>();
+        let groups = self
+            .groups
+            .iter()
+            .map(|group| size_of::<GroupFrame>() + group.label.len())
+            .sum::<usize>();
+
+        size_of::<Self>() + nodes + edges + groups
+    }
+
+    /// 检查再添加一条边是否会超过上限
+    fn check_edge_limit(&self) -> Result<(), GraphError> {
+        match self.metadata.max_edges {
+            Some(max) if self.edges.len() >= max => Err(GraphError::LimitExceeded("edges", max)),
+            _ => Ok(()),
+        }
+    }
+}
+
 /// 教学知识图谱，支持撤回和重做操作。
 #[derive(Debug)]
 pub struct KnowledgeGraph {
@@ -39,6 +268,10 @@ pub struct KnowledgeGraph {
     undo_stack: Vector<Snapshot>,
     redo_stack: Vector<Snapshot>,
     max_history: usize,
+    max_history_bytes: Option<usize>, // 撤回栈估算内存的上限，None 表示不限制
+    history_bytes: usize,             // 撤回栈中快照的估算内存之和
+    revision: u64,                    // 每次修改、撤回或重做后递增，用于判断缓存是否过期
+    id_allocation: IdAllocation,      // 添加节点时 ID 的分配方式
 }
 
 impl Default for KnowledgeGraph {
@@ -47,7 +280,11 @@ impl Default for KnowledgeGraph {
             current: Snapshot::default(),
             undo_stack: Vector::new(),
             redo_stack: Vector::new(),
-            max_history: 100,
+            max_history: DEFAULT_MAX_HISTORY,
+            max_history_bytes: None,
+            history_bytes: 0,
+            revision: 0,
+            id_allocation: IdAllocation::default(),
         }
     }
 }
@@ -61,21 +298,82 @@ impl KnowledgeGraph {
         }
     }
 
+    /// 创建一个使用给定 ID 分配方式的空图谱
+    pub fn with_id_allocation(id_allocation: IdAllocation) -> Self {
+        Self {
+            id_allocation,
+            ..Default::default()
+        }
+    }
+
+    /// 添加节点时 ID 的分配方式
+    #[inline]
+    pub fn id_allocation(&self) -> IdAllocation {
+        self.id_allocation
+    }
+
+    /// 设置之后添加节点时 ID 的分配方式，已有节点的 ID 不变
+    pub fn set_id_allocation(&mut self, id_allocation: IdAllocation) {
+        self.id_allocation = id_allocation;
+    }
+
+    /// 设置撤回栈估算内存的上限，None 表示不限制。
+    /// 超过上限时立即删除最早的记录。
+    pub fn set_max_history_bytes(&mut self, max_bytes: Option<usize>) {
+        self.max_history_bytes = max_bytes;
+        self.trim_history();
+    }
+
     /// 执行修改前的公共操作。
     /// 1. 清空重做栈
     /// 2. 如果历史记录超过最大值，删除最早的记录
     /// 3. 将当前快照压入撤回栈
+    /// 4. 如果撤回栈估算内存超过上限，删除最早的记录
+    #[inline]
     fn before_mutation(&mut self) {
+        self.before_mutation_with(self.current.clone());
+    }
+
+    /// 同 before_mutation，但压入撤回栈的是给定的快照
+    fn before_mutation_with(&mut self, snapshot: Snapshot) {
+        self.revision += 1;
+
         // 清空重做栈
         self.redo_stack.clear();
 
         // 如果历史记录超过最大值，删除最早的记录
         if self.undo_stack.len() >= self.max_history {
-            self.undo_stack.pop_front();
+            self.pop_oldest_history();
         }
 
-        // 将当前快照压入撤回栈
-        self.undo_stack.push_back(self.current.clone());
+        // 将快照压入撤回栈
+        self.push_history(snapshot);
+
+        self.trim_history();
+    }
+
+    /// 将快照压入撤回栈，并记录其估算内存
+    fn push_history(&mut self, snapshot: Snapshot) {
+        self.history_bytes += snapshot.estimated_size();
+        self.undo_stack.push_back(snapshot);
+    }
+
+    /// 删除撤回栈中最早的记录
+    fn pop_oldest_history(&mut self) {
+        if let Some(snapshot) = self.undo_stack.pop_front() {
+            self.history_bytes -= snapshot.estimated_size();
+        }
+    }
+
+    /// 删除最早的记录，直到撤回栈估算内存不超过上限。
+    /// 至少保留一条记录，保证最近一次操作总能撤回。
+    fn trim_history(&mut self) {
+        let Some(max_bytes) = self.max_history_bytes else {
+            return;
+        };
+        while self.history_bytes > max_bytes && self.undo_stack.len() > 1 {
+            self.pop_oldest_history();
+        }
     }
 
     /// 撤回上一次操作。
@@ -86,12 +384,14 @@ impl KnowledgeGraph {
             .undo_stack
             .pop_back()
             .ok_or(GraphError::NothingToUndo)?;
+        self.history_bytes -= current.estimated_size();
 
         // 将当前快照压入重做栈
         self.redo_stack.push_back(self.current.clone());
 
         // 将上一个快照设置为当前快照
         self.current = current;
+        self.revision += 1;
 
         Ok(())
     }
@@ -106,28 +406,30 @@ impl KnowledgeGraph {
             .ok_or(GraphError::NothingToRedo)?;
 
         // 将当前快照压入撤回栈
-        self.undo_stack.push_back(self.current.clone());
+        self.push_history(self.current.clone());
+        self.trim_history();
 
         // 将上一个快照设置为当前快照
         self.current = current;
+        self.revision += 1;
 
         Ok(())
     }
 
     /// 添加一个节点
+    /// 如果节点数已达到上限，或顺序分配的 ID 已被占用，返回错误。
     pub fn add_entity(
         &mut self,
         content: String,
         distinct_type: DistinctEntityType,
         addon_types: &[AddonEntityType],
         coor: (f64, f64),
-    ) -> u64 {
-        self.before_mutation(); // 记录快照
+    ) -> Result<u64, GraphError> {
+        self.current.check_node_limit(1)?;
 
-        // 生成新节点 ID
-        let current = &mut self.current;
-        let id = current.latest_id;
-        current.latest_id += 1;
+        // 生成新节点 ID，失败时不修改图谱
+        let mut current = self.current.clone();
+        let id = current.allocate_id(self.id_allocation, &content, distinct_type)?;
 
         // 插入新节点
         current.nodes.insert(
@@ -135,7 +437,40 @@ impl KnowledgeGraph {
             EntityNode::new(id, content, distinct_type, addon_types, coor),
         );
 
-        id
+        let previous = std::mem::replace(&mut self.current, current);
+        self.before_mutation_with(previous); // 记录快照
+
+        Ok(id)
+    }
+
+    /// 批量添加节点，整体作为一次可撤回的操作。
+    /// 传入节点的 id 会被忽略并重新分配，返回按传入顺序排列的新 id。
+    /// 如果添加后节点数超过上限，或顺序分配的 ID 已被占用，不添加任何节点并返回错误。
+    pub fn add_entities<I>(&mut self, entities: I) -> Result<Vec<u64>, GraphError>
+    where
+        I: IntoIterator<Item = EntityNode>,
+    {
+        let entities = entities.into_iter().collect::<Vec<_>>();
+        self.current.check_node_limit(entities.len())?;
+
+        let mut current = self.current.clone();
+        let ids = entities
+            .into_iter()
+            .map(|mut node| {
+                // 生成新节点 ID
+                let id =
+                    current.allocate_id(self.id_allocation, &node.content, node.distinct_type)?;
+
+                node.id = id;
+                current.nodes.insert(id, node);
+                Ok(id)
+            })
+            .collect::<Result<_, GraphError>>()?;
+
+        let previous = std::mem::replace(&mut self.current, current);
+        self.before_mutation_with(previous); // 记录快照
+
+        Ok(ids)
     }
 
     /// 删除一个节点及其关联的边
@@ -153,6 +488,17 @@ impl KnowledgeGraph {
         current
             .edges
             .retain(|(from, to), _| *from != id && *to != id);
+        current
+            .edge_labels
+            .retain(|(from, to), _| *from != id && *to != id);
+        current
+            .edge_waypoints
+            .retain(|(from, to), _| *from != id && *to != id);
+
+        // 从分组框的成员中移除
+        for group in current.groups.iter_mut() {
+            group.members.retain(|member| *member != id);
+        }
 
         Ok(())
     }
@@ -199,9 +545,34 @@ impl KnowledgeGraph {
             })
     }
 
+    /// 以闭包修改节点的任意字段（如备注），作为一次可撤回的操作。
+    /// 闭包中对 id 的修改会被忽略。
+    /// 如果节点不存在，返回错误。
+    pub fn update_entity_with<F>(&mut self, id: u64, f: F) -> Result<(), GraphError>
+    where
+        F: FnOnce(&mut EntityNode),
+    {
+        if !self.current.nodes.contains_key(&id) {
+            return Err(GraphError::EntityNotFound(id));
+        }
+
+        self.before_mutation(); // 记录快照
+
+        if let Some(node) = self.current.nodes.get_mut(&id) {
+            f(node);
+            node.id = id; // ID 为查找的键，不可修改
+        }
+
+        Ok(())
+    }
+
     /// 添加一条边。
-    /// 如果节点 ID 不存在，或边已经存在，返回错误。
+    /// 如果节点 ID 不存在，或边数已达到上限，返回错误。边已经存在时会覆盖其关系。
     pub fn add_edge(&mut self, from: u64, to: u64, relation: Relation) -> Result<(), GraphError> {
+        if !self.current.edges.contains_key(&(from, to)) {
+            self.current.check_edge_limit()?;
+        }
+
         self.before_mutation(); // 记录快照
 
         // 检查节点是否存在
@@ -227,6 +598,8 @@ impl KnowledgeGraph {
         if self.current.edges.remove(&(from, to)).is_none() {
             return Err(GraphError::EdgeNotFound(from, to));
         }
+        self.current.edge_labels.remove(&(from, to));
+        self.current.edge_waypoints.remove(&(from, to));
 
         Ok(())
     }
@@ -250,11 +623,113 @@ impl KnowledgeGraph {
         )
     }
 
+    /// 设置边的自定义标签，空标签表示清除。
+    /// 如果边不存在，返回错误。
+    pub fn set_edge_label(&mut self, from: u64, to: u64, label: String) -> Result<(), GraphError> {
+        if !self.current.edges.contains_key(&(from, to)) {
+            return Err(GraphError::EdgeNotFound(from, to));
+        }
+
+        self.before_mutation(); // 记录快照
+
+        if label.is_empty() {
+            self.current.edge_labels.remove(&(from, to));
+        } else {
+            self.current.edge_labels.insert((from, to), label);
+        }
+
+        Ok(())
+    }
+
+    /// 设置边的折点，按从起点到终点的顺序排列，空表表示清除。
+    /// 如果边不存在，返回错误。
+    pub fn set_edge_waypoints(
+        &mut self,
+        from: u64,
+        to: u64,
+        waypoints: Vec<(f64, f64)>,
+    ) -> Result<(), GraphError> {
+        if !self.current.edges.contains_key(&(from, to)) {
+            return Err(GraphError::EdgeNotFound(from, to));
+        }
+
+        self.before_mutation(); // 记录快照
+
+        if waypoints.is_empty() {
+            self.current.edge_waypoints.remove(&(from, to));
+        } else {
+            self.current.edge_waypoints.insert((from, to), waypoints);
+        }
+
+        Ok(())
+    }
+
+    /// 按给定顺序设置节点的子节点次序，作为一次可撤回的操作。
+    /// 如果节点不存在，或给定的节点不恰好是它通过包含关系直接包含的全部子节点，返回错误。
+    pub fn set_children_order(
+        &mut self,
+        parent: u64,
+        ordered_children: &[u64],
+    ) -> Result<(), GraphError> {
+        if !self.current.nodes.contains_key(&parent) {
+            return Err(GraphError::EntityNotFound(parent));
+        }
+        let mut children = self.current.children(parent);
+        let mut ordered = ordered_children.to_vec();
+        children.sort_unstable();
+        ordered.sort_unstable();
+        if children != ordered {
+            return Err(GraphError::ChildrenMismatch(parent));
+        }
+
+        self.before_mutation(); // 记录快照
+
+        for (index, id) in ordered_children.iter().enumerate() {
+            if let Some(node) = self.current.nodes.get_mut(id) {
+                node.order_index = Some(index as u32);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// 修改图谱元数据（如节点数和边数上限），作为一次可撤回的操作
+    pub fn set_metadata(&mut self, metadata: GraphMetadata) {
+        self.before_mutation(); // 记录快照
+
+        self.current.metadata = metadata;
+    }
+
+    /// 以给定快照整体替换当前快照，用于重新载入文件和恢复崩溃前的修改。
+    /// 与 from_snapshot 不同，之前的历史记录会保留，替换本身也是一次可撤回的操作。
+    /// 快照与当前快照相同时不做修改
+    pub fn replace_current(&mut self, snapshot: Snapshot) {
+        if snapshot == self.current {
+            return;
+        }
+
+        self.before_mutation(); // 记录快照
+
+        self.current = snapshot;
+    }
+
     /// 获取当前快照
     #[inline]
     pub fn current_snapshot(&self) -> &Snapshot {
         &self.current
     }
+
+    /// 可撤回的次数
+    #[inline]
+    pub fn undo_len(&self) -> usize {
+        self.undo_stack.len()
+    }
+
+    /// 获取修订号，图谱每次变化后都会不同
+    #[inline]
+    pub fn revision(&self) -> u64 {
+        self.revision
+    }
 }
 #[cfg(test)]
 mod tests {
@@ -286,12 +761,14 @@ mod tests {
     fn test_add_entity() {
         let mut graph = KnowledgeGraph::default();
         let content = "Test Node".to_string();
-        let id = graph.add_entity(
-            content.clone(),
-            default_distinct(),
-            &default_addons(),
-            default_coor(),
-        );
+        let id = graph
+            .add_entity(
+                content.clone(),
+                default_distinct(),
+                &default_addons(),
+                default_coor(),
+            )
+            .unwrap();
 
         // 检查节点是否存在
         assert!(graph.current.nodes.contains_key(&id));
@@ -306,12 +783,14 @@ mod tests {
     #[test]
     fn test_remove_entity() {
         let mut graph = KnowledgeGraph::default();
-        let id = graph.add_entity(
-            "Node to remove".to_string(),
-            default_distinct(),
-            &default_addons(),
-            default_coor(),
-        );
+        let id = graph
+            .add_entity(
+                "Node to remove".to_string(),
+                default_distinct(),
+                &default_addons(),
+                default_coor(),
+            )
+            .unwrap();
         // 检查节点是否存在
         assert!(graph.current.nodes.contains_key(&id));
 
@@ -329,12 +808,14 @@ mod tests {
     #[test]
     fn test_update_entity() {
         let mut graph = KnowledgeGraph::default();
-        let id = graph.add_entity(
-            "Old Content".to_string(),
-            default_distinct(),
-            &default_addons(),
-            default_coor(),
-        );
+        let id = graph
+            .add_entity(
+                "Old Content".to_string(),
+                default_distinct(),
+                &default_addons(),
+                default_coor(),
+            )
+            .unwrap();
         // 更新节点内容
         assert!(
             graph
@@ -366,18 +847,22 @@ mod tests {
     #[test]
     fn test_edge_operations() {
         let mut graph = KnowledgeGraph::default();
-        let from = graph.add_entity(
-            "From Node".to_string(),
-            default_distinct(),
-            &default_addons(),
-            default_coor(),
-        );
-        let to = graph.add_entity(
-            "To Node".to_string(),
-            default_distinct(),
-            &default_addons(),
-            default_coor(),
-        );
+        let from = graph
+            .add_entity(
+                "From Node".to_string(),
+                default_distinct(),
+                &default_addons(),
+                default_coor(),
+            )
+            .unwrap();
+        let to = graph
+            .add_entity(
+                "To Node".to_string(),
+                default_distinct(),
+                &default_addons(),
+                default_coor(),
+            )
+            .unwrap();
 
         // 添加边
         assert!(graph.add_edge(from, to, default_relation()).is_ok());
@@ -390,9 +875,36 @@ mod tests {
         // 更新边
         assert!(graph.update_edge(from, to, default_relation()).is_ok());
 
-        // 删除边
+        // 设置和清除边的标签
+        assert!(graph.set_edge_label(from, to, "标签".to_string()).is_ok());
+        assert_eq!(graph.current.edge_labels[&(from, to)], "标签");
+        assert!(graph.set_edge_label(from, to, String::new()).is_ok());
+        assert!(graph.current.edge_labels.is_empty());
+        assert!(graph.set_edge_label(to, from, "标签".to_string()).is_err());
+
+        // 设置和清除边的折点
+        let waypoints = vec![(10.0, 20.0), (30.0, 40.0)];
+        assert!(
+            graph
+                .set_edge_waypoints(from, to, waypoints.clone())
+                .is_ok()
+        );
+        assert_eq!(graph.current.edge_waypoints[&(from, to)], waypoints);
+        assert!(graph.set_edge_waypoints(from, to, Vec::new()).is_ok());
+        assert!(graph.current.edge_waypoints.is_empty());
+        assert!(
+            graph
+                .set_edge_waypoints(to, from, waypoints.clone())
+                .is_err()
+        );
+
+        // 删除边时一并删除标签和折点
+        assert!(graph.set_edge_label(from, to, "标签".to_string()).is_ok());
+        assert!(graph.set_edge_waypoints(from, to, waypoints).is_ok());
         assert!(graph.remove_edge(from, to).is_ok());
         assert!(!graph.current.edges.contains_key(&(from, to)));
+        assert!(graph.current.edge_labels.is_empty());
+        assert!(graph.current.edge_waypoints.is_empty());
 
         // 删除不存在的边应该失败
         match graph.remove_edge(from, to) {
@@ -405,22 +917,190 @@ mod tests {
     }
 
     #[test]
-    fn test_undo_redo() {
+    fn test_add_entities() {
         let mut graph = KnowledgeGraph::default();
-        // 添加一个节点
-        let id = graph.add_entity(
-            "Undo Node".to_string(),
+        let mut node = EntityNode::new(
+            0,
+            "Batch 1".to_string(),
             default_distinct(),
             &default_addons(),
             default_coor(),
         );
+        node.notes = "Notes".to_string();
+        let node_2 = EntityNode::new(
+            0,
+            "Batch 2".to_string(),
+            default_distinct(),
+            &[],
+            (0.0, 150.0),
+        );
+
+        let ids = graph.add_entities([node, node_2]).unwrap();
+        assert_eq!(ids, vec![1, 2]);
+        assert_eq!(graph.current.nodes.get(&1).unwrap().notes, "Notes");
+        assert_eq!(graph.current.nodes.get(&2).unwrap().content, "Batch 2");
+
+        // 批量添加只占用一次撤回
+        assert!(graph.undo().is_ok());
+        assert!(graph.current.nodes.is_empty());
+        assert!(graph.undo().is_err());
+    }
+
+    #[test]
+    fn test_set_children_order() {
+        let mut graph = KnowledgeGraph::default();
+        for i in 0..4 {
+            graph
+                .add_entity(
+                    format!("Node {i}"),
+                    default_distinct(),
+                    &[],
+                    (i as f64 * 200.0, 0.0),
+                )
+                .unwrap();
+        }
+        graph.add_edge(1, 2, Relation::Contain).unwrap();
+        graph.add_edge(1, 3, Relation::Contain).unwrap();
+        graph.add_edge(1, 4, Relation::Order).unwrap();
+        assert_eq!(graph.current.children(1), vec![2, 3]);
+
+        graph.set_children_order(1, &[3, 2]).unwrap();
+        assert_eq!(graph.current.children(1), vec![3, 2]);
+        assert_eq!(graph.current.nodes[&3].order_index, Some(0));
+
+        // 必须恰好是全部子节点
+        for ordered in [&[3][..], &[3, 2, 4], &[3, 3], &[]] {
+            assert_eq!(
+                graph.set_children_order(1, ordered),
+                Err(GraphError::ChildrenMismatch(1))
+            );
+        }
+        assert_eq!(
+            graph.set_children_order(100, &[]),
+            Err(GraphError::EntityNotFound(100))
+        );
+
+        graph.undo().unwrap();
+        assert_eq!(graph.current.children(1), vec![2, 3]);
+    }
+
+    #[test]
+    fn test_update_entity_with() {
+        let mut graph = KnowledgeGraph::default();
+        let id = graph
+            .add_entity(
+                "Node".to_string(),
+                default_distinct(),
+                &default_addons(),
+                default_coor(),
+            )
+            .unwrap();
+
+        assert!(
+            graph
+                .update_entity_with(id, |node| {
+                    node.notes = "New Notes".to_string();
+                    node.id = 999; // 修改 id 无效
+                })
+                .is_ok()
+        );
+        let node = graph.current.nodes.get(&id).unwrap();
+        assert_eq!(node.notes, "New Notes");
+        assert_eq!(node.id, id);
+
+        // 修改不存在的节点应该失败，且不产生撤回记录
+        assert_eq!(
+            graph.update_entity_with(999, |_| {}),
+            Err(GraphError::EntityNotFound(999))
+        );
+        assert!(graph.undo().is_ok());
+        assert!(graph.current.nodes.get(&id).unwrap().notes.is_empty());
+    }
+
+    #[test]
+    fn test_limits() {
+        let mut graph = KnowledgeGraph::default();
+        graph.set_metadata(GraphMetadata {
+            max_nodes: Some(2),
+            max_edges: Some(1),
+            ..Default::default()
+        });
+
+        let from = graph
+            .add_entity(
+                "From Node".to_string(),
+                default_distinct(),
+                &default_addons(),
+                default_coor(),
+            )
+            .unwrap();
+        let to = graph
+            .add_entity(
+                "To Node".to_string(),
+                default_distinct(),
+                &default_addons(),
+                default_coor(),
+            )
+            .unwrap();
+
+        // 达到节点上限后无法再添加节点，批量添加也不会部分成功
+        assert_eq!(
+            graph.add_entity(
+                "Extra Node".to_string(),
+                default_distinct(),
+                &default_addons(),
+                default_coor(),
+            ),
+            Err(GraphError::LimitExceeded("nodes", 2))
+        );
+        assert_eq!(
+            graph.add_entities([EntityNode::new(
+                0,
+                "Extra Node".to_string(),
+                default_distinct(),
+                &[],
+                default_coor(),
+            )]),
+            Err(GraphError::LimitExceeded("nodes", 2))
+        );
+        assert_eq!(graph.current.nodes.len(), 2);
+
+        // 达到边上限后无法添加新边，但可以覆盖已有的边
+        assert!(graph.add_edge(from, to, default_relation()).is_ok());
+        assert!(graph.add_edge(from, to, Relation::Order).is_ok());
+        assert_eq!(
+            graph.add_edge(to, from, default_relation()),
+            Err(GraphError::LimitExceeded("edges", 1))
+        );
+
+        // 撤回到设置上限之前后可以继续添加
+        while graph.undo().is_ok() {}
+        assert!(graph.current.metadata.is_default());
+    }
+
+    #[test]
+    fn test_undo_redo() {
+        let mut graph = KnowledgeGraph::default();
+        // 添加一个节点
+        let id = graph
+            .add_entity(
+                "Undo Node".to_string(),
+                default_distinct(),
+                &default_addons(),
+                default_coor(),
+            )
+            .unwrap();
         assert!(graph.current.nodes.contains_key(&id));
         // 撤回操作会移除节点
+        let revision = graph.revision();
         assert!(graph.undo().is_ok());
         assert!(!graph.current.nodes.contains_key(&id));
+        assert_ne!(graph.revision(), revision);
         // 重做操作会恢复节点
+        let revision = graph.revision();
         assert!(graph.redo().is_ok());
         assert!(graph.current.nodes.contains_key(&id));
+        assert_ne!(graph.revision(), revision);
 
         // 测试空的撤回栈
         let mut graph2 = KnowledgeGraph::default();
@@ -435,26 +1115,104 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_replace_current() -> Result<(), GraphError> {
+        let mut graph = KnowledgeGraph::default();
+        let id = graph.add_entity(
+            "Before".to_string(),
+            default_distinct(),
+            &default_addons(),
+            default_coor(),
+        )?;
+        let before = graph.current.clone();
+
+        // 替换后之前的历史记录仍在，替换本身可以撤回和重做
+        let mut reloaded = before.clone();
+        reloaded.nodes[&id].content = "Reloaded".to_string();
+        graph.replace_current(reloaded.clone());
+        assert_eq!(graph.undo_len(), 2);
+        graph.undo()?;
+        assert_eq!(graph.current, before);
+        graph.undo()?;
+        assert!(graph.current.nodes.is_empty());
+        graph.redo()?;
+        graph.redo()?;
+        assert_eq!(graph.current, reloaded);
+
+        // 相同的快照不产生撤回记录
+        let revision = graph.revision();
+        graph.replace_current(reloaded);
+        assert_eq!(graph.undo_len(), 2);
+        assert_eq!(graph.revision(), revision);
+
+        Ok(())
+    }
+
     #[test]
     fn test_history_limit() {
         let mut graph = KnowledgeGraph::default();
-        // 添加 5 个节点
-        for i in 0..150 {
-            graph.add_entity(
-                format!("Node {}", i),
-                default_distinct(),
-                &default_addons(),
-                default_coor(),
-            );
+        // 添加超过上限的节点
+        for i in 0..DEFAULT_MAX_HISTORY + 50 {
+            graph
+                .add_entity(
+                    format!("Node {}", i),
+                    default_distinct(),
+                    &default_addons(),
+                    default_coor(),
+                )
+                .unwrap();
         }
-        // 撤回栈应该不超过 3
-        assert!(graph.undo_stack.len() == 100);
+        // 撤回栈应该不超过上限
+        assert!(graph.undo_stack.len() == DEFAULT_MAX_HISTORY);
         // 撤回尽可能多的次数，直到没有操作可撤回
         let mut undos = 0;
         while graph.undo().is_ok() {
             undos += 1;
         }
-        // 撤回次数应该为 3
-        assert!(undos == 100);
+        // 撤回次数应该等于上限
+        assert!(undos == DEFAULT_MAX_HISTORY);
+    }
+
+    #[test]
+    fn test_history_bytes_limit() {
+        let mut graph = KnowledgeGraph::default();
+        for i in 0..20 {
+            graph
+                .add_entity(
+                    "x".repeat(1000) + &i.to_string(),
+                    default_distinct(),
+                    &default_addons(),
+                    default_coor(),
+                )
+                .unwrap();
+        }
+        assert_eq!(graph.undo_stack.len(), 20);
+
+        // 设置上限后立即删除最早的记录
+        let max_bytes = graph.current.estimated_size() * 3;
+        graph.set_max_history_bytes(Some(max_bytes));
+        assert!(graph.history_bytes <= max_bytes);
+        let len = graph.undo_stack.len();
+        assert!(len > 1 && len < 20);
+
+        // 继续修改时仍然不超过上限
+        for _ in 0..5 {
+            graph.update_entity_position(1, (1.0, 1.0)).unwrap();
+            assert!(graph.history_bytes <= max_bytes);
+        }
+
+        // 撤回和重做后估算值保持一致
+        graph.undo().unwrap();
+        graph.redo().unwrap();
+        let total = graph
+            .undo_stack
+            .iter()
+            .map(Snapshot::estimated_size)
+            .sum::<usize>();
+        assert_eq!(graph.history_bytes, total);
+
+        // 单条记录超过上限时仍然保留
+        graph.set_max_history_bytes(Some(1));
+        assert_eq!(graph.undo_stack.len(), 1);
     }
 }
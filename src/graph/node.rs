@@ -2,14 +2,27 @@
 
 use std::collections::HashSet;
 
+use serde::{Deserialize, Serialize};
+
 /// 实体节点
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct EntityNode {
     pub id: u64,
     pub content: String,
     pub distinct_type: DistinctEntityType,
     pub addon_types: HashSet<AddonEntityType>,
-    pub coor: (f64, f64),
+    pub coor: (f64, f64), // 与 DPI 无关的逻辑坐标
+    pub notes: String,    // 备注，不参与对接系统的显示
+    #[serde(default)]
+    pub order_index: Option<u32>, // 在兄弟节点中的次序，决定对接平台显示子节点的顺序
+    #[serde(default)]
+    pub reference: bool, // 仅作参考的样板节点，保存在文件中但不导出
+    #[serde(default)]
+    pub links: Vec<String>, // 关联的网址或本地文件路径，如慕课视频、课件
+    #[serde(default)]
+    pub flag: Option<Flag>, // 评审标记，如待办、已审核
+    #[serde(default)]
+    pub primary_parent: Option<u64>, // 有多个父节点时的主父节点，None 或已不是父节点时以 ID 最小的父节点为主
 }
 
 impl EntityNode {
@@ -27,6 +40,32 @@ impl EntityNode {
             distinct_type,
             addon_types: addon_types.iter().copied().collect(),
             coor,
+            notes: String::new(),
+            order_index: None,
+            reference: false,
+            links: Vec::new(),
+            flag: None,
+            primary_parent: None,
+        }
+    }
+
+    /// 以构建器创建实体节点，未设置的内容、备注为空，坐标为原点，没有附加类型和次序
+    ///
+    /// ```
+    /// use py_better_kt_sqep::graph::{AddonEntityType, DistinctEntityType, EntityNode};
+    ///
+    /// let node = EntityNode::builder(1, DistinctEntityType::KnowledgePoint)
+    ///     .content("计算思维")
+    ///     .addon_type(AddonEntityType::Example)
+    ///     .coor((100.0, 200.0))
+    ///     .notes("待补充")
+    ///     .build();
+    /// assert_eq!(node.content, "计算思维");
+    /// assert!(node.addon_types.contains(&AddonEntityType::Example));
+    /// ```
+    pub fn builder(id: u64, distinct_type: DistinctEntityType) -> EntityNodeBuilder {
+        EntityNodeBuilder {
+            node: Self::new(id, String::new(), distinct_type, &[], (0.0, 0.0)),
         }
     }
 
@@ -44,17 +83,147 @@ impl EntityNode {
         self.addon_types = addon_types.iter().copied().collect();
         self.coor = coor;
     }
+
+    /// 内容超过 limit 个字符时，将超出的部分移到备注的开头，返回是否移动了内容
+    pub fn move_excess_content(&mut self, limit: usize) -> bool {
+        let Some((index, _)) = self.content.char_indices().nth(limit) else {
+            return false;
+        };
+        let excess = self.content.split_off(index);
+        self.notes = if self.notes.is_empty() {
+            excess
+        } else {
+            format!("{excess}\n{}", self.notes)
+        };
+        true
+    }
+}
+
+/// 实体节点的构建器，由 EntityNode::builder 创建
+#[derive(Debug, Clone)]
+pub struct EntityNodeBuilder {
+    node: EntityNode,
+}
+
+impl EntityNodeBuilder {
+    pub fn content(mut self, content: impl Into<String>) -> Self {
+        self.node.content = content.into();
+        self
+    }
+
+    /// 添加一个附加类型
+    pub fn addon_type(mut self, addon_type: AddonEntityType) -> Self {
+        self.node.addon_types.insert(addon_type);
+        self
+    }
+
+    /// 以给定的附加类型替换已添加的附加类型
+    pub fn addon_types(mut self, addon_types: &[AddonEntityType]) -> Self {
+        self.node.addon_types = addon_types.iter().copied().collect();
+        self
+    }
+
+    pub fn coor(mut self, coor: (f64, f64)) -> Self {
+        self.node.coor = coor;
+        self
+    }
+
+    pub fn notes(mut self, notes: impl Into<String>) -> Self {
+        self.node.notes = notes.into();
+        self
+    }
+
+    pub fn order_index(mut self, order_index: u32) -> Self {
+        self.node.order_index = Some(order_index);
+        self
+    }
+
+    /// 添加一个关联的网址或本地文件路径
+    pub fn link(mut self, link: impl Into<String>) -> Self {
+        self.node.links.push(link.into());
+        self
+    }
+
+    /// 设置评审标记
+    pub fn flag(mut self, flag: Flag) -> Self {
+        self.node.flag = Some(flag);
+        self
+    }
+
+    /// 标记为仅作参考、不导出的节点
+    pub fn reference(mut self, reference: bool) -> Self {
+        self.node.reference = reference;
+        self
+    }
+
+    pub fn build(self) -> EntityNode {
+        self.node
+    }
+}
+
+/// 评审标记，评审会上标注节点的处理状态
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum Flag {
+    Todo,     // 待办，需要修改
+    Question, // 疑问，需要讨论
+    Approved, // 已审核
+}
+
+impl Flag {
+    /// 所有评审标记，按循环切换的顺序排列
+    pub const ALL: [Flag; 3] = [Flag::Todo, Flag::Question, Flag::Approved];
+
+    /// 循环切换时的下一个标记：无标记、待办、疑问、已审核，之后回到无标记
+    ///
+    /// ```
+    /// use py_better_kt_sqep::graph::Flag;
+    ///
+    /// assert_eq!(Flag::cycle(None), Some(Flag::Todo));
+    /// assert_eq!(Flag::cycle(Some(Flag::Todo)), Some(Flag::Question));
+    /// assert_eq!(Flag::cycle(Some(Flag::Approved)), None);
+    /// ```
+    pub fn cycle(flag: Option<Flag>) -> Option<Flag> {
+        match flag {
+            None => Some(Flag::Todo),
+            Some(Flag::Todo) => Some(Flag::Question),
+            Some(Flag::Question) => Some(Flag::Approved),
+            Some(Flag::Approved) => None,
+        }
+    }
+
+    /// 文件和脚本中使用的名称
+    pub fn name(&self) -> &'static str {
+        match *self {
+            Flag::Todo => "todo",
+            Flag::Question => "question",
+            Flag::Approved => "approved",
+        }
+    }
+
+    /// 由名称得到标记，不区分大小写
+    pub fn from_name(name: &str) -> Option<Self> {
+        Flag::ALL
+            .into_iter()
+            .find(|flag| flag.name().eq_ignore_ascii_case(name.trim()))
+    }
 }
 
 // 关系类型
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum Relation {
-    Contain, // 包含关系
-    Order,   // 次序关系
+    Contain,     // 包含关系
+    Order,       // 次序关系
+    Extension,   // 扩展关系
+    Custom(u16), // 关系类型表中的自定义关系，编号见 RelationRegistry
+}
+
+impl Relation {
+    /// 所有内置关系类型，按界面和图例中的顺序排列。自定义关系在关系类型表中配置
+    pub const ALL: [Relation; 3] = [Relation::Contain, Relation::Order, Relation::Extension];
 }
 
 /// 实体类型
-#[derive(Debug, Clone, Copy, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 #[allow(clippy::enum_variant_names)]
 pub enum DistinctEntityType {
     KnowledgeArena,  // 知识领域
@@ -63,8 +232,29 @@ pub enum DistinctEntityType {
     KnowledgeDetail, // 关键知识细节
 }
 
+impl DistinctEntityType {
+    /// 所有节点类型，从高到低排列
+    pub const ALL: [DistinctEntityType; 4] = [
+        DistinctEntityType::KnowledgeArena,
+        DistinctEntityType::KnowledgeUnit,
+        DistinctEntityType::KnowledgePoint,
+        DistinctEntityType::KnowledgeDetail,
+    ];
+
+    /// 低一级的类型，用于新建子节点。关键知识细节已是最低一级，返回自身
+    pub fn child_type(&self) -> Self {
+        match *self {
+            DistinctEntityType::KnowledgeArena => DistinctEntityType::KnowledgeUnit,
+            DistinctEntityType::KnowledgeUnit => DistinctEntityType::KnowledgePoint,
+            DistinctEntityType::KnowledgePoint | DistinctEntityType::KnowledgeDetail => {
+                DistinctEntityType::KnowledgeDetail
+            }
+        }
+    }
+}
+
 /// 附加实体类型
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum AddonEntityType {
     Knowledge, // 知识
     Thinking,  // 思维
@@ -73,3 +263,15 @@ pub enum AddonEntityType {
     Practice,  // 练习
     Political, // 思政
 }
+
+impl AddonEntityType {
+    /// 所有附加类型，按声明的顺序排列
+    pub const ALL: [AddonEntityType; 6] = [
+        AddonEntityType::Knowledge,
+        AddonEntityType::Thinking,
+        AddonEntityType::Example,
+        AddonEntityType::Question,
+        AddonEntityType::Practice,
+        AddonEntityType::Political,
+    ];
+}
@@ -0,0 +1,138 @@
+//! 内容规范化模块。对接系统按文本匹配术语，录入时混用全角半角、多余的空白会使同一概念被视为不同。
+
+use std::collections::BTreeSet;
+
+use super::{Command, KnowledgeGraph, Snapshot};
+use crate::error::GraphError;
+
+/// 规范化内容的选项
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct NormalizeOptions {
+    pub halfwidth: bool, // 是否将全角的字母、数字、标点和空格转为半角
+}
+
+/// 规范化内容：去掉首尾空白，可选地将全角字符转为半角，再将连续的空白合并为一个。
+/// 含有换行的连续空白合并为一个换行，其余合并为一个空格
+///
+/// ```
+/// use py_better_kt_sqep::graph::{NormalizeOptions, normalize_content};
+///
+/// let options = NormalizeOptions { halfwidth: true };
+/// assert_eq!(normalize_content("　ＴＣＰ／ＩＰ  协议 ", options), "TCP/IP 协议");
+/// assert_eq!(normalize_content("第一行 \n\n 第二行", options), "第一行\n第二行");
+/// ```
+pub fn normalize_content(content: &str, options: NormalizeOptions) -> String {
+    let converted = content.chars().map(|c| {
+        if !options.halfwidth {
+            return c;
+        }
+        match c {
+            '\u{3000}' => ' ',
+            '\u{ff01}'..='\u{ff5e}' => char::from_u32(c as u32 - 0xfee0).unwrap_or(c),
+            _ => c,
+        }
+    });
+
+    let mut result = String::with_capacity(content.len());
+    let mut pending: Option<char> = None; // 尚未输出的连续空白合并后的字符
+    for c in converted {
+        if c.is_whitespace() {
+            if c == '\n' || pending.is_none() {
+                pending = Some(if c == '\n' { '\n' } else { ' ' });
+            }
+            continue;
+        }
+        // 开头的空白直接丢弃
+        if let Some(space) = pending.take()
+            && !result.is_empty()
+        {
+            result.push(space);
+        }
+        result.push(c);
+    }
+    result
+}
+
+impl Snapshot {
+    /// 将指定节点的内容规范化所需的命令，内容不变的节点不生成命令
+    pub fn normalize_commands(
+        &self,
+        ids: &BTreeSet<u64>,
+        options: NormalizeOptions,
+    ) -> Vec<Command> {
+        ids.iter()
+            .filter_map(|id| self.nodes.get(id))
+            .filter_map(|node| {
+                let content = normalize_content(&node.content, options);
+                (content != node.content).then(|| Command::UpdateEntityContent {
+                    id: node.id,
+                    content,
+                    distinct_type: node.distinct_type,
+                    addon_types: node.addon_types.iter().copied().collect(),
+                })
+            })
+            .collect()
+    }
+}
+
+impl KnowledgeGraph {
+    /// 规范化指定节点的内容，整体作为一次可撤回的操作，返回修改的节点数
+    pub fn normalize_contents(
+        &mut self,
+        ids: &BTreeSet<u64>,
+        options: NormalizeOptions,
+    ) -> Result<usize, GraphError> {
+        let commands = self.current.normalize_commands(ids, options);
+        let count = commands.len();
+        if count > 0 {
+            self.apply_batch(commands)?;
+        }
+        Ok(count)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::DistinctEntityType;
+
+    #[test]
+    fn test_normalize_content() {
+        let keep = NormalizeOptions::default();
+        assert_eq!(normalize_content(" \t计算  思维\t ", keep), "计算 思维");
+        assert_eq!(normalize_content("Ａ，Ｂ", keep), "Ａ，Ｂ");
+        assert_eq!(normalize_content("a \r\n b", keep), "a\nb");
+        assert_eq!(normalize_content("   ", keep), "");
+
+        let halfwidth = NormalizeOptions { halfwidth: true };
+        assert_eq!(normalize_content("Ａ，Ｂ（１）", halfwidth), "A,B(1)");
+        assert_eq!(normalize_content("中文。", halfwidth), "中文。");
+    }
+
+    #[test]
+    fn test_normalize_contents() {
+        let mut graph = KnowledgeGraph::default();
+        for content in [" 节点一 ", "节点二", "节点  三"] {
+            graph
+                .add_entity(
+                    content.to_string(),
+                    DistinctEntityType::KnowledgePoint,
+                    &[],
+                    (0.0, 0.0),
+                )
+                .unwrap();
+        }
+
+        let undo_len = graph.undo_len();
+        let ids = BTreeSet::from([1, 2, 3]);
+        let options = NormalizeOptions::default();
+        assert_eq!(graph.normalize_contents(&ids, options), Ok(2));
+        assert_eq!(graph.current.nodes[&1].content, "节点一");
+        assert_eq!(graph.current.nodes[&3].content, "节点 三");
+        assert_eq!(graph.undo_len(), undo_len + 1);
+
+        // 已经规范时不产生撤回记录
+        assert_eq!(graph.normalize_contents(&ids, options), Ok(0));
+        assert_eq!(graph.undo_len(), undo_len + 1);
+    }
+}
@@ -0,0 +1,166 @@
+//! 重叠检测模块，找出位置互相遮挡的节点，并为其寻找空闲的位置。
+
+use std::collections::{BTreeSet, HashMap};
+
+use super::Snapshot;
+
+/// 节点在逻辑坐标中的默认大小，节点坐标为其中心
+pub const NODE_SIZE: (f64, f64) = (150.0, 120.0);
+
+/// 以 (x, y) 为中心、大小为 size 的两个矩形是否相交，仅边缘相接不算相交
+#[inline]
+fn intersects(a: (f64, f64), b: (f64, f64), size: (f64, f64)) -> bool {
+    (a.0 - b.0).abs() < size.0 && (a.1 - b.1).abs() < size.1
+}
+
+impl Snapshot {
+    /// 找出所有互相重叠的节点对，每对中 ID 较小的在前，结果按 ID 排序。
+    /// 使用沿 x 轴的扫描线，只比较横向距离小于宽度的节点。
+    pub fn overlapping_pairs(&self, size: (f64, f64)) -> Vec<(u64, u64)> {
+        let mut nodes = self
+            .nodes
+            .values()
+            .map(|node| (node.id, node.coor))
+            .collect::<Vec<_>>();
+        nodes.sort_unstable_by(|a, b| a.1.0.total_cmp(&b.1.0));
+
+        let mut pairs = Vec::new();
+        let mut active: Vec<(u64, (f64, f64))> = Vec::new(); // 横向上仍可能与后续节点重叠的节点
+        for (id, coor) in nodes {
+            active.retain(|(_, other)| coor.0 - other.0 < size.0);
+            for (other_id, other) in active.iter() {
+                if intersects(coor, *other, size) {
+                    pairs.push((id.min(*other_id), id.max(*other_id)));
+                }
+            }
+            active.push((id, coor));
+        }
+
+        pairs.sort_unstable();
+        pairs
+    }
+
+    /// 在 near 附近寻找不与任何节点重叠的位置，exclude 中的节点不参与检测。
+    /// 以节点大小为步长，由近及远逐圈搜索。
+    pub fn find_free_position(
+        &self,
+        near: (f64, f64),
+        size: (f64, f64),
+        exclude: &BTreeSet<u64>,
+    ) -> (f64, f64) {
+        let is_free = |coor: (f64, f64)| {
+            self.nodes
+                .values()
+                .filter(|node| !exclude.contains(&node.id))
+                .all(|node| !intersects(coor, node.coor, size))
+        };
+
+        for ring in 0..=self.nodes.len() as i64 {
+            // 当前圈上的候选位置，先右后下，使结果尽量靠近原位置的右下方
+            let candidates = (-ring..=ring).flat_map(|dy| {
+                (-ring..=ring)
+                    .filter(move |dx| dx.abs() == ring || dy.abs() == ring)
+                    .map(move |dx| (dx, dy))
+            });
+            let mut candidates = candidates.collect::<Vec<_>>();
+            candidates.sort_by_key(|(dx, dy)| (dx.abs() + dy.abs(), -dx, -dy));
+            for (dx, dy) in candidates {
+                let coor = (near.0 + dx as f64 * size.0, near.1 + dy as f64 * size.1);
+                if is_free(coor) {
+                    return coor;
+                }
+            }
+        }
+
+        // 节点数有限，不会到达此处
+        near
+    }
+
+    /// 计算消除重叠所需的移动：每对重叠节点中 ID 较大的节点移到附近的空闲位置。
+    /// 依次在移动后的布局上计算，因此移动不会产生新的重叠。
+    pub fn resolve_overlaps(&self, size: (f64, f64)) -> HashMap<u64, (f64, f64)> {
+        let mut layout = self.clone();
+        let mut moves = HashMap::new();
+        let movers = self
+            .overlapping_pairs(size)
+            .into_iter()
+            .map(|(_, later)| later)
+            .collect::<BTreeSet<_>>();
+
+        for id in movers {
+            let coor = layout.nodes[&id].coor;
+            let exclude = BTreeSet::from([id]);
+            // 之前的移动可能已消除了此节点的重叠
+            let overlapping = layout
+                .nodes
+                .values()
+                .any(|node| node.id != id && intersects(coor, node.coor, size));
+            if !overlapping {
+                continue;
+            }
+
+            let free = layout.find_free_position(coor, size, &exclude);
+            if let Some(node) = layout.nodes.get_mut(&id) {
+                node.coor = free;
+            }
+            moves.insert(id, free);
+        }
+
+        moves
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::graph::{DistinctEntityType, KnowledgeGraph};
+
+    use super::*;
+
+    fn create_graph(coors: &[(f64, f64)]) -> KnowledgeGraph {
+        let mut graph = KnowledgeGraph::default();
+        for (i, coor) in coors.iter().enumerate() {
+            graph
+                .add_entity(
+                    format!("Node {i}"),
+                    DistinctEntityType::KnowledgePoint,
+                    &[],
+                    *coor,
+                )
+                .unwrap();
+        }
+        graph
+    }
+
+    #[test]
+    fn test_overlapping_pairs() {
+        let graph = create_graph(&[
+            (0.0, 0.0),
+            (100.0, 50.0),   // 与 1 重叠
+            (150.0, 0.0),    // 与 1 恰好相接，与 2 重叠
+            (1000.0, 0.0),   // 不重叠
+            (100.0, 1000.0), // 横向与 2 重叠，纵向不重叠
+        ]);
+        assert_eq!(
+            graph.current.overlapping_pairs(NODE_SIZE),
+            vec![(1, 2), (2, 3)]
+        );
+    }
+
+    #[test]
+    fn test_resolve_overlaps() {
+        // 三个节点两两重叠，且右侧恰好紧邻另一个节点，移动一对不能产生新的重叠
+        let mut graph = create_graph(&[(0.0, 0.0), (10.0, 10.0), (20.0, 0.0), (170.0, 0.0)]);
+        let snapshot = &graph.current;
+        assert_eq!(
+            snapshot.overlapping_pairs(NODE_SIZE),
+            vec![(1, 2), (1, 3), (2, 3)]
+        );
+
+        let moves = snapshot.resolve_overlaps(NODE_SIZE);
+        assert!(!moves.contains_key(&1)); // 较早的节点保持不动
+        for (id, coor) in moves {
+            graph.update_entity_position(id, coor).unwrap();
+        }
+        assert!(graph.current.overlapping_pairs(NODE_SIZE).is_empty());
+    }
+}
@@ -0,0 +1,359 @@
+//! PDF 导出模块，将图谱绘制为矢量的 PDF 文件，用于打印。
+//! 按节点的包围盒缩放到指定的横向页数，纵向按需分页，拼接后即为完整的图谱。
+//!
+//! 文字使用阅读器内置的 STSong-Light 中文字体，不嵌入字体文件，因此只支持基本多文种平面的字符。
+
+use std::fmt::Write;
+
+use super::{NODE_SIZE, Snapshot, thumbnail};
+
+const PAGE_WIDTH: f64 = 842.0; // A4 横向的宽度（点）
+const PAGE_HEIGHT: f64 = 595.0; // A4 横向的高度（点）
+const PAGE_MARGIN: f64 = 36.0; // 页面边缘的留白（点），打印机通常无法打印到纸张边缘
+const CONTENT_PADDING: f64 = 20.0; // 包围盒外额外绘制的范围（逻辑长度），避免边缘的线条被裁切
+const FONT_SIZE: f64 = 12.0; // 节点文字的大小（逻辑长度），与画布一致
+const TEXT_PADDING: f64 = 8.0; // 节点文字与边框的距离（逻辑长度）
+const ARROW_LENGTH: f64 = 12.0; // 箭头的长度（逻辑长度）
+
+impl Snapshot {
+    /// 将图谱导出为 PDF，缩放到横向占 pages_across 页（至少为 1），纵向按需分页，从左到右、从上到下排列。
+    /// 分为多页时在每页底部标注行列号，便于拼接。仅作参考的节点不导出
+    pub fn to_pdf(&self, pages_across: u32) -> Vec<u8> {
+        if let Some(snapshot) = self.without_references() {
+            return snapshot.to_pdf(pages_across);
+        }
+        let pages_across = pages_across.max(1) as usize;
+        let (inner_width, inner_height) = (
+            PAGE_WIDTH - 2.0 * PAGE_MARGIN,
+            PAGE_HEIGHT - 2.0 * PAGE_MARGIN,
+        );
+
+        let half = (NODE_SIZE.0 / 2.0, NODE_SIZE.1 / 2.0);
+        let bounds = self.nodes.values().fold(None, |bounds, node| {
+            let (x, y) = node.coor;
+            let (min_x, min_y, max_x, max_y) = bounds.unwrap_or((x, y, x, y));
+            Some((min_x.min(x), min_y.min(y), max_x.max(x), max_y.max(y)))
+        });
+        let Some((min_x, min_y, max_x, max_y)) = bounds else {
+            return write_pdf(&[String::new()]);
+        };
+        let padding = (half.0 + CONTENT_PADDING, half.1 + CONTENT_PADDING);
+        let (min_x, min_y) = (min_x - padding.0, min_y - padding.1);
+        let graph_width = max_x + padding.0 - min_x;
+        let graph_height = max_y + padding.1 - min_y;
+
+        // 整张图的坐标（点），原点在左下角
+        let scale = pages_across as f64 * inner_width / graph_width;
+        let total_height = graph_height * scale;
+        let to_point =
+            |(x, y): (f64, f64)| ((x - min_x) * scale, total_height - (y - min_y) * scale);
+        let drawing = self.pdf_drawing(to_point, scale);
+
+        let rows = ((total_height / inner_height).ceil() as usize).max(1);
+        let mut pages = Vec::with_capacity(rows * pages_across);
+        for row in 0..rows {
+            for column in 0..pages_across {
+                let tx = PAGE_MARGIN - column as f64 * inner_width;
+                let ty = PAGE_MARGIN - (total_height - (row + 1) as f64 * inner_height);
+                let mut page = format!(
+                    "q\n{PAGE_MARGIN:.2} {PAGE_MARGIN:.2} {inner_width:.2} {inner_height:.2} re W n\n\
+                     1 0 0 1 {tx:.2} {ty:.2} cm\n{drawing}Q\n"
+                );
+                if rows * pages_across > 1 {
+                    let label = format!("第 {} 行第 {} 列", row + 1, column + 1);
+                    let _ = writeln!(
+                        page,
+                        "0 0 0 rg\nBT /F1 8 Tf {PAGE_MARGIN:.2} {:.2} Td <{}> Tj ET",
+                        PAGE_MARGIN / 2.0,
+                        hex_text(&label)
+                    );
+                }
+                pages.push(page);
+            }
+        }
+        write_pdf(&pages)
+    }
+
+    /// 所有边和节点的绘制指令，先画边，再画节点，使连线被节点覆盖
+    fn pdf_drawing<F>(&self, to_point: F, scale: f64) -> String
+    where
+        F: Fn((f64, f64)) -> (f64, f64),
+    {
+        let half = (NODE_SIZE.0 / 2.0 * scale, NODE_SIZE.1 / 2.0 * scale);
+        let mut drawing = String::new();
+
+        let mut edges = self.edges.iter().collect::<Vec<_>>();
+        edges.sort_unstable_by_key(|(key, _)| **key);
+        for (&(from, to), relation) in edges {
+            let (Some(from), Some(to)) = (self.nodes.get(&from), self.nodes.get(&to)) else {
+                continue;
+            };
+            let color = thumbnail::relation_color(*relation);
+            let (from, to) = (to_point(from.coor), to_point(to.coor));
+            let start = rect_boundary_point(from, to, half);
+            let end = rect_boundary_point(to, from, half);
+            let (r, g, b) = rgb(color.0);
+            let _ = writeln!(
+                drawing,
+                "{r:.3} {g:.3} {b:.3} RG {r:.3} {g:.3} {b:.3} rg {:.2} w",
+                scale.max(0.1)
+            );
+            let _ = writeln!(
+                drawing,
+                "{:.2} {:.2} m {:.2} {:.2} l S",
+                start.0, start.1, end.0, end.1
+            );
+
+            // 终点处的实心箭头
+            let (dx, dy) = (end.0 - start.0, end.1 - start.1);
+            let length = (dx * dx + dy * dy).sqrt();
+            if length > f64::EPSILON {
+                let (ux, uy) = (dx / length, dy / length);
+                let arrow = ARROW_LENGTH * scale;
+                let base = (end.0 - ux * arrow, end.1 - uy * arrow);
+                let side = (-uy * arrow / 2.0, ux * arrow / 2.0);
+                let _ = writeln!(
+                    drawing,
+                    "{:.2} {:.2} m {:.2} {:.2} l {:.2} {:.2} l f",
+                    end.0,
+                    end.1,
+                    base.0 + side.0,
+                    base.1 + side.1,
+                    base.0 - side.0,
+                    base.1 - side.1
+                );
+            }
+        }
+
+        let mut nodes = self.nodes.values().collect::<Vec<_>>();
+        nodes.sort_unstable_by_key(|node| node.id);
+        let font_size = FONT_SIZE * scale;
+        let padding = TEXT_PADDING * scale;
+        for node in nodes {
+            let (cx, cy) = to_point(node.coor);
+            let (r, g, b) = rgb(thumbnail::node_color(node.distinct_type).0);
+            let _ = writeln!(
+                drawing,
+                "{r:.3} {g:.3} {b:.3} rg 0 0 0 RG {:.2} w {:.2} {:.2} {:.2} {:.2} re B",
+                (scale / 2.0).max(0.1),
+                cx - half.0,
+                cy - half.1,
+                half.0 * 2.0,
+                half.1 * 2.0
+            );
+
+            let line_height = font_size * 1.2;
+            let max_lines = ((half.1 * 2.0 - 2.0 * padding) / line_height).floor() as usize;
+            let lines = wrap_text(
+                &node.content,
+                (half.0 * 2.0 - 2.0 * padding) / font_size,
+                max_lines,
+            );
+            // 文字整体在节点中垂直居中，每行水平居中
+            let top = cy + lines.len() as f64 * line_height / 2.0;
+            let _ = writeln!(drawing, "0 0 0 rg");
+            for (i, line) in lines.iter().enumerate() {
+                let x = cx - text_width(line) * font_size / 2.0;
+                let y = top - (i as f64 + 1.0) * line_height + (line_height - font_size) / 2.0;
+                let _ = writeln!(
+                    drawing,
+                    "BT /F1 {font_size:.2} Tf {x:.2} {y:.2} Td <{}> Tj ET",
+                    hex_text(line)
+                );
+            }
+        }
+        drawing
+    }
+}
+
+/// 将 0 到 255 的颜色分量转换为 PDF 使用的 0 到 1
+fn rgb([r, g, b]: [u8; 3]) -> (f64, f64, f64) {
+    (r as f64 / 255.0, g as f64 / 255.0, b as f64 / 255.0)
+}
+
+/// 从矩形中心 center 指向 toward 的射线与矩形边框的交点
+fn rect_boundary_point(center: (f64, f64), toward: (f64, f64), half: (f64, f64)) -> (f64, f64) {
+    let (dx, dy) = (toward.0 - center.0, toward.1 - center.1);
+    if dx == 0.0 && dy == 0.0 {
+        return center;
+    }
+    let t = (half.0 / dx.abs()).min(half.1 / dy.abs()).min(1.0);
+    (center.0 + dx * t, center.1 + dy * t)
+}
+
+/// 字符的宽度，以字号为单位。半角字符为一半，其余为全角
+fn char_width(c: char) -> f64 {
+    if c.is_ascii() { 0.5 } else { 1.0 }
+}
+
+fn text_width(text: &str) -> f64 {
+    text.chars().map(char_width).sum()
+}
+
+/// 按宽度（以字号为单位）将文字折行，保留原有的换行。超过 max_lines 行时截断，最后一行以省略号结尾
+fn wrap_text(text: &str, width: f64, max_lines: usize) -> Vec<String> {
+    let mut lines = Vec::new();
+    for paragraph in text.lines() {
+        let mut line = String::new();
+        let mut line_width = 0.0;
+        for c in paragraph.chars() {
+            let w = char_width(c);
+            if line_width + w > width && !line.is_empty() {
+                lines.push(std::mem::take(&mut line));
+                line_width = 0.0;
+            }
+            line.push(c);
+            line_width += w;
+        }
+        lines.push(line);
+    }
+    if lines.len() > max_lines {
+        lines.truncate(max_lines);
+        if let Some(last) = lines.last_mut() {
+            while !last.is_empty() && text_width(last) + 1.0 > width {
+                last.pop();
+            }
+            last.push('…');
+        }
+    }
+    lines
+}
+
+/// 将文字编码为 UniGB-UCS2-H 使用的十六进制字符串，基本多文种平面以外的字符以问号代替
+fn hex_text(text: &str) -> String {
+    text.chars()
+        .map(|c| {
+            let code = u16::try_from(c as u32).unwrap_or(b'?' as u16);
+            format!("{code:04X}")
+        })
+        .collect()
+}
+
+/// 按页面的绘制指令生成完整的 PDF 文件
+fn write_pdf(pages: &[String]) -> Vec<u8> {
+    // 对象 1 为目录，2 为页面树，3 至 5 为字体，之后每页依次为页面和内容流
+    let mut objects = vec![
+        "<< /Type /Catalog /Pages 2 0 R >>".to_string(),
+        format!(
+            "<< /Type /Pages /Kids [{}] /Count {} >>",
+            (0..pages.len())
+                .map(|i| format!("{} 0 R", 6 + 2 * i))
+                .collect::<Vec<_>>()
+                .join(" "),
+            pages.len()
+        ),
+        "<< /Type /Font /Subtype /Type0 /BaseFont /STSong-Light /Encoding /UniGB-UCS2-H \
+         /DescendantFonts [4 0 R] >>"
+            .to_string(),
+        "<< /Type /Font /Subtype /CIDFontType0 /BaseFont /STSong-Light \
+         /CIDSystemInfo << /Registry (Adobe) /Ordering (GB1) /Supplement 4 >> \
+         /FontDescriptor 5 0 R /DW 1000 /W [1 95 500] >>"
+            .to_string(),
+        "<< /Type /FontDescriptor /FontName /STSong-Light /Flags 6 \
+         /FontBBox [-25 -254 1000 880] /ItalicAngle 0 /Ascent 880 /Descent -120 \
+         /CapHeight 880 /StemV 93 >>"
+            .to_string(),
+    ];
+    for (i, content) in pages.iter().enumerate() {
+        objects.push(format!(
+            "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 {PAGE_WIDTH} {PAGE_HEIGHT}] \
+             /Resources << /Font << /F1 3 0 R >> >> /Contents {} 0 R >>",
+            7 + 2 * i
+        ));
+        objects.push(format!(
+            "<< /Length {} >>\nstream\n{content}\nendstream",
+            content.len() + 1
+        ));
+    }
+
+    let mut pdf = b"%PDF-1.4\n".to_vec();
+    let mut offsets = Vec::with_capacity(objects.len());
+    for (i, object) in objects.iter().enumerate() {
+        offsets.push(pdf.len());
+        pdf.extend_from_slice(format!("{} 0 obj\n{object}\nendobj\n", i + 1).as_bytes());
+    }
+    let xref = pdf.len();
+    let mut trailer = format!("xref\n0 {}\n0000000000 65535 f \n", objects.len() + 1);
+    for offset in offsets {
+        let _ = writeln!(trailer, "{offset:010} 00000 n ");
+    }
+    let _ = write!(
+        trailer,
+        "trailer\n<< /Size {} /Root 1 0 R >>\nstartxref\n{xref}\n%%EOF\n",
+        objects.len() + 1
+    );
+    pdf.extend_from_slice(trailer.as_bytes());
+    pdf
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::{DistinctEntityType, KnowledgeGraph, Relation};
+
+    /// 从 PDF 中读取交叉引用表的位置，检查其指向 xref
+    fn check_xref(pdf: &[u8]) {
+        let text = String::from_utf8_lossy(pdf);
+        let start = text.rfind("startxref\n").unwrap() + "startxref\n".len();
+        let offset = text[start..]
+            .lines()
+            .next()
+            .unwrap()
+            .parse::<usize>()
+            .unwrap();
+        assert!(pdf[offset..].starts_with(b"xref\n"));
+    }
+
+    #[test]
+    fn test_wrap_text() {
+        assert_eq!(wrap_text("abcd", 1.0, 10), vec!["ab", "cd"]);
+        assert_eq!(wrap_text("计算思维", 2.0, 10), vec!["计算", "思维"]);
+        assert_eq!(
+            wrap_text("第一行\n第二行", 10.0, 10),
+            vec!["第一行", "第二行"]
+        );
+        assert_eq!(wrap_text("计算思维方法", 3.0, 1), vec!["计算…"]);
+        assert_eq!(hex_text("A计"), "00418BA1");
+    }
+
+    #[test]
+    fn test_to_pdf() {
+        let empty = Snapshot::default().to_pdf(1);
+        assert!(empty.starts_with(b"%PDF-1.4"));
+        assert!(String::from_utf8_lossy(&empty).contains("/Count 1"));
+        check_xref(&empty);
+
+        let mut graph = KnowledgeGraph::default();
+        let a = graph
+            .add_entity(
+                "计算思维".to_string(),
+                DistinctEntityType::KnowledgeUnit,
+                &[],
+                (0.0, 0.0),
+            )
+            .unwrap();
+        let b = graph
+            .add_entity(
+                "小白鼠问题".to_string(),
+                DistinctEntityType::KnowledgePoint,
+                &[],
+                (3000.0, 300.0),
+            )
+            .unwrap();
+        graph.add_edge(a, b, Relation::Contain).unwrap();
+
+        let pdf = graph.current.to_pdf(1);
+        let text = String::from_utf8_lossy(&pdf);
+        assert!(text.contains("/Count 1"));
+        assert!(text.contains(&hex_text("计算思维")));
+        assert!(!text.contains(&hex_text("第 1 行第 1 列")));
+        check_xref(&pdf);
+
+        // 横向两页时放大一倍，分为两页并标注行列号
+        let pdf = graph.current.to_pdf(2);
+        let text = String::from_utf8_lossy(&pdf);
+        assert!(text.contains("/Count 2"));
+        assert!(text.contains(&hex_text("第 1 行第 2 列")));
+        check_xref(&pdf);
+    }
+}
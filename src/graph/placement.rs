@@ -0,0 +1,118 @@
+//! 下级节点的默认位置。按惯例，知识单元和知识点在上级节点下方横向展开，
+//! 关键知识细节在上级节点右侧纵向排列。
+
+use std::collections::BTreeSet;
+
+use super::{DistinctEntityType, NODE_SIZE, Relation, Snapshot};
+
+/// 相邻节点之间的间距，以节点大小为单位
+const SPACING: f64 = 0.5;
+
+/// 为 parent_id 新建 child_type 类型的下级节点时建议的位置。
+/// 关键知识细节排在上级节点右侧，其余类型排在上级节点下方，按已有的下级节点数依次向后排列，
+/// 与已有节点重叠时移到附近的空闲位置。上级节点不存在时返回原点附近的空闲位置
+///
+/// ```
+/// use py_better_kt_sqep::graph::{
+///     DistinctEntityType, KnowledgeGraph, NODE_SIZE, suggest_child_position,
+/// };
+///
+/// let mut graph = KnowledgeGraph::default();
+/// let parent = graph
+///     .add_entity("计算机科学".to_string(), DistinctEntityType::KnowledgeArena, &[], (0.0, 0.0))
+///     .unwrap();
+/// let (x, y) = suggest_child_position(
+///     graph.current_snapshot(),
+///     parent,
+///     DistinctEntityType::KnowledgeUnit,
+/// );
+/// assert_eq!(x, 0.0);
+/// assert!(y >= NODE_SIZE.1);
+/// ```
+pub fn suggest_child_position(
+    snapshot: &Snapshot,
+    parent_id: u64,
+    child_type: DistinctEntityType,
+) -> (f64, f64) {
+    let Some(parent) = snapshot.nodes.get(&parent_id) else {
+        return snapshot.find_free_position((0.0, 0.0), NODE_SIZE, &BTreeSet::new());
+    };
+    let children = snapshot
+        .edges
+        .iter()
+        .filter(|((from, _), relation)| *from == parent_id && **relation == Relation::Contain)
+        .count() as f64;
+
+    let step = (NODE_SIZE.0 * (1.0 + SPACING), NODE_SIZE.1 * (1.0 + SPACING));
+    let (x, y) = parent.coor;
+    let near = match child_type {
+        DistinctEntityType::KnowledgeDetail => (x + step.0, y + children * step.1),
+        _ => (x + children * step.0, y + step.1),
+    };
+    snapshot.find_free_position(near, NODE_SIZE, &BTreeSet::new())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::KnowledgeGraph;
+
+    #[test]
+    fn test_suggest_child_position() {
+        let mut graph = KnowledgeGraph::default();
+        let arena = graph
+            .add_entity(
+                "领域".to_string(),
+                DistinctEntityType::KnowledgeArena,
+                &[],
+                (0.0, 0.0),
+            )
+            .unwrap();
+        let unit = DistinctEntityType::KnowledgeUnit;
+
+        // 知识单元在下方横向展开
+        let first = suggest_child_position(&graph.current, arena, unit);
+        assert_eq!(first, (0.0, 180.0));
+        let id = graph.add_entity(String::new(), unit, &[], first).unwrap();
+        graph.add_edge(arena, id, Relation::Contain).unwrap();
+        let second = suggest_child_position(&graph.current, arena, unit);
+        assert_eq!(second, (225.0, 180.0));
+
+        // 关键知识细节在右侧纵向排列
+        let point = graph
+            .add_entity(
+                "知识点".to_string(),
+                DistinctEntityType::KnowledgePoint,
+                &[],
+                (1000.0, 0.0),
+            )
+            .unwrap();
+        let detail = DistinctEntityType::KnowledgeDetail;
+        let first = suggest_child_position(&graph.current, point, detail);
+        assert_eq!(first, (1225.0, 0.0));
+        let id = graph.add_entity(String::new(), detail, &[], first).unwrap();
+        graph.add_edge(point, id, Relation::Contain).unwrap();
+        assert_eq!(
+            suggest_child_position(&graph.current, point, detail),
+            (1225.0, 180.0)
+        );
+
+        // 建议的位置被占用时移到空闲位置
+        graph.add_entity(String::new(), unit, &[], second).unwrap();
+        let moved = suggest_child_position(&graph.current, arena, unit);
+        assert_ne!(moved, second);
+        assert!(graph.current.overlapping_pairs(NODE_SIZE).is_empty());
+        assert!(
+            graph
+                .current
+                .nodes
+                .values()
+                .all(|node| (node.coor.0 - moved.0).abs() >= NODE_SIZE.0
+                    || (node.coor.1 - moved.1).abs() >= NODE_SIZE.1)
+        );
+
+        // 上级节点不存在时放在原点附近
+        let orphan = suggest_child_position(&graph.current, 99, unit);
+        assert_ne!(orphan, (0.0, 0.0));
+    }
+}
@@ -0,0 +1,175 @@
+//! 主父节点模块。一个知识点可能同时属于多个知识单元，包含它的父节点中有一个为主父节点，
+//! 其余为次要父节点。面包屑优先沿主父节点追溯，教学要求矩阵只沿主父节点统计。
+
+use std::collections::{BTreeSet, HashMap, HashSet};
+
+use super::{Command, KnowledgeGraph, Relation, Snapshot};
+use crate::error::GraphError;
+
+impl Snapshot {
+    /// 节点的主父节点：记录的主父节点仍通过包含关系包含该节点时为它，否则为 ID 最小的父节点。
+    /// 没有父节点时返回 None
+    pub fn primary_parent(&self, id: u64) -> Option<u64> {
+        let parents = self.parents(id);
+        match self.nodes.get(&id).and_then(|node| node.primary_parent) {
+            Some(parent) if parents.contains(&parent) => Some(parent),
+            _ => parents.first().copied(),
+        }
+    }
+
+    /// 所有有父节点的节点到其主父节点的映射，规则同 primary_parent
+    pub fn primary_parents(&self) -> HashMap<u64, u64> {
+        let mut parents = HashMap::<u64, Vec<u64>>::new();
+        for ((from, to), relation) in self.edges.iter() {
+            if *relation == Relation::Contain {
+                parents.entry(*to).or_default().push(*from);
+            }
+        }
+        parents
+            .into_iter()
+            .filter_map(|(child, parents)| {
+                let recorded = self.nodes.get(&child).and_then(|node| node.primary_parent);
+                let primary = match recorded {
+                    Some(parent) if parents.contains(&parent) => parent,
+                    _ => parents.into_iter().min()?,
+                };
+                Some((child, primary))
+            })
+            .collect()
+    }
+
+    /// 次要包含边，即起点不是终点的主父节点的包含边
+    pub fn secondary_contains(&self) -> HashSet<(u64, u64)> {
+        let primary = self.primary_parents();
+        self.edges
+            .iter()
+            .filter(|((from, to), relation)| {
+                **relation == Relation::Contain && primary.get(to) != Some(from)
+            })
+            .map(|(edge, _)| *edge)
+            .collect()
+    }
+
+    /// 只沿主父节点向下收集的所有下级节点，不含根节点自身，按 ID 排列
+    pub fn primary_descendants(&self, root: u64) -> Vec<u64> {
+        primary_descendants(&primary_children(&self.primary_parents()), root)
+            .into_iter()
+            .collect()
+    }
+
+    /// 将 parent 设为 child 的主父节点所需的命令，已是主父节点时为空。
+    /// 节点不存在时返回 EntityNotFound，parent 不包含 child 时返回 EdgeNotFound
+    pub fn set_primary_parent_commands(
+        &self,
+        child: u64,
+        parent: u64,
+    ) -> Result<Vec<Command>, GraphError> {
+        let node = self
+            .nodes
+            .get(&child)
+            .ok_or(GraphError::EntityNotFound(child))?;
+        if self.edges.get(&(parent, child)) != Some(&Relation::Contain) {
+            return Err(GraphError::EdgeNotFound(parent, child));
+        }
+        if node.primary_parent == Some(parent) {
+            return Ok(Vec::new());
+        }
+        let mut node = node.clone();
+        node.primary_parent = Some(parent);
+        Ok(vec![Command::ReplaceEntity(node)])
+    }
+}
+
+/// 由主父节点的映射得到每个节点的主子节点
+pub(super) fn primary_children(primary_parents: &HashMap<u64, u64>) -> HashMap<u64, Vec<u64>> {
+    let mut children = HashMap::<u64, Vec<u64>>::new();
+    for (child, parent) in primary_parents.iter() {
+        children.entry(*parent).or_default().push(*child);
+    }
+    children
+}
+
+/// 沿主子节点收集 root 的所有下级节点，主父节点成环时在重复的节点处停止
+pub(super) fn primary_descendants(children: &HashMap<u64, Vec<u64>>, root: u64) -> BTreeSet<u64> {
+    let mut result = BTreeSet::new();
+    let mut stack = vec![root];
+    while let Some(id) = stack.pop() {
+        for child in children.get(&id).into_iter().flatten() {
+            if *child != root && result.insert(*child) {
+                stack.push(*child);
+            }
+        }
+    }
+    result
+}
+
+impl KnowledgeGraph {
+    /// 将 parent 设为 child 的主父节点，作为一次可撤回的操作。返回是否有修改
+    pub fn set_primary_parent(&mut self, child: u64, parent: u64) -> Result<bool, GraphError> {
+        let commands = self.current.set_primary_parent_commands(child, parent)?;
+        let changed = !commands.is_empty();
+        if changed {
+            self.apply_batch(commands)?;
+        }
+        Ok(changed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::DistinctEntityType;
+
+    #[test]
+    fn test_primary_parent() {
+        let mut graph = KnowledgeGraph::default();
+        for content in ["单元一", "单元二", "知识点", "细节"] {
+            graph
+                .add_entity(
+                    content.to_string(),
+                    DistinctEntityType::KnowledgePoint,
+                    &[],
+                    (0.0, 0.0),
+                )
+                .unwrap();
+        }
+        graph.add_edge(1, 3, Relation::Contain).unwrap();
+        graph.add_edge(2, 3, Relation::Contain).unwrap();
+        graph.add_edge(3, 4, Relation::Contain).unwrap();
+        graph.add_edge(2, 4, Relation::Order).unwrap(); // 次序关系不算父节点
+
+        // 未记录时以 ID 最小的父节点为主
+        let snapshot = graph.current_snapshot();
+        assert_eq!(snapshot.primary_parent(3), Some(1));
+        assert_eq!(snapshot.primary_parent(1), None);
+        assert_eq!(snapshot.secondary_contains(), HashSet::from([(2, 3)]));
+        assert_eq!(snapshot.primary_descendants(1), vec![3, 4]);
+        assert!(snapshot.primary_descendants(2).is_empty());
+
+        // 设为主父节点是一次可撤回的操作
+        let undo_len = graph.undo_len();
+        assert_eq!(graph.set_primary_parent(3, 2), Ok(true));
+        assert_eq!(graph.set_primary_parent(3, 2), Ok(false));
+        assert_eq!(graph.undo_len(), undo_len + 1);
+        let snapshot = graph.current_snapshot();
+        assert_eq!(snapshot.primary_parent(3), Some(2));
+        assert_eq!(snapshot.primary_parents()[&3], 2);
+        assert_eq!(snapshot.secondary_contains(), HashSet::from([(1, 3)]));
+        assert_eq!(snapshot.primary_descendants(2), vec![3, 4]);
+
+        // 不是父节点或节点不存在
+        assert_eq!(
+            graph.set_primary_parent(4, 2),
+            Err(GraphError::EdgeNotFound(2, 4))
+        );
+        assert_eq!(
+            graph.set_primary_parent(9, 2),
+            Err(GraphError::EntityNotFound(9))
+        );
+
+        // 主父节点的包含边删除后改以剩余的父节点为主
+        graph.remove_edge(2, 3).unwrap();
+        assert_eq!(graph.current.primary_parent(3), Some(1));
+        assert!(graph.current.secondary_contains().is_empty());
+    }
+}
@@ -0,0 +1,186 @@
+//! 双向边模块。手动连线或导入可能使两个节点之间同时存在两个方向的边：双向的包含关系使层级矛盾，
+//! 其他关系则是重复的。导出前列出这些边，由用户选择保留的方向，或按层级自动选择。
+
+use super::{
+    Command, DistinctEntityType, IssueLevel, KnowledgeGraph, Relation, Snapshot, ValidationIssue,
+};
+use crate::error::GraphError;
+
+/// 两个节点之间两个方向的边
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ReciprocalEdge {
+    pub forward: (u64, u64), // 起点 ID 较小的边
+    pub forward_relation: Relation,
+    pub backward_relation: Relation,
+    pub suggested: Option<(u64, u64)>, // 按层级应保留的边，层级相同时无法判断
+}
+
+impl ReciprocalEdge {
+    /// 反方向的边
+    #[inline]
+    pub fn backward(&self) -> (u64, u64) {
+        (self.forward.1, self.forward.0)
+    }
+
+    /// 是否为层级矛盾，即有一个方向是包含关系
+    #[inline]
+    pub fn is_conflict(&self) -> bool {
+        self.forward_relation == Relation::Contain || self.backward_relation == Relation::Contain
+    }
+}
+
+/// 节点类型的层级，知识领域为 0，越往下越大
+fn level(distinct_type: DistinctEntityType) -> usize {
+    DistinctEntityType::ALL
+        .iter()
+        .position(|t| *t == distinct_type)
+        .unwrap_or_default()
+}
+
+impl Snapshot {
+    /// 所有双向边，按节点 ID 排列。两端节点的层级不同时，建议保留从上级指向下级的边
+    pub fn reciprocal_edges(&self) -> Vec<ReciprocalEdge> {
+        let mut result = self
+            .edges
+            .iter()
+            .filter(|((from, to), _)| from < to)
+            .filter_map(|((from, to), relation)| {
+                let backward_relation = *self.edges.get(&(*to, *from))?;
+                let levels = (
+                    level(self.nodes.get(from)?.distinct_type),
+                    level(self.nodes.get(to)?.distinct_type),
+                );
+                let suggested = match levels.0.cmp(&levels.1) {
+                    std::cmp::Ordering::Less => Some((*from, *to)),
+                    std::cmp::Ordering::Greater => Some((*to, *from)),
+                    std::cmp::Ordering::Equal => None,
+                };
+                Some(ReciprocalEdge {
+                    forward: (*from, *to),
+                    forward_relation: *relation,
+                    backward_relation,
+                    suggested,
+                })
+            })
+            .collect::<Vec<_>>();
+        result.sort_unstable_by_key(|edge| edge.forward);
+        result
+    }
+
+    /// 双向边的警告，每对节点一条
+    pub(super) fn check_reciprocal_edges(&self, issues: &mut Vec<ValidationIssue>) {
+        for edge in self.reciprocal_edges() {
+            let (a, b) = edge.forward;
+            let message = if edge.is_conflict() {
+                format!("节点 {a} 与 {b} 之间有双向的边，包含关系的层级矛盾")
+            } else {
+                format!("节点 {a} 与 {b} 之间有双向的边，关系重复")
+            };
+            issues.push(ValidationIssue::new(
+                IssueLevel::Warning,
+                message,
+                vec![a, b],
+            ));
+        }
+    }
+
+    /// 对每对有双向边的节点，保留 keep 中列出的那条边、删除另一条所需的命令。
+    /// keep 中不是双向边的项忽略，没有列出的双向边保持不变
+    pub fn normalize_edges_commands(&self, keep: &[(u64, u64)]) -> Vec<Command> {
+        self.reciprocal_edges()
+            .into_iter()
+            .filter_map(|edge| {
+                let (from, to) = if keep.contains(&edge.forward) {
+                    edge.backward()
+                } else if keep.contains(&edge.backward()) {
+                    edge.forward
+                } else {
+                    return None;
+                };
+                Some(Command::RemoveEdge { from, to })
+            })
+            .collect()
+    }
+}
+
+impl KnowledgeGraph {
+    /// 对每对有双向边的节点保留 keep 中列出的边，整体作为一次可撤回的操作，返回删除的边数
+    pub fn normalize_edges(&mut self, keep: &[(u64, u64)]) -> Result<usize, GraphError> {
+        let commands = self.current.normalize_edges_commands(keep);
+        let count = commands.len();
+        if count > 0 {
+            self.apply_batch(commands)?;
+        }
+        Ok(count)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reciprocal_edges() {
+        let mut graph = KnowledgeGraph::default();
+        for distinct_type in [
+            DistinctEntityType::KnowledgeUnit,
+            DistinctEntityType::KnowledgePoint,
+            DistinctEntityType::KnowledgePoint,
+            DistinctEntityType::KnowledgePoint,
+        ] {
+            graph
+                .add_entity(String::new(), distinct_type, &[], (0.0, 0.0))
+                .unwrap();
+        }
+        // 单元与知识点之间双向包含，知识点之间双向次序，另有一条单向的边
+        graph.add_edge(2, 1, Relation::Contain).unwrap();
+        graph.add_edge(1, 2, Relation::Contain).unwrap();
+        graph.add_edge(3, 4, Relation::Order).unwrap();
+        graph.add_edge(4, 3, Relation::Order).unwrap();
+        graph.add_edge(1, 3, Relation::Contain).unwrap();
+
+        let edges = graph.current.reciprocal_edges();
+        assert_eq!(
+            edges,
+            vec![
+                ReciprocalEdge {
+                    forward: (1, 2),
+                    forward_relation: Relation::Contain,
+                    backward_relation: Relation::Contain,
+                    suggested: Some((1, 2)),
+                },
+                ReciprocalEdge {
+                    forward: (3, 4),
+                    forward_relation: Relation::Order,
+                    backward_relation: Relation::Order,
+                    suggested: None,
+                },
+            ]
+        );
+        assert!(edges[0].is_conflict());
+        assert!(!edges[1].is_conflict());
+        let issues = graph.current.validate();
+        assert!(
+            issues
+                .iter()
+                .any(|issue| issue.entities == vec![1, 2] && issue.message.contains("层级矛盾"))
+        );
+
+        // 按建议保留，层级相同的保持不变，整体作为一次撤回
+        let keep = edges
+            .iter()
+            .filter_map(|edge| edge.suggested)
+            .collect::<Vec<_>>();
+        let undo_len = graph.undo_len();
+        assert_eq!(graph.normalize_edges(&keep), Ok(1));
+        assert_eq!(graph.undo_len(), undo_len + 1);
+        assert!(graph.current.edges.contains_key(&(1, 2)));
+        assert!(!graph.current.edges.contains_key(&(2, 1)));
+
+        // 由用户选择保留的方向
+        assert_eq!(graph.normalize_edges(&[(4, 3), (1, 3)]), Ok(1));
+        assert!(graph.current.edges.contains_key(&(4, 3)));
+        assert!(graph.current.reciprocal_edges().is_empty());
+        assert_eq!(graph.normalize_edges(&[(4, 3)]), Ok(0));
+    }
+}
@@ -0,0 +1,193 @@
+//! 宏模块，将录制的一串编辑命令保存为宏，之后可在任意图谱上回放。
+
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use super::{Command, CommandResult, KnowledgeGraph};
+use crate::error::{GraphError, SerdeError};
+
+/// 宏中的一步，记录命令及录制时其新添加的节点 ID
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MacroStep {
+    pub command: Command,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub created: Vec<u64>,
+}
+
+/// 录制的编辑宏
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Macro {
+    pub steps: Vec<MacroStep>,
+}
+
+impl Macro {
+    /// 记录一条已成功执行的命令及其结果
+    pub fn record(&mut self, command: Command, result: &CommandResult) {
+        self.steps.push(MacroStep {
+            command,
+            created: result.created_ids(),
+        });
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.steps.is_empty()
+    }
+
+    pub fn to_json(&self) -> Result<String, SerdeError> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+
+    pub fn from_json(json: &str) -> Result<Self, SerdeError> {
+        Ok(serde_json::from_str(json)?)
+    }
+}
+
+/// 宏录制器。撤销和恢复时同步移除和恢复最近录制的命令，使宏与实际编辑结果一致。
+#[derive(Debug, Default)]
+pub struct Recorder {
+    recorded: Macro,
+    undone: Vec<Option<MacroStep>>, // 被撤销的命令，恢复时重新加入。None 表示录制开始前的操作
+}
+
+impl Recorder {
+    /// 记录一条已成功执行的命令及其结果
+    pub fn record(&mut self, command: Command, result: &CommandResult) {
+        self.recorded.record(command, result);
+        self.undone.clear();
+    }
+
+    /// 撤销后调用，移除最近录制的命令。
+    /// 撤销的是录制开始前的操作时不做任何修改。
+    pub fn undo(&mut self) {
+        self.undone.push(self.recorded.steps.pop());
+    }
+
+    /// 恢复后调用，重新加入最近撤销的命令
+    pub fn redo(&mut self) {
+        if let Some(Some(step)) = self.undone.pop() {
+            self.recorded.steps.push(step);
+        }
+    }
+
+    /// 结束录制，返回录制的宏
+    #[inline]
+    pub fn finish(self) -> Macro {
+        self.recorded
+    }
+}
+
+impl KnowledgeGraph {
+    /// 在当前图谱上回放宏，整体作为一次可撤回的操作。
+    /// 宏中新添加的节点会分配新的 ID，后续命令对它们的引用会相应替换；
+    /// 其余 ID 视为当前图谱中已有的节点。任意一步失败时不做任何修改。
+    pub fn play_macro(&mut self, recorded: &Macro) -> Result<Vec<CommandResult>, GraphError> {
+        self.transaction(|graph| {
+            let mut id_map = HashMap::new();
+            recorded
+                .steps
+                .iter()
+                .map(|step| {
+                    let mut command = step.command.clone();
+                    command.remap_ids(&id_map);
+                    let result = graph.apply(command)?;
+                    id_map.extend(step.created.iter().copied().zip(result.created_ids()));
+                    Ok(result)
+                })
+                .collect()
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::graph::{DistinctEntityType, Relation};
+
+    use super::*;
+
+    fn record(graph: &mut KnowledgeGraph, recorded: &mut Macro, command: Command) -> u64 {
+        let result = graph.apply(command.clone()).unwrap();
+        recorded.record(command, &result);
+        result.created_ids().first().copied().unwrap_or_default()
+    }
+
+    #[test]
+    fn test_record_and_play() -> Result<(), Box<dyn std::error::Error>> {
+        let mut graph = KnowledgeGraph::default();
+        let existing = graph.add_entity(
+            "Existing".to_string(),
+            DistinctEntityType::KnowledgeUnit,
+            &[],
+            (0.0, 0.0),
+        )?;
+
+        // 录制：添加一个节点并与已有节点相连
+        let mut recorded = Macro::default();
+        let added = record(
+            &mut graph,
+            &mut recorded,
+            Command::AddEntity {
+                content: "Added".to_string(),
+                distinct_type: DistinctEntityType::KnowledgePoint,
+                addon_types: vec![],
+                coor: (0.0, 100.0),
+            },
+        );
+        record(
+            &mut graph,
+            &mut recorded,
+            Command::AddEdge {
+                from: existing,
+                to: added,
+                relation: Relation::Contain,
+            },
+        );
+
+        // 宏可以保存为 JSON 并读回
+        let recorded = Macro::from_json(&recorded.to_json()?)?;
+        assert_eq!(recorded.steps.len(), 2);
+
+        // 回放时新节点使用新的 ID，边引用也随之替换
+        let results = graph.play_macro(&recorded)?;
+        let new_id = results[0].created_ids()[0];
+        assert_ne!(new_id, added);
+        assert_eq!(graph.current.nodes.len(), 3);
+        assert!(graph.current.edges.contains_key(&(existing, new_id)));
+
+        // 回放整体作为一次撤回
+        graph.undo()?;
+        assert_eq!(graph.current.nodes.len(), 2);
+
+        // 引用的节点不存在时不做任何修改
+        graph.remove_entity(existing)?;
+        let before = graph.current.clone();
+        assert_eq!(
+            graph.play_macro(&recorded),
+            Err(GraphError::EntityNotFound(existing))
+        );
+        assert_eq!(graph.current, before);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_recorder_undo_redo() {
+        let mut recorder = Recorder::default();
+        recorder.record(Command::RemoveEntity(1), &CommandResult::None);
+        recorder.record(Command::RemoveEntity(2), &CommandResult::None);
+
+        recorder.undo();
+        recorder.undo();
+        recorder.undo(); // 撤销录制开始前的操作
+        recorder.redo(); // 恢复录制开始前的操作
+        recorder.redo();
+        assert_eq!(
+            recorder.finish().steps,
+            vec![MacroStep {
+                command: Command::RemoveEntity(1),
+                created: vec![],
+            }]
+        );
+    }
+}
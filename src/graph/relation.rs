@@ -0,0 +1,385 @@
+//! 关系类型表模块。不同学科和项目需要的关系类型不同，除内置的包含、次序、扩展关系外，
+//! 可从 JSON 配置加载自定义的关系类型，每种类型有编号、名称、颜色、绘制样式和对接系统中的 class_name。
+//! 关系类型表在进程内共享，编解码和绘制都按表查找自定义关系的名称和样式；
+//! 内置关系的定义固定不变，查找时不访问关系类型表。
+//!
+//! 文件中的边按 class_name 保存，不含编号；`Relation::Custom` 的编号只在读取时按当前的表解析，
+//! 同一进程内所有图谱共用这一张表。因此同时打开的图谱必须使用同一份关系类型表，
+//! 自定义关系互相冲突（同一 class_name 需要不同的定义）的文件不能同时打开；
+//! 替换关系类型表后，已读入的图谱中的自定义编号按新表解释，应重新读取文件。
+
+use std::{
+    borrow::Cow,
+    collections::HashSet,
+    sync::{LazyLock, PoisonError, RwLock, RwLockReadGuard},
+};
+
+use serde::{Deserialize, Serialize};
+
+use super::Relation;
+use crate::error::SerdeError;
+
+/// 进程内共享的关系类型表
+static REGISTRY: LazyLock<RwLock<RelationRegistry>> = LazyLock::new(Default::default);
+
+/// 当前的关系类型表
+pub fn relation_registry() -> RwLockReadGuard<'static, RelationRegistry> {
+    REGISTRY.read().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// 替换关系类型表并返回原来的表，之后的编解码和绘制都使用新表。
+/// 表是进程级的，替换会影响所有已打开的图谱，见模块说明
+pub fn set_relation_registry(registry: RelationRegistry) -> RelationRegistry {
+    std::mem::replace(
+        &mut *REGISTRY.write().unwrap_or_else(PoisonError::into_inner),
+        registry,
+    )
+}
+
+/// 测试中临时替换关系类型表，离开作用域时恢复原来的表。
+/// 同时只有一个测试可以替换，避免并行的测试互相影响
+#[cfg(test)]
+pub(crate) struct ScopedRegistry {
+    previous: Option<RelationRegistry>,
+    _lock: std::sync::MutexGuard<'static, ()>,
+}
+
+#[cfg(test)]
+impl ScopedRegistry {
+    pub fn new(registry: RelationRegistry) -> Self {
+        static LOCK: std::sync::Mutex<()> = std::sync::Mutex::new(());
+        let lock = LOCK.lock().unwrap_or_else(PoisonError::into_inner);
+        Self {
+            previous: Some(set_relation_registry(registry)),
+            _lock: lock,
+        }
+    }
+}
+
+#[cfg(test)]
+impl Drop for ScopedRegistry {
+    fn drop(&mut self) {
+        if let Some(previous) = self.previous.take() {
+            set_relation_registry(previous);
+        }
+    }
+}
+
+/// 关系符号的绘制样式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MarkerStyle {
+    #[default]
+    Arrow, // 终点处的箭头
+    Semicircle, // 中点处的半圆
+    Circle,     // 中点处的空心圆
+    Plain,      // 不绘制符号
+}
+
+/// 一种关系类型。编号 0、1、2 为内置的包含、次序、扩展关系，其余为自定义关系。
+/// 自定义关系的编号从 CUSTOM_RELATION_START 开始
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RelationType {
+    pub id: u16,
+    pub name: String,       // 名称，也是导出时没有自定义标签的边的 name
+    pub class_name: String, // 对接系统中的 class_name
+    #[serde(default)]
+    pub color: Option<[u8; 3]>, // 边的颜色，为空时使用默认颜色
+    #[serde(default)]
+    pub marker: MarkerStyle,
+}
+
+/// 自定义关系的最小编号，更小的编号为内置关系
+pub const CUSTOM_RELATION_START: u16 = 3;
+
+/// 内置关系的名称、class_name 和绘制样式，自定义关系返回 None
+const fn builtin(relation: Relation) -> Option<(&'static str, &'static str, MarkerStyle)> {
+    match relation {
+        Relation::Contain => Some(("包含", "包含关系", MarkerStyle::Semicircle)),
+        Relation::Order => Some(("次序", "次序关系", MarkerStyle::Arrow)),
+        Relation::Extension => Some(("扩展", "扩展关系", MarkerStyle::Circle)),
+        Relation::Custom(_) => None,
+    }
+}
+
+impl RelationType {
+    /// 内置关系的定义，自定义关系返回 None
+    fn builtin(relation: Relation) -> Option<Self> {
+        let (name, class_name, marker) = builtin(relation)?;
+        Some(Self {
+            id: relation.id(),
+            name: name.to_string(),
+            class_name: class_name.to_string(),
+            color: None,
+            marker,
+        })
+    }
+
+    #[inline]
+    pub fn relation(&self) -> Relation {
+        Relation::from_id(self.id)
+    }
+}
+
+/// 关系类型表，内置关系总在表中且排在前面
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RelationRegistry {
+    types: Vec<RelationType>,
+}
+
+impl Default for RelationRegistry {
+    fn default() -> Self {
+        Self {
+            types: Relation::ALL
+                .into_iter()
+                .filter_map(RelationType::builtin)
+                .collect(),
+        }
+    }
+}
+
+impl RelationRegistry {
+    /// 从 JSON 数组读取关系类型表，每项为一种自定义关系，内置关系总在表中且不可修改。
+    /// 编号小于 CUSTOM_RELATION_START 或重复、名称或 class_name 为空或与其它关系重复时返回错误
+    ///
+    /// ```
+    /// use py_better_kt_sqep::graph::{MarkerStyle, Relation, RelationRegistry};
+    ///
+    /// let registry = RelationRegistry::from_json(
+    ///     r#"[{ "id": 3, "name": "依赖", "class_name": "依赖关系", "color": [200, 60, 60] }]"#,
+    /// )
+    /// .unwrap();
+    /// assert_eq!(registry.relations().len(), 4);
+    /// assert_eq!(registry.find_class_name("依赖关系"), Some(Relation::Custom(3)));
+    /// assert_eq!(registry.get(Relation::Custom(3)).unwrap().marker, MarkerStyle::Arrow);
+    /// ```
+    pub fn from_json(json: &str) -> Result<Self, SerdeError> {
+        let mut registry = Self::default();
+        let mut ids = HashSet::new();
+        for mut relation_type in serde_json::from_str::<Vec<RelationType>>(json)? {
+            relation_type.name = relation_type.name.trim().to_string();
+            relation_type.class_name = relation_type.class_name.trim().to_string();
+            if relation_type.id < CUSTOM_RELATION_START {
+                return Err(SerdeError::Unexpected(
+                    "关系类型",
+                    format!(
+                        "编号 {} 为内置关系，自定义关系的编号从 {CUSTOM_RELATION_START} 开始",
+                        relation_type.id
+                    ),
+                ));
+            }
+            if relation_type.name.is_empty() || relation_type.class_name.is_empty() {
+                return Err(SerdeError::Unexpected(
+                    "关系类型",
+                    format!("编号 {} 的名称或 class_name 为空", relation_type.id),
+                ));
+            }
+            if !ids.insert(relation_type.id) {
+                return Err(SerdeError::Unexpected(
+                    "关系类型",
+                    format!("编号 {} 重复", relation_type.id),
+                ));
+            }
+            registry.types.push(relation_type);
+        }
+
+        let mut names = HashSet::new();
+        let mut class_names = HashSet::new();
+        for relation_type in registry.types.iter() {
+            if !names.insert(relation_type.name.as_str()) {
+                return Err(SerdeError::Unexpected("关系名", relation_type.name.clone()));
+            }
+            if !class_names.insert(relation_type.class_name.as_str()) {
+                return Err(SerdeError::Unexpected(
+                    "关系 class_name",
+                    relation_type.class_name.clone(),
+                ));
+            }
+        }
+        Ok(registry)
+    }
+
+    /// 所有关系，按界面和图例中的顺序排列
+    pub fn relations(&self) -> Vec<Relation> {
+        self.types.iter().map(RelationType::relation).collect()
+    }
+
+    /// 自定义关系的种数
+    pub fn custom_len(&self) -> usize {
+        self.types
+            .iter()
+            .filter(|t| matches!(t.relation(), Relation::Custom(_)))
+            .count()
+    }
+
+    /// 关系的定义，未配置的自定义关系返回 None
+    pub fn get(&self, relation: Relation) -> Option<&RelationType> {
+        self.types.iter().find(|t| t.id == relation.id())
+    }
+
+    /// class_name 对应的关系
+    pub fn find_class_name(&self, class_name: &str) -> Option<Relation> {
+        self.types
+            .iter()
+            .find(|t| t.class_name == class_name)
+            .map(RelationType::relation)
+    }
+
+    /// name 是否为某种关系的名称
+    pub fn is_name(&self, name: &str) -> bool {
+        self.types.iter().any(|t| t.name == name)
+    }
+}
+
+impl Relation {
+    /// 关系类型表中的编号
+    pub fn id(&self) -> u16 {
+        match *self {
+            Relation::Contain => 0,
+            Relation::Order => 1,
+            Relation::Extension => 2,
+            Relation::Custom(id) => id,
+        }
+    }
+
+    /// 编号对应的关系，0、1、2 为内置关系
+    pub fn from_id(id: u16) -> Self {
+        Relation::ALL
+            .into_iter()
+            .find(|relation| relation.id() == id)
+            .unwrap_or(Relation::Custom(id))
+    }
+
+    /// 关系名，如“包含”。未配置的自定义关系以编号命名，如“关系3”
+    pub fn name(&self) -> Cow<'static, str> {
+        match builtin(*self) {
+            Some((name, _, _)) => Cow::Borrowed(name),
+            None => Cow::Owned(
+                relation_registry()
+                    .get(*self)
+                    .map_or_else(|| format!("关系{}", self.id()), |t| t.name.clone()),
+            ),
+        }
+    }
+
+    /// 对接系统中的 class_name，如“包含关系”。未配置的自定义关系同关系名
+    pub fn class_name(&self) -> Cow<'static, str> {
+        match builtin(*self) {
+            Some((_, class_name, _)) => Cow::Borrowed(class_name),
+            None => Cow::Owned(
+                relation_registry()
+                    .get(*self)
+                    .map_or_else(|| format!("关系{}", self.id()), |t| t.class_name.clone()),
+            ),
+        }
+    }
+
+    /// 配置的边的颜色，内置关系和未配置颜色的自定义关系为 None
+    pub fn color(&self) -> Option<[u8; 3]> {
+        match builtin(*self) {
+            Some(_) => None,
+            None => relation_registry().get(*self).and_then(|t| t.color),
+        }
+    }
+
+    /// 关系符号的绘制样式，未配置的自定义关系为箭头
+    pub fn marker(&self) -> MarkerStyle {
+        match builtin(*self) {
+            Some((_, _, marker)) => marker,
+            None => relation_registry()
+                .get(*self)
+                .map_or(MarkerStyle::Arrow, |t| t.marker),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_relation_registry() {
+        let registry = RelationRegistry::default();
+        assert_eq!(registry.relations(), Relation::ALL.to_vec());
+        assert_eq!(registry.custom_len(), 0);
+        assert_eq!(
+            registry.find_class_name("包含关系"),
+            Some(Relation::Contain)
+        );
+        assert_eq!(Relation::from_id(1), Relation::Order);
+        assert_eq!(Relation::from_id(7), Relation::Custom(7));
+
+        let registry = RelationRegistry::from_json(
+            r#"[
+                { "id": 3, "name": "依赖", "class_name": "依赖关系", "marker": "plain" },
+                { "id": 5, "name": "先修", "class_name": "先修关系", "color": [0, 0, 255] }
+            ]"#,
+        )
+        .unwrap();
+        assert_eq!(
+            registry.relations(),
+            vec![
+                Relation::Contain,
+                Relation::Order,
+                Relation::Extension,
+                Relation::Custom(3),
+                Relation::Custom(5)
+            ]
+        );
+        assert_eq!(registry.custom_len(), 2);
+        assert_eq!(
+            registry.find_class_name("先修关系"),
+            Some(Relation::Custom(5))
+        );
+        assert_eq!(registry.find_class_name("次序关系"), Some(Relation::Order));
+        assert_eq!(
+            registry.find_class_name("依赖关系"),
+            Some(Relation::Custom(3))
+        );
+        assert_eq!(registry.find_class_name("类比关系"), None);
+        assert!(registry.is_name("依赖") && registry.is_name("次序"));
+        assert!(!registry.is_name("类比"));
+        let prerequisite = registry.get(Relation::Custom(5)).unwrap();
+        assert_eq!(prerequisite.color, Some([0, 0, 255]));
+        assert_eq!(prerequisite.marker, MarkerStyle::Arrow);
+
+        // 名称为空、编号为内置关系或重复、名称与内置关系重复时无法使用
+        for json in [
+            r#"[{ "id": 1, "name": "先修", "class_name": "先修关系" }]"#,
+            r#"[{ "id": 0, "name": "包含", "class_name": "包含关系" }]"#,
+            r#"[{ "id": 3, "name": " ", "class_name": "依赖关系" }]"#,
+            r#"[{ "id": 3, "name": "依赖", "class_name": "依赖关系" },
+                { "id": 3, "name": "类比", "class_name": "类比关系" }]"#,
+            r#"[{ "id": 3, "name": "包含", "class_name": "依赖关系" }]"#,
+            r#"{ "id": 3 }"#,
+        ] {
+            assert!(RelationRegistry::from_json(json).is_err());
+        }
+    }
+
+    #[test]
+    fn test_relation_names() {
+        assert_eq!(Relation::Contain.name(), "包含");
+        assert!(matches!(
+            Relation::Order.class_name(),
+            Cow::Borrowed("次序关系")
+        ));
+        assert_eq!(Relation::Extension.marker(), MarkerStyle::Circle);
+
+        // 替换的关系类型表只影响自定义关系，离开作用域后恢复
+        {
+            let _registry = ScopedRegistry::new(
+                RelationRegistry::from_json(
+                    r#"[{ "id": 4, "name": "对比", "class_name": "对比关系", "color": [1, 2, 3] }]"#,
+                )
+                .unwrap(),
+            );
+            assert_eq!(Relation::Custom(4).name(), "对比");
+            assert_eq!(Relation::Custom(4).class_name(), "对比关系");
+            assert_eq!(Relation::Custom(4).color(), Some([1, 2, 3]));
+            assert_eq!(Relation::Contain.name(), "包含");
+        }
+        assert_eq!(Relation::Custom(4).name(), "关系4");
+        assert_eq!(Relation::Custom(4).color(), None);
+    }
+}
@@ -0,0 +1,150 @@
+//! 删除节点的策略模块。删除中间节点时可以选择如何处理其通过包含关系连接的子节点。
+
+use std::collections::BTreeSet;
+
+use serde::{Deserialize, Serialize};
+
+use super::{Command, KnowledgeGraph, Relation, Snapshot};
+use crate::error::GraphError;
+
+/// 删除节点时处理子节点的策略
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum RemovePolicy {
+    /// 只删除节点及其边，子节点与树断开
+    #[default]
+    Detach,
+    /// 子节点改为由被删除节点的每个父节点包含
+    ReattachToParents,
+    /// 删除节点通过包含关系直接或间接包含的所有节点
+    Cascade,
+}
+
+impl Snapshot {
+    /// 通过包含关系直接包含指定节点的父节点，按 ID 排序
+    pub fn parents(&self, child: u64) -> Vec<u64> {
+        let mut parents = self
+            .edges
+            .iter()
+            .filter(|((_, to), relation)| *to == child && **relation == Relation::Contain)
+            .map(|((from, _), _)| *from)
+            .collect::<Vec<_>>();
+        parents.sort_unstable();
+        parents
+    }
+
+    /// 按策略删除节点所需的命令。重新连接时，父节点与子节点之间已有任意关系的边、
+    /// 或父节点就是子节点时跳过；节点不存在时返回错误
+    pub fn removal_commands(
+        &self,
+        id: u64,
+        policy: RemovePolicy,
+    ) -> Result<Vec<Command>, GraphError> {
+        if !self.nodes.contains_key(&id) {
+            return Err(GraphError::EntityNotFound(id));
+        }
+
+        let mut commands = Vec::new();
+        match policy {
+            RemovePolicy::Detach => {}
+            RemovePolicy::ReattachToParents => {
+                let children = self.children(id);
+                for parent in self.parents(id).into_iter().filter(|parent| *parent != id) {
+                    for child in children.iter().filter(|child| **child != parent) {
+                        if !self.edges.contains_key(&(parent, *child)) {
+                            commands.push(Command::AddEdge {
+                                from: parent,
+                                to: *child,
+                                relation: Relation::Contain,
+                            });
+                        }
+                    }
+                }
+            }
+            RemovePolicy::Cascade => {
+                let descendants = self
+                    .descendants(id)
+                    .into_iter()
+                    .map(|(descendant, _)| descendant)
+                    .collect::<BTreeSet<_>>();
+                commands.extend(descendants.into_iter().map(Command::RemoveEntity));
+            }
+        }
+        commands.push(Command::RemoveEntity(id));
+        Ok(commands)
+    }
+}
+
+impl KnowledgeGraph {
+    /// 按策略删除节点，整体作为一次可撤回的操作
+    pub fn remove_entity_with_policy(
+        &mut self,
+        id: u64,
+        policy: RemovePolicy,
+    ) -> Result<(), GraphError> {
+        let commands = self.current.removal_commands(id, policy)?;
+        self.apply_batch(commands).map(|_| ())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::DistinctEntityType;
+
+    /// 1 包含 2，2 包含 3 和 4，4 包含 5；6 也包含 2，且与 3 之间已有次序关系
+    fn create_graph() -> KnowledgeGraph {
+        let mut graph = KnowledgeGraph::default();
+        for i in 1..=6 {
+            graph
+                .add_entity(
+                    format!("Node {i}"),
+                    DistinctEntityType::KnowledgePoint,
+                    &[],
+                    (i as f64 * 200.0, 0.0),
+                )
+                .unwrap();
+        }
+        for (from, to) in [(1, 2), (2, 3), (2, 4), (4, 5), (6, 2)] {
+            graph.add_edge(from, to, Relation::Contain).unwrap();
+        }
+        graph.add_edge(6, 3, Relation::Order).unwrap();
+        graph
+    }
+
+    #[test]
+    fn test_remove_entity_with_policy() {
+        let mut graph = create_graph();
+        let undo_len = graph.undo_len();
+        graph
+            .remove_entity_with_policy(2, RemovePolicy::Detach)
+            .unwrap();
+        assert!(graph.current.parents(3).is_empty());
+        assert_eq!(graph.undo_len(), undo_len + 1);
+        graph.undo().unwrap();
+
+        // 已有的次序关系保持不变
+        graph
+            .remove_entity_with_policy(2, RemovePolicy::ReattachToParents)
+            .unwrap();
+        assert_eq!(graph.current.parents(3), vec![1]);
+        assert_eq!(graph.current.parents(4), vec![1, 6]);
+        assert_eq!(graph.current.edges[&(6, 3)], Relation::Order);
+        assert_eq!(graph.undo_len(), undo_len + 1);
+        graph.undo().unwrap();
+
+        graph
+            .remove_entity_with_policy(2, RemovePolicy::Cascade)
+            .unwrap();
+        assert_eq!(
+            graph.current.nodes.keys().copied().collect::<BTreeSet<_>>(),
+            BTreeSet::from([1, 6])
+        );
+        graph.undo().unwrap();
+        assert_eq!(graph.current.nodes.len(), 6);
+
+        assert_eq!(
+            graph.remove_entity_with_policy(9, RemovePolicy::Cascade),
+            Err(GraphError::EntityNotFound(9))
+        );
+    }
+}
@@ -0,0 +1,151 @@
+//! 自动修复模块，修复导入的脏数据中常见的一致性问题。
+//!
+//! 重复的节点 ID 和空的 level、classification 在读取文件时已经处理：
+//! 快照以 ID 为键保存节点，level 和 classification 由节点类型推导，因此这里不再处理。
+
+use super::{KnowledgeGraph, allocation::next_id_after};
+
+/// 自动修复的报告，记录每项修复的数量
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RepairReport {
+    pub dangling_edges: usize,   // 删除的引用了不存在节点的边
+    pub self_loops: usize,       // 删除的起点和终点相同的边
+    pub dangling_labels: usize,  // 删除的没有对应边的边标签和折点
+    pub dangling_members: usize, // 从分组框中移除的不存在的成员
+    pub mismatched_ids: usize,   // 修正的与键不一致的节点 ID
+}
+
+impl RepairReport {
+    /// 各项修复的说明和数量，供界面逐行显示
+    pub fn items(&self) -> [(&'static str, usize); 5] {
+        [
+            ("引用了不存在节点的边", self.dangling_edges),
+            ("自环边", self.self_loops),
+            ("没有对应边的边标签和折点", self.dangling_labels),
+            ("分组框中不存在的成员", self.dangling_members),
+            ("与键不一致的节点 ID", self.mismatched_ids),
+        ]
+    }
+
+    /// 是否没有进行任何修复
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.items().iter().all(|(_, count)| *count == 0)
+    }
+}
+
+impl KnowledgeGraph {
+    /// 执行所有修复，整体作为一次可撤回的操作；没有需要修复的问题时不会产生撤回记录。
+    /// 下一个可用的 ID 不大于现有的最大 ID 时也会一并修正，但不计入报告。
+    pub fn auto_repair(&mut self) -> RepairReport {
+        let mut repaired = self.current.clone();
+        let mut report = RepairReport::default();
+
+        for (id, node) in repaired.nodes.iter_mut() {
+            if node.id != *id {
+                node.id = *id;
+                report.mismatched_ids += 1;
+            }
+        }
+
+        let nodes = &repaired.nodes;
+        let edge_count = repaired.edges.len();
+        repaired.edges.retain(|(from, to), _| {
+            if !nodes.contains_key(from) || !nodes.contains_key(to) {
+                report.dangling_edges += 1;
+                false
+            } else {
+                true
+            }
+        });
+        repaired.edges.retain(|(from, to), _| from != to);
+        report.self_loops = edge_count - report.dangling_edges - repaired.edges.len();
+
+        let edges = &repaired.edges;
+        let label_count = repaired.edge_labels.len() + repaired.edge_waypoints.len();
+        repaired
+            .edge_labels
+            .retain(|key, _| edges.contains_key(key));
+        repaired
+            .edge_waypoints
+            .retain(|key, _| edges.contains_key(key));
+        report.dangling_labels =
+            label_count - repaired.edge_labels.len() - repaired.edge_waypoints.len();
+
+        for group in repaired.groups.iter_mut() {
+            let member_count = group.members.len();
+            group.members.retain(|id| nodes.contains_key(id));
+            report.dangling_members += member_count - group.members.len();
+        }
+
+        if let Ok(next_id) = next_id_after(&repaired.nodes) {
+            repaired.latest_id = repaired.latest_id.max(next_id);
+        }
+
+        self.replace_current(repaired);
+        report
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::{CoordinateBounds, DistinctEntityType, Relation};
+
+    #[test]
+    fn test_auto_repair() {
+        let mut graph = KnowledgeGraph::default();
+        for i in 0..3 {
+            graph
+                .add_entity(
+                    format!("Node {i}"),
+                    DistinctEntityType::KnowledgePoint,
+                    &[],
+                    (i as f64 * 200.0, 0.0),
+                )
+                .unwrap();
+        }
+        graph.add_edge(1, 2, Relation::Contain).unwrap();
+        graph.add_edge(2, 2, Relation::Order).unwrap();
+        graph.set_edge_label(1, 2, "标签".to_string()).unwrap();
+        let bounds = CoordinateBounds {
+            min: (0.0, 0.0),
+            max: (100.0, 100.0),
+        };
+        graph
+            .add_group("分组".to_string(), bounds, [0, 0, 0], &[1, 3])
+            .unwrap();
+
+        // 一致的图谱只有自环需要修复
+        assert_eq!(
+            graph.auto_repair(),
+            RepairReport {
+                self_loops: 1,
+                ..Default::default()
+            }
+        );
+        let undo_len = graph.undo_len();
+        assert!(graph.auto_repair().is_empty());
+        assert_eq!(graph.undo_len(), undo_len);
+
+        // 模拟导入的脏数据
+        let current = &mut graph.current;
+        current.nodes.remove(&3);
+        current.nodes.get_mut(&2).unwrap().id = 20;
+        current.edges.insert((1, 9), Relation::Order);
+        current.edge_labels.insert((2, 1), "标签".to_string());
+
+        let report = graph.auto_repair();
+        assert_eq!(report.dangling_edges, 1);
+        assert_eq!(report.dangling_labels, 1);
+        assert_eq!(report.dangling_members, 1);
+        assert_eq!(report.mismatched_ids, 1);
+        assert_eq!(graph.current.nodes[&2].id, 2);
+        assert_eq!(graph.current.edges.len(), 1);
+        assert_eq!(graph.current.groups[0].members, [1]);
+
+        // 整体作为一次撤回
+        graph.undo().unwrap();
+        assert!(graph.current.edges.contains_key(&(1, 9)));
+    }
+}
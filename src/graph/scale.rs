@@ -0,0 +1,137 @@
+//! 坐标缩放模块。对接系统的坐标单位可能与本软件的像素不同，导入后布局过于松散或拥挤，
+//! 可将所有坐标乘以一个系数，系数可按节点之间的间距自动估算。
+
+use super::{Command, CoordinateBounds, KnowledgeGraph, Snapshot, bounds::median};
+use crate::error::GraphError;
+
+impl Snapshot {
+    /// 使节点间距接近 spacing 的缩放系数：取每个节点与最近的其他节点中心距离的中位数，
+    /// 以 spacing 除以该值。重合的节点不计，不足两个不同位置的节点时返回 None
+    pub fn estimate_coordinate_scale(&self, spacing: f64) -> Option<f64> {
+        let coors = self
+            .nodes
+            .values()
+            .map(|node| node.coor)
+            .filter(|(x, y)| x.is_finite() && y.is_finite())
+            .collect::<Vec<_>>();
+        let nearest = coors
+            .iter()
+            .filter_map(|a| {
+                coors
+                    .iter()
+                    .map(|b| (a.0 - b.0).hypot(a.1 - b.1))
+                    .filter(|distance| *distance > 0.0)
+                    .min_by(f64::total_cmp)
+            })
+            .collect::<Vec<_>>();
+        if nearest.is_empty() {
+            return None;
+        }
+        Some(spacing / median(nearest))
+    }
+
+    /// 将所有节点坐标、边的折点和分组框范围乘以 factor 所需的命令，即以原点为中心缩放，坐标不变的不生成命令。
+    /// factor 不是正的有限数时返回空表
+    pub fn scale_coordinates_commands(&self, factor: f64) -> Vec<Command> {
+        if !factor.is_finite() || factor <= 0.0 || factor == 1.0 {
+            return Vec::new();
+        }
+        let scale = |(x, y): (f64, f64)| (x * factor, y * factor);
+        let mut nodes = self.nodes.values().collect::<Vec<_>>();
+        nodes.sort_unstable_by_key(|node| node.id);
+        let moves = nodes
+            .into_iter()
+            .filter(|node| scale(node.coor) != node.coor)
+            .map(|node| Command::UpdateEntityPosition {
+                id: node.id,
+                coor: scale(node.coor),
+            });
+        let groups = self.groups.iter().map(|group| {
+            let mut group = group.clone();
+            group.rect = CoordinateBounds {
+                min: scale(group.rect.min),
+                max: scale(group.rect.max),
+            };
+            Command::UpdateGroup(group)
+        });
+        let mut edges = self.edge_waypoints.iter().collect::<Vec<_>>();
+        edges.sort_unstable_by_key(|(edge, _)| **edge);
+        let waypoints = edges
+            .into_iter()
+            .map(|(&(from, to), points)| Command::SetEdgeWaypoints {
+                from,
+                to,
+                waypoints: points.iter().copied().map(scale).collect(),
+            });
+        moves.chain(waypoints).chain(groups).collect()
+    }
+}
+
+impl KnowledgeGraph {
+    /// 将所有坐标乘以 factor，整体作为一次可撤回的操作，返回移动的节点数
+    pub fn scale_coordinates(&mut self, factor: f64) -> Result<usize, GraphError> {
+        let commands = self.current.scale_coordinates_commands(factor);
+        let count = commands
+            .iter()
+            .filter(|command| matches!(command, Command::UpdateEntityPosition { .. }))
+            .count();
+        if !commands.is_empty() {
+            self.apply_batch(commands)?;
+        }
+        Ok(count)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::{DistinctEntityType, Relation};
+
+    #[test]
+    fn test_scale_coordinates() {
+        let mut graph = KnowledgeGraph::default();
+        for coor in [(0.0, 0.0), (2000.0, 0.0), (0.0, 2000.0), (0.0, 2000.0)] {
+            graph
+                .add_entity(String::new(), DistinctEntityType::KnowledgePoint, &[], coor)
+                .unwrap();
+        }
+
+        graph.add_edge(1, 2, Relation::Order).unwrap();
+        graph
+            .set_edge_waypoints(1, 2, vec![(1000.0, 1000.0)])
+            .unwrap();
+
+        // 重合的节点不计入间距，最近距离均为 2000
+        let factor = graph.current.estimate_coordinate_scale(200.0).unwrap();
+        assert!((factor - 0.1).abs() < 1e-9);
+
+        let undo_len = graph.undo_len();
+        assert_eq!(graph.scale_coordinates(factor), Ok(3));
+        assert_eq!(graph.undo_len(), undo_len + 1);
+        assert!((graph.current.nodes[&2].coor.0 - 200.0).abs() < 1e-9);
+        assert!((graph.current.nodes[&3].coor.1 - 200.0).abs() < 1e-9);
+        assert_eq!(graph.current.nodes[&1].coor, (0.0, 0.0));
+        let (x, y) = graph.current.waypoints(1, 2)[0];
+        assert!((x - 100.0).abs() < 1e-9 && (y - 100.0).abs() < 1e-9);
+
+        // 无效的系数不做修改
+        assert_eq!(graph.scale_coordinates(1.0), Ok(0));
+        assert_eq!(graph.scale_coordinates(0.0), Ok(0));
+        assert_eq!(graph.scale_coordinates(f64::NAN), Ok(0));
+        assert_eq!(graph.undo_len(), undo_len + 1);
+
+        // 所有节点重合时无法估算
+        let mut graph = KnowledgeGraph::default();
+        for _ in 0..2 {
+            graph
+                .add_entity(
+                    String::new(),
+                    DistinctEntityType::KnowledgePoint,
+                    &[],
+                    (5.0, 5.0),
+                )
+                .unwrap();
+        }
+        assert_eq!(graph.current.estimate_coordinate_scale(200.0), None);
+    }
+}
@@ -0,0 +1,233 @@
+//! 按知识领域拆分模块。一个文件中有多个互相独立的知识领域时，可将每个领域拆为单独的图谱，
+//! 便于把总图拆回各课程的文件。对接平台要求按知识单元上传时，也可将每个知识单元拆为单独的图谱。
+
+use std::collections::{BTreeSet, HashSet};
+
+use super::{DistinctEntityType, Snapshot};
+
+/// 不属于任何知识领域的节点所在部分的名称
+pub const UNASSIGNED_PART: &str = "其他";
+
+/// 将领域名转换为可用作文件名的形式：文件名中不允许的字符和控制字符替换为下划线，
+/// 去掉首尾的空白和点，结果为空时返回 None
+///
+/// ```
+/// use py_better_kt_sqep::graph::file_stem;
+///
+/// assert_eq!(file_stem(" 数据结构 ").as_deref(), Some("数据结构"));
+/// assert_eq!(file_stem("C/C++ 程序设计").as_deref(), Some("C_C++ 程序设计"));
+/// assert_eq!(file_stem(" .. "), None);
+/// ```
+pub fn file_stem(name: &str) -> Option<String> {
+    let stem = name
+        .chars()
+        .map(|c| match c {
+            '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|' => '_',
+            c if c.is_control() => '_',
+            c => c,
+        })
+        .collect::<String>();
+    let stem = stem.trim_matches(|c: char| c.is_whitespace() || c == '.');
+    (!stem.is_empty()).then(|| stem.to_string())
+}
+
+impl Snapshot {
+    /// 按知识领域拆分图谱：以每个知识领域节点为根沿包含关系收集子树，按领域的 ID 排列，
+    /// 不属于任何领域的节点归入名为“其他”的部分（没有这样的节点时不输出）。
+    /// 每部分的名称由领域的内容经 file_stem 转换，为空或与先前的名称重复时附加领域的 ID；
+    /// 子树相交时共同的节点出现在每个领域中。子图只保留两端都在其中的边，不包含分组框
+    pub fn split_by_arena(&self) -> Vec<(String, Snapshot)> {
+        let mut arenas = self
+            .nodes
+            .values()
+            .filter(|node| node.distinct_type == DistinctEntityType::KnowledgeArena)
+            .collect::<Vec<_>>();
+        arenas.sort_unstable_by_key(|node| node.id);
+
+        let mut parts = Vec::new();
+        let mut names = HashSet::new();
+        let mut assigned = BTreeSet::new();
+        for arena in arenas {
+            let mut ids = BTreeSet::from([arena.id]);
+            ids.extend(self.descendants(arena.id).into_iter().map(|(id, _)| id));
+            assigned.extend(ids.iter().copied());
+
+            let name = match file_stem(&arena.content) {
+                Some(name) if name != UNASSIGNED_PART && !names.contains(&name) => name,
+                Some(name) => format!("{name}_{}", arena.id),
+                None => format!("领域_{}", arena.id),
+            };
+            names.insert(name.clone());
+            parts.push((name, self.subgraph(&ids)));
+        }
+
+        let rest = self
+            .nodes
+            .keys()
+            .filter(|id| !assigned.contains(id))
+            .copied()
+            .collect::<BTreeSet<_>>();
+        if !rest.is_empty() {
+            parts.push((UNASSIGNED_PART.to_string(), self.subgraph(&rest)));
+        }
+        parts
+    }
+
+    /// 按知识单元拆分图谱：每个知识单元及其沿包含关系的所有下级节点为一部分，按单元的 ID 排列。
+    /// with_arena 为 true 时同时包含其所属的知识领域节点作为上下文。
+    /// 每部分的名称由单元的内容经 file_stem 转换，与先前的名称重复时依次附加 _2、_3 等后缀；
+    /// 内容为空（转换后为空）的单元不输出，其 ID 列在 skipped 中
+    pub fn split_by_unit(&self, with_arena: bool) -> UnitSplit {
+        let mut units = self
+            .nodes
+            .values()
+            .filter(|node| node.distinct_type == DistinctEntityType::KnowledgeUnit)
+            .collect::<Vec<_>>();
+        units.sort_unstable_by_key(|node| node.id);
+
+        let mut split = UnitSplit::default();
+        let mut names = HashSet::new();
+        for unit in units {
+            let Some(stem) = file_stem(&unit.content) else {
+                split.skipped.push(unit.id);
+                continue;
+            };
+            let mut name = stem.clone();
+            let mut suffix = 2;
+            while !names.insert(name.clone()) {
+                name = format!("{stem}_{suffix}");
+                suffix += 1;
+            }
+
+            let mut ids = BTreeSet::from([unit.id]);
+            ids.extend(self.descendants(unit.id).into_iter().map(|(id, _)| id));
+            if with_arena {
+                ids.extend(
+                    self.ancestor_paths(unit.id)
+                        .into_iter()
+                        .flatten()
+                        .filter(|id| {
+                            self.nodes[id].distinct_type == DistinctEntityType::KnowledgeArena
+                        }),
+                );
+            }
+            split.parts.push((name, self.subgraph(&ids)));
+        }
+        split
+    }
+}
+
+/// 按知识单元拆分的结果
+#[derive(Debug, Default)]
+pub struct UnitSplit {
+    /// 各部分的名称及其子图
+    pub parts: Vec<(String, Snapshot)>,
+    /// 因内容为空而未输出的单元
+    pub skipped: Vec<u64>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::{KnowledgeGraph, Relation};
+
+    #[test]
+    fn test_split_by_arena() {
+        let mut graph = KnowledgeGraph::default();
+        let nodes = [
+            ("数据结构", DistinctEntityType::KnowledgeArena),
+            ("线性表", DistinctEntityType::KnowledgeUnit),
+            ("栈", DistinctEntityType::KnowledgePoint),
+            ("C/C++", DistinctEntityType::KnowledgeArena),
+            ("指针", DistinctEntityType::KnowledgePoint),
+            ("数据结构", DistinctEntityType::KnowledgeArena),
+            ("孤立", DistinctEntityType::KnowledgePoint),
+        ];
+        for (content, distinct_type) in nodes {
+            graph
+                .add_entity(content.to_string(), distinct_type, &[], (0.0, 0.0))
+                .unwrap();
+        }
+        graph.add_edge(1, 2, Relation::Contain).unwrap();
+        graph.add_edge(2, 3, Relation::Contain).unwrap();
+        graph.add_edge(4, 5, Relation::Contain).unwrap();
+        graph.add_edge(5, 3, Relation::Order).unwrap(); // 跨领域的边不保留
+        graph.add_edge(6, 3, Relation::Contain).unwrap(); // 共同的节点出现在两个领域中
+
+        let parts = graph.current.split_by_arena();
+        let summary = parts
+            .iter()
+            .map(|(name, part)| {
+                let ids = part.nodes.keys().copied().collect::<BTreeSet<_>>();
+                (name.as_str(), ids, part.edges.len())
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            summary,
+            vec![
+                ("数据结构", BTreeSet::from([1, 2, 3]), 2),
+                ("C_C++", BTreeSet::from([4, 5]), 1),
+                ("数据结构_6", BTreeSet::from([3, 6]), 1),
+                (UNASSIGNED_PART, BTreeSet::from([7]), 0),
+            ]
+        );
+
+        // 所有节点都属于领域时没有“其他”部分
+        graph.remove_entity(7).unwrap();
+        let parts = graph.current.split_by_arena();
+        assert!(parts.iter().all(|(name, _)| name != UNASSIGNED_PART));
+        assert!(
+            KnowledgeGraph::default()
+                .current
+                .split_by_arena()
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_split_by_unit() {
+        let mut graph = KnowledgeGraph::default();
+        let nodes = [
+            ("数据结构", DistinctEntityType::KnowledgeArena),
+            ("线性表", DistinctEntityType::KnowledgeUnit),
+            ("栈", DistinctEntityType::KnowledgePoint),
+            ("线性表", DistinctEntityType::KnowledgeUnit),
+            (" ", DistinctEntityType::KnowledgeUnit),
+            ("线性表", DistinctEntityType::KnowledgeUnit),
+        ];
+        for (content, distinct_type) in nodes {
+            graph
+                .add_entity(content.to_string(), distinct_type, &[], (0.0, 0.0))
+                .unwrap();
+        }
+        graph.add_edge(1, 2, Relation::Contain).unwrap();
+        graph.add_edge(2, 3, Relation::Contain).unwrap();
+        graph.add_edge(4, 3, Relation::Order).unwrap(); // 次序关系不计入下级节点
+
+        let summary = |split: &UnitSplit| {
+            split
+                .parts
+                .iter()
+                .map(|(name, part)| {
+                    let ids = part.nodes.keys().copied().collect::<BTreeSet<_>>();
+                    (name.clone(), ids)
+                })
+                .collect::<Vec<_>>()
+        };
+        let split = graph.current.split_by_unit(true);
+        assert_eq!(
+            summary(&split),
+            vec![
+                ("线性表".to_string(), BTreeSet::from([1, 2, 3])),
+                ("线性表_2".to_string(), BTreeSet::from([4])),
+                ("线性表_3".to_string(), BTreeSet::from([6])),
+            ]
+        );
+        assert_eq!(split.parts[0].1.edges.len(), 2);
+        assert_eq!(split.skipped, vec![5]);
+
+        // 不包含所属的领域
+        let split = graph.current.split_by_unit(false);
+        assert_eq!(summary(&split)[0].1, BTreeSet::from([2, 3]));
+    }
+}
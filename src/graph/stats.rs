@@ -0,0 +1,231 @@
+//! 图谱统计模块，提供整体或局部（子图）的计数信息。
+
+use std::collections::{BTreeSet, HashMap};
+
+use super::{AddonEntityType, DistinctEntityType, Flag, Relation, Snapshot};
+
+/// 图谱的统计信息
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Statistics {
+    pub node_count: usize,
+    pub edge_count: usize,
+    pub distinct_counts: HashMap<DistinctEntityType, usize>, // 各独立实体类型的节点数
+    pub addon_counts: HashMap<AddonEntityType, usize>,       // 带有各附加类型的节点数
+    pub flag_counts: HashMap<Flag, usize>,                   // 带有各评审标记的节点数
+    pub bounding_box: Option<((f64, f64), (f64, f64))>,      // 节点坐标的 (最小值, 最大值)
+}
+
+impl Statistics {
+    /// 节点坐标包围盒的宽和高，没有节点时返回 None
+    pub fn bounding_size(&self) -> Option<(f64, f64)> {
+        self.bounding_box
+            .map(|(min, max)| (max.0 - min.0, max.1 - min.1))
+    }
+}
+
+impl Snapshot {
+    /// 统计当前快照
+    pub fn statistics(&self) -> Statistics {
+        let mut stats = Statistics {
+            node_count: self.nodes.len(),
+            edge_count: self.edges.len(),
+            ..Default::default()
+        };
+
+        for node in self.nodes.values() {
+            *stats.distinct_counts.entry(node.distinct_type).or_default() += 1;
+            for addon in node.addon_types.iter() {
+                *stats.addon_counts.entry(*addon).or_default() += 1;
+            }
+            if let Some(flag) = node.flag {
+                *stats.flag_counts.entry(flag).or_default() += 1;
+            }
+
+            let (x, y) = node.coor;
+            stats.bounding_box = Some(match stats.bounding_box {
+                Some((min, max)) => ((min.0.min(x), min.1.min(y)), (max.0.max(x), max.1.max(y))),
+                None => ((x, y), (x, y)),
+            });
+        }
+
+        stats
+    }
+
+    /// 取出由指定节点构成的子图，只保留两端都在其中的边。
+    /// 不存在的节点 ID 会被忽略。
+    pub fn subgraph(&self, ids: &BTreeSet<u64>) -> Snapshot {
+        Snapshot {
+            title: self.title.clone(),
+            nodes: self
+                .nodes
+                .iter()
+                .filter(|(id, _)| ids.contains(id))
+                .map(|(id, node)| (*id, node.clone()))
+                .collect(),
+            edges: self
+                .edges
+                .iter()
+                .filter(|((from, to), _)| ids.contains(from) && ids.contains(to))
+                .map(|(key, relation)| (*key, *relation))
+                .collect(),
+            edge_labels: self
+                .edge_labels
+                .iter()
+                .filter(|((from, to), _)| ids.contains(from) && ids.contains(to))
+                .map(|(key, label)| (*key, label.clone()))
+                .collect(),
+            edge_waypoints: self
+                .edge_waypoints
+                .iter()
+                .filter(|((from, to), _)| ids.contains(from) && ids.contains(to))
+                .map(|(key, waypoints)| (*key, waypoints.clone()))
+                .collect(),
+            metadata: self.metadata.clone(),
+            groups: Vec::new(),
+            latest_id: self.latest_id,
+        }
+    }
+
+    /// 与指定节点直接相连的节点，不区分边的方向和关系，不包含节点自身
+    pub fn neighbors(&self, id: u64) -> BTreeSet<u64> {
+        self.edges
+            .keys()
+            .filter_map(|(from, to)| match (*from == id, *to == id) {
+                (true, false) => Some(*to),
+                (false, true) => Some(*from),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// 指定节点通过包含关系直接包含的子节点，按 (次序, ID) 排序，没有次序的节点排在最后
+    pub fn children(&self, parent: u64) -> Vec<u64> {
+        let mut children = self
+            .edges
+            .iter()
+            .filter(|((from, _), relation)| *from == parent && **relation == Relation::Contain)
+            .map(|((_, to), _)| *to)
+            .collect::<Vec<_>>();
+        children.sort_unstable_by_key(|id| {
+            let order_index = self.nodes.get(id).and_then(|node| node.order_index);
+            (order_index.unwrap_or(u32::MAX), *id)
+        });
+        children
+    }
+
+    /// 指定节点通过包含关系直接或间接包含的所有节点及其相对深度（子节点为 1），不包含节点自身。
+    /// 按先序排列，兄弟节点按 children 的顺序；包含关系成环或多个父节点时每个节点只出现一次
+    pub fn descendants(&self, root: u64) -> Vec<(u64, usize)> {
+        let mut visited = BTreeSet::from([root]);
+        let mut result = Vec::new();
+        let mut stack = self
+            .children(root)
+            .into_iter()
+            .rev()
+            .map(|id| (id, 1))
+            .collect::<Vec<_>>();
+        while let Some((id, depth)) = stack.pop() {
+            if !visited.insert(id) {
+                continue;
+            }
+            result.push((id, depth));
+            stack.extend(
+                self.children(id)
+                    .into_iter()
+                    .rev()
+                    .map(|child| (child, depth + 1)),
+            );
+        }
+        result
+    }
+
+    /// 指定节点与其余节点之间的边，即只有一端在其中的边
+    pub fn boundary_edges(&self, ids: &BTreeSet<u64>) -> Vec<(u64, u64)> {
+        self.edges
+            .keys()
+            .filter(|(from, to)| ids.contains(from) != ids.contains(to))
+            .copied()
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::graph::KnowledgeGraph;
+
+    use super::*;
+
+    #[test]
+    fn test_statistics_and_subgraph() {
+        let mut graph = KnowledgeGraph::default();
+        let a = graph
+            .add_entity(
+                "A".to_string(),
+                DistinctEntityType::KnowledgeUnit,
+                &[AddonEntityType::Example],
+                (0.0, 0.0),
+            )
+            .unwrap();
+        let b = graph
+            .add_entity(
+                "B".to_string(),
+                DistinctEntityType::KnowledgePoint,
+                &[AddonEntityType::Example, AddonEntityType::Question],
+                (100.0, 50.0),
+            )
+            .unwrap();
+        let c = graph
+            .add_entity(
+                "C".to_string(),
+                DistinctEntityType::KnowledgePoint,
+                &[],
+                (-20.0, 300.0),
+            )
+            .unwrap();
+        graph.add_edge(a, b, Relation::Contain).unwrap();
+        graph.add_edge(a, c, Relation::Contain).unwrap();
+
+        let stats = graph.current.statistics();
+        assert_eq!(stats.node_count, 3);
+        assert_eq!(stats.edge_count, 2);
+        assert_eq!(
+            stats.distinct_counts[&DistinctEntityType::KnowledgePoint],
+            2
+        );
+        assert_eq!(stats.addon_counts[&AddonEntityType::Example], 2);
+        assert_eq!(stats.bounding_size(), Some((120.0, 300.0)));
+
+        // 子图只保留内部的边
+        let ids = BTreeSet::from([a, b]);
+        let sub = graph.current.subgraph(&ids);
+        assert_eq!(sub.nodes.len(), 2);
+        assert_eq!(sub.edges.len(), 1);
+        assert_eq!(graph.current.boundary_edges(&ids), vec![(a, c)]);
+        assert_eq!(graph.current.neighbors(a), BTreeSet::from([b, c]));
+        assert_eq!(graph.current.neighbors(c), BTreeSet::from([a]));
+        graph.add_edge(b, c, Relation::Contain).unwrap();
+        graph.add_edge(c, a, Relation::Contain).unwrap();
+        assert_eq!(graph.current.descendants(a), vec![(b, 1), (c, 2)]);
+        graph.undo().unwrap();
+        graph.undo().unwrap();
+
+        let stats = sub.statistics();
+        assert_eq!(
+            stats
+                .distinct_counts
+                .get(&DistinctEntityType::KnowledgeDetail),
+            None
+        );
+        assert_eq!(stats.bounding_size(), Some((100.0, 50.0)));
+
+        // 空子图没有包围盒
+        assert_eq!(
+            graph
+                .current
+                .subgraph(&BTreeSet::new())
+                .statistics()
+                .bounding_box,
+            None
+        );
+    }
+}
@@ -0,0 +1,142 @@
+//! 文本表示模块，为节点类型、附加类型和关系实现 Display 和 FromStr，
+//! 便于在其他 Rust 程序中读写配置或命令行参数。
+
+use std::{fmt, str::FromStr};
+
+use super::{AddonEntityType, DistinctEntityType, Relation, relation_registry};
+use crate::error::SerdeError;
+
+impl fmt::Display for DistinctEntityType {
+    /// 显示为对接平台使用的 class_name，如“知识点”
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.class_name())
+    }
+}
+
+impl FromStr for DistinctEntityType {
+    type Err = SerdeError;
+
+    /// 接受 class_name（如“知识点”）或不区分大小写的缩写 ka、ku、kp、kd
+    ///
+    /// ```
+    /// use py_better_kt_sqep::graph::DistinctEntityType;
+    ///
+    /// assert_eq!("知识点".parse().ok(), Some(DistinctEntityType::KnowledgePoint));
+    /// assert_eq!("KA".parse().ok(), Some(DistinctEntityType::KnowledgeArena));
+    /// assert_eq!(DistinctEntityType::KnowledgeDetail.to_string(), "关键知识细节");
+    /// ```
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        DistinctEntityType::ALL
+            .into_iter()
+            .find(|distinct_type| distinct_type.class_name() == s)
+            .or_else(|| DistinctEntityType::from_abbr(&s.to_lowercase()))
+            .ok_or_else(|| SerdeError::Unexpected("节点类型", s.to_string()))
+    }
+}
+
+impl AddonEntityType {
+    /// 附加类型的中文名
+    fn zh_name(&self) -> &'static str {
+        match *self {
+            AddonEntityType::Knowledge => "知识",
+            AddonEntityType::Thinking => "思维",
+            AddonEntityType::Example => "示例",
+            AddonEntityType::Question => "问题",
+            AddonEntityType::Practice => "练习",
+            AddonEntityType::Political => "思政",
+        }
+    }
+}
+
+impl fmt::Display for AddonEntityType {
+    /// 显示为中文名，如“示例”
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.zh_name())
+    }
+}
+
+impl FromStr for AddonEntityType {
+    type Err = SerdeError;
+
+    /// 接受中文名（如“示例”）或不区分大小写的字符 k、t、e、q、p、z
+    ///
+    /// ```
+    /// use py_better_kt_sqep::graph::AddonEntityType;
+    ///
+    /// assert_eq!("思政".parse().ok(), Some(AddonEntityType::Political));
+    /// assert_eq!("e".parse().ok(), Some(AddonEntityType::Example));
+    /// assert_eq!(AddonEntityType::Thinking.to_string(), "思维");
+    /// ```
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let mut chars = s.chars();
+        let single = match (chars.next(), chars.next()) {
+            (Some(c), None) => Some(c.to_ascii_lowercase()),
+            _ => None,
+        };
+        AddonEntityType::ALL
+            .into_iter()
+            .find(|addon| addon.zh_name() == s || Some(addon.abbr()) == single)
+            .ok_or_else(|| SerdeError::Unexpected("附加类型", s.to_string()))
+    }
+}
+
+impl fmt::Display for Relation {
+    /// 显示为对接平台使用的 class_name，如“包含关系”
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.class_name())
+    }
+}
+
+impl FromStr for Relation {
+    type Err = SerdeError;
+
+    /// 接受关系类型表中的 class_name（如“包含关系”）、关系名（如“包含”）或不区分大小写的
+    /// contain、order、extension 及其首字母
+    ///
+    /// ```
+    /// use py_better_kt_sqep::graph::Relation;
+    ///
+    /// assert_eq!("次序关系".parse().ok(), Some(Relation::Order));
+    /// assert_eq!("扩展".parse().ok(), Some(Relation::Extension));
+    /// assert_eq!("C".parse().ok(), Some(Relation::Contain));
+    /// assert_eq!(Relation::Order.to_string(), "次序关系");
+    /// ```
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let lower = s.to_lowercase();
+        let relations = relation_registry().relations();
+        relation_registry()
+            .find_class_name(s)
+            .or_else(|| relations.into_iter().find(|relation| relation.name() == s))
+            .or_else(|| {
+                Relation::ALL
+                    .into_iter()
+                    .find(|relation| relation.abbr() == lower || relation.abbr()[..1] == lower)
+            })
+            .ok_or_else(|| SerdeError::Unexpected("关系", s.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_trip() {
+        for distinct_type in DistinctEntityType::ALL {
+            assert_eq!(distinct_type.to_string().parse().ok(), Some(distinct_type));
+        }
+        for addon in AddonEntityType::ALL {
+            assert_eq!(addon.to_string().parse().ok(), Some(addon));
+        }
+        for relation in Relation::ALL {
+            assert_eq!(relation.to_string().parse().ok(), Some(relation));
+        }
+
+        assert!("知识".parse::<DistinctEntityType>().is_err());
+        assert!("kt".parse::<AddonEntityType>().is_err());
+        assert!("".parse::<Relation>().is_err());
+    }
+}
@@ -0,0 +1,185 @@
+//! 缩略图模块，将快照绘制为小尺寸的 PNG 图像，供文件管理器或最近文件列表预览。
+//! 只绘制节点的色块和边的连线，不绘制文字。
+
+use image::{ImageFormat, Rgb, RgbImage};
+
+use super::{DistinctEntityType, NODE_SIZE, Relation, Snapshot};
+use crate::error::SerdeError;
+
+/// 缩略图的默认宽度
+pub const THUMBNAIL_WIDTH: u32 = 256;
+
+const MARGIN: f64 = 8.0; // 图像边缘的留白（像素）
+const BACKGROUND: Rgb<u8> = Rgb([255, 255, 255]);
+pub(super) const CONTAIN_COLOR: Rgb<u8> = Rgb([160, 160, 160]);
+pub(super) const ORDER_COLOR: Rgb<u8> = Rgb([54, 131, 248]);
+pub(super) const EXTENSION_COLOR: Rgb<u8> = Rgb([230, 140, 20]);
+
+/// 边的颜色，关系类型表中配置了颜色时使用配置的颜色
+pub(super) fn relation_color(relation: Relation) -> Rgb<u8> {
+    match (relation.color(), relation) {
+        (Some(color), _) => Rgb(color),
+        (None, Relation::Contain) => CONTAIN_COLOR,
+        (None, Relation::Order) => ORDER_COLOR,
+        (None, Relation::Extension | Relation::Custom(_)) => EXTENSION_COLOR,
+    }
+}
+
+/// 节点的填充色，与画布的标准配色一致
+pub(super) fn node_color(distinct_type: DistinctEntityType) -> Rgb<u8> {
+    match distinct_type {
+        DistinctEntityType::KnowledgeArena => Rgb([255, 105, 97]),
+        DistinctEntityType::KnowledgeUnit => Rgb([176, 217, 128]),
+        DistinctEntityType::KnowledgePoint => Rgb([189, 181, 225]),
+        DistinctEntityType::KnowledgeDetail => Rgb([182, 215, 232]),
+    }
+}
+
+/// 以 Bresenham 算法绘制线段，超出图像的部分被忽略
+fn draw_line(image: &mut RgbImage, from: (i64, i64), to: (i64, i64), color: Rgb<u8>) {
+    let (mut x, mut y) = from;
+    let dx = (to.0 - x).abs();
+    let dy = -(to.1 - y).abs();
+    let sx = if x < to.0 { 1 } else { -1 };
+    let sy = if y < to.1 { 1 } else { -1 };
+    let mut error = dx + dy;
+    loop {
+        if let (Ok(px), Ok(py)) = (u32::try_from(x), u32::try_from(y))
+            && px < image.width()
+            && py < image.height()
+        {
+            image.put_pixel(px, py, color);
+        }
+        if (x, y) == to {
+            break;
+        }
+        let doubled = 2 * error;
+        if doubled >= dy {
+            error += dy;
+            x += sx;
+        }
+        if doubled <= dx {
+            error += dx;
+            y += sy;
+        }
+    }
+}
+
+impl Snapshot {
+    /// 将快照绘制为宽度为 width 的缩略图，高度按图谱的长宽比计算，最多为宽度的两倍。
+    /// 空图谱返回 4:3 的空白图像
+    pub fn render_thumbnail(&self, width: u32) -> RgbImage {
+        let half = (NODE_SIZE.0 / 2.0, NODE_SIZE.1 / 2.0);
+        let bounds = self.nodes.values().fold(None, |bounds, node| {
+            let (x, y) = node.coor;
+            let (min_x, min_y, max_x, max_y) = bounds.unwrap_or((x, y, x, y));
+            Some((min_x.min(x), min_y.min(y), max_x.max(x), max_y.max(y)))
+        });
+        let Some((min_x, min_y, max_x, max_y)) = bounds else {
+            return RgbImage::from_pixel(width, width * 3 / 4, BACKGROUND);
+        };
+        let (min_x, min_y) = (min_x - half.0, min_y - half.1);
+        let (graph_width, graph_height) = (max_x + half.0 - min_x, max_y + half.1 - min_y);
+
+        // 按宽度缩放，过高时改为按最大高度缩放
+        let inner = (width as f64 - 2.0 * MARGIN).max(1.0);
+        let max_height = width as f64 * 2.0;
+        let scale = (inner / graph_width).min((max_height - 2.0 * MARGIN) / graph_height);
+        let height = (graph_height * scale + 2.0 * MARGIN).ceil() as u32;
+        let mut image = RgbImage::from_pixel(width, height, BACKGROUND);
+
+        let to_pixel = |(x, y): (f64, f64)| {
+            (
+                ((x - min_x) * scale + MARGIN).round() as i64,
+                ((y - min_y) * scale + MARGIN).round() as i64,
+            )
+        };
+
+        // 先画边，再画节点，使连线被节点覆盖
+        for (&(from, to), relation) in self.edges.iter() {
+            let (Some(from), Some(to)) = (self.nodes.get(&from), self.nodes.get(&to)) else {
+                continue;
+            };
+            let color = relation_color(*relation);
+            draw_line(&mut image, to_pixel(from.coor), to_pixel(to.coor), color);
+        }
+
+        for node in self.nodes.values() {
+            let (left, top) = to_pixel((node.coor.0 - half.0, node.coor.1 - half.1));
+            let (right, bottom) = to_pixel((node.coor.0 + half.0, node.coor.1 + half.1));
+            // 缩得很小时也至少绘制一个像素
+            let right = right.max(left + 1).min(image.width() as i64);
+            let bottom = bottom.max(top + 1).min(image.height() as i64);
+            for y in top.max(0)..bottom {
+                for x in left.max(0)..right {
+                    image.put_pixel(x as u32, y as u32, node_color(node.distinct_type));
+                }
+            }
+        }
+
+        image
+    }
+
+    /// 将快照绘制为宽度为 width 的 PNG 缩略图
+    pub fn to_thumbnail_png(&self, width: u32) -> Result<Vec<u8>, SerdeError> {
+        let mut bytes = std::io::Cursor::new(Vec::new());
+        self.render_thumbnail(width)
+            .write_to(&mut bytes, ImageFormat::Png)?;
+        Ok(bytes.into_inner())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::KnowledgeGraph;
+
+    #[test]
+    fn test_render_thumbnail() -> Result<(), Box<dyn std::error::Error>> {
+        let empty = Snapshot::default().render_thumbnail(THUMBNAIL_WIDTH);
+        assert_eq!(
+            empty.dimensions(),
+            (THUMBNAIL_WIDTH, THUMBNAIL_WIDTH * 3 / 4)
+        );
+
+        let mut graph = KnowledgeGraph::default();
+        let arena = graph.add_entity(
+            "Arena".to_string(),
+            DistinctEntityType::KnowledgeArena,
+            &[],
+            (0.0, 0.0),
+        )?;
+        let point = graph.add_entity(
+            "Point".to_string(),
+            DistinctEntityType::KnowledgePoint,
+            &[],
+            (1000.0, 0.0),
+        )?;
+        graph.add_edge(arena, point, Relation::Contain)?;
+
+        let image = graph.current_snapshot().render_thumbnail(THUMBNAIL_WIDTH);
+        assert_eq!(image.width(), THUMBNAIL_WIDTH);
+        assert!(image.height() < THUMBNAIL_WIDTH);
+        // 左侧为知识领域，右侧为知识点，中间为连线
+        let middle = image.height() / 2;
+        assert_eq!(
+            *image.get_pixel(MARGIN as u32 + 2, middle),
+            node_color(DistinctEntityType::KnowledgeArena)
+        );
+        assert_eq!(
+            *image.get_pixel(THUMBNAIL_WIDTH - MARGIN as u32 - 2, middle),
+            node_color(DistinctEntityType::KnowledgePoint)
+        );
+        assert_eq!(*image.get_pixel(THUMBNAIL_WIDTH / 2, middle), CONTAIN_COLOR);
+
+        // 竖长的图谱高度不超过宽度的两倍
+        graph.update_entity_position(point, (0.0, 100000.0))?;
+        let image = graph.current_snapshot().render_thumbnail(THUMBNAIL_WIDTH);
+        assert!(image.height() <= THUMBNAIL_WIDTH * 2);
+
+        let png = graph.current_snapshot().to_thumbnail_png(THUMBNAIL_WIDTH)?;
+        assert!(png.starts_with(b"\x89PNG"));
+
+        Ok(())
+    }
+}
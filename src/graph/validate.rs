@@ -0,0 +1,480 @@
+//! 图谱校验模块，检查可能导致对接平台拒收或显示异常的问题。
+
+use std::collections::HashMap;
+
+use super::{CoordinateBounds, NODE_SIZE, NormalizeOptions, Relation, Snapshot, normalize_content};
+
+/// 数量达到上限的该比例时开始提示
+const LIMIT_HINT_RATIO: f64 = 0.9;
+
+/// 问题的严重程度
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum IssueLevel {
+    Info,    // 提示
+    Warning, // 警告
+    Error,   // 错误，对接平台会拒收
+}
+
+/// 校验发现的问题
+#[derive(Debug, Clone, PartialEq)]
+pub struct ValidationIssue {
+    pub level: IssueLevel,
+    pub message: String,
+    pub entities: Vec<u64>, // 相关的节点，便于跳转
+}
+
+impl ValidationIssue {
+    pub fn new(level: IssueLevel, message: String, entities: Vec<u64>) -> Self {
+        Self {
+            level,
+            message,
+            entities,
+        }
+    }
+}
+
+impl Snapshot {
+    /// 校验图谱，返回发现的所有问题
+    pub fn validate(&self) -> Vec<ValidationIssue> {
+        let mut issues = Vec::new();
+        self.check_limits(&mut issues);
+        self.check_overlaps(&mut issues);
+        self.check_sibling_order(&mut issues);
+        self.check_content_length(&mut issues);
+        self.check_cross_level_duplicates(&mut issues);
+        self.check_reciprocal_edges(&mut issues);
+        issues.extend(self.coordinate_issue());
+        issues.extend(self.charset_issue());
+        issues
+    }
+
+    /// 坐标超出范围的节点的警告，没有这样的节点时返回 None。
+    /// 严格检查时可据此区分只有坐标超出范围的情况，由用户确认后仍然导出
+    pub fn coordinate_issue(&self) -> Option<ValidationIssue> {
+        let entities = self.out_of_bounds_nodes();
+        if entities.is_empty() {
+            return None;
+        }
+
+        let CoordinateBounds { min, max } = self.metadata.coordinate_bounds();
+        Some(ValidationIssue::new(
+            IssueLevel::Warning,
+            format!(
+                "有 {} 个节点的坐标超出范围 ({}, {})–({}, {})，对接平台上会被裁掉",
+                entities.len(),
+                min.0,
+                min.1,
+                max.0,
+                max.1
+            ),
+            entities,
+        ))
+    }
+
+    /// 检查是否有节点内容过长，过长的内容会使文件膨胀、显示缓慢
+    fn check_content_length(&self, issues: &mut Vec<ValidationIssue>) {
+        let limit = self.metadata.content_limit();
+        let mut entities = self
+            .nodes
+            .values()
+            .filter(|node| node.content.chars().count() > limit)
+            .map(|node| node.id)
+            .collect::<Vec<_>>();
+        if entities.is_empty() {
+            return;
+        }
+
+        entities.sort_unstable();
+        issues.push(ValidationIssue::new(
+            IssueLevel::Warning,
+            format!(
+                "有 {} 个节点的内容超过 {limit} 字，建议移到备注",
+                entities.len()
+            ),
+            entities,
+        ));
+    }
+
+    /// 内容相同的节点分组。内容规范化（去掉首尾空白、全角转半角、合并连续空白）后相同的非空内容
+    /// 为一组，只返回至少有两个节点的组；组内按 ID 排序，各组按第一个节点的 ID 排序
+    pub fn find_duplicate_contents(&self) -> Vec<Vec<u64>> {
+        let options = NormalizeOptions { halfwidth: true };
+        let mut by_content = HashMap::<String, Vec<u64>>::new();
+        for node in self.nodes.values() {
+            let content = normalize_content(&node.content, options);
+            if !content.is_empty() {
+                by_content.entry(content).or_default().push(node.id);
+            }
+        }
+        let mut groups = by_content
+            .into_values()
+            .filter(|ids| ids.len() > 1)
+            .map(|mut ids| {
+                ids.sort_unstable();
+                ids
+            })
+            .collect::<Vec<_>>();
+        groups.sort_unstable();
+        groups
+    }
+
+    /// 同一内容出现在不同层级的节点分组，即 find_duplicate_contents 中实体类型不全相同的组。
+    /// 对接平台将这些节点视为互不相关的概念，通常是录入时的失误
+    pub fn cross_level_duplicates(&self) -> Vec<Vec<u64>> {
+        self.find_duplicate_contents()
+            .into_iter()
+            .filter(|ids| {
+                let first = self.nodes[&ids[0]].distinct_type;
+                ids.iter().any(|id| self.nodes[id].distinct_type != first)
+            })
+            .collect()
+    }
+
+    /// 检查是否有相同的内容出现在不同层级，每组列出各节点的类型和上级节点的内容
+    fn check_cross_level_duplicates(&self, issues: &mut Vec<ValidationIssue>) {
+        for ids in self.cross_level_duplicates() {
+            let places = ids
+                .iter()
+                .map(|id| {
+                    let parent = self
+                        .parents(*id)
+                        .first()
+                        .and_then(|parent| self.nodes.get(parent))
+                        .map_or("无", |parent| parent.content.as_str());
+                    format!(
+                        "{}（上级：{parent}）",
+                        self.nodes[id].distinct_type.class_name()
+                    )
+                })
+                .collect::<Vec<_>>();
+            issues.push(ValidationIssue::new(
+                IssueLevel::Warning,
+                format!(
+                    "内容“{}”同时作为{}出现，对接平台会视为互不相关的节点",
+                    self.nodes[&ids[0]].content.trim(),
+                    places.join("、")
+                ),
+                ids,
+            ));
+        }
+    }
+
+    /// 检查是否有兄弟节点的次序相同，此时对接平台上的顺序由 ID 决定。
+    /// 扩展关系可以连接任意层级的节点，不构成父子关系，因此不参与检查
+    fn check_sibling_order(&self, issues: &mut Vec<ValidationIssue>) {
+        let mut parents = self
+            .edges
+            .iter()
+            .filter(|(_, relation)| **relation == Relation::Contain)
+            .map(|((from, _), _)| *from)
+            .collect::<Vec<_>>();
+        parents.sort_unstable();
+        parents.dedup();
+
+        for parent in parents {
+            let mut indices = HashMap::<u32, Vec<u64>>::new();
+            for child in self.children(parent) {
+                if let Some(index) = self.nodes.get(&child).and_then(|node| node.order_index) {
+                    indices.entry(index).or_default().push(child);
+                }
+            }
+            let mut entities = indices
+                .into_values()
+                .filter(|ids| ids.len() > 1)
+                .flatten()
+                .collect::<Vec<_>>();
+            if entities.is_empty() {
+                continue;
+            }
+            entities.sort_unstable();
+            issues.push(ValidationIssue::new(
+                IssueLevel::Warning,
+                format!("节点 {parent} 有 {} 个子节点的次序重复", entities.len()),
+                entities,
+            ));
+        }
+    }
+
+    /// 检查是否有节点互相遮挡
+    fn check_overlaps(&self, issues: &mut Vec<ValidationIssue>) {
+        let pairs = self.overlapping_pairs(NODE_SIZE);
+        if pairs.is_empty() {
+            return;
+        }
+
+        let mut entities = pairs.iter().flat_map(|(a, b)| [*a, *b]).collect::<Vec<_>>();
+        entities.sort_unstable();
+        entities.dedup();
+        issues.push(ValidationIssue::new(
+            IssueLevel::Warning,
+            format!("有 {} 对节点互相重叠", pairs.len()),
+            entities,
+        ));
+    }
+
+    /// 检查节点数和边数是否接近或超过上限
+    fn check_limits(&self, issues: &mut Vec<ValidationIssue>) {
+        let limits = [
+            ("节点", "个", self.nodes.len(), self.metadata.max_nodes),
+            ("边", "条", self.edges.len(), self.metadata.max_edges),
+        ];
+        for (name, unit, count, max) in limits {
+            let Some(max) = max else {
+                continue;
+            };
+
+            if count > max {
+                issues.push(ValidationIssue::new(
+                    IssueLevel::Error,
+                    format!("{name}数 {count} 超过上限 {max}"),
+                    Vec::new(),
+                ));
+            } else if count == max {
+                issues.push(ValidationIssue::new(
+                    IssueLevel::Warning,
+                    format!("{name}数已达到上限 {max}"),
+                    Vec::new(),
+                ));
+            } else if count as f64 >= max as f64 * LIMIT_HINT_RATIO {
+                issues.push(ValidationIssue::new(
+                    IssueLevel::Info,
+                    format!("还可添加 {} {unit}{name}", max - count),
+                    Vec::new(),
+                ));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::graph::{DistinctEntityType, GraphMetadata, KnowledgeGraph};
+
+    use super::*;
+
+    #[test]
+    fn test_limit_issues() {
+        let mut graph = KnowledgeGraph::default();
+        for i in 0..9 {
+            graph
+                .add_entity(
+                    format!("Node {i}"),
+                    DistinctEntityType::KnowledgePoint,
+                    &[],
+                    (i as f64 * 200.0, 0.0),
+                )
+                .unwrap();
+        }
+
+        // 不限制时没有问题
+        assert!(graph.current.validate().is_empty());
+
+        // 接近上限时提示剩余数量
+        graph.set_metadata(GraphMetadata {
+            max_nodes: Some(10),
+            ..Default::default()
+        });
+        let issues = graph.current.validate();
+        assert_eq!(issues.len(), 1);
+        assert_eq!(issues[0].level, IssueLevel::Info);
+        assert_eq!(issues[0].message, "还可添加 1 个节点");
+
+        // 达到上限时警告
+        graph
+            .add_entity(
+                "Node".to_string(),
+                DistinctEntityType::KnowledgePoint,
+                &[],
+                (2000.0, 0.0),
+            )
+            .unwrap();
+        assert_eq!(graph.current.validate()[0].level, IssueLevel::Warning);
+
+        // 超过上限（如调低上限）时报错
+        graph.set_metadata(GraphMetadata {
+            max_nodes: Some(5),
+            ..Default::default()
+        });
+        assert_eq!(graph.current.validate()[0].level, IssueLevel::Error);
+    }
+
+    #[test]
+    fn test_overlap_issues() {
+        let mut graph = KnowledgeGraph::default();
+        for coor in [(0.0, 0.0), (50.0, 50.0), (500.0, 0.0)] {
+            graph
+                .add_entity(
+                    "Node".to_string(),
+                    DistinctEntityType::KnowledgePoint,
+                    &[],
+                    coor,
+                )
+                .unwrap();
+        }
+
+        let issues = graph.current.validate();
+        assert_eq!(issues.len(), 1);
+        assert_eq!(issues[0].level, IssueLevel::Warning);
+        assert_eq!(issues[0].entities, vec![1, 2]);
+    }
+
+    #[test]
+    fn test_sibling_order_issues() {
+        let mut graph = KnowledgeGraph::default();
+        for i in 0..4 {
+            graph
+                .add_entity(
+                    format!("Node {i}"),
+                    DistinctEntityType::KnowledgePoint,
+                    &[],
+                    (i as f64 * 200.0, 0.0),
+                )
+                .unwrap();
+        }
+        for child in [2, 3, 4] {
+            graph.add_edge(1, child, Relation::Contain).unwrap();
+        }
+        graph.set_children_order(1, &[4, 2, 3]).unwrap();
+        assert!(graph.current.validate().is_empty());
+
+        // 手动修改使次序重复
+        graph
+            .update_entity_with(3, |node| node.order_index = Some(0))
+            .unwrap();
+        let issues = graph.current.validate();
+        assert_eq!(issues.len(), 1);
+        assert_eq!(issues[0].level, IssueLevel::Warning);
+        assert_eq!(issues[0].entities, vec![3, 4]);
+    }
+
+    #[test]
+    fn test_extension_across_levels() {
+        let mut graph = KnowledgeGraph::default();
+        let mut ids = Vec::new();
+        for (i, distinct_type) in [
+            DistinctEntityType::KnowledgeArena,
+            DistinctEntityType::KnowledgeUnit,
+            DistinctEntityType::KnowledgeDetail,
+        ]
+        .into_iter()
+        .enumerate()
+        {
+            ids.push(
+                graph
+                    .add_entity(
+                        format!("Node {i}"),
+                        distinct_type,
+                        &[],
+                        (i as f64 * 200.0, 0.0),
+                    )
+                    .unwrap(),
+            );
+        }
+        graph.add_edge(ids[0], ids[2], Relation::Extension).unwrap();
+        graph.add_edge(ids[2], ids[1], Relation::Extension).unwrap();
+        graph.add_edge(ids[1], ids[0], Relation::Extension).unwrap();
+        assert!(graph.current.validate().is_empty());
+    }
+
+    #[test]
+    fn test_content_length_issues() {
+        let mut graph = KnowledgeGraph::default();
+        let id = graph
+            .add_entity(
+                "长".repeat(500),
+                DistinctEntityType::KnowledgePoint,
+                &[],
+                (0.0, 0.0),
+            )
+            .unwrap();
+        assert!(graph.current.validate().is_empty());
+
+        // 按字符而非字节计数，超过默认上限时警告
+        graph
+            .update_entity_with(id, |node| node.content.push('长'))
+            .unwrap();
+        let issues = graph.current.validate();
+        assert_eq!(issues.len(), 1);
+        assert_eq!(issues[0].level, IssueLevel::Warning);
+        assert_eq!(issues[0].entities, vec![id]);
+
+        // 上限可以调整
+        graph.set_metadata(GraphMetadata {
+            max_content_chars: Some(1000),
+            ..Default::default()
+        });
+        assert!(graph.current.validate().is_empty());
+    }
+
+    #[test]
+    fn test_coordinate_issues() {
+        let mut graph = KnowledgeGraph::default();
+        let id = graph
+            .add_entity(
+                "Node".to_string(),
+                DistinctEntityType::KnowledgePoint,
+                &[],
+                (12800.0, 0.0),
+            )
+            .unwrap();
+        let issues = graph.current.validate();
+        assert_eq!(issues.len(), 1);
+        assert_eq!(issues[0].level, IssueLevel::Warning);
+        assert_eq!(issues[0].entities, vec![id]);
+        assert_eq!(graph.current.coordinate_issue().as_ref(), issues.first());
+
+        // 范围可以调整
+        graph.set_metadata(GraphMetadata {
+            coordinate_bounds: Some(CoordinateBounds {
+                min: (0.0, 0.0),
+                max: (16000.0, 16000.0),
+            }),
+            ..Default::default()
+        });
+        assert!(graph.current.validate().is_empty());
+        assert!(graph.current.coordinate_issue().is_none());
+    }
+
+    #[test]
+    fn test_cross_level_duplicates() {
+        let mut graph = KnowledgeGraph::default();
+        let nodes = [
+            ("数据结构", DistinctEntityType::KnowledgeUnit, (0.0, 0.0)),
+            ("算法", DistinctEntityType::KnowledgeUnit, (200.0, 0.0)),
+            ("栈", DistinctEntityType::KnowledgePoint, (0.0, 200.0)),
+            (" 栈 ", DistinctEntityType::KnowledgeDetail, (200.0, 200.0)),
+            ("队列", DistinctEntityType::KnowledgePoint, (400.0, 200.0)),
+            ("队列", DistinctEntityType::KnowledgePoint, (600.0, 200.0)),
+            ("ＴＣＰ", DistinctEntityType::KnowledgePoint, (800.0, 200.0)),
+            ("TCP", DistinctEntityType::KnowledgeDetail, (1000.0, 200.0)),
+        ];
+        for (content, distinct_type, coor) in nodes {
+            graph
+                .add_entity(content.to_string(), distinct_type, &[], coor)
+                .unwrap();
+        }
+        graph.add_edge(1, 3, Relation::Contain).unwrap();
+        graph.add_edge(2, 4, Relation::Contain).unwrap();
+
+        assert_eq!(
+            graph.current.find_duplicate_contents(),
+            vec![vec![3, 4], vec![5, 6], vec![7, 8]]
+        );
+        // 同一层级的重复不算在内
+        assert_eq!(
+            graph.current.cross_level_duplicates(),
+            vec![vec![3, 4], vec![7, 8]]
+        );
+
+        let issues = graph.current.validate();
+        assert_eq!(issues.len(), 2);
+        assert_eq!(issues[0].level, IssueLevel::Warning);
+        assert_eq!(issues[0].entities, vec![3, 4]);
+        assert!(issues[0].message.contains("知识点（上级：数据结构）"));
+        assert!(issues[0].message.contains("关键知识细节（上级：算法）"));
+        assert!(issues[1].message.contains("（上级：无）"));
+
+        graph.merge_entities(3, &[4]).unwrap();
+        assert_eq!(graph.current.cross_level_duplicates(), vec![vec![7, 8]]);
+    }
+}
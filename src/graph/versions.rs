@@ -0,0 +1,64 @@
+//! 节点内容的历史版本。撤回会回退整个图谱，这里从撤回栈中找出单个节点以前的内容，
+//! 在编辑窗口中查看或恢复，恢复时作为一次普通的修改。
+
+use super::KnowledgeGraph;
+
+impl KnowledgeGraph {
+    /// 节点内容的历史版本，由近到远排列，最多 limit 个。从撤回栈中收集与当前内容不同的内容，
+    /// 重复的内容只保留最近的一次。撤回栈有上限，超出的更早的版本不再保留；节点不存在时为空
+    pub fn content_versions(&self, id: u64, limit: usize) -> Vec<String> {
+        let Some(current) = self.current.nodes.get(&id) else {
+            return Vec::new();
+        };
+        let mut versions = Vec::<String>::new();
+        for snapshot in self.undo_stack.iter().rev() {
+            if versions.len() >= limit {
+                break;
+            }
+            if let Some(node) = snapshot.nodes.get(&id)
+                && node.content != current.content
+                && !versions.contains(&node.content)
+            {
+                versions.push(node.content.clone());
+            }
+        }
+        versions
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::DistinctEntityType;
+
+    #[test]
+    fn test_content_versions() {
+        let mut graph = KnowledgeGraph::default();
+        graph
+            .add_entity(
+                "栈".to_string(),
+                DistinctEntityType::KnowledgePoint,
+                &[],
+                (0.0, 0.0),
+            )
+            .unwrap();
+        assert!(graph.content_versions(1, 5).is_empty());
+
+        for content in ["栈的定义", "栈", "栈的操作"] {
+            graph
+                .update_entity_content(
+                    1,
+                    content.to_string(),
+                    DistinctEntityType::KnowledgePoint,
+                    &[],
+                )
+                .unwrap();
+        }
+        graph.update_entity_position(1, (10.0, 0.0)).unwrap(); // 内容不变的修改不产生版本
+
+        // 由近到远，重复的只保留最近的一次
+        assert_eq!(graph.content_versions(1, 5), vec!["栈", "栈的定义"]);
+        assert_eq!(graph.content_versions(1, 1), vec!["栈"]);
+        assert!(graph.content_versions(2, 5).is_empty());
+    }
+}
@@ -0,0 +1,93 @@
+//! 折点模块。密集的图谱中直线边会穿过其他节点，可以给边添加手动的折点，
+//! 边从起点依次经过各折点到达终点，按折线绘制以避让其他节点。
+
+use super::Snapshot;
+
+/// 点到线段的距离
+fn distance_to_segment(point: (f64, f64), start: (f64, f64), end: (f64, f64)) -> f64 {
+    let (dx, dy) = (end.0 - start.0, end.1 - start.1);
+    let length_sq = dx * dx + dy * dy;
+    let t = if length_sq == 0.0 {
+        0.0
+    } else {
+        (((point.0 - start.0) * dx + (point.1 - start.1) * dy) / length_sq).clamp(0.0, 1.0)
+    };
+    (point.0 - start.0 - t * dx).hypot(point.1 - start.1 - t * dy)
+}
+
+impl Snapshot {
+    /// 边的折点，没有折点或边不存在时为空
+    pub fn waypoints(&self, from: u64, to: u64) -> &[(f64, f64)] {
+        self.edge_waypoints
+            .get(&(from, to))
+            .map_or(&[], Vec::as_slice)
+    }
+
+    /// 在边上 coor 处添加折点后的折点列表：插入到折线中离 coor 最近的一段中间，保持折线的走向。
+    /// 边或其端点不存在时返回 None
+    pub fn waypoints_with_inserted(
+        &self,
+        from: u64,
+        to: u64,
+        coor: (f64, f64),
+    ) -> Option<Vec<(f64, f64)>> {
+        if !self.edges.contains_key(&(from, to)) {
+            return None;
+        }
+        let (start, end) = (self.nodes.get(&from)?.coor, self.nodes.get(&to)?.coor);
+        let mut waypoints = self.waypoints(from, to).to_vec();
+        let path = std::iter::once(start)
+            .chain(waypoints.iter().copied())
+            .chain(std::iter::once(end))
+            .collect::<Vec<_>>();
+        let index = path
+            .windows(2)
+            .map(|segment| distance_to_segment(coor, segment[0], segment[1]))
+            .enumerate()
+            .min_by(|(_, a), (_, b)| a.total_cmp(b))
+            .map_or(0, |(index, _)| index);
+        waypoints.insert(index, coor);
+        Some(waypoints)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::graph::{DistinctEntityType, KnowledgeGraph, Relation};
+
+    #[test]
+    fn test_waypoints_with_inserted() {
+        let mut graph = KnowledgeGraph::default();
+        for coor in [(0.0, 0.0), (400.0, 0.0)] {
+            graph
+                .add_entity(String::new(), DistinctEntityType::KnowledgePoint, &[], coor)
+                .unwrap();
+        }
+        graph.add_edge(1, 2, Relation::Order).unwrap();
+        assert!(graph.current.waypoints(1, 2).is_empty());
+
+        let waypoints = graph
+            .current
+            .waypoints_with_inserted(1, 2, (200.0, 100.0))
+            .unwrap();
+        assert_eq!(waypoints, vec![(200.0, 100.0)]);
+        graph.set_edge_waypoints(1, 2, waypoints).unwrap();
+
+        // 插入到最近的一段中间，不打乱折线的走向
+        let waypoints = graph
+            .current
+            .waypoints_with_inserted(1, 2, (300.0, 50.0))
+            .unwrap();
+        assert_eq!(waypoints, vec![(200.0, 100.0), (300.0, 50.0)]);
+        let waypoints = graph
+            .current
+            .waypoints_with_inserted(1, 2, (100.0, 40.0))
+            .unwrap();
+        assert_eq!(waypoints, vec![(100.0, 40.0), (200.0, 100.0)]);
+
+        assert_eq!(
+            graph.current.waypoints_with_inserted(2, 1, (0.0, 0.0)),
+            None
+        );
+    }
+}
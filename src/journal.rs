@@ -0,0 +1,305 @@
+//! 操作日志模块。开启后 FiledKnowledgeGraph 的每次修改都以一行 JSON 追加到图谱文件旁的
+//! `<文件名>.journal` 中，记录时间、操作和修改后的版本号，供研究编辑过程时审计和回放。
+//! 日志也可以通过 replay_journal() 重放为图谱，作为自动保存之外的恢复手段。
+//!
+//! 增量日志使用相同的格式，写入 `.<文件名>.log`，只保留上次全量保存之后的修改，
+//! 全量保存追上最新的修改后清空。打开文件时通过 recover_delta_log() 将其重放到文件中的快照上，
+//! 恢复崩溃前未保存的修改。
+//!
+//! 无论是否开启日志，本次打开以来的操作都以相同的格式保存在内存中，可随时导出，
+//! 随问题报告一起发送，维护者用 replay_journal() 重放即可复现。
+
+use std::{
+    collections::VecDeque,
+    fs::{self, File, OpenOptions},
+    io::Write,
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    error::{Error, SerdeError},
+    graph::{Command, KnowledgeGraph, Macro, Snapshot},
+};
+
+/// 日志记录的操作，与 FiledKnowledgeGraph 的修改接口对应
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "operation", content = "params", rename_all = "snake_case")]
+pub enum Operation {
+    Open(Snapshot), // 开始记录时或整体替换后的快照，重放时以此替换当前快照
+    Apply(Command),
+    ApplyBatch(Vec<Command>),
+    PlayMacro(Macro),
+    CompactIds,
+    Undo,
+    Redo,
+    Saved, // 全量保存完成，版本号为写入文件的版本，只出现在增量日志中
+}
+
+/// 日志中的一行
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct JournalEntry {
+    pub time: u64, // Unix 时间戳（毫秒）
+    #[serde(flatten)]
+    pub operation: Operation,
+    pub revision: u64, // 操作后图谱的版本号
+}
+
+impl JournalEntry {
+    /// 以当前时间创建一条记录
+    pub fn new(operation: Operation, revision: u64) -> Self {
+        let time = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |duration| duration.as_millis() as u64);
+        Self {
+            time,
+            operation,
+            revision,
+        }
+    }
+}
+
+/// 图谱文件对应的日志路径，如 `name.xml` 对应 `name.xml.journal`
+pub fn journal_path<P>(path: P) -> PathBuf
+where
+    P: AsRef<Path>,
+{
+    let mut path = path.as_ref().as_os_str().to_os_string();
+    path.push(".journal");
+    PathBuf::from(path)
+}
+
+/// 图谱文件对应的增量日志路径，如 `name.xml` 对应同目录下的 `.name.xml.log`
+pub fn delta_log_path<P>(path: P) -> PathBuf
+where
+    P: AsRef<Path>,
+{
+    let path = path.as_ref();
+    let mut name = std::ffi::OsString::from(".");
+    name.push(path.file_name().unwrap_or_default());
+    name.push(".log");
+    path.with_file_name(name)
+}
+
+/// 以追加模式打开的日志文件
+#[derive(Debug)]
+pub(crate) struct Journal {
+    file: File,
+}
+
+impl Journal {
+    /// 打开日志文件，不存在时创建
+    pub fn open<P>(path: P) -> Result<Self, Error>
+    where
+        P: AsRef<Path>,
+    {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        Ok(Self { file })
+    }
+
+    /// 追加一行记录并立即写入文件，避免程序崩溃时丢失
+    pub fn append(&mut self, operation: Operation, revision: u64) -> Result<(), Error> {
+        let entry = JournalEntry::new(operation, revision);
+        let mut line = serde_json::to_string(&entry).map_err(SerdeError::from)?;
+        line.push('\n');
+        self.file.write_all(line.as_bytes())?;
+        self.file.flush()?;
+        Ok(())
+    }
+
+    /// 清空日志，之后的记录从文件开头写入
+    pub fn truncate(&mut self) -> Result<(), Error> {
+        self.file.set_len(0)?;
+        Ok(())
+    }
+}
+
+/// 增量日志，记录上次全量保存之后的修改
+#[derive(Debug)]
+pub(crate) struct DeltaLog {
+    journal: Journal,
+    revision: u64, // 最后一条修改记录的版本号
+}
+
+impl DeltaLog {
+    /// 清空并打开增量日志，先记录当前快照，作为此后重放的起点
+    pub fn create<P>(path: P, snapshot: Snapshot, revision: u64) -> Result<Self, Error>
+    where
+        P: AsRef<Path>,
+    {
+        let mut journal = Journal::open(path)?;
+        journal.truncate()?;
+        journal.append(Operation::Open(snapshot), revision)?;
+        Ok(Self { journal, revision })
+    }
+
+    /// 追加一条修改记录
+    pub fn append(&mut self, operation: Operation, revision: u64) -> Result<(), Error> {
+        self.journal.append(operation, revision)?;
+        self.revision = revision;
+        Ok(())
+    }
+
+    /// 全量保存了 revision 版本后调用。已包含所有记录的修改时清空日志，否则记录保存点
+    pub fn saved(&mut self, revision: u64) -> Result<(), Error> {
+        if revision >= self.revision {
+            self.journal.truncate()
+        } else {
+            self.journal.append(Operation::Saved, revision)
+        }
+    }
+}
+
+/// 本次打开以来的操作历史，保存在内存中，可导出为操作日志的格式。
+/// 与撤回栈一样最多保留 max_len 条操作，超出时最早的操作并入起点的快照
+#[derive(Debug)]
+pub(crate) struct SessionHistory {
+    start: JournalEntry,                         // 起点，总是 Open
+    entries: VecDeque<(JournalEntry, Snapshot)>, // 起点之后的操作及操作后的快照
+    max_len: usize,
+    trimmed: bool, // 是否丢弃过最早的操作
+}
+
+impl SessionHistory {
+    /// 以打开时的快照为起点
+    pub fn new(snapshot: Snapshot, revision: u64, max_len: usize) -> Self {
+        Self {
+            start: JournalEntry::new(Operation::Open(snapshot), revision),
+            entries: VecDeque::new(),
+            max_len,
+            trimmed: false,
+        }
+    }
+
+    /// 记录一条操作，snapshot 为操作后的快照。超出上限时丢弃最早的操作，以其后的快照为新的起点。
+    /// 重放时起点之前的修改无法撤回，因此丢弃过操作后撤回和重做以操作后的快照记录
+    pub fn record(&mut self, mut operation: Operation, revision: u64, snapshot: &Snapshot) {
+        if self.trimmed && matches!(operation, Operation::Undo | Operation::Redo) {
+            operation = Operation::Open(snapshot.clone());
+        }
+        self.entries
+            .push_back((JournalEntry::new(operation, revision), snapshot.clone()));
+        if self.entries.len() <= self.max_len {
+            return;
+        }
+        if let Some((entry, snapshot)) = self.entries.pop_front() {
+            self.start = JournalEntry {
+                operation: Operation::Open(snapshot),
+                ..entry
+            };
+        }
+        if !self.trimmed {
+            self.trimmed = true;
+            for (entry, snapshot) in self.entries.iter_mut() {
+                if matches!(entry.operation, Operation::Undo | Operation::Redo) {
+                    entry.operation = Operation::Open(snapshot.clone());
+                }
+            }
+        }
+    }
+
+    /// 起点之后保留的操作数
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// 导出为操作日志的格式，每行一条记录，可由 replay_journal() 重放
+    pub fn to_journal(&self) -> Result<String, SerdeError> {
+        let mut content = String::new();
+        for entry in std::iter::once(&self.start).chain(self.entries.iter().map(|(entry, _)| entry))
+        {
+            content.push_str(&serde_json::to_string(entry)?);
+            content.push('\n');
+        }
+        Ok(content)
+    }
+}
+
+/// 在图谱上执行一条日志中的操作
+fn replay_operation(graph: &mut KnowledgeGraph, operation: Operation) -> Result<(), Error> {
+    match operation {
+        Operation::Open(snapshot) => graph.replace_current(snapshot),
+        Operation::Apply(command) => {
+            graph.apply(command)?;
+        }
+        Operation::ApplyBatch(commands) => {
+            graph.apply_batch(commands)?;
+        }
+        Operation::PlayMacro(recorded) => {
+            graph.play_macro(&recorded)?;
+        }
+        Operation::CompactIds => {
+            graph.compact_ids();
+        }
+        Operation::Undo => graph.undo()?,
+        Operation::Redo => graph.redo()?,
+        Operation::Saved => {}
+    }
+    Ok(())
+}
+
+/// 从空图谱开始依次执行日志中的操作，重建图谱。遇到 Open 时以其中的快照为新的起点，
+/// 之前的修改仍可撤回
+pub fn replay_journal<P>(path: P) -> Result<KnowledgeGraph, Error>
+where
+    P: AsRef<Path>,
+{
+    let content = fs::read_to_string(path)?;
+    let mut graph = KnowledgeGraph::from_snapshot(Snapshot::default());
+    for line in content.lines().filter(|line| !line.trim().is_empty()) {
+        let entry: JournalEntry = serde_json::from_str(line).map_err(SerdeError::from)?;
+        replay_operation(&mut graph, entry.operation)?;
+    }
+    Ok(graph)
+}
+
+/// 将增量日志中最后一个保存点之后的修改重放到 graph 上，返回重放的操作数。
+/// 日志不存在时返回 0。崩溃时可能只写入了最后一行的一部分，这一行会被忽略；
+/// 其它行无法解析或重放失败时返回错误，graph 保持不变
+pub fn recover_delta_log<P>(graph: &mut KnowledgeGraph, path: P) -> Result<usize, Error>
+where
+    P: AsRef<Path>,
+{
+    let content = match fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(0),
+        Err(e) => return Err(e.into()),
+    };
+    let lines = content
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .collect::<Vec<_>>();
+    let mut entries = Vec::with_capacity(lines.len());
+    for (i, line) in lines.iter().enumerate() {
+        match serde_json::from_str::<JournalEntry>(line) {
+            Ok(entry) => entries.push(entry),
+            Err(_) if i + 1 == lines.len() => {}
+            Err(e) => return Err(SerdeError::from(e).into()),
+        }
+    }
+
+    let saved = entries
+        .iter()
+        .filter(|entry| matches!(entry.operation, Operation::Saved))
+        .map(|entry| entry.revision)
+        .max();
+    let pending = entries
+        .into_iter()
+        .filter(|entry| !matches!(entry.operation, Operation::Saved))
+        .filter(|entry| saved.is_none_or(|saved| entry.revision > saved))
+        .collect::<Vec<_>>();
+    if pending.is_empty() {
+        return Ok(0);
+    }
+
+    let mut recovered = KnowledgeGraph::from_snapshot(graph.current_snapshot().clone());
+    let count = pending.len();
+    for entry in pending {
+        replay_operation(&mut recovered, entry.operation)?;
+    }
+    *graph = recovered;
+    Ok(count)
+}
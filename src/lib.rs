@@ -1,117 +1,640 @@
 #[cfg(feature = "extension-module")]
 use pyo3::{exceptions::PyException, prelude::*};
 
-mod app;
-mod error;
-mod file;
-mod graph;
+#[cfg(feature = "gui")]
+pub mod app;
+#[cfg(feature = "capi")]
+pub mod capi;
+pub mod error;
+pub mod file;
+pub mod graph;
+pub mod journal;
+pub mod shared;
 
 #[cfg(feature = "extension-module")]
 #[pyclass(name = "KnowledgeGraph")]
 pub struct PyKnowledgeGraph {
-    graph: graph::KnowledgeGraph,
+    // 加锁以免多个 Python 线程的操作交错
+    graph: shared::SharedKnowledgeGraph,
+}
+
+#[cfg(feature = "extension-module")]
+impl PyKnowledgeGraph {
+    /// 加锁后访问图谱，锁中毒时转为 Python 异常
+    fn with<T, F>(&self, f: F) -> PyResult<T>
+    where
+        F: FnOnce(&mut graph::KnowledgeGraph) -> T,
+    {
+        self.graph.with(f).map_err(internal_error)
+    }
+}
+
+#[cfg(feature = "extension-module")]
+fn internal_error(e: impl std::fmt::Display) -> PyErr {
+    PyErr::new::<PyException, _>(format!("Internal error: {e}"))
+}
+
+/// 将教学要求矩阵转为以知识单元 ID（“未分配”一行为“未分配”）为键、
+/// 内层以附加类型中文名为键的字典
+#[cfg(feature = "extension-module")]
+fn addon_matrix_dict(
+    snapshot: &graph::Snapshot,
+) -> std::collections::HashMap<String, std::collections::HashMap<String, usize>> {
+    snapshot
+        .addon_matrix()
+        .into_iter()
+        .map(|(id, counts)| {
+            let key = if id == graph::UNASSIGNED_ROW {
+                "未分配".to_string()
+            } else {
+                id.to_string()
+            };
+            let row = graph::AddonEntityType::ALL
+                .iter()
+                .map(|addon| addon.to_string())
+                .zip(counts)
+                .collect();
+            (key, row)
+        })
+        .collect()
+}
+
+/// 将 ka、ku、kp、kd 转为独立实体类型
+#[cfg(feature = "extension-module")]
+fn parse_distinct_type(distinct_type: &str) -> PyResult<graph::DistinctEntityType> {
+    match distinct_type.to_lowercase().as_str() {
+        "ka" => Ok(graph::DistinctEntityType::KnowledgeArena),
+        "ku" => Ok(graph::DistinctEntityType::KnowledgeUnit),
+        "kp" => Ok(graph::DistinctEntityType::KnowledgePoint),
+        "kd" => Ok(graph::DistinctEntityType::KnowledgeDetail),
+        _ => Err(PyErr::new::<PyException, _>(format!(
+            "Invalid distinct type {distinct_type}"
+        ))),
+    }
+}
+
+/// 将 k、t、e、q、p、z 的组合转为附加实体类型
+#[cfg(feature = "extension-module")]
+fn parse_addon_types(addon_types: &str) -> PyResult<Vec<graph::AddonEntityType>> {
+    addon_types
+        .to_lowercase()
+        .chars()
+        .map(|c| match c {
+            'k' => Ok(graph::AddonEntityType::Knowledge),
+            't' => Ok(graph::AddonEntityType::Thinking),
+            'e' => Ok(graph::AddonEntityType::Example),
+            'q' => Ok(graph::AddonEntityType::Question),
+            'p' => Ok(graph::AddonEntityType::Practice),
+            'z' => Ok(graph::AddonEntityType::Political),
+            _ => Err(PyErr::new::<PyException, _>(format!(
+                "Invalid addon type {c}"
+            ))),
+        })
+        .collect()
+}
+
+/// 将 contain (c)、order (o)、extension (e) 转为关系
+#[cfg(feature = "extension-module")]
+fn parse_relation(relation: &str) -> PyResult<graph::Relation> {
+    match relation.to_lowercase().as_str() {
+        "contain" | "c" => Ok(graph::Relation::Contain),
+        "order" | "o" => Ok(graph::Relation::Order),
+        "extension" | "e" => Ok(graph::Relation::Extension),
+        _ => Err(PyErr::new::<PyException, _>(format!(
+            "Invalid relation {relation}"
+        ))),
+    }
+}
+
+/// 将 detach、reattach、cascade 转为删除节点的策略，未指定时为 detach
+#[cfg(feature = "extension-module")]
+fn parse_remove_policy(policy: Option<&str>) -> PyResult<graph::RemovePolicy> {
+    match policy.map(str::to_lowercase).as_deref() {
+        None | Some("detach") => Ok(graph::RemovePolicy::Detach),
+        Some("reattach") => Ok(graph::RemovePolicy::ReattachToParents),
+        Some("cascade") => Ok(graph::RemovePolicy::Cascade),
+        Some(policy) => Err(PyErr::new::<PyException, _>(format!(
+            "Invalid remove policy {policy}"
+        ))),
+    }
+}
+
+/// 将 sequential、content 转为 ID 分配方式
+#[cfg(feature = "extension-module")]
+fn parse_id_allocation(id_allocation: &str) -> PyResult<graph::IdAllocation> {
+    match id_allocation.to_lowercase().as_str() {
+        "sequential" => Ok(graph::IdAllocation::Sequential),
+        "content" => Ok(graph::IdAllocation::Content),
+        _ => Err(PyErr::new::<PyException, _>(format!(
+            "Invalid id allocation {id_allocation}"
+        ))),
+    }
+}
+
+/// 将批量添加的 (内容, 类型, 附加类型, x, y) 转为节点，ID 在添加时分配
+#[cfg(feature = "extension-module")]
+fn parse_entities(
+    list: Vec<(String, String, String, f64, f64)>,
+) -> PyResult<Vec<graph::EntityNode>> {
+    list.into_iter()
+        .map(|(content, distinct_type, addon_types, x, y)| {
+            Ok(graph::EntityNode::new(
+                0,
+                content,
+                parse_distinct_type(&distinct_type)?,
+                &parse_addon_types(&addon_types)?,
+                (x, y),
+            ))
+        })
+        .collect()
+}
+
+/// 将批量添加的 (起点, 终点, 关系) 转为添加边的命令
+#[cfg(feature = "extension-module")]
+fn parse_edges(list: Vec<(u64, u64, String)>) -> PyResult<Vec<graph::Command>> {
+    list.into_iter()
+        .map(|(from, to, relation)| {
+            Ok(graph::Command::AddEdge {
+                from,
+                to,
+                relation: parse_relation(&relation)?,
+            })
+        })
+        .collect()
+}
+
+/// 节点关联的链接，节点不存在时抛出异常
+#[cfg(feature = "extension-module")]
+fn entity_links(snapshot: &graph::Snapshot, id: u64) -> PyResult<Vec<String>> {
+    snapshot
+        .nodes
+        .get(&id)
+        .map(|node| node.links.clone())
+        .ok_or_else(|| internal_error(error::GraphError::EntityNotFound(id)))
+}
+
+/// 节点的评审标记名称（todo、question、approved），没有标记时为 None，节点不存在时抛出异常
+#[cfg(feature = "extension-module")]
+fn entity_flag(snapshot: &graph::Snapshot, id: u64) -> PyResult<Option<String>> {
+    snapshot
+        .nodes
+        .get(&id)
+        .map(|node| node.flag.map(|flag| flag.name().to_string()))
+        .ok_or_else(|| internal_error(error::GraphError::EntityNotFound(id)))
+}
+
+/// 将 todo、question、approved 转为评审标记，None 为清除标记
+#[cfg(feature = "extension-module")]
+fn parse_flag(flag: Option<&str>) -> PyResult<Option<graph::Flag>> {
+    flag.map(|name| {
+        graph::Flag::from_name(name)
+            .ok_or_else(|| PyErr::new::<PyException, _>(format!("Invalid flag {name}")))
+    })
+    .transpose()
+}
+
+/// 将保存线程的错误合并为一个 Python 异常
+#[cfg(feature = "extension-module")]
+fn autosave_error(results: Vec<Result<(), error::Error>>) -> PyResult<()> {
+    let errors = results
+        .into_iter()
+        .filter_map(Result::err)
+        .map(|e| e.to_string())
+        .collect::<Vec<_>>();
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(PyErr::new::<PyException, _>(format!(
+            "Autosave failed: {}",
+            errors.join("; ")
+        )))
+    }
 }
 
 #[cfg(feature = "extension-module")]
 #[pymethods]
 impl PyKnowledgeGraph {
     #[new]
-    fn new() -> Self {
-        Self {
-            graph: graph::KnowledgeGraph::default(),
-        }
+    #[pyo3(signature = (id_allocation = "sequential"))]
+    fn new(id_allocation: &str) -> PyResult<Self> {
+        Ok(Self {
+            graph: shared::SharedKnowledgeGraph::new(graph::KnowledgeGraph::with_id_allocation(
+                parse_id_allocation(id_allocation)?,
+            )),
+        })
     }
 
     fn to_xml(&self) -> PyResult<String> {
-        match self.graph.current.to_xml() {
-            Ok(xml) => Ok(xml),
-            Err(e) => Err(PyErr::new::<PyException, _>(format!("Internal error: {e}"))),
-        }
+        self.with(|graph| graph.current.to_xml())?
+            .map_err(internal_error)
+    }
+
+    /// 导出对接平台可直接打开的 XML，不满足格式要求时抛出异常并列出问题
+    fn to_compatible_xml(&self) -> PyResult<String> {
+        self.with(|graph| graph.current.to_compatible_xml())?
+            .map_err(internal_error)
+    }
+
+    /// 教学要求矩阵，见 addon_matrix_dict
+    fn addon_matrix(
+        &self,
+    ) -> PyResult<std::collections::HashMap<String, std::collections::HashMap<String, usize>>> {
+        self.with(|graph| addon_matrix_dict(&graph.current))
     }
 
     fn add_entity(
-        &mut self,
+        &self,
         content: String,
         distinct_type: String,
         addon_types: String,
         x: f64,
         y: f64,
     ) -> PyResult<u64> {
-        // 将 distinct_type 转为 enum
-        let distinct_type = match distinct_type.to_lowercase().as_str() {
-            "ka" => graph::DistinctEntityType::KnowledgeArena,
-            "ku" => graph::DistinctEntityType::KnowledgeUnit,
-            "kp" => graph::DistinctEntityType::KnowledgePoint,
-            "kd" => graph::DistinctEntityType::KnowledgeDetail,
-            _ => {
-                return Err(PyErr::new::<PyException, _>(format!(
-                    "Invalid distinct type {distinct_type}"
-                )));
-            }
-        };
-
-        // 将 addon_types 转为 HashSet
-        let addon_types = addon_types
-            .to_lowercase()
-            .chars()
-            .map(|c| match c {
-                'k' => Ok(graph::AddonEntityType::Knowledge),
-                't' => Ok(graph::AddonEntityType::Thinking),
-                'e' => Ok(graph::AddonEntityType::Example),
-                'q' => Ok(graph::AddonEntityType::Question),
-                'p' => Ok(graph::AddonEntityType::Practice),
-                'z' => Ok(graph::AddonEntityType::Political),
-                _ => Err(PyErr::new::<PyException, _>(format!(
-                    "Invalid addon type {c}"
-                ))),
-            })
-            .collect::<Result<Vec<_>, _>>()?;
+        let distinct_type = parse_distinct_type(&distinct_type)?;
+        let addon_types = parse_addon_types(&addon_types)?;
 
         let id = self
-            .graph
-            .add_entity(content, distinct_type, &addon_types, (x, y));
+            .with(|graph| graph.add_entity(content, distinct_type, &addon_types, (x, y)))?
+            .map_err(internal_error)?;
 
         Ok(id)
     }
 
-    fn add_edge(&mut self, from: u64, to: u64, relation: String) -> PyResult<()> {
-        let relation = match relation.to_lowercase().as_str() {
-            "contain" => graph::Relation::Contain,
-            "order" => graph::Relation::Order,
-            _ => {
-                return Err(PyErr::new::<PyException, _>(format!(
-                    "Invalid relation {relation}"
-                )));
-            }
-        };
+    /// 批量添加节点，每项为 (内容, 类型, 附加类型, x, y)，整体作为一次可撤回的操作，
+    /// 返回按传入顺序排列的新 ID。任何一项无效时不添加任何节点
+    fn add_entities(&self, list: Vec<(String, String, String, f64, f64)>) -> PyResult<Vec<u64>> {
+        let nodes = parse_entities(list)?;
+        if nodes.is_empty() {
+            return Ok(Vec::new());
+        }
+        self.with(|graph| graph.add_entities(nodes))?
+            .map_err(internal_error)
+    }
 
-        self.graph
-            .add_edge(from, to, relation)
-            .map_err(|e| PyErr::new::<PyException, _>(format!("Internal error: {e}")))?;
+    /// 批量添加边，每项为 (起点, 终点, 关系)，整体作为一次可撤回的操作。
+    /// 任何一条无法添加时不添加任何边
+    fn add_edges(&self, list: Vec<(u64, u64, String)>) -> PyResult<()> {
+        let commands = parse_edges(list)?;
+        if commands.is_empty() {
+            return Ok(());
+        }
+        self.with(|graph| graph.apply_batch(commands))?
+            .map_err(internal_error)?;
 
         Ok(())
     }
 
-    fn remove_entity(&mut self, id: u64) -> PyResult<()> {
-        self.graph
-            .remove_entity(id)
-            .map_err(|e| PyErr::new::<PyException, _>(format!("Internal error: {e}")))?;
+    /// 节点关联的网址或本地文件路径
+    fn entity_links(&self, id: u64) -> PyResult<Vec<String>> {
+        self.with(|graph| entity_links(&graph.current, id))?
+    }
+
+    /// 以给定的列表替换节点关联的网址或本地文件路径
+    fn set_entity_links(&self, id: u64, links: Vec<String>) -> PyResult<()> {
+        self.with(|graph| graph.update_entity_with(id, |node| node.links = links))?
+            .map_err(internal_error)
+    }
+
+    /// 节点的评审标记：todo、question、approved，没有标记时为 None
+    fn entity_flag(&self, id: u64) -> PyResult<Option<String>> {
+        self.with(|graph| entity_flag(&graph.current, id))?
+    }
+
+    /// 设置节点的评审标记，flag 为 None 时清除标记，作为一次可撤回的操作
+    #[pyo3(signature = (id, flag=None))]
+    fn set_entity_flag(&self, id: u64, flag: Option<&str>) -> PyResult<()> {
+        let flag = parse_flag(flag)?;
+        self.with(|graph| graph.set_flags(&[id], flag))?
+            .map(|_| ())
+            .map_err(internal_error)
+    }
+
+    /// 带有指定评审标记的节点 ID，按 ID 排列
+    fn flagged(&self, flag: &str) -> PyResult<Vec<u64>> {
+        let flag = graph::Flag::from_name(flag)
+            .ok_or_else(|| PyErr::new::<PyException, _>(format!("Invalid flag {flag}")))?;
+        self.with(|graph| graph.current.flagged(flag))
+    }
+
+    fn add_edge(&self, from: u64, to: u64, relation: String) -> PyResult<()> {
+        let relation = parse_relation(&relation)?;
+
+        self.with(|graph| graph.add_edge(from, to, relation))?
+            .map_err(internal_error)?;
 
         Ok(())
     }
 
-    fn remove_edge(&mut self, from: u64, to: u64) -> PyResult<()> {
-        self.graph
-            .remove_edge(from, to)
-            .map_err(|e| PyErr::new::<PyException, _>(format!("Internal error: {e}")))?;
+    #[pyo3(signature = (max_nodes=None, max_edges=None))]
+    fn set_limits(&self, max_nodes: Option<usize>, max_edges: Option<usize>) -> PyResult<()> {
+        self.with(|graph| {
+            // 保留书签等其他元数据
+            let mut metadata = graph.current_snapshot().metadata.clone();
+            metadata.max_nodes = max_nodes;
+            metadata.max_edges = max_edges;
+            graph.set_metadata(metadata)
+        })
+    }
+
+    /// 删除节点，policy 为 detach（默认）、reattach 或 cascade
+    #[pyo3(signature = (id, policy=None))]
+    fn remove_entity(&self, id: u64, policy: Option<&str>) -> PyResult<()> {
+        let policy = parse_remove_policy(policy)?;
+        self.with(|graph| graph.remove_entity_with_policy(id, policy))?
+            .map_err(internal_error)?;
 
         Ok(())
     }
+
+    fn remove_edge(&self, from: u64, to: u64) -> PyResult<()> {
+        self.with(|graph| graph.remove_edge(from, to))?
+            .map_err(internal_error)?;
+
+        Ok(())
+    }
+
+    /// 将节点 ID 重新编号为 1..=n，返回旧 ID 到新 ID 的映射
+    fn compact_ids(&self) -> PyResult<std::collections::HashMap<u64, u64>> {
+        self.with(|graph| graph.compact_ids())
+    }
+
+    fn undo(&self) -> PyResult<()> {
+        self.with(|graph| graph.undo())?.map_err(internal_error)
+    }
+
+    fn redo(&self) -> PyResult<()> {
+        self.with(|graph| graph.redo())?.map_err(internal_error)
+    }
+}
+
+/// 绑定到文件的知识图谱，修改后由后台线程自动保存。
+/// 保存线程的错误在下一次调用时作为异常抛出，也可通过 take_errors() 取出。
+#[cfg(feature = "extension-module")]
+#[pyclass(name = "FiledGraph")]
+pub struct PyFiledGraph {
+    // 调用 close() 后为 None
+    graph: std::sync::Mutex<Option<file::FiledKnowledgeGraph>>,
+}
+
+#[cfg(feature = "extension-module")]
+impl PyFiledGraph {
+    /// 加锁后访问图谱。图谱已关闭或保存线程有未取出的错误时抛出异常
+    fn with<T, F>(&self, f: F) -> PyResult<T>
+    where
+        F: FnOnce(&mut file::FiledKnowledgeGraph) -> T,
+    {
+        let mut graph = self.graph.lock().map_err(internal_error)?;
+        let graph = graph
+            .as_mut()
+            .ok_or_else(|| PyErr::new::<PyException, _>("FiledGraph is closed"))?;
+        autosave_error(graph.take_autosave_results())?;
+        Ok(f(graph))
+    }
+}
+
+#[cfg(feature = "extension-module")]
+#[pymethods]
+impl PyFiledGraph {
+    /// 打开文件，create 为 True 或文件不存在时创建空图谱。
+    /// create 为 True 但文件已有内容时抛出异常，除非 overwrite 为 True
+    #[staticmethod]
+    #[pyo3(signature = (path, create=false, autosave=true, overwrite=false))]
+    fn open(
+        path: std::path::PathBuf,
+        create: bool,
+        autosave: bool,
+        overwrite: bool,
+    ) -> PyResult<Self> {
+        let graph = if overwrite {
+            file::FiledKnowledgeGraph::overwrite(path)
+        } else {
+            file::FiledKnowledgeGraph::new(path, create)
+        };
+        let mut graph = graph.map_err(internal_error)?;
+        graph.set_autosave(autosave);
+        Ok(Self {
+            graph: std::sync::Mutex::new(Some(graph)),
+        })
+    }
+
+    fn __enter__(slf: Py<Self>) -> Py<Self> {
+        slf
+    }
+
+    fn __exit__(
+        &self,
+        _exc_type: PyObject,
+        _exc_value: PyObject,
+        _traceback: PyObject,
+    ) -> PyResult<bool> {
+        self.close()?;
+        Ok(false)
+    }
+
+    fn to_xml(&self) -> PyResult<String> {
+        self.with(|graph| graph.current_snapshot().to_xml())?
+            .map_err(internal_error)
+    }
+
+    /// 导出对接平台可直接打开的 XML，不满足格式要求时抛出异常并列出问题
+    fn to_compatible_xml(&self) -> PyResult<String> {
+        self.with(|graph| graph.current_snapshot().to_compatible_xml())?
+            .map_err(internal_error)
+    }
+
+    /// 教学要求矩阵，见 addon_matrix_dict
+    fn addon_matrix(
+        &self,
+    ) -> PyResult<std::collections::HashMap<String, std::collections::HashMap<String, usize>>> {
+        self.with(|graph| addon_matrix_dict(graph.current_snapshot()))
+    }
+
+    fn add_entity(
+        &self,
+        content: String,
+        distinct_type: String,
+        addon_types: String,
+        x: f64,
+        y: f64,
+    ) -> PyResult<u64> {
+        let distinct_type = parse_distinct_type(&distinct_type)?;
+        let addon_types = parse_addon_types(&addon_types)?;
+        self.with(|graph| graph.add_entity(content, distinct_type, &addon_types, (x, y)))?
+            .map_err(internal_error)
+    }
+
+    /// 批量添加节点，每项为 (内容, 类型, 附加类型, x, y)，整体作为一次可撤回的操作，
+    /// 返回按传入顺序排列的新 ID。任何一项无效时不添加任何节点
+    fn add_entities(&self, list: Vec<(String, String, String, f64, f64)>) -> PyResult<Vec<u64>> {
+        let nodes = parse_entities(list)?;
+        if nodes.is_empty() {
+            return Ok(Vec::new());
+        }
+        self.with(|graph| graph.add_entities(nodes))?
+            .map_err(internal_error)
+    }
+
+    fn update_entity_content(
+        &self,
+        id: u64,
+        content: String,
+        distinct_type: String,
+        addon_types: String,
+    ) -> PyResult<()> {
+        let distinct_type = parse_distinct_type(&distinct_type)?;
+        let addon_types = parse_addon_types(&addon_types)?;
+        self.with(|graph| graph.update_entity_content(id, content, distinct_type, &addon_types))?
+            .map_err(internal_error)
+    }
+
+    fn update_entity_position(&self, id: u64, x: f64, y: f64) -> PyResult<()> {
+        self.with(|graph| graph.update_entity_position(id, (x, y)))?
+            .map_err(internal_error)
+    }
+
+    /// 节点关联的网址或本地文件路径
+    fn entity_links(&self, id: u64) -> PyResult<Vec<String>> {
+        self.with(|graph| entity_links(graph.current_snapshot(), id))?
+    }
+
+    /// 以给定的列表替换节点关联的网址或本地文件路径
+    fn set_entity_links(&self, id: u64, links: Vec<String>) -> PyResult<()> {
+        self.with(|graph| graph.update_entity_with(id, |node| node.links = links))?
+            .map_err(internal_error)
+    }
+
+    /// 节点的评审标记：todo、question、approved，没有标记时为 None
+    fn entity_flag(&self, id: u64) -> PyResult<Option<String>> {
+        self.with(|graph| entity_flag(graph.current_snapshot(), id))?
+    }
+
+    /// 设置节点的评审标记，flag 为 None 时清除标记，作为一次可撤回的操作
+    #[pyo3(signature = (id, flag=None))]
+    fn set_entity_flag(&self, id: u64, flag: Option<&str>) -> PyResult<()> {
+        let flag = parse_flag(flag)?;
+        self.with(|graph| graph.set_flags(&[id], flag))?
+            .map(|_| ())
+            .map_err(internal_error)
+    }
+
+    /// 带有指定评审标记的节点 ID，按 ID 排列
+    fn flagged(&self, flag: &str) -> PyResult<Vec<u64>> {
+        let flag = graph::Flag::from_name(flag)
+            .ok_or_else(|| PyErr::new::<PyException, _>(format!("Invalid flag {flag}")))?;
+        self.with(|graph| graph.current_snapshot().flagged(flag))
+    }
+
+    /// 删除节点，policy 为 detach（默认）、reattach 或 cascade
+    #[pyo3(signature = (id, policy=None))]
+    fn remove_entity(&self, id: u64, policy: Option<&str>) -> PyResult<()> {
+        let policy = parse_remove_policy(policy)?;
+        self.with(|graph| graph.remove_entity_with_policy(id, policy))?
+            .map_err(internal_error)
+    }
+
+    fn add_edge(&self, from: u64, to: u64, relation: String) -> PyResult<()> {
+        let relation = parse_relation(&relation)?;
+        self.with(|graph| graph.add_edge(from, to, relation))?
+            .map_err(internal_error)
+    }
+
+    /// 批量添加边，每项为 (起点, 终点, 关系)，整体作为一次可撤回的操作。
+    /// 任何一条无法添加时不添加任何边
+    fn add_edges(&self, list: Vec<(u64, u64, String)>) -> PyResult<()> {
+        let commands = parse_edges(list)?;
+        if commands.is_empty() {
+            return Ok(());
+        }
+        self.with(|graph| graph.apply_batch(commands))?
+            .map(|_| ())
+            .map_err(internal_error)
+    }
+
+    fn update_edge(&self, from: u64, to: u64, relation: String) -> PyResult<()> {
+        let relation = parse_relation(&relation)?;
+        self.with(|graph| graph.update_edge(from, to, relation))?
+            .map_err(internal_error)
+    }
+
+    fn set_edge_label(&self, from: u64, to: u64, label: String) -> PyResult<()> {
+        self.with(|graph| graph.set_edge_label(from, to, label))?
+            .map_err(internal_error)
+    }
+
+    fn remove_edge(&self, from: u64, to: u64) -> PyResult<()> {
+        self.with(|graph| graph.remove_edge(from, to))?
+            .map_err(internal_error)
+    }
+
+    #[pyo3(signature = (max_nodes=None, max_edges=None))]
+    fn set_limits(&self, max_nodes: Option<usize>, max_edges: Option<usize>) -> PyResult<()> {
+        self.with(|graph| {
+            // 保留书签等其他元数据
+            let mut metadata = graph.current_snapshot().metadata.clone();
+            metadata.max_nodes = max_nodes;
+            metadata.max_edges = max_edges;
+            graph.set_metadata(metadata)
+        })
+    }
+
+    fn compact_ids(&self) -> PyResult<std::collections::HashMap<u64, u64>> {
+        self.with(|graph| graph.compact_ids())
+    }
+
+    fn undo(&self) -> PyResult<()> {
+        self.with(|graph| graph.undo())?.map_err(internal_error)
+    }
+
+    fn redo(&self) -> PyResult<()> {
+        self.with(|graph| graph.redo())?.map_err(internal_error)
+    }
+
+    /// 重新读取文件，替换当前图谱，替换前的内容可以撤回找回
+    fn reload(&self) -> PyResult<()> {
+        self.with(|graph| graph.reload())?.map_err(internal_error)
+    }
+
+    /// 立即将当前图谱写入文件
+    fn save(&self) -> PyResult<()> {
+        self.with(|graph| graph.save())?.map_err(internal_error)
+    }
+
+    /// 阻塞直到后台线程写入了之前的所有修改，写入失败时抛出异常
+    fn flush(&self) -> PyResult<()> {
+        self.with(|graph| graph.flush())?.map_err(internal_error)?;
+        self.with(|_| ())
+    }
+
+    /// 取出保存线程的错误信息而不抛出异常
+    fn take_errors(&self) -> PyResult<Vec<String>> {
+        let graph = self.graph.lock().map_err(internal_error)?;
+        Ok(graph
+            .as_ref()
+            .map(|graph| {
+                graph
+                    .take_autosave_results()
+                    .into_iter()
+                    .filter_map(Result::err)
+                    .map(|e| e.to_string())
+                    .collect()
+            })
+            .unwrap_or_default())
+    }
+
+    /// 写入剩余的修改并关闭后台线程，之后的调用都会抛出异常。重复关闭没有效果
+    fn close(&self) -> PyResult<()> {
+        let graph = self.graph.lock().map_err(internal_error)?.take();
+        match graph {
+            Some(graph) => autosave_error(graph.close()),
+            None => Ok(()),
+        }
+    }
 }
 
 #[cfg(feature = "extension-module")]
 #[pymodule]
 pub fn py_better_kt_sqep(_py: Python, m: &Bound<'_, PyModule>) -> PyResult<()> {
     m.add_class::<PyKnowledgeGraph>()?;
+    m.add_class::<PyFiledGraph>()?;
     Ok(())
 }
@@ -1,20 +1,18 @@
 #![windows_subsystem = "windows"]
 
-mod app;
-mod error;
-mod file;
-mod graph;
-
 use std::sync::Arc;
 
-use app::GraphApp;
 use eframe::{
     NativeOptions,
     egui::{self, FontData, IconData, ViewportBuilder},
 };
+use py_better_kt_sqep::app::{GraphApp, logging};
+
+/// 应用名称，也用于确定保存设置和日志的目录
+const APP_NAME: &str = "Better KT-SQEP";
 
 fn main() {
-    let app = GraphApp::default();
+    logging::init(APP_NAME);
 
     let native_options = NativeOptions {
         centered: true,
@@ -25,7 +23,7 @@ fn main() {
     };
 
     eframe::run_native(
-        "Better KT-SQEP",
+        APP_NAME,
         native_options,
         Box::new(|cc| {
             egui_extras::install_image_loaders(&cc.egui_ctx);
@@ -44,7 +42,7 @@ fn main() {
             cc.egui_ctx.set_fonts(fonts);
             cc.egui_ctx.set_visuals(egui::Visuals::light());
 
-            Ok(Box::new(app))
+            Ok(Box::new(GraphApp::new(cc)))
         }),
     )
     .unwrap();
@@ -0,0 +1,114 @@
+//! 共享图谱模块，为需要跨线程访问的场景（如 Python 绑定）提供加锁的知识图谱。
+
+use std::sync::Mutex;
+
+use crate::{error::Error, graph::KnowledgeGraph};
+
+/// 加锁的知识图谱，每次访问持有锁直到操作完成，保证操作之间不会交错
+#[derive(Debug, Default)]
+pub struct SharedKnowledgeGraph {
+    graph: Mutex<KnowledgeGraph>,
+}
+
+impl SharedKnowledgeGraph {
+    pub fn new(graph: KnowledgeGraph) -> Self {
+        Self {
+            graph: Mutex::new(graph),
+        }
+    }
+
+    /// 加锁后访问图谱。
+    /// 其它线程持有锁时 panic 会使锁中毒，此时返回错误而不是 panic，由调用方决定如何处理。
+    pub fn with<T, F>(&self, f: F) -> Result<T, Error>
+    where
+        F: FnOnce(&mut KnowledgeGraph) -> T,
+    {
+        let mut graph = self
+            .graph
+            .lock()
+            .map_err(|e| Error::Poison(e.to_string()))?;
+        Ok(f(&mut graph))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{panic, sync::Arc, thread};
+
+    use crate::graph::{DistinctEntityType, Relation};
+
+    use super::*;
+
+    #[test]
+    fn test_concurrent_mutation() {
+        let shared = Arc::new(SharedKnowledgeGraph::default());
+
+        let handles = (0..8)
+            .map(|t| {
+                let shared = Arc::clone(&shared);
+                thread::spawn(move || {
+                    let mut ids = Vec::new();
+                    for i in 0..200 {
+                        // 混合添加、连边、删除和撤销，失败的操作（如撤销后节点已不存在）直接忽略
+                        shared
+                            .with(|graph| match i % 4 {
+                                0 | 1 => {
+                                    if let Ok(id) = graph.add_entity(
+                                        format!("{t}-{i}"),
+                                        DistinctEntityType::KnowledgePoint,
+                                        &[],
+                                        (0.0, 0.0),
+                                    ) {
+                                        if let Some(&last) = ids.last() {
+                                            let _ = graph.add_edge(last, id, Relation::Order);
+                                        }
+                                        ids.push(id);
+                                    }
+                                }
+                                2 => {
+                                    if let Some(id) = ids.pop() {
+                                        let _ = graph.remove_entity(id);
+                                    }
+                                }
+                                _ => {
+                                    let _ = graph.undo();
+                                }
+                            })
+                            .unwrap();
+                    }
+                })
+            })
+            .collect::<Vec<_>>();
+        for handle in handles {
+            handle.join().unwrap();
+        }
+
+        shared
+            .with(|graph| {
+                let snapshot = &graph.current;
+                // ID 与键一致且唯一
+                assert!(snapshot.nodes.iter().all(|(id, node)| *id == node.id));
+                // 没有悬空的边
+                assert!(snapshot.edges.keys().all(|(from, to)| {
+                    snapshot.nodes.contains_key(from) && snapshot.nodes.contains_key(to)
+                }));
+            })
+            .unwrap();
+    }
+
+    #[test]
+    fn test_poisoned() {
+        let shared = Arc::new(SharedKnowledgeGraph::default());
+
+        // 持有锁的线程 panic
+        let cloned = Arc::clone(&shared);
+        let result = thread::spawn(move || {
+            let _ = cloned.with(|_| panic::panic_any("poison"));
+        })
+        .join();
+        assert!(result.is_err());
+
+        // 之后的访问返回错误而不是 panic
+        assert!(matches!(shared.with(|_| ()), Err(Error::Poison(_))));
+    }
+}
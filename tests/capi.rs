@@ -0,0 +1,107 @@
+//! C ABI 测试：通过 libloading 加载编译出的动态库，按 C 调用方的方式使用导出的函数。
+#![cfg(feature = "capi")]
+
+use std::{
+    ffi::{CStr, CString, c_char},
+    path::{Path, PathBuf},
+    ptr,
+};
+
+use libloading::{Library, Symbol};
+
+type Handle = *mut std::ffi::c_void;
+type Load = unsafe extern "C" fn(*const c_char, *mut *mut c_char) -> Handle;
+type Count = unsafe extern "C" fn(Handle) -> usize;
+type Write = unsafe extern "C" fn(Handle, *mut c_char, usize, *mut *mut c_char) -> isize;
+type Free = unsafe extern "C" fn(Handle);
+type FreeError = unsafe extern "C" fn(*mut c_char);
+
+/// 测试程序位于 target/<profile>/deps，动态库与之同目录；cargo 只在构建库时将其复制到上一级目录，
+/// 切换特性后上一级目录中的可能是旧版本，因此优先使用 deps 中的
+fn library_path() -> PathBuf {
+    let exe = std::env::current_exe().unwrap();
+    let deps = exe.parent().unwrap();
+    let name = libloading::library_filename("py_better_kt_sqep");
+    [deps, deps.parent().unwrap()]
+        .into_iter()
+        .map(|dir| dir.join(&name))
+        .find(|path| path.exists())
+        .unwrap()
+}
+
+/// 按返回的长度分配缓冲区后再次调用，取得完整的字符串
+unsafe fn read_string(write: &Symbol<Write>, handle: Handle) -> String {
+    unsafe {
+        let len = write(handle, ptr::null_mut(), 0, ptr::null_mut());
+        assert!(len >= 0);
+        let mut buffer = vec![0u8; len as usize + 1];
+        let written = write(
+            handle,
+            buffer.as_mut_ptr().cast(),
+            buffer.len(),
+            ptr::null_mut(),
+        );
+        assert_eq!(written, len);
+        CStr::from_bytes_with_nul(&buffer)
+            .unwrap()
+            .to_str()
+            .unwrap()
+            .to_string()
+    }
+}
+
+#[test]
+fn test_capi() -> Result<(), Box<dyn std::error::Error>> {
+    unsafe {
+        let library = Library::new(library_path())?;
+        let load: Symbol<Load> = library.get(b"kg_load")?;
+        let node_count: Symbol<Count> = library.get(b"kg_node_count")?;
+        let edge_count: Symbol<Count> = library.get(b"kg_edge_count")?;
+        let validate: Symbol<Write> = library.get(b"kg_validate")?;
+        let to_xml: Symbol<Write> = library.get(b"kg_to_xml")?;
+        let to_json: Symbol<Write> = library.get(b"kg_to_json")?;
+        let free: Symbol<Free> = library.get(b"kg_free")?;
+        let free_error: Symbol<FreeError> = library.get(b"kg_free_error")?;
+
+        // 加载失败时返回空指针和错误信息
+        let mut error = ptr::null_mut();
+        let missing = CString::new("/nonexistent/graph.xml")?;
+        assert!(load(missing.as_ptr(), &mut error).is_null());
+        assert!(!error.is_null());
+        free_error(error);
+
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/platform_export.xml");
+        let path = CString::new(path.to_str().unwrap())?;
+        let mut error = ptr::null_mut();
+        let handle = load(path.as_ptr(), &mut error);
+        assert!(!handle.is_null());
+        assert!(error.is_null());
+
+        let nodes = node_count(handle);
+        assert!(nodes > 0);
+        assert!(edge_count(handle) > 0);
+
+        // 缓冲区不足时不写入
+        let mut small = [1 as c_char; 4];
+        assert!(to_xml(handle, small.as_mut_ptr(), small.len(), ptr::null_mut()) > 4);
+        assert_eq!(small, [1; 4]);
+
+        let xml = read_string(&to_xml, handle);
+        assert!(xml.starts_with("<KG>"));
+        let json: serde_json::Value = serde_json::from_str(&read_string(&to_json, handle))?;
+        assert_eq!(json["nodes"].as_array().map(Vec::len), Some(nodes));
+        let issues: serde_json::Value = serde_json::from_str(&read_string(&validate, handle))?;
+        assert!(issues.is_array());
+
+        free(handle);
+
+        // 空句柄不会崩溃
+        assert_eq!(node_count(ptr::null_mut()), 0);
+        let mut error = ptr::null_mut();
+        assert_eq!(to_xml(ptr::null_mut(), ptr::null_mut(), 0, &mut error), -1);
+        assert_eq!(CStr::from_ptr(error).to_str()?, "null handle");
+        free_error(error);
+    }
+
+    Ok(())
+}
@@ -0,0 +1,58 @@
+//! 编解码的属性测试：任意图谱保存为 XML 后再读取应完全一致，
+//! 任意输入都不能使解析崩溃，只能返回错误。
+
+mod support;
+
+use proptest::prelude::*;
+use py_better_kt_sqep::graph::{Snapshot, XmlOptions};
+
+use support::{arb_snapshot, arb_text};
+
+proptest! {
+    #[test]
+    fn test_xml_round_trip(snapshot in arb_snapshot(8)) {
+        let xml = snapshot.to_xml().unwrap();
+        prop_assert_eq!(&Snapshot::from_xml(&xml).unwrap(), &snapshot, "{}", xml);
+    }
+
+    #[test]
+    fn test_xml_round_trip_with_options(
+        snapshot in arb_snapshot(4),
+        (indent, escape_non_ascii, declaration, crlf) in any::<(bool, bool, bool, bool)>(),
+    ) {
+        let options = XmlOptions { indent, escape_non_ascii, declaration, crlf };
+        let xml = snapshot.to_xml_with(options).unwrap();
+        prop_assert_eq!(&Snapshot::from_xml(&xml).unwrap(), &snapshot, "{}", xml);
+        let (lenient, _) = Snapshot::from_xml_lenient(&xml).unwrap();
+        prop_assert_eq!(&lenient, &snapshot);
+    }
+
+    #[test]
+    fn test_bytes_round_trip(snapshot in arb_snapshot(8)) {
+        let bytes = snapshot.to_bytes().unwrap();
+        prop_assert_eq!(&Snapshot::from_bytes(&bytes).unwrap(), &snapshot);
+    }
+
+    #[test]
+    fn test_parse_arbitrary_text(text in arb_text()) {
+        let _ = Snapshot::from_xml(&text);
+        let _ = Snapshot::from_xml_lenient(&text);
+    }
+
+    #[test]
+    fn test_parse_mutated_xml(
+        snapshot in arb_snapshot(4),
+        cut in any::<prop::sample::Index>(),
+        insert in arb_text(),
+    ) {
+        // 在合法 XML 的任意位置截断或插入文本
+        let xml = snapshot.to_xml().unwrap();
+        let boundaries = xml.char_indices().map(|(i, _)| i).chain([xml.len()]).collect::<Vec<_>>();
+        let at = *cut.get(&boundaries);
+        let _ = Snapshot::from_xml(&xml[..at]);
+        let _ = Snapshot::from_xml_lenient(&xml[..at]);
+        let mutated = format!("{}{insert}{}", &xml[..at], &xml[at..]);
+        let _ = Snapshot::from_xml(&mutated);
+        let _ = Snapshot::from_xml_lenient(&mutated);
+    }
+}
@@ -0,0 +1,119 @@
+"""FiledGraph 的端到端测试，需先以 `maturin develop` 安装扩展模块后运行 `pytest tests/python`。"""
+
+from py_better_kt_sqep import FiledGraph
+
+
+def test_flush_writes_file(tmp_path):
+    path = tmp_path / "graph.xml"
+    with FiledGraph.open(str(path), create=True) as kg:
+        entity_1 = kg.add_entity("节点一", "ka", "k", 0.0, 0.0)
+        entity_2 = kg.add_entity("节点二", "ku", "te", 300.0, 0.0)
+        kg.add_edge(entity_1, entity_2, "contain")
+        kg.flush()
+
+        # flush 返回时文件内容与内存中的图谱一致
+        assert path.read_text(encoding="utf-8") == kg.to_xml()
+        assert kg.take_errors() == []
+
+    # 重新打开后内容不变
+    with FiledGraph.open(str(path)) as kg:
+        assert "节点二" in kg.to_xml()
+
+
+def test_without_autosave(tmp_path):
+    path = tmp_path / "graph.xml"
+    kg = FiledGraph.open(str(path), create=True, autosave=False)
+    kg.add_entity("节点", "kp", "", 0.0, 0.0)
+    kg.flush()
+    assert path.read_text(encoding="utf-8") == ""
+
+    kg.save()
+    assert path.read_text(encoding="utf-8") == kg.to_xml()
+    kg.close()
+
+
+def test_closed_graph_raises(tmp_path):
+    kg = FiledGraph.open(str(tmp_path / "graph.xml"), create=True)
+    kg.close()
+    kg.close()  # 重复关闭没有效果
+    try:
+        kg.add_entity("节点", "kp", "", 0.0, 0.0)
+    except Exception:
+        pass
+    else:
+        raise AssertionError("expected closed graph to raise")
+
+
+
+def test_create_refuses_existing_file(tmp_path):
+    path = tmp_path / "graph.xml"
+    with FiledGraph.open(str(path), create=True) as kg:
+        kg.add_entity("原有节点", "kp", "", 0.0, 0.0)
+
+    try:
+        FiledGraph.open(str(path), create=True)
+    except Exception:
+        pass
+    else:
+        raise AssertionError("expected existing file to be protected")
+    assert "原有节点" in path.read_text(encoding="utf-8")
+
+    with FiledGraph.open(str(path), create=True, overwrite=True) as kg:
+        assert "原有节点" not in kg.to_xml()
+
+
+def test_batch_add(tmp_path):
+    path = tmp_path / "graph.xml"
+    with FiledGraph.open(str(path), create=True) as kg:
+        ids = kg.add_entities(
+            [
+                ("领域", "ka", "", 0.0, 0.0),
+                ("单元", "ku", "k", 0.0, 200.0),
+                ("知识点", "kp", "te", 0.0, 400.0),
+            ]
+        )
+        assert len(ids) == 3
+        kg.add_edges([(ids[0], ids[1], "contain"), (ids[1], ids[2], "c")])
+        assert "知识点" in kg.to_xml()
+
+        # 任何一条边无效时整批都不添加
+        xml = kg.to_xml()
+        try:
+            kg.add_edges([(ids[0], ids[2], "order"), (ids[0], 999, "order")])
+        except Exception:
+            pass
+        else:
+            raise AssertionError("expected missing entity to raise")
+        assert kg.to_xml() == xml
+
+        # 整批作为一次可撤回的操作
+        kg.undo()
+        kg.undo()
+        assert "领域" not in kg.to_xml()
+
+
+def test_flags(tmp_path):
+    path = tmp_path / "graph.xml"
+    with FiledGraph.open(str(path), create=True) as kg:
+        ids = kg.add_entities(
+            [("待修改", "kp", "", 0.0, 0.0), ("已通过", "kp", "", 0.0, 200.0)]
+        )
+        assert kg.entity_flag(ids[0]) is None
+        kg.set_entity_flag(ids[0], "todo")
+        kg.set_entity_flag(ids[1], "Approved")
+        assert kg.entity_flag(ids[1]) == "approved"
+        assert kg.flagged("todo") == [ids[0]]
+        assert "<flag>todo</flag>" in kg.to_xml()
+
+        try:
+            kg.set_entity_flag(ids[0], "later")
+        except Exception:
+            pass
+        else:
+            raise AssertionError("expected invalid flag to raise")
+
+        # 清除标记，可撤销
+        kg.set_entity_flag(ids[0])
+        assert kg.flagged("todo") == []
+        kg.undo()
+        assert kg.entity_flag(ids[0]) == "todo"
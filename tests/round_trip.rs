@@ -0,0 +1,215 @@
+//! 编解码兼容性测试：fixtures 中的文件读取、保存、再读取后应保持一致，
+//! 固定图谱的 XML 输出应与 golden 文件逐字节相同，防止格式意外变化。
+
+use std::{fs, path::Path};
+
+use py_better_kt_sqep::{
+    error::SerdeError,
+    graph::{
+        AddonEntityType, DEFAULT_TITLE, DistinctEntityType, GraphMetadata, KnowledgeGraph,
+        MAX_ENTITY_ID, Relation, Snapshot, peek_title, peek_xml_title,
+    },
+};
+
+#[test]
+fn test_fixtures_round_trip() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures");
+    let mut count = 0;
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.extension().is_none_or(|ext| ext != "xml") {
+            continue;
+        }
+
+        let snapshot = Snapshot::from_xml(&fs::read_to_string(&path)?)?;
+        let reloaded = Snapshot::from_xml(&snapshot.to_xml()?)?;
+        assert_eq!(snapshot, reloaded, "{}", path.display());
+        count += 1;
+    }
+    assert!(count > 0);
+
+    Ok(())
+}
+
+#[test]
+fn test_wrapped_and_doubled_documents() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures");
+
+    // 跳过外层的包装元素
+    let snapshot = Snapshot::from_xml(&fs::read_to_string(dir.join("wrapped.xml"))?)?;
+    assert_eq!(snapshot.nodes[&1].content, "被包装的图谱");
+    assert_eq!(snapshot.edges.len(), 1);
+
+    // 多个图谱时严格模式报错，宽容模式读取第一个并说明
+    let doubled = fs::read_to_string(dir.join("lenient/doubled.xml"))?;
+    assert!(matches!(
+        Snapshot::from_xml(&doubled),
+        Err(SerdeError::MultipleDocuments(2))
+    ));
+    let (snapshot, warnings) = Snapshot::from_xml_lenient(&doubled)?;
+    assert_eq!(snapshot.nodes[&1].content, "第一个图谱");
+    assert_eq!(
+        warnings,
+        ["文件包含 2 个图谱，只读取了第 1 个（从第 2 行开始），其余已忽略"]
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_peek_title() -> Result<(), Box<dyn std::error::Error>> {
+    // 与完整读取得到的节点数一致
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures");
+    for entry in fs::read_dir(&dir)? {
+        let path = entry?.path();
+        if path.extension().is_none_or(|ext| ext != "xml") {
+            continue;
+        }
+        let snapshot = Snapshot::from_xml(&fs::read_to_string(&path)?)?;
+        assert_eq!(
+            peek_title(&path)?,
+            (DEFAULT_TITLE.to_string(), snapshot.nodes.len()),
+            "{}",
+            path.display()
+        );
+    }
+
+    // 多个图谱时只读取第一个，与宽容模式一致
+    assert_eq!(
+        peek_title(dir.join("lenient/doubled.xml"))?,
+        (DEFAULT_TITLE.to_string(), 1)
+    );
+
+    // 读完节点列表即停止，之后的内容不完整也不影响
+    let truncated =
+        "<KG> 数据结构第3章 <entities><entity/><entity><id>2</id></entity></entities><rel";
+    assert_eq!(peek_xml_title(truncated)?, ("数据结构第3章".to_string(), 2));
+    assert_eq!(peek_xml_title("<KG/>")?, (String::new(), 0));
+    assert!(peek_xml_title("<root></root>").is_err());
+    assert!(peek_xml_title("<KG>标题<entities><entity></id>").is_err());
+    assert!(peek_title(dir.join("missing.xml")).is_err());
+
+    // 保存时写入图谱的标题，读取后不变
+    let mut graph = KnowledgeGraph::default();
+    graph.current.title = "数据结构第3章".to_string();
+    graph.add_entity(
+        "线性表".to_string(),
+        DistinctEntityType::KnowledgeArena,
+        &[],
+        (0.0, 0.0),
+    )?;
+    let path =
+        std::env::temp_dir().join(format!("better_kt_sqep_{}_title.xml", std::process::id()));
+    fs::write(&path, graph.current.to_xml()?)?;
+    assert_eq!(peek_title(&path)?, ("数据结构第3章".to_string(), 1));
+    let snapshot = Snapshot::from_xml(&fs::read_to_string(&path)?)?;
+    assert_eq!(snapshot.title, "数据结构第3章");
+    fs::remove_file(&path)?;
+
+    Ok(())
+}
+
+#[test]
+fn test_huge_entity_id() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures");
+    let xml = fs::read_to_string(dir.join("lenient/huge_id.xml"))?;
+
+    // 严格模式拒绝超出范围的 ID，而不是在计算下一个 ID 时溢出
+    assert!(matches!(
+        Snapshot::from_xml(&xml),
+        Err(SerdeError::Unexpected("节点 ID", _))
+    ));
+
+    // 宽容模式忽略该节点及其边，之后可以正常添加节点
+    let (snapshot, warnings) = Snapshot::from_xml_lenient(&xml)?;
+    assert_eq!(snapshot.nodes.len(), 1);
+    assert!(snapshot.edges.is_empty());
+    assert_eq!(snapshot.next_id(), 2);
+    assert_eq!(
+        warnings[0],
+        format!("节点 18446744073709551615 的 ID 超出范围（最大为 {MAX_ENTITY_ID}），已忽略")
+    );
+    let mut graph = KnowledgeGraph::default();
+    graph.replace_current(snapshot);
+    let id = graph.add_entity(
+        "新节点".to_string(),
+        DistinctEntityType::KnowledgePoint,
+        &[],
+        (0.0, 0.0),
+    )?;
+    assert_eq!(id, 2);
+
+    Ok(())
+}
+
+#[test]
+fn test_non_contiguous_ids() -> Result<(), Box<dyn std::error::Error>> {
+    let mut graph = KnowledgeGraph::default();
+    for content in ["A", "B", "C", "D"] {
+        graph.add_entity(
+            content.to_string(),
+            DistinctEntityType::KnowledgePoint,
+            &[],
+            (0.0, 0.0),
+        )?;
+    }
+    graph.remove_entity(2)?;
+    graph.remove_entity(4)?;
+
+    // 下一个 ID 由现有的最大 ID 决定，中间的空缺不会被重复使用
+    let xml = graph.current_snapshot().to_xml()?;
+    let snapshot = Snapshot::from_xml(&xml)?;
+    assert_eq!(snapshot.next_id(), 4);
+    let (snapshot, _) = Snapshot::from_xml_lenient(&xml)?;
+    assert_eq!(snapshot.next_id(), 4);
+
+    Ok(())
+}
+
+/// 构造固定的图谱，覆盖所有实体类型、附加类型、关系、备注和元数据
+fn create_golden_graph() -> Result<KnowledgeGraph, Box<dyn std::error::Error>> {
+    let mut graph = KnowledgeGraph::default();
+    let arena = graph.add_entity(
+        "计算思维".to_string(),
+        DistinctEntityType::KnowledgeArena,
+        &[AddonEntityType::Thinking],
+        (0.0, 0.0),
+    )?;
+    let unit = graph.add_entity(
+        "问题求解".to_string(),
+        DistinctEntityType::KnowledgeUnit,
+        &[AddonEntityType::Knowledge, AddonEntityType::Political],
+        (-100.0, 150.0),
+    )?;
+    let point = graph.add_entity(
+        "算法 <A> & \"B\"".to_string(),
+        DistinctEntityType::KnowledgePoint,
+        &[AddonEntityType::Example, AddonEntityType::Question],
+        (100.5, 150.0),
+    )?;
+    let detail = graph.add_entity(
+        "练习".to_string(),
+        DistinctEntityType::KnowledgeDetail,
+        &[AddonEntityType::Practice],
+        (100.5, 300.0),
+    )?;
+    graph.update_entity_with(detail, |node| node.notes = "待补充".to_string())?;
+    graph.add_edge(arena, unit, Relation::Contain)?;
+    graph.add_edge(arena, point, Relation::Contain)?;
+    graph.add_edge(unit, point, Relation::Order)?;
+    graph.add_edge(point, detail, Relation::Contain)?;
+    graph.set_metadata(GraphMetadata {
+        max_nodes: Some(500),
+        ..Default::default()
+    });
+    Ok(graph)
+}
+
+#[test]
+fn test_golden_output() -> Result<(), Box<dyn std::error::Error>> {
+    let graph = create_golden_graph()?;
+    let xml = graph.current_snapshot().to_xml()?;
+    assert_eq!(xml, include_str!("golden/graph.xml"));
+
+    Ok(())
+}
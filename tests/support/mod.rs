@@ -0,0 +1,192 @@
+//! 集成测试共用的随机数据生成器，供属性测试生成任意的文本和图谱。
+//! 新功能的测试可通过 `mod support;` 引入后组合使用。
+
+#![allow(dead_code)] // 各测试文件只用到其中一部分
+
+use proptest::{collection, option, prelude::*, sample};
+use py_better_kt_sqep::graph::{
+    AddonEntityType, Bookmark, CoordinateBounds, DistinctEntityType, EntityNode, Flag,
+    GraphMetadata, KnowledgeGraph, Relation, Snapshot,
+};
+
+pub const DISTINCT_TYPES: [DistinctEntityType; 4] = [
+    DistinctEntityType::KnowledgeArena,
+    DistinctEntityType::KnowledgeUnit,
+    DistinctEntityType::KnowledgePoint,
+    DistinctEntityType::KnowledgeDetail,
+];
+
+pub const ADDON_TYPES: [AddonEntityType; 6] = [
+    AddonEntityType::Knowledge,
+    AddonEntityType::Thinking,
+    AddonEntityType::Example,
+    AddonEntityType::Question,
+    AddonEntityType::Practice,
+    AddonEntityType::Political,
+];
+
+/// 容易出问题的字符：控制字符、换行、XML 特殊字符、代理区前后和平面边界的码位
+const TRICKY_CHARS: &[char] = &[
+    '\0',
+    '\t',
+    '\n',
+    '\r',
+    '\u{b}',
+    '\u{c}',
+    '\u{1b}',
+    '\u{7f}',
+    '\u{85}',
+    '\u{a0}',
+    ' ',
+    '<',
+    '>',
+    '&',
+    '"',
+    '\'',
+    ']',
+    '\u{d7ff}',
+    '\u{e000}',
+    '\u{fdd0}',
+    '\u{feff}',
+    '\u{fffd}',
+    '\u{fffe}',
+    '\u{ffff}',
+    '\u{10000}',
+    '\u{10ffff}',
+];
+
+/// 任意文本，包括空串、只含空白的文本，以及混有特殊字符和 CDATA 结束标记的文本
+pub fn arb_text() -> impl Strategy<Value = String> {
+    let piece = prop_oneof![
+        3 => any::<char>().prop_map(String::from),
+        3 => sample::select(TRICKY_CHARS).prop_map(String::from),
+        1 => sample::select(&["]]>", "\r\n", "&amp;", "<![CDATA[", "&#13;", "知识点"][..])
+            .prop_map(str::to_string),
+    ];
+    collection::vec(piece, 0..12).prop_map(|pieces| pieces.concat())
+}
+
+/// 有限的坐标，覆盖负数和小数
+pub fn arb_coor() -> impl Strategy<Value = (f64, f64)> {
+    (-1e6..1e6, -1e6..1e6)
+}
+
+pub fn arb_distinct_type() -> impl Strategy<Value = DistinctEntityType> {
+    sample::select(&DISTINCT_TYPES[..])
+}
+
+pub fn arb_relation() -> impl Strategy<Value = Relation> {
+    prop::sample::select(Relation::ALL.to_vec())
+}
+
+/// 任意节点，ID 由图谱添加时分配
+pub fn arb_node() -> impl Strategy<Value = EntityNode> {
+    (
+        arb_text(),
+        arb_distinct_type(),
+        sample::subsequence(&ADDON_TYPES[..], 0..=ADDON_TYPES.len()),
+        arb_coor(),
+        arb_text(),
+        option::of(any::<u32>()),
+        collection::vec(arb_text(), 0..3),
+        option::of(sample::select(Flag::ALL.to_vec())),
+    )
+        .prop_map(
+            |(content, distinct_type, addon_types, coor, notes, order_index, links, flag)| {
+                let mut node = EntityNode::new(0, content, distinct_type, &addon_types, coor);
+                node.notes = notes;
+                node.order_index = order_index;
+                node.links = links;
+                node.flag = flag;
+                node
+            },
+        )
+}
+
+/// 任意元数据，包括默认值
+pub fn arb_metadata() -> impl Strategy<Value = GraphMetadata> {
+    let bookmark = (arb_text(), (-1e4f32..1e4, -1e4f32..1e4), 0.5f32..3.0).prop_map(
+        |(name, scroll_offset, zoom)| Bookmark {
+            name,
+            scroll_offset,
+            zoom,
+        },
+    );
+    let bounds = (arb_coor(), arb_coor()).prop_map(|(min, max)| CoordinateBounds { min, max });
+    (
+        option::of(1..10_000usize),
+        option::of(1..10_000usize),
+        option::of(1..10_000usize),
+        option::of(bounds),
+        collection::vec(bookmark, 0..3),
+        any::<bool>(),
+    )
+        .prop_map(
+            |(
+                max_nodes,
+                max_edges,
+                max_content_chars,
+                coordinate_bounds,
+                bookmarks,
+                restrict_charset,
+            )| {
+                GraphMetadata {
+                    max_nodes,
+                    max_edges,
+                    max_content_chars,
+                    coordinate_bounds,
+                    bookmarks,
+                    restrict_charset,
+                }
+            },
+        )
+}
+
+/// 任意快照：至多 max_nodes 个节点，节点之间随机连边（可能有环和自环），
+/// 并附带分组框（可能有成员）和元数据。
+/// 元数据在添加完节点和边之后设置，其上限不影响生成
+pub fn arb_snapshot(max_nodes: usize) -> impl Strategy<Value = Snapshot> {
+    let edge = (
+        any::<sample::Index>(),
+        any::<sample::Index>(),
+        arb_relation(),
+    );
+    let group = (
+        arb_text(),
+        arb_coor(),
+        arb_coor(),
+        any::<[u8; 3]>(),
+        collection::vec(any::<sample::Index>(), 0..3),
+    );
+    (
+        collection::vec(arb_node(), 0..=max_nodes),
+        collection::vec(edge, 0..=max_nodes * 2),
+        collection::vec(group, 0..3),
+        arb_metadata(),
+    )
+        .prop_map(|(nodes, edges, groups, metadata)| {
+            let mut graph = KnowledgeGraph::default();
+            let ids = graph
+                .add_entities(nodes)
+                .expect("default graph has no node limit");
+            if !ids.is_empty() {
+                for (from, to, relation) in edges {
+                    graph
+                        .add_edge(*from.get(&ids), *to.get(&ids), relation)
+                        .expect("nodes exist and default graph has no edge limit");
+                }
+            }
+            for (label, min, max, color, members) in groups {
+                let members = if ids.is_empty() {
+                    Vec::new()
+                } else {
+                    members.iter().map(|index| *index.get(&ids)).collect()
+                };
+                graph
+                    .add_group(label, CoordinateBounds { min, max }, color, &members)
+                    .expect("members exist");
+            }
+            graph.set_metadata(metadata);
+            graph.current_snapshot().clone()
+        })
+}